@@ -1,4 +1,5 @@
 use plonky2::fri::reduction_strategies::FriReductionStrategy;
+use plonky2::fri::security::SecurityAssumption;
 use plonky2::fri::{FriConfig, FriParams};
 
 pub struct StarkConfig {
@@ -24,6 +25,8 @@ impl StarkConfig {
                 proof_of_work_bits: 16,
                 reduction_strategy: FriReductionStrategy::ConstantArityBits(4, 5),
                 num_query_rounds: 84,
+                security_assumption: SecurityAssumption::Conjectured,
+                coset_shift: None,
             },
         }
     }
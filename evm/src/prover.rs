@@ -109,6 +109,10 @@ where
     [(); LogicStark::<F, D>::COLUMNS]:,
     [(); MemoryStark::<F, D>::COLUMNS]:,
 {
+    ensure!(
+        config.fri_config.coset_shift.is_none(),
+        "STARK provers only support the default coset shift"
+    );
     let rate_bits = config.fri_config.rate_bits;
     let cap_height = config.fri_config.cap_height;
 
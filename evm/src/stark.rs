@@ -91,6 +91,8 @@ pub trait Stark<F: RichField + Extendable<D>, const D: usize>: Sync {
         let trace_oracle = FriOracleInfo {
             num_polys: Self::COLUMNS,
             blinding: false,
+            extra_rate_bits: 0,
+            leaf_group_bits: 0,
         };
         let trace_info = FriPolynomialInfo::from_range(TRACE_ORACLE_INDEX, 0..Self::COLUMNS);
 
@@ -99,6 +101,8 @@ pub trait Stark<F: RichField + Extendable<D>, const D: usize>: Sync {
         let permutation_ctl_oracle = FriOracleInfo {
             num_polys: num_perutation_ctl_polys,
             blinding: false,
+            extra_rate_bits: 0,
+            leaf_group_bits: 0,
         };
         let permutation_ctl_zs_info = FriPolynomialInfo::from_range(
             PERMUTATION_CTL_ORACLE_INDEX,
@@ -114,6 +118,8 @@ pub trait Stark<F: RichField + Extendable<D>, const D: usize>: Sync {
         let quotient_oracle = FriOracleInfo {
             num_polys: num_quotient_polys,
             blinding: false,
+            extra_rate_bits: 0,
+            leaf_group_bits: 0,
         };
         let quotient_info =
             FriPolynomialInfo::from_range(QUOTIENT_ORACLE_INDEX, 0..num_quotient_polys);
@@ -154,6 +160,8 @@ pub trait Stark<F: RichField + Extendable<D>, const D: usize>: Sync {
         let trace_oracle = FriOracleInfo {
             num_polys: Self::COLUMNS,
             blinding: false,
+            extra_rate_bits: 0,
+            leaf_group_bits: 0,
         };
         let trace_info = FriPolynomialInfo::from_range(TRACE_ORACLE_INDEX, 0..Self::COLUMNS);
 
@@ -162,6 +170,8 @@ pub trait Stark<F: RichField + Extendable<D>, const D: usize>: Sync {
         let permutation_ctl_oracle = FriOracleInfo {
             num_polys: num_perutation_ctl_polys,
             blinding: false,
+            extra_rate_bits: 0,
+            leaf_group_bits: 0,
         };
         let permutation_ctl_zs_info = FriPolynomialInfo::from_range(
             PERMUTATION_CTL_ORACLE_INDEX,
@@ -177,6 +187,8 @@ pub trait Stark<F: RichField + Extendable<D>, const D: usize>: Sync {
         let quotient_oracle = FriOracleInfo {
             num_polys: num_quotient_polys,
             blinding: false,
+            extra_rate_bits: 0,
+            leaf_group_bits: 0,
         };
         let quotient_info =
             FriPolynomialInfo::from_range(QUOTIENT_ORACLE_INDEX, 0..num_quotient_polys);
@@ -0,0 +1,119 @@
+//! Conversions between our Goldilocks types and `ark-ff` field types, enabled by the `ark` feature.
+//!
+//! The arkworks representations use the same modulus and, for the quadratic extension, the same
+//! non-residue `W = 7`, so conversions are ring isomorphisms and commute with arithmetic. The
+//! canonical `ark-serialize` encoding of an [`ArkGoldilocks`] element is its canonical value as 8
+//! little-endian bytes, which matches how our serialization and transcripts encode field elements,
+//! so commitments and challenges can be handed to arkworks-based tooling as-is.
+
+use alloc::vec::Vec;
+
+use ark_ff::fields::{Fp2, Fp2Config, Fp64, MontBackend, MontConfig};
+use ark_ff::{MontFp, PrimeField as ArkPrimeField};
+
+use crate::extension::quadratic::QuadraticExtension;
+use crate::goldilocks_field::GoldilocksField;
+use crate::types::{Field, PrimeField64};
+
+#[derive(MontConfig)]
+#[modulus = "18446744069414584321"]
+#[generator = "7"]
+pub struct ArkGoldilocksConfig;
+
+/// The Goldilocks field as an `ark-ff` prime field.
+pub type ArkGoldilocks = Fp64<MontBackend<ArkGoldilocksConfig, 1>>;
+
+pub struct ArkGoldilocksExt2Config;
+
+impl Fp2Config for ArkGoldilocksExt2Config {
+    type Fp = ArkGoldilocks;
+
+    // Same as `<GoldilocksField as Extendable<2>>::W`.
+    const NONRESIDUE: ArkGoldilocks = MontFp!("7");
+
+    // `W^((p^i - 1) / 2)` for `i = 0, 1`.
+    const FROBENIUS_COEFF_FP2_C1: &'static [ArkGoldilocks] =
+        &[MontFp!("1"), MontFp!("18446744069414584320")];
+}
+
+/// The quadratic extension of Goldilocks as an `ark-ff` field.
+pub type ArkGoldilocksExt2 = Fp2<ArkGoldilocksExt2Config>;
+
+impl From<GoldilocksField> for ArkGoldilocks {
+    fn from(x: GoldilocksField) -> Self {
+        Self::from(x.to_canonical_u64())
+    }
+}
+
+impl From<ArkGoldilocks> for GoldilocksField {
+    fn from(x: ArkGoldilocks) -> Self {
+        Self::from_canonical_u64(x.into_bigint().0[0])
+    }
+}
+
+impl From<QuadraticExtension<GoldilocksField>> for ArkGoldilocksExt2 {
+    fn from(x: QuadraticExtension<GoldilocksField>) -> Self {
+        let QuadraticExtension([c0, c1]) = x;
+        Self::new(c0.into(), c1.into())
+    }
+}
+
+impl From<ArkGoldilocksExt2> for QuadraticExtension<GoldilocksField> {
+    fn from(x: ArkGoldilocksExt2) -> Self {
+        Self([x.c0.into(), x.c1.into()])
+    }
+}
+
+/// Converts a slice of field elements, e.g. a hash output or a list of challenges.
+pub fn to_ark_vec(xs: &[GoldilocksField]) -> Vec<ArkGoldilocks> {
+    xs.iter().map(|&x| x.into()).collect()
+}
+
+pub fn from_ark_vec(xs: &[ArkGoldilocks]) -> Vec<GoldilocksField> {
+    xs.iter().map(|&x| x.into()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_serialize::CanonicalSerialize;
+
+    use super::*;
+    use crate::types::Sample;
+
+    #[test]
+    fn test_base_field_round_trip() {
+        for _ in 0..100 {
+            let x = GoldilocksField::rand();
+            let y = GoldilocksField::rand();
+            let (ax, ay): (ArkGoldilocks, ArkGoldilocks) = (x.into(), y.into());
+            assert_eq!(GoldilocksField::from(ax), x);
+            assert_eq!(GoldilocksField::from(ax * ay), x * y);
+            assert_eq!(GoldilocksField::from(ax + ay), x + y);
+        }
+        assert_eq!(
+            GoldilocksField::from(ArkGoldilocks::from(GoldilocksField::NEG_ONE)),
+            GoldilocksField::NEG_ONE
+        );
+    }
+
+    #[test]
+    fn test_extension_round_trip() {
+        for _ in 0..100 {
+            let x = QuadraticExtension::<GoldilocksField>::rand();
+            let y = QuadraticExtension::<GoldilocksField>::rand();
+            let (ax, ay): (ArkGoldilocksExt2, ArkGoldilocksExt2) = (x.into(), y.into());
+            assert_eq!(QuadraticExtension::from(ax), x);
+            assert_eq!(QuadraticExtension::from(ax * ay), x * y);
+        }
+    }
+
+    #[test]
+    fn test_encoding_matches() {
+        let x = GoldilocksField::rand();
+        let mut bytes = Vec::new();
+        ArkGoldilocks::from(x)
+            .serialize_compressed(&mut bytes)
+            .unwrap();
+        assert_eq!(bytes, x.to_canonical_u64().to_le_bytes());
+    }
+}
@@ -4,6 +4,7 @@ use core::iter::{Product, Sum};
 use core::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 
 use crate::extension::OEF;
+use crate::types::Field;
 
 /// Let `F_D` be the optimal extension field `F[X]/(X^D-W)`. Then `ExtensionAlgebra<F_D>` is the quotient `F_D[X]/(X^D-W)`.
 /// It's a `D`-dimensional algebra over `F_D` useful to lift the multiplication over `F_D` to a multiplication over `(F_D)^D`.
@@ -115,6 +116,20 @@ impl<F: OEF<D>, const D: usize> Mul for ExtensionAlgebra<F, D> {
 
     #[inline]
     fn mul(self, rhs: Self) -> Self {
+        let (a, b) = (&self.0, &rhs.0);
+        match D {
+            2 => Self::reduce(&karatsuba_2([a[0], a[1]], [b[0], b[1]])),
+            4 => Self::reduce(&karatsuba_4(
+                [a[0], a[1], a[2], a[3]],
+                [b[0], b[1], b[2], b[3]],
+            )),
+            _ => self.mul_schoolbook(rhs),
+        }
+    }
+}
+
+impl<F: OEF<D>, const D: usize> ExtensionAlgebra<F, D> {
+    fn mul_schoolbook(self, rhs: Self) -> Self {
         let mut res = [F::ZERO; D];
         let w = F::from_basefield(F::W);
         for i in 0..D {
@@ -128,6 +143,49 @@ impl<F: OEF<D>, const D: usize> Mul for ExtensionAlgebra<F, D> {
         }
         Self(res)
     }
+
+    /// Reduces the coefficients of a polynomial of degree less than `2D` modulo `X^D - W`.
+    #[inline]
+    fn reduce(coeffs: &[F]) -> Self {
+        let w = F::from_basefield(F::W);
+        let mut res = [F::ZERO; D];
+        for (i, &c) in coeffs.iter().enumerate() {
+            if i < D {
+                res[i] += c;
+            } else {
+                res[i - D] += w * c;
+            }
+        }
+        Self(res)
+    }
+}
+
+/// Multiplies two linear polynomials with three multiplications instead of four.
+#[inline]
+fn karatsuba_2<F: Field>(a: [F; 2], b: [F; 2]) -> [F; 3] {
+    let lo = a[0] * b[0];
+    let hi = a[1] * b[1];
+    let mid = (a[0] + a[1]) * (b[0] + b[1]) - lo - hi;
+    [lo, mid, hi]
+}
+
+/// Multiplies two cubic polynomials with nine multiplications instead of sixteen, by applying
+/// Karatsuba to their halves and again to each product of halves.
+///
+/// Toom-4 would need seven, but its interpolation divides by 3 and 5, and inverting those in a
+/// generic field costs more than the two multiplications saved.
+#[inline]
+fn karatsuba_4<F: Field>(a: [F; 4], b: [F; 4]) -> [F; 7] {
+    let lo = karatsuba_2([a[0], a[1]], [b[0], b[1]]);
+    let hi = karatsuba_2([a[2], a[3]], [b[2], b[3]]);
+    let mid = karatsuba_2([a[0] + a[2], a[1] + a[3]], [b[0] + b[2], b[1] + b[3]]);
+    let mut res = [F::ZERO; 7];
+    for i in 0..3 {
+        res[i] += lo[i];
+        res[i + 2] += mid[i] - lo[i] - hi[i];
+        res[i + 4] += hi[i];
+    }
+    res
 }
 
 impl<F: OEF<D>, const D: usize> MulAssign for ExtensionAlgebra<F, D> {
@@ -261,6 +319,13 @@ mod tests {
         assert_eq!(z.0, mul_mle(ts));
     }
 
+    /// Tests that the Karatsuba multiplication agrees with the schoolbook one.
+    fn test_karatsuba<F: Extendable<D>, const D: usize>() {
+        let x = ExtensionAlgebra::<F::Extension, D>(F::Extension::rand_array());
+        let y = ExtensionAlgebra::<F::Extension, D>(F::Extension::rand_array());
+        assert_eq!((x * y).0, x.mul_schoolbook(y).0);
+    }
+
     mod base {
         use super::*;
 
@@ -277,6 +342,11 @@ mod tests {
         fn test_algebra() {
             test_extension_algebra::<GoldilocksField, 2>();
         }
+
+        #[test]
+        fn test_karatsuba() {
+            super::test_karatsuba::<GoldilocksField, 2>();
+        }
     }
 
     mod quartic {
@@ -286,5 +356,10 @@ mod tests {
         fn test_algebra() {
             test_extension_algebra::<GoldilocksField, 4>();
         }
+
+        #[test]
+        fn test_karatsuba() {
+            super::test_karatsuba::<GoldilocksField, 4>();
+        }
     }
 }
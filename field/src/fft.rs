@@ -1,6 +1,8 @@
+use alloc::vec;
 use alloc::vec::Vec;
 use core::cmp::{max, min};
 
+use num::Zero;
 use plonky2_util::{log2_strict, reverse_index_bits_in_place};
 use unroll::unroll_for_loops;
 
@@ -94,6 +96,118 @@ pub fn ifft_with_options<F: Field>(
     PolynomialCoeffs { coeffs: buffer }
 }
 
+/// The radices supported by [`mixed_radix_fft`], besides 2.
+const MIXED_RADICES: [usize; 2] = [5, 3];
+
+/// Factors `n` into the radices of a mixed-radix FFT, odd ones first, or returns `None` if it is
+/// zero or has a prime factor other than 2, 3 or 5.
+fn mixed_radix_factors(mut n: usize) -> Option<Vec<usize>> {
+    if n == 0 {
+        return None;
+    }
+    let mut factors = Vec::new();
+    for r in MIXED_RADICES {
+        while n % r == 0 {
+            factors.push(r);
+            n /= r;
+        }
+    }
+    if !n.is_power_of_two() {
+        return None;
+    }
+    factors.extend(core::iter::repeat(2).take(n.trailing_zeros() as usize));
+    Some(factors)
+}
+
+/// A generator of the subgroup of order `n`, where `n` is of the form `2^k * m` with `m` made up of
+/// the factors 3 and 5, and divides the order of the multiplicative group. This is the product of
+/// `F::primitive_root_of_unity(k)` and a root of order `m`, so that it agrees with the roots used
+/// by [`fft`] when `n` is a power of two.
+pub fn mixed_radix_root_of_unity<F: Field>(n: usize) -> F {
+    assert!(
+        mixed_radix_factors(n).is_some(),
+        "{} isn't of the form 2^k * 3^a * 5^b",
+        n
+    );
+    let lg_two_part = n.trailing_zeros() as usize;
+    let odd_part = n >> lg_two_part;
+    let group_order = F::order() - 1u32;
+    assert!(
+        (&group_order % odd_part).is_zero(),
+        "The multiplicative group has no subgroup of order {}",
+        n
+    );
+    let odd_root = F::MULTIPLICATIVE_GROUP_GENERATOR.exp_biguint(&(group_order / odd_part));
+    F::primitive_root_of_unity(lg_two_part) * odd_root
+}
+
+/// Evaluates `poly` on the subgroup generated by [`mixed_radix_root_of_unity`], for lengths which
+/// aren't powers of two, such as `3 * 2^k`, which can save up to half of the padding a power of two
+/// would need. Lengths which are powers of two are better served by [`fft`].
+///
+/// This is a plain recursive implementation, with naive DFTs of size 3 and 5, meant as a reference
+/// rather than for proving: the LDEs of `PolynomialBatch` and FRI only use power-of-two domains,
+/// and nothing in plonky2 calls this.
+pub fn mixed_radix_fft<F: Field>(poly: PolynomialCoeffs<F>) -> PolynomialValues<F> {
+    let n = poly.len();
+    let factors = mixed_radix_factors(n).expect("Unsupported FFT length");
+    let root = mixed_radix_root_of_unity::<F>(n);
+    // Not `PolynomialValues::new`, which expects a power-of-two length.
+    PolynomialValues {
+        values: mixed_radix_dft(&poly.coeffs, root, &factors),
+    }
+}
+
+/// The inverse of [`mixed_radix_fft`].
+pub fn mixed_radix_ifft<F: Field>(poly: PolynomialValues<F>) -> PolynomialCoeffs<F> {
+    let n = poly.len();
+    let factors = mixed_radix_factors(n).expect("Unsupported FFT length");
+    let root_inv = mixed_radix_root_of_unity::<F>(n).inverse();
+    let n_inv = F::from_canonical_usize(n).inverse();
+    let mut coeffs = mixed_radix_dft(&poly.values, root_inv, &factors);
+    coeffs.iter_mut().for_each(|c| *c *= n_inv);
+    PolynomialCoeffs::new(coeffs)
+}
+
+/// A recursive, decimation-in-time DFT of `input` with respect to `root`, of order
+/// `input.len() == factors.iter().product()`. Each level splits the input into `r` interleaved
+/// subsequences, where `r` is the first factor, transforms them, and combines them with DFTs of
+/// size `r`.
+fn mixed_radix_dft<F: Field>(input: &[F], root: F, factors: &[usize]) -> Vec<F> {
+    let (r, rest) = match factors.split_first() {
+        Some((&r, rest)) => (r, rest),
+        None => return input.to_vec(),
+    };
+    let n = input.len();
+    let m = n / r;
+
+    let sub_root = root.exp_u64(r as u64);
+    let subs: Vec<Vec<F>> = (0..r)
+        .map(|j| {
+            let sub_input: Vec<F> = input[j..].iter().step_by(r).copied().collect();
+            mixed_radix_dft(&sub_input, sub_root, rest)
+        })
+        .collect();
+
+    // out[k + m q] = sum_j root^(j k) subs[j][k] w^(j q), where w = root^m has order r.
+    let w_powers: Vec<F> = root.exp_u64(m as u64).powers().take(r).collect();
+    let mut out = vec![F::ZERO; n];
+    let mut twiddled = vec![F::ZERO; r];
+    for (k, root_k) in root.powers().take(m).enumerate() {
+        for ((t, sub), root_jk) in twiddled.iter_mut().zip(&subs).zip(root_k.powers()) {
+            *t = root_jk * sub[k];
+        }
+        for q in 0..r {
+            out[k + m * q] = twiddled
+                .iter()
+                .enumerate()
+                .map(|(j, &t)| t * w_powers[(j * q) % r])
+                .sum();
+        }
+    }
+    out
+}
+
 /// Generic FFT implementation that works with both scalar and packed inputs.
 #[unroll_for_loops]
 fn fft_classic_simd<P: PackedField>(
@@ -211,7 +325,9 @@ mod tests {
 
     use plonky2_util::{log2_ceil, log2_strict};
 
-    use crate::fft::{fft, fft_with_options, ifft};
+    use crate::fft::{
+        fft, fft_with_options, ifft, mixed_radix_fft, mixed_radix_ifft, mixed_radix_root_of_unity,
+    };
     use crate::goldilocks_field::GoldilocksField;
     use crate::polynomial::{PolynomialCoeffs, PolynomialValues};
     use crate::types::Field;
@@ -252,6 +368,46 @@ mod tests {
         }
     }
 
+    #[test]
+    fn mixed_radix_fft_and_ifft() {
+        type F = GoldilocksField;
+
+        for n in [1, 3, 5, 6, 15, 24, 3 << 5, 15 << 3, 64] {
+            let coeffs = (0..n)
+                .map(|i| F::from_canonical_usize(i * 1337 % 100))
+                .collect::<Vec<_>>();
+            let coefficients = PolynomialCoeffs::new(coeffs);
+
+            let root = mixed_radix_root_of_unity::<F>(n);
+            assert_eq!(root.exp_u64(n as u64), F::ONE);
+            assert!((1..n).all(|i| n % i != 0 || root.exp_u64(i as u64) != F::ONE));
+            let points = mixed_radix_fft(coefficients.clone());
+            let expected = root
+                .powers()
+                .take(n)
+                .map(|x| evaluate_at_naive(&coefficients, x))
+                .collect::<Vec<_>>();
+            assert_eq!(points.values, expected);
+            if n.is_power_of_two() {
+                assert_eq!(points, fft(coefficients.clone()));
+            }
+
+            assert_eq!(mixed_radix_ifft(points), coefficients);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "isn't of the form 2^k * 3^a * 5^b")]
+    fn mixed_radix_fft_unsupported_length() {
+        mixed_radix_root_of_unity::<GoldilocksField>(7);
+    }
+
+    #[test]
+    #[should_panic(expected = "isn't of the form 2^k * 3^a * 5^b")]
+    fn mixed_radix_fft_zero_length() {
+        mixed_radix_root_of_unity::<GoldilocksField>(0);
+    }
+
     fn evaluate_naive<F: Field>(coefficients: &PolynomialCoeffs<F>) -> PolynomialValues<F> {
         let degree = coefficients.len();
         let degree_padded = 1 << log2_ceil(degree);
@@ -1,3 +1,5 @@
+use num::bigint::BigUint;
+
 use crate::extension::{Extendable, Frobenius};
 use crate::ops::Square;
 use crate::types::{Field, Sample};
@@ -156,6 +158,21 @@ pub(crate) fn test_frobenius<BF: Extendable<D>, const D: usize>() {
     }
 }
 
+/// Checks that `X^D - W` is irreducible, using the criterion for binomials: when `D` divides
+/// `p - 1`, it is irreducible iff `W` isn't a `q`-th power for any prime `q` dividing `D`, and, if
+/// `4` divides `D`, `p = 1 mod 4`. Also checks that `DTH_ROOT = W^((p - 1)/D)`.
+pub(crate) fn test_irreducibility<BF: Extendable<D>, const D: usize>() {
+    let p_minus_one = BF::order() - 1u8;
+    assert_eq!(&p_minus_one % D, BigUint::from(0u8));
+    assert_eq!(BF::W.exp_biguint(&(&p_minus_one / D)), BF::DTH_ROOT);
+    for q in (2..=D).filter(|&q| D % q == 0 && (2..q).all(|r| q % r != 0)) {
+        assert_ne!(BF::W.exp_biguint(&(&p_minus_one / q)), BF::ONE);
+    }
+    if D % 4 == 0 {
+        assert_eq!(BF::order() % 4u8, BigUint::from(1u8));
+    }
+}
+
 pub(crate) fn test_field_order<BF: Extendable<D>, const D: usize>() {
     let x = BF::Extension::rand();
     assert_eq!(
@@ -194,6 +211,10 @@ macro_rules! test_field_extension {
                 $crate::field_testing::test_frobenius::<$field, $d>();
             }
             #[test]
+            fn test_irreducibility() {
+                $crate::field_testing::test_irreducibility::<$field, $d>();
+            }
+            #[test]
             fn test_field_order() {
                 $crate::field_testing::test_field_order::<$field, $d>();
             }
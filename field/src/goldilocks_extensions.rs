@@ -69,6 +69,8 @@ impl Mul for QuarticExtension<GoldilocksField> {
 impl Extendable<5> for GoldilocksField {
     type Extension = QuinticExtension<Self>;
 
+    // Verifiable in Sage with
+    // `R.<x> = GF(p)[]; assert (x^5 - 3).is_irreducible()`.
     const W: Self = Self(3);
 
     // DTH_ROOT = W^((ORDER - 1)/5)
@@ -183,8 +183,10 @@ impl Field for GoldilocksField {
 
     #[inline]
     fn multiply_accumulate(&self, x: Self, y: Self) -> Self {
+        let (lo, hi) = widening_mul(x.0, y.0);
+        let (lo, carry) = lo.overflowing_add(self.0);
         // u64 + u64 * u64 cannot overflow.
-        reduce128((self.0 as u128) + (x.0 as u128) * (y.0 as u128))
+        reduce_wide(lo, hi + carry as u64)
     }
 }
 
@@ -250,7 +252,9 @@ impl Add for GoldilocksField {
     fn add(self, rhs: Self) -> Self {
         let (sum, over) = self.0.overflowing_add(rhs.0);
         let (mut sum, over) = sum.overflowing_add((over as u64) * EPSILON);
-        if over {
+        if cfg!(feature = "constant_time") {
+            sum += (over as u64) * EPSILON; // Cannot overflow.
+        } else if over {
             // NB: self.0 > Self::ORDER && rhs.0 > Self::ORDER is necessary but not sufficient for
             // double-overflow.
             // This assume does two things:
@@ -287,7 +291,9 @@ impl Sub for GoldilocksField {
     fn sub(self, rhs: Self) -> Self {
         let (diff, under) = self.0.overflowing_sub(rhs.0);
         let (mut diff, under) = diff.overflowing_sub((under as u64) * EPSILON);
-        if under {
+        if cfg!(feature = "constant_time") {
+            diff -= (under as u64) * EPSILON; // Cannot underflow.
+        } else if under {
             // NB: self.0 < EPSILON - 1 && rhs.0 > Self::ORDER is necessary but not sufficient for
             // double-underflow.
             // This assume does two things:
@@ -315,7 +321,8 @@ impl Mul for GoldilocksField {
 
     #[inline]
     fn mul(self, rhs: Self) -> Self {
-        reduce128((self.0 as u128) * (rhs.0 as u128))
+        let (lo, hi) = widening_mul(self.0, rhs.0);
+        reduce_wide(lo, hi)
     }
 }
 
@@ -383,8 +390,42 @@ unsafe fn add_no_canonicalize_trashing_input(x: u64, y: u64) -> u64 {
 #[cfg(not(target_arch = "x86_64"))]
 unsafe fn add_no_canonicalize_trashing_input(x: u64, y: u64) -> u64 {
     let (res_wrapped, carry) = x.overflowing_add(y);
+    // The adjustment is EPSILON on overflow and 0 otherwise. Deriving it from a mask rather than
+    // a multiplication keeps this branch-free on targets where `carry as u64 * EPSILON` isn't
+    // lowered well, such as wasm32.
+    let adjustment = (carry as u64).wrapping_neg() >> 32;
     // Below cannot overflow unless the assumption if x + y < 2**64 + ORDER is incorrect.
-    res_wrapped + EPSILON * (carry as u64)
+    res_wrapped + adjustment
+}
+
+/// Computes the full product `x * y`, as its low and high 64 bits. 64-bit targets multiply `u128`s,
+/// which is a single `mul`/`umulh` pair on aarch64, while 32-bit targets such as wasm32 use
+/// `widening_mul_u32_limbs`.
+#[inline(always)]
+#[cfg(not(target_pointer_width = "32"))]
+fn widening_mul(x: u64, y: u64) -> (u64, u64) {
+    split((x as u128) * (y as u128))
+}
+
+#[inline(always)]
+#[cfg(target_pointer_width = "32")]
+fn widening_mul(x: u64, y: u64) -> (u64, u64) {
+    widening_mul_u32_limbs(x, y)
+}
+
+/// Computes the full product `x * y`, as its low and high 64 bits, from four `32 x 32 -> 64`
+/// multiplications. On 32-bit targets such as wasm32, a `u128` product is a call to a slow
+/// compiler intrinsic, while these are native multiplications.
+#[inline(always)]
+#[cfg_attr(not(target_pointer_width = "32"), allow(dead_code))]
+fn widening_mul_u32_limbs(x: u64, y: u64) -> (u64, u64) {
+    let (x_lo, x_hi) = (x & EPSILON, x >> 32);
+    let (y_lo, y_hi) = (y & EPSILON, y >> 32);
+    let (mid, mid_carry) = (x_lo * y_hi).overflowing_add(x_hi * y_lo);
+    let (lo, lo_carry) = (x_lo * y_lo).overflowing_add(mid << 32);
+    // Cannot overflow, as the product is less than 2^128.
+    let hi = x_hi * y_hi + (mid >> 32) + ((mid_carry as u64) << 32) + lo_carry as u64;
+    (lo, hi)
 }
 
 /// Reduces to a 64-bit value. The result might not be in canonical form; it could be in between the
@@ -401,11 +442,19 @@ fn reduce96((x_lo, x_hi): (u64, u32)) -> GoldilocksField {
 #[inline]
 fn reduce128(x: u128) -> GoldilocksField {
     let (x_lo, x_hi) = split(x); // This is a no-op
+    reduce_wide(x_lo, x_hi)
+}
+
+/// Like `reduce128`, but takes the low and high 64 bits of the value.
+#[inline(always)]
+fn reduce_wide(x_lo: u64, x_hi: u64) -> GoldilocksField {
     let x_hi_hi = x_hi >> 32;
     let x_hi_lo = x_hi & EPSILON;
 
     let (mut t0, borrow) = x_lo.overflowing_sub(x_hi_hi);
-    if borrow {
+    if cfg!(feature = "constant_time") {
+        t0 -= (borrow as u64) * EPSILON; // Cannot underflow.
+    } else if borrow {
         branch_hint(); // A borrow is exceedingly rare. It is faster to branch.
         t0 -= EPSILON; // Cannot underflow.
     }
@@ -433,7 +482,9 @@ pub(crate) unsafe fn reduce160(x_lo: u128, x_hi: u32) -> GoldilocksField {
 
     // sub + jc (should fuse)
     let (mut t0, borrow) = x_lo.overflowing_sub(x_hi);
-    if borrow {
+    if cfg!(feature = "constant_time") {
+        t0 -= (borrow as u64) * EPSILON; // Cannot underflow if x_hi is canonical.
+    } else if borrow {
         // The maximum possible value of x is (2^64 - 1)^2 * 4 * 7 < 2^133,
         // so x_hi < 2^37. A borrow will happen roughly one in 134 million
         // times, so it's best to branch.
@@ -460,4 +511,25 @@ mod tests {
 
     test_prime_field_arithmetic!(crate::goldilocks_field::GoldilocksField);
     test_field_arithmetic!(crate::goldilocks_field::GoldilocksField);
+
+    #[test]
+    fn test_widening_mul_u32_limbs() {
+        use rand::rngs::OsRng;
+        use rand::Rng;
+
+        use super::{split, widening_mul_u32_limbs};
+
+        let mut rng = OsRng;
+        let edge_cases = [0, 1, u32::MAX as u64, 1 << 32, u64::MAX - 1, u64::MAX];
+        let pairs = edge_cases
+            .iter()
+            .flat_map(|&x| edge_cases.iter().map(move |&y| (x, y)))
+            .chain((0..1000).map(|_| (rng.gen(), rng.gen())));
+        for (x, y) in pairs {
+            assert_eq!(
+                widening_mul_u32_limbs(x, y),
+                split((x as u128) * (y as u128))
+            );
+        }
+    }
 }
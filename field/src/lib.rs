@@ -11,6 +11,8 @@ extern crate alloc;
 
 pub(crate) mod arch;
 
+#[cfg(feature = "ark")]
+pub mod ark;
 pub mod batch_util;
 pub mod cosets;
 pub mod extension;
@@ -18,7 +18,12 @@ pub struct ZeroPolyOnCoset<F: Field> {
 
 impl<F: Field> ZeroPolyOnCoset<F> {
     pub fn new(n_log: usize, rate_bits: usize) -> Self {
-        let g_pow_n = F::coset_shift().exp_power_of_2(n_log);
+        Self::with_shift(n_log, rate_bits, F::coset_shift())
+    }
+
+    /// Like `new`, but for the coset shifted by `shift` rather than `F::coset_shift()`.
+    pub fn with_shift(n_log: usize, rate_bits: usize, shift: F) -> Self {
+        let g_pow_n = shift.exp_power_of_2(n_log);
         let evals = F::two_adic_subgroup(rate_bits)
             .into_iter()
             .map(|x| g_pow_n * x - F::ONE)
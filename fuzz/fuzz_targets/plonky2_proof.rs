@@ -0,0 +1,41 @@
+//! Decodes arbitrary bytes as a proof, in both the normal and the compressed encoding, and verifies
+//! whatever decodes. Neither step may panic.
+
+#![no_main]
+
+use std::sync::OnceLock;
+
+use libfuzzer_sys::fuzz_target;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::plonk::circuit_data::{CircuitConfig, CircuitData};
+use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+use plonky2::plonk::proof::{CompressedProofWithPublicInputs, ProofWithPublicInputs};
+
+const D: usize = 2;
+type C = PoseidonGoldilocksConfig;
+type F = <C as GenericConfig<D>>::F;
+
+fn circuit() -> &'static CircuitData<F, C, D> {
+    static CIRCUIT: OnceLock<CircuitData<F, C, D>> = OnceLock::new();
+    CIRCUIT.get_or_init(|| {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let y = builder.square(x);
+        builder.register_public_input(x);
+        builder.register_public_input(y);
+        builder.build::<C>()
+    })
+}
+
+fuzz_target!(|data: &[u8]| {
+    let circuit = circuit();
+    if let Ok(proof) = ProofWithPublicInputs::<F, C, D>::from_bytes(data.to_vec(), &circuit.common)
+    {
+        let _ = circuit.verify(proof);
+    }
+    if let Ok(proof) =
+        CompressedProofWithPublicInputs::<F, C, D>::from_bytes(data.to_vec(), &circuit.common)
+    {
+        let _ = circuit.verify_compressed(proof);
+    }
+});
@@ -0,0 +1,81 @@
+//! Decodes arbitrary bytes as a STARK proof and verifies it if it decodes. Neither step may panic.
+
+#![no_main]
+#![allow(incomplete_features)]
+#![feature(generic_const_exprs)]
+
+use std::marker::PhantomData;
+
+use libfuzzer_sys::fuzz_target;
+use plonky2::field::extension::{Extendable, FieldExtension};
+use plonky2::field::packed::PackedField;
+use plonky2::hash::hash_types::RichField;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+use starky::config::StarkConfig;
+use starky::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use starky::permutation::PermutationPair;
+use starky::proof::StarkProofWithPublicInputs;
+use starky::stark::Stark;
+use starky::vars::{StarkEvaluationTargets, StarkEvaluationVars};
+use starky::verifier::verify_stark_proof;
+
+const D: usize = 2;
+type C = PoseidonGoldilocksConfig;
+type F = <C as GenericConfig<D>>::F;
+
+/// A counter `[i, j]` with `i' = i + 1`, `j' = j + 1`, where `j` is a permutation of `i`, so that
+/// proofs include permutation `Z`s.
+#[derive(Copy, Clone)]
+struct CounterStark<F: RichField + Extendable<D>, const D: usize> {
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for CounterStark<F, D> {
+    const COLUMNS: usize = 2;
+    const PUBLIC_INPUTS: usize = 1;
+
+    fn eval_packed_generic<FE, P, const D2: usize>(
+        &self,
+        vars: StarkEvaluationVars<FE, P, { Self::COLUMNS }, { Self::PUBLIC_INPUTS }>,
+        yield_constr: &mut ConstraintConsumer<P>,
+    ) where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>,
+    {
+        yield_constr.constraint_first_row(vars.local_values[0] - vars.public_inputs[0]);
+        yield_constr.constraint_transition(vars.next_values[0] - vars.local_values[0] - FE::ONE);
+    }
+
+    fn eval_ext_circuit(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: StarkEvaluationTargets<D, { Self::COLUMNS }, { Self::PUBLIC_INPUTS }>,
+        yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+    ) {
+        let first_row = builder.sub_extension(vars.local_values[0], vars.public_inputs[0]);
+        yield_constr.constraint_first_row(builder, first_row);
+        let diff = builder.sub_extension(vars.next_values[0], vars.local_values[0]);
+        let one = builder.one_extension();
+        let transition = builder.sub_extension(diff, one);
+        yield_constr.constraint_transition(builder, transition);
+    }
+
+    fn constraint_degree(&self) -> usize {
+        2
+    }
+
+    fn permutation_pairs(&self) -> Vec<PermutationPair> {
+        vec![PermutationPair::singletons(0, 1)]
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    let stark = CounterStark::<F, D> {
+        _phantom: PhantomData,
+    };
+    let config = StarkConfig::standard_fast_config();
+    if let Ok(proof) = StarkProofWithPublicInputs::<F, C, D>::from_bytes(data, &stark, &config) {
+        let _ = verify_stark_proof(stark, proof, &config);
+    }
+});
@@ -280,3 +280,25 @@ where
 {
     (oper_a(), oper_b())
 }
+
+/// The number of threads in the current thread pool.
+#[cfg(feature = "parallel")]
+pub fn current_num_threads() -> usize {
+    rayon::current_num_threads()
+}
+
+#[cfg(not(feature = "parallel"))]
+pub fn current_num_threads() -> usize {
+    1
+}
+
+/// The index of the current thread in its thread pool, or `None` outside of a thread pool.
+#[cfg(feature = "parallel")]
+pub fn current_thread_index() -> Option<usize> {
+    rayon::current_thread_index()
+}
+
+#[cfg(not(feature = "parallel"))]
+pub fn current_thread_index() -> Option<usize> {
+    None
+}
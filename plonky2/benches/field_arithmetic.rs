@@ -35,6 +35,37 @@ pub(crate) fn bench_field<F: Field>(c: &mut Criterion) {
         )
     });
 
+    c.bench_function(&format!("mul_add-throughput<{}>", type_name::<F>()), |b| {
+        b.iter_batched(
+            || (F::rand(), F::rand(), F::rand(), F::rand()),
+            |(mut x, mut y, mut z, mut w)| {
+                for _ in 0..25 {
+                    (x, y, z, w) = (
+                        x.multiply_accumulate(y, z),
+                        y.multiply_accumulate(z, w),
+                        z.multiply_accumulate(w, x),
+                        w.multiply_accumulate(x, y),
+                    );
+                }
+                (x, y, z, w)
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    c.bench_function(&format!("mul_add-latency<{}>", type_name::<F>()), |b| {
+        b.iter_batched(
+            || (F::rand(), F::rand()),
+            |(mut x, y)| {
+                for _ in 0..100 {
+                    x = y.multiply_accumulate(x, x);
+                }
+                x
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
     c.bench_function(&format!("sqr-throughput<{}>", type_name::<F>()), |b| {
         b.iter_batched(
             || (F::rand(), F::rand(), F::rand(), F::rand()),
@@ -111,6 +142,56 @@ pub(crate) fn bench_field<F: Field>(c: &mut Criterion) {
         )
     });
 
+    c.bench_function(&format!("sub-throughput<{}>", type_name::<F>()), |b| {
+        b.iter_batched(
+            || {
+                (
+                    F::rand(),
+                    F::rand(),
+                    F::rand(),
+                    F::rand(),
+                    F::rand(),
+                    F::rand(),
+                    F::rand(),
+                    F::rand(),
+                    F::rand(),
+                    F::rand(),
+                )
+            },
+            |(mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h, mut i, mut j)| {
+                for _ in 0..10 {
+                    (a, b, c, d, e, f, g, h, i, j) = (
+                        a - b,
+                        b - c,
+                        c - d,
+                        d - e,
+                        e - f,
+                        f - g,
+                        g - h,
+                        h - i,
+                        i - j,
+                        j - a,
+                    );
+                }
+                (a, b, c, d, e, f, g, h, i, j)
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    c.bench_function(&format!("sub-latency<{}>", type_name::<F>()), |b| {
+        b.iter_batched(
+            || (F::rand(), F::rand()),
+            |(mut x, y)| {
+                for _ in 0..100 {
+                    x = y - x;
+                }
+                x
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
     c.bench_function(&format!("try_inverse<{}>", type_name::<F>()), |b| {
         b.iter_batched(|| F::rand(), |x| x.try_inverse(), BatchSize::SmallInput)
     });
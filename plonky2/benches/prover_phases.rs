@@ -0,0 +1,138 @@
+//! Benchmarks of the main phases of proving, at trace sizes from 2^12 to 2^22 rows, with throughput
+//! reported in rows or hashed bytes per second where it applies. Filter by group, e.g.
+//! `cargo bench --bench prover_phases -- merkle`, to keep runs short.
+
+mod allocator;
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use plonky2::field::goldilocks_field::GoldilocksField;
+use plonky2::field::polynomial::PolynomialCoeffs;
+use plonky2::field::types::{Field, Sample};
+use plonky2::gates::noop::NoopGate;
+use plonky2::hash::hash_types::RichField;
+use plonky2::hash::merkle_tree::MerkleTree;
+use plonky2::hash::poseidon::PoseidonHash;
+use plonky2::iop::witness::{PartialWitness, WitnessWrite};
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::plonk::circuit_data::CircuitConfig;
+use plonky2::plonk::config::{GenericConfig, Hasher, PoseidonGoldilocksConfig};
+use tynm::type_name;
+
+/// Trace sizes, as log2 of the number of rows.
+const SIZES_LOG: [usize; 6] = [12, 14, 16, 18, 20, 22];
+
+/// Recursion benchmarks prove a whole inner circuit during setup, which limits their sizes.
+const RECURSION_SIZES_LOG: [usize; 3] = [12, 14, 16];
+
+/// The number of wires in `CircuitConfig::standard_recursion_config`, i.e. the width of a typical
+/// Merkle leaf.
+const ELEMS_PER_LEAF: usize = 135;
+
+fn bench_fft<F: Field>(c: &mut Criterion) {
+    let mut group = c.benchmark_group(&format!("prover-fft<{}>", type_name::<F>()));
+    group.sample_size(10);
+
+    for size_log in SIZES_LOG {
+        let size = 1 << size_log;
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, _| {
+            let coeffs = PolynomialCoeffs::new(F::rand_vec(size));
+            b.iter(|| coeffs.clone().fft_with_options(None, None));
+        });
+    }
+}
+
+/// Builds a tree with `2^size_log` leaves, each the width of a row.
+fn bench_merkle_tree<F: RichField, H: Hasher<F>>(c: &mut Criterion) {
+    let mut group = c.benchmark_group(&format!(
+        "prover-merkle-tree<{}, {}>",
+        type_name::<F>(),
+        type_name::<H>()
+    ));
+    group.sample_size(10);
+
+    for size_log in SIZES_LOG {
+        let size = 1 << size_log;
+        group.throughput(Throughput::Bytes(
+            (size * ELEMS_PER_LEAF * core::mem::size_of::<F>()) as u64,
+        ));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, _| {
+            let leaves = vec![F::rand_vec(ELEMS_PER_LEAF); size];
+            b.iter_batched(
+                || leaves.clone(),
+                |leaves| MerkleTree::<F, H>::new(leaves, 4),
+                BatchSize::LargeInput,
+            );
+        });
+    }
+}
+
+/// Hashes `2^size_log` field elements with the Poseidon sponge, i.e. about `2^size_log / 8`
+/// permutations.
+fn bench_poseidon_sponge<F: RichField>(c: &mut Criterion) {
+    let mut group = c.benchmark_group(&format!("prover-poseidon<{}>", type_name::<F>()));
+    group.sample_size(10);
+
+    for size_log in SIZES_LOG {
+        let size = 1 << size_log;
+        group.throughput(Throughput::Bytes((size * core::mem::size_of::<F>()) as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, _| {
+            let inputs = F::rand_vec(size);
+            b.iter(|| PoseidonHash::hash_no_pad(&inputs));
+        });
+    }
+}
+
+/// Proves a circuit verifying a proof of a `2^size_log`-row circuit.
+fn bench_recursive_verification(c: &mut Criterion) {
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    let mut group = c.benchmark_group("prover-recursive-verification");
+    group.sample_size(10);
+
+    let config = CircuitConfig::standard_recursion_config();
+    for size_log in RECURSION_SIZES_LOG {
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+        let x = builder.add_virtual_public_input();
+        let y = builder.square(x);
+        builder.register_public_input(y);
+        // Leave room for the gates above, and for the padding added by `build`.
+        for _ in 0..(1 << size_log) - 32 {
+            builder.add_gate(NoopGate, vec![]);
+        }
+        let inner = builder.build::<C>();
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::rand());
+        let inner_proof = inner.prove(pw).expect("Proving the inner circuit failed");
+
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+        let proof_t = builder.add_virtual_proof_with_pis(&inner.common);
+        let vd_t = builder.constant_verifier_data(&inner.verifier_only);
+        builder.verify_proof::<C>(&proof_t, &vd_t, &inner.common);
+        let outer = builder.build::<C>();
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(1 << inner.common.degree_bits()),
+            &size_log,
+            |b, _| {
+                b.iter(|| {
+                    let mut pw = PartialWitness::new();
+                    pw.set_proof_with_pis_target(&proof_t, &inner_proof);
+                    outer.prove(pw).expect("Proving the outer circuit failed")
+                });
+            },
+        );
+    }
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    bench_fft::<GoldilocksField>(c);
+    bench_merkle_tree::<GoldilocksField, PoseidonHash>(c);
+    bench_poseidon_sponge::<GoldilocksField>(c);
+    bench_recursive_verification(c);
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
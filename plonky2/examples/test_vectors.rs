@@ -0,0 +1,10 @@
+//! Prints the test vectors of `plonky2::util::test_vectors` as JSON, e.g. to update the committed
+//! copy with `cargo run --example test_vectors > plonky2/tests/vectors/test_vectors.json`.
+
+use anyhow::Result;
+use plonky2::util::test_vectors::TestVectors;
+
+fn main() -> Result<()> {
+    println!("{}", TestVectors::generate()?.to_json()?);
+    Ok(())
+}
@@ -0,0 +1,201 @@
+//! Proves and verifies circuits of `PoseidonGoldilocksConfig` from serialized files, and prints
+//! their stats.
+//!
+//! ```text
+//! plonky2-cli prove <circuit> <witness> <proof>
+//! plonky2-cli verify <verifier_data> <proof>
+//! plonky2-cli inspect <verifier_data> [<proof>]
+//! plonky2-cli explore <circuit>
+//! ```
+//!
+//! `explore` reads queries on the circuit from stdin, as answered by `CircuitInspector::query`.
+//!
+//! Circuits are read as written by `CircuitData::to_bytes` with the default gate and generator
+//! serializers, and witnesses as dumped by `PartialWitness::to_bytes`. Verifier data and proofs
+//! are read and written as JSON if their file name ends in `.json`, and as bytes otherwise.
+
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::time::Instant;
+use std::{env, fs, process};
+
+use anyhow::{anyhow, bail, Context, Result};
+use plonky2::plonk::circuit_data::{CircuitData, CommonCircuitData, VerifierCircuitData};
+use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+use plonky2::plonk::inspector::CircuitInspector;
+use plonky2::plonk::proof::ProofWithPublicInputs;
+use plonky2::util::serialization::json::hash_to_hex;
+use plonky2::util::serialization::{DefaultGateSerializer, DefaultGeneratorSerializer};
+
+const D: usize = 2;
+type C = PoseidonGoldilocksConfig;
+type F = <C as GenericConfig<D>>::F;
+
+const USAGE: &str = "\
+Usage:
+    plonky2-cli prove <circuit> <witness> <proof>
+    plonky2-cli verify <verifier_data> <proof>
+    plonky2-cli inspect <verifier_data> [<proof>]
+    plonky2-cli explore <circuit>";
+
+fn is_json(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .map_or(false, |ext| ext == "json")
+}
+
+fn read(path: &str) -> Result<Vec<u8>> {
+    fs::read(path).with_context(|| format!("Failed to read {path}"))
+}
+
+fn read_circuit(path: &str) -> Result<CircuitData<F, C, D>> {
+    let generator_serializer = DefaultGeneratorSerializer::<C, D> {
+        _phantom: Default::default(),
+    };
+    CircuitData::from_bytes(&read(path)?, &DefaultGateSerializer, &generator_serializer)
+        .map_err(|_| anyhow!("Invalid circuit in {path}"))
+}
+
+fn read_verifier_data(path: &str) -> Result<VerifierCircuitData<F, C, D>> {
+    if is_json(path) {
+        let json = String::from_utf8(read(path)?)?;
+        VerifierCircuitData::from_json(&json, &DefaultGateSerializer)
+    } else {
+        VerifierCircuitData::from_bytes(read(path)?, &DefaultGateSerializer)
+            .map_err(|_| anyhow!("Invalid verifier data in {path}"))
+    }
+}
+
+fn read_proof(
+    path: &str,
+    common: &CommonCircuitData<F, D>,
+) -> Result<ProofWithPublicInputs<F, C, D>> {
+    if is_json(path) {
+        ProofWithPublicInputs::from_json(&String::from_utf8(read(path)?)?)
+    } else {
+        ProofWithPublicInputs::from_bytes(read(path)?, common)
+    }
+    .with_context(|| format!("Invalid proof in {path}"))
+}
+
+fn write_proof(path: &str, proof: &ProofWithPublicInputs<F, C, D>) -> Result<()> {
+    let bytes = if is_json(path) {
+        proof.to_json()?.into_bytes()
+    } else {
+        proof.to_bytes()
+    };
+    fs::write(path, bytes).with_context(|| format!("Failed to write {path}"))
+}
+
+fn print_circuit_stats(common: &CommonCircuitData<F, D>, circuit_digest: &str) {
+    let config = &common.config;
+    let fri_config = &config.fri_config;
+    println!("Circuit digest: {circuit_digest}");
+    println!(
+        "Config digest: {}",
+        hash_to_hex(&common.config_digest::<<C as GenericConfig<D>>::Hasher>())
+    );
+    println!("Degree: 2^{}", common.degree_bits());
+    println!("Public inputs: {}", common.num_public_inputs);
+    println!(
+        "Wires: {} ({} routed)",
+        config.num_wires, config.num_routed_wires
+    );
+    println!("Constants: {}", common.num_constants);
+    println!("Gate constraints: {}", common.num_gate_constraints);
+    println!(
+        "FRI: rate 2^-{}, cap height {}, {} queries, {} proof-of-work bits, zero-knowledge {}",
+        fri_config.rate_bits,
+        fri_config.cap_height,
+        fri_config.num_query_rounds,
+        fri_config.proof_of_work_bits,
+        config.zero_knowledge
+    );
+    println!("Gates:");
+    for gate in &common.gates {
+        println!("    {}", gate.0.id());
+    }
+}
+
+fn print_proof_stats(proof: &ProofWithPublicInputs<F, C, D>) {
+    println!("Proof size: {} bytes", proof.to_bytes().len());
+    println!("Public inputs: {:?}", proof.public_inputs);
+}
+
+fn prove(circuit_path: &str, witness_path: &str, proof_path: &str) -> Result<()> {
+    let data = read_circuit(circuit_path)?;
+    let start = Instant::now();
+    let proof = data.prove_from_dump(read(witness_path)?)?;
+    println!("Proved in {:.3}s", start.elapsed().as_secs_f64());
+    data.verify(proof.clone())?;
+    print_proof_stats(&proof);
+    write_proof(proof_path, &proof)
+}
+
+fn verify(verifier_data_path: &str, proof_path: &str) -> Result<()> {
+    let data = read_verifier_data(verifier_data_path)?;
+    let proof = read_proof(proof_path, &data.common)?;
+    let start = Instant::now();
+    data.verify(proof)?;
+    println!("Verified in {:.3}s", start.elapsed().as_secs_f64());
+    Ok(())
+}
+
+fn inspect(verifier_data_path: &str, proof_path: Option<&str>) -> Result<()> {
+    let data = read_verifier_data(verifier_data_path)?;
+    print_circuit_stats(
+        &data.common,
+        &hash_to_hex(&data.verifier_only.circuit_digest),
+    );
+    if let Some(proof_path) = proof_path {
+        print_proof_stats(&read_proof(proof_path, &data.common)?);
+    }
+    Ok(())
+}
+
+fn explore(circuit_path: &str) -> Result<()> {
+    let data = read_circuit(circuit_path)?;
+    let inspector = CircuitInspector::new(&data);
+    println!(
+        "{} rows, {} gates. Queries: row <row>, wire <row> <column>, virtual <index>, \
+         pi <index>, gate <prefix>",
+        inspector.degree(),
+        data.common.gates.len()
+    );
+    let stdin = io::stdin();
+    loop {
+        print!("> ");
+        io::stdout().flush()?;
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        match inspector.query(&line) {
+            Ok(answer) => print!("{answer}"),
+            Err(e) => println!("{e}"),
+        }
+    }
+}
+
+fn run(args: &[String]) -> Result<()> {
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+    match args[..] {
+        ["prove", circuit, witness, proof] => prove(circuit, witness, proof),
+        ["verify", verifier_data, proof] => verify(verifier_data, proof),
+        ["inspect", verifier_data] => inspect(verifier_data, None),
+        ["inspect", verifier_data, proof] => inspect(verifier_data, Some(proof)),
+        ["explore", circuit] => explore(circuit),
+        _ => bail!("{USAGE}"),
+    }
+}
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if let Err(e) = run(&args) {
+        eprintln!("{e:#}");
+        process::exit(1);
+    }
+}
@@ -0,0 +1,135 @@
+//! The decider, a plonky2 circuit showing that an accumulated relaxed R1CS instance is satisfied.
+
+use alloc::vec::Vec;
+
+use crate::field::extension::Extendable;
+use crate::folding::r1cs::{RelaxedR1CSInstance, RelaxedR1CSWitness, SparseRow, R1CS};
+use crate::hash::hash_types::RichField;
+use crate::iop::target::Target;
+use crate::iop::witness::{PartialWitness, WitnessWrite};
+use crate::plonk::circuit_builder::CircuitBuilder;
+
+/// The targets of a decider circuit. `u` and `x` are registered as public inputs, in that order.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DeciderTargets {
+    pub u: Target,
+    pub x: Vec<Target>,
+    pub w: Vec<Target>,
+    pub e: Vec<Target>,
+}
+
+fn eval_row_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    row: &SparseRow<F>,
+    z: &[Target],
+) -> Target {
+    let mut acc = builder.zero();
+    for &(i, coeff) in row {
+        acc = builder.mul_const_add(coeff, z[i], acc);
+    }
+    acc
+}
+
+/// Adds the constraints `(A z) ∘ (B z) = u (C z) + e` of a relaxed instance of `r1cs`.
+pub fn add_decider_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    r1cs: &R1CS<F>,
+) -> DeciderTargets {
+    let u = builder.add_virtual_public_input();
+    let x = (0..r1cs.num_public_inputs)
+        .map(|_| builder.add_virtual_public_input())
+        .collect::<Vec<_>>();
+    let w = builder.add_virtual_targets(r1cs.num_witnesses);
+    let e = builder.add_virtual_targets(r1cs.num_constraints());
+
+    let z = [&[u], &x[..], &w[..]].concat();
+    for (((a, b), c), &e_i) in r1cs.a.iter().zip(&r1cs.b).zip(&r1cs.c).zip(&e) {
+        let az = eval_row_circuit(builder, a, &z);
+        let bz = eval_row_circuit(builder, b, &z);
+        let cz = eval_row_circuit(builder, c, &z);
+        let lhs = builder.mul(az, bz);
+        let rhs = builder.mul_add(u, cz, e_i);
+        builder.connect(lhs, rhs);
+    }
+
+    DeciderTargets { u, x, w, e }
+}
+
+/// Sets the decider's targets to an accumulated instance and its witness.
+pub fn set_decider_targets<F: RichField>(
+    witness: &mut PartialWitness<F>,
+    targets: &DeciderTargets,
+    instance: &RelaxedR1CSInstance<F>,
+    relaxed_witness: &RelaxedR1CSWitness<F>,
+) {
+    witness.set_target(targets.u, instance.u);
+    witness.set_target_arr(&targets.x, &instance.x);
+    witness.set_target_arr(&targets.w, &relaxed_witness.w);
+    witness.set_target_arr(&targets.e, &relaxed_witness.e);
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::field::types::Field;
+    use crate::folding::nifs::FoldingAccumulator;
+    use crate::folding::r1cs::tests::{cubic_r1cs, cubic_step};
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+    type H = <C as GenericConfig<D>>::Hasher;
+
+    fn accumulate(num_steps: u64) -> FoldingAccumulator<F, H> {
+        let (x, w) = cubic_step(0);
+        let mut accumulator = FoldingAccumulator::new(cubic_r1cs(), x, w);
+        for i in 1..num_steps {
+            let (x, w) = cubic_step(i);
+            accumulator.fold_step(x, w);
+        }
+        accumulator
+    }
+
+    #[test]
+    fn test_decider() -> Result<()> {
+        let accumulator = accumulate(8);
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let targets = add_decider_circuit(&mut builder, &accumulator.r1cs);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        set_decider_targets(
+            &mut pw,
+            &targets,
+            &accumulator.instance,
+            &accumulator.witness,
+        );
+        let proof = data.prove(pw)?;
+        assert_eq!(proof.public_inputs[0], accumulator.instance.u);
+        assert_eq!(&proof.public_inputs[1..], &accumulator.instance.x[..]);
+        data.verify(proof)
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_decider_unsatisfied() {
+        let mut accumulator = accumulate(8);
+        accumulator.witness.e[0] += F::ONE;
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let targets = add_decider_circuit(&mut builder, &accumulator.r1cs);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        set_decider_targets(
+            &mut pw,
+            &targets,
+            &accumulator.instance,
+            &accumulator.witness,
+        );
+        let _ = data.prove(pw);
+    }
+}
@@ -0,0 +1,19 @@
+//! **This module is not sound, and is only built with the `experimental` feature.** The decider
+//! accepts any satisfying witness of the accumulated instance, since nothing binds that witness
+//! to the steps which were folded, and a folding proof only commits to the cross term.
+//!
+//! An experimental folding scheme for relaxed R1CS, in the style of Nova, which lets a prover
+//! accumulate many steps of a computation into a single instance and then pay for one plonky2
+//! proof, the "decider", at the end.
+//!
+//! Nova commits to witnesses with additively homomorphic commitments, which lets the verifier fold
+//! the commitments of two instances into the commitment of the folded instance. plonky2's
+//! hash-based commitments aren't homomorphic, so here the verifier only folds the public parts of
+//! instances, and the decider only shows that the accumulated instance is satisfiable. Binding the
+//! decider's witness to the steps would need commitments which fold, so it isn't done here. The
+//! module is meant for experimenting with the folding algebra and measuring decider costs, not for
+//! production use.
+
+pub mod decider;
+pub mod nifs;
+pub mod r1cs;
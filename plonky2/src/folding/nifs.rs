@@ -0,0 +1,218 @@
+//! The non-interactive folding scheme, which folds two relaxed R1CS instances into one.
+
+use alloc::vec::Vec;
+
+use crate::field::types::Field;
+use crate::folding::r1cs::{RelaxedR1CSInstance, RelaxedR1CSWitness, R1CS};
+use crate::hash::hash_types::RichField;
+use crate::iop::challenger::Challenger;
+use crate::plonk::config::Hasher;
+
+/// The prover's message when folding two instances: a commitment to the cross term.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FoldingProof<F: RichField, H: Hasher<F>> {
+    pub cross_term_hash: H::Hash,
+}
+
+/// The cross term `T = (A z1) ∘ (B z2) + (A z2) ∘ (B z1) - u1 (C z2) - u2 (C z1)`, which is the
+/// coefficient of `r` in the error of the folded instance.
+fn cross_term<F: Field>(
+    r1cs: &R1CS<F>,
+    instance1: &RelaxedR1CSInstance<F>,
+    witness1: &RelaxedR1CSWitness<F>,
+    instance2: &RelaxedR1CSInstance<F>,
+    witness2: &RelaxedR1CSWitness<F>,
+) -> Vec<F> {
+    let (az1, bz1, cz1) = r1cs.eval(instance1.u, &instance1.x, &witness1.w);
+    let (az2, bz2, cz2) = r1cs.eval(instance2.u, &instance2.x, &witness2.w);
+    (0..r1cs.num_constraints())
+        .map(|i| az1[i] * bz2[i] + az2[i] * bz1[i] - instance1.u * cz2[i] - instance2.u * cz1[i])
+        .collect()
+}
+
+/// Observes the public parts of both instances and the cross term commitment, and returns the
+/// folding challenge.
+fn folding_challenge<F: RichField, H: Hasher<F>>(
+    instance1: &RelaxedR1CSInstance<F>,
+    instance2: &RelaxedR1CSInstance<F>,
+    proof: &FoldingProof<F, H>,
+    challenger: &mut Challenger<F, H>,
+) -> F {
+    for instance in [instance1, instance2] {
+        challenger.observe_element(instance.u);
+        challenger.observe_elements(&instance.x);
+    }
+    challenger.observe_hash::<H>(proof.cross_term_hash);
+    challenger.get_challenge()
+}
+
+fn fold_vectors<F: Field>(v1: &[F], v2: &[F], r: F) -> Vec<F> {
+    v1.iter().zip(v2).map(|(&a, &b)| a + r * b).collect()
+}
+
+/// Folds two satisfied relaxed R1CS instances into one, which is satisfied if both were.
+pub fn fold<F: RichField, H: Hasher<F>>(
+    r1cs: &R1CS<F>,
+    instance1: &RelaxedR1CSInstance<F>,
+    witness1: &RelaxedR1CSWitness<F>,
+    instance2: &RelaxedR1CSInstance<F>,
+    witness2: &RelaxedR1CSWitness<F>,
+    challenger: &mut Challenger<F, H>,
+) -> (
+    RelaxedR1CSInstance<F>,
+    RelaxedR1CSWitness<F>,
+    FoldingProof<F, H>,
+) {
+    let t = cross_term(r1cs, instance1, witness1, instance2, witness2);
+    let proof = FoldingProof {
+        cross_term_hash: H::hash_no_pad(&t),
+    };
+    let r = folding_challenge(instance1, instance2, &proof, challenger);
+
+    let instance = fold_public(instance1, instance2, r);
+    // `e = e1 + r T + r^2 e2`.
+    let e = witness1
+        .e
+        .iter()
+        .zip(t)
+        .zip(&witness2.e)
+        .map(|((&e1, t), &e2)| e1 + r * (t + r * e2))
+        .collect();
+    let witness = RelaxedR1CSWitness {
+        w: fold_vectors(&witness1.w, &witness2.w, r),
+        e,
+    };
+    (instance, witness, proof)
+}
+
+fn fold_public<F: Field>(
+    instance1: &RelaxedR1CSInstance<F>,
+    instance2: &RelaxedR1CSInstance<F>,
+    r: F,
+) -> RelaxedR1CSInstance<F> {
+    RelaxedR1CSInstance {
+        u: instance1.u + r * instance2.u,
+        x: fold_vectors(&instance1.x, &instance2.x, r),
+    }
+}
+
+/// The verifier's side of `fold`, which computes the folded instance from the two instances and
+/// the prover's message.
+pub fn fold_instances<F: RichField, H: Hasher<F>>(
+    instance1: &RelaxedR1CSInstance<F>,
+    instance2: &RelaxedR1CSInstance<F>,
+    proof: &FoldingProof<F, H>,
+    challenger: &mut Challenger<F, H>,
+) -> RelaxedR1CSInstance<F> {
+    let r = folding_challenge(instance1, instance2, proof, challenger);
+    fold_public(instance1, instance2, r)
+}
+
+/// A running instance which accumulates the steps of a computation, each given as the public
+/// inputs and witness of a plain R1CS instance.
+#[derive(Clone)]
+pub struct FoldingAccumulator<F: RichField, H: Hasher<F>> {
+    pub r1cs: R1CS<F>,
+    pub instance: RelaxedR1CSInstance<F>,
+    pub witness: RelaxedR1CSWitness<F>,
+    /// The prover's messages, one per step after the first.
+    pub proofs: Vec<FoldingProof<F, H>>,
+    challenger: Challenger<F, H>,
+}
+
+impl<F: RichField, H: Hasher<F>> FoldingAccumulator<F, H> {
+    /// Starts accumulating from the first step.
+    pub fn new(r1cs: R1CS<F>, x: Vec<F>, w: Vec<F>) -> Self {
+        let (instance, witness) = r1cs.relax(x, w);
+        Self {
+            r1cs,
+            instance,
+            witness,
+            proofs: Vec::new(),
+            challenger: Challenger::new(),
+        }
+    }
+
+    /// Folds the next step into the accumulator.
+    pub fn fold_step(&mut self, x: Vec<F>, w: Vec<F>) {
+        let (step_instance, step_witness) = self.r1cs.relax(x, w);
+        let (instance, witness, proof) = fold(
+            &self.r1cs,
+            &self.instance,
+            &self.witness,
+            &step_instance,
+            &step_witness,
+            &mut self.challenger,
+        );
+        self.instance = instance;
+        self.witness = witness;
+        self.proofs.push(proof);
+    }
+}
+
+/// Recomputes the accumulated instance from the public inputs of each step and the prover's
+/// messages, as `FoldingAccumulator` does.
+pub fn accumulate_instances<F: RichField, H: Hasher<F>>(
+    steps: &[Vec<F>],
+    proofs: &[FoldingProof<F, H>],
+) -> RelaxedR1CSInstance<F> {
+    assert_eq!(
+        steps.len(),
+        proofs.len() + 1,
+        "Expected one proof per folded step"
+    );
+    let mut challenger = Challenger::<F, H>::new();
+    let mut instance = RelaxedR1CSInstance {
+        u: F::ONE,
+        x: steps[0].clone(),
+    };
+    for (x, proof) in steps[1..].iter().zip(proofs) {
+        let step_instance = RelaxedR1CSInstance {
+            u: F::ONE,
+            x: x.clone(),
+        };
+        instance = fold_instances(&instance, &step_instance, proof, &mut challenger);
+    }
+    instance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::goldilocks_field::GoldilocksField;
+    use crate::folding::r1cs::tests::{cubic_r1cs, cubic_step};
+    use crate::hash::poseidon::PoseidonHash;
+
+    type F = GoldilocksField;
+    type H = PoseidonHash;
+
+    #[test]
+    fn test_fold_steps() {
+        let r1cs = cubic_r1cs();
+        let steps = (0..8).map(cubic_step).collect::<Vec<_>>();
+        let (x, w) = steps[0].clone();
+        let mut accumulator = FoldingAccumulator::<F, H>::new(r1cs.clone(), x, w);
+        for (x, w) in steps[1..].iter().cloned() {
+            accumulator.fold_step(x, w);
+            r1cs.check_relaxed(&accumulator.instance, &accumulator.witness)
+                .unwrap();
+        }
+
+        let public_inputs = steps.into_iter().map(|(x, _)| x).collect::<Vec<_>>();
+        let instance = accumulate_instances(&public_inputs, &accumulator.proofs);
+        assert_eq!(instance, accumulator.instance);
+    }
+
+    #[test]
+    fn test_fold_unsatisfied_step() {
+        let r1cs = cubic_r1cs();
+        let (x, w) = cubic_step(2);
+        let mut accumulator = FoldingAccumulator::<F, H>::new(r1cs.clone(), x, w);
+        let (x, mut w) = cubic_step(3);
+        w[0] += F::ONE;
+        accumulator.fold_step(x, w);
+        assert!(r1cs
+            .check_relaxed(&accumulator.instance, &accumulator.witness)
+            .is_err());
+    }
+}
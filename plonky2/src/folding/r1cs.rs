@@ -0,0 +1,141 @@
+//! Relaxed rank-1 constraint systems.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use anyhow::{ensure, Result};
+
+use crate::field::types::Field;
+
+/// A sparse row of an R1CS matrix, as `(column, coefficient)` pairs.
+pub type SparseRow<F> = Vec<(usize, F)>;
+
+/// A rank-1 constraint system `(A z) ∘ (B z) = C z`, over vectors `z = (1, x, w)` where `x` are
+/// the public inputs and `w` the witness.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct R1CS<F: Field> {
+    pub num_public_inputs: usize,
+    pub num_witnesses: usize,
+    pub a: Vec<SparseRow<F>>,
+    pub b: Vec<SparseRow<F>>,
+    pub c: Vec<SparseRow<F>>,
+}
+
+/// An instance of a relaxed R1CS, `(A z) ∘ (B z) = u (C z) + e` with `z = (u, x, w)`. A plain
+/// R1CS instance is a relaxed instance with `u = 1` and `e = 0`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RelaxedR1CSInstance<F: Field> {
+    pub u: F,
+    pub x: Vec<F>,
+}
+
+/// The witness of a relaxed R1CS instance: the R1CS witness `w` and the error vector `e`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RelaxedR1CSWitness<F: Field> {
+    pub w: Vec<F>,
+    pub e: Vec<F>,
+}
+
+impl<F: Field> R1CS<F> {
+    pub fn num_constraints(&self) -> usize {
+        self.a.len()
+    }
+
+    /// The length of `z = (u, x, w)`.
+    pub fn num_variables(&self) -> usize {
+        1 + self.num_public_inputs + self.num_witnesses
+    }
+
+    /// Wraps a plain R1CS instance and its witness as a relaxed one.
+    pub fn relax(&self, x: Vec<F>, w: Vec<F>) -> (RelaxedR1CSInstance<F>, RelaxedR1CSWitness<F>) {
+        let e = vec![F::ZERO; self.num_constraints()];
+        (
+            RelaxedR1CSInstance { u: F::ONE, x },
+            RelaxedR1CSWitness { w, e },
+        )
+    }
+
+    /// Evaluates `A z`, `B z` and `C z`.
+    pub fn eval(&self, u: F, x: &[F], w: &[F]) -> (Vec<F>, Vec<F>, Vec<F>) {
+        assert_eq!(x.len(), self.num_public_inputs);
+        assert_eq!(w.len(), self.num_witnesses);
+        let z = [&[u], x, w].concat();
+        let eval_matrix = |m: &[SparseRow<F>]| -> Vec<F> {
+            m.iter()
+                .map(|row| row.iter().map(|&(i, coeff)| coeff * z[i]).sum())
+                .collect()
+        };
+        (
+            eval_matrix(&self.a),
+            eval_matrix(&self.b),
+            eval_matrix(&self.c),
+        )
+    }
+
+    /// Checks that `(A z) ∘ (B z) = u (C z) + e`.
+    pub fn check_relaxed(
+        &self,
+        instance: &RelaxedR1CSInstance<F>,
+        witness: &RelaxedR1CSWitness<F>,
+    ) -> Result<()> {
+        ensure!(
+            witness.e.len() == self.num_constraints(),
+            "Error vector has the wrong length"
+        );
+        let (az, bz, cz) = self.eval(instance.u, &instance.x, &witness.w);
+        for (i, (((a, b), c), e)) in az.into_iter().zip(bz).zip(cz).zip(&witness.e).enumerate() {
+            ensure!(
+                a * b == instance.u * c + *e,
+                "Constraint {} of the relaxed R1CS is not satisfied",
+                i
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::field::goldilocks_field::GoldilocksField;
+    use crate::field::ops::Square;
+
+    type F = GoldilocksField;
+
+    /// An R1CS for one step of `x -> x^3 + x + 5`, with public inputs `(x, y)` and witnesses
+    /// `(x^2, x^3)`.
+    pub(crate) fn cubic_r1cs() -> R1CS<F> {
+        let one = F::ONE;
+        let (x, y, x2, x3) = (1, 2, 3, 4);
+        R1CS {
+            num_public_inputs: 2,
+            num_witnesses: 2,
+            // x * x = x2, x2 * x = x3, (x3 + x + 5) * 1 = y.
+            a: vec![
+                vec![(x, one)],
+                vec![(x2, one)],
+                vec![(x3, one), (x, one), (0, F::from_canonical_u64(5))],
+            ],
+            b: vec![vec![(x, one)], vec![(x, one)], vec![(0, one)]],
+            c: vec![vec![(x2, one)], vec![(x3, one)], vec![(y, one)]],
+        }
+    }
+
+    /// The public inputs and witness of a step of `cubic_r1cs` starting from `x`.
+    pub(crate) fn cubic_step(x: u64) -> (Vec<F>, Vec<F>) {
+        let x = F::from_canonical_u64(x);
+        let y = x.cube() + x + F::from_canonical_u64(5);
+        (vec![x, y], vec![x.square(), x.cube()])
+    }
+
+    #[test]
+    fn test_check_relaxed() {
+        let r1cs = cubic_r1cs();
+        let (x, w) = cubic_step(3);
+        let (instance, mut witness) = r1cs.relax(x, w);
+        r1cs.check_relaxed(&instance, &witness).unwrap();
+
+        witness.w[1] += F::ONE;
+        assert!(r1cs.check_relaxed(&instance, &witness).is_err());
+    }
+}
@@ -0,0 +1,244 @@
+//! Chooses the height of Merkle caps from the instance size and the number of FRI queries.
+//!
+//! A larger cap makes every Merkle path shorter, at the price of more hashes in each commitment.
+//! Since every commitment is opened at `num_query_rounds` points, the best height grows with the
+//! number of queries, and is bounded by the height of the smallest tree.
+//!
+//! All commitments of a proof share one cap height, so the height is chosen for the sum of their
+//! costs. It must be known before a circuit is built, e.g. to size the verifier data of cyclic
+//! recursion, so it is chosen for an expected degree, when constructing the config.
+
+use serde::{Deserialize, Serialize};
+
+use crate::fri::{FriConfig, FriParams};
+use crate::hash::hash_types::NUM_HASH_OUT_ELTS;
+use crate::hash::poseidon::SPONGE_RATE;
+use crate::util::ceil_div_usize;
+
+/// The extension degree assumed when costing the final polynomial, as in the standard configs.
+const EXTENSION_DEGREE: usize = 2;
+
+/// What to minimize when choosing a Merkle cap height.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum CapHeightObjective {
+    /// The number of hashes in the proof: the caps and the siblings in the Merkle paths.
+    ProofSize,
+    /// The number of permutations a recursive verifier spends on Merkle caps and paths. Caps are
+    /// cheaper here than in the proof, as the verifier only absorbs them into its challenger.
+    RecursiveVerifier,
+}
+
+impl CapHeightObjective {
+    /// The cost of a commitment to `2^tree_height` leaves with a cap of height `cap_height`, which
+    /// is opened at `num_queries` leaves.
+    pub fn commitment_cost(
+        self,
+        tree_height: usize,
+        cap_height: usize,
+        num_queries: usize,
+    ) -> usize {
+        assert!(cap_height <= tree_height);
+        let cap_cost = match self {
+            CapHeightObjective::ProofSize => 1 << cap_height,
+            CapHeightObjective::RecursiveVerifier => {
+                ceil_div_usize(NUM_HASH_OUT_ELTS << cap_height, SPONGE_RATE)
+            }
+        };
+        cap_cost + num_queries * (tree_height - cap_height)
+    }
+
+    /// The cost of sending the final FRI polynomial, with `final_poly_len` coefficients, in the
+    /// same unit as `commitment_cost`.
+    pub fn final_poly_cost(self, final_poly_len: usize) -> usize {
+        let elements = EXTENSION_DEGREE * final_poly_len;
+        match self {
+            CapHeightObjective::ProofSize => ceil_div_usize(elements, NUM_HASH_OUT_ELTS),
+            CapHeightObjective::RecursiveVerifier => ceil_div_usize(elements, SPONGE_RATE),
+        }
+    }
+}
+
+impl FriParams {
+    /// The cost under `objective` of the Merkle trees of a proof with these parameters:
+    /// `num_oracles` trees of LDE values, one tree per FRI reduction, and the final polynomial.
+    /// Returns `None` if the reductions don't fit the degree, or leave a tree smaller than the cap.
+    pub fn merkle_cost(&self, num_oracles: usize, objective: CapHeightObjective) -> Option<usize> {
+        let cap_height = self.config.cap_height;
+        let num_queries = self.config.num_query_rounds;
+        if self.total_arities() > self.degree_bits
+            || self.lde_bits() - self.total_arities() < cap_height
+        {
+            return None;
+        }
+        let mut cost =
+            num_oracles * objective.commitment_cost(self.lde_bits(), cap_height, num_queries);
+        let mut tree_height = self.lde_bits();
+        for &arity_bits in &self.reduction_arity_bits {
+            tree_height -= arity_bits;
+            cost += objective.commitment_cost(tree_height, cap_height, num_queries);
+        }
+        Some(cost + objective.final_poly_cost(self.final_poly_len()))
+    }
+}
+
+impl FriConfig {
+    /// The cap height minimizing `objective` for proofs of polynomials of degree `2^degree_bits`
+    /// with `num_oracles` committed batches. Since the reduction arities may depend on the cap
+    /// height, each candidate is costed with the arities it leads to.
+    pub fn optimal_cap_height(
+        &self,
+        degree_bits: usize,
+        num_oracles: usize,
+        objective: CapHeightObjective,
+    ) -> usize {
+        (0..=degree_bits + self.rate_bits)
+            .filter_map(|cap_height| {
+                let config = Self {
+                    cap_height,
+                    ..self.clone()
+                };
+                let cost = config
+                    .fri_params(degree_bits, false)
+                    .merkle_cost(num_oracles, objective)?;
+                Some((cost, cap_height))
+            })
+            .min()
+            .map_or(self.cap_height, |(_, cap_height)| cap_height)
+    }
+
+    /// This config with the cap height given by `optimal_cap_height`. Tuning is opt-in: configs
+    /// which aren't passed through this keep their explicit `cap_height`, which serves as the
+    /// override, and as the fallback if no height fits the reduction strategy.
+    pub fn with_optimal_cap_height(
+        &self,
+        degree_bits: usize,
+        num_oracles: usize,
+        objective: CapHeightObjective,
+    ) -> Self {
+        Self {
+            cap_height: self.optimal_cap_height(degree_bits, num_oracles, objective),
+            ..self.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::field::types::Field;
+    use crate::fri::reduction_strategies::FriReductionStrategy;
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    #[test]
+    fn test_commitment_cost() {
+        let objective = CapHeightObjective::ProofSize;
+        assert_eq!(objective.commitment_cost(10, 0, 28), 1 + 28 * 10);
+        assert_eq!(objective.commitment_cost(10, 10, 28), 1 << 10);
+        // Recursive verifiers absorb two digests per permutation.
+        let objective = CapHeightObjective::RecursiveVerifier;
+        assert_eq!(objective.commitment_cost(10, 4, 28), 8 + 28 * 6);
+    }
+
+    #[test]
+    fn test_optimal_cap_height() {
+        let config = CircuitConfig::standard_recursion_config().fri_config;
+        for degree_bits in [4, 12, 20] {
+            for objective in [
+                CapHeightObjective::ProofSize,
+                CapHeightObjective::RecursiveVerifier,
+            ] {
+                let tuned = config.with_optimal_cap_height(degree_bits, 4, objective);
+                let params = tuned.fri_params(degree_bits, false);
+                let cost = params.merkle_cost(4, objective).unwrap();
+                // No other height is cheaper.
+                for cap_height in 0..=params.lde_bits() {
+                    let other = FriConfig {
+                        cap_height,
+                        ..config.clone()
+                    };
+                    let other_cost = other
+                        .fri_params(degree_bits, false)
+                        .merkle_cost(4, objective);
+                    assert!(other_cost.map_or(true, |other_cost| other_cost >= cost));
+                }
+            }
+        }
+
+        // Recursive verifiers favour larger caps, and more queries call for larger caps.
+        let proof_size = config.optimal_cap_height(20, 4, CapHeightObjective::ProofSize);
+        let recursion = config.optimal_cap_height(20, 4, CapHeightObjective::RecursiveVerifier);
+        assert!(recursion >= proof_size);
+        let more_queries = FriConfig {
+            num_query_rounds: 4 * config.num_query_rounds,
+            reduction_strategy: FriReductionStrategy::Fixed(vec![4, 4]),
+            ..config
+        };
+        let fewer_queries = FriConfig {
+            num_query_rounds: 4,
+            ..more_queries.clone()
+        };
+        assert!(
+            more_queries.optimal_cap_height(20, 4, CapHeightObjective::ProofSize)
+                > fewer_queries.optimal_cap_height(20, 4, CapHeightObjective::ProofSize)
+        );
+    }
+
+    #[test]
+    fn test_optimal_cap_height_with_large_fixed_arities() {
+        // Reductions which don't fit the degree are skipped rather than underflowing, and the
+        // explicit cap height is kept when no height fits.
+        let config = FriConfig {
+            reduction_strategy: FriReductionStrategy::Fixed(vec![4, 4, 4]),
+            ..CircuitConfig::standard_recursion_config().fri_config
+        };
+        let params = config.fri_params(8, false);
+        assert_eq!(params.merkle_cost(4, CapHeightObjective::ProofSize), None);
+        assert_eq!(
+            config.optimal_cap_height(8, 4, CapHeightObjective::ProofSize),
+            config.cap_height
+        );
+    }
+
+    #[test]
+    fn test_prove_with_optimal_cap_height() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let build = |config: CircuitConfig| {
+            let mut builder = CircuitBuilder::<F, D>::new(config);
+            let x = builder.add_virtual_target();
+            let y = (0..100).fold(x, |y, _| builder.mul(y, x));
+            builder.register_public_input(y);
+            (x, builder.build::<C>())
+        };
+
+        // The cap height doesn't change the degree, which a first build gives.
+        let standard = CircuitConfig::standard_recursion_config();
+        let degree_bits = build(standard.clone()).1.common.degree_bits();
+        let config = CircuitConfig {
+            fri_config: standard.fri_config.with_optimal_cap_height(
+                degree_bits,
+                4,
+                CapHeightObjective::RecursiveVerifier,
+            ),
+            ..standard
+        };
+        let (x, data) = build(config.clone());
+        assert_eq!(data.common.degree_bits(), degree_bits);
+        assert_eq!(
+            data.common.fri_params.config.cap_height,
+            config.fri_config.cap_height
+        );
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::TWO);
+        let proof = data.prove(pw)?;
+        data.verify(proof)
+    }
+}
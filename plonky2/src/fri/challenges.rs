@@ -1,5 +1,8 @@
+use alloc::vec::Vec;
+
 use crate::field::extension::Extendable;
 use crate::field::polynomial::PolynomialCoeffs;
+use crate::field::types::PrimeField64;
 use crate::fri::proof::{FriChallenges, FriChallengesTarget};
 use crate::fri::structure::{FriOpenings, FriOpeningsTarget};
 use crate::fri::FriConfig;
@@ -7,10 +10,42 @@ use crate::gadgets::polynomial::PolynomialCoeffsExtTarget;
 use crate::hash::hash_types::{MerkleCapTarget, RichField};
 use crate::hash::merkle_tree::MerkleCap;
 use crate::iop::challenger::{Challenger, RecursiveChallenger};
-use crate::iop::target::Target;
+use crate::iop::target::{BoolTarget, Target};
 use crate::plonk::circuit_builder::CircuitBuilder;
 use crate::plonk::config::{AlgebraicHasher, GenericConfig, Hasher};
 
+/// Derives a FRI query index into an LDE of size `2^lde_bits` from a challenge `rand` squeezed
+/// from the transcript. The index is `rand mod 2^lde_bits`, where `rand` is taken in canonical
+/// form, i.e. the low `lde_bits` bits of `rand.to_canonical_u64()`.
+pub fn fri_query_index<F: PrimeField64>(rand: F, lde_bits: usize) -> usize {
+    (rand.to_canonical_u64() as usize) & ((1 << lde_bits) - 1)
+}
+
+/// Squeezes `num_query_rounds` challenges and derives a query index from each, as done by the FRI
+/// prover and verifier after the proof-of-work check.
+pub fn fri_query_indices<F: RichField, H: Hasher<F>>(
+    challenger: &mut Challenger<F, H>,
+    num_query_rounds: usize,
+    lde_bits: usize,
+) -> Vec<usize> {
+    (0..num_query_rounds)
+        .map(|_| fri_query_index(challenger.get_challenge(), lde_bits))
+        .collect()
+}
+
+/// In-circuit version of [`fri_query_index`], returning the little-endian bits of the index.
+///
+/// Note that the underlying bit decomposition does not enforce a canonical encoding of `rand`, so
+/// for `rand < 2^64 - p` a prover could use the bits of `rand + p` instead. See
+/// `CircuitBuilder::assert_noncanonical_indices_ok` for why this is harmless.
+pub fn fri_query_index_bits_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    rand: Target,
+    lde_bits: usize,
+) -> Vec<BoolTarget> {
+    builder.low_bits(rand, lde_bits, F::BITS)
+}
+
 impl<F: RichField, H: Hasher<F>> Challenger<F, H> {
     pub fn observe_openings<const D: usize>(&mut self, openings: &FriOpenings<F, D>)
     where
@@ -33,7 +68,7 @@ impl<F: RichField, H: Hasher<F>> Challenger<F, H> {
         F: RichField + Extendable<D>,
     {
         let num_fri_queries = config.num_query_rounds;
-        let lde_size = 1 << (degree_bits + config.rate_bits);
+        let lde_bits = degree_bits + config.rate_bits;
         // Scaling factor to combine polynomials.
         let fri_alpha = self.get_extension_challenge::<D>();
 
@@ -51,9 +86,7 @@ impl<F: RichField, H: Hasher<F>> Challenger<F, H> {
         self.observe_element(pow_witness);
         let fri_pow_response = self.get_challenge();
 
-        let fri_query_indices = (0..num_fri_queries)
-            .map(|_| self.get_challenge().to_canonical_u64() as usize % lde_size)
-            .collect();
+        let fri_query_indices = fri_query_indices(self, num_fri_queries, lde_bits);
 
         FriChallenges {
             fri_alpha,
@@ -111,3 +144,43 @@ impl<F: RichField + Extendable<D>, H: AlgebraicHasher<F>, const D: usize>
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::field::types::{Field, Sample};
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    #[test]
+    fn test_fri_query_index() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let lde_bits = 12;
+
+        assert_eq!(
+            fri_query_index(F::from_canonical_u64(0x1234_5678), lde_bits),
+            0x678
+        );
+        assert_eq!(fri_query_index(F::NEG_ONE, lde_bits), 0);
+
+        let rands = [F::rand(), F::NEG_ONE, F::ZERO];
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let mut pw = PartialWitness::new();
+        for rand in rands {
+            let rand_t = builder.add_virtual_target();
+            pw.set_target(rand_t, rand);
+            let bits = fri_query_index_bits_circuit(&mut builder, rand_t, lde_bits);
+            let index = builder.le_sum(bits.into_iter());
+            let expected =
+                builder.constant(F::from_canonical_usize(fri_query_index(rand, lde_bits)));
+            builder.connect(index, expected);
+        }
+        let data = builder.build::<C>();
+        data.verify(data.prove(pw)?)
+    }
+}
@@ -1,21 +1,29 @@
+use alloc::vec;
 use alloc::vec::Vec;
 
-use serde::Serialize;
+use anyhow::{ensure, Result};
+use num::BigUint;
+use serde::{Deserialize, Serialize};
 
+use crate::field::types::Field;
 use crate::fri::reduction_strategies::FriReductionStrategy;
+use crate::fri::security::{SecurityAssumption, DEFAULT_PROOF_OF_WORK_BITS};
 
-mod challenges;
+pub mod cap_height;
+pub mod challenges;
+pub mod onchain_cost;
 pub mod oracle;
 pub mod proof;
 pub mod prover;
 pub mod recursive_verifier;
 pub mod reduction_strategies;
+pub mod security;
 pub mod structure;
 mod validate_shape;
 pub mod verifier;
 pub mod witness_util;
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct FriConfig {
     /// `rate = 2^{-rate_bits}`.
     pub rate_bits: usize,
@@ -29,9 +37,59 @@ pub struct FriConfig {
 
     /// Number of query rounds to perform.
     pub num_query_rounds: usize,
+
+    /// The soundness model under which this config's security is measured.
+    #[serde(default)]
+    pub security_assumption: SecurityAssumption,
+
+    /// The shift of the coset on which polynomials are committed to (and quotients computed), as
+    /// a canonical field element, or `None` for `Field::coset_shift`. Only plonky2 circuits
+    /// support other shifts; STARK provers require the default.
+    #[serde(default)]
+    pub coset_shift: Option<u64>,
 }
 
 impl FriConfig {
+    /// A config reaching `security_bits` bits of FRI security under `security_assumption`, with
+    /// `DEFAULT_PROOF_OF_WORK_BITS` of grinding and as few query rounds as possible. The achieved
+    /// level also depends on the field and instance size; see [`Self::security_bits`].
+    pub fn for_security(
+        security_bits: usize,
+        security_assumption: SecurityAssumption,
+        rate_bits: usize,
+        cap_height: usize,
+        reduction_strategy: FriReductionStrategy,
+    ) -> Self {
+        let proof_of_work_bits = DEFAULT_PROOF_OF_WORK_BITS.min(security_bits as u32);
+        Self {
+            rate_bits,
+            cap_height,
+            proof_of_work_bits,
+            reduction_strategy,
+            num_query_rounds: security_assumption.num_query_rounds(
+                security_bits,
+                rate_bits,
+                proof_of_work_bits,
+            ),
+            security_assumption,
+            coset_shift: None,
+        }
+    }
+
+    /// The bits of security achieved when proving polynomials of degree `2^degree_bits` over an
+    /// extension field of `field_bits` bits.
+    pub fn security_bits(&self, field_bits: usize, degree_bits: usize) -> usize {
+        let assumption = self.security_assumption;
+        let query_bits = assumption.query_security_bits(self.num_query_rounds, self.rate_bits)
+            + self.proof_of_work_bits as usize;
+        let commit_bits = assumption.commit_phase_security_bits(
+            field_bits,
+            degree_bits + self.rate_bits,
+            self.rate_bits,
+        );
+        query_bits.min(commit_bits)
+    }
+
     pub fn rate(&self) -> f64 {
         1.0 / ((1 << self.rate_bits) as f64)
     }
@@ -54,11 +112,87 @@ impl FriConfig {
     pub fn num_cap_elements(&self) -> usize {
         1 << self.cap_height
     }
+
+    /// The shift of the LDE coset, as configured by the `coset_shift` field.
+    pub fn coset_shift<F: Field>(&self) -> F {
+        self.coset_shift
+            .map_or_else(F::coset_shift, F::from_canonical_u64)
+    }
+
+    /// Checks that the LDE coset of polynomials of degree `2^degree_bits` is disjoint from the LDE
+    /// subgroup, so that in particular the zero polynomial of the trace subgroup has no roots on
+    /// it.
+    pub fn validate_coset_shift<F: Field>(&self, degree_bits: usize) -> Result<()> {
+        if let Some(shift) = self.coset_shift {
+            ensure!(
+                BigUint::from(shift) < F::order(),
+                "The coset shift {} is not a canonical field element",
+                shift
+            );
+        }
+        let shift = self.coset_shift::<F>();
+        ensure!(shift.is_nonzero(), "The coset shift must be nonzero");
+        ensure!(
+            shift.exp_power_of_2(degree_bits + self.rate_bits) != F::ONE,
+            "The coset shift {} lies in the LDE subgroup of size 2^{}",
+            shift,
+            degree_bits + self.rate_bits
+        );
+        Ok(())
+    }
+
+    /// Checks the parts of the config which don't depend on the instance size.
+    pub fn validate(&self) -> Result<()> {
+        ensure!(
+            self.rate_bits > 0,
+            "FRI needs a rate below 1; set `rate_bits` to at least 1"
+        );
+        ensure!(
+            self.num_query_rounds > 0,
+            "FRI needs at least one query round"
+        );
+        match &self.reduction_strategy {
+            FriReductionStrategy::Fixed(arity_bits) => ensure!(
+                !arity_bits.contains(&0),
+                "FRI reduction arities must be at least 2, but `Fixed` has an arity of 2^0"
+            ),
+            FriReductionStrategy::ConstantArityBits(arity_bits, _) => ensure!(
+                *arity_bits > 0,
+                "FRI reduction arities must be at least 2, but `ConstantArityBits` has 2^0"
+            ),
+            FriReductionStrategy::MinSize(_) => {}
+        }
+        Ok(())
+    }
+
+    /// Checks that the config suits polynomials of degree `2^degree_bits`.
+    pub fn validate_for_degree_bits(&self, degree_bits: usize) -> Result<()> {
+        self.validate()?;
+        let lde_bits = degree_bits + self.rate_bits;
+        ensure!(
+            self.cap_height <= lde_bits,
+            "A Merkle cap of height {} needs more than the 2^{} leaves of an LDE of a degree 2^{} \
+             polynomial; reduce `cap_height`",
+            self.cap_height,
+            lde_bits,
+            degree_bits
+        );
+        let total_arity_bits: usize = self.fri_params(degree_bits, false).total_arities();
+        ensure!(
+            total_arity_bits <= lde_bits - self.cap_height,
+            "FRI reductions of total arity 2^{} leave Merkle trees shorter than the cap height {} \
+             for a degree 2^{} polynomial; reduce the arities or `cap_height`",
+            total_arity_bits,
+            self.cap_height,
+            degree_bits
+        );
+        Ok(())
+    }
 }
 
 /// FRI parameters, including generated parameters which are specific to an instance size, in
 /// contrast to `FriConfig` which is user-specified and independent of instance size.
-#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct FriParams {
     /// User-specified FRI configuration.
     pub config: FriConfig,
@@ -89,6 +223,11 @@ impl FriParams {
         self.degree_bits + self.config.rate_bits
     }
 
+    /// The bits of security achieved over an extension field of `field_bits` bits.
+    pub fn security_bits(&self, field_bits: usize) -> usize {
+        self.config.security_bits(field_bits, self.degree_bits)
+    }
+
     pub fn lde_size(&self) -> usize {
         1 << self.lde_bits()
     }
@@ -100,4 +239,27 @@ impl FriParams {
     pub fn final_poly_len(&self) -> usize {
         1 << self.final_poly_bits()
     }
+
+    /// Encodes these parameters as field elements, for use in transcript domain separation.
+    pub fn to_field_elements<F: Field>(&self) -> Vec<F> {
+        let mut elements = vec![
+            F::from_canonical_usize(self.config.rate_bits),
+            F::from_canonical_usize(self.config.cap_height),
+            F::from_canonical_u32(self.config.proof_of_work_bits),
+            F::from_canonical_usize(self.config.num_query_rounds),
+            F::from_bool(self.hiding),
+            F::from_canonical_usize(self.degree_bits),
+            F::from_canonical_usize(self.reduction_arity_bits.len()),
+        ];
+        elements.extend(
+            self.reduction_arity_bits
+                .iter()
+                .map(|&bits| F::from_canonical_usize(bits)),
+        );
+        // Omitted for the default shift, to keep the digests of existing circuits unchanged.
+        if let Some(shift) = self.config.coset_shift {
+            elements.push(F::from_noncanonical_u64(shift));
+        }
+        elements
+    }
 }
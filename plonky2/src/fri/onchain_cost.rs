@@ -0,0 +1,176 @@
+//! Estimates the cost of verifying a FRI-based proof in a smart contract, to compare configs for
+//! on-chain verification.
+//!
+//! There is no contract verifier in this crate, so the estimate only counts the work which any such
+//! verifier has to do: reading the proof from calldata, hashing the opened Merkle leaves and the
+//! digests along their paths, and absorbing the proof into the Fiat-Shamir transcript. Hashes are
+//! counted in permutations of the config's hasher rather than in gas, since their gas cost depends
+//! on how the contract implements the hasher. Field arithmetic, which grows with the number of
+//! openings and queries, isn't counted.
+
+use crate::fri::structure::FriOracleInfo;
+use crate::fri::FriParams;
+use crate::hash::hash_types::RichField;
+use crate::hash::hashing::PlonkyPermutation;
+use crate::plonk::config::Hasher;
+use crate::util::ceil_div_usize;
+
+/// The gas charged per nonzero byte of calldata, as of EIP-2028. Serialized field elements and
+/// hashes are close to uniformly random, so almost every byte of a proof is nonzero.
+pub const CALLDATA_GAS_PER_BYTE: usize = 16;
+
+/// The size in bytes of a serialized field element.
+const FIELD_SIZE: usize = 8;
+
+/// The estimated cost of verifying one proof on chain.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct OnChainVerifierCost {
+    /// The size of the serialized proof, which is passed as calldata.
+    pub proof_bytes: usize,
+    /// Permutations spent hashing the opened Merkle leaves.
+    pub leaf_hashes: usize,
+    /// Two-to-one compressions along the opened Merkle paths.
+    pub path_hashes: usize,
+    /// Permutations spent absorbing the proof into the transcript. This is approximate, as it
+    /// assumes every element sent besides Merkle proofs is absorbed, and ignores squeezing.
+    pub transcript_hashes: usize,
+}
+
+impl OnChainVerifierCost {
+    pub fn calldata_gas(&self) -> usize {
+        self.proof_bytes * CALLDATA_GAS_PER_BYTE
+    }
+
+    pub fn total_hashes(&self) -> usize {
+        self.leaf_hashes + self.path_hashes + self.transcript_hashes
+    }
+
+    /// The estimated gas of a verification whose hash permutations cost `gas_per_hash` each, not
+    /// counting field arithmetic or the transaction's base cost.
+    pub fn estimated_gas(&self, gas_per_hash: usize) -> usize {
+        self.calldata_gas() + self.total_hashes() * gas_per_hash
+    }
+}
+
+impl FriParams {
+    /// Estimates the cost of verifying a proof with `H` whose polynomials are committed to in
+    /// `oracles`. Besides its FRI proof, the proof holds `num_caps` Merkle caps, `num_openings`
+    /// values of the degree `extension_degree` extension, and `num_elements` other base field
+    /// elements, e.g. public inputs.
+    pub fn onchain_verifier_cost<F: RichField, H: Hasher<F>>(
+        &self,
+        oracles: &[FriOracleInfo],
+        num_caps: usize,
+        num_openings: usize,
+        num_elements: usize,
+        extension_degree: usize,
+    ) -> OnChainVerifierCost {
+        let rate = H::Permutation::RATE;
+        let ext_size = extension_degree * FIELD_SIZE;
+        let cap_height = self.config.cap_height;
+        let num_caps = num_caps + self.reduction_arity_bits.len();
+        let final_poly_elements = self.final_poly_len() * extension_degree;
+        // Leaves which fit in a hash are used as their own digest; see `Hasher::hash_or_noop`.
+        let leaf_hashes = |leaf_len: usize| {
+            if leaf_len * FIELD_SIZE <= H::HASH_SIZE {
+                0
+            } else {
+                ceil_div_usize(leaf_len, rate)
+            }
+        };
+
+        // The opened leaves and Merkle proofs of a single query, in field elements and hashes.
+        // Merkle proofs are prefixed with their length, as a single byte.
+        let mut query_elements = 0;
+        let mut query_bytes = 0;
+        let mut query_leaf_hashes = 0;
+        let mut query_path_hashes = 0;
+        for oracle in oracles {
+            let leaf_len = oracle.leaf_len(self.hiding);
+            let path_len = oracle
+                .merkle_proof_len(self)
+                .expect("Oracle's tree is shorter than the Merkle cap");
+            query_elements += leaf_len;
+            query_bytes += 1 + path_len * H::HASH_SIZE;
+            query_leaf_hashes += leaf_hashes(leaf_len);
+            query_path_hashes += path_len;
+        }
+        let mut codeword_len_bits = self.lde_bits();
+        for &arity_bits in &self.reduction_arity_bits {
+            codeword_len_bits -= arity_bits;
+            let path_len = codeword_len_bits - cap_height;
+            query_elements += extension_degree << arity_bits;
+            query_bytes += 1 + path_len * H::HASH_SIZE;
+            query_leaf_hashes += leaf_hashes(extension_degree << arity_bits);
+            query_path_hashes += path_len;
+        }
+        let num_queries = self.config.num_query_rounds;
+
+        // Everything but the query rounds is absorbed into the transcript, including the proof of
+        // work witness.
+        let hash_elements = ceil_div_usize(H::HASH_SIZE, FIELD_SIZE);
+        let absorbed_elements = ((num_caps * hash_elements) << cap_height)
+            + num_openings * extension_degree
+            + num_elements
+            + final_poly_elements
+            + 1;
+        let proof_bytes = ((num_caps * H::HASH_SIZE) << cap_height)
+            + num_openings * ext_size
+            + (num_elements + final_poly_elements + 1) * FIELD_SIZE
+            + num_queries * (query_elements * FIELD_SIZE + query_bytes);
+
+        OnChainVerifierCost {
+            proof_bytes,
+            leaf_hashes: num_queries * query_leaf_hashes,
+            path_hashes: num_queries * query_path_hashes,
+            transcript_hashes: ceil_div_usize(absorbed_elements, rate),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::field::types::Field;
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    #[test]
+    fn test_onchain_verifier_cost() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x = builder.add_virtual_public_input();
+        let x_cubed = builder.cube(x);
+        builder.register_public_input(x_cubed);
+        let data = builder.build::<C>();
+
+        let cost = data.common.onchain_verifier_cost::<C>();
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::TWO);
+        let proof = data.prove(pw)?;
+        assert_eq!(cost.proof_bytes, proof.to_bytes().len());
+        assert_eq!(
+            cost.calldata_gas(),
+            cost.proof_bytes * CALLDATA_GAS_PER_BYTE
+        );
+        assert!(cost.leaf_hashes > 0 && cost.path_hashes > 0 && cost.transcript_hashes > 0);
+        data.verify(proof)?;
+
+        // Fewer queries make for a smaller proof with fewer Merkle paths to check.
+        let mut fewer_queries = data.common;
+        fewer_queries.fri_params.config.num_query_rounds /= 2;
+        let fewer_queries_cost = fewer_queries.onchain_verifier_cost::<C>();
+        assert!(fewer_queries_cost.proof_bytes < cost.proof_bytes);
+        assert!(fewer_queries_cost.path_hashes < cost.path_hashes);
+        assert!(fewer_queries_cost.estimated_gas(1000) < cost.estimated_gas(1000));
+        Ok(())
+    }
+}
@@ -1,12 +1,11 @@
-use alloc::format;
 use alloc::vec::Vec;
+use alloc::{format, vec};
 
-use itertools::Itertools;
 use plonky2_field::types::Field;
 use plonky2_maybe_rayon::*;
 
 use crate::field::extension::Extendable;
-use crate::field::fft::FftRootTable;
+use crate::field::fft::{fft_with_options, FftRootTable};
 use crate::field::packed::PackedField;
 use crate::field::polynomial::{PolynomialCoeffs, PolynomialValues};
 use crate::fri::proof::FriProof;
@@ -20,12 +19,87 @@ use crate::plonk::config::GenericConfig;
 use crate::timed;
 use crate::util::reducing::ReducingFactor;
 use crate::util::timing::TimingTree;
-use crate::util::{log2_strict, reverse_bits, reverse_index_bits_in_place, transpose};
+use crate::util::{log2_strict, reverse_bits};
 
 /// Four (~64 bit) field elements gives ~128 bit security.
 pub const SALT_SIZE: usize = 4;
 
+/// Buffers for the LDEs, Merkle leaves and polynomials of [`PolynomialBatch`]es, reused by every
+/// batch committed with the arena. A batch's buffers are handed back with [`Self::recycle`] once
+/// it is no longer needed. Merkle digests are still allocated per tree, as they are a small
+/// fraction of the leaves' size.
+#[derive(Debug, Default)]
+pub struct CommitmentArena<F: Field> {
+    buffers: Vec<Vec<F>>,
+    /// The number of buffers handed out by `take` and not yet returned. Buffers returned beyond
+    /// it, e.g. polynomials which weren't allocated by the arena, are dropped, so that the arena
+    /// doesn't grow across proofs.
+    num_outstanding: usize,
+}
+
+#[cfg(feature = "hardened")]
+impl<F: Field> Drop for CommitmentArena<F> {
+    fn drop(&mut self) {
+        for buffer in &mut self.buffers {
+            crate::util::zeroize::zeroize_vec(buffer);
+        }
+    }
+}
+
+impl<F: Field> CommitmentArena<F> {
+    pub fn new() -> Self {
+        Self {
+            buffers: Vec::new(),
+            num_outstanding: 0,
+        }
+    }
+
+    /// The number of buffers available for reuse.
+    pub fn num_buffers(&self) -> usize {
+        self.buffers.len()
+    }
+
+    /// Takes `n` buffers, reusing the arena's before allocating new ones. Their contents are
+    /// unspecified, so callers must clear them.
+    pub(crate) fn take(&mut self, n: usize) -> Vec<Vec<F>> {
+        self.num_outstanding += n;
+        let reused = self.buffers.len().saturating_sub(n);
+        let mut buffers = self.buffers.split_off(reused);
+        buffers.resize_with(n, Vec::new);
+        buffers
+    }
+
+    pub(crate) fn put(&mut self, buffers: impl IntoIterator<Item = Vec<F>>) {
+        for buffer in buffers {
+            if self.num_outstanding == 0 {
+                break;
+            }
+            self.num_outstanding -= 1;
+            self.buffers.push(buffer);
+        }
+    }
+
+    /// Returns the Merkle leaves and polynomials of `batch` to the arena.
+    pub fn recycle<C: GenericConfig<D, F = F>, const D: usize>(
+        &mut self,
+        batch: PolynomialBatch<F, C, D>,
+    ) where
+        F: RichField + Extendable<D>,
+    {
+        let PolynomialBatch {
+            polynomials,
+            merkle_tree,
+            ..
+        } = batch;
+        self.put(merkle_tree.leaves);
+        self.put(polynomials.into_iter().map(|p| p.coeffs));
+    }
+}
+
 /// Represents a FRI oracle, i.e. a batch of polynomials which have been Merklized.
+///
+/// Its Merkle leaves are evaluations of a low-degree extension, so it can't be extended in place
+/// with more rows. See `MerkleTree::extend`.
 #[derive(Eq, PartialEq, Debug)]
 pub struct PolynomialBatch<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
 {
@@ -47,6 +121,48 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
         cap_height: usize,
         timing: &mut TimingTree,
         fft_root_table: Option<&FftRootTable<F>>,
+    ) -> Self {
+        Self::from_values_grouped(
+            values,
+            rate_bits,
+            blinding,
+            cap_height,
+            0,
+            timing,
+            fft_root_table,
+        )
+    }
+
+    /// Creates a list polynomial commitment for the polynomials `polynomials`.
+    pub fn from_coeffs(
+        polynomials: Vec<PolynomialCoeffs<F>>,
+        rate_bits: usize,
+        blinding: bool,
+        cap_height: usize,
+        timing: &mut TimingTree,
+        fft_root_table: Option<&FftRootTable<F>>,
+    ) -> Self {
+        Self::from_coeffs_grouped(
+            polynomials,
+            rate_bits,
+            blinding,
+            cap_height,
+            0,
+            timing,
+            fft_root_table,
+        )
+    }
+
+    /// Like `from_values`, but evaluates the polynomials on the coset shifted by `coset_shift`
+    /// rather than `F::coset_shift()`; see `FriConfig::coset_shift`.
+    pub fn from_values_on_coset(
+        values: Vec<PolynomialValues<F>>,
+        rate_bits: usize,
+        blinding: bool,
+        cap_height: usize,
+        coset_shift: F,
+        timing: &mut TimingTree,
+        fft_root_table: Option<&FftRootTable<F>>,
     ) -> Self {
         let coeffs = timed!(
             timing,
@@ -54,39 +170,188 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
             values.into_par_iter().map(|v| v.ifft()).collect::<Vec<_>>()
         );
 
-        Self::from_coeffs(
+        Self::from_coeffs_on_coset(
             coeffs,
             rate_bits,
             blinding,
             cap_height,
+            coset_shift,
             timing,
             fft_root_table,
         )
     }
 
-    /// Creates a list polynomial commitment for the polynomials `polynomials`.
-    pub fn from_coeffs(
+    /// Like `from_coeffs`, but evaluates the polynomials on the coset shifted by `coset_shift`
+    /// rather than `F::coset_shift()`; see `FriConfig::coset_shift`.
+    pub fn from_coeffs_on_coset(
+        polynomials: Vec<PolynomialCoeffs<F>>,
+        rate_bits: usize,
+        blinding: bool,
+        cap_height: usize,
+        coset_shift: F,
+        timing: &mut TimingTree,
+        fft_root_table: Option<&FftRootTable<F>>,
+    ) -> Self {
+        Self::from_coeffs_on_coset_in(
+            polynomials,
+            rate_bits,
+            blinding,
+            cap_height,
+            coset_shift,
+            timing,
+            fft_root_table,
+            &mut CommitmentArena::new(),
+        )
+    }
+
+    /// Like `from_values_on_coset`, but takes the LDE and Merkle leaf buffers from `arena`.
+    pub fn from_values_on_coset_in(
+        values: Vec<PolynomialValues<F>>,
+        rate_bits: usize,
+        blinding: bool,
+        cap_height: usize,
+        coset_shift: F,
+        timing: &mut TimingTree,
+        fft_root_table: Option<&FftRootTable<F>>,
+        arena: &mut CommitmentArena<F>,
+    ) -> Self {
+        let coeffs = timed!(
+            timing,
+            "IFFT",
+            values.into_par_iter().map(|v| v.ifft()).collect::<Vec<_>>()
+        );
+
+        Self::from_coeffs_on_coset_in(
+            coeffs,
+            rate_bits,
+            blinding,
+            cap_height,
+            coset_shift,
+            timing,
+            fft_root_table,
+            arena,
+        )
+    }
+
+    /// Like `from_coeffs_on_coset`, but takes the LDE and Merkle leaf buffers from `arena`.
+    pub fn from_coeffs_on_coset_in(
         polynomials: Vec<PolynomialCoeffs<F>>,
         rate_bits: usize,
         blinding: bool,
         cap_height: usize,
+        coset_shift: F,
+        timing: &mut TimingTree,
+        fft_root_table: Option<&FftRootTable<F>>,
+        arena: &mut CommitmentArena<F>,
+    ) -> Self {
+        Self::from_coeffs_grouped_on_coset(
+            polynomials,
+            rate_bits,
+            blinding,
+            cap_height,
+            0,
+            coset_shift,
+            timing,
+            fft_root_table,
+            arena,
+        )
+    }
+
+    /// Like `from_values`, but each Merkle leaf holds the values at `2^leaf_group_bits`
+    /// consecutive points, as described in `FriOracleInfo::leaf_group_bits`.
+    pub fn from_values_grouped(
+        values: Vec<PolynomialValues<F>>,
+        rate_bits: usize,
+        blinding: bool,
+        cap_height: usize,
+        leaf_group_bits: usize,
         timing: &mut TimingTree,
         fft_root_table: Option<&FftRootTable<F>>,
+    ) -> Self {
+        let coeffs = timed!(
+            timing,
+            "IFFT",
+            values.into_par_iter().map(|v| v.ifft()).collect::<Vec<_>>()
+        );
+
+        Self::from_coeffs_grouped(
+            coeffs,
+            rate_bits,
+            blinding,
+            cap_height,
+            leaf_group_bits,
+            timing,
+            fft_root_table,
+        )
+    }
+
+    /// Like `from_coeffs`, but each Merkle leaf holds the values at `2^leaf_group_bits`
+    /// consecutive points, as described in `FriOracleInfo::leaf_group_bits`.
+    pub fn from_coeffs_grouped(
+        polynomials: Vec<PolynomialCoeffs<F>>,
+        rate_bits: usize,
+        blinding: bool,
+        cap_height: usize,
+        leaf_group_bits: usize,
+        timing: &mut TimingTree,
+        fft_root_table: Option<&FftRootTable<F>>,
+    ) -> Self {
+        Self::from_coeffs_grouped_on_coset(
+            polynomials,
+            rate_bits,
+            blinding,
+            cap_height,
+            leaf_group_bits,
+            F::coset_shift(),
+            timing,
+            fft_root_table,
+            &mut CommitmentArena::new(),
+        )
+    }
+
+    fn from_coeffs_grouped_on_coset(
+        polynomials: Vec<PolynomialCoeffs<F>>,
+        rate_bits: usize,
+        blinding: bool,
+        cap_height: usize,
+        leaf_group_bits: usize,
+        coset_shift: F,
+        timing: &mut TimingTree,
+        fft_root_table: Option<&FftRootTable<F>>,
+        arena: &mut CommitmentArena<F>,
     ) -> Self {
         let degree = polynomials[0].len();
-        let lde_values = timed!(
+        let salt_size = if blinding { SALT_SIZE } else { 0 };
+        let mut lde_values = arena.take(polynomials.len() + salt_size);
+        timed!(
             timing,
             "FFT + blinding",
-            Self::lde_values(&polynomials, rate_bits, blinding, fft_root_table)
+            Self::lde_values(
+                &polynomials,
+                rate_bits,
+                coset_shift,
+                fft_root_table,
+                &mut lde_values
+            )
         );
 
-        let mut leaves = timed!(timing, "transpose LDEs", transpose(&lde_values));
-        reverse_index_bits_in_place(&mut leaves);
+        // Each leaf is gathered from the LDEs, in bit-reversed order, by the task which hashes it,
+        // rather than transposing and permuting the LDEs in separate passes beforehand.
+        let lde_bits = log2_strict(lde_values[0].len());
+        let num_leaves = lde_values[0].len() >> leaf_group_bits;
         let merkle_tree = timed!(
             timing,
             "build Merkle tree",
-            MerkleTree::new(leaves, cap_height)
+            MerkleTree::new_from_fn_in(arena.take(num_leaves), cap_height, |i, leaf| {
+                leaf.clear();
+                leaf.reserve(lde_values.len() << leaf_group_bits);
+                for point in i << leaf_group_bits..(i + 1) << leaf_group_bits {
+                    let point = reverse_bits(point, lde_bits);
+                    leaf.extend(lde_values.iter().map(|values| values[point]));
+                }
+            })
         );
+        arena.put(lde_values);
 
         Self {
             polynomials,
@@ -97,38 +362,50 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
         }
     }
 
+    /// Writes the LDEs of `polynomials` into the first `polynomials.len()` buffers of `lde_values`,
+    /// and random salt into the remaining ones, if blinding.
     fn lde_values(
         polynomials: &[PolynomialCoeffs<F>],
         rate_bits: usize,
-        blinding: bool,
+        coset_shift: F,
         fft_root_table: Option<&FftRootTable<F>>,
-    ) -> Vec<Vec<F>> {
+        lde_values: &mut [Vec<F>],
+    ) {
         let degree = polynomials[0].len();
+        let lde_size = degree << rate_bits;
+        let (ldes, salts) = lde_values.split_at_mut(polynomials.len());
 
-        // If blinding, salt with two random elements to each leaf vector.
-        let salt_size = if blinding { SALT_SIZE } else { 0 };
+        ldes.par_iter_mut().zip(polynomials).for_each(|(lde, p)| {
+            assert_eq!(p.len(), degree, "Polynomial degrees inconsistent");
+            // The same as `p.lde(rate_bits).coset_fft_with_options(..)`, but in `lde`'s allocation.
+            lde.clear();
+            lde.reserve(lde_size);
+            lde.extend(coset_shift.powers().zip(&p.coeffs).map(|(r, &c)| r * c));
+            lde.resize(lde_size, F::ZERO);
+            let coeffs = PolynomialCoeffs::new(core::mem::take(lde));
+            *lde = fft_with_options(coeffs, Some(rate_bits), fft_root_table).values;
+        });
+        salts.par_iter_mut().for_each(|salt| {
+            salt.clear();
+            salt.extend((0..lde_size).map(|_| F::rand()));
+        });
+    }
 
-        polynomials
-            .par_iter()
-            .map(|p| {
-                assert_eq!(p.len(), degree, "Polynomial degrees inconsistent");
-                p.lde(rate_bits)
-                    .coset_fft_with_options(F::coset_shift(), Some(rate_bits), fft_root_table)
-                    .values
-            })
-            .chain(
-                (0..salt_size)
-                    .into_par_iter()
-                    .map(|_| F::rand_vec(degree << rate_bits)),
-            )
-            .collect()
+    /// The number of consecutive points whose values each Merkle leaf holds, in bits. See
+    /// `from_coeffs_grouped`.
+    pub fn leaf_group_bits(&self) -> usize {
+        self.degree_log + self.rate_bits - log2_strict(self.merkle_tree.leaves.len())
     }
 
     /// Fetches LDE values at the `index * step`th point.
     pub fn get_lde_values(&self, index: usize, step: usize) -> &[F] {
         let index = index * step;
         let index = reverse_bits(index, self.degree_log + self.rate_bits);
-        let slice = &self.merkle_tree.leaves[index];
+        let leaf_group_bits = self.leaf_group_bits();
+        let leaf = &self.merkle_tree.leaves[index >> leaf_group_bits];
+        let row_len = leaf.len() >> leaf_group_bits;
+        let row_start = (index & ((1 << leaf_group_bits) - 1)) * row_len;
+        let slice = &leaf[row_start..row_start + row_len];
         &slice[..slice.len() - if self.blinding { SALT_SIZE } else { 0 }]
     }
 
@@ -138,24 +415,44 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
     where
         P: PackedField<Scalar = F>,
     {
-        let row_wise = (0..P::WIDTH)
-            .map(|i| self.get_lde_values(index_start + i, step))
-            .collect_vec();
-
-        // This is essentially a transpose, but we will not use the generic transpose method as we
-        // want inner lists to be of type P, not Vecs which would involve allocation.
-        let leaf_size = row_wise[0].len();
-        (0..leaf_size)
-            .map(|j| {
-                let mut packed = P::ZEROS;
-                packed
-                    .as_slice_mut()
-                    .iter_mut()
-                    .zip(&row_wise)
-                    .for_each(|(packed_i, row_i)| *packed_i = row_i[j]);
-                packed
-            })
-            .collect_vec()
+        let mut packed = vec![P::ZEROS; self.get_lde_values(index_start, step).len()];
+        self.get_lde_values_packed_into(index_start, step, &mut packed);
+        packed
+    }
+
+    /// Like `get_lde_values_packed`, but writes the packed values into `out`, whose length must be
+    /// the number of polynomials, so the quotient loop can reuse a buffer instead of allocating one
+    /// per batch of points. This is a transpose: each point's row is read from its leaf, and its
+    /// values are written one at a time into lane `i` of each packed value of `out`.
+    pub fn get_lde_values_packed_into<P>(&self, index_start: usize, step: usize, out: &mut [P])
+    where
+        P: PackedField<Scalar = F>,
+    {
+        self.get_lde_values_packed_strided_into(index_start, 1, step, out);
+    }
+
+    /// Like `get_lde_values_packed_into`, but lane `i` holds the values at index
+    /// `index_start + i * stride`, e.g. to evaluate on one coset of the trace domain at a time.
+    pub fn get_lde_values_packed_strided_into<P>(
+        &self,
+        index_start: usize,
+        stride: usize,
+        step: usize,
+        out: &mut [P],
+    ) where
+        P: PackedField<Scalar = F>,
+    {
+        for lane in 0..P::WIDTH {
+            let row = self.get_lde_values(index_start + lane * stride, step);
+            assert_eq!(
+                row.len(),
+                out.len(),
+                "Output length must match the row length"
+            );
+            for (packed, &value) in out.iter_mut().zip(row) {
+                packed.as_slice_mut()[lane] = value;
+            }
+        }
     }
 
     /// Produces a batch opening proof.
@@ -167,6 +464,18 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
         timing: &mut TimingTree,
     ) -> FriProof<F, C::Hasher, D> {
         assert!(D > 1, "Not implemented for D=1.");
+        for (oracle, info) in oracles.iter().zip(&instance.oracles) {
+            assert_eq!(
+                oracle.rate_bits,
+                fri_params.config.rate_bits + info.extra_rate_bits,
+                "Oracle committed with an unexpected rate"
+            );
+            assert_eq!(
+                oracle.leaf_group_bits(),
+                info.leaf_group_bits,
+                "Oracle committed with unexpected leaf groups"
+            );
+        }
         let alpha = challenger.get_extension_challenge::<D>();
         let mut alpha = ReducingFactor::new(alpha);
 
@@ -200,7 +509,7 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
         let lde_final_values = timed!(
             timing,
             &format!("perform final FFT {}", lde_final_poly.len()),
-            lde_final_poly.coset_fft(F::coset_shift().into())
+            lde_final_poly.coset_fft(fri_params.config.coset_shift::<F>().into())
         );
 
         let fri_proof = fri_proof::<F, C, D>(
@@ -208,6 +517,11 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
                 .par_iter()
                 .map(|c| &c.merkle_tree)
                 .collect::<Vec<_>>(),
+            &instance
+                .oracles
+                .iter()
+                .map(|o| o.leaf_group_bits)
+                .collect::<Vec<_>>(),
             lde_final_poly,
             lde_final_values,
             challenger,
@@ -42,13 +42,30 @@ pub struct FriInitialTreeProof<F: RichField, H: Hasher<F>> {
 }
 
 impl<F: RichField, H: Hasher<F>> FriInitialTreeProof<F, H> {
-    pub(crate) fn unsalted_eval(&self, oracle_index: usize, poly_index: usize, salted: bool) -> F {
-        self.unsalted_evals(oracle_index, salted)[poly_index]
+    pub(crate) fn unsalted_eval(
+        &self,
+        oracle_index: usize,
+        poly_index: usize,
+        salted: bool,
+        leaf_group_bits: usize,
+        x_index: usize,
+    ) -> F {
+        self.unsalted_evals(oracle_index, salted, leaf_group_bits, x_index)[poly_index]
     }
 
-    fn unsalted_evals(&self, oracle_index: usize, salted: bool) -> &[F] {
+    /// The values opened by an oracle at the point `x_index`, without the salt. If the oracle's
+    /// leaves hold groups of `2^leaf_group_bits` points, this picks `x_index`'s within its group.
+    fn unsalted_evals(
+        &self,
+        oracle_index: usize,
+        salted: bool,
+        leaf_group_bits: usize,
+        x_index: usize,
+    ) -> &[F] {
         let evals = &self.evals_proofs[oracle_index].0;
-        &evals[..evals.len() - salt_size(salted)]
+        let row_len = evals.len() >> leaf_group_bits;
+        let row_start = (x_index & ((1 << leaf_group_bits) - 1)) * row_len;
+        &evals[row_start..row_start + row_len - salt_size(salted)]
     }
 }
 
@@ -58,18 +75,20 @@ pub struct FriInitialTreeProofTarget {
 }
 
 impl FriInitialTreeProofTarget {
-    pub(crate) fn unsalted_eval(
+    /// The values opened by an oracle at each of the `2^leaf_group_bits` points of its leaf
+    /// group, without the salt.
+    pub(crate) fn unsalted_evals_per_point(
         &self,
         oracle_index: usize,
-        poly_index: usize,
         salted: bool,
-    ) -> Target {
-        self.unsalted_evals(oracle_index, salted)[poly_index]
-    }
-
-    fn unsalted_evals(&self, oracle_index: usize, salted: bool) -> &[Target] {
+        leaf_group_bits: usize,
+    ) -> Vec<&[Target]> {
         let evals = &self.evals_proofs[oracle_index].0;
-        &evals[..evals.len() - salt_size(salted)]
+        let row_len = evals.len() >> leaf_group_bits;
+        evals
+            .chunks(row_len)
+            .map(|row| &row[..row_len - salt_size(salted)])
+            .collect()
     }
 }
 
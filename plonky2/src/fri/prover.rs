@@ -4,6 +4,7 @@ use plonky2_maybe_rayon::*;
 
 use crate::field::extension::{flatten, unflatten, Extendable};
 use crate::field::polynomial::{PolynomialCoeffs, PolynomialValues};
+use crate::fri::challenges::fri_query_indices;
 use crate::fri::proof::{FriInitialTreeProof, FriProof, FriQueryRound, FriQueryStep};
 use crate::fri::{FriConfig, FriParams};
 use crate::hash::hash_types::RichField;
@@ -13,12 +14,14 @@ use crate::iop::challenger::Challenger;
 use crate::plonk::config::GenericConfig;
 use crate::plonk::plonk_common::reduce_with_powers;
 use crate::timed;
-use crate::util::reverse_index_bits_in_place;
 use crate::util::timing::TimingTree;
+use crate::util::{log2_strict, reverse_index_bits_in_place};
 
 /// Builds a FRI proof.
 pub fn fri_proof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
     initial_merkle_trees: &[&MerkleTree<F, C::Hasher>],
+    // The `leaf_group_bits` of each initial tree, as in `FriOracleInfo`.
+    initial_leaf_group_bits: &[usize],
     // Coefficients of the polynomial on which the LDT is performed. Only the first `1/rate` coefficients are non-zero.
     lde_polynomial_coeffs: PolynomialCoeffs<F::Extension>,
     // Evaluation of the polynomial on the large domain.
@@ -50,8 +53,14 @@ pub fn fri_proof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const
     );
 
     // Query phase
-    let query_round_proofs =
-        fri_prover_query_rounds::<F, C, D>(initial_merkle_trees, &trees, challenger, n, fri_params);
+    let query_round_proofs = fri_prover_query_rounds::<F, C, D>(
+        initial_merkle_trees,
+        initial_leaf_group_bits,
+        &trees,
+        challenger,
+        n,
+        fri_params,
+    );
 
     FriProof {
         commit_phase_merkle_caps: trees.iter().map(|t| t.cap.clone()).collect(),
@@ -74,7 +83,7 @@ fn fri_committed_trees<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>,
 ) -> FriCommitedTrees<F, C, D> {
     let mut trees = Vec::with_capacity(fri_params.reduction_arity_bits.len());
 
-    let mut shift = F::MULTIPLICATIVE_GROUP_GENERATOR;
+    let mut shift = fri_params.config.coset_shift::<F>();
     for arity_bits in &fri_params.reduction_arity_bits {
         let arity = 1 << arity_bits;
 
@@ -165,19 +174,28 @@ fn fri_prover_query_rounds<
     const D: usize,
 >(
     initial_merkle_trees: &[&MerkleTree<F, C::Hasher>],
+    initial_leaf_group_bits: &[usize],
     trees: &[MerkleTree<F, C::Hasher>],
     challenger: &mut Challenger<F, C::Hasher>,
     n: usize,
     fri_params: &FriParams,
 ) -> Vec<FriQueryRound<F, C::Hasher, D>> {
-    challenger
-        .get_n_challenges(fri_params.config.num_query_rounds)
-        .into_par_iter()
-        .map(|rand| {
-            let x_index = rand.to_canonical_u64() as usize % n;
-            fri_prover_query_round::<F, C, D>(initial_merkle_trees, trees, x_index, fri_params)
-        })
-        .collect()
+    fri_query_indices(
+        challenger,
+        fri_params.config.num_query_rounds,
+        log2_strict(n),
+    )
+    .into_par_iter()
+    .map(|x_index| {
+        fri_prover_query_round::<F, C, D>(
+            initial_merkle_trees,
+            initial_leaf_group_bits,
+            trees,
+            x_index,
+            fri_params,
+        )
+    })
+    .collect()
 }
 
 fn fri_prover_query_round<
@@ -186,6 +204,7 @@ fn fri_prover_query_round<
     const D: usize,
 >(
     initial_merkle_trees: &[&MerkleTree<F, C::Hasher>],
+    initial_leaf_group_bits: &[usize],
     trees: &[MerkleTree<F, C::Hasher>],
     mut x_index: usize,
     fri_params: &FriParams,
@@ -193,7 +212,11 @@ fn fri_prover_query_round<
     let mut query_steps = Vec::new();
     let initial_proof = initial_merkle_trees
         .iter()
-        .map(|t| (t.get(x_index).to_vec(), t.prove(x_index)))
+        .zip(initial_leaf_group_bits)
+        .map(|(t, &leaf_group_bits)| {
+            let leaf_index = x_index >> leaf_group_bits;
+            (t.get(leaf_index).to_vec(), t.prove(leaf_index))
+        })
         .collect::<Vec<_>>();
     for (i, tree) in trees.iter().enumerate() {
         let arity_bits = fri_params.reduction_arity_bits[i];
@@ -1,14 +1,15 @@
-use alloc::format;
 use alloc::vec::Vec;
+use alloc::{format, vec};
 
 use itertools::Itertools;
 
 use crate::field::extension::Extendable;
+use crate::fri::challenges::fri_query_index_bits_circuit;
 use crate::fri::proof::{
     FriChallengesTarget, FriInitialTreeProofTarget, FriProofTarget, FriQueryRoundTarget,
     FriQueryStepTarget,
 };
-use crate::fri::structure::{FriBatchInfoTarget, FriInstanceInfoTarget, FriOpeningsTarget};
+use crate::fri::structure::{FriInstanceInfoTarget, FriOpeningsTarget, FriOracleInfo};
 use crate::fri::{FriConfig, FriParams};
 use crate::gates::coset_interpolation::CosetInterpolationGate;
 use crate::gates::gate::Gate;
@@ -177,23 +178,37 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
 
     fn fri_verify_initial_proof<H: AlgebraicHasher<F>>(
         &mut self,
+        instance: &FriInstanceInfoTarget<D>,
         x_index_bits: &[BoolTarget],
         proof: &FriInitialTreeProofTarget,
         initial_merkle_caps: &[MerkleCapTarget],
         cap_index: Target,
+        cap_height: usize,
     ) {
-        for (i, ((evals, merkle_proof), cap)) in proof
+        for (i, (((evals, merkle_proof), cap), oracle)) in proof
             .evals_proofs
             .iter()
             .zip(initial_merkle_caps)
+            .zip(&instance.oracles)
             .enumerate()
         {
+            // In a taller tree, the FRI domain is the first leaves, so the index's high bits are 0.
+            let (mut leaf_index_bits, cap_index) = if oracle.extra_rate_bits == 0 {
+                (x_index_bits.to_vec(), cap_index)
+            } else {
+                let mut bits = x_index_bits.to_vec();
+                bits.resize(x_index_bits.len() + oracle.extra_rate_bits, self._false());
+                let cap_index = self.le_sum(bits[bits.len() - cap_height..].iter());
+                (bits, cap_index)
+            };
+            // The low bits of the index select a point within the leaf's group.
+            leaf_index_bits.drain(..oracle.leaf_group_bits);
             with_context!(
                 self,
                 &format!("verify {i}'th initial Merkle proof"),
                 self.verify_merkle_proof_to_cap_with_cap_index::<H>(
                     evals.clone(),
-                    x_index_bits,
+                    &leaf_index_bits,
                     cap_index,
                     cap,
                     merkle_proof
@@ -206,6 +221,7 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         &mut self,
         instance: &FriInstanceInfoTarget<D>,
         proof: &FriInitialTreeProofTarget,
+        x_index_bits: &[BoolTarget],
         alpha: ExtensionTarget<D>,
         subgroup_x: Target,
         precomputed_reduced_evals: &PrecomputedReducedOpeningsTarget<D>,
@@ -215,32 +231,59 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         let degree_log = params.degree_bits;
         debug_assert_eq!(
             degree_log,
-            params.config.cap_height + proof.evals_proofs[0].1.siblings.len()
+            params.config.cap_height
+                + proof.evals_proofs[0].1.siblings.len()
+                + instance.oracles[0].leaf_group_bits
                 - params.config.rate_bits
+                - instance.oracles[0].extra_rate_bits
         );
+        // The opened values of each oracle at `x`, picked out of its leaf group if it has one.
+        let unsalted_evals = instance
+            .oracles
+            .iter()
+            .enumerate()
+            .map(|(i, oracle)| {
+                let salted = params.hiding && oracle.blinding;
+                let evals_per_point =
+                    proof.unsalted_evals_per_point(i, salted, oracle.leaf_group_bits);
+                if oracle.leaf_group_bits == 0 {
+                    return evals_per_point[0].to_vec();
+                }
+                let index_in_group = self.le_sum(x_index_bits[..oracle.leaf_group_bits].iter());
+                (0..evals_per_point[0].len())
+                    .map(|j| {
+                        let column = evals_per_point.iter().map(|evals| evals[j]).collect();
+                        self.random_access(index_in_group, column)
+                    })
+                    .collect()
+            })
+            .collect_vec();
         let subgroup_x = self.convert_to_ext(subgroup_x);
         let mut alpha = ReducingFactorTarget::new(alpha);
         let mut sum = self.zero_extension();
 
-        for (batch, reduced_openings) in instance
+        let denominators = instance
+            .batches
+            .iter()
+            .map(|batch| self.sub_extension(subgroup_x, batch.point))
+            .collect_vec();
+        let denominator_invs = self.inverse_many_extension(&denominators);
+
+        for ((batch, reduced_openings), denominator_inv) in instance
             .batches
             .iter()
             .zip(&precomputed_reduced_evals.reduced_openings_at_point)
+            .zip(denominator_invs)
         {
-            let FriBatchInfoTarget { point, polynomials } = batch;
-            let evals = polynomials
+            let evals = batch
+                .polynomials
                 .iter()
-                .map(|p| {
-                    let poly_blinding = instance.oracles[p.oracle_index].blinding;
-                    let salted = params.hiding && poly_blinding;
-                    proof.unsalted_eval(p.oracle_index, p.polynomial_index, salted)
-                })
+                .map(|p| unsalted_evals[p.oracle_index][p.polynomial_index])
                 .collect_vec();
             let reduced_evals = alpha.reduce_base(&evals, self);
             let numerator = self.sub_extension(reduced_evals, *reduced_openings);
-            let denominator = self.sub_extension(subgroup_x, *point);
             sum = alpha.shift(sum, self);
-            sum = self.div_add_extension(numerator, denominator, sum);
+            sum = self.mul_add_extension(numerator, denominator_inv, sum);
         }
 
         sum
@@ -265,7 +308,7 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         // Note that this `low_bits` decomposition permits non-canonical binary encodings. Here we
         // verify that this has a negligible impact on soundness error.
         Self::assert_noncanonical_indices_ok(&params.config);
-        let mut x_index_bits = self.low_bits(x_index, n_log, F::BITS);
+        let mut x_index_bits = fri_query_index_bits_circuit(self, x_index, n_log);
 
         let cap_index =
             self.le_sum(x_index_bits[x_index_bits.len() - params.config.cap_height..].iter());
@@ -273,16 +316,18 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             self,
             "check FRI initial proof",
             self.fri_verify_initial_proof::<C::Hasher>(
+                instance,
                 &x_index_bits,
                 &round_proof.initial_trees_proof,
                 initial_merkle_caps,
-                cap_index
+                cap_index,
+                params.config.cap_height
             )
         );
 
         // `subgroup_x` is `subgroup[x_index]`, i.e., the actual field element in the domain.
         let mut subgroup_x = with_context!(self, "compute x from its index", {
-            let g = self.constant(F::coset_shift());
+            let g = self.constant(params.config.coset_shift());
             let phi = F::primitive_root_of_unity(n_log);
             let phi = self.exp_from_bits_const_base(phi, x_index_bits.iter().rev());
             // subgroup_x = g * phi
@@ -297,6 +342,7 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             self.fri_combine_initial(
                 instance,
                 &round_proof.initial_trees_proof,
+                &x_index_bits,
                 challenges.fri_alpha,
                 subgroup_x,
                 precomputed_reduced_evals,
@@ -384,6 +430,48 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         &mut self,
         num_leaves_per_oracle: &[usize],
         params: &FriParams,
+    ) -> FriProofTarget<D> {
+        assert!(params.lde_bits() >= params.config.cap_height);
+        let initial_merkle_proof_lens =
+            vec![params.lde_bits() - params.config.cap_height; num_leaves_per_oracle.len()];
+        self.add_virtual_fri_proof_with_merkle_proof_lens(
+            num_leaves_per_oracle,
+            &initial_merkle_proof_lens,
+            params,
+        )
+    }
+
+    /// Like `add_virtual_fri_proof`, but with each oracle's leaf and Merkle proof lengths given by
+    /// its `FriOracleInfo`, so oracles may have extra rate bits or leaf groups.
+    pub fn add_virtual_fri_proof_for_oracle_infos(
+        &mut self,
+        oracles: &[FriOracleInfo],
+        params: &FriParams,
+    ) -> FriProofTarget<D> {
+        let num_leaves_per_oracle = oracles
+            .iter()
+            .map(|oracle| oracle.leaf_len(params.hiding))
+            .collect_vec();
+        let initial_merkle_proof_lens = oracles
+            .iter()
+            .map(|oracle| {
+                oracle
+                    .merkle_proof_len(params)
+                    .expect("Merkle cap taller than the oracle's tree")
+            })
+            .collect_vec();
+        self.add_virtual_fri_proof_with_merkle_proof_lens(
+            &num_leaves_per_oracle,
+            &initial_merkle_proof_lens,
+            params,
+        )
+    }
+
+    fn add_virtual_fri_proof_with_merkle_proof_lens(
+        &mut self,
+        num_leaves_per_oracle: &[usize],
+        initial_merkle_proof_lens: &[usize],
+        params: &FriParams,
     ) -> FriProofTarget<D> {
         let cap_height = params.config.cap_height;
         let num_queries = params.config.num_query_rounds;
@@ -391,7 +479,9 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             .map(|_| self.add_virtual_cap(cap_height))
             .collect();
         let query_round_proofs = (0..num_queries)
-            .map(|_| self.add_virtual_fri_query(num_leaves_per_oracle, params))
+            .map(|_| {
+                self.add_virtual_fri_query(num_leaves_per_oracle, initial_merkle_proof_lens, params)
+            })
             .collect();
         let final_poly = self.add_virtual_poly_coeff_ext(params.final_poly_len());
         let pow_witness = self.add_virtual_target();
@@ -406,14 +496,15 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
     fn add_virtual_fri_query(
         &mut self,
         num_leaves_per_oracle: &[usize],
+        initial_merkle_proof_lens: &[usize],
         params: &FriParams,
     ) -> FriQueryRoundTarget<D> {
         let cap_height = params.config.cap_height;
         assert!(params.lde_bits() >= cap_height);
         let mut merkle_proof_len = params.lde_bits() - cap_height;
 
-        let initial_trees_proof =
-            self.add_virtual_fri_initial_trees_proof(num_leaves_per_oracle, merkle_proof_len);
+        let initial_trees_proof = self
+            .add_virtual_fri_initial_trees_proof(num_leaves_per_oracle, initial_merkle_proof_lens);
 
         let mut steps = Vec::with_capacity(params.reduction_arity_bits.len());
         for &arity_bits in &params.reduction_arity_bits {
@@ -431,13 +522,14 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
     fn add_virtual_fri_initial_trees_proof(
         &mut self,
         num_leaves_per_oracle: &[usize],
-        initial_merkle_proof_len: usize,
+        initial_merkle_proof_lens: &[usize],
     ) -> FriInitialTreeProofTarget {
         let evals_proofs = num_leaves_per_oracle
             .iter()
-            .map(|&num_oracle_leaves| {
+            .zip_eq(initial_merkle_proof_lens)
+            .map(|(&num_oracle_leaves, &merkle_proof_len)| {
                 let leaves = self.add_virtual_targets(num_oracle_leaves);
-                let merkle_proof = self.add_virtual_merkle_proof(initial_merkle_proof_len);
+                let merkle_proof = self.add_virtual_merkle_proof(merkle_proof_len);
                 (leaves, merkle_proof)
             })
             .collect();
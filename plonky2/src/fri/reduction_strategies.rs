@@ -4,10 +4,10 @@ use alloc::vec::Vec;
 use std::time::Instant;
 
 use log::debug;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 /// A method for deciding what arity to use at each reduction layer.
-#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum FriReductionStrategy {
     /// Specifies the exact sequence of arities (expressed in bits) to use.
     Fixed(Vec<usize>),
@@ -17,6 +17,10 @@ pub enum FriReductionStrategy {
     /// `arity_bits`-reduction makes the last FRI tree have height less than `cap_height`.
     /// This tends to work well in the recursive setting, as it avoids needing multiple configurations
     /// of gates used in FRI verification, such as `InterpolationGate`.
+    ///
+    /// A larger `final_poly_bits` means fewer reductions, and so fewer Merkle proofs to verify per
+    /// query, at the cost of a longer final polynomial. The recursive verifier evaluates it with
+    /// `ReducingExtensionGate`s, which handle dozens of coefficients per row.
     ConstantArityBits(usize, usize),
 
     /// `MinSize(opt_max_arity_bits)` searches for an optimal sequence of reduction arities, with an
@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+
+/// `log2(1 + 1/(2m))`, the slack in each query's soundness under the Johnson bound, where we take
+/// `m = 16` to trade off the query error against the commit phase error.
+const LOG2_JOHNSON_QUERY_SLACK: f64 = 0.0444;
+
+/// An upper bound on `7 * log2(m + 1/2) - 1`, the bits lost in the commit phase error under the
+/// Johnson bound.
+const JOHNSON_COMMIT_OVERHEAD_BITS: usize = 28;
+
+/// The proof-of-work bits used when deriving a config from a target security level, as in the
+/// standard configs.
+pub const DEFAULT_PROOF_OF_WORK_BITS: u32 = 16;
+
+/// The soundness model used to account for FRI's security.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum SecurityAssumption {
+    /// The ethSTARK conjecture: each query contributes `rate_bits` bits of security, and the commit
+    /// phase is as sound as the extension field is large.
+    #[default]
+    Conjectured,
+    /// Provable soundness up to the Johnson bound, following "Proximity Gaps for Reed-Solomon
+    /// Codes" (BCIKS20): each query contributes about `rate_bits / 2` bits, and the commit phase
+    /// loses about twice the log of the evaluation domain's size to the field.
+    Proven,
+}
+
+impl SecurityAssumption {
+    /// The bits of security given by `num_query_rounds` queries, at rate `2^-rate_bits`.
+    pub fn query_security_bits(self, num_query_rounds: usize, rate_bits: usize) -> usize {
+        match self {
+            SecurityAssumption::Conjectured => num_query_rounds * rate_bits,
+            SecurityAssumption::Proven => {
+                let bits_per_query = rate_bits as f64 / 2.0 - LOG2_JOHNSON_QUERY_SLACK;
+                (num_query_rounds as f64 * bits_per_query.max(0.0)) as usize
+            }
+        }
+    }
+
+    /// The bits of security of the commit phase, over an extension field of `field_bits` bits and
+    /// an evaluation domain of `2^lde_bits` points.
+    pub fn commit_phase_security_bits(
+        self,
+        field_bits: usize,
+        lde_bits: usize,
+        rate_bits: usize,
+    ) -> usize {
+        match self {
+            SecurityAssumption::Conjectured => field_bits,
+            SecurityAssumption::Proven => {
+                // The error is at most `(m + 1/2)^7 |D|^2 / (2 rate^(3/2) |F|)`.
+                let loss = 2 * lde_bits + JOHNSON_COMMIT_OVERHEAD_BITS + (3 * rate_bits + 1) / 2;
+                field_bits.saturating_sub(loss)
+            }
+        }
+    }
+
+    /// The fewest query rounds which, with `proof_of_work_bits` of grinding, reach `security_bits`
+    /// bits of query security.
+    pub fn num_query_rounds(
+        self,
+        security_bits: usize,
+        rate_bits: usize,
+        proof_of_work_bits: u32,
+    ) -> usize {
+        assert!(rate_bits > 0, "Queries give no security at rate 1");
+        let target = security_bits.saturating_sub(proof_of_work_bits as usize);
+        let mut num_query_rounds = 0;
+        while self.query_security_bits(num_query_rounds, rate_bits) < target {
+            num_query_rounds += 1;
+        }
+        num_query_rounds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::extension::Extendable;
+    use crate::field::goldilocks_field::GoldilocksField;
+    use crate::field::types::Field;
+    use crate::fri::reduction_strategies::FriReductionStrategy;
+    use crate::fri::FriConfig;
+    use crate::plonk::circuit_data::CircuitConfig;
+
+    #[test]
+    fn test_conjectured_matches_standard_config() {
+        let standard = CircuitConfig::standard_recursion_config().fri_config;
+        let derived = FriConfig::for_security(
+            100,
+            SecurityAssumption::Conjectured,
+            standard.rate_bits,
+            standard.cap_height,
+            standard.reduction_strategy.clone(),
+        );
+        assert_eq!(derived, standard);
+        assert_eq!(derived.security_bits(128, 20), 100);
+    }
+
+    #[test]
+    fn test_proven_security() {
+        let config = FriConfig::for_security(
+            100,
+            SecurityAssumption::Proven,
+            3,
+            4,
+            FriReductionStrategy::ConstantArityBits(4, 5),
+        );
+        assert_eq!(config.security_assumption, SecurityAssumption::Proven);
+        // Each query gives fewer bits than under the conjecture.
+        assert!(config.num_query_rounds > 28);
+        assert!(config.num_query_rounds <= 60);
+
+        // The quadratic extension of Goldilocks is too small for proven security at this level...
+        let quadratic_bits = <GoldilocksField as Extendable<2>>::Extension::order().bits() as usize;
+        assert!(config.security_bits(quadratic_bits, 20) < 100);
+        // ...but the quintic extension isn't.
+        let quintic_bits = <GoldilocksField as Extendable<5>>::Extension::order().bits() as usize;
+        assert!(config.security_bits(quintic_bits, 20) >= 100);
+    }
+
+    #[test]
+    fn test_num_query_rounds() {
+        for assumption in [SecurityAssumption::Conjectured, SecurityAssumption::Proven] {
+            for rate_bits in 1..=4 {
+                let n = assumption.num_query_rounds(128, rate_bits, 16);
+                assert!(assumption.query_security_bits(n, rate_bits) + 16 >= 128);
+                assert!(assumption.query_security_bits(n - 1, rate_bits) + 16 < 128);
+            }
+        }
+    }
+}
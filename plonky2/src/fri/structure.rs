@@ -5,8 +5,10 @@ use alloc::vec::Vec;
 use core::ops::Range;
 
 use crate::field::extension::Extendable;
+use crate::fri::FriParams;
 use crate::hash::hash_types::RichField;
 use crate::iop::ext_target::ExtensionTarget;
+use crate::plonk::plonk_common::salt_size;
 
 /// Describes an instance of a FRI-based batch opening.
 pub struct FriInstanceInfo<F: RichField + Extendable<D>, const D: usize> {
@@ -28,6 +30,28 @@ pub struct FriInstanceInfoTarget<const D: usize> {
 pub struct FriOracleInfo {
     pub num_polys: usize,
     pub blinding: bool,
+    /// How many more rate bits the oracle was committed with than FRI's `rate_bits`. Its LDE
+    /// domain then contains the FRI domain as the first leaves in bit-reversed order, so it's
+    /// opened at the same indices, with Merkle paths `extra_rate_bits` longer.
+    pub extra_rate_bits: usize,
+    /// Each Merkle leaf of the oracle holds the values at `2^leaf_group_bits` consecutive points
+    /// of its LDE, in bit-reversed order. This divides the number of leaves by `2^leaf_group_bits`
+    /// and shortens Merkle paths accordingly, but each query opens the whole group.
+    pub leaf_group_bits: usize,
+}
+
+impl FriOracleInfo {
+    /// The number of values in each of the oracle's Merkle leaves.
+    pub fn leaf_len(&self, hiding: bool) -> usize {
+        (self.num_polys + salt_size(self.blinding && hiding)) << self.leaf_group_bits
+    }
+
+    /// The number of siblings in each of the oracle's Merkle proofs, or `None` if its tree is
+    /// shorter than the Merkle cap.
+    pub fn merkle_proof_len(&self, params: &FriParams) -> Option<usize> {
+        (params.lde_bits() + self.extra_rate_bits)
+            .checked_sub(self.leaf_group_bits + params.config.cap_height)
+    }
 }
 
 /// A batch of openings at a particular point.
@@ -1,6 +1,7 @@
 use alloc::vec::Vec;
 
 use anyhow::{ensure, Result};
+use plonky2_maybe_rayon::*;
 
 use crate::field::extension::{flatten, Extendable, FieldExtension};
 use crate::field::interpolation::{barycentric_weights, interpolate};
@@ -86,34 +87,40 @@ pub fn verify_fri_proof<
 
     let precomputed_reduced_evals =
         PrecomputedReducedOpenings::from_os_and_alpha(openings, challenges.fri_alpha);
-    for (&x_index, round_proof) in challenges
+    // The query rounds are independent, so they are checked in parallel.
+    challenges
         .fri_query_indices
-        .iter()
+        .par_iter()
         .zip(&proof.query_round_proofs)
-    {
-        fri_verifier_query_round::<F, C, D>(
-            instance,
-            challenges,
-            &precomputed_reduced_evals,
-            initial_merkle_caps,
-            proof,
-            x_index,
-            n,
-            round_proof,
-            params,
-        )?;
-    }
-
-    Ok(())
+        .try_for_each(|(&x_index, round_proof)| {
+            fri_verifier_query_round::<F, C, D>(
+                instance,
+                challenges,
+                &precomputed_reduced_evals,
+                initial_merkle_caps,
+                proof,
+                x_index,
+                n,
+                round_proof,
+                params,
+            )
+        })
 }
 
-fn fri_verify_initial_proof<F: RichField, H: Hasher<F>>(
+fn fri_verify_initial_proof<F: RichField + Extendable<D>, H: Hasher<F>, const D: usize>(
+    instance: &FriInstanceInfo<F, D>,
     x_index: usize,
     proof: &FriInitialTreeProof<F, H>,
     initial_merkle_caps: &[MerkleCap<F, H>],
 ) -> Result<()> {
-    for ((evals, merkle_proof), cap) in proof.evals_proofs.iter().zip(initial_merkle_caps) {
-        verify_merkle_proof_to_cap::<F, H>(evals.clone(), x_index, cap, merkle_proof)?;
+    for (((evals, merkle_proof), cap), oracle) in proof
+        .evals_proofs
+        .iter()
+        .zip(initial_merkle_caps)
+        .zip(&instance.oracles)
+    {
+        let leaf_index = x_index >> oracle.leaf_group_bits;
+        verify_merkle_proof_to_cap::<F, H>(evals.clone(), leaf_index, cap, merkle_proof)?;
     }
 
     Ok(())
@@ -126,6 +133,7 @@ pub(crate) fn fri_combine_initial<
 >(
     instance: &FriInstanceInfo<F, D>,
     proof: &FriInitialTreeProof<F, C::Hasher>,
+    x_index: usize,
     alpha: F::Extension,
     subgroup_x: F,
     precomputed_reduced_evals: &PrecomputedReducedOpenings<F, D>,
@@ -145,9 +153,15 @@ pub(crate) fn fri_combine_initial<
         let evals = polynomials
             .iter()
             .map(|p| {
-                let poly_blinding = instance.oracles[p.oracle_index].blinding;
-                let salted = params.hiding && poly_blinding;
-                proof.unsalted_eval(p.oracle_index, p.polynomial_index, salted)
+                let oracle = &instance.oracles[p.oracle_index];
+                let salted = params.hiding && oracle.blinding;
+                proof.unsalted_eval(
+                    p.oracle_index,
+                    p.polynomial_index,
+                    salted,
+                    oracle.leaf_group_bits,
+                    x_index,
+                )
             })
             .map(F::Extension::from_basefield);
         let reduced_evals = alpha.reduce(evals);
@@ -175,14 +189,15 @@ fn fri_verifier_query_round<
     round_proof: &FriQueryRound<F, C::Hasher, D>,
     params: &FriParams,
 ) -> Result<()> {
-    fri_verify_initial_proof::<F, C::Hasher>(
+    fri_verify_initial_proof::<F, C::Hasher, D>(
+        instance,
         x_index,
         &round_proof.initial_trees_proof,
         initial_merkle_caps,
     )?;
     // `subgroup_x` is `subgroup[x_index]`, i.e., the actual field element in the domain.
     let log_n = log2_strict(n);
-    let mut subgroup_x = F::MULTIPLICATIVE_GROUP_GENERATOR
+    let mut subgroup_x = params.config.coset_shift::<F>()
         * F::primitive_root_of_unity(log_n).exp_u64(reverse_bits(x_index, log_n) as u64);
 
     // old_eval is the last derived evaluation; it will be checked for consistency with its
@@ -190,6 +205,7 @@ fn fri_verifier_query_round<
     let mut old_eval = fri_combine_initial::<F, C, D>(
         instance,
         &round_proof.initial_trees_proof,
+        x_index,
         challenges.fri_alpha,
         subgroup_x,
         precomputed_reduced_evals,
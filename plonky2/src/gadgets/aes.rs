@@ -0,0 +1,241 @@
+//! AES-128 encryption within circuits, built on byte lookup tables: the S-box, multiplication by
+//! `x` in `GF(2^8)` ("xtime"), and the XOR of two bytes, the latter looking up `256 a + b`. Each
+//! table only needs adding once per circuit, however many blocks are encrypted, and the XOR table,
+//! with its `2^16` entries, dominates the cost of circuits encrypting few blocks.
+//!
+//! Decrypting data sealed with AES in counter mode only needs encryption, of the counter blocks.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::field::extension::Extendable;
+use crate::hash::hash_types::RichField;
+use crate::iop::target::Target;
+use crate::plonk::circuit_builder::CircuitBuilder;
+
+const SBOX: [u8; 256] = [
+    0x63, 0x7c, 0x77, 0x7b, 0xf2, 0x6b, 0x6f, 0xc5, 0x30, 0x01, 0x67, 0x2b, 0xfe, 0xd7, 0xab, 0x76,
+    0xca, 0x82, 0xc9, 0x7d, 0xfa, 0x59, 0x47, 0xf0, 0xad, 0xd4, 0xa2, 0xaf, 0x9c, 0xa4, 0x72, 0xc0,
+    0xb7, 0xfd, 0x93, 0x26, 0x36, 0x3f, 0xf7, 0xcc, 0x34, 0xa5, 0xe5, 0xf1, 0x71, 0xd8, 0x31, 0x15,
+    0x04, 0xc7, 0x23, 0xc3, 0x18, 0x96, 0x05, 0x9a, 0x07, 0x12, 0x80, 0xe2, 0xeb, 0x27, 0xb2, 0x75,
+    0x09, 0x83, 0x2c, 0x1a, 0x1b, 0x6e, 0x5a, 0xa0, 0x52, 0x3b, 0xd6, 0xb3, 0x29, 0xe3, 0x2f, 0x84,
+    0x53, 0xd1, 0x00, 0xed, 0x20, 0xfc, 0xb1, 0x5b, 0x6a, 0xcb, 0xbe, 0x39, 0x4a, 0x4c, 0x58, 0xcf,
+    0xd0, 0xef, 0xaa, 0xfb, 0x43, 0x4d, 0x33, 0x85, 0x45, 0xf9, 0x02, 0x7f, 0x50, 0x3c, 0x9f, 0xa8,
+    0x51, 0xa3, 0x40, 0x8f, 0x92, 0x9d, 0x38, 0xf5, 0xbc, 0xb6, 0xda, 0x21, 0x10, 0xff, 0xf3, 0xd2,
+    0xcd, 0x0c, 0x13, 0xec, 0x5f, 0x97, 0x44, 0x17, 0xc4, 0xa7, 0x7e, 0x3d, 0x64, 0x5d, 0x19, 0x73,
+    0x60, 0x81, 0x4f, 0xdc, 0x22, 0x2a, 0x90, 0x88, 0x46, 0xee, 0xb8, 0x14, 0xde, 0x5e, 0x0b, 0xdb,
+    0xe0, 0x32, 0x3a, 0x0a, 0x49, 0x06, 0x24, 0x5c, 0xc2, 0xd3, 0xac, 0x62, 0x91, 0x95, 0xe4, 0x79,
+    0xe7, 0xc8, 0x37, 0x6d, 0x8d, 0xd5, 0x4e, 0xa9, 0x6c, 0x56, 0xf4, 0xea, 0x65, 0x7a, 0xae, 0x08,
+    0xba, 0x78, 0x25, 0x2e, 0x1c, 0xa6, 0xb4, 0xc6, 0xe8, 0xdd, 0x74, 0x1f, 0x4b, 0xbd, 0x8b, 0x8a,
+    0x70, 0x3e, 0xb5, 0x66, 0x48, 0x03, 0xf6, 0x0e, 0x61, 0x35, 0x57, 0xb9, 0x86, 0xc1, 0x1d, 0x9e,
+    0xe1, 0xf8, 0x98, 0x11, 0x69, 0xd9, 0x8e, 0x94, 0x9b, 0x1e, 0x87, 0xe9, 0xce, 0x55, 0x28, 0xdf,
+    0x8c, 0xa1, 0x89, 0x0d, 0xbf, 0xe6, 0x42, 0x68, 0x41, 0x99, 0x2d, 0x0f, 0xb0, 0x54, 0xbb, 0x16,
+];
+
+/// The first byte of the round constants of the key schedule.
+const RCON: [u8; 10] = [0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, 0x1b, 0x36];
+
+const NUM_ROUNDS: usize = 10;
+
+/// Multiplies by `x` in `GF(2^8) = GF(2)[x] / (x^8 + x^4 + x^3 + x + 1)`.
+fn xtime(a: u8) -> u8 {
+    (a << 1) ^ if a & 0x80 != 0 { 0x1b } else { 0 }
+}
+
+fn sbox_lookup(x: u16) -> u16 {
+    SBOX[x as usize] as u16
+}
+
+fn xtime_lookup(x: u16) -> u16 {
+    xtime(x as u8) as u16
+}
+
+fn xor_lookup(x: u16) -> u16 {
+    (x >> 8) ^ (x & 0xff)
+}
+
+/// The index of the byte of the state at `row` and `col`, as the state is filled column by
+/// column.
+fn state_index(row: usize, col: usize) -> usize {
+    row + 4 * col
+}
+
+/// The round keys of AES-128, derived from `key`.
+fn expand_key(key: &[u8; 16]) -> [[u8; 16]; NUM_ROUNDS + 1] {
+    let mut round_keys = [[0; 16]; NUM_ROUNDS + 1];
+    round_keys[0] = *key;
+    for round in 1..=NUM_ROUNDS {
+        let prev = round_keys[round - 1];
+        let mut word: [u8; 4] = core::array::from_fn(|i| SBOX[prev[12 + (i + 1) % 4] as usize]);
+        word[0] ^= RCON[round - 1];
+        for i in 0..16 {
+            word[i % 4] ^= prev[i];
+            round_keys[round][i] = word[i % 4];
+        }
+    }
+    round_keys
+}
+
+/// Encrypts a 16-byte block with AES-128.
+pub fn aes128_encrypt(key: &[u8; 16], block: &[u8; 16]) -> [u8; 16] {
+    let round_keys = expand_key(key);
+    let mut state: [u8; 16] = core::array::from_fn(|i| block[i] ^ round_keys[0][i]);
+    for (round, round_key) in round_keys.iter().enumerate().skip(1) {
+        let shifted: [u8; 16] = core::array::from_fn(|i| {
+            let (row, col) = (i % 4, i / 4);
+            SBOX[state[state_index(row, (col + row) % 4)] as usize]
+        });
+        state = if round == NUM_ROUNDS {
+            shifted
+        } else {
+            core::array::from_fn(|i| {
+                let (row, col) = (i % 4, i / 4);
+                let a = |r: usize| shifted[state_index((row + r) % 4, col)];
+                xtime(a(0)) ^ xtime(a(1)) ^ a(1) ^ a(2) ^ a(3)
+            })
+        };
+        for i in 0..16 {
+            state[i] ^= round_key[i];
+        }
+    }
+    state
+}
+
+/// The indices of the lookup tables used by the AES gadget.
+struct AesTables {
+    sbox: usize,
+    xtime: usize,
+    xor: usize,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
+    fn add_aes_tables(&mut self) -> AesTables {
+        let bytes = (0..256).collect::<Vec<u16>>();
+        let byte_pairs = (0..=u16::MAX).collect::<Vec<u16>>();
+        AesTables {
+            sbox: self.add_lookup_table_from_fn(sbox_lookup, &bytes),
+            xtime: self.add_lookup_table_from_fn(xtime_lookup, &bytes),
+            xor: self.add_lookup_table_from_fn(xor_lookup, &byte_pairs),
+        }
+    }
+
+    /// Computes `a ^ b` for bytes `a` and `b`.
+    fn aes_xor(&mut self, tables: &AesTables, a: Target, b: Target) -> Target {
+        let index = self.mul_const_add(F::from_canonical_u16(256), a, b);
+        self.add_lookup_from_index(index, tables.xor)
+    }
+
+    fn aes_xor_const(&mut self, tables: &AesTables, a: Target, b: u8) -> Target {
+        let b = self.constant(F::from_canonical_u8(b));
+        self.aes_xor(tables, a, b)
+    }
+
+    fn aes_expand_key(&mut self, tables: &AesTables, key: &[Target; 16]) -> Vec<[Target; 16]> {
+        let mut round_keys = vec![*key];
+        for round in 1..=NUM_ROUNDS {
+            let prev = round_keys[round - 1];
+            let mut word: [Target; 4] = core::array::from_fn(|i| {
+                self.add_lookup_from_index(prev[12 + (i + 1) % 4], tables.sbox)
+            });
+            word[0] = self.aes_xor_const(tables, word[0], RCON[round - 1]);
+            let mut round_key = [prev[0]; 16];
+            for i in 0..16 {
+                word[i % 4] = self.aes_xor(tables, word[i % 4], prev[i]);
+                round_key[i] = word[i % 4];
+            }
+            round_keys.push(round_key);
+        }
+        round_keys
+    }
+
+    /// Encrypts `block` under `key` with AES-128, returning the ciphertext. The key and block are
+    /// given as 16 targets each, which are range checked to be bytes.
+    pub fn aes128_encrypt(&mut self, key: &[Target; 16], block: &[Target; 16]) -> [Target; 16] {
+        for &byte in key.iter().chain(block) {
+            self.range_check(byte, 8);
+        }
+        let tables = self.add_aes_tables();
+        let round_keys = self.aes_expand_key(&tables, key);
+
+        let mut state: [Target; 16] =
+            core::array::from_fn(|i| self.aes_xor(&tables, block[i], round_keys[0][i]));
+        for (round, round_key) in round_keys.iter().enumerate().skip(1) {
+            let shifted: [Target; 16] = core::array::from_fn(|i| {
+                let (row, col) = (i % 4, i / 4);
+                self.add_lookup_from_index(state[state_index(row, (col + row) % 4)], tables.sbox)
+            });
+            if round == NUM_ROUNDS {
+                state = shifted;
+            } else {
+                let doubled = shifted.map(|byte| self.add_lookup_from_index(byte, tables.xtime));
+                for i in 0..16 {
+                    let (row, col) = (i % 4, i / 4);
+                    let at = |r: usize| state_index((row + r) % 4, col);
+                    // `2 a0 + 3 a1 + a2 + a3`.
+                    let mut acc = self.aes_xor(&tables, doubled[at(0)], doubled[at(1)]);
+                    for r in 1..4 {
+                        acc = self.aes_xor(&tables, acc, shifted[at(r)]);
+                    }
+                    state[i] = acc;
+                }
+            }
+            for i in 0..16 {
+                state[i] = self.aes_xor(&tables, state[i], round_key[i]);
+            }
+        }
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::field::types::Field;
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    /// The example of appendix C.1 of FIPS 197.
+    const KEY: [u8; 16] = [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+        0x0f,
+    ];
+    const PLAINTEXT: [u8; 16] = [
+        0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee,
+        0xff,
+    ];
+    const CIPHERTEXT: [u8; 16] = [
+        0x69, 0xc4, 0xe0, 0xd8, 0x6a, 0x7b, 0x04, 0x30, 0xd8, 0xcd, 0xb7, 0x80, 0x70, 0xb4, 0xc5,
+        0x5a,
+    ];
+
+    #[test]
+    fn test_aes128_native() {
+        assert_eq!(aes128_encrypt(&KEY, &PLAINTEXT), CIPHERTEXT);
+    }
+
+    #[test]
+    fn test_aes128_circuit() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let key = builder.add_virtual_target_arr();
+        let block = builder.add_virtual_target_arr();
+        let ciphertext = builder.aes128_encrypt(&key, &block);
+        builder.register_public_inputs(&ciphertext);
+        let data = builder.build::<C>();
+
+        let to_field = |bytes: [u8; 16]| bytes.map(F::from_canonical_u8);
+        let mut pw = PartialWitness::new();
+        pw.set_target_arr(&key, &to_field(KEY));
+        pw.set_target_arr(&block, &to_field(PLAINTEXT));
+        let proof = data.prove(pw)?;
+        assert_eq!(proof.public_inputs, to_field(CIPHERTEXT));
+        data.verify(proof)
+    }
+}
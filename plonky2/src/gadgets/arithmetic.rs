@@ -7,12 +7,10 @@ use crate::field::types::Field64;
 use crate::gates::arithmetic_base::ArithmeticGate;
 use crate::gates::exponentiation::ExponentiationGate;
 use crate::hash::hash_types::RichField;
-use crate::iop::generator::{GeneratedValues, SimpleGenerator};
 use crate::iop::target::{BoolTarget, Target};
-use crate::iop::witness::{PartitionWitness, Witness, WitnessWrite};
+use crate::iop::witness::{Witness, WitnessWrite};
 use crate::plonk::circuit_builder::CircuitBuilder;
-use crate::plonk::circuit_data::CommonCircuitData;
-use crate::util::serialization::{Buffer, IoResult, Read, Write};
+use crate::simple_generator;
 
 impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
     /// Computes `-x`.
@@ -329,6 +327,26 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         self.inverse_extension(x_ext).0[0]
     }
 
+    /// Computes `1 / x` for each `x` in `xs`. Results in an unsatisfiable instance if any `x = 0`.
+    /// All inverses are generated at once with Montgomery's trick, and each is checked with one
+    /// multiplication.
+    pub fn inverse_many(&mut self, xs: &[Target]) -> Vec<Target> {
+        if xs.is_empty() {
+            return Vec::new();
+        }
+        let inverses = self.add_virtual_targets(xs.len());
+        self.add_simple_generator(BatchInverseGenerator {
+            xs: xs.to_vec(),
+            inverses: inverses.clone(),
+        });
+
+        for (&x, &inv) in xs.iter().zip(&inverses) {
+            let x_inv = self.mul(x, inv);
+            self.assert_one(x_inv);
+        }
+        inverses
+    }
+
     pub fn not(&mut self, b: BoolTarget) -> BoolTarget {
         let one = self.one();
         let res = self.sub(one, b.target);
@@ -372,46 +390,32 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
     }
 }
 
-#[derive(Debug, Default)]
-pub struct EqualityGenerator {
-    x: Target,
-    y: Target,
-    equal: BoolTarget,
-    inv: Target,
-}
-
-impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F, D> for EqualityGenerator {
-    fn id(&self) -> String {
-        "EqualityGenerator".to_string()
-    }
-
-    fn dependencies(&self) -> Vec<Target> {
-        vec![self.x, self.y]
+simple_generator! {
+    #[derive(Debug, Default)]
+    pub struct EqualityGenerator {
+        inputs { x: Target, y: Target }
+        outputs { equal: BoolTarget, inv: Target }
     }
+    run_once |witness, out_buffer| {
+        let x = witness.get_target(*x);
+        let y = witness.get_target(*y);
 
-    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
-        let x = witness.get_target(self.x);
-        let y = witness.get_target(self.y);
+        let inv_value = if x != y { (x - y).inverse() } else { F::ZERO };
 
-        let inv = if x != y { (x - y).inverse() } else { F::ZERO };
-
-        out_buffer.set_bool_target(self.equal, x == y);
-        out_buffer.set_target(self.inv, inv);
+        out_buffer.set_bool_target(*equal, x == y);
+        out_buffer.set_target(*inv, inv_value);
     }
+}
 
-    fn serialize(&self, dst: &mut Vec<u8>, _common_data: &CommonCircuitData<F, D>) -> IoResult<()> {
-        dst.write_target(self.x)?;
-        dst.write_target(self.y)?;
-        dst.write_target_bool(self.equal)?;
-        dst.write_target(self.inv)
+simple_generator! {
+    #[derive(Debug, Default)]
+    pub struct BatchInverseGenerator {
+        inputs { xs: Vec<Target> }
+        outputs { inverses: Vec<Target> }
     }
-
-    fn deserialize(src: &mut Buffer, _common_data: &CommonCircuitData<F, D>) -> IoResult<Self> {
-        let x = src.read_target()?;
-        let y = src.read_target()?;
-        let equal = src.read_target_bool()?;
-        let inv = src.read_target()?;
-        Ok(Self { x, y, equal, inv })
+    run_once |witness, out_buffer| {
+        let xs = witness.get_targets(xs);
+        out_buffer.set_target_arr(inverses, &F::batch_multiplicative_inverse(&xs));
     }
 }
 
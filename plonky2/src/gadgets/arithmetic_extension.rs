@@ -9,7 +9,7 @@ use crate::gates::multiplication_extension::MulExtensionGate;
 use crate::hash::hash_types::RichField;
 use crate::iop::ext_target::{ExtensionAlgebraTarget, ExtensionTarget};
 use crate::iop::generator::{GeneratedValues, SimpleGenerator};
-use crate::iop::target::Target;
+use crate::iop::target::{BoolTarget, Target};
 use crate::iop::witness::{PartitionWitness, Witness, WitnessWrite};
 use crate::plonk::circuit_builder::CircuitBuilder;
 use crate::plonk::circuit_data::CommonCircuitData;
@@ -405,6 +405,20 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         c
     }
 
+    /// Returns `c - a * b`, for `a` in the extension field and `b, c` in the extension algebra.
+    pub fn sub_scalar_mul_ext_algebra(
+        &mut self,
+        c: ExtensionAlgebraTarget<D>,
+        a: ExtensionTarget<D>,
+        b: ExtensionAlgebraTarget<D>,
+    ) -> ExtensionAlgebraTarget<D> {
+        let mut res = c;
+        for i in 0..D {
+            res.0[i] = self.arithmetic_extension(F::NEG_ONE, F::ONE, a, b.0[i], c.0[i]);
+        }
+        res
+    }
+
     /// Returns `a * b`, where `b` is in the extension algebra and `a` in the extension field.
     pub fn scalar_mul_ext_algebra(
         &mut self,
@@ -428,8 +442,8 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         base
     }
 
-    /// Exponentiate `base` to the power of a known `exponent`.
-    // TODO: Test
+    /// Exponentiate `base` to the power of a known `exponent`, using a sliding window over the
+    /// exponent's bits. The window size is chosen to minimize the number of multiplications.
     pub fn exp_u64_extension(
         &mut self,
         base: ExtensionTarget<D>,
@@ -442,20 +456,95 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             3 => return self.cube_extension(base),
             _ => (),
         }
+        let steps = (1..=MAX_EXP_WINDOW)
+            .map(|window| sliding_window_steps(exponent, window))
+            .min_by_key(|steps| sliding_window_cost(steps))
+            .unwrap();
+
+        // `odd_powers[i] = base^(2i + 1)`, up to the largest digit used.
+        let max_digit = steps.iter().flatten().copied().max().unwrap_or(1);
+        let mut odd_powers = vec![base];
+        if max_digit > 1 {
+            let base_squared = self.square_extension(base);
+            for i in 1..=(max_digit / 2) as usize {
+                let power = self.mul_extension(odd_powers[i - 1], base_squared);
+                odd_powers.push(power);
+            }
+        }
+
+        let mut product = None;
+        for step in steps {
+            product = match (step, product) {
+                (_, None) => step.map(|digit| odd_powers[(digit / 2) as usize]),
+                (None, Some(p)) => Some(self.square_extension(p)),
+                (Some(digit), Some(p)) => {
+                    Some(self.mul_extension(p, odd_powers[(digit / 2) as usize]))
+                }
+            };
+        }
+        product.unwrap()
+    }
+
+    /// Exponentiate `base` to the power of `sum_i bits[i] 2^i`, where the bits are only known at
+    /// proving time. This takes three arithmetic operations per bit.
+    pub fn exp_extension_from_bits(
+        &mut self,
+        base: ExtensionTarget<D>,
+        exponent_bits: impl IntoIterator<Item = impl Borrow<BoolTarget>>,
+    ) -> ExtensionTarget<D> {
         let mut current = base;
         let mut product = self.one_extension();
 
-        for j in 0..bits_u64(exponent) {
+        for (j, bit) in exponent_bits.into_iter().enumerate() {
             if j != 0 {
                 current = self.square_extension(current);
             }
-            if (exponent >> j & 1) != 0 {
-                product = self.mul_extension(product, current);
-            }
+            // `product *= bit ? current : 1`, as `product + bit * (product * current - product)`.
+            let diff = self.mul_sub_extension(product, current, product);
+            product = self.scalar_mul_add_extension(bit.borrow().target, diff, product);
         }
         product
     }
 
+    /// Exponentiate `base` to the power of `exponent`, which must fit in `num_bits` bits.
+    pub fn exp_extension(
+        &mut self,
+        base: ExtensionTarget<D>,
+        exponent: Target,
+        num_bits: usize,
+    ) -> ExtensionTarget<D> {
+        let exponent_bits = self.split_le(exponent, num_bits);
+        self.exp_extension_from_bits(base, exponent_bits)
+    }
+
+    /// Returns `prod_i base_i^exponent_i` for known exponents. The squarings are shared between
+    /// all terms, so this costs one squaring per bit of the largest exponent, plus one
+    /// multiplication per set bit.
+    pub fn multi_exp_u64_extension(
+        &mut self,
+        terms: &[(ExtensionTarget<D>, u64)],
+    ) -> ExtensionTarget<D> {
+        let num_bits = terms
+            .iter()
+            .map(|&(_, exponent)| bits_u64(exponent))
+            .max()
+            .unwrap_or(0);
+
+        let mut product = None;
+        for j in (0..num_bits).rev() {
+            product = product.map(|p| self.square_extension(p));
+            for &(base, exponent) in terms {
+                if (exponent >> j & 1) != 0 {
+                    product = Some(match product {
+                        Some(p) => self.mul_extension(p, base),
+                        None => base,
+                    });
+                }
+            }
+        }
+        product.unwrap_or_else(|| self.one_extension())
+    }
+
     /// Computes `x / y`. Results in an unsatisfiable instance if `y = 0`.
     pub fn div_extension(
         &mut self,
@@ -493,6 +582,62 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         let one = self.one_extension();
         self.div_extension(one, x)
     }
+
+    /// Computes `1 / x` for each `x` in `xs`. Results in an unsatisfiable instance if any `x = 0`.
+    /// All inverses are generated at once with Montgomery's trick, and each is checked with one
+    /// multiplication.
+    pub fn inverse_many_extension(&mut self, xs: &[ExtensionTarget<D>]) -> Vec<ExtensionTarget<D>> {
+        if xs.is_empty() {
+            return Vec::new();
+        }
+        let inverses = self.add_virtual_extension_targets(xs.len());
+        self.add_simple_generator(BatchInverseGeneratorExtension {
+            xs: xs.to_vec(),
+            inverses: inverses.clone(),
+        });
+
+        let one = self.one_extension();
+        for (&x, &inv) in xs.iter().zip(&inverses) {
+            let x_inv = self.mul_extension(x, inv);
+            self.connect_extension(x_inv, one);
+        }
+        inverses
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct BatchInverseGeneratorExtension<const D: usize> {
+    xs: Vec<ExtensionTarget<D>>,
+    inverses: Vec<ExtensionTarget<D>>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F, D>
+    for BatchInverseGeneratorExtension<D>
+{
+    fn id(&self) -> String {
+        "BatchInverseGeneratorExtension".to_string()
+    }
+
+    fn dependencies(&self) -> Vec<Target> {
+        self.xs.iter().flat_map(|x| x.to_target_array()).collect()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let xs = witness.get_extension_targets(&self.xs);
+        let inverses = F::Extension::batch_multiplicative_inverse(&xs);
+        out_buffer.set_extension_targets(&self.inverses, &inverses);
+    }
+
+    fn serialize(&self, dst: &mut Vec<u8>, _common_data: &CommonCircuitData<F, D>) -> IoResult<()> {
+        dst.write_target_ext_vec(&self.xs)?;
+        dst.write_target_ext_vec(&self.inverses)
+    }
+
+    fn deserialize(src: &mut Buffer, _common_data: &CommonCircuitData<F, D>) -> IoResult<Self> {
+        let xs = src.read_target_ext_vec()?;
+        let inverses = src.read_target_ext_vec()?;
+        Ok(Self { xs, inverses })
+    }
 }
 
 #[derive(Debug, Default)]
@@ -589,10 +734,54 @@ pub(crate) struct ExtensionArithmeticOperation<F: Field64 + Extendable<D>, const
     addend: ExtensionTarget<D>,
 }
 
+/// The largest window considered by `exp_u64_extension`.
+const MAX_EXP_WINDOW: usize = 6;
+
+/// The steps of a left-to-right sliding-window exponentiation by `exponent`: `None` squares the
+/// accumulator, and `Some(d)` multiplies it by `base^d`, for some odd `d < 2^window`.
+fn sliding_window_steps(exponent: u64, window: usize) -> Vec<Option<u64>> {
+    let mut steps = Vec::new();
+    let mut i = bits_u64(exponent);
+    while i > 0 {
+        if (exponent >> (i - 1) & 1) == 0 {
+            steps.push(None);
+            i -= 1;
+            continue;
+        }
+        // The window covers bits `low..i`, and ends with a set bit.
+        let mut low = i.saturating_sub(window);
+        while (exponent >> low & 1) == 0 {
+            low += 1;
+        }
+        let digit = (exponent >> low) & ((1 << (i - low)) - 1);
+        steps.extend(core::iter::repeat(None).take(i - low));
+        steps.push(Some(digit));
+        i = low;
+    }
+    steps
+}
+
+/// The number of multiplications, including squarings, `exp_u64_extension` spends on `steps`.
+fn sliding_window_cost(steps: &[Option<u64>]) -> usize {
+    let max_digit = steps.iter().flatten().copied().max().unwrap_or(1);
+    let table_cost = if max_digit > 1 {
+        1 + (max_digit / 2) as usize
+    } else {
+        0
+    };
+    // Steps before the first multiplication are free, as the accumulator is still one.
+    let first = steps
+        .iter()
+        .position(Option::is_some)
+        .unwrap_or(steps.len());
+    table_cost + steps.len() - first - 1
+}
+
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
 
+    use super::*;
     use crate::field::extension::algebra::ExtensionAlgebra;
     use crate::field::types::Sample;
     use crate::iop::ext_target::ExtensionAlgebraTarget;
@@ -665,6 +854,43 @@ mod tests {
         verify(proof, &data.verifier_only, &data.common)
     }
 
+    #[test]
+    fn test_inverse_many() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+
+        let config = CircuitConfig::standard_recursion_config();
+
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let xs = FF::rand_vec(5);
+        let xts = builder.add_virtual_extension_targets(xs.len());
+        pw.set_extension_targets(&xts, &xs);
+        let invs = builder.inverse_many_extension(&xts);
+        for (&x, inv) in xs.iter().zip(invs) {
+            let expected = builder.constant_extension(x.inverse());
+            builder.connect_extension(inv, expected);
+        }
+
+        let ys = F::rand_vec(5);
+        let yts = builder.add_virtual_targets(ys.len());
+        pw.set_target_arr(&yts, &ys);
+        let invs = builder.inverse_many(&yts);
+        for (&y, inv) in ys.iter().zip(invs) {
+            let expected = builder.constant(y.inverse());
+            builder.connect(inv, expected);
+        }
+        assert!(builder.inverse_many(&[]).is_empty());
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
     #[test]
     fn test_mul_algebra() -> Result<()> {
         const D: usize = 2;
@@ -702,4 +928,63 @@ mod tests {
 
         verify(proof, &data.verifier_only, &data.common)
     }
+
+    #[test]
+    fn test_exp_extension() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+
+        let config = CircuitConfig::standard_recursion_config();
+
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let x = FF::rand();
+        let y = FF::rand();
+        let xt = builder.add_virtual_extension_target();
+        let yt = builder.add_virtual_extension_target();
+        pw.set_extension_target(xt, x);
+        pw.set_extension_target(yt, y);
+
+        for exponent in [0, 1, 5, 6, 27, 1 << 20, 0xdead_beef, u64::MAX] {
+            let expected = builder.constant_extension(x.exp_u64(exponent));
+            let constant_exp = builder.exp_u64_extension(xt, exponent);
+            builder.connect_extension(constant_exp, expected);
+        }
+
+        let exponent = 0x1234_5678;
+        let exponent_t = builder.add_virtual_target();
+        pw.set_target(exponent_t, F::from_canonical_u64(exponent));
+        let expected = builder.constant_extension(x.exp_u64(exponent));
+        let variable_exp = builder.exp_extension(xt, exponent_t, 32);
+        builder.connect_extension(variable_exp, expected);
+
+        let terms = [(xt, 12345), (yt, 1 << 40), (xt, 3)];
+        let expected = builder.constant_extension(x.exp_u64(12348) * y.exp_u64(1 << 40));
+        let multi_exp = builder.multi_exp_u64_extension(&terms);
+        builder.connect_extension(multi_exp, expected);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_sliding_window_cost() {
+        // A window of one is the binary method.
+        for exponent in [5u64, 0xffff, u64::MAX] {
+            let steps = sliding_window_steps(exponent, 1);
+            let binary_cost =
+                63 - exponent.leading_zeros() as usize + exponent.count_ones() as usize - 1;
+            assert_eq!(sliding_window_cost(&steps), binary_cost);
+        }
+        let best = (1..=MAX_EXP_WINDOW)
+            .map(|window| sliding_window_cost(&sliding_window_steps(u64::MAX, window)))
+            .min()
+            .unwrap();
+        assert!(best < sliding_window_cost(&sliding_window_steps(u64::MAX, 1)) * 3 / 4);
+    }
 }
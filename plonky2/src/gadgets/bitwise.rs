@@ -0,0 +1,99 @@
+use crate::field::extension::Extendable;
+use crate::gates::bitwise::{BitwiseGate, BitwiseOp};
+use crate::hash::hash_types::RichField;
+use crate::iop::target::Target;
+use crate::plonk::circuit_builder::CircuitBuilder;
+
+impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
+    /// Computes `op` on the 32-bit words `x` and `y`, with a `BitwiseGate`. Results in an
+    /// unsatisfiable instance if either input doesn't fit in 32 bits.
+    pub fn bitwise_u32(&mut self, op: BitwiseOp, x: Target, y: Target) -> Target {
+        let gate = BitwiseGate::new_from_config(op, &self.config);
+        let (row, i) = self.find_slot(gate, &[], &[]);
+        self.connect(x, Target::wire(row, BitwiseGate::wire_ith_input_0(i)));
+        self.connect(y, Target::wire(row, BitwiseGate::wire_ith_input_1(i)));
+        Target::wire(row, BitwiseGate::wire_ith_output(i))
+    }
+
+    /// Computes `x & y` for 32-bit words `x` and `y`.
+    pub fn and_u32(&mut self, x: Target, y: Target) -> Target {
+        self.bitwise_u32(BitwiseOp::And, x, y)
+    }
+
+    /// Computes `x | y` for 32-bit words `x` and `y`.
+    pub fn or_u32(&mut self, x: Target, y: Target) -> Target {
+        self.bitwise_u32(BitwiseOp::Or, x, y)
+    }
+
+    /// Computes `x ^ y` for 32-bit words `x` and `y`.
+    pub fn xor_u32(&mut self, x: Target, y: Target) -> Target {
+        self.bitwise_u32(BitwiseOp::Xor, x, y)
+    }
+
+    /// Computes `!x` for a 32-bit word `x`. `x` is assumed to fit in 32 bits, and isn't range
+    /// checked.
+    pub fn not_u32(&mut self, x: Target) -> Target {
+        let all_ones = self.constant(F::from_canonical_u32(u32::MAX));
+        self.sub(all_ones, x)
+    }
+
+    /// Rotates the 32-bit word `x` left by `n` bits. Results in an unsatisfiable instance if `x`
+    /// doesn't fit in 32 bits.
+    pub fn rotate_left_u32(&mut self, x: Target, n: usize) -> Target {
+        let mut bits = self.split_le(x, 32);
+        bits.rotate_right(n % 32);
+        self.le_sum(bits.into_iter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use rand::rngs::OsRng;
+    use rand::Rng;
+
+    use super::*;
+    use crate::field::types::Field;
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    #[test]
+    fn test_bitwise_u32() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x = builder.add_virtual_target();
+        let y = builder.add_virtual_target();
+        let outputs = [
+            builder.and_u32(x, y),
+            builder.or_u32(x, y),
+            builder.xor_u32(x, y),
+            builder.not_u32(x),
+            builder.rotate_left_u32(x, 7),
+        ];
+        builder.register_public_inputs(&outputs);
+        let data = builder.build::<C>();
+
+        let x_value = OsRng.gen::<u32>();
+        let y_value = OsRng.gen::<u32>();
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u32(x_value));
+        pw.set_target(y, F::from_canonical_u32(y_value));
+        let proof = data.prove(pw)?;
+
+        let expected = [
+            x_value & y_value,
+            x_value | y_value,
+            x_value ^ y_value,
+            !x_value,
+            x_value.rotate_left(7),
+        ]
+        .map(F::from_canonical_u32);
+        assert_eq!(proof.public_inputs, expected);
+        data.verify(proof)
+    }
+}
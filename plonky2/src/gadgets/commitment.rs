@@ -0,0 +1,237 @@
+//! Hash-based commitments, natively and within circuits.
+//!
+//! A commitment to `values` with the blinding `r` is `H(r || values)`. It is binding as `H` is
+//! collision resistant, and hiding as long as `r` is sampled uniformly, e.g. with
+//! `HashOut::rand()`, and kept secret until the commitment is opened.
+//!
+//! A [`VectorCommitment`] commits to a sequence of values, each with its own blinding, as the
+//! Merkle root of their commitments. Each entry can then be opened on its own, revealing nothing
+//! about the others.
+
+use alloc::vec::Vec;
+
+use anyhow::{ensure, Result};
+
+use crate::field::extension::Extendable;
+use crate::hash::hash_types::{HashOut, HashOutTarget, RichField};
+use crate::hash::merkle_proofs::{verify_merkle_proof, MerkleProof, MerkleProofTarget};
+use crate::hash::merkle_tree::MerkleTree;
+use crate::iop::target::Target;
+use crate::iop::witness::WitnessWrite;
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::config::{AlgebraicHasher, Hasher};
+
+/// The commitment to `values` with `blinding`.
+pub fn commit<F: RichField, H: Hasher<F, Hash = HashOut<F>>>(
+    values: &[F],
+    blinding: HashOut<F>,
+) -> HashOut<F> {
+    H::hash_no_pad(&[blinding.elements.as_slice(), values].concat())
+}
+
+/// Checks that `commitment` opens to `values` with `blinding`.
+pub fn verify_opening<F: RichField, H: Hasher<F, Hash = HashOut<F>>>(
+    commitment: HashOut<F>,
+    values: &[F],
+    blinding: HashOut<F>,
+) -> Result<()> {
+    ensure!(
+        commit::<F, H>(values, blinding) == commitment,
+        "The commitment doesn't open to the given values"
+    );
+    Ok(())
+}
+
+/// A commitment to a sequence of values, along with what is needed to open each of them.
+#[derive(Clone, Debug)]
+pub struct VectorCommitment<F: RichField, H: Hasher<F, Hash = HashOut<F>>> {
+    tree: MerkleTree<F, H>,
+    blindings: Vec<HashOut<F>>,
+}
+
+/// The opening of one entry of a [`VectorCommitment`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VectorOpening<F: RichField, H: Hasher<F, Hash = HashOut<F>>> {
+    pub blinding: HashOut<F>,
+    pub proof: MerkleProof<F, H>,
+}
+
+impl<F: RichField, H: Hasher<F, Hash = HashOut<F>>> VectorCommitment<F, H> {
+    /// Commits to `entries`, blinding each with the corresponding element of `blindings`. The
+    /// number of entries must be a power of two.
+    pub fn new(entries: &[Vec<F>], blindings: Vec<HashOut<F>>) -> Self {
+        assert!(entries.len().is_power_of_two());
+        assert_eq!(entries.len(), blindings.len());
+        let leaves = entries
+            .iter()
+            .zip(&blindings)
+            .map(|(values, &blinding)| commit::<F, H>(values, blinding).elements.to_vec())
+            .collect();
+        Self {
+            tree: MerkleTree::new(leaves, 0),
+            blindings,
+        }
+    }
+
+    pub fn root(&self) -> HashOut<F> {
+        self.tree.cap.0[0]
+    }
+
+    /// The depth of the tree, i.e. the number of bits of indices and the length of openings.
+    pub fn depth(&self) -> usize {
+        self.blindings.len().trailing_zeros() as usize
+    }
+
+    pub fn open(&self, index: usize) -> VectorOpening<F, H> {
+        VectorOpening {
+            blinding: self.blindings[index],
+            proof: self.tree.prove(index),
+        }
+    }
+}
+
+/// Checks that the entry at `index` of the vector commitment with root `root` is `values`.
+pub fn verify_vector_opening<F: RichField, H: Hasher<F, Hash = HashOut<F>>>(
+    root: HashOut<F>,
+    index: usize,
+    values: &[F],
+    opening: &VectorOpening<F, H>,
+) -> Result<()> {
+    let leaf = commit::<F, H>(values, opening.blinding);
+    verify_merkle_proof::<F, H>(leaf.elements.to_vec(), index, root, &opening.proof)
+}
+
+/// The opening of one entry of a vector commitment.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VectorOpeningTarget {
+    pub blinding: HashOutTarget,
+    pub proof: MerkleProofTarget,
+}
+
+pub fn set_vector_opening_target<
+    F: RichField,
+    H: Hasher<F, Hash = HashOut<F>>,
+    W: WitnessWrite<F>,
+>(
+    witness: &mut W,
+    target: &VectorOpeningTarget,
+    opening: &VectorOpening<F, H>,
+) {
+    assert_eq!(target.proof.siblings.len(), opening.proof.siblings.len());
+    witness.set_hash_target(target.blinding, opening.blinding);
+    for (&t, &sibling) in target.proof.siblings.iter().zip(&opening.proof.siblings) {
+        witness.set_hash_target(t, sibling);
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
+    /// The commitment to `values` with `blinding`.
+    pub fn commit<H: AlgebraicHasher<F>>(
+        &mut self,
+        values: &[Target],
+        blinding: HashOutTarget,
+    ) -> HashOutTarget {
+        self.hash_n_to_hash_no_pad::<H>([blinding.elements.as_slice(), values].concat())
+    }
+
+    /// Checks that `commitment` opens to `values` with `blinding`.
+    pub fn verify_opening<H: AlgebraicHasher<F>>(
+        &mut self,
+        commitment: HashOutTarget,
+        values: &[Target],
+        blinding: HashOutTarget,
+    ) {
+        let expected = self.commit::<H>(values, blinding);
+        self.connect_hashes(commitment, expected);
+    }
+
+    /// Adds an opening of a vector commitment of the given depth.
+    pub fn add_virtual_vector_opening_target(&mut self, depth: usize) -> VectorOpeningTarget {
+        VectorOpeningTarget {
+            blinding: self.add_virtual_hash(),
+            proof: self.add_virtual_merkle_proof(depth),
+        }
+    }
+
+    /// Checks that the entry at `index` of the vector commitment with root `root` is `values`.
+    /// `index` is range checked to the depth of the opening.
+    pub fn verify_vector_opening<H: AlgebraicHasher<F>>(
+        &mut self,
+        root: HashOutTarget,
+        index: Target,
+        values: &[Target],
+        opening: &VectorOpeningTarget,
+    ) {
+        let index_bits = self.split_le(index, opening.proof.siblings.len());
+        let leaf = self.commit::<H>(values, opening.blinding);
+        self.verify_merkle_proof::<H>(leaf.elements.to_vec(), &index_bits, root, &opening.proof);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::types::{Field, Sample};
+    use crate::iop::witness::PartialWitness;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+    type H = <C as GenericConfig<D>>::Hasher;
+
+    #[test]
+    fn test_commitment() -> Result<()> {
+        let values = F::rand_vec(5);
+        let blinding = HashOut::rand();
+        let commitment = commit::<F, H>(&values, blinding);
+        verify_opening::<F, H>(commitment, &values, blinding)?;
+        assert!(verify_opening::<F, H>(commitment, &values, HashOut::rand()).is_err());
+        assert!(verify_opening::<F, H>(commitment, &values[1..], blinding).is_err());
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let values_t = builder.add_virtual_targets(values.len());
+        let blinding_t = builder.add_virtual_hash();
+        let commitment_t = builder.add_virtual_hash();
+        builder.verify_opening::<H>(commitment_t, &values_t, blinding_t);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target_arr(&values_t, &values);
+        pw.set_hash_target(blinding_t, blinding);
+        pw.set_hash_target(commitment_t, commitment);
+        let proof = data.prove(pw)?;
+        data.verify(proof)
+    }
+
+    #[test]
+    fn test_vector_commitment() -> Result<()> {
+        let entries = (0..8).map(|_| F::rand_vec(3)).collect::<Vec<_>>();
+        let blindings = (0..8).map(|_| HashOut::rand()).collect();
+        let vc = VectorCommitment::<F, H>::new(&entries, blindings);
+        let root = vc.root();
+        let opening = vc.open(5);
+        verify_vector_opening(root, 5, &entries[5], &opening)?;
+        assert!(verify_vector_opening(root, 4, &entries[5], &opening).is_err());
+        assert!(verify_vector_opening(root, 5, &entries[4], &opening).is_err());
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let root_t = builder.add_virtual_hash();
+        let index_t = builder.add_virtual_target();
+        let values_t = builder.add_virtual_targets(3);
+        let opening_t = builder.add_virtual_vector_opening_target(vc.depth());
+        builder.verify_vector_opening::<H>(root_t, index_t, &values_t, &opening_t);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_hash_target(root_t, root);
+        pw.set_target(index_t, F::from_canonical_usize(5));
+        pw.set_target_arr(&values_t, &entries[5]);
+        set_vector_opening_target(&mut pw, &opening_t, &opening);
+        let proof = data.prove(pw)?;
+        data.verify(proof)
+    }
+}
@@ -0,0 +1,718 @@
+//! The elliptic curve ecGFp5, defined over the quintic extension [`GFp5`] of the Goldilocks field,
+//! natively and within circuits. Since its base field is an extension of the circuit field, curve
+//! arithmetic needs no non-native field arithmetic, which makes in-circuit signatures cheap.
+//!
+//! We use the short Weierstrass model `y^2 = x^3 + A x + B`, which is isomorphic to ecGFp5's
+//! `y^2 = x (x^2 + 2 x + 263 z)` through `x -> x + 2/3`, so that points can be added with the
+//! complete formulas of Renes, Costello and Batina. The curve has order `2 n` for a prime `n`, and
+//! we only work with points of the subgroup of order `n`, on which the formulas have no exceptional
+//! cases. An affine point is in that subgroup iff `x - 2/3` is a nonzero square.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::ops::{Add, Neg};
+
+use num::bigint::BigUint;
+use num::Zero;
+
+use crate::field::extension::quintic::QuinticExtension;
+use crate::field::extension::{Extendable, FieldExtension};
+use crate::field::goldilocks_field::GoldilocksField;
+use crate::field::ops::Square;
+use crate::field::types::{Field, PrimeField64};
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator};
+use crate::iop::target::{BoolTarget, Target};
+use crate::iop::witness::{PartitionWitness, Witness, WitnessWrite};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CommonCircuitData;
+use crate::util::serialization::{Buffer, IoResult, Read, Write};
+
+/// The base field of the curve, `GF(p)[z] / (z^5 - 3)` where `p` is the Goldilocks prime.
+pub type GFp5 = QuinticExtension<GoldilocksField>;
+
+const fn gfp5(limbs: [u64; 5]) -> GFp5 {
+    QuinticExtension([
+        GoldilocksField(limbs[0]),
+        GoldilocksField(limbs[1]),
+        GoldilocksField(limbs[2]),
+        GoldilocksField(limbs[3]),
+        GoldilocksField(limbs[4]),
+    ])
+}
+
+/// `A = 263 z - 4/3`.
+pub const A: GFp5 = gfp5([6148914689804861439, 263, 0, 0, 0]);
+
+/// `B = 16/27 - 526 z / 3`.
+pub const B: GFp5 = gfp5([15713893096167979237, 6148914689804861265, 0, 0, 0]);
+
+/// `3 B`, as used by the addition formulas.
+const B3: GFp5 = gfp5([10248191149674769069, 18446744069414583795, 0, 0, 0]);
+
+/// The difference between the `x` coordinates of our model and of ecGFp5's.
+const TWO_THIRDS: GFp5 = gfp5([6148914689804861441, 0, 0, 0, 0]);
+
+/// The little-endian 64-bit limbs of the prime order `n` of the subgroup we work in.
+const GROUP_ORDER_LIMBS: [u64; 5] = [
+    16721823182210465761,
+    16755743817124323484,
+    9223371928670570041,
+    9223371972430266390,
+    9223372026117357575,
+];
+
+/// The number of bits of the group order, and so of scalars.
+pub const NUM_SCALAR_BITS: usize = 319;
+
+/// The prime order `n` of the subgroup we work in.
+pub fn group_order() -> BigUint {
+    GROUP_ORDER_LIMBS
+        .iter()
+        .rev()
+        .fold(BigUint::zero(), |acc, &limb| (acc << 64) + limb)
+}
+
+/// The square root of `x`, if it is a square.
+pub(crate) fn sqrt(x: GFp5) -> Option<GFp5> {
+    if x.is_zero() {
+        return Some(x);
+    }
+    let one = BigUint::from(1u32);
+    if !x.exp_biguint(&((GFp5::order() - &one) >> 1)).is_one() {
+        return None;
+    }
+
+    // Tonelli-Shanks, with the same steps as `PrimeField::sqrt`.
+    let t = (GFp5::order() - &one) >> GFp5::TWO_ADICITY;
+    let mut z = GFp5::POWER_OF_TWO_GENERATOR;
+    let mut w = x.exp_biguint(&((t - &one) >> 1));
+    let mut r = w * x;
+    let mut b = r * w;
+    let mut v = GFp5::TWO_ADICITY;
+    while !b.is_one() {
+        let mut k = 0;
+        let mut b2k = b;
+        while !b2k.is_one() {
+            b2k = b2k.square();
+            k += 1;
+        }
+        w = z;
+        for _ in 0..v - k - 1 {
+            w = w.square();
+        }
+        z = w.square();
+        b *= z;
+        r *= w;
+        v = k;
+    }
+    Some(r)
+}
+
+/// A point of the curve other than the identity, in affine coordinates.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct AffinePoint {
+    pub x: GFp5,
+    pub y: GFp5,
+}
+
+impl AffinePoint {
+    /// The generator of the subgroup of order `n` whose ecGFp5 `x` coordinate is 1, and whose `y`
+    /// coordinate has an even constant term.
+    pub const GENERATOR: Self = Self {
+        x: gfp5([6148914689804861442, 0, 0, 0, 0]),
+        y: gfp5([
+            15639568768132330142,
+            162098043370241963,
+            4541761356061926443,
+            13409231596247359311,
+            17527534390646570545,
+        ]),
+    };
+
+    pub fn is_on_curve(&self) -> bool {
+        self.y.square() == self.x.cube() + A * self.x + B
+    }
+
+    /// The square root of `x - 2/3` which witnesses that the point is in the subgroup of order
+    /// `n`, if it is.
+    fn subgroup_witness(&self) -> Option<GFp5> {
+        sqrt(self.x - TWO_THIRDS).filter(|w| !w.is_zero())
+    }
+
+    pub fn is_in_subgroup(&self) -> bool {
+        self.is_on_curve() && self.subgroup_witness().is_some()
+    }
+
+    pub fn to_projective(&self) -> ProjectivePoint {
+        ProjectivePoint {
+            x: self.x,
+            y: self.y,
+            z: GFp5::ONE,
+        }
+    }
+}
+
+/// A point of the curve in projective coordinates `(X : Y : Z)`, standing for `(X / Z, Y / Z)`, or
+/// for the identity if `Z = 0`.
+#[derive(Copy, Clone, Debug)]
+pub struct ProjectivePoint {
+    pub x: GFp5,
+    pub y: GFp5,
+    pub z: GFp5,
+}
+
+impl ProjectivePoint {
+    pub const IDENTITY: Self = Self {
+        x: GFp5::ZERO,
+        y: GFp5::ONE,
+        z: GFp5::ZERO,
+    };
+
+    pub fn generator() -> Self {
+        AffinePoint::GENERATOR.to_projective()
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.z.is_zero()
+    }
+
+    /// Whether the point is the identity or an affine point in the subgroup of order `n`.
+    pub fn is_in_subgroup(&self) -> bool {
+        match self.to_affine() {
+            Some(p) => p.is_in_subgroup(),
+            None => true,
+        }
+    }
+
+    pub fn to_affine(&self) -> Option<AffinePoint> {
+        let z_inv = self.z.try_inverse()?;
+        Some(AffinePoint {
+            x: self.x * z_inv,
+            y: self.y * z_inv,
+        })
+    }
+
+    pub fn double(&self) -> Self {
+        *self + *self
+    }
+
+    /// Computes `scalar * self` by double-and-add.
+    pub fn scalar_mul(&self, scalar: &BigUint) -> Self {
+        let mut res = Self::IDENTITY;
+        for i in (0..scalar.bits()).rev() {
+            res = res.double();
+            if scalar.bit(i) {
+                res = res + *self;
+            }
+        }
+        res
+    }
+}
+
+impl PartialEq for ProjectivePoint {
+    fn eq(&self, other: &Self) -> bool {
+        self.x * other.z == other.x * self.z && self.y * other.z == other.y * self.z
+    }
+}
+
+impl Eq for ProjectivePoint {}
+
+impl Add for ProjectivePoint {
+    type Output = Self;
+
+    /// Algorithm 1 of "Complete addition formulas for prime order elliptic curves", by Renes,
+    /// Costello and Batina.
+    fn add(self, rhs: Self) -> Self {
+        let (x1, y1, z1) = (self.x, self.y, self.z);
+        let (x2, y2, z2) = (rhs.x, rhs.y, rhs.z);
+        let t0 = x1 * x2;
+        let t1 = y1 * y2;
+        let t2 = z1 * z2;
+        let t3 = (x1 + y1) * (x2 + y2) - (t0 + t1);
+        let t4 = (x1 + z1) * (x2 + z2) - (t0 + t2);
+        let t5 = (y1 + z1) * (y2 + z2) - (t1 + t2);
+        let z3 = A * t4 + B3 * t2;
+        let x3 = t1 - z3;
+        let z3 = t1 + z3;
+        let y3 = x3 * z3;
+        let t1 = t0.triple() + A * t2;
+        let t4 = B3 * t4 + A * (t0 - A * t2);
+        Self {
+            x: t3 * x3 - t5 * t4,
+            y: y3 + t1 * t4,
+            z: t5 * z3 + t3 * t1,
+        }
+    }
+}
+
+impl Neg for ProjectivePoint {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self {
+            x: self.x,
+            y: -self.y,
+            z: self.z,
+        }
+    }
+}
+
+/// A point of the curve other than the identity, in affine coordinates.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct AffinePointTarget {
+    pub x: ExtensionTarget<5>,
+    pub y: ExtensionTarget<5>,
+}
+
+/// A point of the curve in projective coordinates.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct ProjectivePointTarget {
+    pub x: ExtensionTarget<5>,
+    pub y: ExtensionTarget<5>,
+    pub z: ExtensionTarget<5>,
+}
+
+pub fn set_affine_point_target<W: WitnessWrite<GoldilocksField>>(
+    witness: &mut W,
+    target: AffinePointTarget,
+    value: AffinePoint,
+) {
+    witness.set_extension_target(target.x, value.x);
+    witness.set_extension_target(target.y, value.y);
+}
+
+impl<const D: usize> CircuitBuilder<GoldilocksField, D>
+where
+    GoldilocksField: Extendable<D>,
+{
+    fn gfp5_constant(&mut self, c: GFp5) -> ExtensionTarget<5> {
+        ExtensionTarget(c.0.map(|x| self.constant(x)))
+    }
+
+    fn gfp5_add(&mut self, a: ExtensionTarget<5>, b: ExtensionTarget<5>) -> ExtensionTarget<5> {
+        ExtensionTarget(core::array::from_fn(|i| self.add(a.0[i], b.0[i])))
+    }
+
+    fn gfp5_sub(&mut self, a: ExtensionTarget<5>, b: ExtensionTarget<5>) -> ExtensionTarget<5> {
+        ExtensionTarget(core::array::from_fn(|i| self.sub(a.0[i], b.0[i])))
+    }
+
+    fn gfp5_mul(&mut self, a: ExtensionTarget<5>, b: ExtensionTarget<5>) -> ExtensionTarget<5> {
+        let w = <GoldilocksField as Extendable<5>>::W;
+        let zero = self.zero();
+        let mut res = [zero; 5];
+        for i in 0..5 {
+            for j in 0..5 {
+                let (k, c) = if i + j < 5 {
+                    (i + j, GoldilocksField::ONE)
+                } else {
+                    (i + j - 5, w)
+                };
+                res[k] = self.arithmetic(c, GoldilocksField::ONE, a.0[i], b.0[j], res[k]);
+            }
+        }
+        ExtensionTarget(res)
+    }
+
+    /// Multiplies by a constant, keeping its nonzero coefficients in the gates' constants.
+    fn gfp5_mul_const(&mut self, c: GFp5, a: ExtensionTarget<5>) -> ExtensionTarget<5> {
+        let w = <GoldilocksField as Extendable<5>>::W;
+        let zero = self.zero();
+        let one = self.one();
+        let mut res = [zero; 5];
+        for i in (0..5).filter(|&i| !c.0[i].is_zero()) {
+            for j in 0..5 {
+                let (k, coeff) = if i + j < 5 {
+                    (i + j, c.0[i])
+                } else {
+                    (i + j - 5, w * c.0[i])
+                };
+                res[k] = self.arithmetic(coeff, GoldilocksField::ONE, one, a.0[j], res[k]);
+            }
+        }
+        ExtensionTarget(res)
+    }
+
+    fn gfp5_select(
+        &mut self,
+        b: BoolTarget,
+        x: ExtensionTarget<5>,
+        y: ExtensionTarget<5>,
+    ) -> ExtensionTarget<5> {
+        ExtensionTarget(core::array::from_fn(|i| self.select(b, x.0[i], y.0[i])))
+    }
+
+    fn gfp5_connect(&mut self, a: ExtensionTarget<5>, b: ExtensionTarget<5>) {
+        for i in 0..5 {
+            self.connect(a.0[i], b.0[i]);
+        }
+    }
+
+    /// Adds an affine point target. It isn't constrained to be on the curve, see
+    /// [`Self::curve_assert_in_subgroup`].
+    pub fn add_virtual_affine_point_target(&mut self) -> AffinePointTarget {
+        AffinePointTarget {
+            x: ExtensionTarget(self.add_virtual_target_arr()),
+            y: ExtensionTarget(self.add_virtual_target_arr()),
+        }
+    }
+
+    pub fn constant_affine_point(&mut self, p: AffinePoint) -> AffinePointTarget {
+        AffinePointTarget {
+            x: self.gfp5_constant(p.x),
+            y: self.gfp5_constant(p.y),
+        }
+    }
+
+    pub fn affine_to_projective(&mut self, p: AffinePointTarget) -> ProjectivePointTarget {
+        ProjectivePointTarget {
+            x: p.x,
+            y: p.y,
+            z: self.gfp5_constant(GFp5::ONE),
+        }
+    }
+
+    /// Checks that `p` is on the curve and in the subgroup of order `n`, which the other curve
+    /// gadgets assume of their inputs.
+    pub fn curve_assert_in_subgroup(&mut self, p: AffinePointTarget) {
+        let x_squared = self.gfp5_mul(p.x, p.x);
+        let x_cubed = self.gfp5_mul(x_squared, p.x);
+        let ax = self.gfp5_mul_const(A, p.x);
+        let b = self.gfp5_constant(B);
+        let rhs = self.gfp5_add(x_cubed, ax);
+        let rhs = self.gfp5_add(rhs, b);
+        let lhs = self.gfp5_mul(p.y, p.y);
+        self.gfp5_connect(lhs, rhs);
+
+        // `x - 2/3` must be the square of a nonzero `w`.
+        let w = ExtensionTarget(self.add_virtual_target_arr());
+        let w_inv = ExtensionTarget(self.add_virtual_target_arr());
+        self.add_simple_generator(SubgroupWitnessGenerator { x: p.x, w, w_inv });
+        let two_thirds = self.gfp5_constant(TWO_THIRDS);
+        let shifted_x = self.gfp5_sub(p.x, two_thirds);
+        let w_squared = self.gfp5_mul(w, w);
+        self.gfp5_connect(w_squared, shifted_x);
+        let w_w_inv = self.gfp5_mul(w, w_inv);
+        let one = self.gfp5_constant(GFp5::ONE);
+        self.gfp5_connect(w_w_inv, one);
+    }
+
+    /// Computes `p + q`. See [`ProjectivePoint::add`].
+    pub fn curve_add(
+        &mut self,
+        p: ProjectivePointTarget,
+        q: ProjectivePointTarget,
+    ) -> ProjectivePointTarget {
+        let t0 = self.gfp5_mul(p.x, q.x);
+        let t1 = self.gfp5_mul(p.y, q.y);
+        let t2 = self.gfp5_mul(p.z, q.z);
+        let t3 = self.curve_add_cross_term(p.x, p.y, q.x, q.y, t0, t1);
+        let t4 = self.curve_add_cross_term(p.x, p.z, q.x, q.z, t0, t2);
+        let t5 = self.curve_add_cross_term(p.y, p.z, q.y, q.z, t1, t2);
+        let a_t4 = self.gfp5_mul_const(A, t4);
+        let b3_t2 = self.gfp5_mul_const(B3, t2);
+        let z3 = self.gfp5_add(a_t4, b3_t2);
+        let x3 = self.gfp5_sub(t1, z3);
+        let z3 = self.gfp5_add(t1, z3);
+        let y3 = self.gfp5_mul(x3, z3);
+        let a_t2 = self.gfp5_mul_const(A, t2);
+        let t0_double = self.gfp5_add(t0, t0);
+        let t0_triple = self.gfp5_add(t0_double, t0);
+        let t1 = self.gfp5_add(t0_triple, a_t2);
+        let b3_t4 = self.gfp5_mul_const(B3, t4);
+        let t0_minus_a_t2 = self.gfp5_sub(t0, a_t2);
+        let a_t0_minus_a_t2 = self.gfp5_mul_const(A, t0_minus_a_t2);
+        let t4 = self.gfp5_add(b3_t4, a_t0_minus_a_t2);
+
+        let t3_x3 = self.gfp5_mul(t3, x3);
+        let t5_t4 = self.gfp5_mul(t5, t4);
+        let t1_t4 = self.gfp5_mul(t1, t4);
+        let t5_z3 = self.gfp5_mul(t5, z3);
+        let t3_t1 = self.gfp5_mul(t3, t1);
+        ProjectivePointTarget {
+            x: self.gfp5_sub(t3_x3, t5_t4),
+            y: self.gfp5_add(y3, t1_t4),
+            z: self.gfp5_add(t5_z3, t3_t1),
+        }
+    }
+
+    /// Computes `(a1 + b1) * (a2 + b2) - (a1 * a2 + b1 * b2)`, given the two products.
+    fn curve_add_cross_term(
+        &mut self,
+        a1: ExtensionTarget<5>,
+        b1: ExtensionTarget<5>,
+        a2: ExtensionTarget<5>,
+        b2: ExtensionTarget<5>,
+        a1_a2: ExtensionTarget<5>,
+        b1_b2: ExtensionTarget<5>,
+    ) -> ExtensionTarget<5> {
+        let sum1 = self.gfp5_add(a1, b1);
+        let sum2 = self.gfp5_add(a2, b2);
+        let product = self.gfp5_mul(sum1, sum2);
+        let diagonal = self.gfp5_add(a1_a2, b1_b2);
+        self.gfp5_sub(product, diagonal)
+    }
+
+    /// Computes `if b { p } else { q }`.
+    pub fn curve_select(
+        &mut self,
+        b: BoolTarget,
+        p: ProjectivePointTarget,
+        q: ProjectivePointTarget,
+    ) -> ProjectivePointTarget {
+        ProjectivePointTarget {
+            x: self.gfp5_select(b, p.x, q.x),
+            y: self.gfp5_select(b, p.y, q.y),
+            z: self.gfp5_select(b, p.z, q.z),
+        }
+    }
+
+    /// Computes `k * p`, where `bits` are the little-endian bits of `k`.
+    pub fn curve_mul(
+        &mut self,
+        p: ProjectivePointTarget,
+        bits: &[BoolTarget],
+    ) -> ProjectivePointTarget {
+        let identity = self.curve_constant(ProjectivePoint::IDENTITY);
+        let mut res = identity;
+        for &bit in bits.iter().rev() {
+            res = self.curve_add(res, res);
+            let sum = self.curve_add(res, p);
+            res = self.curve_select(bit, sum, res);
+        }
+        res
+    }
+
+    /// Computes `k * G` for the generator `G`, where `bits` are the little-endian bits of `k`. This
+    /// needs no doublings, as the multiples `2^i G` are constants.
+    pub fn curve_mul_generator(&mut self, bits: &[BoolTarget]) -> ProjectivePointTarget {
+        let mut res = self.curve_constant(ProjectivePoint::IDENTITY);
+        let mut power = ProjectivePoint::generator();
+        for &bit in bits {
+            let power_target = self.curve_constant(power);
+            let sum = self.curve_add(res, power_target);
+            res = self.curve_select(bit, sum, res);
+            power = power.double();
+        }
+        res
+    }
+
+    fn curve_constant(&mut self, p: ProjectivePoint) -> ProjectivePointTarget {
+        ProjectivePointTarget {
+            x: self.gfp5_constant(p.x),
+            y: self.gfp5_constant(p.y),
+            z: self.gfp5_constant(p.z),
+        }
+    }
+
+    /// Checks that `p` and `q` are the same point.
+    pub fn curve_connect(&mut self, p: ProjectivePointTarget, q: ProjectivePointTarget) {
+        let px_qz = self.gfp5_mul(p.x, q.z);
+        let qx_pz = self.gfp5_mul(q.x, p.z);
+        self.gfp5_connect(px_qz, qx_pz);
+        let py_qz = self.gfp5_mul(p.y, q.z);
+        let qy_pz = self.gfp5_mul(q.y, p.z);
+        self.gfp5_connect(py_qz, qy_pz);
+    }
+}
+
+/// Computes the witness of [`CircuitBuilder::curve_assert_in_subgroup`].
+///
+/// It is only ever added to Goldilocks circuits, but is generic over the field so that generic
+/// generator serializers, like `DefaultGeneratorSerializer`, can list it. Values are converted
+/// to and from Goldilocks through their canonical representatives.
+#[derive(Debug, Default)]
+pub struct SubgroupWitnessGenerator {
+    x: ExtensionTarget<5>,
+    w: ExtensionTarget<5>,
+    w_inv: ExtensionTarget<5>,
+}
+
+impl SubgroupWitnessGenerator {
+    fn set_gfp5<F: RichField>(
+        out_buffer: &mut GeneratedValues<F>,
+        target: ExtensionTarget<5>,
+        value: GFp5,
+    ) {
+        for (t, c) in target
+            .0
+            .into_iter()
+            .zip(<GFp5 as FieldExtension<5>>::to_basefield_array(&value))
+        {
+            out_buffer.set_target(t, F::from_canonical_u64(c.to_canonical_u64()));
+        }
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F, D>
+    for SubgroupWitnessGenerator
+{
+    fn id(&self) -> String {
+        "SubgroupWitnessGenerator".to_string()
+    }
+
+    fn dependencies(&self) -> Vec<Target> {
+        self.x.to_target_array().to_vec()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let x = <GFp5 as FieldExtension<5>>::from_basefield_array(self.x.0.map(|t| {
+            GoldilocksField::from_canonical_u64(witness.get_target(t).to_canonical_u64())
+        }));
+        // If the point isn't in the subgroup, any value will fail the constraints.
+        let w = sqrt(x - TWO_THIRDS).unwrap_or(GFp5::ONE);
+        Self::set_gfp5(out_buffer, self.w, w);
+        Self::set_gfp5(
+            out_buffer,
+            self.w_inv,
+            w.try_inverse().unwrap_or(GFp5::ZERO),
+        );
+    }
+
+    fn serialize(&self, dst: &mut Vec<u8>, _common_data: &CommonCircuitData<F, D>) -> IoResult<()> {
+        dst.write_target_ext(self.x)?;
+        dst.write_target_ext(self.w)?;
+        dst.write_target_ext(self.w_inv)
+    }
+
+    fn deserialize(src: &mut Buffer, _common_data: &CommonCircuitData<F, D>) -> IoResult<Self> {
+        let x = src.read_target_ext()?;
+        let w = src.read_target_ext()?;
+        let w_inv = src.read_target_ext()?;
+        Ok(Self { x, w, w_inv })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::marker::PhantomData;
+
+    use anyhow::Result;
+
+    use super::*;
+    use crate::field::types::Sample;
+    use crate::iop::witness::PartialWitness;
+    use crate::plonk::circuit_data::{CircuitConfig, CircuitData};
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+    use crate::util::serialization::{DefaultGateSerializer, DefaultGeneratorSerializer};
+
+    #[test]
+    fn test_curve_constants() {
+        let z = gfp5([0, 1, 0, 0, 0]);
+        let b = GFp5::from_canonical_u64(263) * z;
+        let third = GFp5::from_canonical_u64(3).inverse();
+        assert_eq!(TWO_THIRDS, GFp5::TWO * third);
+        assert_eq!(A, b - GFp5::from_canonical_u64(4) * third);
+        assert_eq!(
+            B,
+            GFp5::from_canonical_u64(16) * third.cube() - GFp5::TWO * third * b
+        );
+        assert_eq!(B3, B.triple());
+        assert_eq!(group_order().bits() as usize, NUM_SCALAR_BITS);
+    }
+
+    #[test]
+    fn test_generator() {
+        let g = AffinePoint::GENERATOR;
+        assert!(g.is_in_subgroup());
+        assert_eq!(g.x - TWO_THIRDS, GFp5::ONE);
+
+        let g = ProjectivePoint::generator();
+        assert!(g.scalar_mul(&group_order()).is_identity());
+        let order_minus_one = group_order() - 1u32;
+        assert_eq!(g.scalar_mul(&order_minus_one), -g);
+        assert_eq!(g + ProjectivePoint::IDENTITY, g);
+        assert!((g + -g).is_identity());
+        assert_eq!(g.double() + g, g.scalar_mul(&BigUint::from(3u32)));
+    }
+
+    #[test]
+    fn test_subgroup_check() {
+        let g = AffinePoint::GENERATOR;
+        // The point of order 2.
+        let t = AffinePoint {
+            x: TWO_THIRDS,
+            y: GFp5::ZERO,
+        };
+        assert!(t.is_on_curve());
+        assert!(!t.is_in_subgroup());
+        let off_curve = AffinePoint { x: g.x, y: g.x };
+        assert!(!off_curve.is_in_subgroup());
+
+        assert!(ProjectivePoint::IDENTITY.is_in_subgroup());
+        assert!(ProjectivePoint::generator().is_in_subgroup());
+        assert!(!(ProjectivePoint::generator() + t.to_projective()).is_in_subgroup());
+    }
+
+    #[test]
+    fn test_curve_mul_circuit() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = GoldilocksField;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let p = builder.add_virtual_affine_point_target();
+        builder.curve_assert_in_subgroup(p);
+        let bits = (0..64)
+            .map(|_| builder.add_virtual_bool_target_safe())
+            .collect::<Vec<_>>();
+        let p_projective = builder.affine_to_projective(p);
+        let kp = builder.curve_mul(p_projective, &bits);
+        let kg = builder.curve_mul_generator(&bits);
+        let expected_kp = builder.add_virtual_affine_point_target();
+        let expected_kg = builder.add_virtual_affine_point_target();
+        for (actual, expected) in [(kp, expected_kp), (kg, expected_kg)] {
+            let expected = builder.affine_to_projective(expected);
+            builder.curve_connect(actual, expected);
+        }
+        let data = builder.build::<C>();
+
+        let g = ProjectivePoint::generator();
+        let p_value = g.scalar_mul(&BigUint::from(F::rand().0));
+        let k = BigUint::from(F::rand().0);
+        let mut pw = PartialWitness::new();
+        set_affine_point_target(&mut pw, p, p_value.to_affine().unwrap());
+        for (i, &bit) in bits.iter().enumerate() {
+            pw.set_bool_target(bit, k.bit(i as u64));
+        }
+        let kp_value = p_value.scalar_mul(&k).to_affine().unwrap();
+        let kg_value = g.scalar_mul(&k).to_affine().unwrap();
+        set_affine_point_target(&mut pw, expected_kp, kp_value);
+        set_affine_point_target(&mut pw, expected_kg, kg_value);
+        let proof = data.prove(pw)?;
+        data.verify(proof)
+    }
+
+    #[test]
+    fn test_subgroup_check_serialization() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = GoldilocksField;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let p = builder.add_virtual_affine_point_target();
+        builder.curve_assert_in_subgroup(p);
+        let data = builder.build::<C>();
+
+        // The subgroup witness generator survives a round trip through the default serializers.
+        let gate_serializer = DefaultGateSerializer;
+        let generator_serializer = DefaultGeneratorSerializer::<C, D> {
+            _phantom: PhantomData,
+        };
+        let bytes = data
+            .to_bytes(&gate_serializer, &generator_serializer)
+            .map_err(anyhow::Error::msg)?;
+        let data =
+            CircuitData::<F, C, D>::from_bytes(&bytes, &gate_serializer, &generator_serializer)
+                .map_err(anyhow::Error::msg)?;
+
+        let p_value = ProjectivePoint::generator().scalar_mul(&BigUint::from(F::rand().0));
+        let mut pw = PartialWitness::new();
+        set_affine_point_target(&mut pw, p, p_value.to_affine().unwrap());
+        let proof = data.prove(pw)?;
+        data.verify(proof)
+    }
+}
@@ -0,0 +1,496 @@
+//! Signed fixed-point arithmetic within circuits, for quantized neural-network inference.
+//!
+//! A [`FixedPointTarget`] holds an integer `v` in `[-2^(total_bits - 1), 2^(total_bits - 1))`,
+//! representing `v / 2^frac_bits`. Every result is range checked, so an operation which overflows
+//! makes the instance unsatisfiable rather than wrapping around.
+//!
+//! Range checks split values into bytes, which are looked up in a single table shared by all
+//! checks. Many lookups fit in each `LookupGate`, so a range check costs a fraction of a row,
+//! rather than the full row of a bit decomposition.
+//!
+//! Products are rescaled by `2^frac_bits`, rounding to nearest with ties rounded up. Dot products
+//! sum the exact products and rescale once, so they're both cheaper and more precise than chains
+//! of multiplications and additions.
+
+use alloc::vec::Vec;
+
+use crate::field::extension::Extendable;
+use crate::hash::hash_types::RichField;
+use crate::iop::target::{BoolTarget, Target};
+use crate::iop::witness::{Witness, WitnessWrite};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::simple_generator;
+use crate::util::{ceil_div_usize, log2_ceil};
+
+/// The number of bits of the limbs which are looked up for range checks.
+const LIMB_BITS: usize = 8;
+
+fn limb_lookup(x: u16) -> u16 {
+    x
+}
+
+/// The representation of fixed-point numbers.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct FixedPointConfig {
+    /// The number of bits of the integers representing numbers, including the sign bit.
+    pub total_bits: usize,
+    /// The number of bits after the binary point.
+    pub frac_bits: usize,
+}
+
+impl FixedPointConfig {
+    pub fn new(total_bits: usize, frac_bits: usize) -> Self {
+        assert!(frac_bits < total_bits);
+        assert!(
+            total_bits <= 32,
+            "Products of more than 32-bit numbers may overflow the field"
+        );
+        Self {
+            total_bits,
+            frac_bits,
+        }
+    }
+
+    pub fn min_value(&self) -> i64 {
+        -(1 << (self.total_bits - 1))
+    }
+
+    pub fn max_value(&self) -> i64 {
+        (1 << (self.total_bits - 1)) - 1
+    }
+
+    /// Rescales the product of two numbers, or a sum of such products, as circuits do.
+    pub fn rescale(&self, product: i64) -> i64 {
+        if self.frac_bits == 0 {
+            return product;
+        }
+        (product + (1 << (self.frac_bits - 1))) >> self.frac_bits
+    }
+}
+
+/// A fixed-point number.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct FixedPointTarget {
+    /// The integer representing the number, as a field element.
+    pub value: Target,
+    pub config: FixedPointConfig,
+}
+
+/// Sets `target` to the number represented by the integer `value`.
+pub fn set_fixed_point_target<F: RichField, W: WitnessWrite<F>>(
+    witness: &mut W,
+    target: FixedPointTarget,
+    value: i64,
+) {
+    witness.set_target(target.value, F::from_noncanonical_i64(value));
+}
+
+/// The integer in `(-p/2, p/2)` congruent to `x`.
+fn to_signed<F: RichField>(x: F) -> i64 {
+    let x = x.to_canonical_u64();
+    if x > F::ORDER / 2 {
+        -((F::ORDER - x) as i64)
+    } else {
+        x as i64
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
+    /// Adds a fixed-point number, range checked to `config.total_bits` bits.
+    pub fn add_virtual_fixed_point_target(&mut self, config: FixedPointConfig) -> FixedPointTarget {
+        let value = self.add_virtual_target();
+        self.fixed_range_check(value, config);
+        FixedPointTarget { value, config }
+    }
+
+    /// The number represented by the integer `value`.
+    pub fn constant_fixed_point(
+        &mut self,
+        config: FixedPointConfig,
+        value: i64,
+    ) -> FixedPointTarget {
+        assert!(config.min_value() <= value && value <= config.max_value());
+        FixedPointTarget {
+            value: self.constant(F::from_noncanonical_i64(value)),
+            config,
+        }
+    }
+
+    pub fn fixed_add(&mut self, a: FixedPointTarget, b: FixedPointTarget) -> FixedPointTarget {
+        assert_eq!(a.config, b.config);
+        let value = self.add(a.value, b.value);
+        self.fixed_range_check(value, a.config);
+        FixedPointTarget {
+            value,
+            config: a.config,
+        }
+    }
+
+    pub fn fixed_sub(&mut self, a: FixedPointTarget, b: FixedPointTarget) -> FixedPointTarget {
+        assert_eq!(a.config, b.config);
+        let value = self.sub(a.value, b.value);
+        self.fixed_range_check(value, a.config);
+        FixedPointTarget {
+            value,
+            config: a.config,
+        }
+    }
+
+    pub fn fixed_mul(&mut self, a: FixedPointTarget, b: FixedPointTarget) -> FixedPointTarget {
+        assert_eq!(a.config, b.config);
+        let product = self.mul(a.value, b.value);
+        self.fixed_rescale(product, a.config)
+    }
+
+    /// Computes `max(x, 0)`.
+    pub fn fixed_relu(&mut self, x: FixedPointTarget) -> FixedPointTarget {
+        let nonnegative = self.fixed_is_nonnegative(x.value, x.config.total_bits);
+        FixedPointTarget {
+            value: self.mul(nonnegative.target, x.value),
+            config: x.config,
+        }
+    }
+
+    /// Clamps `x` to the numbers represented by `[min, max]`.
+    pub fn fixed_clamp(&mut self, x: FixedPointTarget, min: i64, max: i64) -> FixedPointTarget {
+        let config = x.config;
+        assert!(config.min_value() <= min && min <= max && max <= config.max_value());
+        let num_bits = config.total_bits + 1;
+
+        // `max(x, min) = min + relu(x - min)`, and `min(y, max) = max - relu(max - y)`.
+        let min_t = self.constant(F::from_noncanonical_i64(min));
+        let above_min = self.sub(x.value, min_t);
+        let nonnegative = self.fixed_is_nonnegative(above_min, num_bits);
+        let lower_clamped = self.mul_add(nonnegative.target, above_min, min_t);
+        let max_t = self.constant(F::from_noncanonical_i64(max));
+        let below_max = self.sub(max_t, lower_clamped);
+        let nonnegative = self.fixed_is_nonnegative(below_max, num_bits);
+        let excess = self.mul(nonnegative.target, below_max);
+        FixedPointTarget {
+            value: self.sub(max_t, excess),
+            config,
+        }
+    }
+
+    /// Computes the dot product of `a` and `b`.
+    pub fn fixed_dot(
+        &mut self,
+        a: &[FixedPointTarget],
+        b: &[FixedPointTarget],
+    ) -> FixedPointTarget {
+        assert_eq!(a.len(), b.len());
+        assert!(!a.is_empty());
+        let config = a[0].config;
+        assert!(
+            2 * (config.total_bits - 1) + log2_ceil(a.len()) <= 62,
+            "The sum of products may overflow the field"
+        );
+
+        let mut sum = self.zero();
+        for (x, y) in a.iter().zip(b) {
+            assert_eq!(x.config, config);
+            assert_eq!(y.config, config);
+            sum = self.mul_add(x.value, y.value, sum);
+        }
+        self.fixed_rescale(sum, config)
+    }
+
+    /// Computes the product of the matrices `a` and `b`, given as rows.
+    pub fn fixed_matmul(
+        &mut self,
+        a: &[Vec<FixedPointTarget>],
+        b: &[Vec<FixedPointTarget>],
+    ) -> Vec<Vec<FixedPointTarget>> {
+        let num_cols = b.first().map_or(0, |row| row.len());
+        let b_cols = (0..num_cols)
+            .map(|j| b.iter().map(|row| row[j]).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        a.iter()
+            .map(|row| b_cols.iter().map(|col| self.fixed_dot(row, col)).collect())
+            .collect()
+    }
+
+    /// Rescales `product`, a product of two numbers or a sum of such products, whose absolute
+    /// value must be at most `2^62`.
+    fn fixed_rescale(&mut self, product: Target, config: FixedPointConfig) -> FixedPointTarget {
+        if config.frac_bits == 0 {
+            self.fixed_range_check(product, config);
+            return FixedPointTarget {
+                value: product,
+                config,
+            };
+        }
+
+        let half = F::from_canonical_u64(1 << (config.frac_bits - 1));
+        let rounded = self.add_const(product, half);
+        let divisor = self.constant(F::from_canonical_u64(1 << config.frac_bits));
+        let quotient = self.add_virtual_target();
+        let remainder = self.add_virtual_target();
+        self.add_simple_generator(FloorDivGenerator {
+            x: rounded,
+            divisor,
+            quotient,
+            remainder,
+        });
+
+        // Both sides are below `2^63` in absolute value, so they're equal as integers.
+        self.fixed_range_check(quotient, config);
+        self.range_check_with_lookups(remainder, config.frac_bits);
+        let computed = self.mul_add(quotient, divisor, remainder);
+        self.connect(rounded, computed);
+        FixedPointTarget {
+            value: quotient,
+            config,
+        }
+    }
+
+    /// Whether `x` is nonnegative, checking that `-2^(num_bits - 1) <= x < 2^(num_bits - 1)`.
+    fn fixed_is_nonnegative(&mut self, x: Target, num_bits: usize) -> BoolTarget {
+        let nonnegative = self.add_virtual_bool_target_safe();
+        self.add_simple_generator(NonnegativeGenerator { x, nonnegative });
+        // Negative values are shifted into `[0, 2^(num_bits - 1))`, and nonnegative ones must
+        // already lie in it.
+        let shift = F::from_canonical_u64(1 << (num_bits - 1));
+        let shifted = self.mul_const_add(-shift, nonnegative.target, x);
+        let shifted = self.add_const(shifted, shift);
+        self.range_check_with_lookups(shifted, num_bits - 1);
+        nonnegative
+    }
+
+    fn fixed_range_check(&mut self, x: Target, config: FixedPointConfig) {
+        let shift = F::from_canonical_u64(1 << (config.total_bits - 1));
+        let shifted = self.add_const(x, shift);
+        self.range_check_with_lookups(shifted, config.total_bits);
+    }
+
+    /// Checks that `x < 2^num_bits`, by looking up its bytes.
+    fn range_check_with_lookups(&mut self, x: Target, num_bits: usize) {
+        if num_bits == 0 {
+            self.assert_zero(x);
+            return;
+        }
+
+        let num_limbs = ceil_div_usize(num_bits, LIMB_BITS);
+        let limbs = self.add_virtual_targets(num_limbs);
+        self.add_simple_generator(LimbsGenerator {
+            x,
+            limbs: limbs.clone(),
+        });
+
+        let inputs = (0..1 << LIMB_BITS).collect::<Vec<u16>>();
+        let table = self.add_lookup_table_from_fn(limb_lookup, &inputs);
+        for &limb in &limbs {
+            self.add_lookup_from_index(limb, table);
+        }
+        // The top limb holds the remaining bits, so must still be a limb when shifted up by the
+        // others.
+        let top_bits = num_bits - (num_limbs - 1) * LIMB_BITS;
+        if top_bits < LIMB_BITS {
+            let scale = F::from_canonical_u64(1 << (LIMB_BITS - top_bits));
+            let scaled = self.mul_const(scale, limbs[num_limbs - 1]);
+            self.add_lookup_from_index(scaled, table);
+        }
+
+        let limb_base = F::from_canonical_u64(1 << LIMB_BITS);
+        let computed = limbs.iter().rev().fold(self.zero(), |acc, &limb| {
+            self.mul_const_add(limb_base, acc, limb)
+        });
+        self.connect(x, computed);
+    }
+}
+
+simple_generator! {
+    #[derive(Debug, Default)]
+    pub struct LimbsGenerator {
+        inputs { x: Target }
+        outputs { limbs: Vec<Target> }
+    }
+    run_once |witness, out_buffer| {
+        let mut x = witness.get_target(*x).to_canonical_u64();
+        for &limb in limbs {
+            out_buffer.set_target(limb, F::from_canonical_u64(x % (1 << LIMB_BITS)));
+            x >>= LIMB_BITS;
+        }
+    }
+}
+
+simple_generator! {
+    #[derive(Debug, Default)]
+    pub struct FloorDivGenerator {
+        inputs { x: Target, divisor: Target }
+        outputs { quotient: Target, remainder: Target }
+    }
+    run_once |witness, out_buffer| {
+        let x = to_signed(witness.get_target(*x));
+        let divisor = to_signed(witness.get_target(*divisor));
+        out_buffer.set_target(*quotient, F::from_noncanonical_i64(x.div_euclid(divisor)));
+        out_buffer.set_target(*remainder, F::from_noncanonical_i64(x.rem_euclid(divisor)));
+    }
+}
+
+simple_generator! {
+    #[derive(Debug, Default)]
+    pub struct NonnegativeGenerator {
+        inputs { x: Target }
+        outputs { nonnegative: BoolTarget }
+    }
+    run_once |witness, out_buffer| {
+        let x = to_signed(witness.get_target(*x));
+        out_buffer.set_bool_target(*nonnegative, x >= 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::marker::PhantomData;
+
+    use anyhow::Result;
+    use rand::rngs::OsRng;
+    use rand::Rng;
+
+    use super::*;
+    use crate::field::types::Field;
+    use crate::iop::witness::PartialWitness;
+    use crate::plonk::circuit_data::{CircuitConfig, CircuitData};
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::util::serialization::{DefaultGateSerializer, DefaultGeneratorSerializer};
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    #[test]
+    fn test_fixed_point() -> Result<()> {
+        let config = FixedPointConfig::new(16, 8);
+        // Small enough that none of the results below overflow.
+        let sample = || OsRng.gen_range(-(1 << 10)..1 << 10);
+        let (a, b) = (sample(), sample());
+        let (min, max) = (-300, 200);
+        let xs = [(); 4].map(|_| sample());
+        let ys = [(); 4].map(|_| sample());
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let a_t = builder.add_virtual_fixed_point_target(config);
+        let b_t = builder.add_virtual_fixed_point_target(config);
+        let xs_t = xs.map(|_| builder.add_virtual_fixed_point_target(config));
+        let ys_t = ys.map(|_| builder.add_virtual_fixed_point_target(config));
+        let outputs = [
+            builder.fixed_add(a_t, b_t),
+            builder.fixed_sub(a_t, b_t),
+            builder.fixed_mul(a_t, b_t),
+            builder.fixed_relu(a_t),
+            builder.fixed_relu(b_t),
+            builder.fixed_clamp(a_t, min, max),
+            builder.fixed_clamp(b_t, min, max),
+            builder.fixed_dot(&xs_t, &ys_t),
+        ];
+        for output in outputs {
+            builder.register_public_input(output.value);
+        }
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        set_fixed_point_target(&mut pw, a_t, a);
+        set_fixed_point_target(&mut pw, b_t, b);
+        for (&t, &x) in xs_t.iter().zip(&xs).chain(ys_t.iter().zip(&ys)) {
+            set_fixed_point_target(&mut pw, t, x);
+        }
+        let proof = data.prove(pw)?;
+
+        let dot = xs.iter().zip(&ys).map(|(x, y)| x * y).sum();
+        let expected = [
+            a + b,
+            a - b,
+            config.rescale(a * b),
+            a.max(0),
+            b.max(0),
+            a.clamp(min, max),
+            b.clamp(min, max),
+            config.rescale(dot),
+        ]
+        .map(F::from_noncanonical_i64);
+        assert_eq!(proof.public_inputs, expected);
+        data.verify(proof)
+    }
+
+    #[test]
+    fn test_fixed_point_serialization() -> Result<()> {
+        let config = FixedPointConfig::new(16, 8);
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let a_t = builder.add_virtual_fixed_point_target(config);
+        let b_t = builder.add_virtual_fixed_point_target(config);
+        let product = builder.fixed_mul(a_t, b_t);
+        let relu = builder.fixed_relu(product);
+        builder.register_public_input(relu.value);
+        let data = builder.build::<C>();
+
+        // The limb, floor division and sign generators survive a round trip through the default
+        // serializers.
+        let gate_serializer = DefaultGateSerializer;
+        let generator_serializer = DefaultGeneratorSerializer::<C, D> {
+            _phantom: PhantomData,
+        };
+        let bytes = data
+            .to_bytes(&gate_serializer, &generator_serializer)
+            .map_err(anyhow::Error::msg)?;
+        let data =
+            CircuitData::<F, C, D>::from_bytes(&bytes, &gate_serializer, &generator_serializer)
+                .map_err(anyhow::Error::msg)?;
+
+        for (a, b) in [(300, 500), (-300, 500)] {
+            let mut pw = PartialWitness::new();
+            set_fixed_point_target(&mut pw, a_t, a);
+            set_fixed_point_target(&mut pw, b_t, b);
+            let proof = data.prove(pw)?;
+            let expected = config.rescale(a * b).max(0);
+            assert_eq!(proof.public_inputs, [F::from_noncanonical_i64(expected)]);
+            data.verify(proof)?;
+        }
+        Ok(())
+    }
+
+    fn constant_matrix<const N: usize>(
+        builder: &mut CircuitBuilder<F, D>,
+        config: FixedPointConfig,
+        m: &[[i64; N]],
+    ) -> Vec<Vec<FixedPointTarget>> {
+        m.iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&x| builder.constant_fixed_point(config, x))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fixed_matmul() -> Result<()> {
+        let config = FixedPointConfig::new(12, 4);
+        let a = [[3, -7, 20], [-16, 5, 1]];
+        let b = [[1, 40], [-12, 9], [33, -2]];
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let a_t = constant_matrix(&mut builder, config, &a);
+        let b_t = constant_matrix(&mut builder, config, &b);
+        let c_t = builder.fixed_matmul(&a_t, &b_t);
+        for row in &c_t {
+            for x in row {
+                builder.register_public_input(x.value);
+            }
+        }
+        let data = builder.build::<C>();
+        let proof = data.prove(PartialWitness::new())?;
+
+        let expected = a
+            .iter()
+            .flat_map(|row| {
+                (0..2).map(move |j| {
+                    let dot = (0..3).map(|k| row[k] * b[k][j]).sum();
+                    F::from_noncanonical_i64(config.rescale(dot))
+                })
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(proof.public_inputs, expected);
+        data.verify(proof)
+    }
+}
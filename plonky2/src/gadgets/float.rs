@@ -0,0 +1,557 @@
+//! Emulation of IEEE 754 single-precision floats within circuits, e.g. to prove the inference of
+//! models trained with `f32` weights.
+//!
+//! An [`F32Target`] holds the bits of an `f32`, as returned by `f32::to_bits`. Arithmetic rounds to
+//! nearest, ties to even, and handles signed zeros, subnormals and infinities as hardware does.
+//! NaN results are always the quiet NaN [`CANONICAL_NAN`], so NaN payloads aren't propagated.
+//!
+//! Operations unpack their operands into a sign, an exponent and an integer significand, compute
+//! the exact result as a scaled integer, and round it by splitting off the bits below the result's
+//! precision, which the prover supplies.
+
+use alloc::vec::Vec;
+
+use crate::field::extension::Extendable;
+use crate::hash::hash_types::RichField;
+use crate::iop::target::{BoolTarget, Target};
+use crate::iop::witness::{Witness, WitnessWrite};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::simple_generator;
+
+/// The bits of the NaN returned by operations with NaN results.
+pub const CANONICAL_NAN: u32 = 0x7FC0_0000;
+
+/// The bits of positive infinity.
+const INFINITY: u32 = 0x7F80_0000;
+
+/// Added to the biased exponents of intermediate results, which lie in `(-512, 512)`, to compare
+/// them as non-negative integers of `EXP_BITS` bits.
+const EXP_OFFSET: u64 = 512;
+const EXP_BITS: usize = 11;
+
+/// The bits of an `f32`.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct F32Target(pub Target);
+
+pub fn set_f32_target<F: RichField, W: WitnessWrite<F>>(
+    witness: &mut W,
+    target: F32Target,
+    value: f32,
+) {
+    witness.set_target(target.0, F::from_canonical_u32(value.to_bits()));
+}
+
+/// The parts of an `f32`, whose value is `(-1)^sign * significand * 2^(exp - 150)` if it's finite.
+struct UnpackedF32 {
+    sign: BoolTarget,
+    /// The bits other than the sign, which order finite values by magnitude.
+    abs: Target,
+    /// The biased exponent, or 1 for subnormals.
+    exp: Target,
+    /// The fraction, with the implicit leading bit of normal values.
+    significand: Target,
+    is_zero: BoolTarget,
+    is_inf: BoolTarget,
+    is_nan: BoolTarget,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
+    pub fn add_virtual_f32_target(&mut self) -> F32Target {
+        F32Target(self.add_virtual_target())
+    }
+
+    pub fn constant_f32(&mut self, x: f32) -> F32Target {
+        F32Target(self.constant(F::from_canonical_u32(x.to_bits())))
+    }
+
+    /// Computes `-x`.
+    pub fn f32_neg(&mut self, x: F32Target) -> F32Target {
+        let sign = self.split_le(x.0, 32)[31];
+        // Flipping the sign bit adds `2^31` if it's unset, and subtracts it otherwise.
+        let cleared = self.mul_const_add(-F::from_canonical_u64(1 << 32), sign.target, x.0);
+        F32Target(self.add_const(cleared, F::from_canonical_u64(1 << 31)))
+    }
+
+    /// Computes `a + b`.
+    pub fn f32_add(&mut self, a: F32Target, b: F32Target) -> F32Target {
+        let a = self.f32_unpack(a);
+        let b = self.f32_unpack(b);
+        // Order the operands so that `x` has the larger magnitude, and sets the exponent.
+        let swap = self.f32_less_than(a.abs, b.abs, 31);
+        let x = self.f32_select_unpacked(swap, &b, &a);
+        let y = self.f32_select_unpacked(swap, &a, &b);
+
+        // Scale `x`'s significand by `2^27`, and `y`'s by `2^(26 - d)` where `d` is the exponent
+        // difference, then double it and set the lowest bit if any bits were shifted out. This
+        // sticky bit lies below the rounding position, so it rounds as the bits it replaces would.
+        // Past `d = 50`, only the sticky bit is left.
+        let d = self.sub(x.exp, y.exp);
+        let max_d = self.constant(F::from_canonical_u32(50));
+        let d_small = self.f32_less_than(d, max_d, 8);
+        let d = self.select(d_small, d, max_d);
+        let twenty_six = self.constant(F::from_canonical_u32(26));
+        let shift_left = self.f32_less_than(d, twenty_six, 6);
+        let shift_right = self.not(shift_left);
+        let left_amount = self.sub(twenty_six, d);
+        let left_amount = self.mul(shift_left.target, left_amount);
+        let right_amount = self.sub(d, twenty_six);
+        let right_amount = self.mul(shift_right.target, right_amount);
+        let left_pow = self.f32_pow2(left_amount, 5);
+        let right_pow = self.f32_pow2(right_amount, 5);
+        let (y_high, y_low) = self.f32_div_rem(y.significand, right_pow, 24);
+        self.range_check(y_high, 24);
+        let zero = self.zero();
+        let exact = self.is_equal(y_low, zero);
+        let sticky = self.not(exact);
+        let y_aligned = self.mul(y_high, left_pow);
+        let y_aligned = self.mul_const_add(F::TWO, y_aligned, sticky.target);
+        let x_aligned = self.mul_const(F::from_canonical_u64(1 << 27), x.significand);
+
+        let effective_sub = self.f32_xor(x.sign, y.sign);
+        let sum = self.add(x_aligned, y_aligned);
+        let difference = self.sub(x_aligned, y_aligned);
+        let total = self.select(effective_sub, difference, sum);
+        let (total, leading_zeros, nonzero) = self.f32_normalize(total, 52);
+        // An exact zero is positive, unless both operands are negative zeros.
+        let is_zero = self.not(nonzero);
+        let cancelled = self.and(effective_sub, is_zero);
+        let not_cancelled = self.not(cancelled);
+        let sign = self.and(x.sign, not_cancelled);
+        let exp = self.sub(x.exp, leading_zeros);
+        let exp = self.add_const(exp, F::ONE);
+        let result = self.f32_round_pack(sign, total, 52, exp, nonzero);
+
+        let any_inf = self.or(x.is_inf, y.is_inf);
+        let infinity = self.f32_infinity(x.sign);
+        let result = self.select(any_inf, infinity, result.0);
+        let both_inf = self.and(x.is_inf, y.is_inf);
+        let inf_minus_inf = self.and(both_inf, effective_sub);
+        let any_nan = self.or(x.is_nan, y.is_nan);
+        let is_nan = self.or(any_nan, inf_minus_inf);
+        let nan = self.constant(F::from_canonical_u32(CANONICAL_NAN));
+        F32Target(self.select(is_nan, nan, result))
+    }
+
+    /// Computes `a - b`.
+    pub fn f32_sub(&mut self, a: F32Target, b: F32Target) -> F32Target {
+        let neg_b = self.f32_neg(b);
+        self.f32_add(a, neg_b)
+    }
+
+    /// Computes `a * b`.
+    pub fn f32_mul(&mut self, a: F32Target, b: F32Target) -> F32Target {
+        let a = self.f32_unpack(a);
+        let b = self.f32_unpack(b);
+        let sign = self.f32_xor(a.sign, b.sign);
+        let product = self.mul(a.significand, b.significand);
+        let (product, leading_zeros, nonzero) = self.f32_normalize(product, 48);
+        let exp = self.add(a.exp, b.exp);
+        let exp = self.sub(exp, leading_zeros);
+        let exp = self.add_const(exp, -F::from_canonical_u32(126));
+        let result = self.f32_round_pack(sign, product, 48, exp, nonzero);
+
+        let any_inf = self.or(a.is_inf, b.is_inf);
+        let infinity = self.f32_infinity(sign);
+        let result = self.select(any_inf, infinity, result.0);
+        let inf_times_zero = self.and(a.is_inf, b.is_zero);
+        let zero_times_inf = self.and(a.is_zero, b.is_inf);
+        let invalid = self.or(inf_times_zero, zero_times_inf);
+        let any_nan = self.or(a.is_nan, b.is_nan);
+        let is_nan = self.or(any_nan, invalid);
+        let nan = self.constant(F::from_canonical_u32(CANONICAL_NAN));
+        F32Target(self.select(is_nan, nan, result))
+    }
+
+    /// Whether `a == b`. Zeros of either sign are equal, and NaN is unequal to everything.
+    pub fn f32_eq(&mut self, a: F32Target, b: F32Target) -> BoolTarget {
+        let (a_key, b_key, ordered) = self.f32_order_keys(a, b);
+        let equal = self.is_equal(a_key, b_key);
+        self.and(equal, ordered)
+    }
+
+    /// Whether `a < b`. This is false if either is NaN.
+    pub fn f32_lt(&mut self, a: F32Target, b: F32Target) -> BoolTarget {
+        let (a_key, b_key, ordered) = self.f32_order_keys(a, b);
+        let less = self.f32_less_than(a_key, b_key, 33);
+        self.and(less, ordered)
+    }
+
+    /// Whether `a <= b`. This is false if either is NaN.
+    pub fn f32_le(&mut self, a: F32Target, b: F32Target) -> BoolTarget {
+        let (a_key, b_key, ordered) = self.f32_order_keys(a, b);
+        let greater = self.f32_less_than(b_key, a_key, 33);
+        let not_greater = self.not(greater);
+        self.and(not_greater, ordered)
+    }
+
+    fn f32_unpack(&mut self, x: F32Target) -> UnpackedF32 {
+        let bits = self.split_le(x.0, 32);
+        let fraction = self.le_sum(bits[..23].iter());
+        let exp_field = self.le_sum(bits[23..31].iter());
+        let abs = self.mul_const_add(F::from_canonical_u64(1 << 23), exp_field, fraction);
+
+        let zero = self.zero();
+        let exp_zero = self.is_equal(exp_field, zero);
+        let normal = self.not(exp_zero);
+        let max_exp = self.constant(F::from_canonical_u32(255));
+        let exp_max = self.is_equal(exp_field, max_exp);
+        let fraction_zero = self.is_equal(fraction, zero);
+        let fraction_nonzero = self.not(fraction_zero);
+        UnpackedF32 {
+            sign: bits[31],
+            abs,
+            exp: self.add(exp_field, exp_zero.target),
+            significand: self.mul_const_add(
+                F::from_canonical_u64(1 << 23),
+                normal.target,
+                fraction,
+            ),
+            is_zero: self.and(exp_zero, fraction_zero),
+            is_inf: self.and(exp_max, fraction_zero),
+            is_nan: self.and(exp_max, fraction_nonzero),
+        }
+    }
+
+    fn f32_select_unpacked(
+        &mut self,
+        b: BoolTarget,
+        x: &UnpackedF32,
+        y: &UnpackedF32,
+    ) -> UnpackedF32 {
+        let mut select_bool = |x: BoolTarget, y: BoolTarget| {
+            BoolTarget::new_unsafe(self.select(b, x.target, y.target))
+        };
+        let sign = select_bool(x.sign, y.sign);
+        let is_zero = select_bool(x.is_zero, y.is_zero);
+        let is_inf = select_bool(x.is_inf, y.is_inf);
+        let is_nan = select_bool(x.is_nan, y.is_nan);
+        UnpackedF32 {
+            sign,
+            abs: self.select(b, x.abs, y.abs),
+            exp: self.select(b, x.exp, y.exp),
+            significand: self.select(b, x.significand, y.significand),
+            is_zero,
+            is_inf,
+            is_nan,
+        }
+    }
+
+    /// Keys in `[1, 2^32]` ordering `a` and `b` as floats, and whether neither is NaN.
+    fn f32_order_keys(&mut self, a: F32Target, b: F32Target) -> (Target, Target, BoolTarget) {
+        let a = self.f32_unpack(a);
+        let b = self.f32_unpack(b);
+        let mut key = |x: &UnpackedF32| {
+            // `2^31 + abs` for positive values, and `2^31 - abs` for negative ones.
+            let signed_abs = self.arithmetic(-F::TWO, F::ONE, x.sign.target, x.abs, x.abs);
+            self.add_const(signed_abs, F::from_canonical_u64(1 << 31))
+        };
+        let a_key = key(&a);
+        let b_key = key(&b);
+        let either_nan = self.or(a.is_nan, b.is_nan);
+        (a_key, b_key, self.not(either_nan))
+    }
+
+    fn f32_infinity(&mut self, sign: BoolTarget) -> Target {
+        let infinity = self.constant(F::from_canonical_u32(INFINITY));
+        self.mul_const_add(F::from_canonical_u64(1 << 31), sign.target, infinity)
+    }
+
+    fn f32_xor(&mut self, x: BoolTarget, y: BoolTarget) -> BoolTarget {
+        let sum = self.add(x.target, y.target);
+        BoolTarget::new_unsafe(self.arithmetic(-F::TWO, F::ONE, x.target, y.target, sum))
+    }
+
+    /// Whether `a < b`, for `a, b < 2^num_bits`.
+    fn f32_less_than(&mut self, a: Target, b: Target, num_bits: usize) -> BoolTarget {
+        // `a - b + 2^num_bits` is in `[1, 2^(num_bits + 1))`, and has its top bit set iff `a >= b`.
+        let difference = self.sub(a, b);
+        let shifted = self.add_const(difference, F::from_canonical_u64(1 << num_bits));
+        let bits = self.split_le(shifted, num_bits + 1);
+        self.not(bits[num_bits])
+    }
+
+    /// Computes `2^x`, for `x < 2^num_bits`.
+    fn f32_pow2(&mut self, x: Target, num_bits: usize) -> Target {
+        let bits = self.split_le(x, num_bits);
+        self.exp_from_bits_const_base(F::TWO, bits)
+    }
+
+    /// Divides `x` by `divisor <= 2^num_bits`, returning the quotient and remainder. The caller
+    /// must range check the quotient so that `quotient * divisor + remainder` can't overflow.
+    fn f32_div_rem(&mut self, x: Target, divisor: Target, num_bits: usize) -> (Target, Target) {
+        let quotient = self.add_virtual_target();
+        let remainder = self.add_virtual_target();
+        self.add_simple_generator(DivRemGenerator {
+            x,
+            divisor,
+            quotient,
+            remainder,
+        });
+
+        self.range_check(remainder, num_bits);
+        let slack = self.sub(divisor, remainder);
+        let slack = self.add_const(slack, F::NEG_ONE);
+        self.range_check(slack, num_bits);
+        let computed_x = self.mul_add(quotient, divisor, remainder);
+        self.connect(x, computed_x);
+        (quotient, remainder)
+    }
+
+    /// Shifts `x < 2^num_bits` left until its top bit is set, returning the shifted value, the
+    /// shift, and whether `x` is nonzero.
+    fn f32_normalize(&mut self, x: Target, num_bits: usize) -> (Target, Target, BoolTarget) {
+        let bits = self.split_le(x, num_bits);
+        // `leading[i]` is whether the top `i + 1` bits are all zeros.
+        let mut leading = Vec::with_capacity(num_bits);
+        let mut all_zeros = self._true();
+        for bit in bits.into_iter().rev() {
+            all_zeros = BoolTarget::new_unsafe(self.arithmetic(
+                F::NEG_ONE,
+                F::ONE,
+                all_zeros.target,
+                bit.target,
+                all_zeros.target,
+            ));
+            leading.push(all_zeros);
+        }
+        let num_leading_zeros = self.add_many(leading.iter().map(|b| b.target));
+        // The flags are set for a prefix of `leading`, so `2^num_leading_zeros` is one more than
+        // the integer with these little-endian bits.
+        let pow = self.le_sum(leading.iter());
+        let pow = self.add_const(pow, F::ONE);
+        let normalized = self.mul(x, pow);
+        let nonzero = self.not(leading[num_bits - 1]);
+        (normalized, num_leading_zeros, nonzero)
+    }
+
+    /// Rounds `significand * 2^(exp - num_bits - 126)` to an `f32` with the given sign, where
+    /// `significand < 2^num_bits` has its top bit set if `nonzero`, and is zero otherwise. `exp`,
+    /// the biased exponent of the result if it's normal and doesn't round up to the next power of
+    /// two, must be in `(-512, 512)`.
+    fn f32_round_pack(
+        &mut self,
+        sign: BoolTarget,
+        significand: Target,
+        num_bits: usize,
+        exp: Target,
+        nonzero: BoolTarget,
+    ) -> F32Target {
+        let offset = F::from_canonical_u64(EXP_OFFSET);
+
+        // Subnormal results have the exponent of the smallest normals, and lose `1 - exp` more
+        // bits. Past 25, the significand is below half the smallest subnormal, so rounds to zero.
+        let exp_offset = self.add_const(exp, offset);
+        let min_normal = self.constant(offset + F::ONE);
+        let subnormal = self.f32_less_than(exp_offset, min_normal, EXP_BITS);
+        let one = self.one();
+        let deficit = self.sub(one, exp);
+        let deficit_offset = self.add_const(deficit, offset);
+        let max_deficit = self.constant(offset + F::from_canonical_u32(25));
+        let deficit_small = self.f32_less_than(deficit_offset, max_deficit, EXP_BITS);
+        let twenty_five = self.constant(F::from_canonical_u32(25));
+        let deficit = self.select(deficit_small, deficit, twenty_five);
+        let extra_shift = self.mul(subnormal.target, deficit);
+
+        // Shift right by `num_bits - 24 + extra_shift`. The quotient has at most
+        // `24 - extra_shift` bits, and checking this bounds `quotient * divisor` by `2^num_bits`.
+        let extra_pow = self.f32_pow2(extra_shift, 5);
+        let divisor = self.mul_const(F::from_canonical_u64(1 << (num_bits - 24)), extra_pow);
+        let (quotient, remainder) = self.f32_div_rem(significand, divisor, num_bits + 1);
+        let quotient_bits = self.split_le(quotient, 24);
+        let quotient_scaled = self.mul(quotient, extra_pow);
+        self.range_check(quotient_scaled, 24);
+
+        // Round to nearest, ties to even.
+        let half = self.mul_const(F::from_canonical_u64(1 << (num_bits - 25)), extra_pow);
+        let above_half = self.f32_less_than(half, remainder, num_bits + 1);
+        let at_half = self.is_equal(remainder, half);
+        let odd_tie = self.and(at_half, quotient_bits[0]);
+        let round_up = self.or(above_half, odd_tie);
+        let rounded = self.add(quotient, round_up.target);
+
+        // The exponent field holds `exp - 1` plus the implicit bit, so a significand rounded up to
+        // `2^24` carries into it. Subnormals have an exponent field of zero.
+        let normal = self.not(subnormal);
+        let exp_minus_one = self.add_const(exp, F::NEG_ONE);
+        let exp_field = self.mul(normal.target, exp_minus_one);
+        let abs = self.mul_const_add(F::from_canonical_u64(1 << 23), exp_field, rounded);
+        let abs = self.mul(nonzero.target, abs);
+        let infinity = self.constant(F::from_canonical_u32(INFINITY));
+        let finite = self.f32_less_than(abs, infinity, 32);
+        let abs = self.select(finite, abs, infinity);
+        F32Target(self.mul_const_add(F::from_canonical_u64(1 << 31), sign.target, abs))
+    }
+}
+
+simple_generator! {
+    #[derive(Debug, Default)]
+    pub struct DivRemGenerator {
+        inputs { x: Target, divisor: Target }
+        outputs { quotient: Target, remainder: Target }
+    }
+    run_once |witness, out_buffer| {
+        let x = witness.get_target(*x).to_canonical_u64();
+        let divisor = witness.get_target(*divisor).to_canonical_u64();
+        out_buffer.set_target(*quotient, F::from_canonical_u64(x / divisor));
+        out_buffer.set_target(*remainder, F::from_canonical_u64(x % divisor));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::marker::PhantomData;
+
+    use anyhow::Result;
+    use rand::rngs::OsRng;
+    use rand::Rng;
+
+    use super::*;
+    use crate::field::types::Field;
+    use crate::iop::witness::PartialWitness;
+    use crate::plonk::circuit_data::{CircuitConfig, CircuitData};
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::util::serialization::{DefaultGateSerializer, DefaultGeneratorSerializer};
+
+    /// Samples a float, favoring subnormals, values near one and values near overflow.
+    fn sample_f32<R: Rng>(rng: &mut R) -> f32 {
+        let exp = match rng.gen_range(0..4) {
+            0 => rng.gen_range(0..24),
+            1 => rng.gen_range(110..145),
+            2 => rng.gen_range(230..256),
+            _ => rng.gen_range(0..256),
+        };
+        let sign = rng.gen::<u32>() & (1 << 31);
+        f32::from_bits(sign | (exp << 23) | (rng.gen::<u32>() >> 9))
+    }
+
+    fn expected_bits(x: f32) -> u32 {
+        if x.is_nan() {
+            CANONICAL_NAN
+        } else {
+            x.to_bits()
+        }
+    }
+
+    #[test]
+    fn test_f32_against_hardware() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let specials = [
+            0.0,
+            -0.0,
+            1.0,
+            -1.5,
+            1.0 + f32::EPSILON,
+            f32::MIN_POSITIVE,
+            f32::from_bits(1),
+            -f32::from_bits(0x007F_FFFF),
+            f32::MAX,
+            f32::INFINITY,
+            f32::NEG_INFINITY,
+            f32::NAN,
+        ];
+        let mut pairs = Vec::new();
+        for (i, &x) in specials.iter().enumerate() {
+            pairs.push((x, -x));
+            pairs.push((x, specials[(i + 1) % specials.len()]));
+            pairs.push((x, specials[(i + 5) % specials.len()]));
+        }
+        for _ in 0..32 {
+            let x = sample_f32(&mut OsRng);
+            // Pair some values with their near negations, for additions that cancel.
+            let y = if OsRng.gen() {
+                sample_f32(&mut OsRng)
+            } else {
+                f32::from_bits((-x).to_bits() ^ OsRng.gen_range(0..4))
+            };
+            pairs.push((x, y));
+        }
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let targets = pairs
+            .iter()
+            .map(|_| {
+                (
+                    builder.add_virtual_f32_target(),
+                    builder.add_virtual_f32_target(),
+                )
+            })
+            .collect::<Vec<_>>();
+        for &(x, y) in &targets {
+            let sum = builder.f32_add(x, y);
+            let difference = builder.f32_sub(x, y);
+            let product = builder.f32_mul(x, y);
+            let eq = builder.f32_eq(x, y);
+            let lt = builder.f32_lt(x, y);
+            let le = builder.f32_le(x, y);
+            builder.register_public_inputs(&[sum.0, difference.0, product.0]);
+            builder.register_public_inputs(&[eq.target, lt.target, le.target]);
+        }
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        let mut expected = Vec::new();
+        for (&(x_t, y_t), &(x, y)) in targets.iter().zip(&pairs) {
+            set_f32_target(&mut pw, x_t, x);
+            set_f32_target(&mut pw, y_t, y);
+            expected.extend(
+                [x + y, x - y, x * y]
+                    .map(|z| F::from_canonical_u32(expected_bits(z)))
+                    .into_iter()
+                    .chain([x == y, x < y, x <= y].map(F::from_bool)),
+            );
+        }
+        let proof = data.prove(pw)?;
+        for (i, (&actual, &expected)) in proof.public_inputs.iter().zip(&expected).enumerate() {
+            let (x, y) = pairs[i / 6];
+            assert_eq!(
+                actual,
+                expected,
+                "operation {} on {:?} and {:?}",
+                i % 6,
+                x,
+                y
+            );
+        }
+        data.verify(proof)
+    }
+
+    #[test]
+    fn test_f32_serialization() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_f32_target();
+        let y = builder.add_virtual_f32_target();
+        let product = builder.f32_mul(x, y);
+        builder.register_public_input(product.0);
+        let data = builder.build::<C>();
+
+        // The circuit's `DivRemGenerator`s survive a round trip through the default serializers.
+        let gate_serializer = DefaultGateSerializer;
+        let generator_serializer = DefaultGeneratorSerializer::<C, D> {
+            _phantom: PhantomData,
+        };
+        let bytes = data
+            .to_bytes(&gate_serializer, &generator_serializer)
+            .map_err(anyhow::Error::msg)?;
+        let data =
+            CircuitData::<F, C, D>::from_bytes(&bytes, &gate_serializer, &generator_serializer)
+                .map_err(anyhow::Error::msg)?;
+
+        let mut pw = PartialWitness::new();
+        set_f32_target(&mut pw, x, 1.5);
+        set_f32_target(&mut pw, y, -2.25);
+        let proof = data.prove(pw)?;
+        assert_eq!(
+            proof.public_inputs,
+            [F::from_canonical_u32((1.5f32 * -2.25).to_bits())]
+        );
+        data.verify(proof)
+    }
+}
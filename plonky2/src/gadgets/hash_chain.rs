@@ -0,0 +1,126 @@
+//! Running hash chains, for statements like "this is the digest of these `n` events".
+//!
+//! The chain over items `x_0, ..., x_{n-1}` starts from the zero hash and absorbs one item at a
+//! time, `acc_{i+1} = H(acc_i || x_i)`, so a chain can be extended without knowing the items
+//! absorbed so far. [`hash_chain`] computes it outside of circuits, and
+//! [`CircuitBuilder::hash_chain`] within them, over a variable number of items.
+
+use alloc::vec::Vec;
+
+use crate::field::extension::Extendable;
+use crate::hash::hash_types::{HashOut, HashOutTarget, RichField};
+use crate::iop::target::Target;
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::config::{AlgebraicHasher, Hasher};
+
+/// Absorbs `item` into the chain whose current digest is `acc`.
+pub fn hash_chain_step<F: RichField, H: Hasher<F, Hash = HashOut<F>>>(
+    acc: HashOut<F>,
+    item: &[F],
+) -> HashOut<F> {
+    let mut inputs = acc.elements.to_vec();
+    inputs.extend_from_slice(item);
+    H::hash_no_pad(&inputs)
+}
+
+/// The digest of the chain over `items`.
+pub fn hash_chain<F: RichField, H: Hasher<F, Hash = HashOut<F>>>(items: &[Vec<F>]) -> HashOut<F> {
+    items.iter().fold(HashOut::ZERO, |acc, item| {
+        hash_chain_step::<F, H>(acc, item)
+    })
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
+    /// Absorbs `item` into the chain whose current digest is `acc`.
+    pub fn hash_chain_step<H: AlgebraicHasher<F>>(
+        &mut self,
+        acc: HashOutTarget,
+        item: &[Target],
+    ) -> HashOutTarget {
+        let mut inputs = acc.elements.to_vec();
+        inputs.extend_from_slice(item);
+        self.hash_n_to_hash_no_pad::<H>(inputs)
+    }
+
+    /// The digest of the chain over the first `count` of `items`. Results in an unsatisfiable
+    /// instance if `count > items.len()`. Every item is hashed regardless of `count`, so the cost
+    /// is that of a chain over all of `items`.
+    pub fn hash_chain<H: AlgebraicHasher<F>>(
+        &mut self,
+        items: &[Vec<Target>],
+        count: Target,
+    ) -> HashOutTarget {
+        let zero = self.zero();
+        let mut acc = HashOutTarget::from_partial(&[], zero);
+        let mut active = self._true();
+        for (i, item) in items.iter().enumerate() {
+            let i = self.constant(F::from_canonical_usize(i));
+            let done = self.is_equal(count, i);
+            let not_done = self.not(done);
+            active = self.and(active, not_done);
+            let next = self.hash_chain_step::<H>(acc, item);
+            acc = self.select_hash(active, next, acc);
+        }
+
+        // If `count` matched none of `0..items.len()`, it must be `items.len()`.
+        let len = self.constant(F::from_canonical_usize(items.len()));
+        let at_end = self.is_equal(count, len);
+        let not_at_end = self.not(at_end);
+        let out_of_range = self.and(active, not_at_end);
+        self.assert_zero(out_of_range.target);
+
+        acc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::field::types::{Field, Sample};
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    #[test]
+    fn test_hash_chain() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type H = <C as GenericConfig<D>>::Hasher;
+
+        const MAX_ITEMS: usize = 4;
+        const ITEM_LEN: usize = 3;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let items = (0..MAX_ITEMS)
+            .map(|_| builder.add_virtual_targets(ITEM_LEN))
+            .collect::<Vec<_>>();
+        let count = builder.add_virtual_target();
+        let digest = builder.hash_chain::<H>(&items, count);
+        builder.register_public_inputs(&digest.elements);
+        let data = builder.build::<C>();
+
+        let values = (0..MAX_ITEMS)
+            .map(|_| F::rand_vec(ITEM_LEN))
+            .collect::<Vec<_>>();
+        let prove = |n: usize| {
+            let mut pw = PartialWitness::new();
+            for (item, value) in items.iter().zip(&values) {
+                pw.set_target_arr(item, value);
+            }
+            pw.set_target(count, F::from_canonical_usize(n));
+            data.prove(pw)
+        };
+
+        for n in [0, 2, MAX_ITEMS] {
+            let proof = prove(n)?;
+            let expected = hash_chain::<F, H>(&values[..n]);
+            assert_eq!(proof.public_inputs, expected.elements);
+            data.verify(proof)?;
+        }
+        Ok(())
+    }
+}
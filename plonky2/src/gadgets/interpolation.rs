@@ -60,46 +60,49 @@ mod tests {
         let pw = PartialWitness::new();
         let mut builder = CircuitBuilder::<F, D>::new(config);
 
-        let subgroup_bits = 2;
-        let len = 1 << subgroup_bits;
-        let coset_shift = F::rand();
-        let g = F::primitive_root_of_unity(subgroup_bits);
-        let points = F::cyclic_subgroup_coset_known_order(g, coset_shift, len);
-        let values = FF::rand_vec(len);
-
-        let homogeneous_points = points
-            .iter()
-            .zip(values.iter())
-            .map(|(&a, &b)| (<FF as FieldExtension<D>>::from_basefield(a), b))
-            .collect::<Vec<_>>();
-
-        let true_interpolant = interpolant(&homogeneous_points);
-
-        let z = FF::rand();
-        let true_eval = true_interpolant.eval(z);
-
-        let coset_shift_target = builder.constant(coset_shift);
-
-        let value_targets = values
-            .iter()
-            .map(|&v| (builder.constant_extension(v)))
-            .collect::<Vec<_>>();
-
-        let zt = builder.constant_extension(z);
-
-        let evals_coset_gates = (2..=4)
-            .map(|max_degree| {
-                builder.interpolate_coset(
-                    CosetInterpolationGate::with_max_degree(subgroup_bits, max_degree),
-                    coset_shift_target,
-                    &value_targets,
-                    zt,
-                )
-            })
-            .collect::<Vec<_>>();
-        let true_eval_target = builder.constant_extension(true_eval);
-        for &eval_coset_gate in evals_coset_gates.iter() {
-            builder.connect_extension(eval_coset_gate, true_eval_target);
+        // Coset sizes 8 and 16 are the usual FRI arities in recursion.
+        for subgroup_bits in 2..=4 {
+            let len = 1 << subgroup_bits;
+            let coset_shift = F::rand();
+            let g = F::primitive_root_of_unity(subgroup_bits);
+            let points = F::cyclic_subgroup_coset_known_order(g, coset_shift, len);
+            let values = FF::rand_vec(len);
+
+            let homogeneous_points = points
+                .iter()
+                .zip(values.iter())
+                .map(|(&a, &b)| (<FF as FieldExtension<D>>::from_basefield(a), b))
+                .collect::<Vec<_>>();
+
+            let true_interpolant = interpolant(&homogeneous_points);
+
+            let z = FF::rand();
+            let true_eval = true_interpolant.eval(z);
+
+            let coset_shift_target = builder.constant(coset_shift);
+
+            let value_targets = values
+                .iter()
+                .map(|&v| (builder.constant_extension(v)))
+                .collect::<Vec<_>>();
+
+            let zt = builder.constant_extension(z);
+
+            let evals_coset_gates = [2, 4, 8]
+                .into_iter()
+                .map(|max_degree| {
+                    builder.interpolate_coset(
+                        CosetInterpolationGate::with_max_degree(subgroup_bits, max_degree),
+                        coset_shift_target,
+                        &value_targets,
+                        zt,
+                    )
+                })
+                .collect::<Vec<_>>();
+            let true_eval_target = builder.constant_extension(true_eval);
+            for &eval_coset_gate in evals_coset_gates.iter() {
+                builder.connect_extension(eval_coset_gate, true_eval_target);
+            }
         }
 
         let data = builder.build::<C>();
@@ -1,11 +1,19 @@
+pub mod aes;
 pub mod arithmetic;
 pub mod arithmetic_extension;
+pub mod bitwise;
+pub mod commitment;
+pub mod ecgfp5;
+pub mod fixed_point;
+pub mod float;
 pub mod hash;
+pub mod hash_chain;
 pub mod interpolation;
 pub mod lookup;
 pub mod polynomial;
 pub mod random_access;
 pub mod range_check;
+pub mod schnorr;
 pub mod select;
 pub mod split_base;
 pub mod split_join;
@@ -0,0 +1,295 @@
+//! Schnorr signatures over [ecGFp5](super::ecgfp5), signed natively and verified either natively
+//! or within circuits.
+//!
+//! A signature of `message` under the public key `P = x G` is a pair `(R, s)` with `s < n` and
+//! `s G = R + e P`, where the challenge `e` is the 256-bit integer whose 64-bit limbs are the
+//! Poseidon hash of `R`, `P` and `message`. The signer derives its nonce from its secret key and
+//! the message, so signing needs no randomness.
+
+use alloc::vec::Vec;
+
+use anyhow::{ensure, Result};
+use num::bigint::{BigUint, RandBigInt};
+use num::One;
+use rand::Rng;
+
+use crate::field::extension::Extendable;
+use crate::field::goldilocks_field::GoldilocksField;
+use crate::field::types::{Field, PrimeField64};
+use crate::gadgets::ecgfp5::{
+    group_order, set_affine_point_target, AffinePoint, AffinePointTarget, ProjectivePoint,
+    NUM_SCALAR_BITS,
+};
+use crate::hash::hashing::hash_n_to_m_no_pad;
+use crate::hash::poseidon::{PoseidonHash, PoseidonPermutation};
+use crate::iop::target::{BoolTarget, Target};
+use crate::iop::witness::WitnessWrite;
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::config::Hasher;
+use crate::util::ceil_div_usize;
+
+type F = GoldilocksField;
+
+/// The number of bits of challenges.
+pub const NUM_CHALLENGE_BITS: usize = 256;
+
+/// The challenge of a signature with nonce commitment `r` of `message` under `public_key`.
+fn challenge(r: &AffinePoint, public_key: &SchnorrPublicKey, message: &[F]) -> BigUint {
+    let inputs = [r.x.0, r.y.0, public_key.0.x.0, public_key.0.y.0].concat();
+    let hash = PoseidonHash::hash_no_pad(&[inputs.as_slice(), message].concat());
+    hash.elements
+        .iter()
+        .rev()
+        .fold(BigUint::default(), |acc, x| {
+            (acc << 64) + x.to_canonical_u64()
+        })
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SchnorrSecretKey(BigUint);
+
+impl SchnorrSecretKey {
+    /// Samples a secret key uniformly from `[1, n)`.
+    pub fn sample<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        Self(rng.gen_biguint_range(&BigUint::one(), &group_order()))
+    }
+
+    pub fn public_key(&self) -> SchnorrPublicKey {
+        let p = ProjectivePoint::generator().scalar_mul(&self.0);
+        SchnorrPublicKey(p.to_affine().expect("The secret key is nonzero"))
+    }
+
+    pub fn sign(&self, message: &[F]) -> SchnorrSignature {
+        let n = group_order();
+
+        // The nonce is a hash of the key and message, reduced from 512 bits so that its bias is
+        // negligible.
+        let mut inputs = self
+            .0
+            .to_u32_digits()
+            .into_iter()
+            .map(F::from_canonical_u32)
+            .collect::<Vec<_>>();
+        inputs.resize(ceil_div_usize(NUM_SCALAR_BITS, 32), F::ZERO);
+        inputs.extend_from_slice(message);
+        let k = hash_n_to_m_no_pad::<F, PoseidonPermutation<F>>(&inputs, 8)
+            .iter()
+            .rev()
+            .fold(BigUint::default(), |acc, x| {
+                (acc << 64) + x.to_canonical_u64()
+            })
+            % &n;
+
+        let r = ProjectivePoint::generator()
+            .scalar_mul(&k)
+            .to_affine()
+            .expect("The nonce is nonzero");
+        let e = challenge(&r, &self.public_key(), message);
+        let s = (k + e * &self.0) % n;
+        SchnorrSignature { r, s }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct SchnorrPublicKey(pub AffinePoint);
+
+impl SchnorrPublicKey {
+    pub fn verify(&self, message: &[F], signature: &SchnorrSignature) -> Result<()> {
+        ensure!(
+            self.0.is_in_subgroup(),
+            "The public key isn't in the subgroup of prime order"
+        );
+        ensure!(
+            signature.r.is_in_subgroup(),
+            "The signature's nonce commitment isn't in the subgroup of prime order"
+        );
+        ensure!(
+            signature.s < group_order(),
+            "The signature's scalar isn't reduced"
+        );
+        let e = challenge(&signature.r, self, message);
+        let lhs = ProjectivePoint::generator().scalar_mul(&signature.s);
+        let rhs = signature.r.to_projective() + self.0.to_projective().scalar_mul(&e);
+        ensure!(lhs == rhs, "Invalid signature");
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SchnorrSignature {
+    pub r: AffinePoint,
+    pub s: BigUint,
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct SchnorrPublicKeyTarget(pub AffinePointTarget);
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SchnorrSignatureTarget {
+    pub r: AffinePointTarget,
+    /// The little-endian bits of `s`.
+    pub s: Vec<BoolTarget>,
+}
+
+pub fn set_schnorr_public_key_target<W: WitnessWrite<F>>(
+    witness: &mut W,
+    target: SchnorrPublicKeyTarget,
+    value: SchnorrPublicKey,
+) {
+    set_affine_point_target(witness, target.0, value.0);
+}
+
+pub fn set_schnorr_signature_target<W: WitnessWrite<F>>(
+    witness: &mut W,
+    target: &SchnorrSignatureTarget,
+    value: &SchnorrSignature,
+) {
+    set_affine_point_target(witness, target.r, value.r);
+    for (i, &bit) in target.s.iter().enumerate() {
+        witness.set_bool_target(bit, value.s.bit(i as u64));
+    }
+}
+
+impl<const D: usize> CircuitBuilder<F, D>
+where
+    F: Extendable<D>,
+{
+    pub fn add_virtual_schnorr_public_key_target(&mut self) -> SchnorrPublicKeyTarget {
+        SchnorrPublicKeyTarget(self.add_virtual_affine_point_target())
+    }
+
+    pub fn add_virtual_schnorr_signature_target(&mut self) -> SchnorrSignatureTarget {
+        SchnorrSignatureTarget {
+            r: self.add_virtual_affine_point_target(),
+            s: (0..NUM_SCALAR_BITS)
+                .map(|_| self.add_virtual_bool_target_safe())
+                .collect(),
+        }
+    }
+
+    /// Checks that `signature` is a valid signature of `message` under `public_key`. This includes
+    /// checking that `public_key` is in the subgroup of prime order.
+    pub fn verify_schnorr_signature(
+        &mut self,
+        public_key: SchnorrPublicKeyTarget,
+        message: &[Target],
+        signature: &SchnorrSignatureTarget,
+    ) {
+        assert_eq!(signature.s.len(), NUM_SCALAR_BITS);
+        self.curve_assert_in_subgroup(public_key.0);
+        self.curve_assert_in_subgroup(signature.r);
+        self.assert_bits_less_than(&signature.s, &group_order());
+
+        let inputs = [
+            signature.r.x.0,
+            signature.r.y.0,
+            public_key.0.x.0,
+            public_key.0.y.0,
+        ]
+        .concat();
+        let hash =
+            self.hash_n_to_hash_no_pad::<PoseidonHash>([inputs.as_slice(), message].concat());
+        let e = hash
+            .elements
+            .iter()
+            .flat_map(|&x| self.split_le_canonical(x))
+            .collect::<Vec<_>>();
+
+        let lhs = self.curve_mul_generator(&signature.s);
+        let public_key = self.affine_to_projective(public_key.0);
+        let e_public_key = self.curve_mul(public_key, &e);
+        let r = self.affine_to_projective(signature.r);
+        let rhs = self.curve_add(r, e_public_key);
+        self.curve_connect(lhs, rhs);
+    }
+
+    /// Splits `x` into its 64 little-endian bits, checking that they are those of its canonical
+    /// representative, i.e. that they don't encode `x + p`.
+    fn split_le_canonical(&mut self, x: Target) -> Vec<BoolTarget> {
+        let bits = self.split_le(x, 64);
+        // The bits encode a value `>= p = 2^64 - 2^32 + 1` iff the high half is all ones and the
+        // low half isn't all zeros.
+        let mut high_all_ones = self._true();
+        for &bit in &bits[32..] {
+            high_all_ones = self.and(high_all_ones, bit);
+        }
+        let low = self.le_sum(bits[..32].iter());
+        let overflow = self.mul(high_all_ones.target, low);
+        self.assert_zero(overflow);
+        bits
+    }
+
+    /// Checks that the integer with little-endian `bits` is less than `bound`.
+    fn assert_bits_less_than(&mut self, bits: &[BoolTarget], bound: &BigUint) {
+        assert!(bound.bits() as usize <= bits.len());
+        // Going from the most significant bit, whether the bits seen so far are equal to those of
+        // `bound`, and whether they are less.
+        let mut equal = self._true();
+        let mut less = self._false();
+        for (i, &bit) in bits.iter().enumerate().rev() {
+            let not_bit = self.not(bit);
+            if bound.bit(i as u64) {
+                let becomes_less = self.and(equal, not_bit);
+                less = self.or(less, becomes_less);
+                equal = self.and(equal, bit);
+            } else {
+                equal = self.and(equal, not_bit);
+            }
+        }
+        self.assert_one(less.target);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::OsRng;
+
+    use super::*;
+    use crate::field::types::Sample;
+    use crate::iop::witness::PartialWitness;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    #[test]
+    fn test_schnorr_native() -> Result<()> {
+        let secret_key = SchnorrSecretKey::sample(&mut OsRng);
+        let public_key = secret_key.public_key();
+        let message = F::rand_vec(3);
+        let signature = secret_key.sign(&message);
+        public_key.verify(&message, &signature)?;
+
+        assert!(public_key.verify(&F::rand_vec(3), &signature).is_err());
+        let other_key = SchnorrSecretKey::sample(&mut OsRng).public_key();
+        assert!(other_key.verify(&message, &signature).is_err());
+        let unreduced = SchnorrSignature {
+            s: &signature.s + group_order(),
+            ..signature
+        };
+        assert!(public_key.verify(&message, &unreduced).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_schnorr_circuit() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let public_key = builder.add_virtual_schnorr_public_key_target();
+        let message = builder.add_virtual_targets(3);
+        let signature = builder.add_virtual_schnorr_signature_target();
+        builder.verify_schnorr_signature(public_key, &message, &signature);
+        let data = builder.build::<C>();
+
+        let secret_key = SchnorrSecretKey::sample(&mut OsRng);
+        let message_value = F::rand_vec(3);
+        let signature_value = secret_key.sign(&message_value);
+        let mut pw = PartialWitness::new();
+        set_schnorr_public_key_target(&mut pw, public_key, secret_key.public_key());
+        pw.set_target_arr(&message, &message_value);
+        set_schnorr_signature_target(&mut pw, &signature, &signature_value);
+        let proof = data.prove(pw)?;
+        data.verify(proof)
+    }
+}
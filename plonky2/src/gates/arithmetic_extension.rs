@@ -118,13 +118,10 @@ impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for ArithmeticExte
             let multiplicand_1 = vars.get_local_ext_algebra(Self::wires_ith_multiplicand_1(i));
             let addend = vars.get_local_ext_algebra(Self::wires_ith_addend(i));
             let output = vars.get_local_ext_algebra(Self::wires_ith_output(i));
-            let computed_output = {
-                let mul = builder.mul_ext_algebra(multiplicand_0, multiplicand_1);
-                let scaled_mul = builder.scalar_mul_ext_algebra(const_0, mul);
-                builder.scalar_mul_add_ext_algebra(const_1, addend, scaled_mul)
-            };
-
-            let diff = builder.sub_ext_algebra(output, computed_output);
+            // Subtracting each scaled term from the output directly saves an addition per limb.
+            let mul = builder.mul_ext_algebra(multiplicand_0, multiplicand_1);
+            let diff = builder.sub_scalar_mul_ext_algebra(output, const_1, addend);
+            let diff = builder.sub_scalar_mul_ext_algebra(diff, const_0, mul);
             constraints.extend(diff.to_ext_target_array());
         }
 
@@ -0,0 +1,400 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::ops::{Add, Mul, Range, Sub};
+
+use crate::field::extension::Extendable;
+use crate::field::packed::PackedField;
+use crate::field::types::Field;
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGeneratorRef};
+use crate::iop::target::Target;
+use crate::iop::witness::{PartitionWitness, Witness, WitnessWrite};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::{CircuitConfig, CommonCircuitData};
+use crate::plonk::plonk_common::{reduce_with_powers, reduce_with_powers_ext_circuit};
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+use crate::util::serialization::{Buffer, IoError, IoResult, Read, Write};
+
+/// A bitwise operation performed by a `BitwiseGate`.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum BitwiseOp {
+    #[default]
+    And,
+    Or,
+    Xor,
+}
+
+impl BitwiseOp {
+    pub fn apply(self, x: u32, y: u32) -> u32 {
+        match self {
+            Self::And => x & y,
+            Self::Or => x | y,
+            Self::Xor => x ^ y,
+        }
+    }
+
+    /// Evaluates the operation on bits `x` and `y`, as a polynomial of degree 2.
+    fn eval<T: Copy + Add<Output = T> + Sub<Output = T> + Mul<Output = T>>(self, x: T, y: T) -> T {
+        let xy = x * y;
+        match self {
+            Self::And => xy,
+            Self::Or => x + y - xy,
+            Self::Xor => x + y - xy - xy,
+        }
+    }
+
+    fn eval_circuit<F: RichField + Extendable<D>, const D: usize>(
+        self,
+        builder: &mut CircuitBuilder<F, D>,
+        x: ExtensionTarget<D>,
+        y: ExtensionTarget<D>,
+    ) -> ExtensionTarget<D> {
+        if self == Self::And {
+            return builder.mul_extension(x, y);
+        }
+        let sum = builder.add_extension(x, y);
+        let xy_coeff = if self == Self::Or {
+            F::NEG_ONE
+        } else {
+            -F::TWO
+        };
+        builder.arithmetic_extension(xy_coeff, F::ONE, x, y, sum)
+    }
+
+    fn to_u8(self) -> u8 {
+        match self {
+            Self::And => 0,
+            Self::Or => 1,
+            Self::Xor => 2,
+        }
+    }
+
+    fn from_u8(x: u8) -> IoResult<Self> {
+        match x {
+            0 => Ok(Self::And),
+            1 => Ok(Self::Or),
+            2 => Ok(Self::Xor),
+            _ => Err(IoError),
+        }
+    }
+}
+
+/// A gate which computes a bitwise operation on pairs of 32-bit words, by decomposing both inputs
+/// into bits. This range checks the inputs to 32 bits. If the config supports enough wires, it can
+/// support several such operations in one gate.
+#[derive(Copy, Clone, Debug)]
+pub struct BitwiseGate {
+    pub op: BitwiseOp,
+    /// Number of operations performed by a bitwise gate.
+    pub num_ops: usize,
+}
+
+impl BitwiseGate {
+    pub const NUM_BITS: usize = 32;
+
+    pub fn new_from_config(op: BitwiseOp, config: &CircuitConfig) -> Self {
+        Self {
+            op,
+            num_ops: Self::num_ops(config),
+        }
+    }
+
+    /// Determine the maximum number of operations that can fit in one gate for the given config.
+    pub(crate) fn num_ops(config: &CircuitConfig) -> usize {
+        let routed_wires_per_op = 3;
+        let wires_per_op = routed_wires_per_op + 2 * Self::NUM_BITS;
+        (config.num_routed_wires / routed_wires_per_op).min(config.num_wires / wires_per_op)
+    }
+
+    pub fn wire_ith_input_0(i: usize) -> usize {
+        3 * i
+    }
+    pub fn wire_ith_input_1(i: usize) -> usize {
+        3 * i + 1
+    }
+    pub fn wire_ith_output(i: usize) -> usize {
+        3 * i + 2
+    }
+
+    /// The wires holding the little-endian bits of the first input of the `i`th operation.
+    pub fn wires_ith_input_0_bits(&self, i: usize) -> Range<usize> {
+        let start = 3 * self.num_ops + 2 * Self::NUM_BITS * i;
+        start..start + Self::NUM_BITS
+    }
+
+    /// The wires holding the little-endian bits of the second input of the `i`th operation.
+    pub fn wires_ith_input_1_bits(&self, i: usize) -> Range<usize> {
+        let start = 3 * self.num_ops + 2 * Self::NUM_BITS * i + Self::NUM_BITS;
+        start..start + Self::NUM_BITS
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for BitwiseGate {
+    fn id(&self) -> String {
+        format!("{self:?}")
+    }
+
+    fn serialize(&self, dst: &mut Vec<u8>, _common_data: &CommonCircuitData<F, D>) -> IoResult<()> {
+        dst.write_u8(self.op.to_u8())?;
+        dst.write_usize(self.num_ops)
+    }
+
+    fn deserialize(src: &mut Buffer, _common_data: &CommonCircuitData<F, D>) -> IoResult<Self> {
+        let op = BitwiseOp::from_u8(src.read_u8()?)?;
+        let num_ops = src.read_usize()?;
+        Ok(Self { op, num_ops })
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let two = F::Extension::TWO;
+        let mut constraints = Vec::with_capacity(self.num_ops * (3 + 2 * Self::NUM_BITS));
+        for i in 0..self.num_ops {
+            let input_0 = vars.local_wires[Self::wire_ith_input_0(i)];
+            let input_1 = vars.local_wires[Self::wire_ith_input_1(i)];
+            let output = vars.local_wires[Self::wire_ith_output(i)];
+            let bits_0 = &vars.local_wires[self.wires_ith_input_0_bits(i)];
+            let bits_1 = &vars.local_wires[self.wires_ith_input_1_bits(i)];
+
+            constraints.push(reduce_with_powers(bits_0, two) - input_0);
+            constraints.push(reduce_with_powers(bits_1, two) - input_1);
+            let output_bits = bits_0
+                .iter()
+                .zip(bits_1)
+                .map(|(&x, &y)| self.op.eval(x, y))
+                .collect::<Vec<_>>();
+            constraints.push(reduce_with_powers(&output_bits, two) - output);
+            constraints.extend(bits_0.iter().chain(bits_1).map(|&b| b * b - b));
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_circuit(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let two = builder.two();
+        let mut constraints = Vec::with_capacity(self.num_ops * (3 + 2 * Self::NUM_BITS));
+        for i in 0..self.num_ops {
+            let input_0 = vars.local_wires[Self::wire_ith_input_0(i)];
+            let input_1 = vars.local_wires[Self::wire_ith_input_1(i)];
+            let output = vars.local_wires[Self::wire_ith_output(i)];
+            let bits_0 = &vars.local_wires[self.wires_ith_input_0_bits(i)];
+            let bits_1 = &vars.local_wires[self.wires_ith_input_1_bits(i)];
+
+            let computed_input_0 = reduce_with_powers_ext_circuit(builder, bits_0, two);
+            constraints.push(builder.sub_extension(computed_input_0, input_0));
+            let computed_input_1 = reduce_with_powers_ext_circuit(builder, bits_1, two);
+            constraints.push(builder.sub_extension(computed_input_1, input_1));
+            let output_bits = bits_0
+                .iter()
+                .zip(bits_1)
+                .map(|(&x, &y)| self.op.eval_circuit(builder, x, y))
+                .collect::<Vec<_>>();
+            let computed_output = reduce_with_powers_ext_circuit(builder, &output_bits, two);
+            constraints.push(builder.sub_extension(computed_output, output));
+            for &b in bits_0.iter().chain(bits_1) {
+                constraints.push(builder.mul_sub_extension(b, b, b));
+            }
+        }
+
+        constraints
+    }
+
+    fn generators(&self, row: usize, _local_constants: &[F]) -> Vec<WitnessGeneratorRef<F, D>> {
+        (0..self.num_ops)
+            .map(|i| {
+                WitnessGeneratorRef::new(
+                    BitwiseGenerator {
+                        row,
+                        op: self.op,
+                        num_ops: self.num_ops,
+                        i,
+                    }
+                    .adapter(),
+                )
+            })
+            .collect()
+    }
+
+    // The inputs and output, then the bits of both inputs, for each operation.
+    fn num_wires(&self) -> usize {
+        self.num_ops * (3 + 2 * Self::NUM_BITS)
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        2
+    }
+
+    // 3 for checking the decompositions and the output, then one to check each bit is boolean.
+    fn num_constraints(&self) -> usize {
+        self.num_ops * (3 + 2 * Self::NUM_BITS)
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> PackedEvaluableBase<F, D> for BitwiseGate {
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        for i in 0..self.num_ops {
+            let input_0 = vars.local_wires[Self::wire_ith_input_0(i)];
+            let input_1 = vars.local_wires[Self::wire_ith_input_1(i)];
+            let output = vars.local_wires[Self::wire_ith_output(i)];
+            let bits_0 = vars.local_wires.view(self.wires_ith_input_0_bits(i));
+            let bits_1 = vars.local_wires.view(self.wires_ith_input_1_bits(i));
+
+            yield_constr.one(reduce_with_powers(bits_0, F::TWO) - input_0);
+            yield_constr.one(reduce_with_powers(bits_1, F::TWO) - input_1);
+            let output_bits = bits_0
+                .into_iter()
+                .zip(bits_1)
+                .map(|(&x, &y)| self.op.eval(x, y))
+                .collect::<Vec<_>>();
+            yield_constr.one(reduce_with_powers(&output_bits, F::TWO) - output);
+            yield_constr.many(bits_0.into_iter().chain(bits_1).map(|&b| b * b - b));
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct BitwiseGenerator {
+    row: usize,
+    op: BitwiseOp,
+    num_ops: usize,
+    i: usize,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F, D> for BitwiseGenerator {
+    fn id(&self) -> String {
+        "BitwiseGenerator".to_string()
+    }
+
+    fn dependencies(&self) -> Vec<Target> {
+        [
+            BitwiseGate::wire_ith_input_0(self.i),
+            BitwiseGate::wire_ith_input_1(self.i),
+        ]
+        .iter()
+        .map(|&i| Target::wire(self.row, i))
+        .collect()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let gate = BitwiseGate {
+            op: self.op,
+            num_ops: self.num_ops,
+        };
+        let get_wire = |wire: usize| -> u64 {
+            witness
+                .get_target(Target::wire(self.row, wire))
+                .to_canonical_u64()
+        };
+
+        let input_0 = get_wire(BitwiseGate::wire_ith_input_0(self.i));
+        let input_1 = get_wire(BitwiseGate::wire_ith_input_1(self.i));
+        debug_assert!(
+            input_0 >> BitwiseGate::NUM_BITS == 0 && input_1 >> BitwiseGate::NUM_BITS == 0,
+            "Bitwise operation inputs must fit in 32 bits"
+        );
+
+        for (input, wires) in [
+            (input_0, gate.wires_ith_input_0_bits(self.i)),
+            (input_1, gate.wires_ith_input_1_bits(self.i)),
+        ] {
+            for (j, wire) in wires.enumerate() {
+                out_buffer.set_target(
+                    Target::wire(self.row, wire),
+                    F::from_bool((input >> j) & 1 == 1),
+                );
+            }
+        }
+
+        let output = self.op.apply(input_0 as u32, input_1 as u32);
+        out_buffer.set_target(
+            Target::wire(self.row, BitwiseGate::wire_ith_output(self.i)),
+            F::from_canonical_u32(output),
+        );
+    }
+
+    fn serialize(&self, dst: &mut Vec<u8>, _common_data: &CommonCircuitData<F, D>) -> IoResult<()> {
+        dst.write_usize(self.row)?;
+        dst.write_u8(self.op.to_u8())?;
+        dst.write_usize(self.num_ops)?;
+        dst.write_usize(self.i)
+    }
+
+    fn deserialize(src: &mut Buffer, _common_data: &CommonCircuitData<F, D>) -> IoResult<Self> {
+        let row = src.read_usize()?;
+        let op = BitwiseOp::from_u8(src.read_u8()?)?;
+        let num_ops = src.read_usize()?;
+        let i = src.read_usize()?;
+        Ok(Self {
+            row,
+            op,
+            num_ops,
+            i,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use crate::field::goldilocks_field::GoldilocksField;
+    use crate::gates::bitwise::{BitwiseGate, BitwiseOp};
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    const OPS: [BitwiseOp; 3] = [BitwiseOp::And, BitwiseOp::Or, BitwiseOp::Xor];
+
+    #[test]
+    fn low_degree() {
+        for op in OPS {
+            let gate =
+                BitwiseGate::new_from_config(op, &CircuitConfig::standard_recursion_config());
+            test_low_degree::<GoldilocksField, _, 4>(gate);
+        }
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        for op in OPS {
+            let gate =
+                BitwiseGate::new_from_config(op, &CircuitConfig::standard_recursion_config());
+            test_eval_fns::<F, C, _, D>(gate)?;
+        }
+        Ok(())
+    }
+}
@@ -139,6 +139,11 @@ pub trait Gate<F: RichField + Extendable<D>, const D: usize>: 'static + Send + S
                 )
             })
             .collect();
+        // A zero filter means the gate isn't selected at that point. This is common on the trace
+        // domain itself, but essentially never happens on the quotient coset.
+        if filters.iter().all(|f| f.is_zero()) {
+            return vec![F::ZERO; self.num_constraints() * filters.len()];
+        }
         vars_batch.remove_prefix(num_selectors + num_lookup_selectors);
         let mut res_batch = self.eval_unfiltered_base_batch(vars_batch);
         for res_chunk in res_batch.chunks_exact_mut(filters.len()) {
@@ -302,3 +307,83 @@ fn compute_filter_circuit<F: RichField + Extendable<D>, const D: usize>(
         .collect::<Vec<_>>();
     builder.mul_many_extension(v)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::goldilocks_field::GoldilocksField;
+    use crate::field::types::Sample;
+    use crate::gates::arithmetic_base::ArithmeticGate;
+    use crate::hash::hash_types::HashOut;
+    use crate::plonk::circuit_data::CircuitConfig;
+
+    const D: usize = 2;
+    type F = GoldilocksField;
+    type FE = <F as Extendable<D>>::Extension;
+
+    #[test]
+    fn test_eval_filtered_base_batch() {
+        let gate: &dyn Gate<F, D> =
+            &ArithmeticGate::new_from_config(&CircuitConfig::standard_recursion_config());
+        let batch_size = 4;
+        let public_inputs_hash = HashOut::rand();
+        let wires = F::rand_vec(gate.num_wires() * batch_size);
+        // The gate is the second of a group of three, and there is another selector after its
+        // own. Batches hold one column per constant or wire, with one value per point.
+        let (row, group_range, num_selectors) = (1, 0..3, 2);
+        let point = |columns: &[F], i: usize| {
+            columns
+                .iter()
+                .skip(i)
+                .step_by(batch_size)
+                .map(|&x| <FE as FieldExtension<D>>::from_basefield(x))
+                .collect::<Vec<_>>()
+        };
+
+        let eval = |selectors: [usize; 4]| {
+            let constants = selectors
+                .into_iter()
+                .map(F::from_canonical_usize)
+                .chain(F::rand_vec(3 * batch_size))
+                .collect::<Vec<_>>();
+            let batch = gate.eval_filtered_base_batch(
+                EvaluationVarsBaseBatch::new(batch_size, &constants, &wires, &public_inputs_hash),
+                row,
+                0,
+                group_range.clone(),
+                num_selectors,
+                0,
+            );
+            assert_eq!(batch.len(), gate.num_constraints() * batch_size);
+
+            // Each point agrees with the evaluation of that point alone.
+            for i in 0..batch_size {
+                let expected = gate.eval_filtered(
+                    EvaluationVars {
+                        local_constants: &point(&constants, i),
+                        local_wires: &point(&wires, i),
+                        public_inputs_hash: &public_inputs_hash,
+                    },
+                    row,
+                    0,
+                    group_range.clone(),
+                    num_selectors,
+                    0,
+                );
+                assert_eq!(point(&batch, i), expected);
+            }
+            batch
+        };
+
+        // No point selects the gate, so every constraint is zero.
+        let unselected = eval([0, 2, UNUSED_SELECTOR, 0]);
+        assert!(unselected.iter().all(|c| c.is_zero()));
+
+        // Only the points which select the gate have non-zero constraints, for random wires.
+        let mixed = eval([row, 0, row, UNUSED_SELECTOR]);
+        for (i, selected) in [true, false, true, false].into_iter().enumerate() {
+            let constraints = point(&mixed, i);
+            assert_eq!(constraints.iter().all(|c| c.is_zero()), !selected);
+        }
+    }
+}
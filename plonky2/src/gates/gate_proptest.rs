@@ -0,0 +1,340 @@
+//! Property-based checks for gates, built on `proptest`. Unlike the fixed checks in
+//! [`gate_testing`](crate::gates::gate_testing), these run many random witnesses per gate and shrink
+//! any failure to a minimal counterexample. Downstream crates can run them against their own gates
+//! by enabling the `gate_proptest` feature.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use anyhow::{anyhow, Result};
+use proptest::collection;
+use proptest::prelude::*;
+use proptest::test_runner::{Config, TestCaseResult, TestRunner};
+
+use crate::field::extension::{Extendable, FieldExtension};
+use crate::field::types::Field;
+use crate::gates::gate::Gate;
+use crate::gates::gate_testing::{constraint_eval_degrees, WITNESS_DEGREE, WITNESS_SIZE};
+use crate::hash::hash_types::{HashOut, RichField};
+use crate::iop::generator::{generate_partial_witness, GeneratedValues};
+use crate::iop::target::Target;
+use crate::iop::witness::{PartialWitness, PartitionWitness, Witness, WitnessWrite};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::config::GenericConfig;
+use crate::plonk::vars::{EvaluationTargets, EvaluationVars, EvaluationVarsBaseBatch};
+
+fn field_elements<F: RichField>(n: usize) -> impl Strategy<Value = Vec<F>> {
+    collection::vec(any::<u64>(), n)
+        .prop_map(|xs| xs.into_iter().map(F::from_noncanonical_u64).collect())
+}
+
+fn extension_elements<F: RichField + Extendable<D>, const D: usize>(
+    n: usize,
+) -> impl Strategy<Value = Vec<F::Extension>> {
+    field_elements::<F>(n * D).prop_map(|xs| {
+        xs.chunks(D)
+            .map(|c| F::Extension::from_basefield_array(core::array::from_fn(|i| c[i])))
+            .collect()
+    })
+}
+
+fn run<S: Strategy>(
+    config: &Config,
+    strategy: &S,
+    test: impl Fn(S::Value) -> TestCaseResult,
+) -> Result<()> {
+    TestRunner::new(config.clone())
+        .run(strategy, test)
+        .map_err(|e| anyhow!("{}", e))
+}
+
+/// Runs all of the checks below against `gate`. `sanitize_input` is passed to
+/// [`check_generators_complete`].
+pub fn check_gate<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    G: Gate<F, D>,
+    const D: usize,
+>(
+    gate: &G,
+    config: &Config,
+    sanitize_input: impl Fn(usize, F) -> F,
+) -> Result<()> {
+    check_low_degree(gate, config)?;
+    check_eval_consistency::<F, C, G, D>(gate, config)?;
+    check_generators_complete(gate, config, sanitize_input)
+}
+
+/// Checks that the constraints have the expected count, and that applying them to random
+/// low-degree witness polynomials gives polynomials of degree at most `gate.degree()` times
+/// larger.
+pub fn check_low_degree<F: RichField + Extendable<D>, G: Gate<F, D>, const D: usize>(
+    gate: &G,
+    config: &Config,
+) -> Result<()> {
+    let strategy = (
+        collection::vec(extension_elements::<F, D>(WITNESS_SIZE), gate.num_wires()),
+        collection::vec(
+            extension_elements::<F, D>(WITNESS_SIZE),
+            gate.num_constants(),
+        ),
+    );
+    let expected_eval_degree = WITNESS_DEGREE * gate.degree();
+    run(config, &strategy, |(wire_coeffs, constant_coeffs)| {
+        let degrees = constraint_eval_degrees(gate, &wire_coeffs, &constant_coeffs);
+        prop_assert_eq!(degrees.len(), gate.num_constraints());
+        prop_assert!(
+            degrees.iter().all(|&deg| deg <= expected_eval_degree),
+            "Expected degrees at most {}, actual {:?}",
+            expected_eval_degree,
+            degrees
+        );
+        Ok(())
+    })
+}
+
+/// Checks that `eval_unfiltered`, `eval_unfiltered_base_batch` and `eval_unfiltered_circuit` agree.
+pub fn check_eval_consistency<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    G: Gate<F, D>,
+    const D: usize,
+>(
+    gate: &G,
+    config: &Config,
+) -> Result<()> {
+    let base_strategy = (
+        field_elements::<F>(gate.num_wires()),
+        field_elements::<F>(gate.num_constants()),
+        field_elements::<F>(4),
+    );
+    run(config, &base_strategy, |(wires, constants, hash)| {
+        let public_inputs_hash = HashOut::from_vec(hash);
+        // Batch of 1.
+        let vars_base_batch =
+            EvaluationVarsBaseBatch::new(1, &constants, &wires, &public_inputs_hash);
+        let evals_base = gate.eval_unfiltered_base_batch(vars_base_batch);
+
+        let wires = wires
+            .into_iter()
+            .map(F::Extension::from_basefield)
+            .collect::<Vec<_>>();
+        let constants = constants
+            .into_iter()
+            .map(F::Extension::from_basefield)
+            .collect::<Vec<_>>();
+        let evals = gate.eval_unfiltered(EvaluationVars {
+            local_constants: &constants,
+            local_wires: &wires,
+            public_inputs_hash: &public_inputs_hash,
+        });
+        prop_assert_eq!(
+            evals,
+            evals_base
+                .into_iter()
+                .map(F::Extension::from_basefield)
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    })?;
+
+    // Build the recursive evaluation once; each case then only needs witness generation.
+    let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+    let wires_t = builder.add_virtual_extension_targets(gate.num_wires());
+    let constants_t = builder.add_virtual_extension_targets(gate.num_constants());
+    let public_inputs_hash_t = builder.add_virtual_hash();
+    let evals_t = gate.eval_unfiltered_circuit(
+        &mut builder,
+        EvaluationTargets {
+            local_constants: &constants_t,
+            local_wires: &wires_t,
+            public_inputs_hash: &public_inputs_hash_t,
+        },
+    );
+    let data = builder.build_prover::<C>();
+
+    let strategy = (
+        extension_elements::<F, D>(gate.num_wires()),
+        extension_elements::<F, D>(gate.num_constants()),
+        field_elements::<F>(4),
+    );
+    run(config, &strategy, |(wires, constants, hash)| {
+        let public_inputs_hash = HashOut::from_vec(hash);
+        let evals = gate.eval_unfiltered(EvaluationVars {
+            local_constants: &constants,
+            local_wires: &wires,
+            public_inputs_hash: &public_inputs_hash,
+        });
+
+        let mut pw = PartialWitness::new();
+        pw.set_extension_targets(&wires_t, &wires);
+        pw.set_extension_targets(&constants_t, &constants);
+        pw.set_hash_target(public_inputs_hash_t, public_inputs_hash);
+        let witness = generate_partial_witness(pw, &data.prover_only, &data.common);
+        let evals_circuit = evals_t
+            .iter()
+            .map(|&t| witness.get_extension_target(t))
+            .collect::<Vec<_>>();
+        prop_assert_eq!(evals, evals_circuit);
+        Ok(())
+    })
+}
+
+/// Checks that the gate's generators, given random inputs, produce a witness satisfying its
+/// constraints.
+///
+/// Wires which some generator depends on but which no generator has set are treated as inputs, and
+/// get random values passed through `sanitize_input(column, value)`, which can map them into the
+/// gate's domain, e.g. to make a bit wire boolean. Wires untouched by generators are assigned the
+/// same way. Gates without generators are skipped, as their wires are set by copy constraints.
+pub fn check_generators_complete<F: RichField + Extendable<D>, G: Gate<F, D>, const D: usize>(
+    gate: &G,
+    config: &Config,
+    sanitize_input: impl Fn(usize, F) -> F,
+) -> Result<()> {
+    let num_wires = gate.num_wires();
+    let num_constants = gate.num_constants();
+    if gate.generators(0, &vec![F::ZERO; num_constants]).is_empty() {
+        return Ok(());
+    }
+
+    let representative_map = (0..num_wires).collect::<Vec<_>>();
+    let strategy = (
+        field_elements::<F>(num_wires),
+        field_elements::<F>(num_constants),
+    );
+    run(config, &strategy, |(inputs, constants)| {
+        let input = |column: usize| sanitize_input(column, inputs[column]);
+        let generators = gate.generators(0, &constants);
+        let mut witness = PartitionWitness::new(num_wires, 1, &representative_map);
+
+        let mut pending = generators.iter().collect::<Vec<_>>();
+        while !pending.is_empty() {
+            let mut progress = false;
+            let mut unfinished = Vec::new();
+            for generator in pending {
+                let mut out_buffer = GeneratedValues::empty();
+                let finished = generator.0.run(&witness, &mut out_buffer);
+                for &(target, value) in &out_buffer.target_values {
+                    match witness.try_get_target(target) {
+                        Some(old_value) => prop_assert_eq!(
+                            old_value,
+                            value,
+                            "{} set {:?} twice with different values",
+                            generator.0.id(),
+                            target
+                        ),
+                        None => witness.set_target(target, value),
+                    }
+                    progress = true;
+                }
+                if finished {
+                    progress = true;
+                } else {
+                    unfinished.push(generator);
+                }
+            }
+
+            if !progress {
+                // Every remaining generator is blocked, so assign its missing inputs.
+                for generator in &unfinished {
+                    for target in generator.0.watch_list() {
+                        if let Target::Wire(wire) = target {
+                            if witness.try_get_target(target).is_none() {
+                                witness.set_target(target, input(wire.column));
+                                progress = true;
+                            }
+                        }
+                    }
+                }
+                prop_assert!(progress, "Generators are blocked on non-wire targets");
+            }
+            pending = unfinished;
+        }
+
+        let wires = (0..num_wires)
+            .map(|column| {
+                let value = witness
+                    .try_get_target(Target::wire(0, column))
+                    .unwrap_or_else(|| input(column));
+                F::Extension::from_basefield(value)
+            })
+            .collect::<Vec<_>>();
+        let constants = constants
+            .into_iter()
+            .map(F::Extension::from_basefield)
+            .collect::<Vec<_>>();
+        let evals = gate.eval_unfiltered(EvaluationVars {
+            local_constants: &constants,
+            local_wires: &wires,
+            public_inputs_hash: &HashOut::ZERO,
+        });
+        prop_assert!(
+            evals.iter().all(|e| e.is_zero()),
+            "Generated witness violates constraints: {:?}",
+            evals
+        );
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::types::{Field, PrimeField64};
+    use crate::gates::arithmetic_base::ArithmeticGate;
+    use crate::gates::exponentiation::ExponentiationGate;
+    use crate::gates::poseidon::PoseidonGate;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    fn config() -> Config {
+        Config::with_cases(16)
+    }
+
+    fn to_bit(x: F) -> F {
+        F::from_bool(x.to_canonical_u64() & 1 == 1)
+    }
+
+    #[test]
+    fn arithmetic() -> Result<()> {
+        let gate = ArithmeticGate::new_from_config(&CircuitConfig::standard_recursion_config());
+        check_gate::<F, C, _, D>(&gate, &config(), |_, x| x)
+    }
+
+    #[test]
+    fn exponentiation() -> Result<()> {
+        let gate = ExponentiationGate::<F, D>::new(5);
+        let power_bits = gate.wire_power_bit(0)..=gate.wire_power_bit(gate.num_power_bits - 1);
+        check_gate::<F, C, _, D>(&gate, &config(), |column, x| {
+            if power_bits.contains(&column) {
+                to_bit(x)
+            } else {
+                x
+            }
+        })
+    }
+
+    #[test]
+    fn poseidon() -> Result<()> {
+        let gate = PoseidonGate::<F, D>::new();
+        check_gate::<F, C, _, D>(&gate, &config(), |column, x| {
+            if column == PoseidonGate::<F, D>::WIRE_SWAP {
+                to_bit(x)
+            } else {
+                x
+            }
+        })
+    }
+
+    #[test]
+    fn generators_violating_constraints_are_caught() {
+        // Power bits which are not boolean give a witness the constraints reject.
+        let gate = ExponentiationGate::<F, D>::new(5);
+        assert!(check_generators_complete(&gate, &config(), |_, x| x + F::TWO).is_err());
+    }
+}
@@ -16,35 +16,19 @@ use crate::plonk::vars::{EvaluationTargets, EvaluationVars, EvaluationVarsBaseBa
 use crate::plonk::verifier::verify;
 use crate::util::{log2_ceil, transpose};
 
-const WITNESS_SIZE: usize = 1 << 5;
-const WITNESS_DEGREE: usize = WITNESS_SIZE - 1;
+pub(crate) const WITNESS_SIZE: usize = 1 << 5;
+pub(crate) const WITNESS_DEGREE: usize = WITNESS_SIZE - 1;
 
 /// Tests that the constraints imposed by the given gate are low-degree by applying them to random
 /// low-degree witness polynomials.
 pub fn test_low_degree<F: RichField + Extendable<D>, G: Gate<F, D>, const D: usize>(gate: G) {
-    let rate_bits = log2_ceil(gate.degree() + 1);
-
-    let wire_ldes = random_low_degree_matrix::<F::Extension>(gate.num_wires(), rate_bits);
-    let constant_ldes = random_low_degree_matrix::<F::Extension>(gate.num_constants(), rate_bits);
-    assert_eq!(wire_ldes.len(), constant_ldes.len());
-    let public_inputs_hash = &HashOut::rand();
-
-    let constraint_evals = wire_ldes
-        .iter()
-        .zip(constant_ldes.iter())
-        .map(|(local_wires, local_constants)| EvaluationVars {
-            local_constants,
-            local_wires,
-            public_inputs_hash,
-        })
-        .map(|vars| gate.eval_unfiltered(vars))
+    let wire_coeffs = (0..gate.num_wires())
+        .map(|_| F::Extension::rand_vec(WITNESS_SIZE))
         .collect::<Vec<_>>();
-
-    let constraint_eval_degrees = transpose(&constraint_evals)
-        .into_iter()
-        .map(PolynomialValues::new)
-        .map(|p| p.degree())
+    let constant_coeffs = (0..gate.num_constants())
+        .map(|_| F::Extension::rand_vec(WITNESS_SIZE))
         .collect::<Vec<_>>();
+    let constraint_eval_degrees = constraint_eval_degrees(&gate, &wire_coeffs, &constant_coeffs);
 
     assert_eq!(
         constraint_eval_degrees.len(),
@@ -66,9 +50,46 @@ pub fn test_low_degree<F: RichField + Extendable<D>, G: Gate<F, D>, const D: usi
     );
 }
 
-fn random_low_degree_matrix<F: Field>(num_polys: usize, rate_bits: usize) -> Vec<Vec<F>> {
-    let polys = (0..num_polys)
-        .map(|_| random_low_degree_values(rate_bits))
+/// Applies the gate's constraints to the witness polynomials with the given coefficients, each of
+/// length `WITNESS_SIZE`, and returns the degrees of the resulting constraint polynomials.
+pub(crate) fn constraint_eval_degrees<
+    F: RichField + Extendable<D>,
+    G: Gate<F, D>,
+    const D: usize,
+>(
+    gate: &G,
+    wire_coeffs: &[Vec<F::Extension>],
+    constant_coeffs: &[Vec<F::Extension>],
+) -> Vec<usize> {
+    let rate_bits = log2_ceil(gate.degree() + 1);
+
+    let wire_ldes = low_degree_matrix(wire_coeffs, rate_bits);
+    let constant_ldes = low_degree_matrix(constant_coeffs, rate_bits);
+    assert_eq!(wire_ldes.len(), constant_ldes.len());
+    let public_inputs_hash = &HashOut::rand();
+
+    let constraint_evals = wire_ldes
+        .iter()
+        .zip(constant_ldes.iter())
+        .map(|(local_wires, local_constants)| EvaluationVars {
+            local_constants,
+            local_wires,
+            public_inputs_hash,
+        })
+        .map(|vars| gate.eval_unfiltered(vars))
+        .collect::<Vec<_>>();
+
+    transpose(&constraint_evals)
+        .into_iter()
+        .map(PolynomialValues::new)
+        .map(|p| p.degree())
+        .collect()
+}
+
+fn low_degree_matrix<F: Field>(coeffs: &[Vec<F>], rate_bits: usize) -> Vec<Vec<F>> {
+    let polys = coeffs
+        .iter()
+        .map(|c| low_degree_values(c, rate_bits))
         .collect::<Vec<_>>();
 
     if polys.is_empty() {
@@ -79,8 +100,9 @@ fn random_low_degree_matrix<F: Field>(num_polys: usize, rate_bits: usize) -> Vec
     }
 }
 
-fn random_low_degree_values<F: Field>(rate_bits: usize) -> Vec<F> {
-    PolynomialCoeffs::new(F::rand_vec(WITNESS_SIZE))
+fn low_degree_values<F: Field>(coeffs: &[F], rate_bits: usize) -> Vec<F> {
+    debug_assert_eq!(coeffs.len(), WITNESS_SIZE);
+    PolynomialCoeffs::new(coeffs.to_vec())
         .lde(rate_bits)
         .fft()
         .values
@@ -3,6 +3,7 @@
 pub mod arithmetic_base;
 pub mod arithmetic_extension;
 pub mod base_sum;
+pub mod bitwise;
 pub mod constant;
 pub mod coset_interpolation;
 pub mod exponentiation;
@@ -23,5 +24,7 @@ pub mod util;
 
 // Can't use #[cfg(test)] here because it needs to be visible to other crates.
 // See https://github.com/rust-lang/cargo/issues/8379
+#[cfg(any(feature = "gate_proptest", test))]
+pub mod gate_proptest;
 #[cfg(any(feature = "gate_testing", test))]
 pub mod gate_testing;
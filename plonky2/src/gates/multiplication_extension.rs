@@ -18,6 +18,10 @@ use crate::util::serialization::{Buffer, IoResult, Read, Write};
 
 /// A gate which can perform a weighted multiplication, i.e. `result = c0 x y`. If the config
 /// supports enough routed wires, it can support several such operations in one gate.
+///
+/// Its constraints are products in the extension algebra, so this is also the gate for the algebra
+/// identity: the wires and constraints of an operation are those of its inputs and output, whatever
+/// formula evaluates the product.
 #[derive(Debug, Clone)]
 pub struct MulExtensionGate<const D: usize> {
     /// Number of multiplications performed by the gate.
@@ -107,12 +111,8 @@ impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for MulExtensionGa
             let multiplicand_0 = vars.get_local_ext_algebra(Self::wires_ith_multiplicand_0(i));
             let multiplicand_1 = vars.get_local_ext_algebra(Self::wires_ith_multiplicand_1(i));
             let output = vars.get_local_ext_algebra(Self::wires_ith_output(i));
-            let computed_output = {
-                let mul = builder.mul_ext_algebra(multiplicand_0, multiplicand_1);
-                builder.scalar_mul_ext_algebra(const_0, mul)
-            };
-
-            let diff = builder.sub_ext_algebra(output, computed_output);
+            let mul = builder.mul_ext_algebra(multiplicand_0, multiplicand_1);
+            let diff = builder.sub_scalar_mul_ext_algebra(output, const_0, mul);
             constraints.extend(diff.to_ext_target_array());
         }
 
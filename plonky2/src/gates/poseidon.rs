@@ -420,6 +420,58 @@ pub struct PoseidonGenerator<F: RichField + Extendable<D> + Poseidon, const D: u
     _phantom: PhantomData<F>,
 }
 
+impl<F: RichField + Extendable<D> + Poseidon, const D: usize> PoseidonGenerator<F, D> {
+    /// The wires computed by the permutation of `state`, as `(column, value)` pairs.
+    fn permutation_wires(mut state: [F; SPONGE_WIDTH]) -> Vec<(usize, F)> {
+        let mut wires = Vec::new();
+        let mut round_ctr = 0;
+
+        for r in 0..poseidon::HALF_N_FULL_ROUNDS {
+            <F as Poseidon>::constant_layer_field(&mut state, round_ctr);
+            if r != 0 {
+                for i in 0..SPONGE_WIDTH {
+                    wires.push((PoseidonGate::<F, D>::wire_full_sbox_0(r, i), state[i]));
+                }
+            }
+            <F as Poseidon>::sbox_layer_field(&mut state);
+            state = <F as Poseidon>::mds_layer_field(&state);
+            round_ctr += 1;
+        }
+
+        <F as Poseidon>::partial_first_constant_layer(&mut state);
+        state = <F as Poseidon>::mds_partial_layer_init(&state);
+        for r in 0..(poseidon::N_PARTIAL_ROUNDS - 1) {
+            wires.push((PoseidonGate::<F, D>::wire_partial_sbox(r), state[0]));
+            state[0] = <F as Poseidon>::sbox_monomial(state[0]);
+            state[0] += F::from_canonical_u64(<F as Poseidon>::FAST_PARTIAL_ROUND_CONSTANTS[r]);
+            state = <F as Poseidon>::mds_partial_layer_fast_field(&state, r);
+        }
+        wires.push((
+            PoseidonGate::<F, D>::wire_partial_sbox(poseidon::N_PARTIAL_ROUNDS - 1),
+            state[0],
+        ));
+        state[0] = <F as Poseidon>::sbox_monomial(state[0]);
+        state =
+            <F as Poseidon>::mds_partial_layer_fast_field(&state, poseidon::N_PARTIAL_ROUNDS - 1);
+        round_ctr += poseidon::N_PARTIAL_ROUNDS;
+
+        for r in 0..poseidon::HALF_N_FULL_ROUNDS {
+            <F as Poseidon>::constant_layer_field(&mut state, round_ctr);
+            for i in 0..SPONGE_WIDTH {
+                wires.push((PoseidonGate::<F, D>::wire_full_sbox_1(r, i), state[i]));
+            }
+            <F as Poseidon>::sbox_layer_field(&mut state);
+            state = <F as Poseidon>::mds_layer_field(&state);
+            round_ctr += 1;
+        }
+
+        for i in 0..SPONGE_WIDTH {
+            wires.push((PoseidonGate::<F, D>::wire_output(i), state[i]));
+        }
+        wires
+    }
+}
+
 impl<F: RichField + Extendable<D> + Poseidon, const D: usize> SimpleGenerator<F, D>
     for PoseidonGenerator<F, D>
 {
@@ -459,62 +511,17 @@ impl<F: RichField + Extendable<D> + Poseidon, const D: usize> SimpleGenerator<F,
             }
         }
 
-        let mut state: [F; SPONGE_WIDTH] = state.try_into().unwrap();
-        let mut round_ctr = 0;
-
-        for r in 0..poseidon::HALF_N_FULL_ROUNDS {
-            <F as Poseidon>::constant_layer_field(&mut state, round_ctr);
-            if r != 0 {
-                for i in 0..SPONGE_WIDTH {
-                    out_buffer.set_wire(
-                        local_wire(PoseidonGate::<F, D>::wire_full_sbox_0(r, i)),
-                        state[i],
-                    );
-                }
-            }
-            <F as Poseidon>::sbox_layer_field(&mut state);
-            state = <F as Poseidon>::mds_layer_field(&state);
-            round_ctr += 1;
-        }
-
-        <F as Poseidon>::partial_first_constant_layer(&mut state);
-        state = <F as Poseidon>::mds_partial_layer_init(&state);
-        for r in 0..(poseidon::N_PARTIAL_ROUNDS - 1) {
-            out_buffer.set_wire(
-                local_wire(PoseidonGate::<F, D>::wire_partial_sbox(r)),
-                state[0],
-            );
-            state[0] = <F as Poseidon>::sbox_monomial(state[0]);
-            state[0] += F::from_canonical_u64(<F as Poseidon>::FAST_PARTIAL_ROUND_CONSTANTS[r]);
-            state = <F as Poseidon>::mds_partial_layer_fast_field(&state, r);
-        }
-        out_buffer.set_wire(
-            local_wire(PoseidonGate::<F, D>::wire_partial_sbox(
-                poseidon::N_PARTIAL_ROUNDS - 1,
-            )),
-            state[0],
-        );
-        state[0] = <F as Poseidon>::sbox_monomial(state[0]);
-        state =
-            <F as Poseidon>::mds_partial_layer_fast_field(&state, poseidon::N_PARTIAL_ROUNDS - 1);
-        round_ctr += poseidon::N_PARTIAL_ROUNDS;
-
-        for r in 0..poseidon::HALF_N_FULL_ROUNDS {
-            <F as Poseidon>::constant_layer_field(&mut state, round_ctr);
-            for i in 0..SPONGE_WIDTH {
-                out_buffer.set_wire(
-                    local_wire(PoseidonGate::<F, D>::wire_full_sbox_1(r, i)),
-                    state[i],
-                );
-            }
-            <F as Poseidon>::sbox_layer_field(&mut state);
-            state = <F as Poseidon>::mds_layer_field(&state);
-            round_ctr += 1;
-        }
+        let state: [F; SPONGE_WIDTH] = state.try_into().unwrap();
+        // Structurally identical recursive verifiers repeat the same permutations, so their wires
+        // may be cached across proofs.
+        out_buffer.set_gate_wires_cached("PoseidonGate", self.row, &state, || {
+            Self::permutation_wires(state)
+        });
+    }
 
-        for i in 0..SPONGE_WIDTH {
-            out_buffer.set_wire(local_wire(PoseidonGate::<F, D>::wire_output(i)), state[i]);
-        }
+    fn cost(&self) -> usize {
+        // Dominated by the permutation's S-boxes and MDS layers.
+        2000
     }
 
     fn serialize(&self, dst: &mut Vec<u8>, _common_data: &CommonCircuitData<F, D>) -> IoResult<()> {
@@ -23,6 +23,20 @@ impl SelectorsInfo {
     pub fn num_selectors(&self) -> usize {
         self.groups.len()
     }
+
+    /// The index of the gate occupying `row`, given the values of the selector polynomials. Only
+    /// that gate's selector isn't unused on the row, and its value is the gate's index.
+    pub(crate) fn gate_index<F: RichField>(
+        &self,
+        selectors: &[PolynomialValues<F>],
+        row: usize,
+    ) -> usize {
+        selectors[..self.num_selectors()]
+            .iter()
+            .map(|selector| selector.values[row].to_canonical_u64() as usize)
+            .find(|&i| i != UNUSED_SELECTOR)
+            .expect("Every row holds a gate")
+    }
 }
 
 /// Enum listing the different selectors for lookup constraints:
@@ -114,25 +128,48 @@ pub(crate) fn selector_polynomials<F: RichField + Extendable<D>, const D: usize>
     max_degree: usize,
 ) -> (Vec<PolynomialValues<F>>, SelectorsInfo) {
     let n = instances.len();
-    let num_gates = gates.len();
-    let max_gate_degree = gates.last().expect("No gates?").0.degree();
+    let info = selectors_info(gates, max_degree);
+    let SelectorsInfo {
+        selector_indices,
+        groups,
+    } = &info;
 
     let index = |id| gates.iter().position(|g| g.0.id() == id).unwrap();
 
+    // Placeholder value to indicate that a gate doesn't use a selector polynomial.
+    let unused = F::from_canonical_usize(UNUSED_SELECTOR);
+
+    let mut polynomials = vec![PolynomialValues::zero(n); groups.len()];
+    for (j, g) in instances.iter().enumerate() {
+        let GateInstance { gate_ref, .. } = g;
+        let i = index(gate_ref.0.id());
+        let gr = selector_indices[i];
+        for g in 0..groups.len() {
+            polynomials[g].values[j] = if g == gr {
+                F::from_canonical_usize(i)
+            } else {
+                unused
+            };
+        }
+    }
+
+    (polynomials, info)
+}
+
+/// Groups the gates as [`selector_polynomials`] does, without building the polynomials.
+pub(crate) fn selectors_info<F: RichField + Extendable<D>, const D: usize>(
+    gates: &[GateRef<F, D>],
+    max_degree: usize,
+) -> SelectorsInfo {
+    let num_gates = gates.len();
+    let max_gate_degree = gates.last().expect("No gates?").0.degree();
+
     // Special case if we can use only one selector polynomial.
     if max_gate_degree + num_gates - 1 <= max_degree {
-        return (
-            vec![PolynomialValues::new(
-                instances
-                    .iter()
-                    .map(|g| F::from_canonical_usize(index(g.gate_ref.0.id())))
-                    .collect(),
-            )],
-            SelectorsInfo {
-                selector_indices: vec![0; num_gates],
-                groups: vec![0..num_gates],
-            },
-        );
+        return SelectorsInfo {
+            selector_indices: vec![0; num_gates],
+            groups: vec![0..num_gates],
+        };
     }
 
     if max_gate_degree >= max_degree {
@@ -159,28 +196,8 @@ pub(crate) fn selector_polynomials<F: RichField + Extendable<D>, const D: usize>
     // `selector_indices[i] = j` iff the `i`-th gate uses the `j`-th selector polynomial.
     let selector_indices = (0..num_gates).map(group).collect();
 
-    // Placeholder value to indicate that a gate doesn't use a selector polynomial.
-    let unused = F::from_canonical_usize(UNUSED_SELECTOR);
-
-    let mut polynomials = vec![PolynomialValues::zero(n); groups.len()];
-    for (j, g) in instances.iter().enumerate() {
-        let GateInstance { gate_ref, .. } = g;
-        let i = index(gate_ref.0.id());
-        let gr = group(i);
-        for g in 0..groups.len() {
-            polynomials[g].values[j] = if g == gr {
-                F::from_canonical_usize(i)
-            } else {
-                unused
-            };
-        }
+    SelectorsInfo {
+        selector_indices,
+        groups,
     }
-
-    (
-        polynomials,
-        SelectorsInfo {
-            selector_indices,
-            groups,
-        },
-    )
 }
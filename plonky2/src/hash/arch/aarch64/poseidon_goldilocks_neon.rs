@@ -105,6 +105,11 @@ unsafe fn sub_with_wraparound_lsr32(a: u64, b: u64) -> u64 {
         inlateout(reg) b_hi,
         options(nomem, nostack, preserves_flags, pure),
     );
+    if cfg!(feature = "hardened") {
+        // Branch-free, so that timing doesn't depend on the operands.
+        let (res_wrapped, borrow) = a.overflowing_sub(b_hi);
+        return res_wrapped - (borrow as u64) * EPSILON;
+    }
     // This could be done with a.overflowing_add(b_hi), but `checked_sub` signals to the compiler
     // that overflow is unlikely (note: this is a standard library implementation detail, not part
     // of the spec).
@@ -294,6 +294,12 @@ unsafe fn add_small(
 
 #[inline(always)]
 unsafe fn maybe_adj_sub(res_wrapped_s: __m256i, mask: __m256i) -> __m256i {
+    if cfg!(feature = "hardened") {
+        // Branch-free, so that timing doesn't depend on the operands. The high halves of `mask`
+        // are all ones iff the lane underflowed, so this subtracts EPSILON from exactly those.
+        let adj_amount = _mm256_srli_epi64::<32>(mask);
+        return _mm256_sub_epi64(res_wrapped_s, adj_amount);
+    }
     // The subtraction is very unlikely to overflow so we're best off branching.
     // The even u32s in `mask` are meaningless, so we want to ignore them. `_mm256_testz_pd`
     // branches depending on the sign bit of double-precision (64-bit) floats. Bit cast `mask` to
@@ -979,3 +985,54 @@ pub unsafe fn mds_layer(state: &[GoldilocksField; WIDTH]) -> [GoldilocksField; W
     store_state(&mut res, state);
     res
 }
+
+#[cfg(test)]
+mod tests {
+    use core::arch::x86_64::*;
+
+    use crate::field::goldilocks_field::GoldilocksField as F;
+    use crate::field::types::{Field, PrimeField64, Sample};
+    use crate::hash::arch::x86_64::poseidon_goldilocks_avx2_bmi2::{poseidon, sub_tiny};
+    use crate::hash::poseidon::Poseidon;
+
+    const SIGN_BIT: u64 = 1 << 63;
+
+    #[test]
+    fn test_sub_tiny_underflow() {
+        // Every lane of the first vector underflows, none of the second and some of the third, so
+        // both the adjusting and the non-adjusting paths of `maybe_adj_sub` are exercised.
+        let xs: [u64; 12] = [0, 1, 2, 3, 10, 20, 30, 40, 0, 100, 1, 200];
+        let ys: [u64; 12] = [1, 2, 3, 4, 1, 2, 3, 4, 5, 6, 7, 8];
+        let load = |v: &[u64; 12], i: usize| unsafe {
+            _mm256_loadu_si256(v[4 * i..].as_ptr().cast::<__m256i>())
+        };
+        let xs_s = xs.map(|x| x ^ SIGN_BIT);
+        let res_s = unsafe {
+            sub_tiny(
+                (load(&xs_s, 0), load(&xs_s, 1), load(&xs_s, 2)),
+                (load(&ys, 0), load(&ys, 1), load(&ys, 2)),
+            )
+        };
+        let mut res = [0u64; 12];
+        unsafe {
+            _mm256_storeu_si256(res[0..4].as_mut_ptr().cast::<__m256i>(), res_s.0);
+            _mm256_storeu_si256(res[4..8].as_mut_ptr().cast::<__m256i>(), res_s.1);
+            _mm256_storeu_si256(res[8..12].as_mut_ptr().cast::<__m256i>(), res_s.2);
+        }
+        for i in 0..12 {
+            let expected = F::from_canonical_u64(xs[i]) - F::from_canonical_u64(ys[i]);
+            let actual = F::from_noncanonical_u64(res[i] ^ SIGN_BIT);
+            assert_eq!(actual.to_canonical_u64(), expected.to_canonical_u64());
+        }
+    }
+
+    #[test]
+    fn test_poseidon_matches_scalar() {
+        let inputs = [[F::ZERO; 12], [F::NEG_ONE; 12], F::rand_array()];
+        for input in inputs {
+            let expected = <F as Poseidon>::poseidon(input);
+            let actual = unsafe { poseidon(&input) };
+            assert_eq!(actual, expected);
+        }
+    }
+}
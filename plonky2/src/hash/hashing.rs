@@ -114,6 +114,16 @@ pub fn compress<F: Field, P: PlonkyPermutation<F>>(x: HashOut<F>, y: HashOut<F>)
     }
 }
 
+/// Absorbs `inputs` into a sponge, one `P::RATE`-sized chunk per permutation, as
+/// `hash_n_to_m_no_pad` does. A message absorbed in several calls yields the same state as one
+/// absorbed at once, provided every call but the last absorbs a multiple of `P::RATE` elements.
+pub fn absorb_no_pad<F: RichField, P: PlonkyPermutation<F>>(perm: &mut P, inputs: &[F]) {
+    for input_chunk in inputs.chunks(P::RATE) {
+        perm.set_from_slice(input_chunk, 0);
+        perm.permute();
+    }
+}
+
 /// Hash a message without any padding step. Note that this can enable length-extension attacks.
 /// However, it is still collision-resistant in cases where the input has a fixed length.
 pub fn hash_n_to_m_no_pad<F: RichField, P: PlonkyPermutation<F>>(
@@ -123,10 +133,7 @@ pub fn hash_n_to_m_no_pad<F: RichField, P: PlonkyPermutation<F>>(
     let mut perm = P::new(repeat(F::ZERO));
 
     // Absorb all input chunks.
-    for input_chunk in inputs.chunks(P::RATE) {
-        perm.set_from_slice(input_chunk, 0);
-        perm.permute();
-    }
+    absorb_no_pad(&mut perm, inputs);
 
     // Squeeze until we have the desired number of outputs.
     let mut outputs = Vec::new();
@@ -104,6 +104,7 @@ impl<F: RichField> PlonkyPermutation<F> for KeccakPermutation<F> {
 pub struct KeccakHash<const N: usize>;
 impl<F: RichField, const N: usize> Hasher<F> for KeccakHash<N> {
     const HASH_SIZE: usize = N;
+    const ID: &'static str = "keccak";
     type Hash = BytesHash<N>;
     type Permutation = KeccakPermutation<F>;
 
@@ -0,0 +1,191 @@
+//! A Keccak-256 transcript whose challenges an EVM verifier can recompute with the `KECCAK256`
+//! opcode and `uint256` arithmetic alone.
+//!
+//! The challenger is the usual overwrite-mode duplex sponge over a state of 12 field elements, of
+//! which the first 8 are the rate, with the permutation below. Observed field elements are
+//! canonical `uint64`s; observed `BytesHash` digests are split into 7-byte little-endian chunks, as
+//! in `GenericHashOut::to_vec`. Challenges are popped from the end of the squeezed rate, so the
+//! first challenge after a permutation is `s_7`, the second `s_6`, and so on.
+//!
+//! The permutation sends a state `s_0, ..., s_11` to `s'_0, ..., s'_11`, where
+//! ```text
+//! seed = keccak256(abi.encodePacked(uint64(s_0), ..., uint64(s_11)))
+//! s'_i = uint256(keccak256(abi.encodePacked(seed, uint8(i)))) % p
+//! ```
+//! i.e. each element is encoded as 8 big-endian bytes, and each output is a 256-bit big-endian
+//! word reduced modulo the field order `p`. The bias of this reduction is below `2^-190` for
+//! 64-bit fields.
+
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use keccak_hash::keccak;
+
+use crate::hash::hash_types::{BytesHash, RichField};
+use crate::hash::hashing::PlonkyPermutation;
+use crate::hash::keccak::{KeccakHash, SPONGE_RATE, SPONGE_WIDTH};
+use crate::plonk::config::Hasher;
+
+/// The Keccak-256 pseudo-permutation described in the module documentation.
+#[derive(Copy, Clone, Default, Debug, PartialEq)]
+pub struct SolidityKeccakPermutation<F: RichField> {
+    state: [F; SPONGE_WIDTH],
+}
+
+impl<F: RichField> Eq for SolidityKeccakPermutation<F> {}
+
+impl<F: RichField> AsRef<[F]> for SolidityKeccakPermutation<F> {
+    fn as_ref(&self) -> &[F] {
+        &self.state
+    }
+}
+
+/// Reduces a big-endian 256-bit word modulo the order of `F`.
+fn reduce_be_word<F: RichField>(word: &[u8; 32]) -> F {
+    let order = F::ORDER as u128;
+    let reduced = word
+        .chunks_exact(size_of::<u64>())
+        .map(|limb| u64::from_be_bytes(limb.try_into().unwrap()))
+        .fold(0u128, |acc, limb| ((acc << 64) | limb as u128) % order);
+    F::from_canonical_u64(reduced as u64)
+}
+
+impl<F: RichField> PlonkyPermutation<F> for SolidityKeccakPermutation<F> {
+    const RATE: usize = SPONGE_RATE;
+    const WIDTH: usize = SPONGE_WIDTH;
+
+    fn new<I: IntoIterator<Item = F>>(elts: I) -> Self {
+        let mut perm = Self {
+            state: [F::default(); SPONGE_WIDTH],
+        };
+        perm.set_from_iter(elts, 0);
+        perm
+    }
+
+    fn set_elt(&mut self, elt: F, idx: usize) {
+        self.state[idx] = elt;
+    }
+
+    fn set_from_slice(&mut self, elts: &[F], start_idx: usize) {
+        let begin = start_idx;
+        let end = start_idx + elts.len();
+        self.state[begin..end].copy_from_slice(elts);
+    }
+
+    fn set_from_iter<I: IntoIterator<Item = F>>(&mut self, elts: I, start_idx: usize) {
+        for (s, e) in self.state[start_idx..].iter_mut().zip(elts) {
+            *s = e;
+        }
+    }
+
+    fn permute(&mut self) {
+        let state_bytes: Vec<u8> = self
+            .state
+            .iter()
+            .flat_map(|x| x.to_canonical_u64().to_be_bytes())
+            .collect();
+        let seed = keccak(state_bytes).to_fixed_bytes();
+
+        for (i, s) in self.state.iter_mut().enumerate() {
+            let mut input = [0u8; 33];
+            input[..32].copy_from_slice(&seed);
+            input[32] = i as u8;
+            *s = reduce_be_word(&keccak(input).to_fixed_bytes());
+        }
+    }
+
+    fn squeeze(&self) -> &[F] {
+        &self.state[..Self::RATE]
+    }
+}
+
+/// Keccak-256 hash function, with challenges derived by `SolidityKeccakPermutation`. Digests are
+/// the same as `KeccakHash`'s.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct SolidityKeccakHash<const N: usize>;
+impl<F: RichField, const N: usize> Hasher<F> for SolidityKeccakHash<N> {
+    const HASH_SIZE: usize = N;
+    const ID: &'static str = "solidity-keccak";
+    type Hash = BytesHash<N>;
+    type Permutation = SolidityKeccakPermutation<F>;
+
+    fn hash_no_pad(input: &[F]) -> Self::Hash {
+        <KeccakHash<N> as Hasher<F>>::hash_no_pad(input)
+    }
+
+    fn two_to_one(left: Self::Hash, right: Self::Hash) -> Self::Hash {
+        <KeccakHash<N> as Hasher<F>>::two_to_one(left, right)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::field::goldilocks_field::GoldilocksField;
+    use crate::field::types::{Field, Field64, PrimeField64, Sample};
+    use crate::iop::challenger::Challenger;
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, SolidityKeccakGoldilocksConfig};
+
+    type F = GoldilocksField;
+
+    #[test]
+    fn test_reduce_be_word() {
+        let mut word = [0u8; 32];
+        word[24..].copy_from_slice(&(F::ORDER + 5).to_be_bytes());
+        assert_eq!(reduce_be_word::<F>(&word), F::from_canonical_u64(5));
+
+        // 2^64 = 2^32 - 1 mod p.
+        let mut word = [0u8; 32];
+        word[23] = 1;
+        assert_eq!(
+            reduce_be_word::<F>(&word),
+            F::from_canonical_u64(u32::MAX as u64)
+        );
+    }
+
+    #[test]
+    fn test_challenges_match_encoding() {
+        let inputs = F::rand_vec(3);
+        let mut challenger = Challenger::<F, SolidityKeccakHash<25>>::new();
+        challenger.observe_elements(&inputs);
+        let challenges = challenger.get_n_challenges(2);
+
+        // Recompute the first permutation as an EVM verifier would.
+        let mut state_bytes = Vec::new();
+        for i in 0..SPONGE_WIDTH {
+            let x = inputs.get(i).map_or(0, |x| x.to_canonical_u64());
+            state_bytes.extend_from_slice(&x.to_be_bytes());
+        }
+        let seed = keccak(state_bytes).to_fixed_bytes();
+        let output = |i: u8| {
+            let mut input = seed.to_vec();
+            input.push(i);
+            reduce_be_word::<F>(&keccak(input).to_fixed_bytes())
+        };
+        assert_eq!(challenges, vec![output(7), output(6)]);
+    }
+
+    #[test]
+    fn test_prove_with_solidity_keccak_transcript() -> Result<()> {
+        const D: usize = 2;
+        type C = SolidityKeccakGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x = builder.add_virtual_target();
+        let y = builder.square(x);
+        builder.register_public_input(y);
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::rand());
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        data.verify(proof)
+    }
+}
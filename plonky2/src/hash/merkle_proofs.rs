@@ -121,25 +121,11 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
     ) {
         debug_assert!(H::AlgebraicPermutation::RATE >= NUM_HASH_OUT_ELTS);
 
-        let zero = self.zero();
         let mut state: HashOutTarget = self.hash_or_noop::<H>(leaf_data);
         debug_assert_eq!(state.elements.len(), NUM_HASH_OUT_ELTS);
 
         for (&bit, &sibling) in leaf_index_bits.iter().zip(&proof.siblings) {
-            debug_assert_eq!(sibling.elements.len(), NUM_HASH_OUT_ELTS);
-
-            let mut perm_inputs = H::AlgebraicPermutation::default();
-            perm_inputs.set_from_slice(&state.elements, 0);
-            perm_inputs.set_from_slice(&sibling.elements, NUM_HASH_OUT_ELTS);
-            // Ensure the rest of the state, if any, is zero:
-            perm_inputs.set_from_iter(std::iter::repeat(zero), 2 * NUM_HASH_OUT_ELTS);
-            let perm_outs = self.permute_swapped::<H>(perm_inputs, bit);
-            let hash_outs = perm_outs.squeeze()[0..NUM_HASH_OUT_ELTS]
-                .try_into()
-                .unwrap();
-            state = HashOutTarget {
-                elements: hash_outs,
-            };
+            state = self.hash_merkle_siblings::<H>(state, sibling, bit);
         }
 
         for i in 0..NUM_HASH_OUT_ELTS {
@@ -151,6 +137,85 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         }
     }
 
+    /// Verifies that the given leaf data is present at the given index in a Merkle tree of depth
+    /// `depth` with the given root, where `depth` is only known at proving time and is at most
+    /// `proof.siblings.len()`. The index is given by its `proof.siblings.len()` little-endian bits,
+    /// of which those at positions `depth` and above must be zero. The siblings past the first
+    /// `depth` ones are padding, and are ignored.
+    pub fn verify_variable_depth_merkle_proof<H: AlgebraicHasher<F>>(
+        &mut self,
+        leaf_data: Vec<Target>,
+        leaf_index_bits: &[BoolTarget],
+        depth: Target,
+        merkle_root: HashOutTarget,
+        proof: &MerkleProofTarget,
+    ) {
+        let max_depth = proof.siblings.len();
+        assert_eq!(
+            leaf_index_bits.len(),
+            max_depth,
+            "Expected one index bit per sibling"
+        );
+
+        // `depth` equals exactly one of `0..=max_depth`. Layer `i` of the path is active iff
+        // `depth > i`, i.e. iff `depth` equals none of `0..=i`.
+        let depth_is = (0..=max_depth)
+            .map(|d| {
+                let d = self.constant(F::from_canonical_usize(d));
+                self.is_equal(depth, d).target
+            })
+            .collect::<Vec<_>>();
+        let one = self.one();
+        let num_matches = self.add_many(&depth_is);
+        self.connect(num_matches, one);
+
+        let mut state = self.hash_or_noop::<H>(leaf_data);
+        let mut depth_reached = self.zero();
+        for ((&bit, &sibling), &depth_is_layer) in
+            leaf_index_bits.iter().zip(&proof.siblings).zip(&depth_is)
+        {
+            depth_reached = self.add(depth_reached, depth_is_layer);
+            let active = BoolTarget::new_unsafe(self.sub(one, depth_reached));
+            // Index bits above the tree's depth are padding, and must be zero.
+            let padding_bit = self.mul(bit.target, depth_reached);
+            self.assert_zero(padding_bit);
+
+            let parent = self.hash_merkle_siblings::<H>(state, sibling, bit);
+            state = HashOutTarget {
+                elements: core::array::from_fn(|i| {
+                    self.select(active, parent.elements[i], state.elements[i])
+                }),
+            };
+        }
+
+        self.connect_hashes(state, merkle_root);
+    }
+
+    /// The digest of the parent of a Merkle tree node with digest `state` and sibling `sibling`,
+    /// where `bit` tells whether the node is its parent's right child.
+    fn hash_merkle_siblings<H: AlgebraicHasher<F>>(
+        &mut self,
+        state: HashOutTarget,
+        sibling: HashOutTarget,
+        bit: BoolTarget,
+    ) -> HashOutTarget {
+        debug_assert_eq!(sibling.elements.len(), NUM_HASH_OUT_ELTS);
+
+        let zero = self.zero();
+        let mut perm_inputs = H::AlgebraicPermutation::default();
+        perm_inputs.set_from_slice(&state.elements, 0);
+        perm_inputs.set_from_slice(&sibling.elements, NUM_HASH_OUT_ELTS);
+        // Ensure the rest of the state, if any, is zero:
+        perm_inputs.set_from_iter(std::iter::repeat(zero), 2 * NUM_HASH_OUT_ELTS);
+        let perm_outs = self.permute_swapped::<H>(perm_inputs, bit);
+        let hash_outs = perm_outs.squeeze()[0..NUM_HASH_OUT_ELTS]
+            .try_into()
+            .unwrap();
+        HashOutTarget {
+            elements: hash_outs,
+        }
+    }
+
     pub fn connect_hashes(&mut self, x: HashOutTarget, y: HashOutTarget) {
         for i in 0..NUM_HASH_OUT_ELTS {
             self.connect(x.elements[i], y.elements[i]);
@@ -177,6 +242,7 @@ mod tests {
 
     use super::*;
     use crate::field::types::Field;
+    use crate::hash::hash_types::HashOut;
     use crate::hash::merkle_tree::MerkleTree;
     use crate::iop::witness::{PartialWitness, WitnessWrite};
     use crate::plonk::circuit_builder::CircuitBuilder;
@@ -232,4 +298,74 @@ mod tests {
 
         verify(proof, &data.verifier_only, &data.common)
     }
+    /// Proves that a random leaf of a tree of depth 5 is at `index_offset` past its index, in a
+    /// tree of depth `claimed_depth`, using a proof padded to a maximum depth of 8.
+    fn prove_variable_depth_merkle_proof(claimed_depth: usize, index_offset: usize) -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type H = <C as GenericConfig<D>>::InnerHasher;
+        let config = CircuitConfig::standard_recursion_config();
+        let max_depth = 8;
+        let depth = 5;
+
+        let leaves = random_data::<F>(1 << depth, 7);
+        let tree = MerkleTree::<F, H>::new(leaves, 0);
+        let i = OsRng.gen_range(0..1 << depth);
+        let proof = tree.prove(i);
+
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let data_t = builder.add_virtual_targets(7);
+        pw.set_target_arr(&data_t, &tree.leaves[i]);
+        let index_t = builder.add_virtual_target();
+        pw.set_target(index_t, F::from_canonical_usize(i + index_offset));
+        let index_bits = builder.split_le(index_t, max_depth);
+        let depth_t = builder.add_virtual_target();
+        pw.set_target(depth_t, F::from_canonical_usize(claimed_depth));
+        let root_t = builder.add_virtual_hash();
+        pw.set_hash_target(root_t, tree.cap.0[0]);
+        let proof_t = MerkleProofTarget {
+            siblings: builder.add_virtual_hashes(max_depth),
+        };
+        for (j, &sibling) in proof_t.siblings.iter().enumerate() {
+            let sibling_value = proof.siblings.get(j).copied().unwrap_or(HashOut::ZERO);
+            pw.set_hash_target(sibling, sibling_value);
+        }
+
+        builder.verify_variable_depth_merkle_proof::<H>(
+            data_t,
+            &index_bits,
+            depth_t,
+            root_t,
+            &proof_t,
+        );
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        data.verify(proof)
+    }
+
+    #[test]
+    fn test_variable_depth_merkle_proof() -> Result<()> {
+        prove_variable_depth_merkle_proof(5, 0)
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_variable_depth_merkle_proof_wrong_depth() {
+        prove_variable_depth_merkle_proof(4, 0).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_variable_depth_merkle_proof_depth_too_large() {
+        prove_variable_depth_merkle_proof(9, 0).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_variable_depth_merkle_proof_nonzero_padding_bit() {
+        prove_variable_depth_merkle_proof(5, 1 << 5).unwrap();
+    }
 }
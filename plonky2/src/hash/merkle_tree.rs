@@ -1,13 +1,16 @@
+use alloc::vec;
 use alloc::vec::Vec;
+use core::iter::repeat;
 use core::mem::MaybeUninit;
 use core::slice;
 
 use plonky2_maybe_rayon::*;
 use serde::{Deserialize, Serialize};
 
-use crate::hash::hash_types::RichField;
+use crate::hash::hash_types::{HashOut, RichField, NUM_HASH_OUT_ELTS};
+use crate::hash::hashing::{absorb_no_pad, PlonkyPermutation};
 use crate::hash::merkle_proofs::MerkleProof;
-use crate::plonk::config::{GenericHashOut, Hasher};
+use crate::plonk::config::{AlgebraicHasher, GenericHashOut, Hasher};
 use crate::util::log2_strict;
 
 /// The Merkle cap of height `h` of a Merkle tree is the `h`-th layer (from the root) of the tree.
@@ -95,6 +98,37 @@ fn fill_subtree<F: RichField, H: Hasher<F>>(
     }
 }
 
+/// Like `fill_subtree`, but each leaf of `leaves` is written by `fill`, given the index of the leaf
+/// in the whole tree. Each leaf is hashed as soon as it is written, while it is still in cache.
+fn fill_subtree_from_fn<F: RichField, H: Hasher<F>>(
+    digests_buf: &mut [MaybeUninit<H::Hash>],
+    leaves: &mut [Vec<F>],
+    leaf_offset: usize,
+    fill: &(impl Fn(usize, &mut Vec<F>) + Sync),
+) -> H::Hash {
+    assert_eq!(leaves.len(), digests_buf.len() / 2 + 1);
+    if digests_buf.is_empty() {
+        fill(leaf_offset, &mut leaves[0]);
+        H::hash_or_noop(&leaves[0])
+    } else {
+        // Same layout as in `fill_subtree`.
+        let (left_digests_buf, right_digests_buf) = digests_buf.split_at_mut(digests_buf.len() / 2);
+        let (left_digest_mem, left_digests_buf) = left_digests_buf.split_last_mut().unwrap();
+        let (right_digest_mem, right_digests_buf) = right_digests_buf.split_first_mut().unwrap();
+        let (left_leaves, right_leaves) = leaves.split_at_mut(leaves.len() / 2);
+        let right_offset = leaf_offset + left_leaves.len();
+
+        let (left_digest, right_digest) = plonky2_maybe_rayon::join(
+            || fill_subtree_from_fn::<F, H>(left_digests_buf, left_leaves, leaf_offset, fill),
+            || fill_subtree_from_fn::<F, H>(right_digests_buf, right_leaves, right_offset, fill),
+        );
+
+        left_digest_mem.write(left_digest);
+        right_digest_mem.write(right_digest);
+        H::two_to_one(left_digest, right_digest)
+    }
+}
+
 fn fill_digests_buf<F: RichField, H: Hasher<F>>(
     digests_buf: &mut [MaybeUninit<H::Hash>],
     cap_buf: &mut [MaybeUninit<H::Hash>],
@@ -131,6 +165,70 @@ fn fill_digests_buf<F: RichField, H: Hasher<F>>(
     );
 }
 
+/// Like `fill_subtree`, but for a subtree of a tree extending an old tree with `old_leaves_len`
+/// leaves, `old_digests` and `old_cap`, where the subtree's leaves start at `leaf_offset`. Subtrees
+/// which coincide with one of the old tree's cap subtrees are copied rather than rehashed.
+fn fill_subtree_reusing<F: RichField, H: Hasher<F>>(
+    digests_buf: &mut [MaybeUninit<H::Hash>],
+    leaves: &[Vec<F>],
+    leaf_offset: usize,
+    old_leaves_len: usize,
+    old_digests: &[H::Hash],
+    old_cap: &[H::Hash],
+) -> H::Hash {
+    let old_subtree_leaves_len = old_leaves_len / old_cap.len();
+    let all_old = leaf_offset + leaves.len() <= old_leaves_len;
+    let all_new = leaf_offset >= old_leaves_len;
+    if all_new || (all_old && leaves.len() < old_subtree_leaves_len) {
+        // Either there's nothing to reuse, or the subtree is smaller than `old`'s cap subtrees,
+        // whose inner digests we don't bother locating.
+        fill_subtree::<F, H>(digests_buf, leaves)
+    } else if all_old && leaves.len() == old_subtree_leaves_len {
+        let old_subtree_index = leaf_offset / old_subtree_leaves_len;
+        let old_subtree_digests_len = old_digests.len() / old_cap.len();
+        let old_subtree_digests = &old_digests[old_subtree_digests_len * old_subtree_index
+            ..old_subtree_digests_len * (old_subtree_index + 1)];
+        for (mem, &digest) in digests_buf.iter_mut().zip(old_subtree_digests) {
+            mem.write(digest);
+        }
+        old_cap[old_subtree_index]
+    } else {
+        // The subtree contains both old and new leaves, or several of `old`'s cap subtrees.
+        let (left_digests_buf, right_digests_buf) = digests_buf.split_at_mut(digests_buf.len() / 2);
+        let (left_digest_mem, left_digests_buf) = left_digests_buf.split_last_mut().unwrap();
+        let (right_digest_mem, right_digests_buf) = right_digests_buf.split_first_mut().unwrap();
+        let (left_leaves, right_leaves) = leaves.split_at(leaves.len() / 2);
+        let right_offset = leaf_offset + left_leaves.len();
+
+        let (left_digest, right_digest) = plonky2_maybe_rayon::join(
+            || {
+                fill_subtree_reusing::<F, H>(
+                    left_digests_buf,
+                    left_leaves,
+                    leaf_offset,
+                    old_leaves_len,
+                    old_digests,
+                    old_cap,
+                )
+            },
+            || {
+                fill_subtree_reusing::<F, H>(
+                    right_digests_buf,
+                    right_leaves,
+                    right_offset,
+                    old_leaves_len,
+                    old_digests,
+                    old_cap,
+                )
+            },
+        );
+
+        left_digest_mem.write(left_digest);
+        right_digest_mem.write(right_digest);
+        H::two_to_one(left_digest, right_digest)
+    }
+}
+
 impl<F: RichField, H: Hasher<F>> MerkleTree<F, H> {
     pub fn new(leaves: Vec<Vec<F>>, cap_height: usize) -> Self {
         let log2_leaves_len = log2_strict(leaves.len());
@@ -165,6 +263,173 @@ impl<F: RichField, H: Hasher<F>> MerkleTree<F, H> {
         }
     }
 
+    /// Like `new`, but the `num_leaves` leaves are generated with `leaf`, given a leaf's index.
+    /// Each leaf is hashed by the task which generates it, right after it is generated, which saves
+    /// a separate pass over the leaves when they are computed from other data, e.g. LDE values.
+    pub fn new_from_fn(
+        num_leaves: usize,
+        cap_height: usize,
+        leaf: impl Fn(usize) -> Vec<F> + Sync,
+    ) -> Self {
+        Self::new_from_fn_in(vec![Vec::new(); num_leaves], cap_height, |i, buf| {
+            *buf = leaf(i);
+        })
+    }
+
+    /// Like `new_from_fn`, but each leaf is written by `fill` into the corresponding buffer of
+    /// `leaves`, whose allocation is reused. `fill` is responsible for clearing the buffer.
+    pub fn new_from_fn_in(
+        mut leaves: Vec<Vec<F>>,
+        cap_height: usize,
+        fill: impl Fn(usize, &mut Vec<F>) + Sync,
+    ) -> Self {
+        let num_leaves = leaves.len();
+        let log2_leaves_len = log2_strict(num_leaves);
+        assert!(
+            cap_height <= log2_leaves_len,
+            "cap_height={} should be at most log2(leaves.len())={}",
+            cap_height,
+            log2_leaves_len
+        );
+
+        let num_digests = 2 * (num_leaves - (1 << cap_height));
+        let mut digests = Vec::with_capacity(num_digests);
+
+        let len_cap = 1 << cap_height;
+        let mut cap = Vec::with_capacity(len_cap);
+
+        let digests_buf = capacity_up_to_mut(&mut digests, num_digests);
+        let cap_buf = capacity_up_to_mut(&mut cap, len_cap);
+        let subtree_leaves_len = num_leaves >> cap_height;
+        let subtree_digests_len = num_digests >> cap_height;
+        if subtree_digests_len == 0 {
+            // The tree is all cap, and each subtree is a single leaf.
+            cap_buf
+                .par_iter_mut()
+                .zip(leaves.par_chunks_exact_mut(1))
+                .enumerate()
+                .for_each(|(i, (cap_buf, subtree_leaves))| {
+                    cap_buf.write(fill_subtree_from_fn::<F, H>(
+                        &mut [],
+                        subtree_leaves,
+                        i,
+                        &fill,
+                    ));
+                });
+        } else {
+            digests_buf
+                .par_chunks_exact_mut(subtree_digests_len)
+                .zip(cap_buf)
+                .zip(leaves.par_chunks_exact_mut(subtree_leaves_len))
+                .enumerate()
+                .for_each(|(i, ((subtree_digests, subtree_cap), subtree_leaves))| {
+                    subtree_cap.write(fill_subtree_from_fn::<F, H>(
+                        subtree_digests,
+                        subtree_leaves,
+                        i * subtree_leaves_len,
+                        &fill,
+                    ));
+                });
+        }
+
+        unsafe {
+            // SAFETY: Every subtree's digests and cap entry were initialized above.
+            digests.set_len(num_digests);
+            cap.set_len(len_cap);
+        }
+
+        Self {
+            leaves,
+            digests,
+            cap: MerkleCap(cap),
+        }
+    }
+
+    /// Builds the tree whose leaves are those of `self` followed by `new_leaves`, e.g. after rows
+    /// are appended to a trace whose rows are committed as leaves. The digests of `self`'s cap
+    /// subtrees are reused, so only subtrees containing new leaves are hashed, as long as the new
+    /// tree's cap subtrees are at least as large as the old tree's.
+    ///
+    /// This can't extend the commitments in a `PolynomialBatch`, such as plonky2's wire commitment
+    /// or a STARK's trace commitment. Their leaves are evaluations of a low-degree extension. Each
+    /// evaluation depends on every row of the trace, and on the evaluation domain, which grows
+    /// with the trace. Appending rows thus changes every leaf, and nothing can be reused. An
+    /// application growing its trace over time can instead commit to each chunk of new rows as a
+    /// separate batch.
+    pub fn extend(self, new_leaves: Vec<Vec<F>>, cap_height: usize) -> Self {
+        let MerkleTree {
+            mut leaves,
+            digests: old_digests,
+            cap: old_cap,
+        } = self;
+        let old_leaves_len = leaves.len();
+        leaves.extend(new_leaves);
+
+        let log2_leaves_len = log2_strict(leaves.len());
+        assert!(
+            cap_height <= log2_leaves_len,
+            "cap_height={} should be at most log2(leaves.len())={}",
+            cap_height,
+            log2_leaves_len
+        );
+
+        let num_digests = 2 * (leaves.len() - (1 << cap_height));
+        let mut digests = Vec::with_capacity(num_digests);
+
+        let len_cap = 1 << cap_height;
+        let mut cap = Vec::with_capacity(len_cap);
+
+        let digests_buf = capacity_up_to_mut(&mut digests, num_digests);
+        let cap_buf = capacity_up_to_mut(&mut cap, len_cap);
+        let subtree_leaves_len = leaves.len() >> cap_height;
+        let subtree_digests_len = num_digests >> cap_height;
+        if subtree_digests_len == 0 {
+            // The tree is all cap, and each subtree is a single leaf.
+            cap_buf
+                .par_iter_mut()
+                .zip(&leaves)
+                .enumerate()
+                .for_each(|(i, (cap_buf, leaf))| {
+                    cap_buf.write(fill_subtree_reusing::<F, H>(
+                        &mut [],
+                        slice::from_ref(leaf),
+                        i,
+                        old_leaves_len,
+                        &old_digests,
+                        &old_cap.0,
+                    ));
+                });
+        } else {
+            digests_buf
+                .par_chunks_exact_mut(subtree_digests_len)
+                .zip(cap_buf)
+                .zip(leaves.par_chunks_exact(subtree_leaves_len))
+                .enumerate()
+                .for_each(|(i, ((subtree_digests, subtree_cap), subtree_leaves))| {
+                    subtree_cap.write(fill_subtree_reusing::<F, H>(
+                        subtree_digests,
+                        subtree_leaves,
+                        i * subtree_leaves_len,
+                        old_leaves_len,
+                        &old_digests,
+                        &old_cap.0,
+                    ));
+                });
+        }
+
+        unsafe {
+            // SAFETY: Every subtree's digests and cap entry were initialized above.
+            digests.set_len(num_digests);
+            cap.set_len(len_cap);
+        }
+
+        Self {
+            leaves,
+            digests,
+            cap: MerkleCap(cap),
+        }
+    }
+
     pub fn get(&self, i: usize) -> &[F] {
         &self.leaves[i]
     }
@@ -207,6 +472,63 @@ impl<F: RichField, H: Hasher<F>> MerkleTree<F, H> {
     }
 }
 
+/// A leaf hash in progress, which lets leaf hashing be split by column range, e.g. across machines
+/// which each hold some of the columns of a large trace. Column segments must be absorbed in order,
+/// and every segment but the last must have a length which is a multiple of the sponge rate.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct PartialLeafHash<F: RichField, H: AlgebraicHasher<F>> {
+    pub state: H::Permutation,
+    /// The number of leaf elements absorbed so far.
+    pub len: usize,
+}
+
+impl<F: RichField, H: AlgebraicHasher<F>> Default for PartialLeafHash<F, H> {
+    fn default() -> Self {
+        Self {
+            state: H::Permutation::new(repeat(F::ZERO)),
+            len: 0,
+        }
+    }
+}
+
+impl<F: RichField, H: AlgebraicHasher<F>> PartialLeafHash<F, H> {
+    pub fn absorb(&mut self, segment: &[F]) {
+        assert_eq!(
+            self.len % H::Permutation::RATE,
+            0,
+            "Only the last segment of a leaf may have a length which isn't a multiple of the rate"
+        );
+        absorb_no_pad(&mut self.state, segment);
+        self.len += segment.len();
+    }
+
+    /// Returns the hash of the leaf, which matches `H::hash_or_noop` on the whole leaf.
+    pub fn finish(self) -> HashOut<F> {
+        assert!(
+            self.len * 8 > H::HASH_SIZE,
+            "Leaves short enough to be used as their own hash can't be hashed by segments"
+        );
+        HashOut {
+            elements: self.state.squeeze()[..NUM_HASH_OUT_ELTS]
+                .try_into()
+                .unwrap(),
+        }
+    }
+}
+
+/// Absorbs a segment of each leaf, given as `leaf_segments[i]` for leaf `i`, into the
+/// corresponding partial hash.
+pub fn absorb_leaf_segments<F: RichField, H: AlgebraicHasher<F>>(
+    partial_hashes: &mut [PartialLeafHash<F, H>],
+    leaf_segments: &[Vec<F>],
+) {
+    assert_eq!(partial_hashes.len(), leaf_segments.len());
+    partial_hashes
+        .par_iter_mut()
+        .zip(leaf_segments)
+        .for_each(|(partial_hash, segment)| partial_hash.absorb(segment));
+}
+
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
@@ -236,6 +558,27 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_partial_leaf_hash() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type H = <C as GenericConfig<D>>::Hasher;
+
+        let leaves = random_data::<F>(8, 21);
+        let mut partial_hashes = vec![PartialLeafHash::<F, H>::default(); leaves.len()];
+        for columns in [0..8, 8..16, 16..21] {
+            let segments = leaves
+                .iter()
+                .map(|leaf| leaf[columns.clone()].to_vec())
+                .collect::<Vec<_>>();
+            absorb_leaf_segments(&mut partial_hashes, &segments);
+        }
+        for (leaf, partial_hash) in leaves.iter().zip(partial_hashes) {
+            assert_eq!(partial_hash.finish(), H::hash_or_noop(leaf));
+        }
+    }
+
     #[test]
     #[should_panic]
     fn test_cap_height_too_big() {
@@ -265,6 +608,50 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_extend() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type H = <C as GenericConfig<D>>::Hasher;
+
+        let leaves = random_data::<F>(1 << 8, 7);
+        for (old_len, old_cap_height, cap_height) in [
+            (1 << 6, 2, 2),
+            (1 << 6, 0, 3),
+            (1 << 7, 4, 1),
+            (1 << 8, 3, 3),
+            (1 << 5, 5, 8),
+        ] {
+            let old = MerkleTree::<F, H>::new(leaves[..old_len].to_vec(), old_cap_height);
+            let extended = old.extend(leaves[old_len..].to_vec(), cap_height);
+            assert_eq!(extended, MerkleTree::new(leaves.clone(), cap_height));
+        }
+    }
+
+    #[test]
+    fn test_new_from_fn() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type H = <C as GenericConfig<D>>::Hasher;
+
+        let leaves = random_data::<F>(1 << 6, 7);
+        for cap_height in [0, 3, 6] {
+            let tree =
+                MerkleTree::<F, H>::new_from_fn(leaves.len(), cap_height, |i| leaves[i].clone());
+            assert_eq!(tree, MerkleTree::new(leaves.clone(), cap_height));
+
+            // Stale contents of reused buffers must not leak into the leaves.
+            let buffers = random_data::<F>(leaves.len(), 3);
+            let tree = MerkleTree::<F, H>::new_from_fn_in(buffers, cap_height, |i, buf| {
+                buf.clear();
+                buf.extend_from_slice(&leaves[i]);
+            });
+            assert_eq!(tree, MerkleTree::new(leaves.clone(), cap_height));
+        }
+    }
+
     #[test]
     fn test_merkle_trees() -> Result<()> {
         const D: usize = 2;
@@ -2,8 +2,11 @@ mod arch;
 pub mod hash_types;
 pub mod hashing;
 pub mod keccak;
+pub mod keccak_transcript;
 pub mod merkle_proofs;
 pub mod merkle_tree;
 pub mod path_compression;
 pub mod poseidon;
+#[cfg(feature = "poseidon_bn254")]
+pub mod poseidon_bn254;
 pub mod poseidon_goldilocks;
@@ -2,6 +2,8 @@ use alloc::vec;
 use alloc::vec::Vec;
 use core::marker::PhantomData;
 
+use anyhow::{ensure, Result};
+
 use crate::field::extension::{Extendable, FieldExtension};
 use crate::hash::hash_types::{HashOut, HashOutTarget, MerkleCapTarget, RichField};
 use crate::hash::hashing::PlonkyPermutation;
@@ -10,6 +12,13 @@ use crate::iop::ext_target::ExtensionTarget;
 use crate::iop::target::Target;
 use crate::plonk::circuit_builder::CircuitBuilder;
 use crate::plonk::config::{AlgebraicHasher, GenericHashOut, Hasher};
+use crate::util::serialization::{Buffer, IoResult, Read, Remaining, Write};
+
+/// Version of the Fiat-Shamir transcripts used by plonky2 and starky. It is absorbed before
+/// anything else, so proofs produced under one version are rejected under any other.
+///
+/// Version 2 makes starky transcripts absorb the public inputs before the trace cap.
+pub const TRANSCRIPT_VERSION: u8 = 2;
 
 /// Observes prover messages, and generates challenges by hashing the transcript, a la Fiat-Shamir.
 #[derive(Clone)]
@@ -27,6 +36,16 @@ pub struct Challenger<F: RichField, H: Hasher<F>> {
 /// design, but it can be viewed as a duplex sponge whose inputs are sometimes zero (when we perform
 /// multiple squeezes) and whose outputs are sometimes ignored (when we perform multiple
 /// absorptions). Thus the security properties of a duplex sponge still apply to our design.
+///
+/// Precisely, the sponge state starts as `WIDTH` zeros. Observed elements are appended to the input
+/// buffer, and any buffered outputs are discarded. A duplexing overwrites the first elements of
+/// the state with the buffered inputs, applies the permutation, and replaces the output buffer with
+/// the first `RATE` elements of the new state. It happens whenever the input buffer reaches `RATE`
+/// elements, and before a challenge is taken if there are buffered inputs or no buffered outputs.
+/// Challenges are taken from the end of the output buffer.
+///
+/// The whole state can be saved with `to_bytes` and restored with `from_bytes`, so that a
+/// transcript started in one process can be continued in another.
 impl<F: RichField, H: Hasher<F>> Challenger<F, H> {
     pub fn new() -> Challenger<F, H> {
         Challenger {
@@ -36,6 +55,13 @@ impl<F: RichField, H: Hasher<F>> Challenger<F, H> {
         }
     }
 
+    /// Observes the transcript version followed by a domain separation tag, such as a digest of
+    /// the configuration. This should be the first observation of any transcript.
+    pub fn observe_domain_separator<OH: Hasher<F>>(&mut self, tag: OH::Hash) {
+        self.observe_element(F::from_canonical_u8(TRANSCRIPT_VERSION));
+        self.observe_hash::<OH>(tag);
+    }
+
     pub fn observe_element(&mut self, element: F) {
         // Any buffered outputs are now invalid, since they wouldn't reflect this input.
         self.output_buffer.clear();
@@ -150,6 +176,54 @@ impl<F: RichField, H: Hasher<F>> Challenger<F, H> {
         self.output_buffer.clear();
         self.sponge_state
     }
+
+    /// Serializes the sponge state, followed by the buffered inputs and outputs, each preceded by
+    /// its length.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        self.write(&mut buffer)
+            .expect("Writing to a byte-vector cannot fail.");
+        buffer
+    }
+
+    fn write(&self, buffer: &mut Vec<u8>) -> IoResult<()> {
+        buffer.write_field_vec(self.sponge_state.as_ref())?;
+        buffer.write_usize(self.input_buffer.len())?;
+        buffer.write_field_vec(&self.input_buffer)?;
+        buffer.write_usize(self.output_buffer.len())?;
+        buffer.write_field_vec(&self.output_buffer)
+    }
+
+    /// Restores a challenger serialized with `to_bytes`, which continues the same transcript.
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self> {
+        let mut buffer = Buffer::new(&bytes);
+        let (sponge_state, input_buffer, output_buffer) =
+            Self::read(&mut buffer).map_err(anyhow::Error::msg)?;
+        ensure!(buffer.is_empty(), "Trailing bytes after challenger state");
+        ensure!(
+            input_buffer.len() < H::Permutation::RATE
+                && output_buffer.len() <= H::Permutation::RATE,
+            "Challenger buffers exceed the sponge rate"
+        );
+        ensure!(
+            input_buffer.is_empty() || output_buffer.is_empty(),
+            "Challenger has both buffered inputs and outputs"
+        );
+        Ok(Self {
+            sponge_state: H::Permutation::new(sponge_state),
+            input_buffer,
+            output_buffer,
+        })
+    }
+
+    fn read(buffer: &mut Buffer) -> IoResult<(Vec<F>, Vec<F>, Vec<F>)> {
+        let sponge_state = buffer.read_field_vec(H::Permutation::WIDTH)?;
+        let input_len = buffer.read_usize()?;
+        let input_buffer = buffer.read_field_vec(input_len)?;
+        let output_len = buffer.read_usize()?;
+        let output_buffer = buffer.read_field_vec(output_len)?;
+        Ok((sponge_state, input_buffer, output_buffer))
+    }
 }
 
 impl<F: RichField, H: AlgebraicHasher<F>> Default for Challenger<F, H> {
@@ -161,6 +235,9 @@ impl<F: RichField, H: AlgebraicHasher<F>> Default for Challenger<F, H> {
 /// A recursive version of `Challenger`. The main difference is that `RecursiveChallenger`'s input
 /// buffer can grow beyond `H::Permutation::RATE`. This is so that `observe_element` etc do not need access
 /// to the `CircuitBuilder`.
+///
+/// It can be cloned to continue a transcript along several paths, and serialized with `to_bytes`
+/// to continue it with a builder in another process.
 pub struct RecursiveChallenger<F: RichField + Extendable<D>, H: AlgebraicHasher<F>, const D: usize>
 {
     sponge_state: H::AlgebraicPermutation,
@@ -191,6 +268,19 @@ impl<F: RichField + Extendable<D>, H: AlgebraicHasher<F>, const D: usize>
         }
     }
 
+    /// Observes the transcript version followed by a domain separation tag, both as constants.
+    /// This mirrors `Challenger::observe_domain_separator`.
+    pub fn observe_domain_separator(
+        &mut self,
+        builder: &mut CircuitBuilder<F, D>,
+        tag: HashOut<F>,
+    ) {
+        let version = builder.constant(F::from_canonical_u8(TRANSCRIPT_VERSION));
+        self.observe_element(version);
+        let tag = builder.constant_hash(tag);
+        self.observe_hash(&tag);
+    }
+
     pub fn observe_element(&mut self, target: Target) {
         // Any buffered outputs are now invalid, since they wouldn't reflect this input.
         self.output_buffer.clear();
@@ -289,6 +379,64 @@ impl<F: RichField + Extendable<D>, H: AlgebraicHasher<F>, const D: usize>
         self.output_buffer.clear();
         self.sponge_state
     }
+
+    /// Serializes the targets of the sponge state, followed by the buffered inputs and outputs.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        self.write(&mut buffer)
+            .expect("Writing to a byte-vector cannot fail.");
+        buffer
+    }
+
+    fn write(&self, buffer: &mut Vec<u8>) -> IoResult<()> {
+        buffer.write_target_vec(self.sponge_state.as_ref())?;
+        buffer.write_target_vec(&self.input_buffer)?;
+        buffer.write_target_vec(&self.output_buffer)
+    }
+
+    /// Restores a challenger serialized with `to_bytes`. Its targets refer to the circuit it was
+    /// built in.
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self> {
+        let mut buffer = Buffer::new(&bytes);
+        let (sponge_state, input_buffer, output_buffer) =
+            Self::read(&mut buffer).map_err(anyhow::Error::msg)?;
+        ensure!(
+            buffer.is_empty(),
+            "Trailing bytes after recursive challenger state"
+        );
+        ensure!(
+            sponge_state.len() == H::AlgebraicPermutation::WIDTH,
+            "Sponge state has {} targets, expected {}",
+            sponge_state.len(),
+            H::AlgebraicPermutation::WIDTH
+        );
+        Ok(Self {
+            sponge_state: H::AlgebraicPermutation::new(sponge_state),
+            input_buffer,
+            output_buffer,
+            __: PhantomData,
+        })
+    }
+
+    fn read(buffer: &mut Buffer) -> IoResult<(Vec<Target>, Vec<Target>, Vec<Target>)> {
+        let sponge_state = buffer.read_target_vec()?;
+        let input_buffer = buffer.read_target_vec()?;
+        let output_buffer = buffer.read_target_vec()?;
+        Ok((sponge_state, input_buffer, output_buffer))
+    }
+}
+
+impl<F: RichField + Extendable<D>, H: AlgebraicHasher<F>, const D: usize> Clone
+    for RecursiveChallenger<F, H, D>
+{
+    fn clone(&self) -> Self {
+        Self {
+            sponge_state: self.sponge_state,
+            input_buffer: self.input_buffer.clone(),
+            output_buffer: self.output_buffer.clone(),
+            __: PhantomData,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -369,4 +517,42 @@ mod tests {
 
         assert_eq!(outputs_per_round, recursive_output_values_per_round);
     }
+
+    #[test]
+    fn test_resume_transcript() -> anyhow::Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type H = <C as GenericConfig<D>>::InnerHasher;
+
+        let mut challenger = Challenger::<F, H>::new();
+        // Resume from states with buffered inputs, then with buffered outputs.
+        for num_inputs in [3, 13] {
+            challenger.observe_elements(&F::rand_vec(num_inputs));
+            let mut resumed = Challenger::<F, H>::from_bytes(challenger.to_bytes())?;
+            assert_eq!(resumed.get_n_challenges(5), challenger.get_n_challenges(5));
+
+            let mut resumed = Challenger::<F, H>::from_bytes(challenger.to_bytes())?;
+            let inputs = F::rand_vec(2);
+            resumed.observe_elements(&inputs);
+            challenger.observe_elements(&inputs);
+            assert_eq!(resumed.get_n_challenges(3), challenger.get_n_challenges(3));
+        }
+
+        let mut bytes = challenger.to_bytes();
+        bytes.push(0);
+        assert!(Challenger::<F, H>::from_bytes(bytes).is_err());
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let mut recursive_challenger = RecursiveChallenger::<F, H, D>::new(&mut builder);
+        recursive_challenger.observe_elements(&builder.add_virtual_targets(3));
+        let forked = recursive_challenger.clone();
+        let resumed = RecursiveChallenger::<F, H, D>::from_bytes(recursive_challenger.to_bytes())?;
+        assert_eq!(forked.to_bytes(), recursive_challenger.to_bytes());
+        assert_eq!(resumed.to_bytes(), recursive_challenger.to_bytes());
+        assert!(RecursiveChallenger::<F, H, D>::from_bytes(vec![0; 8]).is_err());
+
+        Ok(())
+    }
 }
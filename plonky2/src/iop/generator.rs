@@ -1,10 +1,15 @@
+use alloc::collections::BTreeMap;
 use alloc::vec;
 use alloc::vec::Vec;
 use core::fmt::Debug;
 use core::marker::PhantomData;
 
+use hashbrown::HashMap;
+use plonky2_maybe_rayon::*;
+
 use crate::field::extension::Extendable;
 use crate::field::types::Field;
+use crate::fri::oracle::CommitmentArena;
 use crate::hash::hash_types::RichField;
 use crate::iop::ext_target::ExtensionTarget;
 use crate::iop::target::Target;
@@ -25,37 +30,174 @@ pub fn generate_partial_witness<
     inputs: PartialWitness<F>,
     prover_data: &'a ProverOnlyCircuitData<F, C, D>,
     common_data: &'a CommonCircuitData<F, D>,
+) -> PartitionWitness<'a, F> {
+    generate_partial_witness_in(inputs, prover_data, common_data, &mut WitnessArena::new())
+}
+
+/// Scratch buffers used during witness generation and, through [`Self::commitments`], by the
+/// polynomial commitments of the prover. Reusing an arena across repeated proofs of the same
+/// circuit avoids reallocating the (large) witness, generator queues, LDEs and Merkle leaves each
+/// time.
+#[derive(Debug, Default)]
+pub struct WitnessArena<F: Field> {
+    values: Vec<Option<F>>,
+    pending_generator_indices: Vec<usize>,
+    next_pending_generator_indices: Vec<usize>,
+    generator_is_expired: Vec<bool>,
+    generated_values: Vec<(Target, F)>,
+    gate_cache: Option<GateWitnessCache<F>>,
+    commitments: CommitmentArena<F>,
+}
+
+#[cfg(feature = "hardened")]
+impl<F: Field> Drop for WitnessArena<F> {
+    fn drop(&mut self) {
+        crate::util::zeroize::zeroize_vec(&mut self.values);
+        crate::util::zeroize::zeroize_vec(&mut self.generated_values);
+    }
+}
+
+impl<F: Field> WitnessArena<F> {
+    pub fn new() -> Self {
+        Self {
+            values: Vec::new(),
+            pending_generator_indices: Vec::new(),
+            next_pending_generator_indices: Vec::new(),
+            generator_is_expired: Vec::new(),
+            generated_values: Vec::new(),
+            gate_cache: None,
+            commitments: CommitmentArena::new(),
+        }
+    }
+
+    /// An arena which also caches the wires computed by gate generators, such as Poseidon
+    /// permutations, for up to `max_entries` distinct inputs. The cache is kept across proofs, so
+    /// proofs of circuits which repeat the same hashes, like the verifiers of structurally
+    /// identical proofs in an aggregation tree, only compute each of them once.
+    pub fn with_gate_cache(max_entries: usize) -> Self {
+        let mut arena = Self::new();
+        arena.gate_cache = Some(GateWitnessCache::new(max_entries));
+        arena
+    }
+
+    pub fn gate_cache(&self) -> Option<&GateWitnessCache<F>> {
+        self.gate_cache.as_ref()
+    }
+
+    /// The buffers of the prover's polynomial commitments.
+    pub fn commitments(&self) -> &CommitmentArena<F> {
+        &self.commitments
+    }
+
+    pub fn commitments_mut(&mut self) -> &mut CommitmentArena<F> {
+        &mut self.commitments
+    }
+
+    /// Makes room for the witness of a circuit with `num_values` distinct values, so that
+    /// generating it won't reallocate.
+    pub fn reserve(&mut self, num_values: usize) {
+        self.values
+            .reserve(num_values.saturating_sub(self.values.len()));
+    }
+
+    /// Returns the buffer of a witness which is no longer needed to the arena, so that the next
+    /// call to [`generate_partial_witness_in`] can reuse it.
+    pub fn recycle(&mut self, mut witness: PartitionWitness<F>) {
+        self.values = core::mem::take(&mut witness.values);
+    }
+}
+
+/// Like [`generate_partial_witness`], but takes its scratch buffers from `arena`. The returned
+/// witness can be handed back with [`WitnessArena::recycle`] once it is no longer needed.
+pub fn generate_partial_witness_in<
+    'a,
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    inputs: PartialWitness<F>,
+    prover_data: &'a ProverOnlyCircuitData<F, C, D>,
+    common_data: &'a CommonCircuitData<F, D>,
+    arena: &mut WitnessArena<F>,
 ) -> PartitionWitness<'a, F> {
     let config = &common_data.config;
     let generators = &prover_data.generators;
     let generator_indices_by_watches = &prover_data.generator_indices_by_watches;
 
-    let mut witness = PartitionWitness::new(
-        config.num_wires,
-        common_data.degree(),
-        &prover_data.representative_map,
-    );
-
-    for (t, v) in inputs.target_values.into_iter() {
+    let mut values = core::mem::take(&mut arena.values);
+    values.clear();
+    values.resize(prover_data.representative_map.len(), None);
+    let mut witness = PartitionWitness {
+        values,
+        representative_map: &prover_data.representative_map,
+        num_wires: config.num_wires,
+        degree: common_data.degree(),
+        target_names: Some(&prover_data.target_names),
+    };
+
+    for (&t, &v) in inputs.target_values.iter() {
         witness.set_target(t, v);
     }
 
     // Build a list of "pending" generators which are queued to be run. Initially, all generators
     // are queued.
-    let mut pending_generator_indices: Vec<_> = (0..generators.len()).collect();
+    let pending_generator_indices = &mut arena.pending_generator_indices;
+    pending_generator_indices.clear();
+    pending_generator_indices.extend(0..generators.len());
+    let next_pending_generator_indices = &mut arena.next_pending_generator_indices;
 
     // We also track a list of "expired" generators which have already returned false.
-    let mut generator_is_expired = vec![false; generators.len()];
+    let generator_is_expired = &mut arena.generator_is_expired;
+    generator_is_expired.clear();
+    generator_is_expired.resize(generators.len(), false);
     let mut remaining_generators = generators.len();
 
-    let mut buffer = GeneratedValues::empty();
+    let mut buffer = GeneratedValues::from(core::mem::take(&mut arena.generated_values));
+    buffer.target_values.clear();
+    buffer.gate_cache = arena.gate_cache.take();
+
+    // Expensive generators are run at the start of each round, in parallel, unless they may use
+    // the gate cache, which is only available to one generator at a time.
+    let run_expensive_first = buffer.gate_cache.is_none();
+    let is_expensive = |generator_idx: usize| {
+        run_expensive_first && generators[generator_idx].0.cost() >= PARALLEL_GENERATOR_COST
+    };
 
     // Keep running generators until we fail to make progress.
     while !pending_generator_indices.is_empty() {
-        let mut next_pending_generator_indices = Vec::new();
+        next_pending_generator_indices.clear();
+
+        let mut expensive_generator_indices = pending_generator_indices
+            .iter()
+            .copied()
+            .filter(|&i| !generator_is_expired[i] && is_expensive(i))
+            .collect::<Vec<_>>();
+        expensive_generator_indices.sort_unstable();
+        expensive_generator_indices.dedup();
+        let expensive_outputs = expensive_generator_indices
+            .into_par_iter()
+            .map(|generator_idx| {
+                let mut out_buffer = GeneratedValues::empty();
+                let finished = generators[generator_idx].0.run(&witness, &mut out_buffer);
+                (generator_idx, finished, out_buffer)
+            })
+            .collect::<Vec<_>>();
+        for (generator_idx, finished, mut out_buffer) in expensive_outputs {
+            if finished {
+                generator_is_expired[generator_idx] = true;
+                remaining_generators -= 1;
+            }
+            merge_generated_values(
+                &mut out_buffer,
+                &mut witness,
+                generator_indices_by_watches,
+                generator_is_expired,
+                next_pending_generator_indices,
+            );
+        }
 
-        for &generator_idx in &pending_generator_indices {
-            if generator_is_expired[generator_idx] {
+        for &generator_idx in pending_generator_indices.iter() {
+            if generator_is_expired[generator_idx] || is_expensive(generator_idx) {
                 continue;
             }
 
@@ -64,29 +206,19 @@ pub fn generate_partial_witness<
                 generator_is_expired[generator_idx] = true;
                 remaining_generators -= 1;
             }
-
-            // Merge any generated values into our witness, and get a list of newly-populated
-            // targets' representatives.
-            let new_target_reps = buffer
-                .target_values
-                .drain(..)
-                .flat_map(|(t, v)| witness.set_target_returning_rep(t, v));
-
-            // Enqueue unfinished generators that were watching one of the newly populated targets.
-            for watch in new_target_reps {
-                let opt_watchers = generator_indices_by_watches.get(&watch);
-                if let Some(watchers) = opt_watchers {
-                    for &watching_generator_idx in watchers {
-                        if !generator_is_expired[watching_generator_idx] {
-                            next_pending_generator_indices.push(watching_generator_idx);
-                        }
-                    }
-                }
-            }
+            merge_generated_values(
+                &mut buffer,
+                &mut witness,
+                generator_indices_by_watches,
+                generator_is_expired,
+                next_pending_generator_indices,
+            );
         }
 
-        pending_generator_indices = next_pending_generator_indices;
+        core::mem::swap(pending_generator_indices, next_pending_generator_indices);
     }
+    arena.generated_values = core::mem::take(&mut buffer.target_values);
+    arena.gate_cache = buffer.gate_cache.take();
 
     assert_eq!(
         remaining_generators, 0,
@@ -97,6 +229,35 @@ pub fn generate_partial_witness<
     witness
 }
 
+/// Merges generated values into the witness, and enqueues the unfinished generators watching any
+/// newly populated target.
+fn merge_generated_values<F: Field>(
+    buffer: &mut GeneratedValues<F>,
+    witness: &mut PartitionWitness<F>,
+    generator_indices_by_watches: &BTreeMap<usize, Vec<usize>>,
+    generator_is_expired: &[bool],
+    next_pending_generator_indices: &mut Vec<usize>,
+) {
+    let new_target_reps = buffer
+        .target_values
+        .drain(..)
+        .flat_map(|(t, v)| witness.set_target_returning_rep(t, v));
+
+    for watch in new_target_reps {
+        let opt_watchers = generator_indices_by_watches.get(&watch);
+        if let Some(watchers) = opt_watchers {
+            for &watching_generator_idx in watchers {
+                if !generator_is_expired[watching_generator_idx] {
+                    next_pending_generator_indices.push(watching_generator_idx);
+                }
+            }
+        }
+    }
+}
+
+/// Generators whose `cost` is at least this are run in parallel, ahead of the others.
+pub const PARALLEL_GENERATOR_COST: usize = 1000;
+
 /// A generator participates in the generation of the witness.
 pub trait WitnessGenerator<F: RichField + Extendable<D>, const D: usize>:
     'static + Send + Sync + Debug
@@ -112,6 +273,13 @@ pub trait WitnessGenerator<F: RichField + Extendable<D>, const D: usize>:
     /// run next time a target in its watch list is populated.
     fn run(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) -> bool;
 
+    /// A rough estimate of the cost of a run, in field multiplications. Generators costing at
+    /// least `PARALLEL_GENERATOR_COST`, such as hash permutations, are run in parallel at the start
+    /// of each round of witness generation, which shortens its critical path.
+    fn cost(&self) -> usize {
+        1
+    }
+
     fn serialize(&self, dst: &mut Vec<u8>, common_data: &CommonCircuitData<F, D>) -> IoResult<()>;
 
     fn deserialize(src: &mut Buffer, common_data: &CommonCircuitData<F, D>) -> IoResult<Self>
@@ -145,15 +313,82 @@ impl<F: RichField + Extendable<D>, const D: usize> Debug for WitnessGeneratorRef
     }
 }
 
+/// A gate, by the name identifying it, and the values of its input wires.
+type GateWitnessKey<F> = (&'static str, Vec<F>);
+
+/// Maps the inputs of a gate to the wire values its generator computes from them, as
+/// `(column, value)` pairs, so that identical gates are only generated once.
+#[derive(Debug, Default)]
+pub struct GateWitnessCache<F: Field> {
+    entries: HashMap<GateWitnessKey<F>, Vec<(usize, F)>>,
+    max_entries: usize,
+    hits: usize,
+    misses: usize,
+}
+
+#[cfg(feature = "hardened")]
+impl<F: Field> Drop for GateWitnessCache<F> {
+    fn drop(&mut self) {
+        for ((_, mut inputs), mut values) in self.entries.drain() {
+            crate::util::zeroize::zeroize_vec(&mut inputs);
+            crate::util::zeroize::zeroize_vec(&mut values);
+        }
+    }
+}
+
+impl<F: Field> GateWitnessCache<F> {
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            max_entries,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The number of lookups which found cached values.
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// The number of lookups which had to generate the values.
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
 /// Values generated by a generator invocation.
 #[derive(Debug)]
 pub struct GeneratedValues<F: Field> {
     pub(crate) target_values: Vec<(Target, F)>,
+    gate_cache: Option<GateWitnessCache<F>>,
+}
+
+#[cfg(feature = "hardened")]
+impl<F: Field> Drop for GeneratedValues<F> {
+    fn drop(&mut self) {
+        crate::util::zeroize::zeroize_vec(&mut self.target_values);
+    }
 }
 
 impl<F: Field> From<Vec<(Target, F)>> for GeneratedValues<F> {
     fn from(target_values: Vec<(Target, F)>) -> Self {
-        Self { target_values }
+        Self {
+            target_values,
+            gate_cache: None,
+        }
     }
 }
 
@@ -180,6 +415,48 @@ impl<F: Field> GeneratedValues<F> {
         vec![(target, value)].into()
     }
 
+    /// Sets the wires of `row` which `generate` computes, as `(column, value)` pairs, from the
+    /// `inputs` of a gate identified by `gate`. If witness generation is caching gates, values
+    /// generated earlier for the same gate and inputs are reused instead.
+    pub fn set_gate_wires_cached<G>(
+        &mut self,
+        gate: &'static str,
+        row: usize,
+        inputs: &[F],
+        generate: G,
+    ) where
+        G: FnOnce() -> Vec<(usize, F)>,
+    {
+        let Self {
+            target_values,
+            gate_cache,
+        } = self;
+        let set_wires = |target_values: &mut Vec<(Target, F)>, values: &[(usize, F)]| {
+            target_values.extend(
+                values
+                    .iter()
+                    .map(|&(column, value)| (Target::wire(row, column), value)),
+            );
+        };
+
+        let cache = match gate_cache {
+            Some(cache) => cache,
+            None => return set_wires(target_values, &generate()),
+        };
+        let key = (gate, inputs.to_vec());
+        if let Some(values) = cache.entries.get(&key) {
+            cache.hits += 1;
+            set_wires(target_values, values);
+            return;
+        }
+        cache.misses += 1;
+        let values = generate();
+        set_wires(target_values, &values);
+        if cache.entries.len() < cache.max_entries {
+            cache.entries.insert(key, values);
+        }
+    }
+
     pub fn singleton_extension_target<const D: usize>(
         et: ExtensionTarget<D>,
         value: F::Extension,
@@ -203,6 +480,11 @@ pub trait SimpleGenerator<F: RichField + Extendable<D>, const D: usize>:
 
     fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>);
 
+    /// See `WitnessGenerator::cost`.
+    fn cost(&self) -> usize {
+        1
+    }
+
     fn adapter(self) -> SimpleGeneratorAdapter<F, Self, D>
     where
         Self: Sized,
@@ -250,6 +532,10 @@ impl<F: RichField + Extendable<D>, SG: SimpleGenerator<F, D>, const D: usize> Wi
         }
     }
 
+    fn cost(&self) -> usize {
+        self.inner.cost()
+    }
+
     fn serialize(&self, dst: &mut Vec<u8>, common_data: &CommonCircuitData<F, D>) -> IoResult<()> {
         self.inner.serialize(dst, common_data)
     }
@@ -413,3 +699,140 @@ impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F, D> for Con
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::poseidon::PoseidonHash;
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::{CircuitConfig, CircuitData};
+    use crate::plonk::config::{Hasher, PoseidonGoldilocksConfig};
+
+    /// Checks that `values` agree with a witness from `generate_partial_witness`, except for the
+    /// unused public input gate wires, which `randomize_unused_pi_wires` fills with fresh random
+    /// values in every witness. These are found as the values two reference witnesses disagree on.
+    fn assert_matches_reference<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        values: &[Option<F>],
+        pw: &PartialWitness<F>,
+        data: &CircuitData<F, C, D>,
+    ) {
+        let reference = || {
+            generate_partial_witness(pw.clone(), &data.prover_only, &data.common)
+                .values
+                .clone()
+        };
+        let (expected, other) = (reference(), reference());
+        assert_eq!(values.len(), expected.len());
+        for ((value, expected), other) in values.iter().zip(expected).zip(other) {
+            if expected == other {
+                assert_eq!(*value, expected);
+            } else {
+                assert!(value.is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn test_witness_arena_reuse() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let y = builder.add_virtual_target();
+        let z = builder.div(x, y);
+        builder.register_public_input(z);
+        let data = builder.build::<C>();
+
+        let mut arena = WitnessArena::new();
+        for i in 1..4 {
+            let mut pw = PartialWitness::new();
+            pw.set_target(x, F::from_canonical_u64(i * 6));
+            pw.set_target(y, F::from_canonical_u64(i));
+            let witness = generate_partial_witness_in(
+                pw.clone(),
+                &data.prover_only,
+                &data.common,
+                &mut arena,
+            );
+            assert_matches_reference(&witness.values, &pw, &data);
+            assert_eq!(witness.get_target(z), F::from_canonical_u64(6));
+            arena.recycle(witness);
+        }
+    }
+
+    #[test]
+    fn test_gate_witness_cache() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        // The same permutation twice, and a different one.
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let y = builder.add_virtual_target();
+        let h0 = builder.hash_n_to_hash_no_pad::<PoseidonHash>(vec![x]);
+        let h1 = builder.hash_n_to_hash_no_pad::<PoseidonHash>(vec![x]);
+        let h2 = builder.hash_n_to_hash_no_pad::<PoseidonHash>(vec![y]);
+        builder.register_public_inputs(&h0.elements);
+        builder.register_public_inputs(&h1.elements);
+        builder.register_public_inputs(&h2.elements);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(1));
+        pw.set_target(y, F::from_canonical_u64(2));
+
+        let mut arena = WitnessArena::with_gate_cache(100);
+        let witness =
+            generate_partial_witness_in(pw.clone(), &data.prover_only, &data.common, &mut arena);
+        assert_matches_reference(&witness.values, &pw, &data);
+        arena.recycle(witness);
+        // Hashing the 12 public inputs takes two more permutations.
+        let cache = arena.gate_cache().unwrap();
+        assert_eq!((cache.len(), cache.hits(), cache.misses()), (4, 1, 4));
+
+        // A second witness with the same inputs reuses every permutation.
+        let witness =
+            generate_partial_witness_in(pw.clone(), &data.prover_only, &data.common, &mut arena);
+        assert_matches_reference(&witness.values, &pw, &data);
+        arena.recycle(witness);
+        let cache = arena.gate_cache().unwrap();
+        assert_eq!((cache.len(), cache.hits(), cache.misses()), (4, 6, 4));
+    }
+
+    #[test]
+    fn test_expensive_generators() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        // A chain of permutations, each of which can only run once the previous one has.
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let mut h = builder.hash_n_to_hash_no_pad::<PoseidonHash>(vec![x]);
+        for _ in 0..3 {
+            h = builder.hash_n_to_hash_no_pad::<PoseidonHash>(h.elements.to_vec());
+        }
+        let data = builder.build::<C>();
+        assert!(data
+            .prover_only
+            .generators
+            .iter()
+            .any(|g| g.0.cost() >= PARALLEL_GENERATOR_COST));
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(7));
+        let witness = generate_partial_witness(pw, &data.prover_only, &data.common);
+        let mut expected = PoseidonHash::hash_no_pad(&[F::from_canonical_u64(7)]);
+        for _ in 0..3 {
+            expected = PoseidonHash::hash_no_pad(&expected.elements);
+        }
+        assert_eq!(witness.get_hash_target(h), expected);
+    }
+}
@@ -0,0 +1,274 @@
+//! Records which generators populate which targets during witness generation, and exports the
+//! resulting dependency graph in Graphviz's DOT format. This helps to debug witness generation which
+//! gets stuck, or targets with unexpected fan-in.
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use core::fmt::Write;
+
+use crate::field::extension::Extendable;
+use crate::hash::hash_types::RichField;
+use crate::iop::generator::GeneratedValues;
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartialWitness, PartitionWitness, Witness, WitnessWrite};
+use crate::plonk::circuit_data::{CommonCircuitData, ProverOnlyCircuitData};
+use crate::plonk::config::GenericConfig;
+
+/// A generator, with the targets it watched and the targets it populated.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GeneratorNode {
+    /// The generator's ID, as given by `WitnessGenerator::id`.
+    pub id: String,
+    pub watch_list: Vec<Target>,
+    /// The targets this generator populated, in order.
+    pub generated: Vec<Target>,
+    /// Whether the generator finished. Witness generation is stuck if any generator didn't.
+    pub finished: bool,
+}
+
+/// A target which some generator watched or populated, or which was given as an input.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+struct TargetNode {
+    /// The first target seen in this partition of copy-constrained targets.
+    target: Target,
+    input: bool,
+    populated: bool,
+}
+
+/// The dependency graph between targets and generators, as observed while generating a witness.
+/// Targets which are copy-constrained to each other form a single node.
+#[derive(Clone, Debug)]
+pub struct GeneratorGraph {
+    /// One node per generator, in the order of `ProverOnlyCircuitData::generators`.
+    pub generators: Vec<GeneratorNode>,
+    /// Target nodes, indexed by the representative of their partition.
+    targets: BTreeMap<usize, TargetNode>,
+    /// The representative of each generator's watched and generated targets.
+    generator_reps: Vec<(Vec<usize>, Vec<usize>)>,
+    /// Debug names of target nodes, as given by `CircuitBuilder::name_target`.
+    target_names: BTreeMap<usize, String>,
+}
+
+impl GeneratorGraph {
+    /// Runs witness generation like `generate_partial_witness`, recording the targets each
+    /// generator populates. Unlike `generate_partial_witness`, this doesn't panic if some
+    /// generators can't run.
+    pub fn new<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+        inputs: &PartialWitness<F>,
+        prover_data: &ProverOnlyCircuitData<F, C, D>,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> Self {
+        let generators = &prover_data.generators;
+        let mut witness = PartitionWitness::new(
+            common_data.config.num_wires,
+            common_data.degree(),
+            &prover_data.representative_map,
+        );
+        witness.target_names = Some(&prover_data.target_names);
+        let rep = |witness: &PartitionWitness<F>, t: Target| {
+            prover_data.representative_map[witness.target_index(t)]
+        };
+
+        let mut targets = BTreeMap::new();
+        for (&t, &v) in inputs.target_values.iter() {
+            witness.set_target(t, v);
+            targets.entry(rep(&witness, t)).or_insert(TargetNode {
+                target: t,
+                input: true,
+                populated: true,
+            });
+        }
+
+        let mut nodes = generators
+            .iter()
+            .map(|g| GeneratorNode {
+                id: g.0.id(),
+                watch_list: g.0.watch_list(),
+                generated: Vec::new(),
+                finished: false,
+            })
+            .collect::<Vec<_>>();
+
+        let mut pending_generator_indices = (0..generators.len()).collect::<Vec<_>>();
+        let mut buffer = GeneratedValues::empty();
+        while !pending_generator_indices.is_empty() {
+            let mut next_pending_generator_indices = Vec::new();
+            for generator_idx in pending_generator_indices {
+                if nodes[generator_idx].finished {
+                    continue;
+                }
+                nodes[generator_idx].finished =
+                    generators[generator_idx].0.run(&witness, &mut buffer);
+
+                for (t, v) in buffer.target_values.drain(..) {
+                    nodes[generator_idx].generated.push(t);
+                    let watchers = witness
+                        .set_target_returning_rep(t, v)
+                        .and_then(|r| prover_data.generator_indices_by_watches.get(&r));
+                    if let Some(watchers) = watchers {
+                        next_pending_generator_indices.extend(
+                            watchers
+                                .iter()
+                                .filter(|&&watcher_idx| !nodes[watcher_idx].finished),
+                        );
+                    }
+                }
+            }
+            pending_generator_indices = next_pending_generator_indices;
+        }
+
+        let mut reps = |ts: &[Target]| {
+            let mut reps = ts
+                .iter()
+                .map(|&t| {
+                    let r = rep(&witness, t);
+                    targets.entry(r).or_insert(TargetNode {
+                        target: t,
+                        input: false,
+                        populated: witness.try_get_target(t).is_some(),
+                    });
+                    r
+                })
+                .collect::<Vec<_>>();
+            reps.sort_unstable();
+            reps.dedup();
+            reps
+        };
+        let generator_reps = nodes
+            .iter()
+            .map(|node| (reps(&node.watch_list), reps(&node.generated)))
+            .collect();
+
+        Self {
+            generators: nodes,
+            targets,
+            generator_reps,
+            target_names: prover_data.target_names.clone(),
+        }
+    }
+
+    /// The generators which never finished, and thus left some targets unpopulated.
+    pub fn unfinished_generators(&self) -> impl Iterator<Item = &GeneratorNode> {
+        self.generators.iter().filter(|node| !node.finished)
+    }
+
+    /// Exports the graph in DOT format. Targets are nodes, and each generator is a small point
+    /// connecting the targets it watched to the targets it populated, labelled with its ID and, if
+    /// `gate_contexts` (as given by `CircuitBuilder::gate_contexts`) is non-empty, the gadget
+    /// which added it. Targets are labelled with their names, if they were given any. Input targets
+    /// are drawn with a double border, and unfinished generators and unpopulated targets in red.
+    pub fn to_dot(&self, gate_contexts: &[String]) -> String {
+        let mut dot = String::from("digraph generators {\n");
+        for (rep, node) in &self.targets {
+            let mut label = target_label(node.target);
+            if let Some(name) = self.target_names.get(rep) {
+                label = format!("{}\\n{}", label, escape(name));
+            }
+            let mut attributes = vec![format!("label=\"{}\"", label)];
+            if node.input {
+                attributes.push("peripheries=2".into());
+            }
+            if !node.populated {
+                attributes.push("color=red".into());
+            }
+            writeln!(dot, "    t{} [{}];", rep, attributes.join(", ")).unwrap();
+        }
+
+        for (i, (node, (watched, generated))) in
+            self.generators.iter().zip(&self.generator_reps).enumerate()
+        {
+            let mut label = escape(&node.id);
+            if let Some(context) = generator_row(node).and_then(|row| gate_contexts.get(row)) {
+                label = format!("{}\\n{}", label, escape(context));
+            }
+            let color = if node.finished { "black" } else { "red" };
+            writeln!(
+                dot,
+                "    g{} [shape=point, color={}, xlabel=\"{}\"];",
+                i, color, label
+            )
+            .unwrap();
+            for rep in watched {
+                writeln!(dot, "    t{} -> g{} [arrowhead=none];", rep, i).unwrap();
+            }
+            for rep in generated {
+                writeln!(dot, "    g{} -> t{};", i, rep).unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// The row of the first wire a generator watches or populates, used to find the gadget which
+/// added it.
+fn generator_row(node: &GeneratorNode) -> Option<usize> {
+    node.watch_list
+        .iter()
+        .chain(&node.generated)
+        .find_map(|t| match t {
+            Target::Wire(Wire { row, .. }) => Some(*row),
+            Target::VirtualTarget { .. } => None,
+        })
+}
+
+fn target_label(target: Target) -> String {
+    match target {
+        Target::Wire(Wire { row, column }) => format!("w{},{}", row, column),
+        Target::VirtualTarget { index } => format!("v{}", index),
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::types::Field;
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+    use crate::with_context;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    #[test]
+    fn test_generator_graph() {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let y = builder.add_virtual_target();
+        let z = with_context!(builder, "x / y", {
+            let z = builder.div(x, y);
+            builder.name_target(z, "quotient");
+            z
+        });
+        builder.register_public_input(z);
+        let gate_contexts = builder.gate_contexts();
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(6));
+        pw.set_target(y, F::from_canonical_u64(3));
+        let graph = GeneratorGraph::new(&pw, &data.prover_only, &data.common);
+        assert_eq!(graph.unfinished_generators().count(), 0);
+        let dot = graph.to_dot(&gate_contexts);
+        assert!(dot.starts_with("digraph generators {"));
+        assert!(dot.contains("root > x / y"));
+        assert!(dot.contains("root > x / y > quotient"));
+        assert!(!dot.contains("color=red"));
+
+        // Without `y`, the division is stuck.
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(6));
+        let graph = GeneratorGraph::new(&pw, &data.prover_only, &data.common);
+        assert!(graph.unfinished_generators().count() > 0);
+        assert!(graph.to_dot(&[]).contains("color=red"));
+    }
+}
@@ -0,0 +1,310 @@
+//! [`simple_generator!`](crate::simple_generator) declares a `SimpleGenerator` from its input and
+//! output targets and the body of `run_once`. The macro generates the struct, and the `id`,
+//! `dependencies`, `serialize` and `deserialize` methods. The fields are bound by reference in the
+//! body, along with the witness and output buffer under the names given, and `F` and `D` refer to
+//! the field and extension degree:
+//!
+//! ```ignore
+//! simple_generator! {
+//!     #[derive(Debug, Default)]
+//!     pub struct SquareRootGenerator {
+//!         inputs { x: Target }
+//!         outputs { sqrt: Target }
+//!     }
+//!     run_once |witness, out_buffer| {
+//!         let x = witness.get_target(*x);
+//!         out_buffer.set_target(*sqrt, x.sqrt().unwrap());
+//!     }
+//! }
+//! ```
+//!
+//! Fields are serialized in declaration order, inputs first. Their types must implement
+//! [`GeneratorTargets`].
+
+#[doc(hidden)]
+pub use alloc::string::String;
+use alloc::vec;
+#[doc(hidden)]
+pub use alloc::vec::Vec;
+
+use crate::hash::hash_types::HashOutTarget;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::target::{BoolTarget, Target};
+use crate::util::serialization::{Buffer, IoResult, Read, Write};
+
+/// Targets which can be inputs or outputs of a generator declared with
+/// [`simple_generator!`](crate::simple_generator).
+pub trait GeneratorTargets: Sized {
+    /// The targets to wait for, if these are inputs.
+    fn targets(&self) -> Vec<Target>;
+
+    fn write_targets(&self, dst: &mut Vec<u8>) -> IoResult<()>;
+
+    fn read_targets(src: &mut Buffer) -> IoResult<Self>;
+}
+
+impl GeneratorTargets for Target {
+    fn targets(&self) -> Vec<Target> {
+        vec![*self]
+    }
+
+    fn write_targets(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        dst.write_target(*self)
+    }
+
+    fn read_targets(src: &mut Buffer) -> IoResult<Self> {
+        src.read_target()
+    }
+}
+
+impl GeneratorTargets for BoolTarget {
+    fn targets(&self) -> Vec<Target> {
+        vec![self.target]
+    }
+
+    fn write_targets(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        dst.write_target_bool(*self)
+    }
+
+    fn read_targets(src: &mut Buffer) -> IoResult<Self> {
+        src.read_target_bool()
+    }
+}
+
+impl<const D: usize> GeneratorTargets for ExtensionTarget<D> {
+    fn targets(&self) -> Vec<Target> {
+        self.to_target_array().to_vec()
+    }
+
+    fn write_targets(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        dst.write_target_ext(*self)
+    }
+
+    fn read_targets(src: &mut Buffer) -> IoResult<Self> {
+        src.read_target_ext()
+    }
+}
+
+impl GeneratorTargets for HashOutTarget {
+    fn targets(&self) -> Vec<Target> {
+        self.elements.to_vec()
+    }
+
+    fn write_targets(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        dst.write_target_hash(self)
+    }
+
+    fn read_targets(src: &mut Buffer) -> IoResult<Self> {
+        src.read_target_hash()
+    }
+}
+
+impl<const N: usize> GeneratorTargets for [Target; N] {
+    fn targets(&self) -> Vec<Target> {
+        self.to_vec()
+    }
+
+    fn write_targets(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        dst.write_target_array(self)
+    }
+
+    fn read_targets(src: &mut Buffer) -> IoResult<Self> {
+        src.read_target_array()
+    }
+}
+
+impl GeneratorTargets for Vec<Target> {
+    fn targets(&self) -> Vec<Target> {
+        self.clone()
+    }
+
+    fn write_targets(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        dst.write_target_vec(self)
+    }
+
+    fn read_targets(src: &mut Buffer) -> IoResult<Self> {
+        src.read_target_vec()
+    }
+}
+
+impl GeneratorTargets for Vec<BoolTarget> {
+    fn targets(&self) -> Vec<Target> {
+        self.iter().map(|b| b.target).collect()
+    }
+
+    fn write_targets(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        dst.write_target_bool_vec(self)
+    }
+
+    fn read_targets(src: &mut Buffer) -> IoResult<Self> {
+        src.read_target_bool_vec()
+    }
+}
+
+impl<const D: usize> GeneratorTargets for Vec<ExtensionTarget<D>> {
+    fn targets(&self) -> Vec<Target> {
+        self.iter().flat_map(|t| t.to_target_array()).collect()
+    }
+
+    fn write_targets(&self, dst: &mut Vec<u8>) -> IoResult<()> {
+        dst.write_target_ext_vec(self)
+    }
+
+    fn read_targets(src: &mut Buffer) -> IoResult<Self> {
+        src.read_target_ext_vec()
+    }
+}
+
+/// Declares a `SimpleGenerator`. See the [module docs](crate::iop::generator_macro).
+#[macro_export]
+macro_rules! simple_generator {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $name:ident {
+            inputs { $($input:ident : $input_ty:ty),* $(,)? }
+            outputs { $($output:ident : $output_ty:ty),* $(,)? }
+        }
+        run_once |$witness:ident, $out_buffer:ident| $body:block
+    ) => {
+        $(#[$attr])*
+        $vis struct $name {
+            $($input: $input_ty,)*
+            $($output: $output_ty,)*
+        }
+
+        impl<F, const D: usize> $crate::iop::generator::SimpleGenerator<F, D> for $name
+        where
+            F: $crate::hash::hash_types::RichField + $crate::field::extension::Extendable<D>,
+        {
+            fn id(&self) -> $crate::iop::generator_macro::String {
+                $crate::iop::generator_macro::String::from(stringify!($name))
+            }
+
+            fn dependencies(
+                &self,
+            ) -> $crate::iop::generator_macro::Vec<$crate::iop::target::Target> {
+                let mut targets = $crate::iop::generator_macro::Vec::new();
+                $(targets.extend(
+                    $crate::iop::generator_macro::GeneratorTargets::targets(&self.$input),
+                );)*
+                targets
+            }
+
+            #[allow(unused_variables)]
+            fn run_once(
+                &self,
+                $witness: &$crate::iop::witness::PartitionWitness<F>,
+                $out_buffer: &mut $crate::iop::generator::GeneratedValues<F>,
+            ) {
+                let Self {
+                    $($input,)*
+                    $($output,)*
+                } = self;
+                $body
+            }
+
+            fn serialize(
+                &self,
+                dst: &mut $crate::iop::generator_macro::Vec<u8>,
+                _common_data: &$crate::plonk::circuit_data::CommonCircuitData<F, D>,
+            ) -> $crate::util::serialization::IoResult<()> {
+                $($crate::iop::generator_macro::GeneratorTargets::write_targets(
+                    &self.$input,
+                    dst,
+                )?;)*
+                $($crate::iop::generator_macro::GeneratorTargets::write_targets(
+                    &self.$output,
+                    dst,
+                )?;)*
+                Ok(())
+            }
+
+            fn deserialize(
+                src: &mut $crate::util::serialization::Buffer,
+                _common_data: &$crate::plonk::circuit_data::CommonCircuitData<F, D>,
+            ) -> $crate::util::serialization::IoResult<Self> {
+                $(let $input =
+                    $crate::iop::generator_macro::GeneratorTargets::read_targets(src)?;)*
+                $(let $output =
+                    $crate::iop::generator_macro::GeneratorTargets::read_targets(src)?;)*
+                Ok(Self {
+                    $($input,)*
+                    $($output,)*
+                })
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::field::extension::Extendable;
+    use crate::field::types::Field;
+    use crate::iop::generator::SimpleGenerator;
+    use crate::iop::witness::{PartialWitness, Witness, WitnessWrite};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    crate::simple_generator! {
+        /// Sets `sum` to the sum of `xs`, scaled by the first limb of `scale`.
+        #[derive(Debug)]
+        struct SumGenerator {
+            inputs { xs: Vec<Target>, scale: ExtensionTarget<2> }
+            outputs { sum: Target }
+        }
+        run_once |witness, out_buffer| {
+            let total: F = witness.get_targets(xs).into_iter().sum();
+            let scale = witness.get_target(scale.0[0]);
+            out_buffer.set_target(*sum, total * scale);
+        }
+    }
+
+    #[test]
+    fn test_simple_generator_macro() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let xs = builder.add_virtual_targets(3);
+        let scale = builder.add_virtual_extension_target();
+        let sum = builder.add_virtual_target();
+        builder.register_public_input(sum);
+        let generator = SumGenerator {
+            xs: xs.clone(),
+            scale,
+            sum,
+        };
+        assert_eq!(SimpleGenerator::<F, D>::id(&generator), "SumGenerator");
+        assert_eq!(SimpleGenerator::<F, D>::dependencies(&generator).len(), 5);
+        builder.add_simple_generator(generator);
+        let data = builder.build::<C>();
+
+        let generator = SumGenerator { xs, scale, sum };
+        let mut bytes = Vec::new();
+        SimpleGenerator::<F, D>::serialize(&generator, &mut bytes, &data.common).unwrap();
+        let read = <SumGenerator as SimpleGenerator<F, D>>::deserialize(
+            &mut Buffer::new(&bytes),
+            &data.common,
+        )
+        .unwrap();
+        assert_eq!(
+            (&read.xs, read.scale, read.sum),
+            (&generator.xs, generator.scale, generator.sum)
+        );
+
+        let mut pw = PartialWitness::new();
+        pw.set_target_arr(&generator.xs, &[F::ONE, F::TWO, F::from_canonical_u64(3)]);
+        pw.set_extension_target(
+            generator.scale,
+            <F as Extendable<D>>::Extension::from_canonical_u64(5),
+        );
+        let proof = data.prove(pw)?;
+        assert_eq!(proof.public_inputs, [F::from_canonical_u64(30)]);
+        data.verify(proof)
+    }
+}
@@ -3,6 +3,8 @@
 pub mod challenger;
 pub mod ext_target;
 pub mod generator;
+pub mod generator_graph;
+pub mod generator_macro;
 pub mod target;
 pub mod wire;
 pub mod witness;
@@ -1,6 +1,9 @@
-use alloc::vec;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
 use alloc::vec::Vec;
+use alloc::{format, vec};
 
+use anyhow::{ensure, Result};
 use hashbrown::HashMap;
 use itertools::{zip_eq, Itertools};
 
@@ -16,6 +19,7 @@ use crate::iop::wire::Wire;
 use crate::plonk::circuit_data::{VerifierCircuitTarget, VerifierOnlyCircuitData};
 use crate::plonk::config::{AlgebraicHasher, GenericConfig, Hasher};
 use crate::plonk::proof::{Proof, ProofTarget, ProofWithPublicInputs, ProofWithPublicInputsTarget};
+use crate::util::serialization::{Buffer, IoResult, Read, Remaining, Write};
 
 pub trait WitnessWrite<F: Field> {
     fn set_target(&mut self, target: Target, value: F);
@@ -257,6 +261,15 @@ pub struct MatrixWitness<F: Field> {
     pub(crate) wire_values: Vec<Vec<F>>,
 }
 
+#[cfg(feature = "hardened")]
+impl<F: Field> Drop for MatrixWitness<F> {
+    fn drop(&mut self) {
+        for column in &mut self.wire_values {
+            crate::util::zeroize::zeroize_vec(column);
+        }
+    }
+}
+
 impl<F: Field> MatrixWitness<F> {
     pub fn get_wire(&self, gate: usize, input: usize) -> F {
         self.wire_values[input][gate]
@@ -268,12 +281,88 @@ pub struct PartialWitness<F: Field> {
     pub(crate) target_values: HashMap<Target, F>,
 }
 
+#[cfg(feature = "hardened")]
+impl<F: Field> Drop for PartialWitness<F> {
+    fn drop(&mut self) {
+        crate::util::zeroize::zeroize_values(self.target_values.values_mut());
+    }
+}
+
 impl<F: Field> PartialWitness<F> {
     pub fn new() -> Self {
         Self {
             target_values: HashMap::new(),
         }
     }
+
+    /// Like `set_target`, but returns an error rather than panicking if `target` was already set
+    /// to a different value, e.g. when the assignments come from untrusted input.
+    pub fn try_set_target(&mut self, target: Target, value: F) -> Result<()> {
+        if let Some(old_value) = self.try_get_target(target) {
+            ensure!(
+                value == old_value,
+                "Target {:?} was set twice with different values: {} != {}",
+                target,
+                old_value,
+                value
+            );
+        }
+        self.target_values.insert(target, value);
+        Ok(())
+    }
+}
+
+impl<F: RichField> PartialWitness<F> {
+    /// Dumps this witness to a compact byte format, so that a failing proof can be replayed
+    /// elsewhere with `CircuitData::prove_from_dump`. Targets are written in a fixed order, so
+    /// equal witnesses always produce identical dumps.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        self.write(&mut buffer)
+            .expect("Writing to a byte-vector cannot fail.");
+        buffer
+    }
+
+    fn write(&self, buffer: &mut Vec<u8>) -> IoResult<()> {
+        let mut entries = self.target_values.iter().collect::<Vec<_>>();
+        entries.sort_unstable_by_key(|(&target, _)| target_sort_key(target));
+        buffer.write_usize(entries.len())?;
+        for (&target, &value) in entries {
+            buffer.write_target(target)?;
+            buffer.write_field(value)?;
+        }
+        Ok(())
+    }
+
+    /// Loads a witness dumped with `to_bytes`.
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self> {
+        let mut buffer = Buffer::new(&bytes);
+        let entries = Self::read(&mut buffer).map_err(anyhow::Error::msg)?;
+        ensure!(buffer.is_empty(), "Trailing bytes after witness dump");
+        let mut target_values = HashMap::with_capacity(entries.len());
+        for (target, value) in entries {
+            ensure!(
+                target_values.insert(target, value).is_none(),
+                "Target {:?} appears twice in witness dump",
+                target
+            );
+        }
+        Ok(Self { target_values })
+    }
+
+    fn read(buffer: &mut Buffer) -> IoResult<Vec<(Target, F)>> {
+        let len = buffer.read_usize()?;
+        (0..len)
+            .map(|_| Ok((buffer.read_target()?, buffer.read_field()?)))
+            .collect()
+    }
+}
+
+fn target_sort_key(target: Target) -> (bool, usize, usize) {
+    match target {
+        Target::Wire(Wire { row, column }) => (true, row, column),
+        Target::VirtualTarget { index } => (false, index, 0),
+    }
 }
 
 impl<F: Field> WitnessWrite<F> for PartialWitness<F> {
@@ -303,6 +392,15 @@ pub struct PartitionWitness<'a, F: Field> {
     pub representative_map: &'a [usize],
     pub num_wires: usize,
     pub degree: usize,
+    /// Debug names of partitions, by representative, to mention in errors.
+    pub target_names: Option<&'a BTreeMap<usize, String>>,
+}
+
+#[cfg(feature = "hardened")]
+impl<'a, F: Field> Drop for PartitionWitness<'a, F> {
+    fn drop(&mut self) {
+        crate::util::zeroize::zeroize_vec(&mut self.values);
+    }
 }
 
 impl<'a, F: Field> PartitionWitness<'a, F> {
@@ -312,6 +410,7 @@ impl<'a, F: Field> PartitionWitness<'a, F> {
             representative_map,
             num_wires,
             degree,
+            target_names: None,
         }
     }
 
@@ -322,9 +421,15 @@ impl<'a, F: Field> PartitionWitness<'a, F> {
         let rep_value = &mut self.values[rep_index];
         if let Some(old_value) = *rep_value {
             assert_eq!(
-                value, old_value,
-                "Partition containing {:?} was set twice with different values: {} != {}",
-                target, old_value, value
+                value,
+                old_value,
+                "Partition containing {:?}{} was set twice with different values: {} != {}",
+                target,
+                self.target_names
+                    .and_then(|names| names.get(&rep_index))
+                    .map_or(String::new(), |name| format!(" ({})", name)),
+                old_value,
+                value
             );
             None
         } else {
@@ -338,6 +443,12 @@ impl<'a, F: Field> PartitionWitness<'a, F> {
     }
 
     pub fn full_witness(self) -> MatrixWitness<F> {
+        self.to_full_witness()
+    }
+
+    /// Like [`Self::full_witness`], but leaves this witness intact, e.g. so that its buffer can be
+    /// recycled into a [`WitnessArena`](crate::iop::generator::WitnessArena).
+    pub fn to_full_witness(&self) -> MatrixWitness<F> {
         let mut wire_values = vec![vec![F::ZERO; self.degree]; self.num_wires];
         for i in 0..self.degree {
             for j in 0..self.num_wires {
@@ -364,3 +475,71 @@ impl<'a, F: Field> Witness<F> for PartitionWitness<'a, F> {
         self.values[rep_index]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::types::Sample;
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    #[test]
+    fn test_partial_witness_dump() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x = builder.add_virtual_target();
+        let y = builder.add_virtual_target();
+        let z = builder.mul(x, y);
+        builder.register_public_input(z);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::rand());
+        pw.set_target(y, F::rand());
+        pw.set_target(Target::wire(0, 1), F::rand());
+        let dump = pw.to_bytes();
+
+        let loaded = PartialWitness::<F>::from_bytes(dump.clone())?;
+        assert_eq!(loaded.target_values, pw.target_values);
+        assert_eq!(loaded.to_bytes(), dump);
+
+        let mut truncated = dump;
+        truncated.pop();
+        assert!(PartialWitness::<F>::from_bytes(truncated).is_err());
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::rand());
+        pw.set_target(y, F::rand());
+        let proof = data.prove_from_dump(pw.to_bytes())?;
+        data.verify(proof)
+    }
+
+    #[test]
+    #[should_panic(expected = "(root > product) was set twice with different values")]
+    fn test_named_target_in_error() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x = builder.add_virtual_target();
+        let y = builder.add_virtual_target();
+        let z = builder.mul(x, y);
+        builder.name_target(z, "product");
+        assert_eq!(builder.target_name(z), Some("root > product"));
+        builder.register_public_input(z);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::ONE);
+        pw.set_target(y, F::ONE);
+        pw.set_target(z, F::TWO);
+        data.prove(pw).unwrap();
+    }
+}
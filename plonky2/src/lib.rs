@@ -7,12 +7,15 @@ extern crate alloc;
 #[doc(inline)]
 pub use plonky2_field as field;
 
+#[cfg(feature = "experimental")]
+pub mod folding;
 pub mod fri;
 pub mod gadgets;
 pub mod gates;
 pub mod hash;
 pub mod iop;
 pub mod lookup_test;
+pub mod pcs;
 pub mod plonk;
 pub mod recursion;
 pub mod util;
@@ -0,0 +1,493 @@
+//! An experimental inner product argument (IPA) backend, in the style of Bulletproofs and Halo, over
+//! the ecGFp5 curve.
+//!
+//! Each polynomial is committed to with a Pedersen vector commitment `sum_i a_i G_i` to its
+//! coefficients, for generators `G_i` hashed to the curve. There is thus no trusted setup and no
+//! LDE: polynomials can have any power-of-two length, with no rate or coset. An opening proof holds
+//! `2 log2(n)` points per opening point, but its verifier does work linear in the length `n`.
+//!
+//! The curve's scalar field `Z_q` is larger than Goldilocks rather than equal to it, so openings
+//! are proven over the integers. The inner product of the canonical coefficients of a polynomial
+//! with one coordinate of the canonical powers of a point of `GF(p^2)` is below `n p^2 < q`, so the
+//! argument computes it exactly in `Z_q`. The proof includes these integer inner products, whose
+//! reductions mod `p` are the coordinates of the evaluation.
+//!
+//! This is an experiment, not a replacement of FRI. Commitments bind vectors of `Z_q`, and nothing
+//! checks that the committed coefficients are canonical Goldilocks elements, so a prover committing
+//! to larger ones can open values that no polynomial over Goldilocks has. Openings aren't hiding
+//! either.
+
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use anyhow::{ensure, Result};
+use num::bigint::BigUint;
+use num::Zero;
+
+use crate::field::extension::quadratic::QuadraticExtension;
+use crate::field::extension::FieldExtension;
+use crate::field::goldilocks_field::GoldilocksField;
+use crate::field::polynomial::PolynomialCoeffs;
+use crate::field::types::{Field, PrimeField64};
+use crate::fri::structure::{FriInstanceInfo, FriOpenings};
+use crate::gadgets::ecgfp5::{group_order, sqrt, AffinePoint, GFp5, ProjectivePoint, A, B};
+use crate::hash::hashing::hash_n_to_m_no_pad;
+use crate::hash::poseidon::PoseidonPermutation;
+use crate::iop::challenger::Challenger;
+use crate::pcs::PolynomialCommitmentScheme;
+use crate::plonk::config::Hasher;
+use crate::util::timing::TimingTree;
+
+type F = GoldilocksField;
+
+/// The IPA over ecGFp5, for polynomials of degree below `2^degree_bits`.
+#[derive(Clone, Debug)]
+pub struct IpaPcs<H> {
+    pub degree_bits: usize,
+    /// The generators of the commitments to coefficients.
+    generators: Vec<ProjectivePoint>,
+    /// The generator of the inner products, independent of `generators`.
+    u: ProjectivePoint,
+    _phantom: PhantomData<H>,
+}
+
+/// The polynomials of a committed batch, along with their commitments.
+#[derive(Clone, Debug)]
+pub struct IpaCommitted {
+    pub polynomials: Vec<PolynomialCoeffs<F>>,
+    pub commitments: Vec<ProjectivePoint>,
+}
+
+/// A proof of the openings of several polynomials at a point.
+#[derive(Clone, Debug)]
+pub struct IpaBatchProof {
+    /// For each polynomial, the integer inner products of its coefficients with both coordinates of
+    /// the powers of the point.
+    pub lifted_values: Vec<[BigUint; 2]>,
+    /// The cross terms of each round of the argument.
+    pub ls: Vec<ProjectivePoint>,
+    pub rs: Vec<ProjectivePoint>,
+    /// The folded vector of coefficients, of length one.
+    pub a: BigUint,
+}
+
+/// Proofs of the openings at each point of an instance, in the order of its batches.
+#[derive(Clone, Debug)]
+pub struct IpaOpeningProof {
+    pub batches: Vec<IpaBatchProof>,
+}
+
+impl<H> IpaPcs<H> {
+    pub fn new(degree_bits: usize) -> Self {
+        let n = 1 << degree_bits;
+        Self {
+            degree_bits,
+            generators: (0..n).map(hash_to_curve).collect(),
+            u: hash_to_curve(n),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// A point of the subgroup of order `q` with no known discrete logarithm, found by hashing `index`
+/// and a counter to `x` coordinates until one is on the curve, then clearing the cofactor.
+fn hash_to_curve(index: usize) -> ProjectivePoint {
+    (0u64..)
+        .find_map(|counter| {
+            let inputs = [
+                F::from_canonical_usize(index),
+                F::from_canonical_u64(counter),
+            ];
+            let hash = hash_n_to_m_no_pad::<F, PoseidonPermutation<F>>(&inputs, 5);
+            let x = <GFp5 as FieldExtension<5>>::from_basefield_array(hash.try_into().unwrap());
+            let y = sqrt(x.cube() + A * x + B)?;
+            let point = AffinePoint { x, y }.to_projective().double();
+            (!point.is_identity()).then_some(point)
+        })
+        .unwrap()
+}
+
+fn msm(points: &[ProjectivePoint], scalars: &[BigUint]) -> ProjectivePoint {
+    points
+        .iter()
+        .zip(scalars)
+        .fold(ProjectivePoint::IDENTITY, |acc, (p, s)| {
+            acc + p.scalar_mul(s)
+        })
+}
+
+fn inner_product(a: &[BigUint], b: &[BigUint]) -> BigUint {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn to_biguint(x: F) -> BigUint {
+    BigUint::from(x.to_canonical_u64())
+}
+
+fn observe_point<H: Hasher<F>>(challenger: &mut Challenger<F, H>, point: &ProjectivePoint) {
+    // `(0, 0)` isn't on the curve, so it can stand for the identity.
+    let (x, y) = point
+        .to_affine()
+        .map_or((GFp5::ZERO, GFp5::ZERO), |p| (p.x, p.y));
+    challenger.observe_elements(&<GFp5 as FieldExtension<5>>::to_basefield_array(&x));
+    challenger.observe_elements(&<GFp5 as FieldExtension<5>>::to_basefield_array(&y));
+}
+
+/// Observes `scalar`, which must be below `q`, as ten 32-bit limbs.
+fn observe_scalar<H: Hasher<F>>(challenger: &mut Challenger<F, H>, scalar: &BigUint) {
+    let mut limbs = scalar.to_u32_digits();
+    limbs.resize(10, 0);
+    for limb in limbs {
+        challenger.observe_element(F::from_canonical_u32(limb));
+    }
+}
+
+/// A challenge of 128 bits, as in Halo, which keeps the verifier's scalar multiplications by
+/// challenges short.
+fn scalar_challenge<H: Hasher<F>>(challenger: &mut Challenger<F, H>) -> BigUint {
+    challenger
+        .get_n_challenges(2)
+        .iter()
+        .rev()
+        .fold(BigUint::zero(), |acc, c| (acc << 64) + c.to_canonical_u64())
+}
+
+/// The canonical coordinates of the first `n` powers of `point`.
+fn lifted_powers(point: QuadraticExtension<F>, n: usize) -> [Vec<BigUint>; 2] {
+    let (us, ws): (Vec<_>, Vec<_>) = point
+        .powers()
+        .take(n)
+        .map(|x| {
+            let [u, w] = x.to_basefield_array();
+            (to_biguint(u), to_biguint(w))
+        })
+        .unzip();
+    [us, ws]
+}
+
+/// The challenges which batch the openings at a point: `r` combines the polynomials, `s` the
+/// coordinates of the point's powers, and `x` scales the generator of inner products.
+fn batch_challenges<H: Hasher<F>>(
+    challenger: &mut Challenger<F, H>,
+    lifted_values: &[[BigUint; 2]],
+) -> (BigUint, BigUint, BigUint) {
+    for value in lifted_values.iter().flatten() {
+        observe_scalar(challenger, value);
+    }
+    let r = scalar_challenge(challenger);
+    let s = scalar_challenge(challenger);
+    let x = scalar_challenge(challenger);
+    (r, s, x)
+}
+
+/// Computes `sum_j r^j values[j] mod q` with Horner's method.
+fn combine_scalars<'a>(
+    values: impl DoubleEndedIterator<Item = &'a BigUint>,
+    r: &BigUint,
+    q: &BigUint,
+) -> BigUint {
+    values
+        .rev()
+        .fold(BigUint::zero(), |acc, v| (acc * r + v) % q)
+}
+
+impl<H: Hasher<F>> PolynomialCommitmentScheme<F, 2> for IpaPcs<H> {
+    type Hasher = H;
+    type Commitment = Vec<ProjectivePoint>;
+    type Committed = IpaCommitted;
+    type OpeningProof = IpaOpeningProof;
+
+    fn commit(
+        &self,
+        polynomials: Vec<PolynomialCoeffs<F>>,
+        _timing: &mut TimingTree,
+    ) -> Self::Committed {
+        assert!(polynomials.iter().all(|p| p.len() == self.generators.len()));
+        let commitments = polynomials
+            .iter()
+            .map(|p| {
+                let coeffs = p.coeffs.iter().copied().map(to_biguint).collect::<Vec<_>>();
+                msm(&self.generators, &coeffs)
+            })
+            .collect();
+        IpaCommitted {
+            polynomials,
+            commitments,
+        }
+    }
+
+    fn commitment(&self, committed: &Self::Committed) -> Self::Commitment {
+        committed.commitments.clone()
+    }
+
+    fn observe_commitment(
+        challenger: &mut Challenger<F, Self::Hasher>,
+        commitment: &Self::Commitment,
+    ) {
+        for point in commitment {
+            observe_point(challenger, point);
+        }
+    }
+
+    fn prove_openings(
+        &self,
+        instance: &FriInstanceInfo<F, 2>,
+        committed: &[&Self::Committed],
+        challenger: &mut Challenger<F, Self::Hasher>,
+        _timing: &mut TimingTree,
+    ) -> Self::OpeningProof {
+        let q = group_order();
+        let n = self.generators.len();
+        let batches = instance
+            .batches
+            .iter()
+            .map(|batch| {
+                let coeffs = batch
+                    .polynomials
+                    .iter()
+                    .map(|p| {
+                        committed[p.oracle_index].polynomials[p.polynomial_index]
+                            .coeffs
+                            .iter()
+                            .copied()
+                            .map(to_biguint)
+                            .collect::<Vec<_>>()
+                    })
+                    .collect::<Vec<_>>();
+                let powers = lifted_powers(batch.point, n);
+                let lifted_values = coeffs
+                    .iter()
+                    .map(|a| powers.clone().map(|b| inner_product(a, &b)))
+                    .collect::<Vec<_>>();
+                let (r, s, x) = batch_challenges(challenger, &lifted_values);
+
+                let mut a = (0..n)
+                    .map(|i| combine_scalars(coeffs.iter().map(|c| &c[i]), &r, &q))
+                    .collect::<Vec<_>>();
+                let mut b = (0..n)
+                    .map(|i| (&powers[0][i] + &s * &powers[1][i]) % &q)
+                    .collect::<Vec<_>>();
+                let mut g = self.generators.clone();
+                let u = self.u.scalar_mul(&x);
+
+                // Each round halves the vectors, with `a' = x a_lo + a_hi`, `b' = b_lo + x b_hi`
+                // and `G' = G_lo + x G_hi`, so that the commitment `P = <a, G> + <a, b> U` folds to
+                // `P' = x P + x^2 L + R`.
+                let mut ls = Vec::with_capacity(self.degree_bits);
+                let mut rs = Vec::with_capacity(self.degree_bits);
+                while a.len() > 1 {
+                    let half = a.len() / 2;
+                    let (a_lo, a_hi) = a.split_at(half);
+                    let (b_lo, b_hi) = b.split_at(half);
+                    let (g_lo, g_hi) = g.split_at(half);
+                    let left = msm(g_hi, a_lo) + u.scalar_mul(&(inner_product(a_lo, b_hi) % &q));
+                    let right = msm(g_lo, a_hi) + u.scalar_mul(&(inner_product(a_hi, b_lo) % &q));
+                    observe_point(challenger, &left);
+                    observe_point(challenger, &right);
+                    ls.push(left);
+                    rs.push(right);
+
+                    let x = scalar_challenge(challenger);
+                    a = (0..half).map(|i| (&x * &a_lo[i] + &a_hi[i]) % &q).collect();
+                    b = (0..half).map(|i| (&b_lo[i] + &x * &b_hi[i]) % &q).collect();
+                    g = (0..half)
+                        .map(|i| g_lo[i] + g_hi[i].scalar_mul(&x))
+                        .collect();
+                }
+
+                IpaBatchProof {
+                    lifted_values,
+                    ls,
+                    rs,
+                    a: a.pop().unwrap(),
+                }
+            })
+            .collect();
+        IpaOpeningProof { batches }
+    }
+
+    fn verify_openings(
+        &self,
+        instance: &FriInstanceInfo<F, 2>,
+        openings: &FriOpenings<F, 2>,
+        commitments: &[Self::Commitment],
+        proof: &Self::OpeningProof,
+        challenger: &mut Challenger<F, Self::Hasher>,
+    ) -> Result<()> {
+        let q = group_order();
+        let n = self.generators.len();
+        ensure!(
+            commitments.len() == instance.oracles.len()
+                && commitments
+                    .iter()
+                    .zip(&instance.oracles)
+                    .all(|(c, oracle)| c.len() == oracle.num_polys),
+            "Commitments don't match the instance's oracles"
+        );
+        ensure!(
+            proof.batches.len() == instance.batches.len()
+                && openings.batches.len() == instance.batches.len(),
+            "Wrong number of batches"
+        );
+
+        for ((batch, opening), batch_proof) in instance
+            .batches
+            .iter()
+            .zip(&openings.batches)
+            .zip(&proof.batches)
+        {
+            ensure!(
+                batch_proof.lifted_values.len() == batch.polynomials.len()
+                    && opening.values.len() == batch.polynomials.len(),
+                "Wrong number of opened values"
+            );
+            ensure!(
+                batch_proof.ls.len() == self.degree_bits
+                    && batch_proof.rs.len() == self.degree_bits,
+                "Wrong number of rounds"
+            );
+            // A component of small order in `L` or `R` would vanish for some challenges.
+            ensure!(
+                batch_proof
+                    .ls
+                    .iter()
+                    .chain(&batch_proof.rs)
+                    .all(ProjectivePoint::is_in_subgroup),
+                "A cross term isn't in the subgroup of prime order"
+            );
+            for (lifted, value) in batch_proof.lifted_values.iter().zip(&opening.values) {
+                ensure!(lifted.iter().all(|v| v < &q), "Lifted value out of range");
+                let reduced = lifted.clone().map(F::from_noncanonical_biguint);
+                ensure!(
+                    reduced == value.to_basefield_array(),
+                    "Lifted value doesn't match the opening"
+                );
+            }
+
+            let (r, s, x) = batch_challenges(challenger, &batch_proof.lifted_values);
+            let u = self.u.scalar_mul(&x);
+            let combined_commitment = batch
+                .polynomials
+                .iter()
+                .rev()
+                .fold(ProjectivePoint::IDENTITY, |acc, p| {
+                    acc.scalar_mul(&r) + commitments[p.oracle_index][p.polynomial_index]
+                });
+            let values = batch_proof
+                .lifted_values
+                .iter()
+                .map(|[v0, v1]| (v0 + &s * v1) % &q)
+                .collect::<Vec<_>>();
+            let combined_value = combine_scalars(values.iter(), &r, &q);
+            let mut p = combined_commitment + u.scalar_mul(&combined_value);
+
+            let powers = lifted_powers(batch.point, n);
+            let mut b = (0..n)
+                .map(|i| (&powers[0][i] + &s * &powers[1][i]) % &q)
+                .collect::<Vec<_>>();
+            let mut g = self.generators.clone();
+            for (left, right) in batch_proof.ls.iter().zip(&batch_proof.rs) {
+                observe_point(challenger, left);
+                observe_point(challenger, right);
+                let x = scalar_challenge(challenger);
+                p = p.scalar_mul(&x) + left.scalar_mul(&x).scalar_mul(&x) + *right;
+
+                let half = b.len() / 2;
+                let (b_lo, b_hi) = b.split_at(half);
+                let (g_lo, g_hi) = g.split_at(half);
+                b = (0..half).map(|i| (&b_lo[i] + &x * &b_hi[i]) % &q).collect();
+                g = (0..half)
+                    .map(|i| g_lo[i] + g_hi[i].scalar_mul(&x))
+                    .collect();
+            }
+
+            let a = &batch_proof.a % &q;
+            let ab = (&a * &b[0]) % &q;
+            ensure!(
+                p == g[0].scalar_mul(&a) + u.scalar_mul(&ab),
+                "Inner product argument failed"
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::field::types::Sample;
+    use crate::fri::structure::{FriBatchInfo, FriOpeningBatch, FriOracleInfo, FriPolynomialInfo};
+    use crate::hash::poseidon::PoseidonHash;
+    use crate::pcs::tests::prove_and_verify;
+
+    #[test]
+    fn test_ipa_pcs() -> Result<()> {
+        let degree_bits = 3;
+        let pcs = IpaPcs::<PoseidonHash>::new(degree_bits);
+        let polynomials = || {
+            (0..2)
+                .map(|_| PolynomialCoeffs::new(F::rand_vec(1 << degree_bits)))
+                .collect::<Vec<_>>()
+        };
+        prove_and_verify(&pcs, polynomials(), degree_bits, false)?;
+        assert!(prove_and_verify(&pcs, polynomials(), degree_bits, true).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_ipa_rejects_cross_terms_outside_subgroup() -> Result<()> {
+        let degree_bits = 2;
+        let pcs = IpaPcs::<PoseidonHash>::new(degree_bits);
+        let polynomial = PolynomialCoeffs::new(F::rand_vec(1 << degree_bits));
+        let committed = pcs.commit(vec![polynomial.clone()], &mut TimingTree::default());
+        let commitment = pcs.commitment(&committed);
+
+        let point = QuadraticExtension::<F>::rand();
+        let instance = FriInstanceInfo {
+            oracles: vec![FriOracleInfo {
+                num_polys: 1,
+                blinding: false,
+                extra_rate_bits: 0,
+                leaf_group_bits: 0,
+            }],
+            batches: vec![FriBatchInfo {
+                point,
+                polynomials: FriPolynomialInfo::from_range(0, 0..1),
+            }],
+        };
+        let openings = FriOpenings {
+            batches: vec![FriOpeningBatch {
+                values: vec![polynomial.to_extension::<2>().eval(point)],
+            }],
+        };
+        let mut challenger = Challenger::<F, PoseidonHash>::new();
+        let mut proof = pcs.prove_openings(
+            &instance,
+            &[&committed],
+            &mut challenger.clone(),
+            &mut TimingTree::default(),
+        );
+        pcs.verify_openings(
+            &instance,
+            &openings,
+            &[commitment.clone()],
+            &proof,
+            &mut challenger.clone(),
+        )?;
+
+        // Shift `L` by the point of order 2, which `x^2 L` would absorb for any even challenge.
+        let two_thirds = GFp5::TWO / GFp5::from_canonical_u32(3);
+        let order_two = AffinePoint {
+            x: two_thirds,
+            y: GFp5::ZERO,
+        };
+        proof.batches[0].ls[0] = proof.batches[0].ls[0] + order_two.to_projective();
+        let err = pcs
+            .verify_openings(&instance, &openings, &[commitment], &proof, &mut challenger)
+            .unwrap_err();
+        assert!(err.to_string().contains("subgroup"));
+        Ok(())
+    }
+}
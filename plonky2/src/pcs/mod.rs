@@ -0,0 +1,271 @@
+//! A common interface for polynomial commitment schemes.
+//!
+//! Provers commit to batches of polynomials, then open them at points derived from the transcript.
+//! [`PolynomialCommitmentScheme`] captures this flow, with the openings described by a
+//! [`FriInstanceInfo`]; schemes other than FRI ignore its oracles' rates and leaf groups.
+//! [`FriPcs`] implements it with FRI over Merkle trees of LDEs. With the `experimental` feature,
+//! `ipa::IpaPcs` implements it with an inner product argument over the ecGFp5 curve, whose openings
+//! aren't sound for Goldilocks polynomials.
+//!
+//! The trait is for comparing backends on the same openings; the plonky2 and starky provers and
+//! verifiers don't go through it. They commit with [`PolynomialBatch`] directly, as they need leaf
+//! groups, blinding and LDEs computed from values, and they derive the FRI challenges together with
+//! the rest of the transcript. There is no KZG backend: KZG commits to polynomials over the scalar
+//! field of a pairing-friendly curve such as BN254, so it can't open the Goldilocks polynomials of
+//! a plonky2 proof.
+
+#[cfg(feature = "experimental")]
+pub mod ipa;
+
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::marker::PhantomData;
+
+use anyhow::Result;
+
+use crate::field::extension::Extendable;
+use crate::field::polynomial::PolynomialCoeffs;
+use crate::fri::oracle::PolynomialBatch;
+use crate::fri::proof::FriProof;
+use crate::fri::structure::{FriInstanceInfo, FriOpenings};
+use crate::fri::verifier::verify_fri_proof;
+use crate::fri::FriParams;
+use crate::hash::hash_types::RichField;
+use crate::hash::merkle_tree::MerkleCap;
+use crate::iop::challenger::Challenger;
+use crate::plonk::config::{GenericConfig, Hasher};
+use crate::util::timing::TimingTree;
+
+/// A scheme for committing to batches of polynomials and proving their evaluations.
+pub trait PolynomialCommitmentScheme<F: RichField + Extendable<D>, const D: usize> {
+    /// The hasher of the Fiat-Shamir transcript.
+    type Hasher: Hasher<F>;
+    /// A commitment to a batch of polynomials, as sent to the verifier.
+    type Commitment: Clone + Debug + Eq;
+    /// The prover's data for a committed batch.
+    type Committed;
+    /// A proof of the evaluations of several batches at several points.
+    type OpeningProof: Clone + Debug;
+
+    /// Commits to `polynomials`, whose degrees must be within the scheme's bound.
+    fn commit(
+        &self,
+        polynomials: Vec<PolynomialCoeffs<F>>,
+        timing: &mut TimingTree,
+    ) -> Self::Committed;
+
+    fn commitment(&self, committed: &Self::Committed) -> Self::Commitment;
+
+    fn observe_commitment(
+        challenger: &mut Challenger<F, Self::Hasher>,
+        commitment: &Self::Commitment,
+    );
+
+    /// Proves the openings described by `instance`, whose `i`-th oracle is `committed[i]`.
+    /// `challenger` must have observed the commitments and the opened values.
+    fn prove_openings(
+        &self,
+        instance: &FriInstanceInfo<F, D>,
+        committed: &[&Self::Committed],
+        challenger: &mut Challenger<F, Self::Hasher>,
+        timing: &mut TimingTree,
+    ) -> Self::OpeningProof;
+
+    /// Checks that `openings` are the values of the polynomials of `instance` at its points.
+    /// `challenger` must be in the same state as the prover's was in `prove_openings`.
+    fn verify_openings(
+        &self,
+        instance: &FriInstanceInfo<F, D>,
+        openings: &FriOpenings<F, D>,
+        commitments: &[Self::Commitment],
+        proof: &Self::OpeningProof,
+        challenger: &mut Challenger<F, Self::Hasher>,
+    ) -> Result<()>;
+}
+
+/// FRI over Merkle trees of LDEs, for polynomials of degree below `2^params.degree_bits`.
+#[derive(Clone, Debug)]
+pub struct FriPcs<C> {
+    pub params: FriParams,
+    _phantom: PhantomData<C>,
+}
+
+impl<C> FriPcs<C> {
+    pub fn new(params: FriParams) -> Self {
+        Self {
+            params,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<F, C, const D: usize> PolynomialCommitmentScheme<F, D> for FriPcs<C>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+{
+    type Hasher = C::Hasher;
+    type Commitment = MerkleCap<F, C::Hasher>;
+    type Committed = PolynomialBatch<F, C, D>;
+    type OpeningProof = FriProof<F, C::Hasher, D>;
+
+    fn commit(
+        &self,
+        polynomials: Vec<PolynomialCoeffs<F>>,
+        timing: &mut TimingTree,
+    ) -> Self::Committed {
+        assert!(polynomials
+            .iter()
+            .all(|p| p.len() == 1 << self.params.degree_bits));
+        PolynomialBatch::from_coeffs_on_coset(
+            polynomials,
+            self.params.config.rate_bits,
+            self.params.hiding,
+            self.params.config.cap_height,
+            self.params.config.coset_shift::<F>(),
+            timing,
+            None,
+        )
+    }
+
+    fn commitment(&self, committed: &Self::Committed) -> Self::Commitment {
+        committed.merkle_tree.cap.clone()
+    }
+
+    fn observe_commitment(
+        challenger: &mut Challenger<F, Self::Hasher>,
+        commitment: &Self::Commitment,
+    ) {
+        challenger.observe_cap(commitment);
+    }
+
+    fn prove_openings(
+        &self,
+        instance: &FriInstanceInfo<F, D>,
+        committed: &[&Self::Committed],
+        challenger: &mut Challenger<F, Self::Hasher>,
+        timing: &mut TimingTree,
+    ) -> Self::OpeningProof {
+        PolynomialBatch::prove_openings(instance, committed, challenger, &self.params, timing)
+    }
+
+    fn verify_openings(
+        &self,
+        instance: &FriInstanceInfo<F, D>,
+        openings: &FriOpenings<F, D>,
+        commitments: &[Self::Commitment],
+        proof: &Self::OpeningProof,
+        challenger: &mut Challenger<F, Self::Hasher>,
+    ) -> Result<()> {
+        let challenges = challenger.fri_challenges::<C, D>(
+            &proof.commit_phase_merkle_caps,
+            &proof.final_poly,
+            proof.pow_witness,
+            self.params.degree_bits,
+            &self.params.config,
+        );
+        verify_fri_proof::<F, C, D>(
+            instance,
+            openings,
+            &challenges,
+            commitments,
+            proof,
+            &self.params,
+        )
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::field::extension::FieldExtension;
+    use crate::field::types::{Field, Sample};
+    use crate::fri::structure::{FriBatchInfo, FriOpeningBatch, FriOracleInfo, FriPolynomialInfo};
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+    type FE = <F as Extendable<D>>::Extension;
+
+    /// Commits to `polynomials` and proves their openings at a random point and its successor in
+    /// the trace domain, with `pcs`.
+    pub(crate) fn prove_and_verify<P: PolynomialCommitmentScheme<F, D>>(
+        pcs: &P,
+        polynomials: Vec<PolynomialCoeffs<F>>,
+        degree_bits: usize,
+        tamper: bool,
+    ) -> Result<()> {
+        let num_polys = polynomials.len();
+        let opened_polys = polynomials
+            .iter()
+            .map(|p| p.to_extension::<D>())
+            .collect::<Vec<_>>();
+        let mut timing = TimingTree::default();
+        let committed = pcs.commit(polynomials, &mut timing);
+        let commitment = pcs.commitment(&committed);
+
+        let mut challenger = Challenger::<F, P::Hasher>::new();
+        P::observe_commitment(&mut challenger, &commitment);
+        let zeta = challenger.get_extension_challenge::<D>();
+        let points = [
+            zeta,
+            <FE as FieldExtension<D>>::scalar_mul(&zeta, F::primitive_root_of_unity(degree_bits)),
+        ];
+        let instance = FriInstanceInfo {
+            oracles: vec![FriOracleInfo {
+                num_polys,
+                blinding: false,
+                extra_rate_bits: 0,
+                leaf_group_bits: 0,
+            }],
+            batches: points
+                .iter()
+                .map(|&point| FriBatchInfo {
+                    point,
+                    polynomials: FriPolynomialInfo::from_range(0, 0..num_polys),
+                })
+                .collect(),
+        };
+        let mut openings = FriOpenings {
+            batches: points
+                .iter()
+                .map(|&point| FriOpeningBatch {
+                    values: opened_polys.iter().map(|p| p.eval(point)).collect(),
+                })
+                .collect(),
+        };
+        challenger.observe_openings(&openings);
+        let mut verifier_challenger = challenger.clone();
+        let proof = pcs.prove_openings(&instance, &[&committed], &mut challenger, &mut timing);
+
+        if tamper {
+            openings.batches[1].values[0] += FE::ONE;
+        }
+        pcs.verify_openings(
+            &instance,
+            &openings,
+            &[commitment],
+            &proof,
+            &mut verifier_challenger,
+        )
+    }
+
+    #[test]
+    fn test_fri_pcs() -> Result<()> {
+        let degree_bits = 8;
+        let config = CircuitConfig::standard_recursion_config().fri_config;
+        let pcs = FriPcs::<C>::new(config.fri_params(degree_bits, false));
+        let polynomials = || {
+            (0..3)
+                .map(|_| PolynomialCoeffs::new(F::rand_vec(1 << degree_bits)))
+                .collect::<Vec<_>>()
+        };
+        prove_and_verify(&pcs, polynomials(), degree_bits, false)?;
+        assert!(prove_and_verify(&pcs, polynomials(), degree_bits, true).is_err());
+        Ok(())
+    }
+}
@@ -1,7 +1,8 @@
 use alloc::collections::BTreeMap;
+use alloc::string::String;
 use alloc::sync::Arc;
-use alloc::vec;
 use alloc::vec::Vec;
+use alloc::{format, vec};
 use core::cmp::max;
 #[cfg(feature = "std")]
 use std::time::Instant;
@@ -17,7 +18,7 @@ use crate::field::fft::fft_root_table;
 use crate::field::polynomial::PolynomialValues;
 use crate::field::types::Field;
 use crate::fri::oracle::PolynomialBatch;
-use crate::fri::{FriConfig, FriParams};
+use crate::fri::FriParams;
 use crate::gadgets::arithmetic::BaseArithmeticOperation;
 use crate::gadgets::arithmetic_extension::ExtensionArithmeticOperation;
 use crate::gadgets::polynomial::PolynomialCoeffsExtTarget;
@@ -26,13 +27,19 @@ use crate::gates::arithmetic_extension::ArithmeticExtensionGate;
 use crate::gates::constant::ConstantGate;
 use crate::gates::gate::{CurrentSlot, Gate, GateInstance, GateRef};
 use crate::gates::lookup::{Lookup, LookupGate};
-use crate::gates::lookup_table::LookupTable;
+use crate::gates::lookup_table::{LookupTable, LookupTableGate};
 use crate::gates::noop::NoopGate;
+use crate::gates::poseidon::PoseidonGate;
 use crate::gates::public_input::PublicInputGate;
-use crate::gates::selectors::{selector_ends_lookups, selector_polynomials, selectors_lookup};
-use crate::hash::hash_types::{HashOut, HashOutTarget, MerkleCapTarget, RichField};
+use crate::gates::selectors::{
+    selector_ends_lookups, selector_polynomials, selectors_info, selectors_lookup, LookupSelectors,
+};
+use crate::hash::hash_types::{
+    HashOut, HashOutTarget, MerkleCapTarget, RichField, NUM_HASH_OUT_ELTS,
+};
 use crate::hash::merkle_proofs::MerkleProofTarget;
 use crate::hash::merkle_tree::MerkleCap;
+use crate::hash::poseidon::SPONGE_RATE;
 use crate::iop::ext_target::ExtensionTarget;
 use crate::iop::generator::{
     ConstantGenerator, CopyGenerator, RandomValueGenerator, SimpleGenerator, WitnessGeneratorRef,
@@ -40,8 +47,8 @@ use crate::iop::generator::{
 use crate::iop::target::{BoolTarget, Target};
 use crate::iop::wire::Wire;
 use crate::plonk::circuit_data::{
-    CircuitConfig, CircuitData, CommonCircuitData, ProverCircuitData, ProverOnlyCircuitData,
-    VerifierCircuitData, VerifierCircuitTarget, VerifierOnlyCircuitData,
+    proof_size, CircuitConfig, CircuitData, CommonCircuitData, ProverCircuitData,
+    ProverOnlyCircuitData, VerifierCircuitData, VerifierCircuitTarget, VerifierOnlyCircuitData,
 };
 use crate::plonk::config::{AlgebraicHasher, GenericConfig, GenericHashOut, Hasher};
 use crate::plonk::copy_constraint::CopyConstraint;
@@ -83,6 +90,26 @@ pub struct LookupWire {
     /// Index of the first lookup table row (i.e. the last `LookupTableGate`).
     pub first_lut_gate: usize,
 }
+
+/// Statistics about a circuit under construction, as returned by [`CircuitBuilder::stats`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CircuitStats {
+    /// The number of gates added so far.
+    pub num_gates: usize,
+    /// The number of instances of each type of gate added so far, by gate ID.
+    pub gate_counts: Vec<(String, usize)>,
+    pub num_copy_constraints: usize,
+    pub num_public_inputs: usize,
+    pub num_luts: usize,
+    /// The total number of lookups, over all LUTs.
+    pub num_lookups: usize,
+    /// The estimated log2 of the circuit's degree once built, including the gates `build` adds for
+    /// public inputs, constants, lookups and blinding.
+    pub estimated_degree_bits: usize,
+    /// The estimated size in bytes of a serialized proof, assuming Poseidon hashes.
+    pub estimated_proof_size: usize,
+}
+
 pub struct CircuitBuilder<F: RichField + Extendable<D>, const D: usize> {
     pub config: CircuitConfig,
 
@@ -108,6 +135,9 @@ pub struct CircuitBuilder<F: RichField + Extendable<D>, const D: usize> {
     /// A tree of named scopes, used for debugging.
     context_log: ContextTree,
 
+    /// Debug names of targets, qualified by the scopes they were given in.
+    target_names: Vec<(Target, String)>,
+
     /// Generators used to generate the witness.
     generators: Vec<WitnessGeneratorRef<F, D>>,
 
@@ -156,6 +186,7 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             virtual_target_index: 0,
             copy_constraints: Vec::new(),
             context_log: ContextTree::new(),
+            target_names: Vec::new(),
             generators: Vec::new(),
             constants_to_targets: HashMap::new(),
             targets_to_constants: HashMap::new(),
@@ -174,26 +205,9 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
     }
 
     fn check_config(&self) {
-        let &CircuitConfig {
-            security_bits,
-            fri_config:
-                FriConfig {
-                    rate_bits,
-                    proof_of_work_bits,
-                    num_query_rounds,
-                    ..
-                },
-            ..
-        } = &self.config;
-
-        // Conjectured FRI security; see the ethSTARK paper.
-        let fri_field_bits = F::Extension::order().bits() as usize;
-        let fri_query_security_bits = num_query_rounds * rate_bits + proof_of_work_bits as usize;
-        let fri_security_bits = fri_field_bits.min(fri_query_security_bits);
-        assert!(
-            fri_security_bits >= security_bits,
-            "FRI params fall short of target security"
-        );
+        if let Err(e) = self.config.validate::<F, D>() {
+            panic!("Invalid circuit config: {e}");
+        }
     }
 
     pub fn set_domain_separator(&mut self, separator: Vec<F>) {
@@ -571,6 +585,31 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         self.context_log.pop(self.num_gates());
     }
 
+    /// Gives `target` a name for debugging, which is qualified by the current stack of contexts,
+    /// e.g. `root > sha256 > round 3 > carry`. Names show up in witness generation errors
+    /// involving the target, or any target connected to it, and in exported
+    /// [`GeneratorGraph`](crate::iop::generator_graph::GeneratorGraph)s.
+    pub fn name_target(&mut self, target: Target, name: &str) {
+        let name = format!("{} > {}", self.context_log.open_stack(), name);
+        self.target_names.push((target, name));
+    }
+
+    /// The name given to `target` with [`Self::name_target`], if any. If it was named more than
+    /// once, returns the latest name.
+    pub fn target_name(&self, target: Target) -> Option<&str> {
+        self.target_names
+            .iter()
+            .rev()
+            .find(|(t, _)| *t == target)
+            .map(|(_, name)| name.as_str())
+    }
+
+    /// For each gate added so far, a description of the stack of contexts in which it was added.
+    /// Useful to annotate a [`GeneratorGraph`](crate::iop::generator_graph::GeneratorGraph).
+    pub fn gate_contexts(&self) -> Vec<String> {
+        self.context_log.gate_contexts(self.num_gates())
+    }
+
     /// Returns the total number of LUTs.
     pub fn get_luts_length(&self) -> usize {
         self.luts.len()
@@ -893,16 +932,150 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
 
         // Print total count of each gate type.
         debug!("Total gate counts:");
-        for gate in self.gates.iter().cloned() {
-            let count = self
-                .gate_instances
-                .iter()
-                .filter(|inst| inst.gate_ref == gate)
-                .count();
+        for (gate, count) in self.gate_counts() {
             debug!("- {} instances of {}", count, gate.0.id());
         }
     }
 
+    /// The number of instances of each type of gate added so far, sorted by gate ID. Gates added
+    /// by `build`, e.g. for constants and public inputs, are not included.
+    pub fn gate_counts(&self) -> Vec<(GateRef<F, D>, usize)> {
+        self.gates
+            .iter()
+            .map(|gate| {
+                let count = self
+                    .gate_instances
+                    .iter()
+                    .filter(|inst| &inst.gate_ref == gate)
+                    .count();
+                (gate.clone(), count)
+            })
+            .sorted_by_key(|(gate, _)| gate.0.id())
+            .collect()
+    }
+
+    /// Summarizes the circuit built so far, to help find what makes it large.
+    pub fn stats(&self) -> CircuitStats {
+        let estimated_degree_bits = log2_ceil(self.estimated_num_rows());
+        let fri_params = self.fri_params(estimated_degree_bits);
+        let quotient_degree_factor = self.config.max_quotient_degree_factor;
+        let estimated_proof_size = proof_size::<D>(
+            &self.config,
+            &fri_params,
+            self.estimated_num_constants(),
+            num_partial_products(self.config.num_routed_wires, quotient_degree_factor),
+            self.num_lookup_polys(),
+            quotient_degree_factor,
+            self.public_inputs.len(),
+            NUM_HASH_OUT_ELTS * 8,
+        );
+
+        CircuitStats {
+            num_gates: self.num_gates(),
+            gate_counts: self
+                .gate_counts()
+                .into_iter()
+                .map(|(gate, count)| (gate.0.id(), count))
+                .collect(),
+            num_copy_constraints: self.copy_constraints.len(),
+            num_public_inputs: self.public_inputs.len(),
+            num_luts: self.num_luts(),
+            num_lookups: self
+                .lut_to_lookups
+                .iter()
+                .map(|lookups| lookups.len())
+                .sum(),
+            estimated_degree_bits,
+            estimated_proof_size,
+        }
+    }
+
+    pub fn print_stats(&self) {
+        let stats = self.stats();
+        debug!("Gates: {}", stats.num_gates);
+        for (gate, count) in &stats.gate_counts {
+            debug!("- {} instances of {}", count, gate);
+        }
+        debug!("Copy constraints: {}", stats.num_copy_constraints);
+        debug!("Public inputs: {}", stats.num_public_inputs);
+        debug!(
+            "LUTs: {}, with {} lookups",
+            stats.num_luts, stats.num_lookups
+        );
+        debug!(
+            "Estimated degree: 2^{}, estimated proof size: {} bytes",
+            stats.estimated_degree_bits, stats.estimated_proof_size
+        );
+    }
+
+    /// Estimates the number of rows `build` will produce, before padding to a power of two.
+    fn estimated_num_rows(&self) -> usize {
+        let mut num_rows = self.num_gates();
+        for lut_index in 0..self.num_luts() {
+            num_rows += ceil_div_usize(
+                self.lut_to_lookups[lut_index].len(),
+                LookupGate::num_slots(&self.config),
+            );
+            num_rows += ceil_div_usize(
+                self.get_luts_idx_length(lut_index),
+                LookupTableGate::num_slots(&self.config),
+            );
+            // A `NoopGate` follows each LUT.
+            num_rows += 1;
+        }
+        // Hashing the public inputs, and the `PublicInputGate`.
+        num_rows += ceil_div_usize(self.public_inputs.len(), SPONGE_RATE) + 1;
+        num_rows += ceil_div_usize(
+            self.constants_to_targets
+                .len()
+                .saturating_sub(self.constant_generators.len()),
+            self.config.num_constants,
+        );
+        if self.config.zero_knowledge {
+            let (regular_poly_openings, z_openings) = self.blinding_counts();
+            num_rows += regular_poly_openings + 2 * z_openings;
+        }
+        num_rows
+    }
+
+    /// Estimates the number of constant polynomials, including selectors.
+    fn estimated_num_constants(&self) -> usize {
+        let mut gates = self.gates.clone();
+        gates.insert(GateRef::new(PublicInputGate));
+        gates.insert(GateRef::new(NoopGate));
+        // The gates `build` adds to hash the public inputs, assuming Poseidon as for the rows, and
+        // to hold constants.
+        if !self.public_inputs.is_empty() {
+            gates.insert(GateRef::new(PoseidonGate::<F, D>::new()));
+        }
+        if self.constants_to_targets.len() > self.constant_generators.len() {
+            gates.insert(GateRef::new(ConstantGate {
+                num_consts: self.config.num_constants,
+            }));
+        }
+        let gates = gates
+            .into_iter()
+            .sorted_unstable_by_key(|g| (g.0.degree(), g.0.id()))
+            .collect::<Vec<_>>();
+        let selectors_info = selectors_info(&gates, self.config.max_quotient_degree_factor + 1);
+        let num_lookup_selectors = if self.num_luts() == 0 {
+            0
+        } else {
+            LookupSelectors::StartEnd as usize + self.num_luts()
+        };
+        selectors_info.num_selectors() + num_lookup_selectors + self.config.num_constants
+    }
+
+    fn num_lookup_polys(&self) -> usize {
+        if self.num_luts() == 0 {
+            0
+        } else {
+            let lookup_degree = self.config.max_quotient_degree_factor - 1;
+            // There is 1 RE polynomial and multiple Sum/LDC polynomials.
+            ceil_div_usize(LookupGate::num_slots(&self.config), lookup_degree) + 1
+        }
+    }
+
     /// In PLONK's permutation argument, there's a slight chance of division by zero. We can
     /// mitigate this by randomizing some unused witness elements, so if proving fails with
     /// division by zero, the next attempt will have an (almost) independent chance of success.
@@ -981,10 +1154,21 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         let degree = self.gate_instances.len();
         debug!("Degree after blinding & padding: {}", degree);
         let degree_bits = log2_strict(degree);
+        if let Err(e) = self.config.fri_config.validate_for_degree_bits(degree_bits) {
+            panic!("Invalid FRI config for a circuit of 2^{degree_bits} rows: {e}");
+        }
+        if let Err(e) = self
+            .config
+            .fri_config
+            .validate_coset_shift::<F>(degree_bits)
+        {
+            panic!("Invalid FRI config for a circuit of 2^{degree_bits} rows: {e}");
+        }
         let fri_params = self.fri_params(degree_bits);
         assert!(
-            fri_params.total_arities() <= degree_bits + rate_bits - cap_height,
-            "FRI total reduction arity is too large.",
+            fri_params.security_bits(F::Extension::order().bits() as usize)
+                >= self.config.security_bits,
+            "FRI params fall short of target security"
         );
 
         let quotient_degree_factor = self.config.max_quotient_degree_factor;
@@ -1024,11 +1208,12 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         let fft_root_table = fft_root_table(max_fft_points);
 
         let constants_sigmas_vecs = [constant_vecs, sigma_vecs.clone()].concat();
-        let constants_sigmas_commitment = PolynomialBatch::<F, C, D>::from_values(
+        let constants_sigmas_commitment = PolynomialBatch::<F, C, D>::from_values_on_coset(
             constants_sigmas_vecs,
             rate_bits,
             PlonkOracle::CONSTANTS_SIGMAS.blinding,
             cap_height,
+            self.config.fri_config.coset_shift(),
             &mut timing,
             Some(&fft_root_table),
         );
@@ -1056,6 +1241,19 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
                 .collect(),
         );
 
+        // Name each partition after the targets named in it.
+        let mut target_names = BTreeMap::<usize, String>::new();
+        for (target, name) in &self.target_names {
+            let rep = forest.parents[forest.target_index(*target)];
+            target_names
+                .entry(rep)
+                .and_modify(|names| {
+                    names.push_str(", ");
+                    names.push_str(name);
+                })
+                .or_insert_with(|| name.clone());
+        }
+
         // Index generator indices by their watched targets.
         let mut generator_indices_by_watches = BTreeMap::new();
         for (i, generator) in self.generators.iter().enumerate() {
@@ -1082,13 +1280,7 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         let num_partial_products =
             num_partial_products(self.config.num_routed_wires, quotient_degree_factor);
 
-        let lookup_degree = self.config.max_quotient_degree_factor - 1;
-        let num_lookup_polys = if num_luts == 0 {
-            0
-        } else {
-            // There is 1 RE polynomial and multiple Sum/LDC polynomials.
-            ceil_div_usize(LookupGate::num_slots(&self.config), lookup_degree) + 1
-        };
+        let num_lookup_polys = self.num_lookup_polys();
         let constants_sigmas_cap = constants_sigmas_commitment.merkle_tree.cap.clone();
         let domain_separator = self.domain_separator.unwrap_or_default();
         let domain_separator_digest = C::Hasher::hash_pad(&domain_separator);
@@ -1119,7 +1311,12 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             luts: self.luts,
         };
         if let Some(goal_data) = self.goal_common_data {
-            assert_eq!(goal_data, common, "The expected circuit data passed to cyclic recursion method did not match the actual circuit");
+            if let Err(e) = goal_data.check_compatible(&common) {
+                panic!(
+                    "The expected circuit data passed to cyclic recursion method did not match \
+                     the actual circuit: {e}"
+                );
+            }
         }
 
         let prover_only = ProverOnlyCircuitData::<F, C, D> {
@@ -1130,6 +1327,7 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             subgroup,
             public_inputs: self.public_inputs,
             representative_map: forest.parents,
+            target_names,
             fft_root_table: Some(fft_root_table),
             circuit_digest,
             lookup_rows: self.lookup_rows.clone(),
@@ -1165,3 +1363,118 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         circuit_data.verifier_data()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::field::types::PrimeField64;
+    use crate::fri::reduction_strategies::FriReductionStrategy;
+    use crate::fri::FriConfig;
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    #[test]
+    fn test_stats() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x = builder.add_virtual_public_input();
+        let x_cubed = builder.cube(x);
+        let y = builder.add_const(x_cubed, F::from_canonical_u64(5));
+        builder.register_public_input(y);
+        for _ in 0..300 {
+            builder.add_gate(NoopGate, vec![]);
+        }
+
+        let stats = builder.stats();
+        assert_eq!(stats.num_gates, builder.num_gates());
+        assert_eq!(
+            stats.num_gates,
+            stats.gate_counts.iter().map(|(_, n)| n).sum::<usize>()
+        );
+        assert!(stats.gate_counts.contains(&("NoopGate".into(), 300)));
+        assert_eq!(stats.num_copy_constraints, builder.copy_constraints.len());
+        assert_eq!(stats.num_public_inputs, 2);
+        assert_eq!(stats.num_luts, 0);
+        assert_eq!(stats.num_lookups, 0);
+
+        let data = builder.build::<C>();
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(3));
+        let proof = data.prove(pw)?;
+        let proof_size = proof.to_bytes().len();
+
+        assert_eq!(data.common.proof_size::<C>(), proof_size);
+        assert_eq!(stats.estimated_degree_bits, data.common.degree_bits());
+        assert_eq!(stats.estimated_proof_size, proof_size);
+        data.verify(proof)
+    }
+
+    #[test]
+    fn test_config_validation() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let standard = CircuitConfig::standard_recursion_config();
+        assert!(standard.validate::<F, D>().is_ok());
+        assert!(standard.fri_config.validate_for_degree_bits(12).is_ok());
+
+        let invalid_configs = [
+            CircuitConfig {
+                num_routed_wires: 140,
+                ..standard.clone()
+            },
+            CircuitConfig {
+                num_routed_wires: 7,
+                ..standard.clone()
+            },
+            CircuitConfig {
+                num_constants: 1,
+                ..standard.clone()
+            },
+            CircuitConfig {
+                max_quotient_degree_factor: 16,
+                ..standard.clone()
+            },
+            CircuitConfig {
+                fri_config: FriConfig {
+                    reduction_strategy: FriReductionStrategy::ConstantArityBits(0, 5),
+                    ..standard.fri_config
+                },
+                ..standard
+            },
+            CircuitConfig {
+                security_bits: 128,
+                ..standard.clone()
+            },
+        ];
+        for config in invalid_configs {
+            assert!(config.validate::<F, D>().is_err(), "{config:?}");
+        }
+
+        let shifted = |coset_shift| FriConfig {
+            coset_shift: Some(coset_shift),
+            ..standard.fri_config.clone()
+        };
+        assert!(standard.fri_config.validate_coset_shift::<F>(12).is_ok());
+        assert!(shifted(49).validate_coset_shift::<F>(12).is_ok());
+        // Zero, shifts within the LDE subgroup, and a non-canonical one.
+        let lde_root = F::primitive_root_of_unity(12 + standard.fri_config.rate_bits);
+        for coset_shift in [0, 1, lde_root.to_canonical_u64(), u64::MAX] {
+            assert!(shifted(coset_shift).validate_coset_shift::<F>(12).is_err());
+        }
+
+        let tall_cap = FriConfig {
+            cap_height: 12,
+            ..standard.fri_config
+        };
+        assert!(tall_cap.validate_for_degree_bits(12).is_ok());
+        assert!(tall_cap.validate_for_degree_bits(8).is_err());
+    }
+}
@@ -1,22 +1,27 @@
 use alloc::collections::BTreeMap;
-use alloc::vec;
+use alloc::string::String;
 use alloc::vec::Vec;
+use alloc::{format, vec};
 use core::ops::{Range, RangeFrom};
 
-use anyhow::Result;
-use serde::Serialize;
+use anyhow::{ensure, Result};
+use serde::{Deserialize, Serialize};
 
 use super::circuit_builder::LookupWire;
 use crate::field::extension::Extendable;
 use crate::field::fft::FftRootTable;
 use crate::field::types::Field;
+use crate::fri::onchain_cost::OnChainVerifierCost;
 use crate::fri::oracle::PolynomialBatch;
 use crate::fri::reduction_strategies::FriReductionStrategy;
+use crate::fri::security::SecurityAssumption;
 use crate::fri::structure::{
     FriBatchInfo, FriBatchInfoTarget, FriInstanceInfo, FriInstanceInfoTarget, FriOracleInfo,
     FriPolynomialInfo,
 };
 use crate::fri::{FriConfig, FriParams};
+use crate::gates::arithmetic_base::ArithmeticGate;
+use crate::gates::arithmetic_extension::ArithmeticExtensionGate;
 use crate::gates::gate::GateRef;
 use crate::gates::lookup::Lookup;
 use crate::gates::lookup_table::LookupTable;
@@ -29,16 +34,17 @@ use crate::iop::target::Target;
 use crate::iop::witness::PartialWitness;
 use crate::plonk::circuit_builder::CircuitBuilder;
 use crate::plonk::config::{GenericConfig, Hasher};
-use crate::plonk::plonk_common::PlonkOracle;
+use crate::plonk::plonk_common::{salt_size, PlonkOracle};
 use crate::plonk::proof::{CompressedProofWithPublicInputs, ProofWithPublicInputs};
 use crate::plonk::prover::prove;
-use crate::plonk::verifier::verify;
+use crate::plonk::verifier::{verify, verify_batch};
+use crate::util::log2_ceil;
 use crate::util::serialization::{
     Buffer, GateSerializer, IoResult, Read, WitnessGeneratorSerializer, Write,
 };
 use crate::util::timing::TimingTree;
 
-#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct CircuitConfig {
     pub num_wires: usize,
     pub num_routed_wires: usize,
@@ -68,6 +74,60 @@ impl CircuitConfig {
         self.num_wires - self.num_routed_wires
     }
 
+    /// Checks that the config is consistent, and that circuits over the extension of degree `D` of
+    /// `F` can be built and proven with it. The FRI parameters are checked again against the
+    /// circuit's size in `CircuitBuilder::build`.
+    pub fn validate<F: RichField + Extendable<D>, const D: usize>(&self) -> Result<()> {
+        ensure!(
+            self.num_routed_wires <= self.num_wires,
+            "`num_routed_wires` ({}) exceeds `num_wires` ({})",
+            self.num_routed_wires,
+            self.num_wires
+        );
+        ensure!(
+            ArithmeticExtensionGate::<D>::num_ops(self) > 0,
+            "Extension arithmetic needs at least {} routed wires, but the config has {}",
+            4 * D,
+            self.num_routed_wires
+        );
+        ensure!(
+            !self.use_base_arithmetic_gate || ArithmeticGate::num_ops(self) > 0,
+            "Base arithmetic needs at least 4 routed wires, but the config has {}",
+            self.num_routed_wires
+        );
+        ensure!(
+            self.num_constants >= 2,
+            "Arithmetic gates need 2 constants, but `num_constants` is {}",
+            self.num_constants
+        );
+        ensure!(
+            self.max_quotient_degree_factor >= 2,
+            "`max_quotient_degree_factor` must be at least 2 for arithmetic gates, but is {}",
+            self.max_quotient_degree_factor
+        );
+        let quotient_degree_bits = log2_ceil(self.max_quotient_degree_factor);
+        ensure!(
+            quotient_degree_bits <= self.fri_config.rate_bits,
+            "A `max_quotient_degree_factor` of {} needs `rate_bits` of at least {}, but it is {}",
+            self.max_quotient_degree_factor,
+            quotient_degree_bits,
+            self.fri_config.rate_bits
+        );
+        self.fri_config.validate()?;
+        // Under proven soundness, FRI's security also decreases with the instance size.
+        let fri_security_bits = self
+            .fri_config
+            .security_bits(F::Extension::order().bits() as usize, 0);
+        ensure!(
+            fri_security_bits >= self.security_bits,
+            "FRI params fall short of target security: {} bits, targeting {}; add query rounds \
+             or proof-of-work bits",
+            fri_security_bits,
+            self.security_bits
+        );
+        Ok(())
+    }
+
     /// A typical recursion config, without zero-knowledge, targeting ~100 bit security.
     pub fn standard_recursion_config() -> Self {
         Self {
@@ -85,7 +145,25 @@ impl CircuitConfig {
                 proof_of_work_bits: 16,
                 reduction_strategy: FriReductionStrategy::ConstantArityBits(4, 5),
                 num_query_rounds: 28,
+                security_assumption: SecurityAssumption::Conjectured,
+                coset_shift: None,
+            },
+        }
+    }
+
+    /// A config targeting ~160 bit conjectured security, for use with the quintic extension of
+    /// Goldilocks (e.g. `PoseidonGoldilocksQuinticConfig`). With a degree 2 extension, FRI's
+    /// soundness would be capped by the extension field's ~128 bits.
+    pub fn standard_quintic_config() -> Self {
+        let standard = Self::standard_recursion_config();
+        Self {
+            security_bits: 160,
+            num_challenges: 4,
+            fri_config: FriConfig {
+                num_query_rounds: 48,
+                ..standard.fri_config
             },
+            ..standard
         }
     }
 
@@ -150,10 +228,21 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
         )
     }
 
+    /// Proves using a witness dumped with `PartialWitness::to_bytes`, to replay a failing proof.
+    pub fn prove_from_dump(&self, dump: Vec<u8>) -> Result<ProofWithPublicInputs<F, C, D>> {
+        self.prove(PartialWitness::from_bytes(dump)?)
+    }
+
     pub fn verify(&self, proof_with_pis: ProofWithPublicInputs<F, C, D>) -> Result<()> {
         verify::<F, C, D>(proof_with_pis, &self.verifier_only, &self.common)
     }
 
+    /// Verifies several proofs of this circuit in parallel, sharing the setup which doesn't
+    /// depend on the proof.
+    pub fn verify_batch(&self, proofs_with_pis: &[ProofWithPublicInputs<F, C, D>]) -> Result<()> {
+        verify_batch::<F, C, D>(proofs_with_pis, &self.verifier_only, &self.common)
+    }
+
     pub fn verify_compressed(
         &self,
         compressed_proof_with_pis: CompressedProofWithPublicInputs<F, C, D>,
@@ -246,6 +335,11 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
             &mut TimingTree::default(),
         )
     }
+
+    /// Proves using a witness dumped with `PartialWitness::to_bytes`, to replay a failing proof.
+    pub fn prove_from_dump(&self, dump: Vec<u8>) -> Result<ProofWithPublicInputs<F, C, D>> {
+        self.prove(PartialWitness::from_bytes(dump)?)
+    }
 }
 
 /// Circuit data required by the prover.
@@ -280,6 +374,12 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
         verify::<F, C, D>(proof_with_pis, &self.verifier_only, &self.common)
     }
 
+    /// Verifies several proofs of this circuit in parallel, sharing the setup which doesn't
+    /// depend on the proof.
+    pub fn verify_batch(&self, proofs_with_pis: &[ProofWithPublicInputs<F, C, D>]) -> Result<()> {
+        verify_batch::<F, C, D>(proofs_with_pis, &self.verifier_only, &self.common)
+    }
+
     pub fn verify_compressed(
         &self,
         compressed_proof_with_pis: CompressedProofWithPublicInputs<F, C, D>,
@@ -319,6 +419,9 @@ pub struct ProverOnlyCircuitData<
     pub lookup_rows: Vec<LookupWire>,
     /// A vector of (looking_in, looking_out) pairs for for each lookup table index.
     pub lut_to_lookups: Vec<Lookup>,
+    /// The names given with `CircuitBuilder::name_target`, indexed by the representative of each
+    /// named target. Only used in error messages and debugging output.
+    pub target_names: BTreeMap<usize, String>,
 }
 
 impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
@@ -426,6 +529,26 @@ impl<F: RichField + Extendable<D>, const D: usize> CommonCircuitData<F, D> {
         self.fri_params.degree_bits
     }
 
+    /// A digest of the circuit configuration and FRI parameters. It is absorbed at the start of
+    /// the Fiat-Shamir transcript, so that a proof can't be checked against another configuration.
+    pub fn config_digest<H: Hasher<F>>(&self) -> H::Hash {
+        let config = &self.config;
+        let mut elements = vec![
+            F::from_canonical_usize(config.num_wires),
+            F::from_canonical_usize(config.num_routed_wires),
+            F::from_canonical_usize(config.num_constants),
+            F::from_bool(config.use_base_arithmetic_gate),
+            F::from_canonical_usize(config.security_bits),
+            F::from_canonical_usize(config.num_challenges),
+            F::from_bool(config.zero_knowledge),
+            F::from_canonical_usize(config.max_quotient_degree_factor),
+            F::from_canonical_usize(self.quotient_degree_factor),
+            F::from_canonical_usize(self.num_public_inputs),
+        ];
+        elements.extend(self.fri_params.to_field_elements::<F>());
+        H::hash_no_pad(&elements)
+    }
+
     pub fn degree(&self) -> usize {
         1 << self.degree_bits()
     }
@@ -446,10 +569,134 @@ impl<F: RichField + Extendable<D>, const D: usize> CommonCircuitData<F, D> {
             .expect("No gates?")
     }
 
+    /// The bits of FRI security achieved for this circuit, under the config's
+    /// `SecurityAssumption`.
+    pub fn achieved_security_bits(&self) -> usize {
+        self.fri_params
+            .security_bits(F::Extension::order().bits() as usize)
+    }
+
     pub fn quotient_degree(&self) -> usize {
         self.quotient_degree_factor * self.degree()
     }
 
+    /// The size in bytes of a proof of this circuit, as serialized by
+    /// [`ProofWithPublicInputs::to_bytes`].
+    pub fn proof_size<C: GenericConfig<D, F = F>>(&self) -> usize {
+        proof_size::<D>(
+            &self.config,
+            &self.fri_params,
+            self.num_constants,
+            self.num_partial_products,
+            self.num_lookup_polys,
+            self.quotient_degree_factor,
+            self.num_public_inputs,
+            C::Hasher::HASH_SIZE,
+        )
+    }
+
+    /// Estimates the cost of verifying a proof of this circuit on chain; see
+    /// [`OnChainVerifierCost`].
+    pub fn onchain_verifier_cost<C: GenericConfig<D, F = F>>(&self) -> OnChainVerifierCost {
+        let layout = self.fri_instance_layout();
+        // The proof holds the caps of all oracles but the preprocessed one, and its public inputs
+        // are prefixed with their count.
+        self.fri_params.onchain_verifier_cost::<F, C::Hasher>(
+            &layout.oracles,
+            layout.oracles.len() - 1,
+            layout.zeta_polys.len() + layout.zeta_next_polys.len(),
+            1 + self.num_public_inputs,
+            D,
+        )
+    }
+
+    /// Checks that `other` describes circuits of the same shape, so that a recursive verifier
+    /// built for one can verify proofs of the other. Unlike comparing with `==`, this explains
+    /// which parameters differ: the degree, the config, the FRI parameters, the gate set, the
+    /// public inputs or any of the derived polynomial counts.
+    pub fn check_compatible(&self, other: &Self) -> Result<()> {
+        let mut diffs = Vec::new();
+        macro_rules! compare {
+            ($($field:ident).+) => {
+                if self.$($field).+ != other.$($field).+ {
+                    diffs.push(format!(
+                        "{}: {:?} vs {:?}",
+                        [$(stringify!($field)),+].join("."),
+                        self.$($field).+,
+                        other.$($field).+
+                    ));
+                }
+            };
+        }
+
+        if self.degree_bits() != other.degree_bits() {
+            diffs.push(format!(
+                "degree_bits: {} vs {}",
+                self.degree_bits(),
+                other.degree_bits()
+            ));
+        }
+        compare!(num_public_inputs);
+        compare!(config.num_wires);
+        compare!(config.num_routed_wires);
+        compare!(config.num_constants);
+        compare!(config.use_base_arithmetic_gate);
+        compare!(config.security_bits);
+        compare!(config.num_challenges);
+        compare!(config.zero_knowledge);
+        compare!(config.max_quotient_degree_factor);
+        compare!(config.fri_config);
+        // The FRI parameters keep their own copy of the FRI config, which is what the prover and
+        // verifier actually use.
+        compare!(fri_params.config);
+        compare!(fri_params.hiding);
+        compare!(fri_params.reduction_arity_bits);
+        if self.gates != other.gates {
+            let missing = |gates: &[GateRef<F, D>], from: &[GateRef<F, D>]| {
+                gates
+                    .iter()
+                    .filter(|g| !from.contains(g))
+                    .map(|g| g.0.id())
+                    .collect::<Vec<_>>()
+            };
+            let only_ours = missing(&self.gates, &other.gates);
+            let only_theirs = missing(&other.gates, &self.gates);
+            if only_ours.is_empty() && only_theirs.is_empty() {
+                diffs.push(String::from("gates: same gates in a different order"));
+            } else {
+                diffs.push(format!(
+                    "gates: only ours {:?}, only theirs {:?}",
+                    only_ours, only_theirs
+                ));
+            }
+        }
+        compare!(selectors_info);
+        compare!(quotient_degree_factor);
+        compare!(num_gate_constraints);
+        compare!(num_constants);
+        compare!(num_partial_products);
+        compare!(num_lookup_polys);
+        compare!(num_lookup_selectors);
+        // These are long, and follow from the parameters above in all but contrived cases.
+        if self.k_is != other.k_is {
+            diffs.push(String::from("k_is differ"));
+        }
+        if self.luts != other.luts {
+            diffs.push(format!(
+                "luts: {} tables vs {} tables, or different contents",
+                self.luts.len(),
+                other.luts.len()
+            ));
+        }
+
+        ensure!(
+            diffs.is_empty(),
+            "Incompatible common circuit data (ours vs theirs):\n  {}",
+            diffs.join("\n  ")
+        );
+        Ok(())
+    }
+
     /// Range of the constants polynomials in the `constants_sigmas_commitment`.
     pub fn constants_range(&self) -> Range<usize> {
         0..self.num_constants
@@ -482,24 +729,16 @@ impl<F: RichField + Extendable<D>, const D: usize> CommonCircuitData<F, D> {
     }
 
     pub(crate) fn get_fri_instance(&self, zeta: F::Extension) -> FriInstanceInfo<F, D> {
-        // All polynomials are opened at zeta.
-        let zeta_batch = FriBatchInfo {
-            point: zeta,
-            polynomials: self.fri_all_polys(),
-        };
-
-        // The Z polynomials are also opened at g * zeta.
-        let g = F::Extension::primitive_root_of_unity(self.degree_bits());
-        let zeta_next = g * zeta;
-        let zeta_next_batch = FriBatchInfo {
-            point: zeta_next,
-            polynomials: self.fri_next_batch_polys(),
-        };
+        self.fri_instance_layout().instance(zeta)
+    }
 
-        let openings = vec![zeta_batch, zeta_next_batch];
-        FriInstanceInfo {
+    /// The parts of `get_fri_instance` which don't depend on `zeta`.
+    pub(crate) fn fri_instance_layout(&self) -> FriInstanceLayout<F, D> {
+        FriInstanceLayout {
             oracles: self.fri_oracles(),
-            batches: openings,
+            zeta_polys: self.fri_all_polys(),
+            zeta_next_polys: self.fri_next_batch_polys(),
+            g: F::Extension::primitive_root_of_unity(self.degree_bits()),
         }
     }
 
@@ -534,18 +773,26 @@ impl<F: RichField + Extendable<D>, const D: usize> CommonCircuitData<F, D> {
             FriOracleInfo {
                 num_polys: self.num_preprocessed_polys(),
                 blinding: PlonkOracle::CONSTANTS_SIGMAS.blinding,
+                extra_rate_bits: 0,
+                leaf_group_bits: 0,
             },
             FriOracleInfo {
                 num_polys: self.config.num_wires,
                 blinding: PlonkOracle::WIRES.blinding,
+                extra_rate_bits: 0,
+                leaf_group_bits: 0,
             },
             FriOracleInfo {
                 num_polys: self.num_zs_partial_products_polys() + self.num_all_lookup_polys(),
                 blinding: PlonkOracle::ZS_PARTIAL_PRODUCTS.blinding,
+                extra_rate_bits: 0,
+                leaf_group_bits: 0,
             },
             FriOracleInfo {
                 num_polys: self.num_quotient_polys(),
                 blinding: PlonkOracle::QUOTIENT.blinding,
+                extra_rate_bits: 0,
+                leaf_group_bits: 0,
             },
         ]
     }
@@ -618,6 +865,37 @@ impl<F: RichField + Extendable<D>, const D: usize> CommonCircuitData<F, D> {
     }
 }
 
+/// The oracles and opened polynomials of a circuit's FRI instance, from which the instance for any
+/// opening point can be built without recomputing them.
+#[derive(Clone)]
+pub(crate) struct FriInstanceLayout<F: RichField + Extendable<D>, const D: usize> {
+    oracles: Vec<FriOracleInfo>,
+    zeta_polys: Vec<FriPolynomialInfo>,
+    zeta_next_polys: Vec<FriPolynomialInfo>,
+    g: F::Extension,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> FriInstanceLayout<F, D> {
+    pub(crate) fn instance(&self, zeta: F::Extension) -> FriInstanceInfo<F, D> {
+        // All polynomials are opened at zeta.
+        let zeta_batch = FriBatchInfo {
+            point: zeta,
+            polynomials: self.zeta_polys.clone(),
+        };
+
+        // The Z polynomials are also opened at g * zeta.
+        let zeta_next_batch = FriBatchInfo {
+            point: self.g * zeta,
+            polynomials: self.zeta_next_polys.clone(),
+        };
+
+        FriInstanceInfo {
+            oracles: self.oracles.clone(),
+            batches: vec![zeta_batch, zeta_next_batch],
+        }
+    }
+}
+
 /// The `Target` version of `VerifierCircuitData`, for use inside recursive circuits. Note that this
 /// is intentionally missing certain fields, such as `CircuitConfig`, because we support only a
 /// limited form of dynamic inner circuits. We can't practically make things like the wire count
@@ -630,3 +908,169 @@ pub struct VerifierCircuitTarget {
     /// seed Fiat-Shamir.
     pub circuit_digest: HashOutTarget,
 }
+
+/// The size in bytes of a serialized [`ProofWithPublicInputs`] of the given shape, with hashes of
+/// `hash_size` bytes.
+pub(crate) fn proof_size<const D: usize>(
+    config: &CircuitConfig,
+    fri_params: &FriParams,
+    num_constants: usize,
+    num_partial_products: usize,
+    num_lookup_polys: usize,
+    quotient_degree_factor: usize,
+    num_public_inputs: usize,
+    hash_size: usize,
+) -> usize {
+    const FIELD_SIZE: usize = 8;
+    let ext_size = D * FIELD_SIZE;
+    let cap_height = config.fri_config.cap_height;
+    let cap_size = (1 << cap_height) * hash_size;
+    // Merkle proofs are prefixed with their length, as a single byte.
+    let merkle_proof_size =
+        |codeword_len_bits: usize| 1 + (codeword_len_bits - cap_height) * hash_size;
+
+    let num_challenges = config.num_challenges;
+    let num_zs_partial_products = num_challenges * (1 + num_partial_products);
+    let num_lookup_zs = num_challenges * num_lookup_polys;
+    let num_quotient_polys = num_challenges * quotient_degree_factor;
+    let salt = salt_size(fri_params.hiding);
+
+    // Openings of each polynomial at zeta, and of the Z polynomials at g * zeta too.
+    let num_openings = num_constants
+        + config.num_routed_wires
+        + config.num_wires
+        + num_zs_partial_products
+        + num_challenges
+        + 2 * num_lookup_zs
+        + num_quotient_polys;
+
+    let leaf_sizes = [
+        num_constants + config.num_routed_wires,
+        config.num_wires + salt,
+        num_zs_partial_products + num_lookup_zs + salt,
+        num_quotient_polys + salt,
+    ];
+    let mut query_round_size = leaf_sizes
+        .iter()
+        .map(|&leaf_size| leaf_size * FIELD_SIZE + merkle_proof_size(fri_params.lde_bits()))
+        .sum::<usize>();
+    let mut codeword_len_bits = fri_params.lde_bits();
+    for &arity_bits in &fri_params.reduction_arity_bits {
+        codeword_len_bits -= arity_bits;
+        query_round_size += (1 << arity_bits) * ext_size + merkle_proof_size(codeword_len_bits);
+    }
+
+    let fri_proof_size = fri_params.reduction_arity_bits.len() * cap_size
+        + fri_params.config.num_query_rounds * query_round_size
+        + fri_params.final_poly_len() * ext_size
+        + FIELD_SIZE;
+    // The public inputs are prefixed with their count.
+    let public_inputs_size = FIELD_SIZE + num_public_inputs * FIELD_SIZE;
+
+    3 * cap_size + num_openings * ext_size + fri_proof_size + public_inputs_size
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::types::PrimeField64;
+    use crate::gates::noop::NoopGate;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+    use crate::util::serialization::{DefaultGateSerializer, CIRCUIT_DATA_FORMAT_VERSION};
+
+    #[test]
+    fn test_check_compatible() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let circuit = |config: CircuitConfig, num_gates: usize, cube: bool| {
+            let mut builder = CircuitBuilder::<F, D>::new(config);
+            let x = builder.add_virtual_public_input();
+            if cube {
+                let x_cubed = builder.cube(x);
+                builder.register_public_input(x_cubed);
+            }
+            for _ in 0..num_gates {
+                builder.add_gate(NoopGate, vec![]);
+            }
+            builder.build::<C>().common
+        };
+        let config = CircuitConfig::standard_recursion_config();
+        let common = circuit(config.clone(), 10, true);
+        assert!(common
+            .check_compatible(&circuit(config.clone(), 10, true))
+            .is_ok());
+
+        let err = common
+            .check_compatible(&circuit(config.clone(), 1000, true))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("degree_bits"));
+        assert!(!err.contains("gates"));
+
+        let err = common
+            .check_compatible(&circuit(config.clone(), 10, false))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("num_public_inputs: 2 vs 1"));
+
+        let mut other_config = config;
+        other_config.fri_config.num_query_rounds += 1;
+        let err = common
+            .check_compatible(&circuit(other_config, 10, true))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("config.fri_config"));
+        assert!(!err.contains("degree_bits"));
+
+        // A FRI config differing only in the FRI parameters, e.g. after deserializing tampered
+        // data, must be caught too, since cyclic recursion relies on this check.
+        #[allow(clippy::redundant_clone)] // `common` is used below; a false positive.
+        let mut other = common.clone();
+        other.fri_params.config.proof_of_work_bits += 1;
+        let err = common.check_compatible(&other).unwrap_err().to_string();
+        assert!(err.contains("fri_params.config"));
+        assert!(!err.contains("config.fri_config"));
+    }
+
+    #[test]
+    fn test_common_data_serialization_checks() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_public_input();
+        let x_cubed = builder.cube(x);
+        builder.register_public_input(x_cubed);
+        let common = builder.build::<C>().common;
+        let gate_serializer = DefaultGateSerializer;
+
+        let bytes = common.to_bytes(&gate_serializer).unwrap();
+        assert_eq!(bytes[0], CIRCUIT_DATA_FORMAT_VERSION);
+        assert_eq!(
+            CommonCircuitData::<F, D>::from_bytes(bytes.clone(), &gate_serializer).unwrap(),
+            common
+        );
+
+        let mut other_version = bytes;
+        other_version[0] = CIRCUIT_DATA_FORMAT_VERSION.wrapping_add(1);
+        assert!(CommonCircuitData::<F, D>::from_bytes(other_version, &gate_serializer).is_err());
+
+        // Zero, a non-canonical value, and a root of unity of the LDE subgroup are all rejected.
+        let lde_bits = common.degree_bits() + common.config.fri_config.rate_bits;
+        let root = F::primitive_root_of_unity(lde_bits).to_canonical_u64();
+        for shift in [0, u64::MAX, root] {
+            let mut bad = common.clone();
+            bad.fri_params.config.coset_shift = Some(shift);
+            let bytes = bad.to_bytes(&gate_serializer).unwrap();
+            assert!(CommonCircuitData::<F, D>::from_bytes(bytes, &gate_serializer).is_err());
+
+            let mut bad = common.clone();
+            bad.config.fri_config.coset_shift = Some(shift);
+            let bytes = bad.to_bytes(&gate_serializer).unwrap();
+            assert!(CommonCircuitData::<F, D>::from_bytes(bytes, &gate_serializer).is_err());
+        }
+    }
+}
@@ -6,12 +6,16 @@ use serde::de::DeserializeOwned;
 use serde::Serialize;
 
 use crate::field::extension::quadratic::QuadraticExtension;
+use crate::field::extension::quintic::QuinticExtension;
 use crate::field::extension::{Extendable, FieldExtension};
 use crate::field::goldilocks_field::GoldilocksField;
 use crate::hash::hash_types::{HashOut, RichField};
 use crate::hash::hashing::PlonkyPermutation;
 use crate::hash::keccak::KeccakHash;
+use crate::hash::keccak_transcript::SolidityKeccakHash;
 use crate::hash::poseidon::PoseidonHash;
+#[cfg(feature = "poseidon_bn254")]
+use crate::hash::poseidon_bn254::PoseidonBn254Hash;
 use crate::iop::target::{BoolTarget, Target};
 use crate::plonk::circuit_builder::CircuitBuilder;
 
@@ -29,6 +33,10 @@ pub trait Hasher<F: RichField>: Sized + Copy + Debug + Eq + PartialEq {
     /// Size of `Hash` in bytes.
     const HASH_SIZE: usize;
 
+    /// A stable name for this hash function, used to identify it outside of Rust, e.g. in exported
+    /// verifier data. Unlike `core::any::type_name`, it doesn't change with the crate layout.
+    const ID: &'static str;
+
     /// Hash Output
     type Hash: GenericHashOut<F>;
 
@@ -107,6 +115,18 @@ impl GenericConfig<2> for PoseidonGoldilocksConfig {
     type InnerHasher = PoseidonHash;
 }
 
+/// Configuration using Poseidon over the Goldilocks field, with challenges drawn from its quintic
+/// extension. The larger extension field allows FRI to target ~160 bits of conjectured security;
+/// see `CircuitConfig::standard_quintic_config`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize)]
+pub struct PoseidonGoldilocksQuinticConfig;
+impl GenericConfig<5> for PoseidonGoldilocksQuinticConfig {
+    type F = GoldilocksField;
+    type FE = QuinticExtension<Self::F>;
+    type Hasher = PoseidonHash;
+    type InnerHasher = PoseidonHash;
+}
+
 /// Configuration using truncated Keccak over the Goldilocks field.
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct KeccakGoldilocksConfig;
@@ -116,3 +136,27 @@ impl GenericConfig<2> for KeccakGoldilocksConfig {
     type Hasher = KeccakHash<25>;
     type InnerHasher = PoseidonHash;
 }
+
+/// Configuration using truncated Keccak over the Goldilocks field, with a transcript an EVM
+/// verifier can recompute; see `hash::keccak_transcript`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct SolidityKeccakGoldilocksConfig;
+impl GenericConfig<2> for SolidityKeccakGoldilocksConfig {
+    type F = GoldilocksField;
+    type FE = QuadraticExtension<Self::F>;
+    type Hasher = SolidityKeccakHash<25>;
+    type InnerHasher = PoseidonHash;
+}
+
+/// Configuration using Poseidon over BN254 for Merkle trees and the transcript, for proofs verified
+/// by a gnark wrapper circuit; see `hash::poseidon_bn254`.
+#[cfg(feature = "poseidon_bn254")]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct PoseidonBn254GoldilocksConfig;
+#[cfg(feature = "poseidon_bn254")]
+impl GenericConfig<2> for PoseidonBn254GoldilocksConfig {
+    type F = GoldilocksField;
+    type FE = QuadraticExtension<Self::F>;
+    type Hasher = PoseidonBn254Hash;
+    type InnerHasher = PoseidonHash;
+}
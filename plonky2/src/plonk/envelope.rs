@@ -0,0 +1,292 @@
+//! A proof bundled with the metadata needed to route and audit it: the digests of the circuit and
+//! of its config, the version of plonky2 which produced it, and optionally a Schnorr signature of
+//! all of these by the prover. Signatures use Schnorr over ecGFp5, so only envelopes of proofs over
+//! Goldilocks can be signed.
+//!
+//! An envelope has a single canonical byte encoding, written by [`ProofEnvelope::to_bytes`], which
+//! [`ProofEnvelope::from_bytes`] is the only decoding of. Signatures cover this encoding, without
+//! the signature itself.
+
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use anyhow::{anyhow, ensure, Result};
+use num::BigUint;
+
+use crate::field::extension::quintic::QuinticExtension;
+use crate::field::extension::Extendable;
+use crate::field::goldilocks_field::GoldilocksField;
+use crate::field::types::Field;
+use crate::gadgets::ecgfp5::AffinePoint;
+use crate::gadgets::schnorr::{SchnorrPublicKey, SchnorrSecretKey, SchnorrSignature};
+use crate::hash::hash_types::RichField;
+use crate::hash::poseidon::PoseidonHash;
+use crate::plonk::circuit_data::{CommonCircuitData, VerifierCircuitData};
+use crate::plonk::config::{GenericConfig, Hasher};
+use crate::plonk::proof::ProofWithPublicInputs;
+use crate::util::serialization::{Buffer, IoError, IoResult, Read, Remaining, Write};
+
+/// Version of the envelope byte encoding.
+pub const ENVELOPE_VERSION: u8 = 1;
+
+/// The number of 64-bit limbs of a signature's scalar.
+const SCALAR_LIMBS: usize = 5;
+
+/// A signature of an envelope, along with the key of the prover who made it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProverSignature {
+    pub public_key: SchnorrPublicKey,
+    pub signature: SchnorrSignature,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProofEnvelope<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> {
+    /// The proof, which holds the public inputs.
+    pub proof: ProofWithPublicInputs<F, C, D>,
+    pub circuit_digest: <C::Hasher as Hasher<F>>::Hash,
+    /// The digest of the circuit's config, as given by `CommonCircuitData::config_digest`.
+    pub config_digest: <C::Hasher as Hasher<F>>::Hash,
+    /// The version of plonky2 which produced the proof.
+    pub crate_version: String,
+    pub signature: Option<ProverSignature>,
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    ProofEnvelope<F, C, D>
+{
+    /// An unsigned envelope of `proof`, a proof of the circuit of `data`.
+    pub fn new(proof: ProofWithPublicInputs<F, C, D>, data: &VerifierCircuitData<F, C, D>) -> Self {
+        Self {
+            proof,
+            circuit_digest: data.verifier_only.circuit_digest,
+            config_digest: data.common.config_digest::<C::Hasher>(),
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            signature: None,
+        }
+    }
+
+    pub fn public_inputs(&self) -> &[F] {
+        &self.proof.public_inputs
+    }
+
+    /// Checks that the envelope is for the circuit of `data`, and its proof. The signature, which
+    /// only Goldilocks envelopes can have, is checked by `verify_signature`.
+    pub fn verify(&self, data: &VerifierCircuitData<F, C, D>) -> Result<()> {
+        ensure!(
+            self.circuit_digest == data.verifier_only.circuit_digest,
+            "The envelope is for another circuit"
+        );
+        ensure!(
+            self.config_digest == data.common.config_digest::<C::Hasher>(),
+            "The envelope is for another config"
+        );
+        data.verify(self.proof.clone())
+    }
+
+    /// The encoding of everything but the signature, which signatures cover.
+    fn unsigned_bytes(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        self.write_unsigned(&mut buffer)
+            .expect("Writing to a byte-vector cannot fail.");
+        buffer
+    }
+
+    fn write_unsigned(&self, buffer: &mut Vec<u8>) -> IoResult<()> {
+        buffer.write_u8(ENVELOPE_VERSION)?;
+        buffer.write_usize(self.crate_version.len())?;
+        buffer.write_all(self.crate_version.as_bytes())?;
+        buffer.write_hash::<F, C::Hasher>(self.circuit_digest)?;
+        buffer.write_hash::<F, C::Hasher>(self.config_digest)?;
+        buffer.write_proof_with_public_inputs(&self.proof)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buffer = self.unsigned_bytes();
+        match &self.signature {
+            None => buffer.write_bool(false),
+            Some(signature) => buffer
+                .write_bool(true)
+                .and_then(|_| write_prover_signature(&mut buffer, signature)),
+        }
+        .expect("Writing to a byte-vector cannot fail.");
+        buffer
+    }
+
+    /// Decodes an envelope of a proof of a circuit with `common_data`. Only the canonical encoding
+    /// is accepted.
+    pub fn from_bytes(bytes: &[u8], common_data: &CommonCircuitData<F, D>) -> Result<Self> {
+        let mut buffer = Buffer::new(bytes);
+        let envelope =
+            Self::read(&mut buffer, common_data).map_err(|_| anyhow!("Invalid envelope"))?;
+        ensure!(buffer.is_empty(), "Trailing bytes after envelope");
+        Ok(envelope)
+    }
+
+    fn read(buffer: &mut Buffer<'_>, common_data: &CommonCircuitData<F, D>) -> IoResult<Self> {
+        if buffer.read_u8()? != ENVELOPE_VERSION {
+            return Err(IoError);
+        }
+        let version_len = buffer.read_usize()?;
+        if version_len > buffer.remaining() {
+            return Err(IoError);
+        }
+        let mut crate_version = vec![0; version_len];
+        buffer.read_exact(&mut crate_version)?;
+        let crate_version = String::from_utf8(crate_version).map_err(|_| IoError)?;
+        let circuit_digest = buffer.read_hash::<F, C::Hasher>()?;
+        let config_digest = buffer.read_hash::<F, C::Hasher>()?;
+        let proof = buffer.read_proof_with_public_inputs(common_data)?;
+        let signature = if buffer.read_bool()? {
+            Some(read_prover_signature(buffer)?)
+        } else {
+            None
+        };
+        Ok(Self {
+            proof,
+            circuit_digest,
+            config_digest,
+            crate_version,
+            signature,
+        })
+    }
+}
+
+impl<C: GenericConfig<2, F = GoldilocksField>> ProofEnvelope<GoldilocksField, C, 2> {
+    /// The message which signatures sign: a Poseidon hash of the unsigned encoding, packed four
+    /// bytes per element.
+    fn signed_message(&self) -> Vec<GoldilocksField> {
+        let elements = self
+            .unsigned_bytes()
+            .chunks(4)
+            .map(|chunk| {
+                let mut bytes = [0; 4];
+                bytes[..chunk.len()].copy_from_slice(chunk);
+                GoldilocksField::from_canonical_u32(u32::from_le_bytes(bytes))
+            })
+            .collect::<Vec<_>>();
+        PoseidonHash::hash_no_pad(&elements).elements.to_vec()
+    }
+
+    /// Signs the envelope with `secret_key`, replacing any previous signature.
+    pub fn sign(&mut self, secret_key: &SchnorrSecretKey) {
+        let signature = secret_key.sign(&self.signed_message());
+        self.signature = Some(ProverSignature {
+            public_key: secret_key.public_key(),
+            signature,
+        });
+    }
+
+    /// Like `verify`, but also checks the signature, which must be present.
+    pub fn verify_signed(&self, data: &VerifierCircuitData<GoldilocksField, C, 2>) -> Result<()> {
+        self.verify_signature()?;
+        self.verify(data)
+    }
+
+    /// Checks the envelope's signature, which must be present.
+    pub fn verify_signature(&self) -> Result<()> {
+        let ProverSignature {
+            public_key,
+            signature,
+        } = self
+            .signature
+            .as_ref()
+            .ok_or_else(|| anyhow!("The envelope isn't signed"))?;
+        public_key.verify(&self.signed_message(), signature)
+    }
+}
+
+fn write_point(buffer: &mut Vec<u8>, point: &AffinePoint) -> IoResult<()> {
+    buffer.write_field_vec(&point.x.0)?;
+    buffer.write_field_vec(&point.y.0)
+}
+
+fn write_prover_signature(buffer: &mut Vec<u8>, signature: &ProverSignature) -> IoResult<()> {
+    write_point(buffer, &signature.public_key.0)?;
+    write_point(buffer, &signature.signature.r)?;
+    let mut limbs = signature.signature.s.to_u64_digits();
+    limbs.resize(SCALAR_LIMBS, 0);
+    for limb in limbs {
+        buffer.write_u64(limb)?;
+    }
+    Ok(())
+}
+
+fn read_point(buffer: &mut Buffer<'_>) -> IoResult<AffinePoint> {
+    let x = buffer.read_field_vec(5)?;
+    let y = buffer.read_field_vec(5)?;
+    Ok(AffinePoint {
+        x: QuinticExtension(x.try_into().unwrap()),
+        y: QuinticExtension(y.try_into().unwrap()),
+    })
+}
+
+fn read_prover_signature(buffer: &mut Buffer<'_>) -> IoResult<ProverSignature> {
+    let public_key = SchnorrPublicKey(read_point(buffer)?);
+    let r = read_point(buffer)?;
+    let limbs = (0..SCALAR_LIMBS)
+        .map(|_| buffer.read_u64())
+        .collect::<IoResult<Vec<_>>>()?;
+    let s = limbs
+        .iter()
+        .rev()
+        .fold(BigUint::default(), |acc, &limb| (acc << 64) + limb);
+    Ok(ProverSignature {
+        public_key,
+        signature: SchnorrSignature { r, s },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::OsRng;
+
+    use super::*;
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    #[test]
+    fn test_proof_envelope() -> Result<()> {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let y = builder.cube(x);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::TWO);
+        let proof = data.prove(pw)?;
+        let verifier_data = data.verifier_data();
+
+        let mut envelope = ProofEnvelope::new(proof, &verifier_data);
+        assert_eq!(envelope.public_inputs(), [F::from_canonical_u32(8)]);
+        envelope.verify(&verifier_data)?;
+        assert!(envelope.verify_signature().is_err());
+
+        envelope.sign(&SchnorrSecretKey::sample(&mut OsRng));
+        envelope.verify_signed(&verifier_data)?;
+        let bytes = envelope.to_bytes();
+        let decoded = ProofEnvelope::<F, C, D>::from_bytes(&bytes, &verifier_data.common)?;
+        assert_eq!(decoded, envelope);
+        decoded.verify_signed(&verifier_data)?;
+
+        // The signature covers the metadata.
+        let mut tampered = envelope.clone();
+        tampered.crate_version = "0.0.0".to_string();
+        assert!(tampered.verify_signed(&verifier_data).is_err());
+        let mut tampered = envelope;
+        tampered.config_digest.elements[0] += F::ONE;
+        assert!(tampered.verify(&verifier_data).is_err());
+
+        // Only the canonical encoding decodes.
+        let mut trailing = bytes;
+        trailing.push(0);
+        assert!(ProofEnvelope::<F, C, D>::from_bytes(&trailing, &verifier_data.common).is_err());
+        Ok(())
+    }
+}
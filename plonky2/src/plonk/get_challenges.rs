@@ -42,6 +42,7 @@ fn get_challenges<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, cons
     let has_lookup = common_data.num_lookup_polys != 0;
 
     // Observe the instance.
+    challenger.observe_domain_separator::<C::Hasher>(common_data.config_digest::<C::Hasher>());
     challenger.observe_hash::<C::Hasher>(*circuit_digest);
     challenger.observe_hash::<C::InnerHasher>(public_inputs_hash);
 
@@ -206,7 +207,7 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
         // Simulate the proof verification and collect the inferred elements.
         // The content of the loop is basically the same as the `fri_verifier_query_round` function.
         for &(mut x_index) in fri_query_indices {
-            let mut subgroup_x = F::MULTIPLICATIVE_GROUP_GENERATOR
+            let mut subgroup_x = common_data.config.fri_config.coset_shift::<F>()
                 * F::primitive_root_of_unity(log_n).exp_u64(reverse_bits(x_index, log_n) as u64);
             let mut old_eval = fri_combine_initial::<F, C, D>(
                 &common_data.get_fri_instance(*plonk_zeta),
@@ -215,6 +216,7 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
                     .opening_proof
                     .query_round_proofs
                     .initial_trees_proofs[&x_index],
+                x_index,
                 *fri_alpha,
                 subgroup_x,
                 &precomputed_reduced_evals,
@@ -277,6 +279,7 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         let has_lookup = inner_common_data.num_lookup_polys != 0;
 
         // Observe the instance.
+        challenger.observe_domain_separator(self, inner_common_data.config_digest::<C::Hasher>());
         challenger.observe_hash(&inner_circuit_digest);
         challenger.observe_hash(&public_inputs_hash);
 
@@ -0,0 +1,525 @@
+//! Export of proofs and verifier data in a format consumable by a gnark (BN254) wrapper circuit.
+//!
+//! A wrapper circuit verifies a plonky2 proof inside a Groth16 or PLONK circuit over BN254, so that
+//! the final proof can be checked cheaply on Ethereum. This module fixes the layout such a wrapper
+//! reads:
+//!
+//! - Every base field element is a decimal string holding its canonical `u64` value, so it can be
+//!   loaded directly into an emulated Goldilocks element.
+//! - Every extension field element is an array of `D` base field elements, lowest degree first.
+//! - Every hash is the array of base field elements returned by `GenericHashOut::to_vec`, and a
+//!   Merkle cap is an array of such hashes.
+//! - Openings and Merkle proofs keep the exact order in which the verifier consumes them.
+//!
+//! The wrapper must recompute the Fiat-Shamir challenges with the same hasher the proof was
+//! generated with, identified by its `Hasher::ID` in [`GnarkVerifierData::hasher`], and truncated
+//! to `hasher_hash_size` bytes for byte-oriented hashers. [`GnarkVerifierData::transcript`]
+//! records the order of observations and squeezes.
+//!
+//! This is the transcript of the proof being wrapped. To keep the wrapper's hashing native, the
+//! outermost proof should use `PoseidonBn254GoldilocksConfig`, behind the `poseidon_bn254` feature.
+//! Its hasher, with ID `poseidon-bn254`, is circomlib's Poseidon over BN254, and
+//! `hash::poseidon_bn254` specifies how its Merkle trees and challenger pack Goldilocks elements
+//! into BN254 elements. Its digests are BN254 elements, exported as their 32 little-endian bytes
+//! split into 7-byte chunks.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::field::extension::{Extendable, FieldExtension};
+use crate::field::types::PrimeField64;
+use crate::fri::proof::FriProof;
+use crate::hash::hash_types::RichField;
+use crate::hash::merkle_tree::MerkleCap;
+use crate::iop::challenger::TRANSCRIPT_VERSION;
+use crate::plonk::circuit_builder::NUM_COINS_LOOKUP;
+use crate::plonk::circuit_data::{CommonCircuitData, VerifierCircuitData, VerifierOnlyCircuitData};
+use crate::plonk::config::{GenericConfig, GenericHashOut, Hasher};
+use crate::plonk::proof::{OpeningSet, ProofWithPublicInputs};
+
+/// Version of the export format. Bumped whenever the layout of any exported structure changes.
+pub const GNARK_EXPORT_VERSION: u32 = 3;
+
+fn field_to_string<F: PrimeField64>(x: F) -> String {
+    x.to_canonical_u64().to_string()
+}
+
+fn fields_to_strings<F: PrimeField64>(xs: &[F]) -> Vec<String> {
+    xs.iter().copied().map(field_to_string).collect()
+}
+
+fn ext_to_strings<F: RichField + Extendable<D>, const D: usize>(x: F::Extension) -> Vec<String> {
+    fields_to_strings(&x.to_basefield_array())
+}
+
+fn exts_to_strings<F: RichField + Extendable<D>, const D: usize>(
+    xs: &[F::Extension],
+) -> Vec<Vec<String>> {
+    xs.iter().map(|&x| ext_to_strings::<F, D>(x)).collect()
+}
+
+fn hash_to_strings<F: RichField, H: GenericHashOut<F>>(h: &H) -> Vec<String> {
+    fields_to_strings(&h.to_vec())
+}
+
+fn cap_to_strings<F: RichField, H: Hasher<F>>(cap: &MerkleCap<F, H>) -> Vec<Vec<String>> {
+    cap.0.iter().map(hash_to_strings).collect()
+}
+
+/// Everything a wrapper circuit needs to know about the circuit whose proofs it verifies.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct GnarkVerifierData {
+    pub version: u32,
+    /// The order of the base field, as a decimal string.
+    pub field_order: String,
+    pub extension_degree: usize,
+    /// `Hasher::ID` of the hasher used for Merkle trees and the Fiat-Shamir transcript.
+    pub hasher: String,
+    /// `Hasher::HASH_SIZE` of `hasher`, in bytes.
+    pub hasher_hash_size: usize,
+    /// `Hasher::ID` of the hasher used to hash public inputs.
+    pub inner_hasher: String,
+
+    pub constants_sigmas_cap: Vec<Vec<String>>,
+    pub circuit_digest: Vec<String>,
+    /// Digest of the circuit configuration, observed at the start of the transcript.
+    pub config_digest: Vec<String>,
+
+    pub degree_bits: usize,
+    pub num_wires: usize,
+    pub num_routed_wires: usize,
+    pub num_constants: usize,
+    pub num_public_inputs: usize,
+    pub num_challenges: usize,
+    pub quotient_degree_factor: usize,
+    pub num_partial_products: usize,
+    pub num_lookup_polys: usize,
+    pub k_is: Vec<String>,
+    /// Gate ids, in the order of their selector indices.
+    pub gates: Vec<String>,
+    pub selector_indices: Vec<usize>,
+    /// Gate index ranges `[start, end)` sharing a selector polynomial.
+    pub selector_groups: Vec<(usize, usize)>,
+
+    pub fri_rate_bits: usize,
+    pub fri_cap_height: usize,
+    pub fri_proof_of_work_bits: u32,
+    pub fri_num_query_rounds: usize,
+    pub fri_reduction_arity_bits: Vec<usize>,
+    pub fri_hiding: bool,
+    /// The shift of the LDE coset, by which the verifier multiplies the query points, as a decimal
+    /// string. This is `Field::coset_shift` unless `FriConfig::coset_shift` overrides it.
+    pub fri_coset_shift: String,
+
+    /// The Fiat-Shamir transcript, one step per entry, in the order the verifier performs them.
+    pub transcript: Vec<String>,
+}
+
+impl GnarkVerifierData {
+    pub fn new<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+        verifier_only: &VerifierOnlyCircuitData<C, D>,
+        common: &CommonCircuitData<F, D>,
+    ) -> Self {
+        Self {
+            version: GNARK_EXPORT_VERSION,
+            field_order: F::ORDER.to_string(),
+            extension_degree: D,
+            hasher: C::Hasher::ID.to_string(),
+            hasher_hash_size: C::Hasher::HASH_SIZE,
+            inner_hasher: C::InnerHasher::ID.to_string(),
+            constants_sigmas_cap: cap_to_strings(&verifier_only.constants_sigmas_cap),
+            circuit_digest: hash_to_strings(&verifier_only.circuit_digest),
+            config_digest: hash_to_strings(&common.config_digest::<C::Hasher>()),
+            degree_bits: common.degree_bits(),
+            num_wires: common.config.num_wires,
+            num_routed_wires: common.config.num_routed_wires,
+            num_constants: common.num_constants,
+            num_public_inputs: common.num_public_inputs,
+            num_challenges: common.config.num_challenges,
+            quotient_degree_factor: common.quotient_degree_factor,
+            num_partial_products: common.num_partial_products,
+            num_lookup_polys: common.num_lookup_polys,
+            k_is: fields_to_strings(&common.k_is),
+            gates: common.gates.iter().map(|g| g.0.id()).collect(),
+            selector_indices: common.selectors_info.selector_indices.clone(),
+            selector_groups: common
+                .selectors_info
+                .groups
+                .iter()
+                .map(|r| (r.start, r.end))
+                .collect(),
+            fri_rate_bits: common.fri_params.config.rate_bits,
+            fri_cap_height: common.fri_params.config.cap_height,
+            fri_proof_of_work_bits: common.fri_params.config.proof_of_work_bits,
+            fri_num_query_rounds: common.fri_params.config.num_query_rounds,
+            fri_reduction_arity_bits: common.fri_params.reduction_arity_bits.clone(),
+            fri_hiding: common.fri_params.hiding,
+            fri_coset_shift: field_to_string(common.fri_params.config.coset_shift::<F>()),
+            transcript: transcript_spec(common),
+        }
+    }
+
+    pub fn from_circuit_data<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        const D: usize,
+    >(
+        data: &VerifierCircuitData<F, C, D>,
+    ) -> Self {
+        Self::new(&data.verifier_only, &data.common)
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+}
+
+/// Describes the Fiat-Shamir transcript used by `get_challenges`, one step per entry.
+fn transcript_spec<F: RichField + Extendable<D>, const D: usize>(
+    common: &CommonCircuitData<F, D>,
+) -> Vec<String> {
+    let num_challenges = common.config.num_challenges;
+    let mut steps = Vec::new();
+    steps.push(format!(
+        "observe_element transcript_version ({TRANSCRIPT_VERSION})"
+    ));
+    steps.push("observe_hash config_digest".to_string());
+    steps.push("observe_hash circuit_digest".to_string());
+    steps.push("observe_hash inner_hash(public_inputs)".to_string());
+    steps.push("observe_cap wires_cap".to_string());
+    steps.push(format!("squeeze {num_challenges} plonk_betas"));
+    steps.push(format!("squeeze {num_challenges} plonk_gammas"));
+    if common.num_lookup_polys != 0 {
+        let num_additional = (NUM_COINS_LOOKUP - 2) * num_challenges;
+        steps.push(format!(
+            "squeeze {num_additional} plonk_deltas (prefixed by plonk_betas and plonk_gammas)"
+        ));
+    }
+    steps.push("observe_cap plonk_zs_partial_products_cap".to_string());
+    steps.push(format!("squeeze {num_challenges} plonk_alphas"));
+    steps.push("observe_cap quotient_polys_cap".to_string());
+    steps.push(format!("squeeze_extension {D} plonk_zeta"));
+    steps.push(
+        "observe_extension_elements openings at zeta: constants, plonk_sigmas, wires, plonk_zs, \
+         partial_products, quotient_polys, lookup_zs"
+            .to_string(),
+    );
+    steps.push(
+        "observe_extension_elements openings at g * zeta: plonk_zs_next, lookup_zs_next"
+            .to_string(),
+    );
+    steps.push(format!("squeeze_extension {D} fri_alpha"));
+    for i in 0..common.fri_params.reduction_arity_bits.len() {
+        steps.push(format!("observe_cap fri_commit_phase_merkle_caps[{i}]"));
+        steps.push(format!("squeeze_extension {D} fri_betas[{i}]"));
+    }
+    steps.push("observe_extension_elements fri_final_poly".to_string());
+    steps.push("observe_element fri_pow_witness".to_string());
+    steps.push("squeeze 1 fri_pow_response".to_string());
+    steps.push(format!(
+        "squeeze {} fri_query_indices (each reduced mod 2^{})",
+        common.fri_params.config.num_query_rounds,
+        common.degree_bits() + common.fri_params.config.rate_bits
+    ));
+    steps
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct GnarkOpeningSet {
+    pub constants: Vec<Vec<String>>,
+    pub plonk_sigmas: Vec<Vec<String>>,
+    pub wires: Vec<Vec<String>>,
+    pub plonk_zs: Vec<Vec<String>>,
+    pub plonk_zs_next: Vec<Vec<String>>,
+    pub partial_products: Vec<Vec<String>>,
+    pub quotient_polys: Vec<Vec<String>>,
+    pub lookup_zs: Vec<Vec<String>>,
+    pub lookup_zs_next: Vec<Vec<String>>,
+}
+
+impl GnarkOpeningSet {
+    fn new<F: RichField + Extendable<D>, const D: usize>(openings: &OpeningSet<F, D>) -> Self {
+        Self {
+            constants: exts_to_strings::<F, D>(&openings.constants),
+            plonk_sigmas: exts_to_strings::<F, D>(&openings.plonk_sigmas),
+            wires: exts_to_strings::<F, D>(&openings.wires),
+            plonk_zs: exts_to_strings::<F, D>(&openings.plonk_zs),
+            plonk_zs_next: exts_to_strings::<F, D>(&openings.plonk_zs_next),
+            partial_products: exts_to_strings::<F, D>(&openings.partial_products),
+            quotient_polys: exts_to_strings::<F, D>(&openings.quotient_polys),
+            lookup_zs: exts_to_strings::<F, D>(&openings.lookup_zs),
+            lookup_zs_next: exts_to_strings::<F, D>(&openings.lookup_zs_next),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct GnarkMerkleProof {
+    pub siblings: Vec<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct GnarkFriInitialTreeProof {
+    /// For each initial oracle, the leaf values followed by their Merkle proof.
+    pub evals_proofs: Vec<(Vec<String>, GnarkMerkleProof)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct GnarkFriQueryStep {
+    pub evals: Vec<Vec<String>>,
+    pub merkle_proof: GnarkMerkleProof,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct GnarkFriQueryRound {
+    pub initial_trees_proof: GnarkFriInitialTreeProof,
+    pub steps: Vec<GnarkFriQueryStep>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct GnarkFriProof {
+    pub commit_phase_merkle_caps: Vec<Vec<Vec<String>>>,
+    pub query_round_proofs: Vec<GnarkFriQueryRound>,
+    pub final_poly: Vec<Vec<String>>,
+    pub pow_witness: String,
+}
+
+impl GnarkFriProof {
+    fn new<F: RichField + Extendable<D>, H: Hasher<F>, const D: usize>(
+        proof: &FriProof<F, H, D>,
+    ) -> Self {
+        let merkle_proof = |siblings: &[H::Hash]| GnarkMerkleProof {
+            siblings: siblings.iter().map(hash_to_strings).collect(),
+        };
+        Self {
+            commit_phase_merkle_caps: proof
+                .commit_phase_merkle_caps
+                .iter()
+                .map(cap_to_strings)
+                .collect(),
+            query_round_proofs: proof
+                .query_round_proofs
+                .iter()
+                .map(|round| GnarkFriQueryRound {
+                    initial_trees_proof: GnarkFriInitialTreeProof {
+                        evals_proofs: round
+                            .initial_trees_proof
+                            .evals_proofs
+                            .iter()
+                            .map(|(evals, proof)| {
+                                (fields_to_strings(evals), merkle_proof(&proof.siblings))
+                            })
+                            .collect(),
+                    },
+                    steps: round
+                        .steps
+                        .iter()
+                        .map(|step| GnarkFriQueryStep {
+                            evals: exts_to_strings::<F, D>(&step.evals),
+                            merkle_proof: merkle_proof(&step.merkle_proof.siblings),
+                        })
+                        .collect(),
+                })
+                .collect(),
+            final_poly: exts_to_strings::<F, D>(&proof.final_poly.coeffs),
+            pow_witness: field_to_string(proof.pow_witness),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct GnarkProof {
+    pub wires_cap: Vec<Vec<String>>,
+    pub plonk_zs_partial_products_cap: Vec<Vec<String>>,
+    pub quotient_polys_cap: Vec<Vec<String>>,
+    pub openings: GnarkOpeningSet,
+    pub opening_proof: GnarkFriProof,
+}
+
+/// The witness file for a wrapper circuit. `public_inputs` and `circuit_digest` are meant to be
+/// public inputs of the wrapper; `proof` is its private witness.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct GnarkWrapperWitness {
+    pub version: u32,
+    pub public_inputs: Vec<String>,
+    pub circuit_digest: Vec<String>,
+    pub proof: GnarkProof,
+}
+
+impl GnarkWrapperWitness {
+    pub fn new<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+        proof_with_pis: &ProofWithPublicInputs<F, C, D>,
+        verifier_only: &VerifierOnlyCircuitData<C, D>,
+    ) -> Self {
+        let proof = &proof_with_pis.proof;
+        Self {
+            version: GNARK_EXPORT_VERSION,
+            public_inputs: fields_to_strings(&proof_with_pis.public_inputs),
+            circuit_digest: hash_to_strings(&verifier_only.circuit_digest),
+            proof: GnarkProof {
+                wires_cap: cap_to_strings(&proof.wires_cap),
+                plonk_zs_partial_products_cap: cap_to_strings(&proof.plonk_zs_partial_products_cap),
+                quotient_polys_cap: cap_to_strings(&proof.quotient_polys_cap),
+                openings: GnarkOpeningSet::new(&proof.openings),
+                opening_proof: GnarkFriProof::new(&proof.opening_proof),
+            },
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+}
+
+/// Writes `verifier_data.json` and `proof_with_public_inputs.json` to `dir`.
+#[cfg(feature = "std")]
+pub fn write_to_dir<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    dir: &std::path::Path,
+    proof_with_pis: &ProofWithPublicInputs<F, C, D>,
+    data: &VerifierCircuitData<F, C, D>,
+) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let verifier_data = GnarkVerifierData::from_circuit_data(data);
+    std::fs::write(dir.join("verifier_data.json"), verifier_data.to_json()?)?;
+    let witness = GnarkWrapperWitness::new(proof_with_pis, &data.verifier_only);
+    std::fs::write(
+        dir.join("proof_with_public_inputs.json"),
+        witness.to_json()?,
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::field::types::Field;
+    use crate::fri::FriConfig;
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    #[test]
+    fn test_gnark_export_round_trip() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x = builder.add_virtual_target();
+        let y = builder.mul(x, x);
+        builder.register_public_input(x);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(7));
+        let proof = data.prove(pw)?;
+        data.verify(proof.clone())?;
+
+        let verifier_data = data.verifier_data();
+        let exported_vd = GnarkVerifierData::from_circuit_data(&verifier_data);
+        assert_eq!(exported_vd.num_public_inputs, 2);
+        assert_eq!(exported_vd.hasher, "poseidon");
+        assert_eq!(exported_vd.inner_hasher, "poseidon");
+        assert_eq!(
+            exported_vd.constants_sigmas_cap.len(),
+            1 << data.common.config.fri_config.cap_height
+        );
+        assert_eq!(exported_vd.fri_coset_shift, "7");
+        assert_eq!(
+            GnarkVerifierData::from_json(&exported_vd.to_json()?)?,
+            exported_vd
+        );
+
+        let witness = GnarkWrapperWitness::new(&proof, &verifier_data.verifier_only);
+        assert_eq!(witness.public_inputs, vec!["7", "49"]);
+        assert_eq!(
+            witness.proof.opening_proof.query_round_proofs.len(),
+            data.common.config.fri_config.num_query_rounds
+        );
+        assert!(witness.proof.openings.wires.iter().all(|e| e.len() == D));
+        assert_eq!(
+            GnarkWrapperWitness::from_json(&witness.to_json()?)?,
+            witness
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_gnark_export_custom_coset_shift() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let standard = CircuitConfig::standard_recursion_config();
+        let config = CircuitConfig {
+            fri_config: FriConfig {
+                coset_shift: Some(49),
+                ..standard.fri_config.clone()
+            },
+            ..standard
+        };
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x = builder.add_virtual_public_input();
+        let y = builder.mul(x, x);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+
+        let exported_vd = GnarkVerifierData::from_circuit_data(&data.verifier_data());
+        assert_eq!(exported_vd.fri_coset_shift, "49");
+    }
+
+    #[cfg(feature = "poseidon_bn254")]
+    #[test]
+    fn test_gnark_export_poseidon_bn254() -> Result<()> {
+        use crate::plonk::config::PoseidonBn254GoldilocksConfig;
+
+        const D: usize = 2;
+        type C = PoseidonBn254GoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x = builder.add_virtual_public_input();
+        let y = builder.mul(x, x);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(7));
+        let proof = data.prove(pw)?;
+        let verifier_data = data.verifier_data();
+        let exported_vd = GnarkVerifierData::from_circuit_data(&verifier_data);
+        assert_eq!(exported_vd.hasher, "poseidon-bn254");
+        assert_eq!(exported_vd.hasher_hash_size, 32);
+
+        // Digests are recovered from their 7-byte chunks.
+        let bytes = exported_vd
+            .circuit_digest
+            .iter()
+            .flat_map(|chunk| chunk.parse::<u64>().unwrap().to_le_bytes()[..7].to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(bytes[..32], verifier_data.verifier_only.circuit_digest.0);
+
+        let witness = GnarkWrapperWitness::new(&proof, &verifier_data.verifier_only);
+        assert_eq!(witness.public_inputs, vec!["7", "49"]);
+        Ok(())
+    }
+}
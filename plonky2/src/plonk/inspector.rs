@@ -0,0 +1,318 @@
+//! Queries on a built circuit, for tracking a constraint failure at some row back to the circuit:
+//! which gate occupies a row, which wires a target is copied to, and which target a public input
+//! is. [`CircuitInspector::query`] answers these as text, for `plonky2-cli explore`.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use anyhow::{anyhow, bail, ensure, Result};
+
+use crate::field::extension::Extendable;
+use crate::field::polynomial::PolynomialValues;
+use crate::field::types::Field;
+use crate::hash::hash_types::RichField;
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::plonk::circuit_data::CircuitData;
+use crate::plonk::config::GenericConfig;
+
+/// The gate occupying a row.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RowInfo<F: Field> {
+    pub row: usize,
+    /// The index of the gate in `CommonCircuitData::gates`.
+    pub gate_index: usize,
+    pub gate_id: String,
+    /// The gate's constants on this row.
+    pub constants: Vec<F>,
+}
+
+/// The copies of a target.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TargetInfo {
+    /// The names given with `CircuitBuilder::name_target` to the target or its copies.
+    pub name: Option<String>,
+    /// The wires which the target is copied to.
+    pub wires: Vec<Wire>,
+    /// The indices of the public inputs which are copies of the target.
+    pub public_inputs: Vec<usize>,
+}
+
+pub struct CircuitInspector<
+    'a,
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+> {
+    data: &'a CircuitData<F, C, D>,
+    /// The values of the constant polynomials, selectors first.
+    constants: Vec<PolynomialValues<F>>,
+}
+
+impl<'a, F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    CircuitInspector<'a, F, C, D>
+{
+    pub fn new(data: &'a CircuitData<F, C, D>) -> Self {
+        let constants = data.prover_only.constants_sigmas_commitment.polynomials
+            [..data.common.num_constants]
+            .iter()
+            .map(|p| p.clone().fft())
+            .collect();
+        Self { data, constants }
+    }
+
+    pub fn degree(&self) -> usize {
+        self.data.common.degree()
+    }
+
+    pub fn row(&self, row: usize) -> Result<RowInfo<F>> {
+        ensure!(
+            row < self.degree(),
+            "Row {row} is out of range, the circuit has {} rows",
+            self.degree()
+        );
+        let common = &self.data.common;
+        let gate_index = common.selectors_info.gate_index(&self.constants, row);
+        let gate = &common.gates[gate_index].0;
+        let first_constant = common.selectors_info.num_selectors() + common.num_lookup_selectors;
+        let constants = self.constants[first_constant..first_constant + gate.num_constants()]
+            .iter()
+            .map(|c| c.values[row])
+            .collect();
+        Ok(RowInfo {
+            row,
+            gate_index,
+            gate_id: gate.id(),
+            constants,
+        })
+    }
+
+    /// The rows occupied by the gate with index `gate_index` in `CommonCircuitData::gates`.
+    pub fn rows_of_gate(&self, gate_index: usize) -> Vec<usize> {
+        let num_selectors = self.data.common.selectors_info.num_selectors();
+        let gate_index = F::from_canonical_usize(gate_index);
+        (0..self.degree())
+            .filter(|&row| {
+                self.constants[..num_selectors]
+                    .iter()
+                    .any(|selector| selector.values[row] == gate_index)
+            })
+            .collect()
+    }
+
+    fn representative(&self, target: Target) -> Result<usize> {
+        let num_wires = self.data.common.config.num_wires;
+        let representative_map = &self.data.prover_only.representative_map;
+        // Check the target's coordinates before computing its index, which could overflow.
+        let in_range = match target {
+            Target::Wire(Wire { row, column }) => row < self.degree() && column < num_wires,
+            Target::VirtualTarget { index } => {
+                index
+                    < representative_map
+                        .len()
+                        .saturating_sub(self.degree() * num_wires)
+            }
+        };
+        ensure!(in_range, "{target:?} is out of range");
+        Ok(representative_map[target.index(num_wires, self.degree())])
+    }
+
+    /// The name given to `target` or its copies, which unlike `target` doesn't scan the witness.
+    pub fn target_name(&self, target: Target) -> Result<Option<&str>> {
+        let rep = self.representative(target)?;
+        Ok(self
+            .data
+            .prover_only
+            .target_names
+            .get(&rep)
+            .map(String::as_str))
+    }
+
+    pub fn target(&self, target: Target) -> Result<TargetInfo> {
+        let rep = self.representative(target)?;
+        let num_wires = self.data.common.config.num_wires;
+        let prover_only = &self.data.prover_only;
+        let wires = prover_only.representative_map[..self.degree() * num_wires]
+            .iter()
+            .enumerate()
+            .filter(|&(_, &r)| r == rep)
+            .map(|(i, _)| Wire {
+                row: i / num_wires,
+                column: i % num_wires,
+            })
+            .collect();
+        let public_inputs = prover_only
+            .public_inputs
+            .iter()
+            .enumerate()
+            .filter(|&(_, &t)| self.representative(t).ok() == Some(rep))
+            .map(|(i, _)| i)
+            .collect();
+        Ok(TargetInfo {
+            name: prover_only.target_names.get(&rep).cloned(),
+            wires,
+            public_inputs,
+        })
+    }
+
+    /// The target registered as the public input with index `index`.
+    pub fn public_input(&self, index: usize) -> Result<Target> {
+        self.data
+            .prover_only
+            .public_inputs
+            .get(index)
+            .copied()
+            .ok_or_else(|| {
+                anyhow!(
+                    "Public input {index} is out of range, the circuit has {}",
+                    self.data.common.num_public_inputs
+                )
+            })
+    }
+
+    /// Answers a textual query, one of:
+    /// - `row <row>`: the gate occupying a row, its constants and the names of its wires;
+    /// - `wire <row> <column>` or `virtual <index>`: the copies of a target;
+    /// - `pi <index>`: the target of a public input, and its copies;
+    /// - `gate <prefix>`: the rows occupied by the gates whose ID starts with `prefix`.
+    pub fn query(&self, query: &str) -> Result<String> {
+        let words = query.split_whitespace().collect::<Vec<_>>();
+        let parse = |word: &str| {
+            word.parse::<usize>()
+                .map_err(|_| anyhow!("Expected an index, got {word}"))
+        };
+        match words[..] {
+            ["row", row] => self.describe_row(parse(row)?),
+            ["wire", row, column] => {
+                self.describe_target(Target::wire(parse(row)?, parse(column)?))
+            }
+            ["virtual", index] => self.describe_target(Target::VirtualTarget {
+                index: parse(index)?,
+            }),
+            ["pi", index] => {
+                let target = self.public_input(parse(index)?)?;
+                Ok(format!("{target:?}\n{}", self.describe_target(target)?))
+            }
+            ["gate", ..] => {
+                let prefix = query.trim_start()["gate".len()..].trim();
+                let mut out = String::new();
+                for (i, gate) in self.data.common.gates.iter().enumerate() {
+                    let id = gate.0.id();
+                    if id.starts_with(prefix) {
+                        let rows = self.rows_of_gate(i);
+                        writeln!(out, "{id} on {} rows: {rows:?}", rows.len()).unwrap();
+                    }
+                }
+                ensure!(!out.is_empty(), "No gate's ID starts with {prefix}");
+                Ok(out)
+            }
+            _ => bail!(
+                "Unknown query {query:?}, expected `row <row>`, `wire <row> <column>`, \
+                 `virtual <index>`, `pi <index>` or `gate <prefix>`"
+            ),
+        }
+    }
+
+    fn describe_row(&self, row: usize) -> Result<String> {
+        let info = self.row(row)?;
+        let mut out = format!("Row {row}: {} (gate {})\n", info.gate_id, info.gate_index);
+        if !info.constants.is_empty() {
+            let constants = info
+                .constants
+                .iter()
+                .map(|c| format!("{c}"))
+                .collect::<Vec<_>>();
+            writeln!(out, "Constants: {}", constants.join(", ")).unwrap();
+        }
+        for column in 0..self.data.common.config.num_wires {
+            if let Some(name) = self.target_name(Target::wire(row, column))? {
+                writeln!(out, "Wire {column}: {name}").unwrap();
+            }
+        }
+        Ok(out)
+    }
+
+    fn describe_target(&self, target: Target) -> Result<String> {
+        let info = self.target(target)?;
+        let mut out = String::new();
+        if let Some(name) = &info.name {
+            writeln!(out, "Name: {name}").unwrap();
+        }
+        let wires = info
+            .wires
+            .iter()
+            .map(|w| format!("({}, {})", w.row, w.column))
+            .collect::<Vec<_>>();
+        writeln!(out, "Wires (row, column): {}", wires.join(", ")).unwrap();
+        if !info.public_inputs.is_empty() {
+            writeln!(out, "Public inputs: {:?}", info.public_inputs).unwrap();
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    #[test]
+    fn test_circuit_inspector() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        builder.name_target(x, "x");
+        let zero = builder.zero();
+        let y = builder.arithmetic(F::from_canonical_u32(3), F::ZERO, x, x, zero);
+        builder.register_public_input(x);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+        let inspector = CircuitInspector::new(&data);
+
+        assert_eq!(inspector.public_input(0)?, x);
+        let info = inspector.target(x)?;
+        assert!(info.name.unwrap().ends_with('x'));
+        assert_eq!(info.public_inputs, [0]);
+
+        // `y` is the output of an arithmetic gate, whose constants include the factor 3.
+        let y_info = inspector.target(y)?;
+        assert_eq!(y_info.public_inputs, [1]);
+        let arithmetic_row = y_info
+            .wires
+            .iter()
+            .map(|w| inspector.row(w.row))
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .find(|row| row.gate_id.starts_with("Arithmetic"))
+            .expect("No arithmetic gate outputs y");
+        assert_eq!(arithmetic_row.constants[0], F::from_canonical_u32(3));
+        assert!(inspector
+            .rows_of_gate(arithmetic_row.gate_index)
+            .contains(&arithmetic_row.row));
+
+        assert!(inspector.query("row 0")?.starts_with("Row 0: "));
+        assert!(inspector
+            .query("gate Arithmetic")?
+            .contains("ArithmeticGate"));
+        assert!(inspector.query("pi 1")?.contains("Public inputs: [1]"));
+        assert!(inspector
+            .query(&format!("row {}", inspector.degree()))
+            .is_err());
+        assert!(inspector.query("pi 2").is_err());
+        assert!(inspector.query("wire 0").is_err());
+
+        // Indices whose flattened index would overflow are rejected rather than panicking.
+        let max = usize::MAX;
+        assert!(inspector.query(&format!("wire {max} {max}")).is_err());
+        assert!(inspector.query(&format!("wire 0 {max}")).is_err());
+        assert!(inspector.query(&format!("virtual {max}")).is_err());
+        Ok(())
+    }
+}
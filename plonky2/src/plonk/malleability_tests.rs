@@ -0,0 +1,200 @@
+//! Mutates valid proofs in targeted ways and checks that the verifier rejects each of them. Every
+//! element of a proof is either bound by the Fiat-Shamir transcript or checked against it, so a
+//! mutation which still verifies points to a soundness or transcript-binding regression.
+
+use alloc::vec;
+
+use anyhow::Result;
+
+use crate::field::extension::Extendable;
+use crate::field::types::Field;
+use crate::gates::noop::NoopGate;
+use crate::iop::witness::{PartialWitness, WitnessWrite};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::{CircuitConfig, CircuitData};
+use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+use crate::plonk::proof::ProofWithPublicInputs;
+
+const D: usize = 2;
+type C = PoseidonGoldilocksConfig;
+type F = <C as GenericConfig<D>>::F;
+type FE = <F as Extendable<D>>::Extension;
+type Mutation = fn(&mut ProofWithPublicInputs<F, C, D>);
+
+/// Proves `x^3 + x = y` for a fixed `x`, with public inputs `[x, y]`. The witness is fixed so that
+/// the proof, and thus each test, is deterministic. The circuit is padded so that FRI has at least
+/// one folding step.
+fn proof() -> Result<(CircuitData<F, C, D>, ProofWithPublicInputs<F, C, D>)> {
+    let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+    let x = builder.add_virtual_public_input();
+    let x_cubed = builder.cube(x);
+    let y = builder.add(x_cubed, x);
+    builder.register_public_input(y);
+    for _ in 0..100 {
+        builder.add_gate(NoopGate, vec![]);
+    }
+    let data = builder.build::<C>();
+
+    let mut pw = PartialWitness::new();
+    pw.set_target(x, F::from_canonical_u64(7));
+    let proof = data.prove(pw)?;
+    Ok((data, proof))
+}
+
+/// Applies each mutation to a fresh copy of a valid proof, and checks that the result is
+/// rejected.
+fn check_rejected(mutations: &[(&str, Mutation)]) -> Result<()> {
+    let (data, proof) = proof()?;
+    data.verify(proof.clone())?;
+    for (name, mutate) in mutations {
+        let mut mutated = proof.clone();
+        mutate(&mut mutated);
+        assert_ne!(mutated, proof, "Mutation {name} had no effect");
+        assert!(
+            data.verify(mutated).is_err(),
+            "Proof with mutation {name} was accepted"
+        );
+    }
+    Ok(())
+}
+
+fn bump(x: &mut FE) {
+    *x += FE::ONE;
+}
+
+#[test]
+fn test_flipped_openings() -> Result<()> {
+    check_rejected(&[
+        ("constants", |p| bump(&mut p.proof.openings.constants[0])),
+        ("plonk_sigmas", |p| {
+            bump(&mut p.proof.openings.plonk_sigmas[0])
+        }),
+        ("wires", |p| bump(&mut p.proof.openings.wires[0])),
+        ("plonk_zs", |p| bump(&mut p.proof.openings.plonk_zs[0])),
+        ("plonk_zs_next", |p| {
+            bump(&mut p.proof.openings.plonk_zs_next[0])
+        }),
+        ("partial_products", |p| {
+            bump(&mut p.proof.openings.partial_products[0])
+        }),
+        ("quotient_polys", |p| {
+            bump(&mut p.proof.openings.quotient_polys[0])
+        }),
+        ("last quotient_polys", |p| {
+            bump(p.proof.openings.quotient_polys.last_mut().unwrap())
+        }),
+    ])
+}
+
+#[test]
+fn test_altered_commitments() -> Result<()> {
+    check_rejected(&[
+        ("wires_cap", |p| {
+            p.proof.wires_cap.0[0].elements[0] += F::ONE
+        }),
+        ("plonk_zs_partial_products_cap", |p| {
+            p.proof.plonk_zs_partial_products_cap.0[0].elements[0] += F::ONE
+        }),
+        ("quotient_polys_cap", |p| {
+            p.proof.quotient_polys_cap.0[0].elements[0] += F::ONE
+        }),
+        ("commit_phase_merkle_caps", |p| {
+            p.proof.opening_proof.commit_phase_merkle_caps[0].0[0].elements[0] += F::ONE
+        }),
+    ])
+}
+
+#[test]
+fn test_altered_merkle_paths() -> Result<()> {
+    check_rejected(&[
+        ("truncated initial tree path", |p| {
+            let round = &mut p.proof.opening_proof.query_round_proofs[0];
+            round.initial_trees_proof.evals_proofs[0].1.siblings.pop();
+        }),
+        ("truncated query step path", |p| {
+            let round = &mut p.proof.opening_proof.query_round_proofs[0];
+            round.steps[0].merkle_proof.siblings.pop();
+        }),
+        ("altered initial tree sibling", |p| {
+            let round = &mut p.proof.opening_proof.query_round_proofs[0];
+            round.initial_trees_proof.evals_proofs[0].1.siblings[0].elements[0] += F::ONE;
+        }),
+        ("altered initial tree leaf", |p| {
+            let round = &mut p.proof.opening_proof.query_round_proofs[0];
+            round.initial_trees_proof.evals_proofs[0].0[0] += F::ONE;
+        }),
+        ("altered query step evaluation", |p| {
+            let round = &mut p.proof.opening_proof.query_round_proofs[0];
+            bump(&mut round.steps[0].evals[0]);
+        }),
+    ])
+}
+
+#[test]
+fn test_swapped_query_responses() -> Result<()> {
+    check_rejected(&[
+        ("swapped query rounds", |p| {
+            p.proof.opening_proof.query_round_proofs.swap(0, 1)
+        }),
+        ("swapped initial tree proofs", |p| {
+            let rounds = &mut p.proof.opening_proof.query_round_proofs;
+            let first = rounds[0].initial_trees_proof.clone();
+            rounds[0].initial_trees_proof = rounds[1].initial_trees_proof.clone();
+            rounds[1].initial_trees_proof = first;
+        }),
+        ("swapped query steps", |p| {
+            let rounds = &mut p.proof.opening_proof.query_round_proofs;
+            let first = rounds[0].steps.clone();
+            rounds[0].steps = rounds[1].steps.clone();
+            rounds[1].steps = first;
+        }),
+        ("duplicated query round", |p| {
+            let rounds = &mut p.proof.opening_proof.query_round_proofs;
+            rounds[1] = rounds[0].clone();
+        }),
+    ])
+}
+
+#[test]
+fn test_altered_fri_tail() -> Result<()> {
+    check_rejected(&[
+        ("final_poly", |p| {
+            bump(&mut p.proof.opening_proof.final_poly.coeffs[0])
+        }),
+        ("pow_witness", |p| {
+            p.proof.opening_proof.pow_witness += F::ONE
+        }),
+    ])
+}
+
+#[test]
+fn test_altered_public_inputs() -> Result<()> {
+    check_rejected(&[
+        ("first public input", |p| p.public_inputs[0] += F::ONE),
+        ("last public input", |p| {
+            *p.public_inputs.last_mut().unwrap() += F::ONE
+        }),
+        ("swapped public inputs", |p| p.public_inputs.swap(0, 1)),
+        ("extra public input", |p| p.public_inputs.push(F::ZERO)),
+        ("missing public input", |p| {
+            p.public_inputs.pop();
+        }),
+    ])
+}
+
+/// A proof must not verify against a different circuit, even one of the same shape.
+#[test]
+fn test_other_circuit() -> Result<()> {
+    let (_, proof) = proof()?;
+    let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+    let x = builder.add_virtual_public_input();
+    let x_squared = builder.square(x);
+    let y = builder.add(x_squared, x);
+    builder.register_public_input(y);
+    for _ in 0..100 {
+        builder.add_gate(NoopGate, vec![]);
+    }
+    let other = builder.build::<C>();
+    assert!(other.verify(proof).is_err());
+    Ok(())
+}
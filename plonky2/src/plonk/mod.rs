@@ -2,11 +2,18 @@ pub mod circuit_builder;
 pub mod circuit_data;
 pub mod config;
 pub(crate) mod copy_constraint;
+pub mod envelope;
 mod get_challenges;
+pub mod gnark_export;
+pub mod inspector;
+#[cfg(test)]
+mod malleability_tests;
 pub(crate) mod permutation_argument;
 pub mod plonk_common;
 pub mod proof;
 pub mod prover;
+pub mod public_input_abi;
+pub mod sanitizer;
 mod validate_shape;
 pub(crate) mod vanishing_poly;
 pub mod vars;
@@ -1,6 +1,8 @@
 use alloc::vec;
 use alloc::vec::Vec;
 
+use anyhow::{ensure, Result};
+
 use crate::field::extension::Extendable;
 use crate::field::packed::PackedField;
 use crate::field::types::Field;
@@ -153,3 +155,61 @@ pub fn reduce_with_powers_ext_circuit<F: RichField + Extendable<D>, const D: usi
     let mut alpha = ReducingFactorTarget::new(alpha);
     alpha.reduce(terms, builder)
 }
+
+/// Checks the identity `vanishing(zeta) = Z_H(zeta) t(zeta)` for each challenge, given the
+/// openings at `zeta` of the vanishing polynomials and of the quotient chunks, and `zeta^n` where
+/// `n` is the trace length.
+///
+/// `quotient_polys_zeta` holds `quotient_degree_factor` evaluations per challenge, namely
+/// `t_0(zeta), ..., t_{quotient_degree_factor - 1}(zeta)`, where the full quotient polynomial is
+/// `t(X) = t_0(X) + t_1(X) X^n + t_2(X) X^{2n} + ...`. So `t(zeta)` is recombined as
+/// `reduce_with_powers(chunk, zeta^n)` for each chunk of `quotient_degree_factor` evaluations.
+pub fn check_quotient_openings<F: Field>(
+    vanishing_polys_zeta: &[F],
+    quotient_polys_zeta: &[F],
+    quotient_degree_factor: usize,
+    zeta_pow_deg: F,
+) -> Result<()> {
+    ensure!(
+        quotient_polys_zeta.len() == vanishing_polys_zeta.len() * quotient_degree_factor,
+        "Expected {} quotient chunk openings, got {}",
+        vanishing_polys_zeta.len() * quotient_degree_factor,
+        quotient_polys_zeta.len()
+    );
+    let z_h_zeta = zeta_pow_deg - F::ONE;
+    for (&vanishing, chunk) in vanishing_polys_zeta
+        .iter()
+        .zip(quotient_polys_zeta.chunks(quotient_degree_factor))
+    {
+        ensure!(
+            vanishing == z_h_zeta * reduce_with_powers(chunk, zeta_pow_deg),
+            "Mismatch between evaluation and opening of quotient polynomial"
+        );
+    }
+    Ok(())
+}
+
+/// In-circuit version of [`check_quotient_openings`].
+pub fn check_quotient_openings_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    vanishing_polys_zeta: &[ExtensionTarget<D>],
+    quotient_polys_zeta: &[ExtensionTarget<D>],
+    quotient_degree_factor: usize,
+    zeta_pow_deg: ExtensionTarget<D>,
+) {
+    assert_eq!(
+        quotient_polys_zeta.len(),
+        vanishing_polys_zeta.len() * quotient_degree_factor
+    );
+    let one = builder.one_extension();
+    let z_h_zeta = builder.sub_extension(zeta_pow_deg, one);
+    let mut scale = ReducingFactorTarget::new(zeta_pow_deg);
+    for (&vanishing, chunk) in vanishing_polys_zeta
+        .iter()
+        .zip(quotient_polys_zeta.chunks(quotient_degree_factor))
+    {
+        let recombined_quotient = scale.reduce(chunk, builder);
+        let computed_vanishing_poly = builder.mul_extension(z_h_zeta, recombined_quotient);
+        builder.connect_extension(vanishing, computed_vanishing_poly);
+    }
+}
@@ -21,7 +21,7 @@ use crate::iop::target::Target;
 use crate::plonk::circuit_data::{CommonCircuitData, VerifierOnlyCircuitData};
 use crate::plonk::config::{GenericConfig, Hasher};
 use crate::plonk::verifier::verify_with_challenges;
-use crate::util::serialization::{Buffer, Read, Write};
+use crate::util::serialization::{Buffer, Read, Remaining, Write};
 
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
 #[serde(bound = "")]
@@ -117,6 +117,7 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
         let proof = buffer
             .read_proof_with_public_inputs(common_data)
             .map_err(anyhow::Error::msg)?;
+        ensure!(buffer.is_empty(), "Trailing bytes after proof");
         Ok(proof)
     }
 }
@@ -186,6 +187,7 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
     ) -> anyhow::Result<ProofWithPublicInputs<F, C, D>> {
         let challenges =
             self.get_challenges(self.get_public_inputs_hash(), circuit_digest, common_data)?;
+        self.check_query_indices(&challenges)?;
         let fri_inferred_elements = self.get_inferred_elements(&challenges, common_data);
         let decompressed_proof =
             self.proof
@@ -211,6 +213,7 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
             &verifier_data.circuit_digest,
             common_data,
         )?;
+        self.check_query_indices(&challenges)?;
         let fri_inferred_elements = self.get_inferred_elements(&challenges, common_data);
         let decompressed_proof =
             self.proof
@@ -230,6 +233,17 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
         C::InnerHasher::hash_no_pad(&self.public_inputs)
     }
 
+    /// Decompression looks up the query round proofs by the challenged indices, so a proof storing
+    /// other indices must be rejected beforehand.
+    fn check_query_indices(&self, challenges: &ProofChallenges<F, D>) -> anyhow::Result<()> {
+        ensure!(
+            self.proof.opening_proof.query_round_proofs.indices
+                == challenges.fri_challenges.fri_query_indices,
+            "Compressed proof was not generated for the challenged query indices"
+        );
+        Ok(())
+    }
+
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut buffer = Vec::new();
         buffer
@@ -454,10 +468,11 @@ mod tests {
     use crate::fri::reduction_strategies::FriReductionStrategy;
     use crate::gates::lookup_table::LookupTable;
     use crate::gates::noop::NoopGate;
-    use crate::iop::witness::PartialWitness;
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
     use crate::plonk::circuit_builder::CircuitBuilder;
     use crate::plonk::circuit_data::CircuitConfig;
     use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::proof::{CompressedProofWithPublicInputs, ProofWithPublicInputs};
     use crate::plonk::verifier::verify;
 
     #[test]
@@ -554,4 +569,64 @@ mod tests {
         verify(proof, &data.verifier_only, &data.common)?;
         data.verify_compressed(compressed_proof)
     }
+
+    #[test]
+    fn test_malformed_proof_bytes() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x = builder.add_virtual_public_input();
+        let y = builder.square(x);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::rand());
+        let proof = data.prove(pw)?;
+
+        let bytes = proof.to_bytes();
+        assert_eq!(
+            ProofWithPublicInputs::from_bytes(bytes.clone(), &data.common)?,
+            proof
+        );
+        let compressed_bytes = data.compress(proof)?.to_bytes();
+
+        let verify_bytes = |bytes: Vec<u8>| {
+            ProofWithPublicInputs::from_bytes(bytes, &data.common).and_then(|p| data.verify(p))
+        };
+        let verify_compressed_bytes = |bytes: Vec<u8>| {
+            CompressedProofWithPublicInputs::from_bytes(bytes, &data.common)
+                .and_then(|p| data.verify_compressed(p))
+        };
+        #[allow(clippy::type_complexity)]
+        let cases: [(Vec<u8>, &dyn Fn(Vec<u8>) -> Result<()>); 2] = [
+            (bytes, &verify_bytes),
+            (compressed_bytes, &verify_compressed_bytes),
+        ];
+
+        for (bytes, verify_bytes) in cases {
+            verify_bytes(bytes.clone())?;
+
+            assert!(verify_bytes(bytes[..bytes.len() - 1].to_vec()).is_err());
+            let mut extended = bytes.clone();
+            extended.push(0);
+            assert!(verify_bytes(extended).is_err());
+
+            // The last public input, encoded non-canonically.
+            let mut non_canonical = bytes.clone();
+            let len = non_canonical.len();
+            non_canonical[len - 8..].copy_from_slice(&u64::MAX.to_le_bytes());
+            assert!(verify_bytes(non_canonical).is_err());
+
+            // Corruptions anywhere must be rejected, and never cause a panic.
+            for i in (0..bytes.len()).step_by(bytes.len() / 64) {
+                let mut corrupted = bytes.clone();
+                corrupted[i] ^= 1;
+                assert!(verify_bytes(corrupted).is_err());
+            }
+        }
+        Ok(())
+    }
 }
@@ -1,6 +1,7 @@
+use alloc::collections::BTreeMap;
 use alloc::vec::Vec;
 use alloc::{format, vec};
-use core::cmp::min;
+use core::cmp::{max, min};
 use core::mem::swap;
 
 use anyhow::{ensure, Result};
@@ -8,28 +9,34 @@ use hashbrown::HashMap;
 use plonky2_maybe_rayon::*;
 
 use super::circuit_builder::{LookupChallenges, LookupWire};
-use crate::field::extension::Extendable;
+use crate::field::extension::{Extendable, FieldExtension};
+use crate::field::fft::fft_root_table;
 use crate::field::polynomial::{PolynomialCoeffs, PolynomialValues};
 use crate::field::types::Field;
 use crate::field::zero_poly_coset::ZeroPolyOnCoset;
-use crate::fri::oracle::PolynomialBatch;
+use crate::fri::oracle::{CommitmentArena, PolynomialBatch};
 use crate::gates::lookup::LookupGate;
 use crate::gates::lookup_table::LookupTableGate;
 use crate::gates::selectors::LookupSelectors;
 use crate::hash::hash_types::RichField;
+use crate::hash::merkle_tree::MerkleCap;
 use crate::iop::challenger::Challenger;
-use crate::iop::generator::generate_partial_witness;
+use crate::iop::generator::{generate_partial_witness_in, WitnessArena};
 use crate::iop::target::Target;
 use crate::iop::witness::{MatrixWitness, PartialWitness, PartitionWitness, Witness, WitnessWrite};
 use crate::plonk::circuit_builder::NUM_COINS_LOOKUP;
-use crate::plonk::circuit_data::{CommonCircuitData, ProverOnlyCircuitData};
-use crate::plonk::config::{GenericConfig, Hasher};
+use crate::plonk::circuit_data::{
+    CircuitData, CommonCircuitData, ProverCircuitData, ProverOnlyCircuitData,
+};
+use crate::plonk::config::{GenericConfig, GenericHashOut, Hasher};
 use crate::plonk::plonk_common::PlonkOracle;
 use crate::plonk::proof::{OpeningSet, Proof, ProofWithPublicInputs};
 use crate::plonk::vanishing_poly::{eval_vanishing_poly_base_batch, get_lut_poly};
 use crate::plonk::vars::EvaluationVarsBaseBatch;
 use crate::timed;
 use crate::util::partial_products::{partial_products_and_z_gx, quotient_chunk_products};
+use crate::util::permutation::batch_quotients;
+use crate::util::serialization::{Buffer, IoResult, Read, Remaining, Write};
 use crate::util::timing::TimingTree;
 use crate::util::{ceil_div_usize, log2_ceil, transpose};
 
@@ -117,59 +124,267 @@ where
     C::Hasher: Hasher<F>,
     C::InnerHasher: Hasher<F>,
 {
-    let has_lookup = !common_data.luts.is_empty();
+    prove_with_arena(
+        prover_data,
+        common_data,
+        inputs,
+        timing,
+        &mut WitnessArena::new(),
+    )
+}
+
+/// Like [`prove`], but reuses the witness generation buffers in `arena`, which is worthwhile when
+/// proving the same circuit repeatedly.
+pub fn prove_with_arena<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+    inputs: PartialWitness<F>,
+    timing: &mut TimingTree,
+    arena: &mut WitnessArena<F>,
+) -> Result<ProofWithPublicInputs<F, C, D>>
+where
+    C::Hasher: Hasher<F>,
+    C::InnerHasher: Hasher<F>,
+{
+    #[cfg(feature = "tracing")]
+    let _span = {
+        let config = &common_data.config;
+        tracing::info_span!(
+            "prove",
+            degree_bits = common_data.degree_bits(),
+            num_wires = config.num_wires,
+            num_routed_wires = config.num_routed_wires,
+            num_public_inputs = common_data.num_public_inputs,
+            num_challenges = config.num_challenges,
+            rate_bits = config.fri_config.rate_bits,
+            cap_height = config.fri_config.cap_height,
+            num_query_rounds = config.fri_config.num_query_rounds,
+            proof_of_work_bits = config.fri_config.proof_of_work_bits,
+            zero_knowledge = config.zero_knowledge,
+        )
+        .entered()
+    };
+    let commitment = commit_witness_in(prover_data, common_data, inputs, timing, arena)?;
+    let proof = finish_proof_in(
+        prover_data,
+        common_data,
+        commitment,
+        timing,
+        arena.commitments_mut(),
+    )?;
+    #[cfg(feature = "metrics")]
+    crate::util::metrics::record_proof("plonky2", || proof.to_bytes().len());
+    Ok(proof)
+}
+
+/// The first round of a proof: the witness, committed to but not yet proven.
+///
+/// This lets a protocol publish the wires cap, or use the challenges derived from it, before the
+/// proof is finished with [`finish_proof`]. The serialized state holds the whole witness, so it
+/// must be kept as private as the witness itself.
+#[derive(Debug)]
+pub struct WitnessCommitment<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+> {
+    pub public_inputs: Vec<F>,
+    /// The permutation challenges drawn after observing the wires cap.
+    pub betas: Vec<F>,
+    pub gammas: Vec<F>,
+    /// The lookup challenges, empty if the circuit has no lookups.
+    pub deltas: Vec<F>,
+    witness: MatrixWitness<F>,
+    wires_commitment: PolynomialBatch<F, C, D>,
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    WitnessCommitment<F, C, D>
+{
+    /// The wires cap, which the finished proof will contain.
+    pub fn wires_cap(&self) -> &MerkleCap<F, C::Hasher> {
+        &self.wires_commitment.merkle_tree.cap
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        self.write(&mut buffer)
+            .expect("Writing to a byte-vector cannot fail.");
+        buffer
+    }
+
+    fn write(&self, buffer: &mut Vec<u8>) -> IoResult<()> {
+        for values in [&self.public_inputs, &self.betas, &self.gammas, &self.deltas] {
+            buffer.write_usize(values.len())?;
+            buffer.write_field_vec(values)?;
+        }
+        buffer.write_usize(self.witness.wire_values.len())?;
+        for column in &self.witness.wire_values {
+            buffer.write_usize(column.len())?;
+            buffer.write_field_vec(column)?;
+        }
+        buffer.write_polynomial_batch(&self.wires_commitment)
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self> {
+        let mut buffer = Buffer::new(&bytes);
+        let commitment = Self::read(&mut buffer).map_err(anyhow::Error::msg)?;
+        ensure!(buffer.is_empty(), "Trailing bytes after witness commitment");
+        Ok(commitment)
+    }
+
+    fn read(buffer: &mut Buffer) -> IoResult<Self> {
+        let mut read_values = || {
+            let len = buffer.read_usize()?;
+            buffer.read_field_vec(len)
+        };
+        let public_inputs = read_values()?;
+        let betas = read_values()?;
+        let gammas = read_values()?;
+        let deltas = read_values()?;
+        let num_wires = buffer.read_usize()?;
+        let wire_values = (0..num_wires)
+            .map(|_| {
+                let len = buffer.read_usize()?;
+                buffer.read_field_vec(len)
+            })
+            .collect::<IoResult<Vec<_>>>()?;
+        Ok(Self {
+            public_inputs,
+            betas,
+            gammas,
+            deltas,
+            witness: MatrixWitness { wire_values },
+            wires_commitment: buffer.read_polynomial_batch()?,
+        })
+    }
+}
+
+/// Generates the witness and commits to it, returning the state from which [`finish_proof`]
+/// completes the proof.
+pub fn commit_witness<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+    inputs: PartialWitness<F>,
+    timing: &mut TimingTree,
+) -> Result<WitnessCommitment<F, C, D>>
+where
+    C::Hasher: Hasher<F>,
+    C::InnerHasher: Hasher<F>,
+{
+    commit_witness_in(
+        prover_data,
+        common_data,
+        inputs,
+        timing,
+        &mut WitnessArena::new(),
+    )
+}
+
+fn commit_witness_in<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+    inputs: PartialWitness<F>,
+    timing: &mut TimingTree,
+    arena: &mut WitnessArena<F>,
+) -> Result<WitnessCommitment<F, C, D>>
+where
+    C::Hasher: Hasher<F>,
+    C::InnerHasher: Hasher<F>,
+{
     let config = &common_data.config;
-    let num_challenges = config.num_challenges;
-    let quotient_degree = common_data.quotient_degree();
-    let degree = common_data.degree();
 
     let mut partition_witness = timed!(
         timing,
         &format!("run {} generators", prover_data.generators.len()),
-        generate_partial_witness(inputs, prover_data, common_data)
+        generate_partial_witness_in(inputs, prover_data, common_data, arena)
     );
 
     set_lookup_wires(prover_data, common_data, &mut partition_witness);
 
     let public_inputs = partition_witness.get_targets(&prover_data.public_inputs);
-    let public_inputs_hash = C::InnerHasher::hash_no_pad(&public_inputs);
 
     let witness = timed!(
         timing,
         "compute full witness",
-        partition_witness.full_witness()
+        partition_witness.to_full_witness()
     );
+    arena.recycle(partition_witness);
 
-    let wires_values: Vec<PolynomialValues<F>> = timed!(
+    #[cfg(feature = "sanitize")]
+    timed!(
+        timing,
+        "check constraints on the witness",
+        crate::plonk::sanitizer::check_witness(&witness, &public_inputs, prover_data, common_data)?
+    );
+
+    let arena = arena.commitments_mut();
+    let mut wires_values = arena.take(witness.wire_values.len());
+    timed!(
         timing,
         "compute wire polynomials",
-        witness
-            .wire_values
-            .par_iter()
-            .map(|column| PolynomialValues::new(column.clone()))
-            .collect()
+        wires_values
+            .par_iter_mut()
+            .zip(&witness.wire_values)
+            .for_each(|(values, column)| {
+                values.clear();
+                values.extend_from_slice(column);
+            })
     );
 
     let wires_commitment = timed!(
         timing,
         "compute wires commitment",
-        PolynomialBatch::<F, C, D>::from_values(
-            wires_values,
+        PolynomialBatch::<F, C, D>::from_values_on_coset_in(
+            wires_values
+                .into_iter()
+                .map(PolynomialValues::new)
+                .collect(),
             config.fri_config.rate_bits,
             config.zero_knowledge && PlonkOracle::WIRES.blinding,
             config.fri_config.cap_height,
+            config.fri_config.coset_shift(),
             timing,
             prover_data.fft_root_table.as_ref(),
+            arena,
         )
     );
 
+    let (_, betas, gammas, deltas) = wires_challenges(
+        prover_data,
+        common_data,
+        &public_inputs,
+        &wires_commitment.merkle_tree.cap,
+    );
+
+    Ok(WitnessCommitment {
+        public_inputs,
+        betas,
+        gammas,
+        deltas,
+        witness,
+        wires_commitment,
+    })
+}
+
+/// Observes the instance and the wires cap, and draws the permutation challenges (betas and
+/// gammas) and, if the circuit has lookups, the lookup challenges (deltas).
+#[allow(clippy::type_complexity)]
+fn wires_challenges<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+    public_inputs: &[F],
+    wires_cap: &MerkleCap<F, C::Hasher>,
+) -> (Challenger<F, C::Hasher>, Vec<F>, Vec<F>, Vec<F>) {
+    let num_challenges = common_data.config.num_challenges;
     let mut challenger = Challenger::<F, C::Hasher>::new();
 
     // Observe the instance.
+    challenger.observe_domain_separator::<C::Hasher>(common_data.config_digest::<C::Hasher>());
     challenger.observe_hash::<C::Hasher>(prover_data.circuit_digest);
-    challenger.observe_hash::<C::InnerHasher>(public_inputs_hash);
+    challenger.observe_hash::<C::InnerHasher>(C::InnerHasher::hash_no_pad(public_inputs));
 
-    challenger.observe_cap::<C::Hasher>(&wires_commitment.merkle_tree.cap);
+    challenger.observe_cap::<C::Hasher>(wires_cap);
 
     // We need 4 values per challenge: 2 for the combos, 1 for (X-combo) in the accumulators and 1 to prove that the lookup table was computed correctly.
     // We can reuse betas and gammas for two of them.
@@ -178,7 +393,7 @@ where
     let betas = challenger.get_n_challenges(num_challenges);
     let gammas = challenger.get_n_challenges(num_challenges);
 
-    let deltas = if has_lookup {
+    let deltas = if !common_data.luts.is_empty() {
         let mut delts = Vec::with_capacity(2 * num_challenges);
         let num_additional_challenges = num_lookup_challenges - 2 * num_challenges;
         let additional = challenger.get_n_challenges(num_additional_challenges);
@@ -190,6 +405,79 @@ where
         vec![]
     };
 
+    (challenger, betas, gammas, deltas)
+}
+
+/// Completes a proof from a [`WitnessCommitment`] of the same circuit.
+pub fn finish_proof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+    commitment: WitnessCommitment<F, C, D>,
+    timing: &mut TimingTree,
+) -> Result<ProofWithPublicInputs<F, C, D>>
+where
+    C::Hasher: Hasher<F>,
+    C::InnerHasher: Hasher<F>,
+{
+    finish_proof_in(
+        prover_data,
+        common_data,
+        commitment,
+        timing,
+        &mut CommitmentArena::new(),
+    )
+}
+
+/// Like [`finish_proof`], but takes the LDE, quotient and Merkle leaf buffers from `arena`, and
+/// returns those of the commitments to it once the proof is complete.
+fn finish_proof_in<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+    commitment: WitnessCommitment<F, C, D>,
+    timing: &mut TimingTree,
+    arena: &mut CommitmentArena<F>,
+) -> Result<ProofWithPublicInputs<F, C, D>>
+where
+    C::Hasher: Hasher<F>,
+    C::InnerHasher: Hasher<F>,
+{
+    let has_lookup = !common_data.luts.is_empty();
+    let config = &common_data.config;
+    let num_challenges = config.num_challenges;
+    let quotient_degree = common_data.quotient_degree();
+    let degree = common_data.degree();
+
+    let WitnessCommitment {
+        public_inputs,
+        betas,
+        gammas,
+        deltas,
+        witness,
+        wires_commitment,
+    } = commitment;
+    ensure!(
+        wires_commitment.polynomials.len() == config.num_wires
+            && witness.wire_values.len() == config.num_wires,
+        "The witness commitment is not for this circuit"
+    );
+    let public_inputs_hash = C::InnerHasher::hash_no_pad(&public_inputs);
+
+    // Replay the transcript up to the permutation challenges, which also checks that the
+    // commitment's challenges were derived from its wires cap.
+    let mut challenger = {
+        let (challenger, expected_betas, expected_gammas, expected_deltas) = wires_challenges(
+            prover_data,
+            common_data,
+            &public_inputs,
+            &wires_commitment.merkle_tree.cap,
+        );
+        ensure!(
+            (&betas, &gammas, &deltas) == (&expected_betas, &expected_gammas, &expected_deltas),
+            "The witness commitment's challenges don't match its wires cap"
+        );
+        challenger
+    };
+
     assert!(
         common_data.quotient_degree_factor < common_data.config.num_routed_wires,
         "When the number of routed wires is smaller that the degree, we should change the logic to avoid computing partial products."
@@ -220,13 +508,15 @@ where
     let partial_products_zs_and_lookup_commitment = timed!(
         timing,
         "commit to partial products, Z's and, if any, lookup polynomials",
-        PolynomialBatch::from_values(
+        PolynomialBatch::from_values_on_coset_in(
             zs_partial_products_lookups,
             config.fri_config.rate_bits,
             config.zero_knowledge && PlonkOracle::ZS_PARTIAL_PRODUCTS.blinding,
             config.fri_config.cap_height,
+            config.fri_config.coset_shift(),
             timing,
             prover_data.fft_root_table.as_ref(),
+            arena,
         )
     );
 
@@ -234,7 +524,7 @@ where
 
     let alphas = challenger.get_n_challenges(num_challenges);
 
-    let quotient_polys = timed!(
+    let mut quotient_polys = timed!(
         timing,
         "compute quotient polys",
         compute_quotient_polys::<F, C, D>(
@@ -247,34 +537,46 @@ where
             &gammas,
             &deltas,
             &alphas,
+            arena,
         )
     );
 
-    let all_quotient_poly_chunks: Vec<PolynomialCoeffs<F>> = timed!(
+    let num_chunks = quotient_degree / degree;
+    let mut all_quotient_poly_chunks = arena.take(quotient_polys.len() * num_chunks);
+    timed!(
         timing,
         "split up quotient polys",
-        quotient_polys
-            .into_par_iter()
-            .flat_map(|mut quotient_poly| {
+        all_quotient_poly_chunks
+            .par_chunks_mut(num_chunks)
+            .zip(quotient_polys.par_iter_mut())
+            .for_each(|(chunks, quotient_poly)| {
                 quotient_poly.trim_to_len(quotient_degree).expect(
                     "Quotient has failed, the vanishing polynomial is not divisible by Z_H",
                 );
                 // Split quotient into degree-n chunks.
-                quotient_poly.chunks(degree)
+                for (chunk, coeffs) in chunks.iter_mut().zip(quotient_poly.coeffs.chunks(degree)) {
+                    chunk.clear();
+                    chunk.extend_from_slice(coeffs);
+                }
             })
-            .collect()
     );
+    arena.put(quotient_polys.into_iter().map(|p| p.coeffs));
 
     let quotient_polys_commitment = timed!(
         timing,
         "commit to quotient polys",
-        PolynomialBatch::<F, C, D>::from_coeffs(
-            all_quotient_poly_chunks,
+        PolynomialBatch::<F, C, D>::from_coeffs_on_coset_in(
+            all_quotient_poly_chunks
+                .into_iter()
+                .map(PolynomialCoeffs::new)
+                .collect(),
             config.fri_config.rate_bits,
             config.zero_knowledge && PlonkOracle::QUOTIENT.blinding,
             config.fri_config.cap_height,
+            config.fri_config.coset_shift(),
             timing,
             prover_data.fft_root_table.as_ref(),
+            arena,
         )
     );
 
@@ -289,6 +591,14 @@ where
         zeta.exp_power_of_2(common_data.degree_bits()) != F::Extension::ONE,
         "Opening point is in the subgroup."
     );
+    // Nor may `zeta` lie in the LDE coset, where FRI divides by `X - zeta`.
+    let coset_shift_inv =
+        F::Extension::from_basefield(config.fri_config.coset_shift::<F>().inverse());
+    ensure!(
+        (zeta * coset_shift_inv).exp_power_of_2(common_data.fri_params.lde_bits())
+            != F::Extension::ONE,
+        "Opening point is in the LDE coset."
+    );
 
     let openings = timed!(
         timing,
@@ -324,12 +634,18 @@ where
     );
 
     let proof = Proof::<F, C, D> {
-        wires_cap: wires_commitment.merkle_tree.cap,
-        plonk_zs_partial_products_cap: partial_products_zs_and_lookup_commitment.merkle_tree.cap,
-        quotient_polys_cap: quotient_polys_commitment.merkle_tree.cap,
+        wires_cap: wires_commitment.merkle_tree.cap.clone(),
+        plonk_zs_partial_products_cap: partial_products_zs_and_lookup_commitment
+            .merkle_tree
+            .cap
+            .clone(),
+        quotient_polys_cap: quotient_polys_commitment.merkle_tree.cap.clone(),
         openings,
         opening_proof,
     };
+    arena.recycle(wires_commitment);
+    arena.recycle(partial_products_zs_and_lookup_commitment);
+    arena.recycle(quotient_polys_commitment);
     Ok(ProofWithPublicInputs::<F, C, D> {
         proof,
         public_inputs,
@@ -384,12 +700,14 @@ fn wires_permutation_partial_products_and_zs<
         .enumerate()
         .map(|(i, &x)| {
             let s_sigmas = &prover_data.sigmas[i];
-            let numerators = (0..common_data.config.num_routed_wires).map(|j| {
-                let wire_value = witness.get_wire(i, j);
-                let k_i = k_is[j];
-                let s_id = k_i * x;
-                wire_value + beta * s_id + gamma
-            });
+            let numerators = (0..common_data.config.num_routed_wires)
+                .map(|j| {
+                    let wire_value = witness.get_wire(i, j);
+                    let k_i = k_is[j];
+                    let s_id = k_i * x;
+                    wire_value + beta * s_id + gamma
+                })
+                .collect::<Vec<_>>();
             let denominators = (0..common_data.config.num_routed_wires)
                 .map(|j| {
                     let wire_value = witness.get_wire(i, j);
@@ -397,11 +715,7 @@ fn wires_permutation_partial_products_and_zs<
                     wire_value + beta * s_sigma + gamma
                 })
                 .collect::<Vec<_>>();
-            let denominator_invs = F::batch_multiplicative_inverse(&denominators);
-            let quotient_values = numerators
-                .zip(denominator_invs)
-                .map(|(num, den_inv)| num * den_inv)
-                .collect::<Vec<_>>();
+            let quotient_values = batch_quotients(&numerators, &denominators);
 
             quotient_chunk_products(&quotient_values, degree)
         })
@@ -596,6 +910,7 @@ fn compute_quotient_polys<
     gammas: &[F],
     deltas: &[F],
     alphas: &[F],
+    arena: &mut CommitmentArena<F>,
 ) -> Vec<PolynomialCoeffs<F>> {
     let num_challenges = common_data.config.num_challenges;
 
@@ -618,7 +933,9 @@ fn compute_quotient_polys<
     let points = F::two_adic_subgroup(common_data.degree_bits() + quotient_degree_bits);
     let lde_size = points.len();
 
-    let z_h_on_coset = ZeroPolyOnCoset::new(common_data.degree_bits(), quotient_degree_bits);
+    let coset_shift = common_data.config.fri_config.coset_shift::<F>();
+    let z_h_on_coset =
+        ZeroPolyOnCoset::with_shift(common_data.degree_bits(), quotient_degree_bits, coset_shift);
 
     // Precompute the lookup table evals on the challenges in delta
     // These values are used to produce the final RE constraints for each lut,
@@ -685,7 +1002,7 @@ fn compute_quotient_polys<
             let mut local_wires_batch_refs = Vec::with_capacity(xs_batch.len());
 
             for (&i, &x) in indices_batch.iter().zip(xs_batch) {
-                let shifted_x = F::coset_shift() * x;
+                let shifted_x = coset_shift * x;
                 let i_next = (i + next_step) % lde_size;
                 let local_constants_sigmas = prover_data
                     .constants_sigmas_commitment
@@ -782,9 +1099,314 @@ fn compute_quotient_polys<
         })
         .collect();
 
-    transpose(&quotient_values)
+    // Transpose the values into one column per challenge, in buffers from the arena.
+    let mut columns = arena.take(num_challenges);
+    columns.par_iter_mut().enumerate().for_each(|(i, column)| {
+        column.clear();
+        column.extend(quotient_values.iter().map(|values| values[i]));
+    });
+    columns
         .into_par_iter()
         .map(PolynomialValues::new)
-        .map(|values| values.coset_ifft(F::coset_shift()))
+        .map(|values| values.coset_ifft(coset_shift))
         .collect()
 }
+
+/// A prover for a fixed circuit, meant to be constructed once and used for many proofs.
+///
+/// It ensures the FFT root table is precomputed (it is absent from prover data which was
+/// serialized without it), and keeps a pool of [`WitnessArena`]s so that repeated witness
+/// generation does not reallocate its buffers. Concurrent calls to [`Self::prove`] each take their
+/// own arena from the pool. Our hashers are stateless permutations, so there is no hasher state to
+/// cache.
+pub struct Prover<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> {
+    pub data: ProverCircuitData<F, C, D>,
+    #[cfg(feature = "std")]
+    arenas: std::sync::Mutex<Vec<WitnessArena<F>>>,
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> Prover<F, C, D> {
+    pub fn new(mut data: ProverCircuitData<F, C, D>) -> Self {
+        if data.prover_only.fft_root_table.is_none() {
+            let common = &data.common;
+            let rate_bits = common.config.fri_config.rate_bits;
+            let quotient_degree_bits = log2_ceil(common.quotient_degree_factor);
+            let max_fft_points = 1 << (common.degree_bits() + max(rate_bits, quotient_degree_bits));
+            data.prover_only.fft_root_table = Some(fft_root_table(max_fft_points));
+        }
+        Self {
+            data,
+            #[cfg(feature = "std")]
+            arenas: std::sync::Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn prove(&self, inputs: PartialWitness<F>) -> Result<ProofWithPublicInputs<F, C, D>> {
+        self.prove_with_timing(inputs, &mut TimingTree::default())
+    }
+
+    pub fn prove_with_timing(
+        &self,
+        inputs: PartialWitness<F>,
+        timing: &mut TimingTree,
+    ) -> Result<ProofWithPublicInputs<F, C, D>> {
+        let mut arena = self.take_arena();
+        let proof = prove_with_arena(
+            &self.data.prover_only,
+            &self.data.common,
+            inputs,
+            timing,
+            &mut arena,
+        );
+        self.return_arena(arena);
+        proof
+    }
+
+    fn take_arena(&self) -> WitnessArena<F> {
+        #[cfg(feature = "std")]
+        if let Some(arena) = self
+            .arenas
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .pop()
+        {
+            return arena;
+        }
+        WitnessArena::new()
+    }
+
+    fn return_arena(&self, arena: WitnessArena<F>) {
+        #[cfg(feature = "std")]
+        self.arenas
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .push(arena);
+        #[cfg(not(feature = "std"))]
+        drop(arena);
+    }
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    From<CircuitData<F, C, D>> for Prover<F, C, D>
+{
+    fn from(data: CircuitData<F, C, D>) -> Self {
+        Self::new(data.prover_data())
+    }
+}
+
+/// A session proving several circuits, possibly interleaved, with a single [`WitnessArena`].
+///
+/// The arena's buffers, for witness generation as well as for the LDEs, quotient polynomials and
+/// Merkle leaves of the commitments, grow to fit the largest circuit proven so far and are then
+/// reused by every circuit, instead of each circuit keeping (or reallocating) its own. Circuits can
+/// be registered with [`Self::add_circuit`] up front, so that the witness buffers are allocated
+/// once, at their final size, before the first proof; the commitment buffers reach theirs after
+/// the first proof of the largest circuit.
+#[derive(Debug, Default)]
+pub struct ProvingSession<F: Field> {
+    arena: WitnessArena<F>,
+    /// The number of distinct witness values of each circuit seen so far, by circuit digest.
+    witness_sizes: BTreeMap<Vec<u8>, usize>,
+}
+
+impl<F: RichField> ProvingSession<F> {
+    pub fn new() -> Self {
+        Self::with_arena(WitnessArena::new())
+    }
+
+    /// A session using `arena`, e.g. one created with [`WitnessArena::with_gate_cache`].
+    pub fn with_arena(arena: WitnessArena<F>) -> Self {
+        Self {
+            arena,
+            witness_sizes: BTreeMap::new(),
+        }
+    }
+
+    /// Records the size of a circuit's witness and grows the shared buffers to fit it.
+    pub fn add_circuit<C: GenericConfig<D, F = F>, const D: usize>(
+        &mut self,
+        prover_data: &ProverOnlyCircuitData<F, C, D>,
+    ) where
+        F: Extendable<D>,
+    {
+        let num_values = prover_data.representative_map.len();
+        self.witness_sizes
+            .insert(prover_data.circuit_digest.to_bytes(), num_values);
+        self.arena.reserve(self.max_witness_size());
+    }
+
+    /// The number of distinct witness values of the circuit with the given digest, if it was
+    /// added to or proven in this session.
+    pub fn witness_size<C: GenericConfig<D, F = F>, const D: usize>(
+        &self,
+        circuit_digest: &<C::Hasher as Hasher<F>>::Hash,
+    ) -> Option<usize>
+    where
+        F: Extendable<D>,
+    {
+        self.witness_sizes.get(&circuit_digest.to_bytes()).copied()
+    }
+
+    /// The size of the largest witness of this session, which the shared buffers are sized for.
+    pub fn max_witness_size(&self) -> usize {
+        self.witness_sizes.values().copied().max().unwrap_or(0)
+    }
+
+    pub fn prove<C: GenericConfig<D, F = F>, const D: usize>(
+        &mut self,
+        prover_data: &ProverOnlyCircuitData<F, C, D>,
+        common_data: &CommonCircuitData<F, D>,
+        inputs: PartialWitness<F>,
+        timing: &mut TimingTree,
+    ) -> Result<ProofWithPublicInputs<F, C, D>>
+    where
+        F: Extendable<D>,
+    {
+        self.add_circuit(prover_data);
+        prove_with_arena(prover_data, common_data, inputs, timing, &mut self.arena)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{PoseidonGoldilocksConfig, PoseidonGoldilocksQuinticConfig};
+
+    #[test]
+    fn test_reusable_prover() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let x_cubed = builder.exp_u64(x, 3);
+        builder.register_public_input(x_cubed);
+        let data = builder.build::<C>();
+        let verifier_data = data.verifier_data();
+
+        let mut prover_data = data.prover_data();
+        prover_data.prover_only.fft_root_table = None;
+        let prover = Prover::new(prover_data);
+        for i in 0..3 {
+            let mut pw = PartialWitness::new();
+            pw.set_target(x, F::from_canonical_u64(i));
+            let proof = prover.prove(pw)?;
+            assert_eq!(proof.public_inputs, vec![F::from_canonical_u64(i * i * i)]);
+            verifier_data.verify(proof)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_proving_session() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let circuit = |num_squarings: usize| {
+            let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+            let x = builder.add_virtual_target();
+            let y = (0..num_squarings).fold(x, |y, _| builder.square(y));
+            builder.register_public_input(y);
+            (x, builder.build::<C>())
+        };
+        let (small_x, small) = circuit(1);
+        let (large_x, large) = circuit(1000);
+
+        let mut session = ProvingSession::new();
+        session.add_circuit(&large.prover_only);
+        let large_size = large.prover_only.representative_map.len();
+        assert_eq!(session.max_witness_size(), large_size);
+
+        let timing = &mut TimingTree::default();
+        let mut num_commitment_buffers = vec![];
+        for i in 0..2 {
+            for (x, data) in [(small_x, &small), (large_x, &large)] {
+                let mut pw = PartialWitness::new();
+                pw.set_target(x, F::from_canonical_u64(i + 2));
+                let proof = session.prove(&data.prover_only, &data.common, pw, timing)?;
+                data.verify(proof)?;
+                num_commitment_buffers.push(session.arena.commitments().num_buffers());
+            }
+        }
+        // The commitment buffers are returned after each proof and reused by the next, so their
+        // number levels off instead of growing with the number of proofs.
+        assert!(num_commitment_buffers[0] > 0);
+        assert_eq!(num_commitment_buffers[1], num_commitment_buffers[3]);
+        assert_eq!(
+            session.witness_size::<C, D>(&small.verifier_only.circuit_digest),
+            Some(small.prover_only.representative_map.len())
+        );
+        assert_eq!(session.max_witness_size(), large_size);
+        Ok(())
+    }
+
+    #[test]
+    fn test_commit_witness_then_finish_proof() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let x_cubed = builder.exp_u64(x, 3);
+        builder.register_public_input(x_cubed);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(2));
+        let timing = &mut TimingTree::default();
+        let commitment = commit_witness(&data.prover_only, &data.common, pw, timing)?;
+        let wires_cap = commitment.wires_cap().clone();
+        assert_eq!(commitment.public_inputs, vec![F::from_canonical_u64(8)]);
+
+        // The state survives a round trip, e.g. while waiting for the interactive step.
+        let bytes = commitment.to_bytes();
+        let commitment = WitnessCommitment::<F, C, D>::from_bytes(bytes.clone())?;
+        assert_eq!(commitment.to_bytes(), bytes);
+        let mut tampered = WitnessCommitment::<F, C, D>::from_bytes(bytes)?;
+        tampered.betas[0] += F::ONE;
+        assert!(finish_proof(&data.prover_only, &data.common, tampered, timing).is_err());
+
+        let proof = finish_proof(&data.prover_only, &data.common, commitment, timing)?;
+        assert_eq!(proof.proof.wires_cap, wires_cap);
+        data.verify(proof)
+    }
+
+    #[test]
+    fn test_quintic_extension() -> Result<()> {
+        const D: usize = 5;
+        type C = PoseidonGoldilocksQuinticConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_quintic_config());
+        let x = builder.add_virtual_target();
+        let x_ext = builder.convert_to_ext(x);
+        let x_ext_inv = builder.inverse_extension(x_ext);
+        let one = builder.mul_extension(x_ext, x_ext_inv);
+        builder.assert_one(one.0[0]);
+        let x_cubed = builder.exp_u64(x, 3);
+        builder.register_public_input(x_cubed);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(5));
+        let proof = data.prove(pw)?;
+        assert_eq!(proof.public_inputs, vec![F::from_canonical_u64(125)]);
+        data.verify(proof)
+    }
+
+    #[test]
+    #[should_panic(expected = "FRI params fall short of target security")]
+    fn test_quintic_config_needs_quintic_extension() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        CircuitBuilder::<F, D>::new(CircuitConfig::standard_quintic_config());
+    }
+}
@@ -0,0 +1,254 @@
+//! A standard layout for the first public inputs of a circuit, so that circuits developed
+//! independently can be aggregated without agreeing on where each one keeps its data.
+//!
+//! The layout is
+//! - the layout version, [`PUBLIC_INPUT_ABI_VERSION`] (1),
+//! - the circuit's own digest (4),
+//! - a commitment to the circuit's inputs (4),
+//! - a commitment to the circuit's outputs (4),
+//!
+//! after which a circuit may register any other public inputs. Commitments are hashes of the
+//! committed values with the config's hasher, see [`abi_commitment`].
+//!
+//! A circuit can't compute its own digest, so the digest is a witness which the prover sets with
+//! [`set_standard_public_inputs_target`]. Verifiers must check it against the verifier data they
+//! verified the proof with: [`StandardPublicInputs::check`] does so outside of circuits, and
+//! [`CircuitBuilder::check_standard_public_inputs`] within them.
+
+use anyhow::{ensure, Result};
+
+use crate::field::extension::Extendable;
+use crate::hash::hash_types::{HashOut, HashOutTarget, RichField, NUM_HASH_OUT_ELTS};
+use crate::iop::target::Target;
+use crate::iop::witness::WitnessWrite;
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::{VerifierCircuitTarget, VerifierOnlyCircuitData};
+use crate::plonk::config::{AlgebraicHasher, GenericConfig, Hasher};
+
+/// The version of the layout, which is the first public input of circuits using it.
+pub const PUBLIC_INPUT_ABI_VERSION: u64 = 1;
+
+/// The number of public inputs the layout takes up.
+pub const PUBLIC_INPUT_ABI_LEN: usize = 1 + 3 * NUM_HASH_OUT_ELTS;
+
+/// The commitment to `values`, as exposed by circuits using the layout.
+pub fn abi_commitment<F: RichField, H: Hasher<F, Hash = HashOut<F>>>(values: &[F]) -> HashOut<F> {
+    H::hash_no_pad(values)
+}
+
+/// The standard public inputs of a proof.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct StandardPublicInputs<F: RichField> {
+    pub version: u64,
+    pub circuit_digest: HashOut<F>,
+    pub input_commitment: HashOut<F>,
+    pub output_commitment: HashOut<F>,
+}
+
+impl<F: RichField> StandardPublicInputs<F> {
+    /// Reads the standard public inputs from the start of `public_inputs`. Fails if there aren't
+    /// enough public inputs, or if they use a different version of the layout.
+    pub fn from_public_inputs(public_inputs: &[F]) -> Result<Self> {
+        ensure!(
+            public_inputs.len() >= PUBLIC_INPUT_ABI_LEN,
+            "Expected at least {} public inputs, got {}",
+            PUBLIC_INPUT_ABI_LEN,
+            public_inputs.len()
+        );
+        let version = public_inputs[0].to_canonical_u64();
+        ensure!(
+            version == PUBLIC_INPUT_ABI_VERSION,
+            "Unsupported public input layout version {}",
+            version
+        );
+        let hash = |i: usize| {
+            let start = 1 + i * NUM_HASH_OUT_ELTS;
+            HashOut::from_partial(&public_inputs[start..start + NUM_HASH_OUT_ELTS])
+        };
+        Ok(Self {
+            version,
+            circuit_digest: hash(0),
+            input_commitment: hash(1),
+            output_commitment: hash(2),
+        })
+    }
+
+    /// Checks that the proof claims to be of the circuit described by `verifier_data`.
+    pub fn check<C: GenericConfig<D, F = F>, const D: usize>(
+        &self,
+        verifier_data: &VerifierOnlyCircuitData<C, D>,
+    ) -> Result<()>
+    where
+        C::Hasher: Hasher<F, Hash = HashOut<F>>,
+    {
+        ensure!(
+            self.circuit_digest == verifier_data.circuit_digest,
+            "The proof's circuit digest doesn't match the verifier data"
+        );
+        Ok(())
+    }
+}
+
+/// The standard public inputs of a circuit, or of a proof verified within a circuit.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct StandardPublicInputsTarget {
+    pub version: Target,
+    pub circuit_digest: HashOutTarget,
+    pub input_commitment: HashOutTarget,
+    pub output_commitment: HashOutTarget,
+}
+
+impl StandardPublicInputsTarget {
+    /// Reads the standard public inputs from the start of `public_inputs`, e.g. those of a proof
+    /// target.
+    pub fn from_public_inputs(public_inputs: &[Target]) -> Result<Self> {
+        ensure!(
+            public_inputs.len() >= PUBLIC_INPUT_ABI_LEN,
+            "Expected at least {} public inputs, got {}",
+            PUBLIC_INPUT_ABI_LEN,
+            public_inputs.len()
+        );
+        let hash = |i: usize| {
+            let start = 1 + i * NUM_HASH_OUT_ELTS;
+            HashOutTarget::try_from(&public_inputs[start..start + NUM_HASH_OUT_ELTS]).unwrap()
+        };
+        Ok(Self {
+            version: public_inputs[0],
+            circuit_digest: hash(0),
+            input_commitment: hash(1),
+            output_commitment: hash(2),
+        })
+    }
+}
+
+/// Sets the digest registered by [`CircuitBuilder::register_standard_public_inputs`] to that of
+/// the circuit being proven.
+pub fn set_standard_public_inputs_target<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    W: WitnessWrite<F>,
+    const D: usize,
+>(
+    witness: &mut W,
+    target: &StandardPublicInputsTarget,
+    verifier_data: &VerifierOnlyCircuitData<C, D>,
+) where
+    C::Hasher: Hasher<F, Hash = HashOut<F>>,
+{
+    witness.set_hash_target(target.circuit_digest, verifier_data.circuit_digest);
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
+    /// Registers the standard public inputs, committing to `inputs` and `outputs` with `H`. They
+    /// must be the first public inputs of the circuit.
+    pub fn register_standard_public_inputs<H: AlgebraicHasher<F>>(
+        &mut self,
+        inputs: &[Target],
+        outputs: &[Target],
+    ) -> StandardPublicInputsTarget {
+        let input_commitment = self.hash_n_to_hash_no_pad::<H>(inputs.to_vec());
+        let output_commitment = self.hash_n_to_hash_no_pad::<H>(outputs.to_vec());
+        self.register_standard_public_input_commitments(input_commitment, output_commitment)
+    }
+
+    /// Like [`Self::register_standard_public_inputs`], but takes the commitments, e.g. to forward
+    /// those of a verified proof.
+    pub fn register_standard_public_input_commitments(
+        &mut self,
+        input_commitment: HashOutTarget,
+        output_commitment: HashOutTarget,
+    ) -> StandardPublicInputsTarget {
+        assert_eq!(
+            self.num_public_inputs(),
+            0,
+            "The standard public inputs must come first"
+        );
+        let target = StandardPublicInputsTarget {
+            version: self.constant(F::from_canonical_u64(PUBLIC_INPUT_ABI_VERSION)),
+            circuit_digest: self.add_virtual_hash(),
+            input_commitment,
+            output_commitment,
+        };
+        self.register_public_input(target.version);
+        self.register_public_inputs(&target.circuit_digest.elements);
+        self.register_public_inputs(&target.input_commitment.elements);
+        self.register_public_inputs(&target.output_commitment.elements);
+        target
+    }
+
+    /// Checks that the standard public inputs of a proof verified with `verifier_data` use this
+    /// version of the layout, and claim to be of the circuit described by `verifier_data`.
+    pub fn check_standard_public_inputs(
+        &mut self,
+        public_inputs: &StandardPublicInputsTarget,
+        verifier_data: &VerifierCircuitTarget,
+    ) {
+        let version = self.constant(F::from_canonical_u64(PUBLIC_INPUT_ABI_VERSION));
+        self.connect(public_inputs.version, version);
+        self.connect_hashes(public_inputs.circuit_digest, verifier_data.circuit_digest);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::types::Field;
+    use crate::iop::witness::PartialWitness;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    #[test]
+    fn test_public_input_abi() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type H = <C as GenericConfig<D>>::Hasher;
+
+        let config = CircuitConfig::standard_recursion_config();
+
+        // A leaf squaring its input, with one public input of its own after the standard ones.
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+        let x = builder.add_virtual_target();
+        let x_squared = builder.square(x);
+        let leaf_pis = builder.register_standard_public_inputs::<H>(&[x], &[x_squared]);
+        builder.register_public_input(x);
+        let leaf = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(3));
+        set_standard_public_inputs_target(&mut pw, &leaf_pis, &leaf.verifier_only);
+        let leaf_proof = leaf.prove(pw)?;
+        let pis = StandardPublicInputs::from_public_inputs(&leaf_proof.public_inputs)?;
+        pis.check(&leaf.verifier_only)?;
+        let input_commitment = abi_commitment::<F, H>(&[F::from_canonical_u64(3)]);
+        let output_commitment = abi_commitment::<F, H>(&[F::from_canonical_u64(9)]);
+        assert_eq!(pis.input_commitment, input_commitment);
+        assert_eq!(pis.output_commitment, output_commitment);
+
+        // An aggregator which only relies on the layout, forwarding the leaf's commitments.
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let proof = builder.add_virtual_proof_with_pis(&leaf.common);
+        let leaf_vd = builder.constant_verifier_data(&leaf.verifier_only);
+        builder.verify_proof::<C>(&proof, &leaf_vd, &leaf.common);
+        let child_pis = StandardPublicInputsTarget::from_public_inputs(&proof.public_inputs)?;
+        builder.check_standard_public_inputs(&child_pis, &leaf_vd);
+        let agg_pis = builder.register_standard_public_input_commitments(
+            child_pis.input_commitment,
+            child_pis.output_commitment,
+        );
+        let agg = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_proof_with_pis_target(&proof, &leaf_proof);
+        set_standard_public_inputs_target(&mut pw, &agg_pis, &agg.verifier_only);
+        let agg_proof = agg.prove(pw)?;
+        let pis = StandardPublicInputs::from_public_inputs(&agg_proof.public_inputs)?;
+        pis.check(&agg.verifier_only)?;
+        assert!(pis.check(&leaf.verifier_only).is_err());
+        assert_eq!(pis.output_commitment, output_commitment);
+        assert!(
+            StandardPublicInputs::<F>::from_public_inputs(&agg_proof.public_inputs[1..]).is_err()
+        );
+        agg.verify(agg_proof)
+    }
+}
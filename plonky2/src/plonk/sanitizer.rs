@@ -0,0 +1,209 @@
+//! Checks of a full witness against every gate and copy constraint of its circuit, which report the
+//! row, gate and constraint of each failure. A bad witness otherwise only shows up as a vanishing
+//! polynomial which isn't divisible, after all the FFTs. With the `sanitize` feature, the prover
+//! runs [`check_witness`] before committing to the wires.
+//!
+//! Lookups are enforced by the lookup argument rather than by gate constraints, so they aren't
+//! checked here.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use anyhow::{anyhow, Result};
+use hashbrown::HashMap;
+use plonky2_maybe_rayon::*;
+
+use crate::field::extension::{Extendable, FieldExtension};
+use crate::field::types::Field;
+use crate::hash::hash_types::RichField;
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::MatrixWitness;
+use crate::plonk::circuit_data::{CommonCircuitData, ProverOnlyCircuitData};
+use crate::plonk::config::{GenericConfig, Hasher};
+use crate::plonk::vars::EvaluationVars;
+
+/// At most this many failures are listed in errors.
+const MAX_LISTED_FAILURES: usize = 10;
+
+/// Checks that `witness` satisfies every gate and copy constraint of the circuit, with
+/// `public_inputs` as its public inputs.
+pub fn check_witness<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    witness: &MatrixWitness<F>,
+    public_inputs: &[F],
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+) -> Result<()> {
+    let mut failures = copy_constraint_failures(witness, prover_data, common_data);
+    failures.extend(gate_constraint_failures(
+        witness,
+        public_inputs,
+        prover_data,
+        common_data,
+    ));
+    if failures.is_empty() {
+        return Ok(());
+    }
+
+    let mut message = format!("The witness fails {} constraints:", failures.len());
+    for failure in failures.iter().take(MAX_LISTED_FAILURES) {
+        message.push_str("\n    ");
+        message.push_str(failure);
+    }
+    if failures.len() > MAX_LISTED_FAILURES {
+        message.push_str(&format!(
+            "\n    and {} more",
+            failures.len() - MAX_LISTED_FAILURES
+        ));
+    }
+    Err(anyhow!(message))
+}
+
+/// Compares each routed wire with the first wire of its partition.
+fn copy_constraint_failures<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    witness: &MatrixWitness<F>,
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+) -> Vec<String> {
+    let config = &common_data.config;
+    let degree = common_data.degree();
+    let mut first_copies = HashMap::<usize, Wire>::new();
+    let mut failures = Vec::new();
+    for row in 0..degree {
+        for column in 0..config.num_routed_wires {
+            let index = Target::wire(row, column).index(config.num_wires, degree);
+            let rep = prover_data.representative_map[index];
+            let first = *first_copies.entry(rep).or_insert(Wire { row, column });
+            let value = witness.get_wire(row, column);
+            let first_value = witness.get_wire(first.row, first.column);
+            if value != first_value {
+                failures.push(format!(
+                    "Copy constraint: wire ({row}, {column}) is {value}, but its copy ({}, {}){} \
+                     is {first_value}",
+                    first.row,
+                    first.column,
+                    prover_data
+                        .target_names
+                        .get(&rep)
+                        .map_or(String::new(), |name| format!(" ({})", name)),
+                ));
+            }
+        }
+    }
+    failures
+}
+
+/// Evaluates the constraints of the gate on each row.
+fn gate_constraint_failures<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    witness: &MatrixWitness<F>,
+    public_inputs: &[F],
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+) -> Vec<String> {
+    let selectors_info = &common_data.selectors_info;
+    let constants = prover_data.constants_sigmas_commitment.polynomials
+        [..common_data.num_constants]
+        .par_iter()
+        .map(|p| p.clone().fft())
+        .collect::<Vec<_>>();
+    let first_constant = selectors_info.num_selectors() + common_data.num_lookup_selectors;
+    let public_inputs_hash = C::InnerHasher::hash_no_pad(public_inputs);
+
+    let failures_by_row = (0..common_data.degree())
+        .into_par_iter()
+        .map(|row| {
+            let gate = &common_data.gates[selectors_info.gate_index(&constants, row)].0;
+            let local_constants = constants[first_constant..]
+                .iter()
+                .map(|c| F::Extension::from_basefield(c.values[row]))
+                .collect::<Vec<_>>();
+            let local_wires = (0..common_data.config.num_wires)
+                .map(|column| F::Extension::from_basefield(witness.get_wire(row, column)))
+                .collect::<Vec<_>>();
+            let vars = EvaluationVars {
+                local_constants: &local_constants,
+                local_wires: &local_wires,
+                public_inputs_hash: &public_inputs_hash,
+            };
+            gate.eval_unfiltered(vars)
+                .into_iter()
+                .enumerate()
+                .filter(|(_, value)| !value.is_zero())
+                .map(|(i, value)| format!("Row {row}: constraint {i} of {} is {value}", gate.id()))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    failures_by_row.into_iter().flatten().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::iop::generator::generate_partial_witness;
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+    use crate::plonk::inspector::CircuitInspector;
+
+    #[test]
+    fn test_check_witness() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let zero = builder.zero();
+        let y = builder.arithmetic(F::from_canonical_u32(3), F::ZERO, x, x, zero);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::TWO);
+        let witness = generate_partial_witness(pw, &data.prover_only, &data.common).full_witness();
+        let public_inputs = [F::from_canonical_u32(12)];
+        check_witness(&witness, &public_inputs, &data.prover_only, &data.common)?;
+
+        // Public inputs other than the witness's fail the public input gate.
+        let err = check_witness(&witness, &[F::ONE], &data.prover_only, &data.common)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("PublicInputGate"), "{err}");
+
+        // Changing the output of the arithmetic gate fails both the gate and the copy constraints
+        // of `y`, its copies.
+        let inspector = CircuitInspector::new(&data);
+        let wire = inspector
+            .target(y)?
+            .wires
+            .into_iter()
+            .find(|w| {
+                inspector
+                    .row(w.row)
+                    .unwrap()
+                    .gate_id
+                    .starts_with("Arithmetic")
+            })
+            .unwrap();
+        let mut tampered = witness;
+        tampered.wire_values[wire.column][wire.row] += F::ONE;
+        let err = check_witness(&tampered, &public_inputs, &data.prover_only, &data.common)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains(&format!("Row {}: constraint", wire.row)),
+            "{err}"
+        );
+        assert!(err.contains("Copy constraint"), "{err}");
+        Ok(())
+    }
+}
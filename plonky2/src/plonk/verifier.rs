@@ -1,12 +1,13 @@
-use anyhow::{ensure, Result};
+use anyhow::{anyhow, Result};
+use plonky2_maybe_rayon::*;
 
 use crate::field::extension::Extendable;
 use crate::field::types::Field;
 use crate::fri::verifier::verify_fri_proof;
 use crate::hash::hash_types::RichField;
-use crate::plonk::circuit_data::{CommonCircuitData, VerifierOnlyCircuitData};
+use crate::plonk::circuit_data::{CommonCircuitData, FriInstanceLayout, VerifierOnlyCircuitData};
 use crate::plonk::config::{GenericConfig, Hasher};
-use crate::plonk::plonk_common::reduce_with_powers;
+use crate::plonk::plonk_common::check_quotient_openings;
 use crate::plonk::proof::{Proof, ProofChallenges, ProofWithPublicInputs};
 use crate::plonk::validate_shape::validate_proof_with_pis_shape;
 use crate::plonk::vanishing_poly::eval_vanishing_poly;
@@ -17,7 +18,43 @@ pub(crate) fn verify<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, c
     verifier_data: &VerifierOnlyCircuitData<C, D>,
     common_data: &CommonCircuitData<F, D>,
 ) -> Result<()> {
-    validate_proof_with_pis_shape(&proof_with_pis, common_data)?;
+    verify_with_layout::<F, C, D>(
+        &proof_with_pis,
+        verifier_data,
+        common_data,
+        &common_data.fri_instance_layout(),
+    )
+}
+
+/// Verifies several proofs of the same circuit, in parallel when the `parallel` feature is enabled.
+/// Fails if any of them is invalid. The parts of the FRI instance which don't depend on the proof
+/// are only computed once.
+pub(crate) fn verify_batch<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    proofs_with_pis: &[ProofWithPublicInputs<F, C, D>],
+    verifier_data: &VerifierOnlyCircuitData<C, D>,
+    common_data: &CommonCircuitData<F, D>,
+) -> Result<()> {
+    let fri_layout = common_data.fri_instance_layout();
+    proofs_with_pis
+        .par_iter()
+        .enumerate()
+        .try_for_each(|(i, proof_with_pis)| {
+            verify_with_layout::<F, C, D>(proof_with_pis, verifier_data, common_data, &fri_layout)
+                .map_err(|e| anyhow!("Proof {} of the batch is invalid: {}", i, e))
+        })
+}
+
+fn verify_with_layout<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    proof_with_pis: &ProofWithPublicInputs<F, C, D>,
+    verifier_data: &VerifierOnlyCircuitData<C, D>,
+    common_data: &CommonCircuitData<F, D>,
+    fri_layout: &FriInstanceLayout<F, D>,
+) -> Result<()> {
+    validate_proof_with_pis_shape(proof_with_pis, common_data)?;
 
     let public_inputs_hash = proof_with_pis.get_public_inputs_hash();
     let challenges = proof_with_pis.get_challenges(
@@ -26,12 +63,13 @@ pub(crate) fn verify<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, c
         common_data,
     )?;
 
-    verify_with_challenges::<F, C, D>(
-        proof_with_pis.proof,
+    verify_proof::<F, C, D>(
+        &proof_with_pis.proof,
         public_inputs_hash,
         challenges,
         verifier_data,
         common_data,
+        fri_layout,
     )
 }
 
@@ -45,6 +83,24 @@ pub(crate) fn verify_with_challenges<
     challenges: ProofChallenges<F, D>,
     verifier_data: &VerifierOnlyCircuitData<C, D>,
     common_data: &CommonCircuitData<F, D>,
+) -> Result<()> {
+    verify_proof::<F, C, D>(
+        &proof,
+        public_inputs_hash,
+        challenges,
+        verifier_data,
+        common_data,
+        &common_data.fri_instance_layout(),
+    )
+}
+
+fn verify_proof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    proof: &Proof<F, C, D>,
+    public_inputs_hash: <<C as GenericConfig<D>>::InnerHasher as Hasher<F>>::Hash,
+    challenges: ProofChallenges<F, D>,
+    verifier_data: &VerifierOnlyCircuitData<C, D>,
+    common_data: &CommonCircuitData<F, D>,
+    fri_layout: &FriInstanceLayout<F, D>,
 ) -> Result<()> {
     let local_constants = &proof.openings.constants;
     let local_wires = &proof.openings.wires;
@@ -78,33 +134,26 @@ pub(crate) fn verify_with_challenges<
     );
 
     // Check each polynomial identity, of the form `vanishing(x) = Z_H(x) quotient(x)`, at zeta.
-    let quotient_polys_zeta = &proof.openings.quotient_polys;
     let zeta_pow_deg = challenges
         .plonk_zeta
         .exp_power_of_2(common_data.degree_bits());
-    let z_h_zeta = zeta_pow_deg - F::Extension::ONE;
-    // `quotient_polys_zeta` holds `num_challenges * quotient_degree_factor` evaluations.
-    // Each chunk of `quotient_degree_factor` holds the evaluations of `t_0(zeta),...,t_{quotient_degree_factor-1}(zeta)`
-    // where the "real" quotient polynomial is `t(X) = t_0(X) + t_1(X)*X^n + t_2(X)*X^{2n} + ...`.
-    // So to reconstruct `t(zeta)` we can compute `reduce_with_powers(chunk, zeta^n)` for each
-    // `quotient_degree_factor`-sized chunk of the original evaluations.
-    for (i, chunk) in quotient_polys_zeta
-        .chunks(common_data.quotient_degree_factor)
-        .enumerate()
-    {
-        ensure!(vanishing_polys_zeta[i] == z_h_zeta * reduce_with_powers(chunk, zeta_pow_deg));
-    }
+    check_quotient_openings(
+        &vanishing_polys_zeta,
+        &proof.openings.quotient_polys,
+        common_data.quotient_degree_factor,
+        zeta_pow_deg,
+    )?;
 
     let merkle_caps = &[
         verifier_data.constants_sigmas_cap.clone(),
-        proof.wires_cap,
+        proof.wires_cap.clone(),
         // In the lookup case, `plonk_zs_partial_products_cap` should also include the lookup commitment.
-        proof.plonk_zs_partial_products_cap,
-        proof.quotient_polys_cap,
+        proof.plonk_zs_partial_products_cap.clone(),
+        proof.quotient_polys_cap.clone(),
     ];
 
     verify_fri_proof::<F, C, D>(
-        &common_data.get_fri_instance(challenges.plonk_zeta),
+        &fri_layout.instance(challenges.plonk_zeta),
         &proof.openings.to_fri_openings(),
         &challenges.fri_challenges,
         merkle_caps,
@@ -114,3 +163,45 @@ pub(crate) fn verify_with_challenges<
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use anyhow::Result;
+
+    use super::*;
+    use crate::field::types::Sample;
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    #[test]
+    fn test_verify_batch() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let y = builder.cube(x);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+
+        let mut proofs = (0..4)
+            .map(|_| {
+                let mut pw = PartialWitness::new();
+                pw.set_target(x, F::rand());
+                data.prove(pw)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        data.verify_batch(&proofs)?;
+        data.verifier_data().verify_batch(&proofs)?;
+        data.verify_batch(&[])?;
+
+        proofs[2].public_inputs[0] += F::ONE;
+        assert!(data.verify_batch(&proofs).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,357 @@
+//! Primitives for distributing the proving of an aggregation tree across workers.
+//!
+//! An orchestrator describes each node of the tree as a [`ProvingTask`], which is plain data and
+//! can be serialized and shipped to any worker. A worker holds the circuits it may be asked to
+//! prove, and calls [`run_task`] on each task it receives; the resulting [`TaskOutput`]s are sent
+//! back and fed into the parent task via [`ProvingTask::aggregation`]. Workers keep no state
+//! between tasks, so tasks may be retried or reassigned freely.
+
+use alloc::vec::Vec;
+
+use anyhow::{anyhow, ensure, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::field::extension::Extendable;
+use crate::hash::hash_types::RichField;
+use crate::iop::witness::{PartialWitness, WitnessWrite};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::{
+    CircuitConfig, CircuitData, CommonCircuitData, VerifierCircuitData, VerifierOnlyCircuitData,
+};
+use crate::plonk::config::{AlgebraicHasher, GenericConfig, GenericHashOut};
+use crate::plonk::proof::{ProofWithPublicInputs, ProofWithPublicInputsTarget};
+use crate::util::serialization::{Buffer, Read, Remaining, Write};
+
+/// Identifies a node within an aggregation tree. Ids only need to be unique within one tree.
+pub type TaskId = u64;
+
+/// Identifies a circuit by the bytes of its circuit digest.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct CircuitId(pub Vec<u8>);
+
+impl CircuitId {
+    pub fn of<C: GenericConfig<D>, const D: usize>(
+        verifier_only: &VerifierOnlyCircuitData<C, D>,
+    ) -> Self {
+        Self(verifier_only.circuit_digest.to_bytes())
+    }
+}
+
+/// The input of a proving task.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub enum TaskInput {
+    /// A leaf task, holding a partial witness encoded with [`encode_witness`].
+    Witness(Vec<u8>),
+    /// An aggregation task, holding the serialized proofs of its children, in order.
+    ChildProofs(Vec<Vec<u8>>),
+}
+
+/// A self-contained description of a node of an aggregation tree.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct ProvingTask {
+    pub id: TaskId,
+    /// The circuit which should be proven.
+    pub circuit_id: CircuitId,
+    /// The task which consumes this task's proof, or `None` for the root.
+    pub parent: Option<TaskId>,
+    pub input: TaskInput,
+}
+
+impl ProvingTask {
+    pub fn leaf<F: RichField>(
+        id: TaskId,
+        circuit_id: CircuitId,
+        parent: Option<TaskId>,
+        witness: &PartialWitness<F>,
+    ) -> Self {
+        Self {
+            id,
+            circuit_id,
+            parent,
+            input: TaskInput::Witness(encode_witness(witness)),
+        }
+    }
+
+    /// Creates the task aggregating the given child outputs, which must all point to `id` as
+    /// their parent.
+    pub fn aggregation(
+        id: TaskId,
+        circuit_id: CircuitId,
+        parent: Option<TaskId>,
+        children: &[TaskOutput],
+    ) -> Result<Self> {
+        for child in children {
+            ensure!(
+                child.parent == Some(id),
+                "Task {} has parent {:?}, not {}",
+                child.id,
+                child.parent,
+                id
+            );
+        }
+        Ok(Self {
+            id,
+            circuit_id,
+            parent,
+            input: TaskInput::ChildProofs(children.iter().map(|c| c.proof.clone()).collect()),
+        })
+    }
+}
+
+/// The result of running a [`ProvingTask`].
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct TaskOutput {
+    pub id: TaskId,
+    pub parent: Option<TaskId>,
+    pub circuit_id: CircuitId,
+    /// The proof, serialized with [`ProofWithPublicInputs::to_bytes`].
+    pub proof: Vec<u8>,
+}
+
+/// Encodes the values assigned in a partial witness.
+pub fn encode_witness<F: RichField>(witness: &PartialWitness<F>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes
+        .write_usize(witness.target_values.len())
+        .expect("Writing to a byte-vector cannot fail.");
+    for (&target, &value) in witness.target_values.iter() {
+        bytes
+            .write_target(target)
+            .expect("Writing to a byte-vector cannot fail.");
+        bytes
+            .write_field(value)
+            .expect("Writing to a byte-vector cannot fail.");
+    }
+    bytes
+}
+
+pub fn decode_witness<F: RichField>(bytes: &[u8]) -> Result<PartialWitness<F>> {
+    let mut buffer = Buffer::new(bytes);
+    let mut witness = PartialWitness::new();
+    let len = buffer
+        .read_usize()
+        .map_err(|_| anyhow!("Invalid witness encoding"))?;
+    for _ in 0..len {
+        let target = buffer
+            .read_target()
+            .map_err(|_| anyhow!("Invalid witness encoding"))?;
+        let value = buffer
+            .read_field()
+            .map_err(|_| anyhow!("Invalid witness encoding"))?;
+        witness.try_set_target(target, value)?;
+    }
+    ensure!(buffer.is_empty(), "Trailing bytes in witness encoding");
+    Ok(witness)
+}
+
+/// A circuit which verifies a fixed number of proofs of the same child circuit. Its public inputs
+/// are a hash of the concatenated public inputs of its children.
+pub struct AggregationCircuit<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+> {
+    pub data: CircuitData<F, C, D>,
+    pub child_id: CircuitId,
+    child_common: CommonCircuitData<F, D>,
+    child_proofs: Vec<ProofWithPublicInputsTarget<D>>,
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    AggregationCircuit<F, C, D>
+where
+    C::Hasher: AlgebraicHasher<F>,
+{
+    pub fn new(config: CircuitConfig, child: &VerifierCircuitData<F, C, D>, arity: usize) -> Self {
+        assert!(arity > 0, "An aggregation circuit needs at least one child");
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let child_vd = builder.constant_verifier_data(&child.verifier_only);
+        let child_proofs = (0..arity)
+            .map(|_| {
+                let proof = builder.add_virtual_proof_with_pis(&child.common);
+                builder.verify_proof::<C>(&proof, &child_vd, &child.common);
+                proof
+            })
+            .collect::<Vec<_>>();
+        let child_pis = child_proofs
+            .iter()
+            .flat_map(|p| p.public_inputs.clone())
+            .collect();
+        let pis_hash = builder.hash_n_to_hash_no_pad::<C::Hasher>(child_pis);
+        builder.register_public_inputs(&pis_hash.elements);
+
+        Self {
+            data: builder.build::<C>(),
+            child_id: CircuitId::of(&child.verifier_only),
+            child_common: child.common.clone(),
+            child_proofs,
+        }
+    }
+
+    pub fn id(&self) -> CircuitId {
+        CircuitId::of(&self.data.verifier_only)
+    }
+
+    pub fn arity(&self) -> usize {
+        self.child_proofs.len()
+    }
+
+    pub fn prove(
+        &self,
+        child_proofs: &[ProofWithPublicInputs<F, C, D>],
+    ) -> Result<ProofWithPublicInputs<F, C, D>> {
+        ensure!(
+            child_proofs.len() == self.arity(),
+            "Expected {} child proofs, got {}",
+            self.arity(),
+            child_proofs.len()
+        );
+        let mut pw = PartialWitness::new();
+        for (target, proof) in self.child_proofs.iter().zip(child_proofs) {
+            pw.set_proof_with_pis_target(target, proof);
+        }
+        self.data.prove(pw)
+    }
+}
+
+/// A circuit a worker can run tasks against.
+pub enum TaskCircuit<'a, F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> {
+    Leaf(&'a CircuitData<F, C, D>),
+    Aggregation(&'a AggregationCircuit<F, C, D>),
+}
+
+impl<'a, F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    TaskCircuit<'a, F, C, D>
+{
+    pub fn id(&self) -> CircuitId {
+        match self {
+            Self::Leaf(data) => CircuitId::of(&data.verifier_only),
+            Self::Aggregation(agg) => CircuitId::of(&agg.data.verifier_only),
+        }
+    }
+}
+
+/// Runs a single task. This is stateless: everything needed besides the circuit itself is
+/// contained in the task.
+pub fn run_task<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    task: &ProvingTask,
+    circuit: &TaskCircuit<F, C, D>,
+) -> Result<TaskOutput>
+where
+    C::Hasher: AlgebraicHasher<F>,
+{
+    ensure!(
+        task.circuit_id == circuit.id(),
+        "Task {} targets a different circuit",
+        task.id
+    );
+    let proof = match (&task.input, circuit) {
+        (TaskInput::Witness(witness), TaskCircuit::Leaf(data)) => {
+            data.prove(decode_witness(witness)?)?
+        }
+        (TaskInput::ChildProofs(children), TaskCircuit::Aggregation(agg)) => {
+            let child_proofs = children
+                .iter()
+                .map(|bytes| ProofWithPublicInputs::from_bytes(bytes.clone(), &agg.child_common))
+                .collect::<Result<Vec<_>>>()?;
+            agg.prove(&child_proofs)?
+        }
+        _ => {
+            return Err(anyhow!(
+                "Task {} does not match the kind of circuit",
+                task.id
+            ))
+        }
+    };
+    Ok(TaskOutput {
+        id: task.id,
+        parent: task.parent,
+        circuit_id: task.circuit_id.clone(),
+        proof: proof.to_bytes(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::types::Field;
+    use crate::iop::target::Target;
+    use crate::iop::witness::Witness;
+    use crate::plonk::config::{Hasher, PoseidonGoldilocksConfig};
+
+    #[test]
+    fn test_decode_witness_conflicting_values() -> Result<()> {
+        type F = crate::field::goldilocks_field::GoldilocksField;
+
+        let target = Target::VirtualTarget { index: 0 };
+        let mut bytes = Vec::new();
+        bytes.write_usize(2).unwrap();
+        for value in [1, 2] {
+            bytes.write_target(target).unwrap();
+            bytes.write_field(F::from_canonical_u64(value)).unwrap();
+        }
+        assert!(decode_witness::<F>(&bytes).is_err());
+
+        // Repeating a target with the same value is harmless.
+        let mut bytes = Vec::new();
+        bytes.write_usize(2).unwrap();
+        for _ in 0..2 {
+            bytes.write_target(target).unwrap();
+            bytes.write_field(F::ONE).unwrap();
+        }
+        let witness = decode_witness::<F>(&bytes)?;
+        assert_eq!(witness.try_get_target(target), Some(F::ONE));
+        Ok(())
+    }
+
+    #[test]
+    fn test_distributed_aggregation() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+        let x = builder.add_virtual_target();
+        let y = builder.square(x);
+        builder.register_public_input(x);
+        builder.register_public_input(y);
+        let leaf = builder.build::<C>();
+        let leaf_id = CircuitId::of(&leaf.verifier_only);
+
+        let agg = AggregationCircuit::new(config, &leaf.verifier_data(), 2);
+        assert_eq!(agg.child_id, leaf_id);
+
+        // The orchestrator: a root with two leaves. Tasks travel as JSON.
+        let leaf_tasks = (1..=2)
+            .map(|i| {
+                let mut pw = PartialWitness::new();
+                pw.set_target(x, F::from_canonical_u64(i));
+                let task = ProvingTask::leaf(i, leaf_id.clone(), Some(0), &pw);
+                serde_json::to_string(&task).unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        // The workers.
+        let leaf_outputs = leaf_tasks
+            .iter()
+            .map(|json| {
+                let task: ProvingTask = serde_json::from_str(json)?;
+                run_task(&task, &TaskCircuit::Leaf(&leaf))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let root_task = ProvingTask::aggregation(0, agg.id(), None, &leaf_outputs)?;
+        assert!(run_task(&root_task, &TaskCircuit::Leaf(&leaf)).is_err());
+        let root = run_task(&root_task, &TaskCircuit::Aggregation(&agg))?;
+
+        let proof = ProofWithPublicInputs::from_bytes(root.proof, &agg.data.common)?;
+        let expected = <C as GenericConfig<D>>::Hasher::hash_no_pad(&[
+            F::ONE,
+            F::ONE,
+            F::TWO,
+            F::from_canonical_u64(4),
+        ]);
+        assert_eq!(proof.public_inputs, expected.elements.to_vec());
+        agg.data.verify(proof)
+    }
+}
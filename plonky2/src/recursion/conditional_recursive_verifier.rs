@@ -149,7 +149,7 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
     }
 
     /// Computes `if b { cap0 } else { cap1 }`.
-    fn select_cap(
+    pub(crate) fn select_cap(
         &mut self,
         b: BoolTarget,
         cap0: &MerkleCapTarget,
@@ -345,7 +345,7 @@ mod tests {
     use crate::iop::witness::{PartialWitness, WitnessWrite};
     use crate::plonk::circuit_data::CircuitConfig;
     use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
-    use crate::recursion::dummy_circuit::{dummy_circuit, dummy_proof};
+    use crate::recursion::dummy_circuit::{dummy_circuit, prove_dummy_circuit};
 
     #[test]
     fn test_conditional_recursive_verifier() -> Result<()> {
@@ -371,7 +371,7 @@ mod tests {
 
         // Generate dummy proof with the same `CommonCircuitData`.
         let dummy_data = dummy_circuit(&data.common);
-        let dummy_proof = dummy_proof(&dummy_data, HashMap::new())?;
+        let dummy_proof = prove_dummy_circuit(&dummy_data, HashMap::new())?;
 
         // Conditionally verify the two proofs.
         let mut builder = CircuitBuilder::<F, D>::new(config);
@@ -12,7 +12,7 @@ use crate::gates::noop::NoopGate;
 use crate::hash::hash_types::{HashOutTarget, MerkleCapTarget, RichField};
 use crate::hash::merkle_tree::MerkleCap;
 use crate::iop::generator::{GeneratedValues, SimpleGenerator};
-use crate::iop::target::Target;
+use crate::iop::target::{BoolTarget, Target};
 use crate::iop::witness::{PartialWitness, PartitionWitness, WitnessWrite};
 use crate::plonk::circuit_builder::CircuitBuilder;
 use crate::plonk::circuit_data::{
@@ -54,17 +54,46 @@ where
 
     // TODO: A bit wasteful to build a dummy circuit here. We could potentially use a proof that
     // just consists of zeros, apart from public inputs.
-    dummy_proof::<F, C, D>(
+    prove_dummy_circuit::<F, C, D>(
         &dummy_circuit::<F, C, D>(common_data),
         nonzero_public_inputs,
     )
     .unwrap()
 }
 
+/// Creates a valid proof of a trivial circuit with the same `CommonCircuitData` as `common_data`,
+/// and with the given public inputs, along with the trivial circuit's verifier data. Such proofs
+/// can fill the unused slots of an aggregation circuit, so that aggregation trees can always be
+/// full; see [`CircuitBuilder::verify_proof_or_ignore`].
+pub fn dummy_proof<F, C, const D: usize>(
+    common_data: &CommonCircuitData<F, D>,
+    public_inputs: &[F],
+) -> anyhow::Result<(
+    ProofWithPublicInputs<F, C, D>,
+    VerifierOnlyCircuitData<C, D>,
+)>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+{
+    anyhow::ensure!(
+        public_inputs.len() == common_data.num_public_inputs,
+        "Expected {} public inputs, got {}",
+        common_data.num_public_inputs,
+        public_inputs.len()
+    );
+    let circuit = dummy_circuit::<F, C, D>(common_data);
+    let proof = prove_dummy_circuit(
+        &circuit,
+        public_inputs.iter().copied().enumerate().collect(),
+    )?;
+    Ok((proof, circuit.verifier_only))
+}
+
 /// Generate a proof for a dummy circuit. The `public_inputs` parameter let the caller specify
 /// certain public inputs (identified by their indices) which should be given specific values.
 /// The rest will default to zero.
-pub(crate) fn dummy_proof<
+pub(crate) fn prove_dummy_circuit<
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
     const D: usize,
@@ -118,6 +147,37 @@ pub(crate) fn dummy_circuit<
 }
 
 impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
+    /// Verifies `proof_with_pis` against `inner_verifier_data` if `ignore` is false, or else
+    /// against the verifier data of the trivial circuit of [`dummy_proof`], so that a dummy proof
+    /// can take the place of a real one. The caller is responsible for disregarding the public
+    /// inputs of ignored proofs, e.g. by selecting neutral values when `ignore` is true.
+    pub fn verify_proof_or_ignore<C: GenericConfig<D, F = F>>(
+        &mut self,
+        ignore: BoolTarget,
+        proof_with_pis: &ProofWithPublicInputsTarget<D>,
+        inner_verifier_data: &VerifierCircuitTarget,
+        inner_common_data: &CommonCircuitData<F, D>,
+    ) where
+        C::Hasher: AlgebraicHasher<F>,
+    {
+        // The trivial circuit only depends on the common data, so its verifier data is a constant.
+        let dummy_circuit = dummy_circuit::<F, C, D>(inner_common_data);
+        let dummy_verifier_data = self.constant_verifier_data(&dummy_circuit.verifier_only);
+        let verifier_data = VerifierCircuitTarget {
+            constants_sigmas_cap: self.select_cap(
+                ignore,
+                &dummy_verifier_data.constants_sigmas_cap,
+                &inner_verifier_data.constants_sigmas_cap,
+            ),
+            circuit_digest: self.select_hash(
+                ignore,
+                dummy_verifier_data.circuit_digest,
+                inner_verifier_data.circuit_digest,
+            ),
+        };
+        self.verify_proof::<C>(proof_with_pis, &verifier_data, inner_common_data);
+    }
+
     pub(crate) fn dummy_proof_and_vk<C: GenericConfig<D, F = F> + 'static>(
         &mut self,
         common_data: &CommonCircuitData<F, D>,
@@ -126,7 +186,7 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         C::Hasher: AlgebraicHasher<F>,
     {
         let dummy_circuit = dummy_circuit::<F, C, D>(common_data);
-        let dummy_proof_with_pis = dummy_proof::<F, C, D>(&dummy_circuit, HashMap::new())?;
+        let dummy_proof_with_pis = prove_dummy_circuit::<F, C, D>(&dummy_circuit, HashMap::new())?;
         let dummy_proof_with_pis_target = self.add_virtual_proof_with_pis(common_data);
         let dummy_verifier_data_target =
             self.add_virtual_verifier_data(self.config.fri_config.cap_height);
@@ -254,3 +314,68 @@ where
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::types::Field;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    #[test]
+    fn test_verify_proof_or_ignore() -> anyhow::Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+        let x = builder.add_virtual_public_input();
+        let x_squared = builder.square(x);
+        builder.register_public_input(x_squared);
+        // The dummy circuit is padded with `NoopGate`s, so the leaf circuit must have some too.
+        builder.add_gate(NoopGate, vec![]);
+        let leaf = builder.build::<C>();
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(3));
+        let leaf_proof = leaf.prove(pw)?;
+
+        let (dummy, dummy_verifier_data) = dummy_proof::<F, C, D>(&leaf.common, &[F::ONE, F::TWO])?;
+        assert_eq!(dummy.public_inputs, [F::ONE, F::TWO]);
+        assert!(dummy_proof::<F, C, D>(&leaf.common, &[F::ONE]).is_err());
+        dummy_circuit::<F, C, D>(&leaf.common).verify(dummy.clone())?;
+        assert_ne!(dummy_verifier_data, leaf.verifier_only);
+
+        // Sums the squares of two proofs, counting ignored ones as zero.
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let leaf_verifier_data = builder.constant_verifier_data(&leaf.verifier_only);
+        let zero = builder.zero();
+        let children = [(); 2].map(|_| {
+            let proof = builder.add_virtual_proof_with_pis(&leaf.common);
+            let ignore = builder.add_virtual_bool_target_safe();
+            builder.verify_proof_or_ignore::<C>(ignore, &proof, &leaf_verifier_data, &leaf.common);
+            (proof, ignore)
+        });
+        let squares = children
+            .iter()
+            .map(|(proof, ignore)| builder.select(*ignore, zero, proof.public_inputs[1]))
+            .collect::<Vec<_>>();
+        let sum = builder.add(squares[0], squares[1]);
+        builder.register_public_input(sum);
+        let data = builder.build::<C>();
+
+        let prove = |second: &ProofWithPublicInputs<F, C, D>, ignore_second: bool| {
+            let mut pw = PartialWitness::new();
+            pw.set_proof_with_pis_target(&children[0].0, &leaf_proof);
+            pw.set_bool_target(children[0].1, false);
+            pw.set_proof_with_pis_target(&children[1].0, second);
+            pw.set_bool_target(children[1].1, ignore_second);
+            data.prove(pw)
+        };
+        let proof = prove(&leaf_proof, false)?;
+        assert_eq!(proof.public_inputs, [F::from_canonical_u64(18)]);
+        let proof = prove(&dummy, true)?;
+        assert_eq!(proof.public_inputs, [F::from_canonical_u64(9)]);
+        data.verify(proof)
+    }
+}
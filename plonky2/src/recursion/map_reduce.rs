@@ -0,0 +1,255 @@
+//! A map-reduce framework on top of recursion.
+//!
+//! An application provides a [`MapCircuit`], which computes an output from one chunk of the
+//! input, and a [`ReduceCircuit`], which combines two outputs into one. [`MapReduceCircuits`]
+//! builds the map circuit and one reduce circuit per level of a binary tree over the chunks, each
+//! verifying two proofs of the level below, and [`MapReduceCircuits::prove`] proves the chunks and
+//! then each level in parallel, up to a single root proof whose public inputs are the output of
+//! the whole computation.
+
+use alloc::vec::Vec;
+
+use anyhow::{ensure, Result};
+use plonky2_maybe_rayon::*;
+
+use crate::field::extension::Extendable;
+use crate::hash::hash_types::RichField;
+use crate::iop::target::Target;
+use crate::iop::witness::{PartialWitness, WitnessWrite};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::{CircuitConfig, CircuitData};
+use crate::plonk::config::{AlgebraicHasher, GenericConfig};
+use crate::plonk::proof::{ProofWithPublicInputs, ProofWithPublicInputsTarget};
+
+/// Computes an output from a single chunk of the input.
+pub trait MapCircuit<F: RichField + Extendable<D>, const D: usize> {
+    /// A chunk of the input.
+    type Chunk;
+    /// The targets a chunk is assigned to.
+    type Targets;
+
+    /// Adds the gadget mapping a chunk, returning the chunk's targets and the output.
+    fn map(&self, builder: &mut CircuitBuilder<F, D>) -> (Self::Targets, Vec<Target>);
+
+    /// Assigns `chunk` to the targets returned by `map`.
+    fn set_chunk(
+        &self,
+        witness: &mut PartialWitness<F>,
+        targets: &Self::Targets,
+        chunk: &Self::Chunk,
+    );
+}
+
+/// Combines two outputs of a [`MapCircuit`], or of itself, into one.
+pub trait ReduceCircuit<F: RichField + Extendable<D>, const D: usize> {
+    /// Adds the gadget combining `left` and `right`, returning an output of the same length.
+    fn reduce(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        left: &[Target],
+        right: &[Target],
+    ) -> Vec<Target>;
+}
+
+/// A circuit verifying two proofs of the level below and reducing their outputs.
+#[derive(Debug)]
+struct ReduceLevel<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> {
+    data: CircuitData<F, C, D>,
+    left: ProofWithPublicInputsTarget<D>,
+    right: ProofWithPublicInputsTarget<D>,
+}
+
+/// The circuits of a map-reduce tree over a fixed number of chunks.
+#[derive(Debug)]
+pub struct MapReduceCircuits<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    M: MapCircuit<F, D>,
+    const D: usize,
+> {
+    map: M,
+    map_data: CircuitData<F, C, D>,
+    map_targets: M::Targets,
+    levels: Vec<ReduceLevel<F, C, D>>,
+}
+
+impl<F, C, M, const D: usize> MapReduceCircuits<F, C, M, D>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    C::Hasher: AlgebraicHasher<F>,
+    M: MapCircuit<F, D>,
+{
+    /// Builds the circuits for `num_chunks` chunks, which must be a power of two. All circuits use
+    /// `config`; the reduce circuits need it to support recursion.
+    pub fn new<R: ReduceCircuit<F, D>>(
+        config: &CircuitConfig,
+        map: M,
+        reduce: &R,
+        num_chunks: usize,
+    ) -> Result<Self> {
+        ensure!(
+            num_chunks.is_power_of_two(),
+            "The number of chunks must be a power of two, got {}",
+            num_chunks
+        );
+
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+        let (map_targets, output) = map.map(&mut builder);
+        let output_len = output.len();
+        builder.register_public_inputs(&output);
+        let map_data = builder.build::<C>();
+
+        let mut levels = Vec::new();
+        for _ in 0..num_chunks.trailing_zeros() {
+            let child = levels
+                .last()
+                .map_or(&map_data, |level: &ReduceLevel<F, C, D>| &level.data);
+            let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+            let child_vd = builder.constant_verifier_data(&child.verifier_only);
+            let left = builder.add_virtual_proof_with_pis(&child.common);
+            let right = builder.add_virtual_proof_with_pis(&child.common);
+            builder.verify_proof::<C>(&left, &child_vd, &child.common);
+            builder.verify_proof::<C>(&right, &child_vd, &child.common);
+            let output = reduce.reduce(&mut builder, &left.public_inputs, &right.public_inputs);
+            ensure!(
+                output.len() == output_len,
+                "The reduce circuit output {} elements, expected {}",
+                output.len(),
+                output_len
+            );
+            builder.register_public_inputs(&output);
+            levels.push(ReduceLevel {
+                data: builder.build::<C>(),
+                left,
+                right,
+            });
+        }
+
+        Ok(Self {
+            map,
+            map_data,
+            map_targets,
+            levels,
+        })
+    }
+
+    pub fn num_chunks(&self) -> usize {
+        1 << self.levels.len()
+    }
+
+    /// The circuit whose proofs [`Self::prove`] returns: the top reduce circuit, or the map
+    /// circuit if there is a single chunk.
+    pub fn root_data(&self) -> &CircuitData<F, C, D> {
+        self.levels
+            .last()
+            .map_or(&self.map_data, |level| &level.data)
+    }
+
+    /// Proves the map circuit on each chunk, and reduces the resulting proofs pairwise, level by
+    /// level, into a single proof of the root circuit. Proofs within a level are generated in
+    /// parallel.
+    pub fn prove(&self, chunks: &[M::Chunk]) -> Result<ProofWithPublicInputs<F, C, D>>
+    where
+        M: Sync,
+        M::Chunk: Sync,
+        M::Targets: Sync,
+    {
+        ensure!(
+            chunks.len() == self.num_chunks(),
+            "Expected {} chunks, got {}",
+            self.num_chunks(),
+            chunks.len()
+        );
+
+        let mut proofs = chunks
+            .par_iter()
+            .map(|chunk| {
+                let mut pw = PartialWitness::new();
+                self.map.set_chunk(&mut pw, &self.map_targets, chunk);
+                self.map_data.prove(pw)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        for level in &self.levels {
+            proofs = proofs
+                .par_chunks(2)
+                .map(|pair| {
+                    let mut pw = PartialWitness::new();
+                    pw.set_proof_with_pis_target(&level.left, &pair[0]);
+                    pw.set_proof_with_pis_target(&level.right, &pair[1]);
+                    level.data.prove(pw)
+                })
+                .collect::<Result<Vec<_>>>()?;
+        }
+        Ok(proofs.pop().expect("There is at least one chunk"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::field::types::Field;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    /// Maps a chunk `[a, b]` to `[a * b, 1]`, and reduces by adding the products and counts.
+    struct ProductSum;
+
+    impl<F: RichField + Extendable<D>, const D: usize> MapCircuit<F, D> for ProductSum {
+        type Chunk = [F; 2];
+        type Targets = [Target; 2];
+
+        fn map(&self, builder: &mut CircuitBuilder<F, D>) -> (Self::Targets, Vec<Target>) {
+            let chunk = builder.add_virtual_target_arr();
+            let product = builder.mul(chunk[0], chunk[1]);
+            (chunk, vec![product, builder.one()])
+        }
+
+        fn set_chunk(
+            &self,
+            witness: &mut PartialWitness<F>,
+            targets: &Self::Targets,
+            chunk: &Self::Chunk,
+        ) {
+            witness.set_target_arr(targets, chunk);
+        }
+    }
+
+    impl<F: RichField + Extendable<D>, const D: usize> ReduceCircuit<F, D> for ProductSum {
+        fn reduce(
+            &self,
+            builder: &mut CircuitBuilder<F, D>,
+            left: &[Target],
+            right: &[Target],
+        ) -> Vec<Target> {
+            left.iter()
+                .zip(right)
+                .map(|(&l, &r)| builder.add(l, r))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_map_reduce() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let circuits = MapReduceCircuits::<F, C, _, D>::new(&config, ProductSum, &ProductSum, 4)?;
+        assert!(MapReduceCircuits::<F, C, _, D>::new(&config, ProductSum, &ProductSum, 3).is_err());
+
+        let chunks = (1..=4)
+            .map(|i| [F::from_canonical_u64(i), F::from_canonical_u64(i + 1)])
+            .collect::<Vec<_>>();
+        assert!(circuits.prove(&chunks[..3]).is_err());
+        let proof = circuits.prove(&chunks)?;
+        // 1 * 2 + 2 * 3 + 3 * 4 + 4 * 5 = 40, over 4 chunks.
+        assert_eq!(
+            proof.public_inputs,
+            [F::from_canonical_u64(40), F::from_canonical_u64(4)]
+        );
+        circuits.root_data().verify(proof)
+    }
+}
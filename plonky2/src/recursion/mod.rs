@@ -1,4 +1,8 @@
+pub mod aggregation;
 pub mod conditional_recursive_verifier;
 pub mod cyclic_recursion;
 pub mod dummy_circuit;
+pub mod map_reduce;
 pub mod recursive_verifier;
+pub mod shrinking;
+pub mod state_machine;
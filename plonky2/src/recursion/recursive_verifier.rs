@@ -3,13 +3,12 @@ use crate::hash::hash_types::{HashOutTarget, RichField};
 use crate::plonk::circuit_builder::CircuitBuilder;
 use crate::plonk::circuit_data::{CommonCircuitData, VerifierCircuitTarget};
 use crate::plonk::config::{AlgebraicHasher, GenericConfig};
-use crate::plonk::plonk_common::salt_size;
+use crate::plonk::plonk_common::{check_quotient_openings_circuit, salt_size};
 use crate::plonk::proof::{
     OpeningSetTarget, ProofChallengesTarget, ProofTarget, ProofWithPublicInputsTarget,
 };
 use crate::plonk::vanishing_poly::eval_vanishing_poly_circuit;
 use crate::plonk::vars::EvaluationTargets;
-use crate::util::reducing::ReducingFactorTarget;
 use crate::with_context;
 
 impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
@@ -55,8 +54,6 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
     ) where
         C::Hasher: AlgebraicHasher<F>,
     {
-        let one = self.one_extension();
-
         let local_constants = &proof.openings.constants;
         let local_wires = &proof.openings.wires;
         let vars = EvaluationTargets {
@@ -95,19 +92,17 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             )
         );
 
-        with_context!(self, "check vanishing and quotient polynomials.", {
-            let quotient_polys_zeta = &proof.openings.quotient_polys;
-            let mut scale = ReducingFactorTarget::new(zeta_pow_deg);
-            let z_h_zeta = self.sub_extension(zeta_pow_deg, one);
-            for (i, chunk) in quotient_polys_zeta
-                .chunks(inner_common_data.quotient_degree_factor)
-                .enumerate()
-            {
-                let recombined_quotient = scale.reduce(chunk, self);
-                let computed_vanishing_poly = self.mul_extension(z_h_zeta, recombined_quotient);
-                self.connect_extension(vanishing_polys_zeta[i], computed_vanishing_poly);
-            }
-        });
+        with_context!(
+            self,
+            "check vanishing and quotient polynomials.",
+            check_quotient_openings_circuit(
+                self,
+                &vanishing_polys_zeta,
+                &proof.openings.quotient_polys,
+                inner_common_data.quotient_degree_factor,
+                zeta_pow_deg,
+            )
+        );
 
         let merkle_caps = &[
             inner_verifier_data.constants_sigmas_cap.clone(),
@@ -199,6 +194,7 @@ mod tests {
 
     use super::*;
     use crate::fri::reduction_strategies::FriReductionStrategy;
+    use crate::fri::security::SecurityAssumption;
     use crate::fri::FriConfig;
     use crate::gadgets::lookup::{OTHER_TABLE, TIP5_TABLE};
     use crate::gates::lookup_table::LookupTable;
@@ -226,6 +222,78 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_recursive_verifier_large_final_poly() -> Result<()> {
+        init_logger();
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let standard_config = CircuitConfig::standard_recursion_config();
+
+        // A single FRI reduction, leaving a final polynomial of 2^8 coefficients.
+        let inner_config = CircuitConfig {
+            fri_config: FriConfig {
+                reduction_strategy: FriReductionStrategy::ConstantArityBits(4, 8),
+                ..standard_config.fri_config
+            },
+            ..standard_config
+        };
+        let (proof, vd, common_data) = dummy_proof::<F, C, D>(&inner_config, 4_000)?;
+        assert_eq!(common_data.fri_params.reduction_arity_bits, [4]);
+        assert_eq!(proof.proof.opening_proof.final_poly.len(), 1 << 8);
+
+        let (proof, vd, common_data) = recursive_proof::<F, C, C, D>(
+            proof,
+            vd,
+            common_data,
+            &standard_config,
+            None,
+            true,
+            true,
+        )?;
+        test_serialization(&proof, &vd, &common_data)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_recursive_verifier_custom_coset_shift() -> Result<()> {
+        init_logger();
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let standard_config = CircuitConfig::standard_recursion_config();
+
+        // The square of the default shift, a generator of the group's index 2 subgroup.
+        let inner_config = CircuitConfig {
+            fri_config: FriConfig {
+                coset_shift: Some(49),
+                ..standard_config.fri_config.clone()
+            },
+            ..standard_config
+        };
+        let (proof, vd, common_data) = dummy_proof::<F, C, D>(&inner_config, 4_000)?;
+        assert_ne!(
+            common_data.config_digest::<<C as GenericConfig<D>>::Hasher>(),
+            dummy_proof::<F, C, D>(&standard_config, 4_000)?
+                .2
+                .config_digest::<<C as GenericConfig<D>>::Hasher>()
+        );
+
+        let (proof, vd, common_data) = recursive_proof::<F, C, C, D>(
+            proof,
+            vd,
+            common_data,
+            &standard_config,
+            None,
+            true,
+            true,
+        )?;
+        test_serialization(&proof, &vd, &common_data)?;
+
+        Ok(())
+    }
+
     #[test]
     fn test_recursive_verifier_one_lookup() -> Result<()> {
         init_logger();
@@ -361,6 +429,8 @@ mod tests {
                 proof_of_work_bits: 20,
                 reduction_strategy: FriReductionStrategy::MinSize(None),
                 num_query_rounds: 10,
+                security_assumption: SecurityAssumption::Conjectured,
+                coset_shift: None,
             },
             ..high_rate_config
         };
@@ -0,0 +1,201 @@
+//! Advice on shrinking proofs by wrapping them in further layers of recursion.
+//!
+//! Verifying a proof in a wrapper circuit and proving the wrapper replaces the proof with one whose
+//! size depends on the wrapper's degree and config rather than the inner circuit's. This pays off
+//! while the inner circuit is larger, or uses a cheaper-to-prove config, than the wrapper would,
+//! and stops paying off once wrapping reaches a fixed point. [`advise_shrinking`] decides whether
+//! one more step helps, by building candidate wrappers (without proving anything) and comparing
+//! their costs, and [`shrinking_plan`] repeats this until no candidate helps.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::field::extension::Extendable;
+use crate::fri::FriConfig;
+use crate::hash::hash_types::RichField;
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::{CircuitConfig, CommonCircuitData};
+use crate::plonk::config::{AlgebraicHasher, GenericConfig};
+
+/// A wrapper circuit verifying proofs of some inner circuit.
+#[derive(Clone, Debug)]
+pub struct ShrinkingStep<F: RichField + Extendable<D>, const D: usize> {
+    /// The wrapper's config.
+    pub config: CircuitConfig,
+    /// The wrapper's common data, which can be passed back to [`advise_shrinking`] to consider
+    /// another step.
+    pub common: CommonCircuitData<F, D>,
+    /// The cost of verifying the wrapper's proofs.
+    pub cost: usize,
+}
+
+/// The outcome of [`advise_shrinking`].
+#[derive(Clone, Debug)]
+pub struct ShrinkingAdvice<F: RichField + Extendable<D>, const D: usize> {
+    /// The cost of verifying the inner circuit's proofs directly.
+    pub inner_cost: usize,
+    /// The cheapest wrapper among the candidate configs.
+    pub best: ShrinkingStep<F, D>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> ShrinkingAdvice<F, D> {
+    /// Whether wrapping the inner circuit's proofs in `best` makes them cheaper to verify.
+    pub fn should_shrink(&self) -> bool {
+        self.best.cost < self.inner_cost
+    }
+}
+
+/// Candidate configs for a wrapper, derived from `base`: `base` itself, and variants with higher
+/// rates, which make proving the wrapper slower but need fewer FRI queries for the same security.
+pub fn shrinking_candidates(base: &CircuitConfig) -> Vec<CircuitConfig> {
+    let fri = &base.fri_config;
+    let mut candidates = vec![base.clone()];
+    for rate_bits in [fri.rate_bits + 2, fri.rate_bits + 4] {
+        candidates.push(CircuitConfig {
+            fri_config: FriConfig::for_security(
+                base.security_bits,
+                fri.security_assumption,
+                rate_bits,
+                fri.cap_height,
+                fri.reduction_strategy.clone(),
+            ),
+            ..base.clone()
+        });
+    }
+    candidates
+}
+
+/// The common data of a circuit with `config` which verifies a proof of the circuit described by
+/// `inner_common`, proven with `InnerC`, and exposes its public inputs.
+pub fn wrapper_common_data<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    InnerC: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    inner_common: &CommonCircuitData<F, D>,
+    config: &CircuitConfig,
+) -> CommonCircuitData<F, D>
+where
+    InnerC::Hasher: AlgebraicHasher<F>,
+{
+    let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+    let proof = builder.add_virtual_proof_with_pis(inner_common);
+    let verifier_data =
+        builder.add_virtual_verifier_data(inner_common.config.fri_config.cap_height);
+    builder.verify_proof::<InnerC>(&proof, &verifier_data, inner_common);
+    builder.register_public_inputs(&proof.public_inputs);
+    builder.build::<C>().common
+}
+
+/// Decides whether wrapping proofs of the circuit described by `inner_common` in one more layer of
+/// recursion reduces `cost`, e.g. `CommonCircuitData::proof_size` or an on-chain gas estimate
+/// from `CommonCircuitData::onchain_verifier_cost`, and which of `candidates` the wrapper should
+/// use. Each candidate wrapper is built, but nothing is proven.
+pub fn advise_shrinking<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    InnerC: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    inner_common: &CommonCircuitData<F, D>,
+    candidates: &[CircuitConfig],
+    cost: impl Fn(&CommonCircuitData<F, D>) -> usize,
+) -> ShrinkingAdvice<F, D>
+where
+    InnerC::Hasher: AlgebraicHasher<F>,
+{
+    let best = candidates
+        .iter()
+        .map(|config| {
+            let common = wrapper_common_data::<F, C, InnerC, D>(inner_common, config);
+            ShrinkingStep {
+                config: config.clone(),
+                cost: cost(&common),
+                common,
+            }
+        })
+        .min_by_key(|step| step.cost)
+        .expect("No candidate configs");
+    ShrinkingAdvice {
+        inner_cost: cost(inner_common),
+        best,
+    }
+}
+
+/// The sequence of wrappers, each verifying the previous one's proofs (the first verifying proofs
+/// of `inner_common`), obtained by following [`advise_shrinking`] until another step no longer
+/// reduces `cost`, or `max_steps` steps were taken. An empty plan means the inner circuit's proofs
+/// shouldn't be wrapped.
+pub fn shrinking_plan<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    inner_common: &CommonCircuitData<F, D>,
+    candidates: &[CircuitConfig],
+    cost: impl Fn(&CommonCircuitData<F, D>) -> usize,
+    max_steps: usize,
+) -> Vec<ShrinkingStep<F, D>>
+where
+    C::Hasher: AlgebraicHasher<F>,
+{
+    let mut plan: Vec<ShrinkingStep<F, D>> = Vec::new();
+    while plan.len() < max_steps {
+        let inner = plan.last().map_or(inner_common, |step| &step.common);
+        let advice = advise_shrinking::<F, C, C, D>(inner, candidates, &cost);
+        if !advice.should_shrink() {
+            break;
+        }
+        plan.push(advice.best);
+    }
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gates::noop::NoopGate;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    #[test]
+    fn test_advise_shrinking() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let circuit = |num_gates| {
+            let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+            let x = builder.add_virtual_public_input();
+            builder.register_public_input(x);
+            for _ in 0..num_gates {
+                builder.add_gate(NoopGate, vec![]);
+            }
+            builder.build::<C>().common
+        };
+        let proof_size = |common: &CommonCircuitData<F, D>| common.proof_size::<C>();
+        let candidates = [config.clone()];
+
+        // A tiny circuit is smaller than any recursive verifier.
+        let small = circuit(1);
+        let advice = advise_shrinking::<F, C, C, D>(&small, &candidates, proof_size);
+        assert!(!advice.should_shrink());
+        assert!(shrinking_plan::<F, C, D>(&small, &candidates, proof_size, 4).is_empty());
+
+        // A large one is worth wrapping, until wrapping reaches a fixed point.
+        let large = circuit((1 << 13) + 1);
+        let advice = advise_shrinking::<F, C, C, D>(&large, &candidates, proof_size);
+        assert!(advice.should_shrink());
+        assert!(advice.best.common.degree_bits() < large.degree_bits());
+        let plan = shrinking_plan::<F, C, D>(&large, &candidates, proof_size, 4);
+        assert!(!plan.is_empty() && plan.len() < 4);
+    }
+
+    #[test]
+    fn test_shrinking_candidates() {
+        let base = CircuitConfig::standard_recursion_config();
+        let candidates = shrinking_candidates(&base);
+        assert_eq!(candidates[0], base);
+        for candidate in &candidates[1..] {
+            assert!(candidate.fri_config.rate_bits > base.fri_config.rate_bits);
+            assert!(candidate.fri_config.num_query_rounds < base.fri_config.num_query_rounds);
+        }
+    }
+}
@@ -0,0 +1,397 @@
+//! A harness for incrementally verifiable computation (IVC) of a state machine, on top of cyclic
+//! recursion.
+//!
+//! An application implements [`StateTransitionCircuit`], describing a single transition as a
+//! gadget, and [`StateMachineCircuit`] takes care of the rest: it builds a cyclic circuit which
+//! applies the transition to the state output by a previous proof of itself, finds the common
+//! data that circuit has to verify (which must be its own), and proves the first and subsequent
+//! steps. A [`StateMachineCompressor`] then wraps the latest proof into one which hides the
+//! cyclic verifier data and no longer needs `check_cyclic_proof_verifier_data`.
+//!
+//! Proofs of the cyclic circuit have the public inputs
+//! - the initial state (`STATE_LEN`),
+//! - the current state (`STATE_LEN`),
+//! - the number of transitions applied (1),
+//! - the circuit's verifier data,
+//!
+//! while proofs of the compressor have all of these but the verifier data.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use anyhow::{anyhow, ensure, Result};
+
+use crate::field::extension::Extendable;
+use crate::gates::noop::NoopGate;
+use crate::hash::hash_types::RichField;
+use crate::iop::target::{BoolTarget, Target};
+use crate::iop::witness::{PartialWitness, WitnessWrite};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::{
+    CircuitConfig, CircuitData, CommonCircuitData, VerifierCircuitTarget,
+};
+use crate::plonk::config::{AlgebraicHasher, GenericConfig};
+use crate::plonk::proof::{ProofWithPublicInputs, ProofWithPublicInputsTarget};
+use crate::recursion::cyclic_recursion::check_cyclic_proof_verifier_data;
+use crate::recursion::dummy_circuit::cyclic_base_proof;
+
+/// The number of times the cyclic circuit is rebuilt against its previous common data before
+/// giving up on reaching a fixed point. In practice it takes two or three.
+const MAX_COMMON_DATA_ITERATIONS: usize = 8;
+
+/// The degree of the circuit the search for the cyclic circuit's common data starts from. A
+/// recursive verifier for the standard recursion config has a bit more than `2^12` gates, so this
+/// is close to the fixed point for small transitions.
+const SEED_DEGREE_BITS: usize = 12;
+
+/// A single transition of a state machine, `apply(state, input) -> state`, expressed as a gadget.
+pub trait StateTransitionCircuit<F: RichField + Extendable<D>, const D: usize> {
+    /// The number of field elements in a state.
+    const STATE_LEN: usize;
+    /// The number of field elements consumed by a single transition.
+    const INPUT_LEN: usize;
+
+    /// Adds a gadget computing the state which follows `state` after consuming `input`. Returns
+    /// `STATE_LEN` targets.
+    fn apply(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        state: &[Target],
+        input: &[Target],
+    ) -> Vec<Target>;
+}
+
+#[derive(Debug)]
+struct StateMachineTargets<const D: usize> {
+    input: Vec<Target>,
+    has_previous: BoolTarget,
+    previous_proof: ProofWithPublicInputsTarget<D>,
+    verifier_data: VerifierCircuitTarget,
+}
+
+/// The cyclic circuit proving that a state results from applying some number of transitions to an
+/// initial state.
+#[derive(Debug)]
+pub struct StateMachineCircuit<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+> {
+    pub data: CircuitData<F, C, D>,
+    state_len: usize,
+    targets: StateMachineTargets<D>,
+}
+
+impl<F, C, const D: usize> StateMachineCircuit<F, C, D>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F> + 'static,
+    C::Hasher: AlgebraicHasher<F>,
+{
+    /// Builds the cyclic circuit for `transition`. Fails if the circuit's common data doesn't
+    /// converge, which can only happen if its degree keeps oscillating around a power of two.
+    pub fn new<T: StateTransitionCircuit<F, D>>(
+        config: &CircuitConfig,
+        transition: &T,
+    ) -> Result<Self> {
+        let mut common_data = seed_common_data::<F, C, T, D>(config, transition);
+        for _ in 0..MAX_COMMON_DATA_ITERATIONS {
+            let (mut builder, targets) =
+                build_cyclic::<F, C, T, D>(config, transition, &common_data)?;
+            // The common data is only a guess until the circuit built against it has the same
+            // common data, so don't let `build` assert that they match.
+            builder.goal_common_data = None;
+            let data = builder.build::<C>();
+            if data.common == common_data {
+                return Ok(Self {
+                    data,
+                    state_len: T::STATE_LEN,
+                    targets,
+                });
+            }
+            common_data = data.common;
+        }
+        Err(anyhow!(
+            "The state machine circuit's common data did not converge after {} iterations",
+            MAX_COMMON_DATA_ITERATIONS
+        ))
+    }
+
+    /// Proves the first transition, from `initial_state`.
+    pub fn prove_base(
+        &self,
+        initial_state: &[F],
+        input: &[F],
+    ) -> Result<ProofWithPublicInputs<F, C, D>> {
+        ensure!(
+            initial_state.len() == self.state_len,
+            "Expected a state of length {}, got {}",
+            self.state_len,
+            initial_state.len()
+        );
+        // The base proof is never verified, but its initial state is connected to ours.
+        let base_proof = cyclic_base_proof(
+            &self.data.common,
+            &self.data.verifier_only,
+            initial_state.iter().copied().enumerate().collect(),
+        );
+        self.prove(false, &base_proof, input)
+    }
+
+    /// Proves the transition following the one proven by `previous`.
+    pub fn prove_step(
+        &self,
+        previous: &ProofWithPublicInputs<F, C, D>,
+        input: &[F],
+    ) -> Result<ProofWithPublicInputs<F, C, D>> {
+        self.prove(true, previous, input)
+    }
+
+    fn prove(
+        &self,
+        has_previous: bool,
+        previous: &ProofWithPublicInputs<F, C, D>,
+        input: &[F],
+    ) -> Result<ProofWithPublicInputs<F, C, D>> {
+        ensure!(
+            input.len() == self.targets.input.len(),
+            "Expected an input of length {}, got {}",
+            self.targets.input.len(),
+            input.len()
+        );
+        let mut pw = PartialWitness::new();
+        pw.set_target_arr(&self.targets.input, input);
+        pw.set_bool_target(self.targets.has_previous, has_previous);
+        pw.set_proof_with_pis_target(&self.targets.previous_proof, previous);
+        pw.set_verifier_data_target(&self.targets.verifier_data, &self.data.verifier_only);
+        self.data.prove(pw)
+    }
+
+    /// Verifies a proof of the cyclic circuit, including the verifier data in its public inputs.
+    pub fn verify(&self, proof: ProofWithPublicInputs<F, C, D>) -> Result<()> {
+        check_cyclic_proof_verifier_data(&proof, &self.data.verifier_only, &self.data.common)?;
+        self.data.verify(proof)
+    }
+
+    pub fn initial_state<'a>(&self, proof: &'a ProofWithPublicInputs<F, C, D>) -> &'a [F] {
+        &proof.public_inputs[..self.state_len]
+    }
+
+    pub fn state<'a>(&self, proof: &'a ProofWithPublicInputs<F, C, D>) -> &'a [F] {
+        &proof.public_inputs[self.state_len..2 * self.state_len]
+    }
+
+    /// The number of transitions applied to the initial state.
+    pub fn num_steps(&self, proof: &ProofWithPublicInputs<F, C, D>) -> F {
+        proof.public_inputs[2 * self.state_len]
+    }
+
+    /// Builds a circuit with `config` which verifies proofs of this circuit and exposes all of
+    /// their public inputs but the verifier data, which it checks against this circuit's own.
+    pub fn compressor(&self, config: CircuitConfig) -> StateMachineCompressor<F, C, D> {
+        let common_data = &self.data.common;
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let proof = builder.add_virtual_proof_with_pis(common_data);
+        let verifier_data = builder.constant_verifier_data(&self.data.verifier_only);
+        builder.verify_proof::<C>(&proof, &verifier_data, common_data);
+
+        let num_state_pis = 2 * self.state_len + 1;
+        let verifier_data_elements = verifier_data.circuit_digest.elements.into_iter().chain(
+            verifier_data
+                .constants_sigmas_cap
+                .0
+                .iter()
+                .flat_map(|h| h.elements),
+        );
+        for (pi, element) in proof.public_inputs[num_state_pis..]
+            .iter()
+            .zip(verifier_data_elements)
+        {
+            builder.connect(*pi, element);
+        }
+        builder.register_public_inputs(&proof.public_inputs[..num_state_pis]);
+
+        StateMachineCompressor {
+            data: builder.build::<C>(),
+            proof,
+        }
+    }
+}
+
+/// A circuit wrapping proofs of a [`StateMachineCircuit`], see
+/// [`StateMachineCircuit::compressor`].
+#[derive(Debug)]
+pub struct StateMachineCompressor<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+> {
+    pub data: CircuitData<F, C, D>,
+    proof: ProofWithPublicInputsTarget<D>,
+}
+
+impl<F, C, const D: usize> StateMachineCompressor<F, C, D>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    C::Hasher: AlgebraicHasher<F>,
+{
+    pub fn compress(
+        &self,
+        proof: &ProofWithPublicInputs<F, C, D>,
+    ) -> Result<ProofWithPublicInputs<F, C, D>> {
+        let mut pw = PartialWitness::new();
+        pw.set_proof_with_pis_target(&self.proof, proof);
+        self.data.prove(pw)
+    }
+}
+
+/// A first guess at the cyclic circuit's common data: a circuit with its public inputs and gates
+/// for the transition, padded to `2^SEED_DEGREE_BITS` gates.
+fn seed_common_data<F, C, T, const D: usize>(
+    config: &CircuitConfig,
+    transition: &T,
+) -> CommonCircuitData<F, D>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    C::Hasher: AlgebraicHasher<F>,
+    T: StateTransitionCircuit<F, D>,
+{
+    let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+    let state = builder.add_virtual_targets(T::STATE_LEN);
+    let input = builder.add_virtual_targets(T::INPUT_LEN);
+    let next_state = transition.apply(&mut builder, &state, &input);
+    builder.register_public_inputs(&state);
+    builder.register_public_inputs(&next_state);
+    builder.add_virtual_public_input();
+    builder.add_verifier_data_public_inputs();
+    while builder.num_gates() < 1 << SEED_DEGREE_BITS {
+        builder.add_gate(NoopGate, vec![]);
+    }
+    builder.build::<C>().common
+}
+
+/// Builds the cyclic circuit, which verifies proofs with `common_data`, up to calling `build`.
+fn build_cyclic<F, C, T, const D: usize>(
+    config: &CircuitConfig,
+    transition: &T,
+    common_data: &CommonCircuitData<F, D>,
+) -> Result<(CircuitBuilder<F, D>, StateMachineTargets<D>)>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F> + 'static,
+    C::Hasher: AlgebraicHasher<F>,
+    T: StateTransitionCircuit<F, D>,
+{
+    let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+    let one = builder.one();
+
+    let initial_state = builder.add_virtual_targets(T::STATE_LEN);
+    builder.register_public_inputs(&initial_state);
+    let state = builder.add_virtual_targets(T::STATE_LEN);
+    let input = builder.add_virtual_targets(T::INPUT_LEN);
+    let next_state = transition.apply(&mut builder, &state, &input);
+    ensure!(
+        next_state.len() == T::STATE_LEN,
+        "The transition output a state of length {}, expected {}",
+        next_state.len(),
+        T::STATE_LEN
+    );
+    builder.register_public_inputs(&next_state);
+    let num_steps = builder.add_virtual_public_input();
+    let verifier_data = builder.add_verifier_data_public_inputs();
+
+    let has_previous = builder.add_virtual_bool_target_safe();
+    let previous_proof = builder.add_virtual_proof_with_pis(common_data);
+    let previous_pis = &previous_proof.public_inputs;
+
+    // Carry the initial state over from the previous proof. In the base case it's unconstrained,
+    // and taken from the dummy proof's public inputs.
+    for (&x, &previous_x) in initial_state.iter().zip(&previous_pis[..T::STATE_LEN]) {
+        builder.connect(x, previous_x);
+    }
+    // The transition starts from the previous proof's state, or from the initial state in the base
+    // case.
+    for i in 0..T::STATE_LEN {
+        let previous_x = previous_pis[T::STATE_LEN + i];
+        let x = builder.select(has_previous, previous_x, initial_state[i]);
+        builder.connect(state[i], x);
+    }
+    let previous_num_steps = previous_pis[2 * T::STATE_LEN];
+    let new_num_steps = builder.mul_add(has_previous.target, previous_num_steps, one);
+    builder.connect(num_steps, new_num_steps);
+
+    builder.conditionally_verify_cyclic_proof_or_dummy::<C>(
+        has_previous,
+        &previous_proof,
+        common_data,
+    )?;
+
+    Ok((
+        builder,
+        StateMachineTargets {
+            input,
+            has_previous,
+            previous_proof,
+            verifier_data,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::types::Field;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    /// Maps `(a, b)` to `(b, a + b + x)` for an input `x`.
+    struct Fibonacci;
+
+    impl<F: RichField + Extendable<D>, const D: usize> StateTransitionCircuit<F, D> for Fibonacci {
+        const STATE_LEN: usize = 2;
+        const INPUT_LEN: usize = 1;
+
+        fn apply(
+            &self,
+            builder: &mut CircuitBuilder<F, D>,
+            state: &[Target],
+            input: &[Target],
+        ) -> Vec<Target> {
+            let sum = builder.add(state[0], state[1]);
+            vec![state[1], builder.add(sum, input[0])]
+        }
+    }
+
+    #[test]
+    fn test_state_machine() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let circuit = StateMachineCircuit::<F, C, D>::new(&config, &Fibonacci)?;
+
+        let initial_state = [F::ZERO, F::ONE];
+        let proof = circuit.prove_base(&initial_state, &[F::ZERO])?;
+        circuit.verify(proof.clone())?;
+        assert_eq!(circuit.state(&proof), [F::ONE, F::ONE]);
+
+        let proof = circuit.prove_step(&proof, &[F::TWO])?;
+        let proof = circuit.prove_step(&proof, &[F::ZERO])?;
+        circuit.verify(proof.clone())?;
+        assert_eq!(circuit.initial_state(&proof), initial_state);
+        assert_eq!(
+            circuit.state(&proof),
+            [F::from_canonical_u64(4), F::from_canonical_u64(5)]
+        );
+        assert_eq!(circuit.num_steps(&proof), F::from_canonical_u64(3));
+
+        assert!(circuit.prove_step(&proof, &[]).is_err());
+
+        let compressor = circuit.compressor(config);
+        let compressed = compressor.compress(&proof)?;
+        assert_eq!(compressed.public_inputs, proof.public_inputs[..5]);
+        compressor.data.verify(compressed)
+    }
+}
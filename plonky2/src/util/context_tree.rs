@@ -1,6 +1,6 @@
 use alloc::string::{String, ToString};
-use alloc::vec;
 use alloc::vec::Vec;
+use alloc::{format, vec};
 
 use log::{log, Level};
 
@@ -50,6 +50,32 @@ impl ContextTree {
         }
     }
 
+    /// For each of the first `num_gates` gates, a description of the stack of scopes in which it
+    /// was added.
+    pub fn gate_contexts(&self, num_gates: usize) -> Vec<String> {
+        let mut contexts = vec![String::new(); num_gates];
+        self.gate_contexts_helper("", &mut contexts);
+        contexts
+    }
+
+    fn gate_contexts_helper(&self, prefix: &str, contexts: &mut [String]) {
+        let stack = if prefix.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{} > {}", prefix, self.name)
+        };
+        let end = self
+            .exit_gate_count
+            .unwrap_or(contexts.len())
+            .min(contexts.len());
+        for context in contexts.iter_mut().take(end).skip(self.enter_gate_count) {
+            context.clone_from(&stack);
+        }
+        for child in &self.children {
+            child.gate_contexts_helper(&stack, contexts);
+        }
+    }
+
     pub fn push(&mut self, ctx: &str, mut level: log::Level, current_gate_count: usize) {
         assert!(self.is_open());
 
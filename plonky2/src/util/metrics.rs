@@ -0,0 +1,128 @@
+//! A facade for reporting prover metrics to a service's metrics registry, such as a Prometheus
+//! registry. A service implements [`MetricsRecorder`] on top of its registry and installs it with
+//! [`set_metrics_recorder`]; until then, no metrics are computed.
+//!
+//! The provers of plonky2 and starky report:
+//! - [`PROOFS_PRODUCED`], a counter incremented for each proof;
+//! - [`PROOF_SIZE_BYTES`], a histogram of the serialized size of each proof;
+//! - [`PHASE_DURATION_SECONDS`], a histogram of the duration of each `timed!` scope, labelled with
+//!   its name as `phase`.
+//!
+//! The first two are labelled with `prover`, either `plonky2` or `starky`.
+
+use alloc::boxed::Box;
+use std::sync::OnceLock;
+
+/// The number of proofs produced.
+pub const PROOFS_PRODUCED: &str = "plonky2_proofs_produced_total";
+/// The size of serialized proofs, in bytes.
+pub const PROOF_SIZE_BYTES: &str = "plonky2_proof_size_bytes";
+/// The duration of prover phases, in seconds.
+pub const PHASE_DURATION_SECONDS: &str = "plonky2_phase_duration_seconds";
+
+/// Labels of a metric, as `(name, value)` pairs.
+pub type Labels<'a> = &'a [(&'static str, &'a str)];
+
+/// A sink for metrics, which forwards them to a registry.
+pub trait MetricsRecorder: Send + Sync {
+    fn increment_counter(&self, name: &'static str, labels: Labels);
+
+    fn record_histogram(&self, name: &'static str, labels: Labels, value: f64);
+}
+
+static RECORDER: OnceLock<Box<dyn MetricsRecorder>> = OnceLock::new();
+
+/// Installs the recorder which receives all metrics. It can only be installed once; if another
+/// recorder already was, `recorder` is returned as an error.
+pub fn set_metrics_recorder(
+    recorder: Box<dyn MetricsRecorder>,
+) -> Result<(), Box<dyn MetricsRecorder>> {
+    RECORDER.set(recorder)
+}
+
+/// The installed recorder, if any.
+pub fn metrics_recorder() -> Option<&'static dyn MetricsRecorder> {
+    RECORDER.get().map(|r| r.as_ref())
+}
+
+/// Reports a proof produced by `prover`, whose serialization `proof_bytes` is only computed if a
+/// recorder is installed.
+pub fn record_proof(prover: &str, proof_bytes: impl FnOnce() -> usize) {
+    if let Some(recorder) = metrics_recorder() {
+        let labels = [("prover", prover)];
+        recorder.increment_counter(PROOFS_PRODUCED, &labels);
+        recorder.record_histogram(PROOF_SIZE_BYTES, &labels, proof_bytes() as f64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::{String, ToString};
+    use alloc::vec::Vec;
+    use std::sync::Mutex;
+
+    use anyhow::Result;
+
+    use super::*;
+    use crate::field::types::Field;
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    #[derive(Default)]
+    struct TestRecorder {
+        counters: Mutex<Vec<String>>,
+        histograms: Mutex<Vec<(String, String)>>,
+    }
+
+    impl MetricsRecorder for &'static TestRecorder {
+        fn increment_counter(&self, name: &'static str, labels: Labels) {
+            assert_eq!(labels, [("prover", "plonky2")]);
+            self.counters.lock().unwrap().push(name.to_string());
+        }
+
+        fn record_histogram(&self, name: &'static str, labels: Labels, value: f64) {
+            assert!(value >= 0.0);
+            let label = labels.first().map_or("", |(_, v)| v).to_string();
+            self.histograms
+                .lock()
+                .unwrap()
+                .push((name.to_string(), label));
+        }
+    }
+
+    #[test]
+    fn test_metrics_recorder() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let recorder: &'static TestRecorder = Box::leak(Box::default());
+        assert!(set_metrics_recorder(Box::new(recorder)).is_ok());
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let y = builder.square(x);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::TWO);
+        let proof = data.prove(pw)?;
+        data.verify(proof)?;
+
+        // Other tests may be proving concurrently, so only check that this proof was reported.
+        assert!(recorder
+            .counters
+            .lock()
+            .unwrap()
+            .contains(&PROOFS_PRODUCED.to_string()));
+        let histograms = recorder.histograms.lock().unwrap();
+        assert!(histograms.contains(&(PROOF_SIZE_BYTES.to_string(), "plonky2".to_string())));
+        assert!(histograms.contains(&(
+            PHASE_DURATION_SECONDS.to_string(),
+            "compute quotient polys".to_string()
+        )));
+        Ok(())
+    }
+}
@@ -8,26 +8,73 @@ use crate::field::polynomial::PolynomialValues;
 use crate::field::types::Field;
 
 pub(crate) mod context_tree;
+#[cfg(feature = "metrics")]
+pub mod metrics;
 pub(crate) mod partial_products;
+pub mod permutation;
 pub mod reducing;
 pub mod serialization;
 pub mod strided_view;
+pub mod test_vectors;
 pub mod timing;
+#[cfg(feature = "hardened")]
+pub(crate) mod zeroize;
 
 pub(crate) fn transpose_poly_values<F: Field>(polys: Vec<PolynomialValues<F>>) -> Vec<Vec<F>> {
     let poly_values = polys.into_iter().map(|p| p.values).collect::<Vec<_>>();
     transpose(&poly_values)
 }
 
-pub fn transpose<T: Send + Sync + Copy>(matrix: &[Vec<T>]) -> Vec<Vec<T>> {
-    let len = matrix[0].len();
-    (0..len)
-        .into_par_iter()
-        .map(|i| matrix.iter().map(|row| row[i]).collect())
-        .collect()
+/// Side length of the square blocks the transposes below work on. A block of 64-bit elements
+/// fits comfortably in L1, so each block is read and written with few cache misses.
+const TRANSPOSE_BLOCK: usize = 32;
+
+/// Transposes a row-major matrix. Rows may be any slice-like type, e.g. `Vec<T>` or `[T; N]`.
+///
+/// The output is split into blocks of rows which are filled in parallel. Each block of output rows
+/// is built from `TRANSPOSE_BLOCK x TRANSPOSE_BLOCK` tiles of the input, so that reads of the input
+/// stay within a few cache lines per row instead of striding across the whole matrix.
+pub fn transpose<T: Send + Sync + Copy, R: AsRef<[T]> + Sync>(matrix: &[R]) -> Vec<Vec<T>> {
+    let len = matrix[0].as_ref().len();
+    let mut transposed = (0..len)
+        .map(|_| Vec::with_capacity(matrix.len()))
+        .collect::<Vec<_>>();
+    transposed
+        .par_chunks_mut(TRANSPOSE_BLOCK)
+        .enumerate()
+        .for_each(|(block, out_rows)| {
+            let start = block * TRANSPOSE_BLOCK;
+            for in_rows in matrix.chunks(TRANSPOSE_BLOCK) {
+                for (i, out_row) in out_rows.iter_mut().enumerate() {
+                    out_row.extend(in_rows.iter().map(|row| row.as_ref()[start + i]));
+                }
+            }
+        });
+    transposed
 }
 
-pub(crate) fn reverse_bits(n: usize, num_bits: usize) -> usize {
+/// Transposes a square matrix in place, without allocating. Tiles below the diagonal are swapped
+/// with their mirror images above it, one `TRANSPOSE_BLOCK x TRANSPOSE_BLOCK` tile at a time.
+pub fn transpose_in_place_square<T>(matrix: &mut [Vec<T>]) {
+    let n = matrix.len();
+    assert!(
+        matrix.iter().all(|row| row.len() == n),
+        "Matrix must be square"
+    );
+    for block_i in (0..n).step_by(TRANSPOSE_BLOCK) {
+        for block_j in (0..=block_i).step_by(TRANSPOSE_BLOCK) {
+            for i in block_i..(block_i + TRANSPOSE_BLOCK).min(n) {
+                let (upper, lower) = matrix.split_at_mut(i);
+                let row_i = &mut lower[0];
+                for j in block_j..(block_j + TRANSPOSE_BLOCK).min(i) {
+                    core::mem::swap(&mut row_i[j], &mut upper[j][i]);
+                }
+            }
+        }
+    }
+}
+
+pub fn reverse_bits(n: usize, num_bits: usize) -> usize {
     // NB: The only reason we need overflowing_shr() here as opposed
     // to plain '>>' is to accommodate the case n == num_bits == 0,
     // which would become `0 >> 64`. Rust thinks that any shift of 64
@@ -50,6 +97,37 @@ mod tests {
         assert_eq!(reverse_bits(0b01011, 5), 0b11010);
     }
 
+    fn naive_transpose(matrix: &[Vec<u64>]) -> Vec<Vec<u64>> {
+        (0..matrix[0].len())
+            .map(|i| matrix.iter().map(|row| row[i]).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_transpose() {
+        for (rows, cols) in [(1, 1), (3, 70), (100, 5), (65, 65), (257, 33)] {
+            let matrix = (0..rows)
+                .map(|i| (0..cols).map(|j| (i * cols + j) as u64).collect())
+                .collect::<Vec<Vec<u64>>>();
+            assert_eq!(transpose(&matrix), naive_transpose(&matrix));
+        }
+
+        let arrays = [[1u64, 2, 3], [4, 5, 6]];
+        assert_eq!(transpose(&arrays), vec![vec![1, 4], vec![2, 5], vec![3, 6]]);
+    }
+
+    #[test]
+    fn test_transpose_in_place_square() {
+        for n in [1, 2, 31, 32, 33, 100] {
+            let matrix = (0..n)
+                .map(|i| (0..n).map(|j| (i * n + j) as u64).collect())
+                .collect::<Vec<Vec<u64>>>();
+            let mut transposed = matrix.clone();
+            transpose_in_place_square(&mut transposed);
+            assert_eq!(transposed, naive_transpose(&matrix));
+        }
+    }
+
     #[test]
     fn test_reverse_index_bits() {
         assert_eq!(reverse_index_bits(&[10, 20, 30, 40]), vec![10, 30, 20, 40]);
@@ -0,0 +1,97 @@
+//! Grand product arguments, shared by plonky2's copy constraints and starky's permutation checks.
+//!
+//! A grand product argument shows that `prod_i f_i = prod_i g_i` for two lists of values, by
+//! committing to a running product `Z` with `Z(g^0) = 1` and `Z(g^(i+1)) = Z(g^i) f_i / g_i`, and
+//! checking `Z(g x) g(x) = Z(x) f(x)` over the domain.
+
+use alloc::vec::Vec;
+
+use crate::field::batch_util::batch_multiply_inplace;
+use crate::field::extension::Extendable;
+use crate::field::packed::PackedField;
+use crate::field::types::Field;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::plonk::circuit_builder::CircuitBuilder;
+
+/// Computes `numerators[i] / denominators[i]` for each `i`, with a single batched inversion.
+pub fn batch_quotients<F: Field>(numerators: &[F], denominators: &[F]) -> Vec<F> {
+    assert_eq!(numerators.len(), denominators.len());
+    let mut quotients = F::batch_multiplicative_inverse(denominators);
+    batch_multiply_inplace(&mut quotients, numerators);
+    quotients
+}
+
+/// Computes the values of the running product `Z` over a domain of size `numerators.len()`, i.e.
+/// `Z_0 = 1` and `Z_(i+1) = Z_i * numerators[i] / denominators[i]`. If the products of the
+/// numerators and of the denominators agree, `Z` wraps around to `1`.
+pub fn grand_product_z<F: Field>(numerators: &[F], denominators: &[F]) -> Vec<F> {
+    let quotients = batch_quotients(numerators, denominators);
+    let mut z = Vec::with_capacity(quotients.len());
+    let mut acc = F::ONE;
+    for q in quotients {
+        z.push(acc);
+        acc *= q;
+    }
+    z
+}
+
+/// The transition constraint of a grand product argument, `Z(g x) prod_j g_j(x) - Z(x) prod_j
+/// f_j(x)`, where the numerators `f_j` and denominators `g_j` of several arguments are batched into
+/// a single `Z`.
+pub fn grand_product_constraint<P: PackedField>(
+    z_x: P,
+    z_gx: P,
+    numerators: impl IntoIterator<Item = P>,
+    denominators: impl IntoIterator<Item = P>,
+) -> P {
+    z_gx * denominators.into_iter().product::<P>() - z_x * numerators.into_iter().product::<P>()
+}
+
+/// Circuit version of `grand_product_constraint`.
+pub fn grand_product_constraint_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    z_x: ExtensionTarget<D>,
+    z_gx: ExtensionTarget<D>,
+    numerators: Vec<ExtensionTarget<D>>,
+    denominators: Vec<ExtensionTarget<D>>,
+) -> ExtensionTarget<D> {
+    let numerator = builder.mul_many_extension(numerators);
+    let denominator = builder.mul_many_extension(denominators);
+    let tmp = builder.mul_extension(z_x, numerator);
+    builder.mul_sub_extension(z_gx, denominator, tmp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::goldilocks_field::GoldilocksField;
+    use crate::field::types::Sample;
+
+    type F = GoldilocksField;
+
+    #[test]
+    fn test_grand_product_z() {
+        let numerators = F::rand_vec(16);
+        let mut denominators = numerators.clone();
+        denominators.reverse();
+        let z = grand_product_z(&numerators, &denominators);
+
+        assert_eq!(z[0], F::ONE);
+        for i in 0..16 {
+            let z_gx = z[(i + 1) % 16];
+            assert_eq!(
+                grand_product_constraint(z[i], z_gx, [numerators[i]], [denominators[i]]),
+                F::ZERO
+            );
+        }
+
+        // Without a permutation, `Z` doesn't wrap around.
+        denominators[0] += F::ONE;
+        let z = grand_product_z(&numerators, &denominators);
+        assert_ne!(
+            grand_product_constraint(z[15], z[0], [numerators[15]], [denominators[15]]),
+            F::ZERO
+        );
+    }
+}
@@ -0,0 +1,247 @@
+//! A `GateSerializer` to which gates are added at runtime, each under a stable ID. Unlike the
+//! serializers declared with `impl_gate_serializer!`, whose tags are positions in a list, a gate's
+//! tag doesn't change when other gates are added, so downstream crates can register their own
+//! gates and still read circuits serialized before. Deserialized gates are then evaluated by the
+//! native and recursive verifiers like any other.
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::any::TypeId;
+
+use anyhow::{bail, ensure, Result};
+use hashbrown::HashMap;
+
+use crate::field::extension::Extendable;
+use crate::gates::arithmetic_base::ArithmeticGate;
+use crate::gates::arithmetic_extension::ArithmeticExtensionGate;
+use crate::gates::base_sum::BaseSumGate;
+use crate::gates::bitwise::BitwiseGate;
+use crate::gates::constant::ConstantGate;
+use crate::gates::coset_interpolation::CosetInterpolationGate;
+use crate::gates::exponentiation::ExponentiationGate;
+use crate::gates::gate::{Gate, GateRef};
+use crate::gates::lookup::LookupGate;
+use crate::gates::lookup_table::LookupTableGate;
+use crate::gates::multiplication_extension::MulExtensionGate;
+use crate::gates::noop::NoopGate;
+use crate::gates::poseidon::PoseidonGate;
+use crate::gates::poseidon_mds::PoseidonMdsGate;
+use crate::gates::public_input::PublicInputGate;
+use crate::gates::random_access::RandomAccessGate;
+use crate::gates::reducing::ReducingGate;
+use crate::gates::reducing_extension::ReducingExtensionGate;
+use crate::hash::hash_types::RichField;
+use crate::plonk::circuit_data::CommonCircuitData;
+use crate::util::serialization::{Buffer, GateSerializer, IoError, IoResult, Read, Write};
+
+/// IDs below this are reserved for plonky2's own gates. Gates from other crates should use IDs
+/// from here on.
+pub const FIRST_CUSTOM_GATE_ID: u32 = 1 << 16;
+
+type ReadGateFn<F, const D: usize> =
+    fn(&mut Buffer, &CommonCircuitData<F, D>) -> IoResult<GateRef<F, D>>;
+
+struct RegisteredGate<F: RichField + Extendable<D>, const D: usize> {
+    name: String,
+    read: ReadGateFn<F, D>,
+}
+
+/// A `GateSerializer` for the gates registered with it, which are tagged with their IDs.
+pub struct GateRegistry<F: RichField + Extendable<D>, const D: usize> {
+    gates: BTreeMap<u32, RegisteredGate<F, D>>,
+    ids_by_type: HashMap<TypeId, u32>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> GateRegistry<F, D> {
+    /// An empty registry.
+    pub fn new() -> Self {
+        Self {
+            gates: BTreeMap::new(),
+            ids_by_type: HashMap::new(),
+        }
+    }
+
+    /// A registry of plonky2's gates, with the same tags as `DefaultGateSerializer`, so it reads
+    /// circuits written by either.
+    pub fn with_default_gates() -> Self {
+        let mut registry = Self::new();
+        registry.register_builtin::<ArithmeticGate>(0, "ArithmeticGate");
+        registry.register_builtin::<ArithmeticExtensionGate<D>>(1, "ArithmeticExtensionGate");
+        registry.register_builtin::<BaseSumGate<2>>(2, "BaseSumGate<2>");
+        registry.register_builtin::<ConstantGate>(3, "ConstantGate");
+        registry.register_builtin::<CosetInterpolationGate<F, D>>(4, "CosetInterpolationGate");
+        registry.register_builtin::<ExponentiationGate<F, D>>(5, "ExponentiationGate");
+        registry.register_builtin::<LookupGate>(6, "LookupGate");
+        registry.register_builtin::<LookupTableGate>(7, "LookupTableGate");
+        registry.register_builtin::<MulExtensionGate<D>>(8, "MulExtensionGate");
+        registry.register_builtin::<NoopGate>(9, "NoopGate");
+        registry.register_builtin::<PoseidonMdsGate<F, D>>(10, "PoseidonMdsGate");
+        registry.register_builtin::<PoseidonGate<F, D>>(11, "PoseidonGate");
+        registry.register_builtin::<PublicInputGate>(12, "PublicInputGate");
+        registry.register_builtin::<RandomAccessGate<F, D>>(13, "RandomAccessGate");
+        registry.register_builtin::<ReducingExtensionGate<D>>(14, "ReducingExtensionGate");
+        registry.register_builtin::<ReducingGate<D>>(15, "ReducingGate");
+        registry.register_builtin::<BitwiseGate>(16, "BitwiseGate");
+        registry
+    }
+
+    fn register_builtin<G: Gate<F, D>>(&mut self, id: u32, name: &str) {
+        self.insert::<G>(id, name)
+            .expect("Built-in gate IDs are distinct");
+    }
+
+    /// Registers the gate type `G` under `id`, which must be at least `FIRST_CUSTOM_GATE_ID`.
+    /// Fails if the ID or the type is already registered.
+    pub fn register<G: Gate<F, D>>(&mut self, id: u32, name: &str) -> Result<()> {
+        ensure!(
+            id >= FIRST_CUSTOM_GATE_ID,
+            "Gate ID {} of {} is reserved for plonky2's gates",
+            id,
+            name
+        );
+        self.insert::<G>(id, name)
+    }
+
+    fn insert<G: Gate<F, D>>(&mut self, id: u32, name: &str) -> Result<()> {
+        if let Some(existing) = self.gates.get(&id) {
+            bail!(
+                "Gate ID {} is already registered, for {}",
+                id,
+                existing.name
+            );
+        }
+        ensure!(
+            !self.ids_by_type.contains_key(&TypeId::of::<G>()),
+            "Gate {} is already registered",
+            name
+        );
+        self.gates.insert(
+            id,
+            RegisteredGate {
+                name: name.into(),
+                read: |buf, common_data| Ok(GateRef::new(G::deserialize(buf, common_data)?)),
+            },
+        );
+        self.ids_by_type.insert(TypeId::of::<G>(), id);
+        Ok(())
+    }
+
+    /// The ID under which `gate`'s type is registered.
+    pub fn id_of(&self, gate: &GateRef<F, D>) -> Option<u32> {
+        self.ids_by_type.get(&gate.0.as_any().type_id()).copied()
+    }
+
+    /// The name under which `id` is registered.
+    pub fn name_of(&self, id: u32) -> Option<&str> {
+        self.gates.get(&id).map(|gate| gate.name.as_str())
+    }
+
+    /// The registered IDs and names, in order of ID.
+    pub fn gates(&self) -> Vec<(u32, &str)> {
+        self.gates
+            .iter()
+            .map(|(&id, gate)| (id, gate.name.as_str()))
+            .collect()
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Default for GateRegistry<F, D> {
+    fn default() -> Self {
+        Self::with_default_gates()
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> GateSerializer<F, D> for GateRegistry<F, D> {
+    fn read_gate(
+        &self,
+        buf: &mut Buffer,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> IoResult<GateRef<F, D>> {
+        let id = buf.read_u32()?;
+        let gate = self.gates.get(&id).ok_or_else(|| {
+            log::error!("attempted to read gate with unregistered ID {}", id);
+            IoError
+        })?;
+        (gate.read)(buf, common_data)
+    }
+
+    fn write_gate(
+        &self,
+        buf: &mut Vec<u8>,
+        gate: &GateRef<F, D>,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> IoResult<()> {
+        let id = self.id_of(gate).ok_or_else(|| {
+            log::error!("attempted to write unregistered gate `{}`", gate.0.id());
+            IoError
+        })?;
+        buf.write_u32(id)?;
+        gate.0.serialize(buf, common_data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::util::serialization::DefaultGateSerializer;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    #[test]
+    fn test_gate_registry() -> Result<()> {
+        // `BaseSumGate<4>` stands in for a third-party gate, as the default serializer lacks it.
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let limbs = builder.split_le_base::<4>(x, 8);
+        builder.register_public_inputs(&limbs);
+        let common = builder.build::<C>().common;
+        assert!(common.to_bytes(&DefaultGateSerializer).is_err());
+
+        let mut registry = GateRegistry::<F, D>::with_default_gates();
+        assert!(registry
+            .register::<BaseSumGate<4>>(3, "BaseSumGate<4>")
+            .is_err());
+        registry.register::<BaseSumGate<4>>(FIRST_CUSTOM_GATE_ID, "BaseSumGate<4>")?;
+        assert!(registry
+            .register::<BaseSumGate<4>>(FIRST_CUSTOM_GATE_ID + 1, "BaseSumGate<4>")
+            .is_err());
+        assert!(registry
+            .register::<BaseSumGate<8>>(FIRST_CUSTOM_GATE_ID, "BaseSumGate<8>")
+            .is_err());
+        assert_eq!(
+            registry.name_of(FIRST_CUSTOM_GATE_ID),
+            Some("BaseSumGate<4>")
+        );
+
+        let bytes = common.to_bytes(&registry).unwrap();
+        let read = CommonCircuitData::<F, D>::from_bytes(bytes.clone(), &registry).unwrap();
+        assert_eq!(read, common);
+
+        // Registering more gates doesn't change the tags of existing ones.
+        registry.register::<BaseSumGate<8>>(FIRST_CUSTOM_GATE_ID + 7, "BaseSumGate<8>")?;
+        assert_eq!(common.to_bytes(&registry).unwrap(), bytes);
+        Ok(())
+    }
+
+    #[test]
+    fn test_default_gate_tags() {
+        // The registry reads circuits written by `DefaultGateSerializer`.
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let y = builder.exp_u64(x, 5);
+        builder.register_public_input(y);
+        let common = builder.build::<C>().common;
+
+        let bytes = common.to_bytes(&DefaultGateSerializer).unwrap();
+        let registry = GateRegistry::<F, D>::with_default_gates();
+        assert_eq!(common.to_bytes(&registry).unwrap(), bytes);
+        let read = CommonCircuitData::<F, D>::from_bytes(bytes, &registry).unwrap();
+        assert_eq!(read, common);
+    }
+}
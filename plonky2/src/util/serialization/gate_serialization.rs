@@ -87,6 +87,7 @@ pub mod default {
     use crate::gates::arithmetic_base::ArithmeticGate;
     use crate::gates::arithmetic_extension::ArithmeticExtensionGate;
     use crate::gates::base_sum::BaseSumGate;
+    use crate::gates::bitwise::BitwiseGate;
     use crate::gates::constant::ConstantGate;
     use crate::gates::coset_interpolation::CosetInterpolationGate;
     use crate::gates::exponentiation::ExponentiationGate;
@@ -122,7 +123,8 @@ pub mod default {
             PublicInputGate,
             RandomAccessGate<F, D>,
             ReducingExtensionGate<D>,
-            ReducingGate<D>
+            ReducingGate<D>,
+            BitwiseGate
         }
     }
 }
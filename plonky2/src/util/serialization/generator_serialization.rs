@@ -92,14 +92,20 @@ pub mod default {
 
     use plonky2_field::extension::Extendable;
 
-    use crate::gadgets::arithmetic::EqualityGenerator;
-    use crate::gadgets::arithmetic_extension::QuotientGeneratorExtension;
+    use crate::gadgets::arithmetic::{BatchInverseGenerator, EqualityGenerator};
+    use crate::gadgets::arithmetic_extension::{
+        BatchInverseGeneratorExtension, QuotientGeneratorExtension,
+    };
+    use crate::gadgets::ecgfp5::SubgroupWitnessGenerator;
+    use crate::gadgets::fixed_point::{FloorDivGenerator, LimbsGenerator, NonnegativeGenerator};
+    use crate::gadgets::float::DivRemGenerator;
     use crate::gadgets::range_check::LowHighGenerator;
     use crate::gadgets::split_base::BaseSumGenerator;
     use crate::gadgets::split_join::{SplitGenerator, WireSplitGenerator};
     use crate::gates::arithmetic_base::ArithmeticBaseGenerator;
     use crate::gates::arithmetic_extension::ArithmeticExtensionGenerator;
     use crate::gates::base_sum::BaseSplitGenerator;
+    use crate::gates::bitwise::BitwiseGenerator;
     use crate::gates::coset_interpolation::InterpolationGenerator;
     use crate::gates::exponentiation::ExponentiationGenerator;
     use crate::gates::lookup::LookupGenerator;
@@ -153,7 +159,16 @@ pub mod default {
             ReducingGenerator<D>,
             ReducingExtensionGenerator<D>,
             SplitGenerator,
-            WireSplitGenerator
+            WireSplitGenerator,
+            // Generators added later go at the end, so that existing IDs keep their meaning.
+            BatchInverseGenerator,
+            BatchInverseGeneratorExtension<D>,
+            BitwiseGenerator,
+            SubgroupWitnessGenerator,
+            DivRemGenerator,
+            LimbsGenerator,
+            FloorDivGenerator,
+            NonnegativeGenerator
         }
     }
 }
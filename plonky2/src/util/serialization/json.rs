@@ -0,0 +1,639 @@
+//! A stable JSON encoding of proofs, verifier data and configs, meant for RPC APIs and test
+//! fixtures.
+//!
+//! Every document is wrapped in a [`JsonDocument`] carrying a schema name and a
+//! [`JSON_SCHEMA_VERSION`], and decoding rejects documents with any other schema or version.
+//! Within a document:
+//!
+//! - a base field element is a `0x`-prefixed, zero-padded, 16 digit hex string of its canonical
+//!   value, e.g. `"0x0000000000000007"`; non-canonical values are rejected when decoding,
+//! - an extension field element is an array of `D` base field elements, lowest degree first,
+//! - a hash is a `0x`-prefixed hex string of `GenericHashOut::to_bytes`,
+//! - a Merkle cap is an array of hashes.
+//!
+//! Structures which contain gates, such as `CommonCircuitData`, are stored as a readable summary,
+//! e.g. with the IDs of the gates, along with a hex string of their binary serialization, since
+//! gates can only be decoded with a `GateSerializer`. Decoding checks that the summary agrees with
+//! the binary serialization.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use anyhow::{anyhow, ensure, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::field::extension::{Extendable, FieldExtension};
+use crate::field::polynomial::PolynomialCoeffs;
+use crate::field::types::{Field64, PrimeField64};
+use crate::fri::proof::{FriInitialTreeProof, FriProof, FriQueryRound, FriQueryStep};
+use crate::fri::{FriConfig, FriParams};
+use crate::hash::hash_types::RichField;
+use crate::hash::merkle_proofs::MerkleProof;
+use crate::hash::merkle_tree::MerkleCap;
+use crate::plonk::circuit_data::{
+    CircuitConfig, CommonCircuitData, VerifierCircuitData, VerifierOnlyCircuitData,
+};
+use crate::plonk::config::{GenericConfig, GenericHashOut, Hasher};
+use crate::plonk::proof::{OpeningSet, Proof, ProofWithPublicInputs};
+use crate::util::serialization::GateSerializer;
+
+/// Version of the JSON schema. Bumped whenever the layout of any encoded structure changes.
+///
+/// Version 2 replaces the loose fields of verifier circuit data with a summary of its
+/// `CommonCircuitData`.
+pub const JSON_SCHEMA_VERSION: u32 = 2;
+
+/// A versioned, self-describing JSON document.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct JsonDocument<T> {
+    pub schema: String,
+    pub version: u32,
+    pub data: T,
+}
+
+impl<T: Serialize + DeserializeOwned> JsonDocument<T> {
+    pub fn new(schema: &str, data: T) -> Self {
+        Self {
+            schema: schema.to_string(),
+            version: JSON_SCHEMA_VERSION,
+            data,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Parses a document, checking that it has the expected schema and the current version.
+    pub fn from_json(json: &str, schema: &str) -> Result<T> {
+        let doc: Self = serde_json::from_str(json)?;
+        ensure!(
+            doc.schema == schema,
+            "Expected a {} document, got {}",
+            schema,
+            doc.schema
+        );
+        ensure!(
+            doc.version == JSON_SCHEMA_VERSION,
+            "Unsupported {} schema version {}, expected {}",
+            schema,
+            doc.version,
+            JSON_SCHEMA_VERSION
+        );
+        Ok(doc.data)
+    }
+}
+
+pub fn bytes_to_hex(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(2 + 2 * bytes.len());
+    s.push_str("0x");
+    for b in bytes {
+        s.push_str(&format!("{b:02x}"));
+    }
+    s
+}
+
+pub fn hex_to_bytes(s: &str) -> Result<Vec<u8>> {
+    let s = s
+        .strip_prefix("0x")
+        .ok_or_else(|| anyhow!("Hex string {} is missing the 0x prefix", s))?;
+    // Slicing below is by byte, which would panic in the middle of a multi-byte character.
+    ensure!(s.is_ascii(), "Hex string {} has non-ASCII characters", s);
+    ensure!(s.len() % 2 == 0, "Hex string has odd length");
+    (0..s.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&s[i..i + 2], 16).map_err(|_| anyhow!("Invalid hex string {}", s))
+        })
+        .collect()
+}
+
+pub fn field_to_hex<F: PrimeField64>(x: F) -> String {
+    format!("0x{:016x}", x.to_canonical_u64())
+}
+
+pub fn field_from_hex<F: Field64>(s: &str) -> Result<F> {
+    let digits = s
+        .strip_prefix("0x")
+        .ok_or_else(|| anyhow!("Field element {} is missing the 0x prefix", s))?;
+    let n = u64::from_str_radix(digits, 16).map_err(|_| anyhow!("Invalid field element {}", s))?;
+    ensure!(n < F::ORDER, "Non-canonical field element {}", s);
+    Ok(F::from_canonical_u64(n))
+}
+
+pub fn fields_to_hex<F: PrimeField64>(xs: &[F]) -> Vec<String> {
+    xs.iter().copied().map(field_to_hex).collect()
+}
+
+pub fn fields_from_hex<F: Field64>(xs: &[String]) -> Result<Vec<F>> {
+    xs.iter().map(|s| field_from_hex(s)).collect()
+}
+
+pub fn ext_to_hex<F: RichField + Extendable<D>, const D: usize>(x: F::Extension) -> Vec<String> {
+    fields_to_hex(&x.to_basefield_array())
+}
+
+pub fn ext_from_hex<F: RichField + Extendable<D>, const D: usize>(
+    x: &[String],
+) -> Result<F::Extension> {
+    ensure!(
+        x.len() == D,
+        "Expected an extension element with {} coefficients, got {}",
+        D,
+        x.len()
+    );
+    let coeffs: Vec<F> = fields_from_hex(x)?;
+    Ok(F::Extension::from_basefield_array(
+        coeffs.try_into().unwrap(),
+    ))
+}
+
+pub fn exts_to_hex<F: RichField + Extendable<D>, const D: usize>(
+    xs: &[F::Extension],
+) -> Vec<Vec<String>> {
+    xs.iter().map(|&x| ext_to_hex::<F, D>(x)).collect()
+}
+
+pub fn exts_from_hex<F: RichField + Extendable<D>, const D: usize>(
+    xs: &[Vec<String>],
+) -> Result<Vec<F::Extension>> {
+    xs.iter().map(|x| ext_from_hex::<F, D>(x)).collect()
+}
+
+pub fn hash_to_hex<F: RichField, H: GenericHashOut<F>>(h: &H) -> String {
+    bytes_to_hex(&h.to_bytes())
+}
+
+pub fn hash_from_hex<F: RichField, H: Hasher<F>>(s: &str) -> Result<H::Hash> {
+    let bytes = hex_to_bytes(s)?;
+    ensure!(
+        bytes.len() == H::HASH_SIZE,
+        "Expected a {} byte hash, got {} bytes",
+        H::HASH_SIZE,
+        bytes.len()
+    );
+    Ok(H::Hash::from_bytes(&bytes))
+}
+
+pub fn cap_to_hex<F: RichField, H: Hasher<F>>(cap: &MerkleCap<F, H>) -> Vec<String> {
+    cap.0.iter().map(hash_to_hex).collect()
+}
+
+pub fn cap_from_hex<F: RichField, H: Hasher<F>>(cap: &[String]) -> Result<MerkleCap<F, H>> {
+    Ok(MerkleCap(
+        cap.iter()
+            .map(|s| hash_from_hex::<F, H>(s))
+            .collect::<Result<_>>()?,
+    ))
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct JsonMerkleProof {
+    pub siblings: Vec<String>,
+}
+
+impl JsonMerkleProof {
+    pub fn new<F: RichField, H: Hasher<F>>(proof: &MerkleProof<F, H>) -> Self {
+        Self {
+            siblings: proof.siblings.iter().map(hash_to_hex).collect(),
+        }
+    }
+
+    pub fn decode<F: RichField, H: Hasher<F>>(&self) -> Result<MerkleProof<F, H>> {
+        Ok(MerkleProof {
+            siblings: self
+                .siblings
+                .iter()
+                .map(|s| hash_from_hex::<F, H>(s))
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct JsonFriQueryStep {
+    pub evals: Vec<Vec<String>>,
+    pub merkle_proof: JsonMerkleProof,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct JsonFriQueryRound {
+    /// For each initial oracle, the opened leaf and its Merkle proof.
+    pub initial_trees_proof: Vec<(Vec<String>, JsonMerkleProof)>,
+    pub steps: Vec<JsonFriQueryStep>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct JsonFriProof {
+    pub commit_phase_merkle_caps: Vec<Vec<String>>,
+    pub query_round_proofs: Vec<JsonFriQueryRound>,
+    pub final_poly: Vec<Vec<String>>,
+    pub pow_witness: String,
+}
+
+impl JsonFriProof {
+    pub fn new<F: RichField + Extendable<D>, H: Hasher<F>, const D: usize>(
+        proof: &FriProof<F, H, D>,
+    ) -> Self {
+        Self {
+            commit_phase_merkle_caps: proof
+                .commit_phase_merkle_caps
+                .iter()
+                .map(cap_to_hex)
+                .collect(),
+            query_round_proofs: proof
+                .query_round_proofs
+                .iter()
+                .map(|round| JsonFriQueryRound {
+                    initial_trees_proof: round
+                        .initial_trees_proof
+                        .evals_proofs
+                        .iter()
+                        .map(|(evals, proof)| (fields_to_hex(evals), JsonMerkleProof::new(proof)))
+                        .collect(),
+                    steps: round
+                        .steps
+                        .iter()
+                        .map(|step| JsonFriQueryStep {
+                            evals: exts_to_hex::<F, D>(&step.evals),
+                            merkle_proof: JsonMerkleProof::new(&step.merkle_proof),
+                        })
+                        .collect(),
+                })
+                .collect(),
+            final_poly: exts_to_hex::<F, D>(&proof.final_poly.coeffs),
+            pow_witness: field_to_hex(proof.pow_witness),
+        }
+    }
+
+    pub fn decode<F: RichField + Extendable<D>, H: Hasher<F>, const D: usize>(
+        &self,
+    ) -> Result<FriProof<F, H, D>> {
+        Ok(FriProof {
+            commit_phase_merkle_caps: self
+                .commit_phase_merkle_caps
+                .iter()
+                .map(|cap| cap_from_hex(cap))
+                .collect::<Result<_>>()?,
+            query_round_proofs: self
+                .query_round_proofs
+                .iter()
+                .map(|round| {
+                    Ok(FriQueryRound {
+                        initial_trees_proof: FriInitialTreeProof {
+                            evals_proofs: round
+                                .initial_trees_proof
+                                .iter()
+                                .map(|(evals, proof)| {
+                                    Ok((fields_from_hex(evals)?, proof.decode()?))
+                                })
+                                .collect::<Result<_>>()?,
+                        },
+                        steps: round
+                            .steps
+                            .iter()
+                            .map(|step| {
+                                Ok(FriQueryStep {
+                                    evals: exts_from_hex::<F, D>(&step.evals)?,
+                                    merkle_proof: step.merkle_proof.decode()?,
+                                })
+                            })
+                            .collect::<Result<_>>()?,
+                    })
+                })
+                .collect::<Result<_>>()?,
+            final_poly: PolynomialCoeffs::new(exts_from_hex::<F, D>(&self.final_poly)?),
+            pow_witness: field_from_hex(&self.pow_witness)?,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct JsonOpeningSet {
+    pub constants: Vec<Vec<String>>,
+    pub plonk_sigmas: Vec<Vec<String>>,
+    pub wires: Vec<Vec<String>>,
+    pub plonk_zs: Vec<Vec<String>>,
+    pub plonk_zs_next: Vec<Vec<String>>,
+    pub partial_products: Vec<Vec<String>>,
+    pub quotient_polys: Vec<Vec<String>>,
+    pub lookup_zs: Vec<Vec<String>>,
+    pub lookup_zs_next: Vec<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct JsonProof {
+    pub wires_cap: Vec<String>,
+    pub plonk_zs_partial_products_cap: Vec<String>,
+    pub quotient_polys_cap: Vec<String>,
+    pub openings: JsonOpeningSet,
+    pub opening_proof: JsonFriProof,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct JsonProofWithPublicInputs {
+    pub proof: JsonProof,
+    pub public_inputs: Vec<String>,
+}
+
+impl JsonProofWithPublicInputs {
+    pub const SCHEMA: &'static str = "plonky2/proof_with_public_inputs";
+
+    pub fn new<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+        proof_with_pis: &ProofWithPublicInputs<F, C, D>,
+    ) -> Self {
+        let proof = &proof_with_pis.proof;
+        let o = &proof.openings;
+        Self {
+            proof: JsonProof {
+                wires_cap: cap_to_hex(&proof.wires_cap),
+                plonk_zs_partial_products_cap: cap_to_hex(&proof.plonk_zs_partial_products_cap),
+                quotient_polys_cap: cap_to_hex(&proof.quotient_polys_cap),
+                openings: JsonOpeningSet {
+                    constants: exts_to_hex::<F, D>(&o.constants),
+                    plonk_sigmas: exts_to_hex::<F, D>(&o.plonk_sigmas),
+                    wires: exts_to_hex::<F, D>(&o.wires),
+                    plonk_zs: exts_to_hex::<F, D>(&o.plonk_zs),
+                    plonk_zs_next: exts_to_hex::<F, D>(&o.plonk_zs_next),
+                    partial_products: exts_to_hex::<F, D>(&o.partial_products),
+                    quotient_polys: exts_to_hex::<F, D>(&o.quotient_polys),
+                    lookup_zs: exts_to_hex::<F, D>(&o.lookup_zs),
+                    lookup_zs_next: exts_to_hex::<F, D>(&o.lookup_zs_next),
+                },
+                opening_proof: JsonFriProof::new(&proof.opening_proof),
+            },
+            public_inputs: fields_to_hex(&proof_with_pis.public_inputs),
+        }
+    }
+
+    pub fn decode<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+        &self,
+    ) -> Result<ProofWithPublicInputs<F, C, D>> {
+        let proof = &self.proof;
+        let o = &proof.openings;
+        Ok(ProofWithPublicInputs {
+            proof: Proof {
+                wires_cap: cap_from_hex(&proof.wires_cap)?,
+                plonk_zs_partial_products_cap: cap_from_hex(&proof.plonk_zs_partial_products_cap)?,
+                quotient_polys_cap: cap_from_hex(&proof.quotient_polys_cap)?,
+                openings: OpeningSet {
+                    constants: exts_from_hex::<F, D>(&o.constants)?,
+                    plonk_sigmas: exts_from_hex::<F, D>(&o.plonk_sigmas)?,
+                    wires: exts_from_hex::<F, D>(&o.wires)?,
+                    plonk_zs: exts_from_hex::<F, D>(&o.plonk_zs)?,
+                    plonk_zs_next: exts_from_hex::<F, D>(&o.plonk_zs_next)?,
+                    partial_products: exts_from_hex::<F, D>(&o.partial_products)?,
+                    quotient_polys: exts_from_hex::<F, D>(&o.quotient_polys)?,
+                    lookup_zs: exts_from_hex::<F, D>(&o.lookup_zs)?,
+                    lookup_zs_next: exts_from_hex::<F, D>(&o.lookup_zs_next)?,
+                },
+                opening_proof: proof.opening_proof.decode()?,
+            },
+            public_inputs: fields_from_hex(&self.public_inputs)?,
+        })
+    }
+}
+
+/// A readable summary of `CommonCircuitData`. Gates are listed by their IDs, and lookup tables by
+/// their number.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct JsonCommonCircuitData {
+    pub config: CircuitConfig,
+    pub fri_params: FriParams,
+    pub gates: Vec<String>,
+    pub selector_indices: Vec<usize>,
+    pub selector_groups: Vec<Range<usize>>,
+    pub quotient_degree_factor: usize,
+    pub num_gate_constraints: usize,
+    pub num_constants: usize,
+    pub num_public_inputs: usize,
+    pub k_is: Vec<String>,
+    pub num_partial_products: usize,
+    pub num_lookup_polys: usize,
+    pub num_lookup_selectors: usize,
+    pub num_luts: usize,
+}
+
+impl JsonCommonCircuitData {
+    pub fn new<F: RichField + Extendable<D>, const D: usize>(
+        common: &CommonCircuitData<F, D>,
+    ) -> Self {
+        Self {
+            config: common.config.clone(),
+            fri_params: common.fri_params.clone(),
+            gates: common.gates.iter().map(|gate| gate.0.id()).collect(),
+            selector_indices: common.selectors_info.selector_indices.clone(),
+            selector_groups: common.selectors_info.groups.clone(),
+            quotient_degree_factor: common.quotient_degree_factor,
+            num_gate_constraints: common.num_gate_constraints,
+            num_constants: common.num_constants,
+            num_public_inputs: common.num_public_inputs,
+            k_is: fields_to_hex(&common.k_is),
+            num_partial_products: common.num_partial_products,
+            num_lookup_polys: common.num_lookup_polys,
+            num_lookup_selectors: common.num_lookup_selectors,
+            num_luts: common.luts.len(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct JsonVerifierCircuitData {
+    pub constants_sigmas_cap: Vec<String>,
+    pub circuit_digest: String,
+    pub common: JsonCommonCircuitData,
+    /// Hex encoding of the binary serialization of `CommonCircuitData`, from which it is decoded.
+    pub common_data: String,
+}
+
+impl JsonVerifierCircuitData {
+    pub const SCHEMA: &'static str = "plonky2/verifier_circuit_data";
+
+    pub fn new<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+        data: &VerifierCircuitData<F, C, D>,
+        gate_serializer: &dyn GateSerializer<F, D>,
+    ) -> Result<Self> {
+        let common_data = data
+            .common
+            .to_bytes(gate_serializer)
+            .map_err(|_| anyhow!("Failed to serialize common circuit data"))?;
+        Ok(Self {
+            constants_sigmas_cap: cap_to_hex(&data.verifier_only.constants_sigmas_cap),
+            circuit_digest: hash_to_hex(&data.verifier_only.circuit_digest),
+            common: JsonCommonCircuitData::new(&data.common),
+            common_data: bytes_to_hex(&common_data),
+        })
+    }
+
+    pub fn decode<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+        &self,
+        gate_serializer: &dyn GateSerializer<F, D>,
+    ) -> Result<VerifierCircuitData<F, C, D>> {
+        let common =
+            CommonCircuitData::from_bytes(hex_to_bytes(&self.common_data)?, gate_serializer)
+                .map_err(|_| anyhow!("Failed to deserialize common circuit data"))?;
+        ensure!(
+            JsonCommonCircuitData::new(&common) == self.common,
+            "The summary of the common circuit data disagrees with its serialization"
+        );
+        Ok(VerifierCircuitData {
+            verifier_only: VerifierOnlyCircuitData {
+                constants_sigmas_cap: cap_from_hex(&self.constants_sigmas_cap)?,
+                circuit_digest: hash_from_hex::<F, C::Hasher>(&self.circuit_digest)?,
+            },
+            common,
+        })
+    }
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    ProofWithPublicInputs<F, C, D>
+{
+    pub fn to_json(&self) -> Result<String> {
+        JsonDocument::new(
+            JsonProofWithPublicInputs::SCHEMA,
+            JsonProofWithPublicInputs::new(self),
+        )
+        .to_json()
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        JsonDocument::<JsonProofWithPublicInputs>::from_json(
+            json,
+            JsonProofWithPublicInputs::SCHEMA,
+        )?
+        .decode()
+    }
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    VerifierCircuitData<F, C, D>
+{
+    pub fn to_json(&self, gate_serializer: &dyn GateSerializer<F, D>) -> Result<String> {
+        JsonDocument::new(
+            JsonVerifierCircuitData::SCHEMA,
+            JsonVerifierCircuitData::new(self, gate_serializer)?,
+        )
+        .to_json()
+    }
+
+    pub fn from_json(json: &str, gate_serializer: &dyn GateSerializer<F, D>) -> Result<Self> {
+        JsonDocument::<JsonVerifierCircuitData>::from_json(json, JsonVerifierCircuitData::SCHEMA)?
+            .decode(gate_serializer)
+    }
+}
+
+impl CircuitConfig {
+    pub const JSON_SCHEMA: &'static str = "plonky2/circuit_config";
+
+    pub fn to_json(&self) -> Result<String> {
+        JsonDocument::new(Self::JSON_SCHEMA, self.clone()).to_json()
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        JsonDocument::from_json(json, Self::JSON_SCHEMA)
+    }
+}
+
+impl FriConfig {
+    pub const JSON_SCHEMA: &'static str = "plonky2/fri_config";
+
+    pub fn to_json(&self) -> Result<String> {
+        JsonDocument::new(Self::JSON_SCHEMA, self.clone()).to_json()
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        JsonDocument::from_json(json, Self::JSON_SCHEMA)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::field::goldilocks_field::GoldilocksField;
+    use crate::field::types::Field;
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+    use crate::util::serialization::DefaultGateSerializer;
+
+    #[test]
+    fn test_field_hex() -> Result<()> {
+        type F = GoldilocksField;
+        assert_eq!(field_to_hex(F::from_canonical_u64(7)), "0x0000000000000007");
+        assert_eq!(
+            field_from_hex::<F>("0x0000000000000007")?,
+            F::from_canonical_u64(7)
+        );
+        assert!(field_from_hex::<F>("0xffffffffffffffff").is_err());
+        assert!(field_from_hex::<F>("7").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_hex_to_bytes() -> Result<()> {
+        assert_eq!(hex_to_bytes("0x00ff")?, vec![0, 255]);
+        assert!(hex_to_bytes("0x0").is_err());
+        assert!(hex_to_bytes("0xzz").is_err());
+        // Multi-byte characters must be rejected rather than sliced through.
+        assert!(hex_to_bytes("0x0é0").is_err());
+        assert!(hex_to_bytes("0x00é0").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_round_trip() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        assert_eq!(CircuitConfig::from_json(&config.to_json()?)?, config);
+
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+        let x = builder.add_virtual_target();
+        let y = builder.square(x);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(3));
+        let proof = data.prove(pw)?;
+
+        let decoded_proof = ProofWithPublicInputs::<F, C, D>::from_json(&proof.to_json()?)?;
+        assert_eq!(decoded_proof, proof);
+
+        let gate_serializer = DefaultGateSerializer;
+        let verifier_data = data.verifier_data();
+        let decoded_verifier_data = VerifierCircuitData::<F, C, D>::from_json(
+            &verifier_data.to_json(&gate_serializer)?,
+            &gate_serializer,
+        )?;
+        assert_eq!(
+            decoded_verifier_data.verifier_only,
+            verifier_data.verifier_only
+        );
+        assert_eq!(decoded_verifier_data.common, verifier_data.common);
+        decoded_verifier_data.verify(decoded_proof)?;
+
+        // The summary is readable, and must agree with the serialized common data.
+        let mut json_data = JsonVerifierCircuitData::new(&verifier_data, &gate_serializer)?;
+        assert_eq!(json_data.common.num_public_inputs, 1);
+        assert!(json_data
+            .common
+            .gates
+            .iter()
+            .any(|id| id.starts_with("ArithmeticGate")));
+        json_data.common.num_public_inputs = 2;
+        assert!(json_data.decode::<F, C, D>(&gate_serializer).is_err());
+
+        // Documents of another schema must be rejected.
+        assert!(ProofWithPublicInputs::<F, C, D>::from_json(&config.to_json()?).is_err());
+        Ok(())
+    }
+}
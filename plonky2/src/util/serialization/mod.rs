@@ -4,7 +4,12 @@ pub mod generator_serialization;
 #[macro_use]
 pub mod gate_serialization;
 
+pub mod gate_registry;
+
+pub mod json;
+
 use alloc::collections::BTreeMap;
+use alloc::string::String;
 use alloc::sync::Arc;
 use alloc::vec;
 use alloc::vec::Vec;
@@ -13,6 +18,7 @@ use core::fmt::{Debug, Display, Formatter};
 use core::mem::size_of;
 use core::ops::Range;
 
+pub use gate_registry::GateRegistry;
 pub use gate_serialization::default::DefaultGateSerializer;
 pub use gate_serialization::GateSerializer;
 pub use generator_serialization::default::DefaultGeneratorSerializer;
@@ -28,6 +34,8 @@ use crate::fri::proof::{
     FriProof, FriProofTarget, FriQueryRound, FriQueryRoundTarget, FriQueryStep, FriQueryStepTarget,
 };
 use crate::fri::reduction_strategies::FriReductionStrategy;
+use crate::fri::security::SecurityAssumption;
+use crate::fri::structure::FriOracleInfo;
 use crate::fri::{FriConfig, FriParams};
 use crate::gadgets::polynomial::PolynomialCoeffsExtTarget;
 use crate::gates::gate::GateRef;
@@ -52,6 +60,15 @@ use crate::plonk::proof::{
     ProofTarget, ProofWithPublicInputs, ProofWithPublicInputsTarget,
 };
 
+/// The version of the binary encoding of [`CommonCircuitData`] and [`ProverOnlyCircuitData`],
+/// written ahead of each so that data serialized by an incompatible release is rejected rather
+/// than misread.
+///
+/// Version 1 added the security assumption and the optional coset shift to the encoding of
+/// [`FriConfig`]; earlier encodings carried no version byte. Version 2 added the target names of
+/// [`ProverOnlyCircuitData`], and its version byte.
+pub const CIRCUIT_DATA_FORMAT_VERSION: u8 = 2;
+
 /// A no_std compatible variant of `std::io::Error`
 #[derive(Debug)]
 pub struct IoError;
@@ -76,11 +93,55 @@ pub trait Remaining: Read {
     }
 }
 
+/// The sizes of the leaves of the four oracles opened by a PLONK proof.
+fn plonk_oracle_leaf_sizes<F: RichField + Extendable<D>, const D: usize>(
+    common_data: &CommonCircuitData<F, D>,
+) -> [usize; 4] {
+    let config = &common_data.config;
+    let salt = salt_size(common_data.fri_params.hiding);
+    [
+        common_data.num_constants + config.num_routed_wires,
+        config.num_wires + salt,
+        config.num_challenges
+            * (1 + common_data.num_partial_products + common_data.num_lookup_polys)
+            + salt,
+        config.num_challenges * common_data.quotient_degree_factor + salt,
+    ]
+}
+
+/// The number of siblings in a Merkle proof for a tree with `2^codeword_len_bits` leaves.
+fn merkle_proof_len(codeword_len_bits: usize, params: &FriParams) -> IoResult<usize> {
+    codeword_len_bits
+        .checked_sub(params.config.cap_height)
+        .ok_or(IoError)
+}
+
+/// Checks that a Merkle proof has `len` siblings, or at most `len` if it is `compressed`, since
+/// compression drops the siblings which other proofs of the same query round already determine.
+fn check_merkle_proof_len<F: RichField, H: Hasher<F>>(
+    proof: &MerkleProof<F, H>,
+    len: usize,
+    compressed: bool,
+) -> IoResult<()> {
+    let num_siblings = proof.siblings.len();
+    if num_siblings == len || (compressed && num_siblings < len) {
+        Ok(())
+    } else {
+        Err(IoError)
+    }
+}
+
 /// Similar to `std::io::Read`, but works with no_std.
 pub trait Read {
     /// Reads exactly the length of `bytes` from `self` and writes it to `bytes`.
     fn read_exact(&mut self, bytes: &mut [u8]) -> IoResult<()>;
 
+    /// The number of bytes left to read, if known. Length prefixes larger than this are rejected
+    /// before anything is allocated for them.
+    fn remaining_len(&self) -> Option<usize> {
+        None
+    }
+
     /// Reads a `bool` value from `self`.
     #[inline]
     fn read_bool(&mut self) -> IoResult<bool> {
@@ -131,6 +192,14 @@ pub trait Read {
         Ok(u32::from_le_bytes(buf))
     }
 
+    /// Reads a `u64` value from `self`.
+    #[inline]
+    fn read_u64(&mut self) -> IoResult<u64> {
+        let mut buf = [0; size_of::<u64>()];
+        self.read_exact(&mut buf)?;
+        Ok(u64::from_le_bytes(buf))
+    }
+
     /// Reads a `usize` value from `self`.
     #[inline]
     fn read_usize(&mut self) -> IoResult<usize> {
@@ -151,7 +220,25 @@ pub trait Read {
         Ok(res)
     }
 
-    /// Reads a element from the field `F` with size less than `2^64` from `self.`
+    /// Reads a UTF-8 string prefixed with its length in bytes.
+    fn read_string(&mut self) -> IoResult<String> {
+        let len = self.read_usize()?;
+        if matches!(self.remaining_len(), Some(remaining) if len > remaining) {
+            return Err(IoError);
+        }
+        // The length is untrusted, so if the remaining length is unknown, only grow the buffer as
+        // bytes are actually read.
+        let mut bytes = Vec::new();
+        while bytes.len() < len {
+            let start = bytes.len();
+            bytes.resize(start + (len - start).min(1 << 12), 0);
+            self.read_exact(&mut bytes[start..])?;
+        }
+        String::from_utf8(bytes).map_err(|_| IoError)
+    }
+
+    /// Reads a element from the field `F` with size less than `2^64` from `self.` Fails if the
+    /// encoding is not canonical.
     #[inline]
     fn read_field<F>(&mut self) -> IoResult<F>
     where
@@ -159,7 +246,11 @@ pub trait Read {
     {
         let mut buf = [0; size_of::<u64>()];
         self.read_exact(&mut buf)?;
-        Ok(F::from_canonical_u64(u64::from_le_bytes(buf)))
+        let n = u64::from_le_bytes(buf);
+        if n >= F::ORDER {
+            return Err(IoError);
+        }
+        Ok(F::from_canonical_u64(n))
     }
 
     /// Reads a vector of elements from the field `F` from `self`.
@@ -252,7 +343,7 @@ pub trait Read {
             .collect::<Result<Vec<_>, _>>()
     }
 
-    /// Reads a hash value from `self`.
+    /// Reads a hash value from `self`. Fails if the encoding is not canonical.
     #[inline]
     fn read_hash<F, H>(&mut self) -> IoResult<H::Hash>
     where
@@ -261,7 +352,11 @@ pub trait Read {
     {
         let mut buf = vec![0; H::HASH_SIZE];
         self.read_exact(&mut buf)?;
-        Ok(H::Hash::from_bytes(&buf))
+        let hash = H::Hash::from_bytes(&buf);
+        if hash.to_bytes() != buf {
+            return Err(IoError);
+        }
+        Ok(hash)
     }
 
     /// Reads a HashOutTarget value from `self`.
@@ -294,6 +389,9 @@ pub trait Read {
         F: RichField,
         H: Hasher<F>,
     {
+        if cap_height >= usize::BITS as usize {
+            return Err(IoError);
+        }
         let cap_length = 1 << cap_height;
         Ok(MerkleCap(
             (0..cap_length)
@@ -426,7 +524,8 @@ pub trait Read {
         })
     }
 
-    /// Reads a value of type [`FriInitialTreeProof`] from `self` with the given `common_data`.
+    /// Reads a value of type [`FriInitialTreeProof`] from `self` with the given `common_data`, as
+    /// found in a [`CompressedFriQueryRounds`], whose Merkle proofs may be compressed.
     #[inline]
     fn read_fri_initial_proof<F, C, const D: usize>(
         &mut self,
@@ -436,32 +535,38 @@ pub trait Read {
         F: RichField + Extendable<D>,
         C: GenericConfig<D, F = F>,
     {
-        let config = &common_data.config;
-        let salt = salt_size(common_data.fri_params.hiding);
-        let mut evals_proofs = Vec::with_capacity(4);
-
-        let constants_sigmas_v =
-            self.read_field_vec(common_data.num_constants + config.num_routed_wires)?;
-        let constants_sigmas_p = self.read_merkle_proof()?;
-        evals_proofs.push((constants_sigmas_v, constants_sigmas_p));
-
-        let wires_v = self.read_field_vec(config.num_wires + salt)?;
-        let wires_p = self.read_merkle_proof()?;
-        evals_proofs.push((wires_v, wires_p));
-
-        let zs_partial_v = self.read_field_vec(
-            config.num_challenges
-                * (1 + common_data.num_partial_products + common_data.num_lookup_polys)
-                + salt,
-        )?;
-        let zs_partial_p = self.read_merkle_proof()?;
-        evals_proofs.push((zs_partial_v, zs_partial_p));
-
-        let quotient_v =
-            self.read_field_vec(config.num_challenges * common_data.quotient_degree_factor + salt)?;
-        let quotient_p = self.read_merkle_proof()?;
-        evals_proofs.push((quotient_v, quotient_p));
+        let merkle_proof_len =
+            merkle_proof_len(common_data.fri_params.lde_bits(), &common_data.fri_params)?;
+        self.read_fri_initial_proof_for_oracles::<F, C, D>(
+            &plonk_oracle_leaf_sizes(common_data),
+            &[merkle_proof_len; 4],
+            true,
+        )
+    }
 
+    /// Reads a value of type [`FriInitialTreeProof`] from `self`, for oracles whose leaves and
+    /// Merkle proofs have the given sizes, and with the given `compressed` flag.
+    #[inline]
+    fn read_fri_initial_proof_for_oracles<F, C, const D: usize>(
+        &mut self,
+        leaf_sizes: &[usize],
+        merkle_proof_lens: &[usize],
+        compressed: bool,
+    ) -> IoResult<FriInitialTreeProof<F, C::Hasher>>
+    where
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+    {
+        let evals_proofs = leaf_sizes
+            .iter()
+            .zip(merkle_proof_lens)
+            .map(|(&leaf_size, &merkle_proof_len)| {
+                let evals = self.read_field_vec(leaf_size)?;
+                let merkle_proof = self.read_merkle_proof()?;
+                check_merkle_proof_len(&merkle_proof, merkle_proof_len, compressed)?;
+                Ok((evals, merkle_proof))
+            })
+            .collect::<IoResult<Vec<_>>>()?;
         Ok(FriInitialTreeProof { evals_proofs })
     }
 
@@ -520,16 +625,51 @@ pub trait Read {
         F: RichField + Extendable<D>,
         C: GenericConfig<D, F = F>,
     {
-        let config = &common_data.config;
-        let mut fqrs = Vec::with_capacity(config.fri_config.num_query_rounds);
-        for _ in 0..config.fri_config.num_query_rounds {
-            let initial_trees_proof = self.read_fri_initial_proof::<F, C, D>(common_data)?;
-            let steps = common_data
-                .fri_params
+        let merkle_proof_len =
+            merkle_proof_len(common_data.fri_params.lde_bits(), &common_data.fri_params)?;
+        self.read_fri_query_rounds_for_oracles::<F, C, D>(
+            &plonk_oracle_leaf_sizes(common_data),
+            &[merkle_proof_len; 4],
+            &common_data.fri_params,
+        )
+    }
+
+    /// Reads a vector of [`FriQueryRound`]s from `self`, for oracles whose leaves and initial
+    /// Merkle proofs have the given sizes.
+    #[inline]
+    #[allow(clippy::type_complexity)]
+    fn read_fri_query_rounds_for_oracles<F, C, const D: usize>(
+        &mut self,
+        leaf_sizes: &[usize],
+        initial_merkle_proof_lens: &[usize],
+        params: &FriParams,
+    ) -> IoResult<Vec<FriQueryRound<F, C::Hasher, D>>>
+    where
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+    {
+        let mut fqrs = Vec::with_capacity(params.config.num_query_rounds);
+        for _ in 0..params.config.num_query_rounds {
+            let initial_trees_proof = self.read_fri_initial_proof_for_oracles::<F, C, D>(
+                leaf_sizes,
+                initial_merkle_proof_lens,
+                false,
+            )?;
+            let mut codeword_len_bits = params.lde_bits();
+            let steps = params
                 .reduction_arity_bits
                 .iter()
-                .map(|&ar| self.read_fri_query_step::<F, C, D>(1 << ar, false))
-                .collect::<Result<_, _>>()?;
+                .map(|&arity_bits| {
+                    let step = self.read_fri_query_step::<F, C, D>(1 << arity_bits, false)?;
+                    codeword_len_bits = codeword_len_bits.checked_sub(arity_bits).ok_or(IoError)?;
+                    check_merkle_proof_len(
+                        &step.merkle_proof,
+                        merkle_proof_len(codeword_len_bits, params)?,
+                        false,
+                    )?;
+                    Ok(step)
+                })
+                .collect::<IoResult<_>>()?;
             fqrs.push(FriQueryRound {
                 initial_trees_proof,
                 steps,
@@ -569,14 +709,81 @@ pub trait Read {
         F: RichField + Extendable<D>,
         C: GenericConfig<D, F = F>,
     {
-        let config = &common_data.config;
-        let commit_phase_merkle_caps = (0..common_data.fri_params.reduction_arity_bits.len())
-            .map(|_| self.read_merkle_cap(config.fri_config.cap_height))
+        self.read_fri_proof_for_oracles::<F, C, D>(
+            &plonk_oracle_leaf_sizes(common_data),
+            &common_data.fri_params,
+        )
+    }
+
+    /// Reads a value of type [`FriProof`] from `self`, for a batch opening of oracles whose leaves
+    /// have the given sizes. Unlike [`Self::read_fri_proof`], this doesn't depend on the layout of
+    /// a PLONK circuit, so other FRI-based proof systems can use it too.
+    #[inline]
+    fn read_fri_proof_for_oracles<F, C, const D: usize>(
+        &mut self,
+        leaf_sizes: &[usize],
+        params: &FriParams,
+    ) -> IoResult<FriProof<F, C::Hasher, D>>
+    where
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+    {
+        let merkle_proof_len = merkle_proof_len(params.lde_bits(), params)?;
+        self.read_fri_proof_with_merkle_proof_lens::<F, C, D>(
+            leaf_sizes,
+            &vec![merkle_proof_len; leaf_sizes.len()],
+            params,
+        )
+    }
+
+    /// Like [`Self::read_fri_proof_for_oracles`], with each oracle's leaf and Merkle proof sizes
+    /// given by its [`FriOracleInfo`], so oracles may have extra rate bits or leaf groups.
+    #[inline]
+    fn read_fri_proof_for_oracle_infos<F, C, const D: usize>(
+        &mut self,
+        oracles: &[FriOracleInfo],
+        params: &FriParams,
+    ) -> IoResult<FriProof<F, C::Hasher, D>>
+    where
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+    {
+        let leaf_sizes = oracles
+            .iter()
+            .map(|oracle| oracle.leaf_len(params.hiding))
+            .collect::<Vec<_>>();
+        let merkle_proof_lens = oracles
+            .iter()
+            .map(|oracle| oracle.merkle_proof_len(params).ok_or(IoError))
+            .collect::<IoResult<Vec<_>>>()?;
+        self.read_fri_proof_with_merkle_proof_lens::<F, C, D>(
+            &leaf_sizes,
+            &merkle_proof_lens,
+            params,
+        )
+    }
+
+    #[inline]
+    fn read_fri_proof_with_merkle_proof_lens<F, C, const D: usize>(
+        &mut self,
+        leaf_sizes: &[usize],
+        initial_merkle_proof_lens: &[usize],
+        params: &FriParams,
+    ) -> IoResult<FriProof<F, C::Hasher, D>>
+    where
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+    {
+        let commit_phase_merkle_caps = (0..params.reduction_arity_bits.len())
+            .map(|_| self.read_merkle_cap(params.config.cap_height))
             .collect::<Result<Vec<_>, _>>()?;
-        let query_round_proofs = self.read_fri_query_rounds::<F, C, D>(common_data)?;
-        let final_poly = PolynomialCoeffs::new(
-            self.read_field_ext_vec::<F, D>(common_data.fri_params.final_poly_len())?,
-        );
+        let query_round_proofs = self.read_fri_query_rounds_for_oracles::<F, C, D>(
+            leaf_sizes,
+            initial_merkle_proof_lens,
+            params,
+        )?;
+        let final_poly =
+            PolynomialCoeffs::new(self.read_field_ext_vec::<F, D>(params.final_poly_len())?);
         let pow_witness = self.read_field()?;
         Ok(FriProof {
             commit_phase_merkle_caps,
@@ -642,6 +849,16 @@ pub trait Read {
         let num_query_rounds = self.read_usize()?;
         let proof_of_work_bits = self.read_u32()?;
         let reduction_strategy = self.read_fri_reduction_strategy()?;
+        let security_assumption = match self.read_u8()? {
+            0 => SecurityAssumption::Conjectured,
+            1 => SecurityAssumption::Proven,
+            _ => return Err(IoError),
+        };
+        let coset_shift = if self.read_bool()? {
+            Some(self.read_u64()?)
+        } else {
+            None
+        };
 
         Ok(FriConfig {
             rate_bits,
@@ -649,6 +866,8 @@ pub trait Read {
             num_query_rounds,
             proof_of_work_bits,
             reduction_strategy,
+            security_assumption,
+            coset_shift,
         })
     }
 
@@ -750,8 +969,18 @@ pub trait Read {
         &mut self,
         gate_serializer: &dyn GateSerializer<F, D>,
     ) -> IoResult<CommonCircuitData<F, D>> {
+        if self.read_u8()? != CIRCUIT_DATA_FORMAT_VERSION {
+            return Err(IoError);
+        }
         let config = self.read_circuit_config()?;
         let fri_params = self.read_fri_params()?;
+        // A coset shift lying in the LDE subgroup would make the quotient polynomial undefined on
+        // the LDE, so untrusted data must not be able to select one.
+        for fri_config in [&config.fri_config, &fri_params.config] {
+            fri_config
+                .validate_coset_shift::<F>(fri_params.degree_bits)
+                .map_err(|_| IoError)?;
+        }
 
         let selectors_info = self.read_selectors_info()?;
         let quotient_degree_factor = self.read_usize()?;
@@ -832,6 +1061,9 @@ pub trait Read {
         generator_serializer: &dyn WitnessGeneratorSerializer<F, D>,
         common_data: &CommonCircuitData<F, D>,
     ) -> IoResult<ProverOnlyCircuitData<F, C, D>> {
+        if self.read_u8()? != CIRCUIT_DATA_FORMAT_VERSION {
+            return Err(IoError);
+        }
         let gen_len = self.read_usize()?;
         let mut generators = Vec::with_capacity(gen_len);
         for _ in 0..gen_len {
@@ -891,6 +1123,13 @@ pub trait Read {
             lut_to_lookups.push(self.read_target_lut()?);
         }
 
+        let length = self.read_usize()?;
+        let mut target_names = BTreeMap::new();
+        for _ in 0..length {
+            let rep = self.read_usize()?;
+            target_names.insert(rep, self.read_string()?);
+        }
+
         Ok(ProverOnlyCircuitData {
             generators,
             generator_indices_by_watches,
@@ -903,6 +1142,7 @@ pub trait Read {
             circuit_digest,
             lookup_rows,
             lut_to_lookups,
+            target_names,
         })
     }
 
@@ -1019,6 +1259,9 @@ pub trait Read {
     {
         let proof = self.read_proof(common_data)?;
         let pi_len = self.read_usize()?;
+        if pi_len != common_data.num_public_inputs {
+            return Err(IoError);
+        }
         let public_inputs = self.read_field_vec(pi_len)?;
         Ok(ProofWithPublicInputs {
             proof,
@@ -1050,8 +1293,12 @@ pub trait Read {
         C: GenericConfig<D, F = F>,
     {
         let config = &common_data.config;
+        let lde_size = 1 << common_data.fri_params.lde_bits();
         let original_indices = (0..config.fri_config.num_query_rounds)
-            .map(|_| self.read_u32().map(|i| i as usize))
+            .map(|_| match self.read_u32()? as usize {
+                i if i < lde_size => Ok(i),
+                _ => Err(IoError),
+            })
             .collect::<Result<Vec<_>, _>>()?;
         let mut indices = original_indices.clone();
         indices.sort_unstable();
@@ -1063,13 +1310,20 @@ pub trait Read {
         let initial_trees_proofs = HashMap::from_iter(pairs);
 
         let mut steps = Vec::with_capacity(common_data.fri_params.reduction_arity_bits.len());
+        let mut codeword_len_bits = common_data.fri_params.lde_bits();
         for &a in &common_data.fri_params.reduction_arity_bits {
             indices.iter_mut().for_each(|x| {
                 *x >>= a;
             });
             indices.dedup();
+            codeword_len_bits = codeword_len_bits.checked_sub(a).ok_or(IoError)?;
+            let proof_len = merkle_proof_len(codeword_len_bits, &common_data.fri_params)?;
             let query_steps = (0..indices.len())
-                .map(|_| self.read_fri_query_step::<F, C, D>(1 << a, true))
+                .map(|_| {
+                    let step = self.read_fri_query_step::<F, C, D>(1 << a, true)?;
+                    check_merkle_proof_len(&step.merkle_proof, proof_len, true)?;
+                    Ok(step)
+                })
                 .collect::<Result<Vec<_>, _>>()?;
             steps.push(
                 indices
@@ -1151,7 +1405,10 @@ pub trait Read {
         C: GenericConfig<D, F = F>,
     {
         let proof = self.read_compressed_proof(common_data)?;
-        let public_inputs = self.read_field_vec(self.remaining() / size_of::<u64>())?;
+        if self.remaining() != common_data.num_public_inputs * size_of::<u64>() {
+            return Err(IoError);
+        }
+        let public_inputs = self.read_field_vec(common_data.num_public_inputs)?;
         Ok(CompressedProofWithPublicInputs {
             proof,
             public_inputs,
@@ -1232,6 +1489,12 @@ pub trait Write {
         self.write_all(&x.to_le_bytes())
     }
 
+    /// Writes a word `x` to `self.`
+    #[inline]
+    fn write_u64(&mut self, x: u64) -> IoResult<()> {
+        self.write_all(&x.to_le_bytes())
+    }
+
     /// Writes a word `x` to `self.`
     #[inline]
     fn write_usize(&mut self, x: usize) -> IoResult<()> {
@@ -1249,6 +1512,12 @@ pub trait Write {
         Ok(())
     }
 
+    /// Writes a UTF-8 string prefixed with its length in bytes.
+    fn write_string(&mut self, s: &str) -> IoResult<()> {
+        self.write_usize(s.len())?;
+        self.write_all(s.as_bytes())
+    }
+
     /// Writes an element `x` from the field `F` to `self`.
     #[inline]
     fn write_field<F>(&mut self, x: F) -> IoResult<()>
@@ -1659,6 +1928,8 @@ pub trait Write {
             num_query_rounds,
             proof_of_work_bits,
             reduction_strategy,
+            security_assumption,
+            coset_shift,
         } = &config;
 
         self.write_usize(*rate_bits)?;
@@ -1666,6 +1937,14 @@ pub trait Write {
         self.write_usize(*num_query_rounds)?;
         self.write_u32(*proof_of_work_bits)?;
         self.write_fri_reduction_strategy(reduction_strategy)?;
+        self.write_u8(match security_assumption {
+            SecurityAssumption::Conjectured => 0,
+            SecurityAssumption::Proven => 1,
+        })?;
+        self.write_bool(coset_shift.is_some())?;
+        if let Some(shift) = coset_shift {
+            self.write_u64(*shift)?;
+        }
 
         Ok(())
     }
@@ -1783,6 +2062,7 @@ pub trait Write {
             luts,
         } = common_data;
 
+        self.write_u8(CIRCUIT_DATA_FORMAT_VERSION)?;
         self.write_circuit_config(config)?;
         self.write_fri_params(fri_params)?;
 
@@ -1853,8 +2133,10 @@ pub trait Write {
             circuit_digest,
             lookup_rows,
             lut_to_lookups,
+            target_names,
         } = prover_only_circuit_data;
 
+        self.write_u8(CIRCUIT_DATA_FORMAT_VERSION)?;
         self.write_usize(generators.len())?;
         for generator in generators.iter() {
             self.write_generator::<F, D>(generator, generator_serializer, common_data)?;
@@ -1903,6 +2185,12 @@ pub trait Write {
             self.write_target_lut(tlut)?;
         }
 
+        self.write_usize(target_names.len())?;
+        for (rep, name) in target_names {
+            self.write_usize(*rep)?;
+            self.write_string(name)?;
+        }
+
         Ok(())
     }
 
@@ -2203,6 +2491,11 @@ impl<'a> Remaining for Buffer<'a> {
 }
 
 impl<'a> Read for Buffer<'a> {
+    #[inline]
+    fn remaining_len(&self) -> Option<usize> {
+        Some(self.remaining())
+    }
+
     #[inline]
     fn read_exact(&mut self, bytes: &mut [u8]) -> IoResult<()> {
         let n = bytes.len();
@@ -2231,3 +2524,21 @@ impl<'a> Read for Buffer<'a> {
         generator_serializer.read_generator(self, common_data)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_string() {
+        let mut bytes = Vec::new();
+        bytes.write_string("target").unwrap();
+        assert_eq!(Buffer::new(&bytes).read_string().unwrap(), "target");
+
+        // A length prefix beyond the end of the buffer is rejected before allocating for it.
+        let mut bytes = Vec::new();
+        bytes.write_usize(usize::MAX >> 1).unwrap();
+        bytes.extend_from_slice(b"target");
+        assert!(Buffer::new(&bytes).read_string().is_err());
+    }
+}
@@ -0,0 +1,297 @@
+//! Canonical test vectors for external reimplementations of our primitives, e.g. Solidity, Go or
+//! JS verifiers.
+//!
+//! Everything is specialized to Goldilocks and `PoseidonGoldilocksConfig`, and encoded with the
+//! conventions of [`crate::util::serialization::json`]: field elements are `0x`-prefixed, 16-digit
+//! hex strings, and extension field elements are lists of their coefficients. Inputs are derived
+//! deterministically, so regenerating the vectors always gives the same document, except for the
+//! proof: `build` fills the unused wires of the public input gate with random values.
+//!
+//! The vectors are committed in `plonky2/tests/vectors/test_vectors.json`, as written by
+//! `cargo run --example test_vectors`.
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use anyhow::{ensure, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::field::extension::{Extendable, FieldExtension};
+use crate::field::goldilocks_field::GoldilocksField;
+use crate::field::types::{Field, Field64};
+use crate::fri::verifier::compute_evaluation;
+use crate::hash::poseidon::{Poseidon, SPONGE_WIDTH};
+use crate::iop::challenger::Challenger;
+use crate::iop::witness::{PartialWitness, WitnessWrite};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+use crate::plonk::verifier::verify;
+use crate::util::serialization::gate_serialization::default::DefaultGateSerializer;
+use crate::util::serialization::json::{
+    ext_to_hex, exts_to_hex, field_to_hex, fields_to_hex, JsonDocument, JsonProofWithPublicInputs,
+    JsonVerifierCircuitData,
+};
+
+const D: usize = 2;
+type C = PoseidonGoldilocksConfig;
+type F = GoldilocksField;
+type FE = <F as Extendable<D>>::Extension;
+
+/// Results of basic arithmetic on a pair of Goldilocks elements.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct FieldArithmeticVector {
+    pub a: String,
+    pub b: String,
+    pub sum: String,
+    pub difference: String,
+    pub product: String,
+    /// The inverse of `a`, or `None` if `a` is zero.
+    pub a_inverse: Option<String>,
+}
+
+/// The Poseidon permutation applied to a full width-12 state.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct PoseidonVector {
+    pub input: Vec<String>,
+    pub output: Vec<String>,
+}
+
+/// A fresh Poseidon challenger which observes `observations`, in order, then squeezes
+/// `challenges`.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct ChallengerVector {
+    pub observations: Vec<String>,
+    pub challenges: Vec<String>,
+}
+
+/// One FRI folding step: given the evaluations `evals` of `P` on the coset containing `x`, where
+/// `x` is at index `x_index_within_coset` of the (bit-reversed) coset, computes `P'(x^arity)` for
+/// the folding challenge `beta`.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct FriFoldingVector {
+    pub arity_bits: usize,
+    pub x: String,
+    pub x_index_within_coset: usize,
+    pub evals: Vec<Vec<String>>,
+    pub beta: Vec<String>,
+    pub folded: Vec<String>,
+}
+
+/// A proof of knowledge of `x` such that `x^2 + x + 1 = y`, with public inputs `[y]`.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct ProofVector {
+    pub verifier_data: JsonVerifierCircuitData,
+    pub proof_with_public_inputs: JsonProofWithPublicInputs,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct TestVectors {
+    pub field_arithmetic: Vec<FieldArithmeticVector>,
+    pub poseidon: Vec<PoseidonVector>,
+    pub challenger: Vec<ChallengerVector>,
+    pub fri_folding: Vec<FriFoldingVector>,
+    pub proof: ProofVector,
+}
+
+/// A deterministic stream of field elements, based on SplitMix64.
+struct Sampler(u64);
+
+impl Sampler {
+    fn next(&mut self) -> F {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        F::from_noncanonical_u64(z ^ (z >> 31))
+    }
+
+    fn next_ext(&mut self) -> FE {
+        FE::from_basefield_array([self.next(), self.next()])
+    }
+}
+
+impl TestVectors {
+    pub const SCHEMA: &'static str = "plonky2/test_vectors";
+
+    pub fn generate() -> Result<Self> {
+        let mut sampler = Sampler(0);
+        Ok(Self {
+            field_arithmetic: field_arithmetic_vectors(&mut sampler),
+            poseidon: poseidon_vectors(&mut sampler),
+            challenger: challenger_vectors(&mut sampler),
+            fri_folding: fri_folding_vectors(&mut sampler),
+            proof: proof_vector()?,
+        })
+    }
+
+    /// Checks that these vectors match the ones generated by this version of the crate. Since the
+    /// proof isn't reproducible, it is checked to prove `y = 13` for the regenerated verifier data
+    /// instead.
+    pub fn check(&self) -> Result<()> {
+        let expected = Self::generate()?;
+        ensure!(
+            self.field_arithmetic == expected.field_arithmetic
+                && self.poseidon == expected.poseidon
+                && self.challenger == expected.challenger
+                && self.fri_folding == expected.fri_folding
+                && self.proof.verifier_data == expected.proof.verifier_data
+                && self.proof.proof_with_public_inputs.public_inputs
+                    == expected.proof.proof_with_public_inputs.public_inputs,
+            "Test vectors do not match this implementation"
+        );
+        let verifier_data = self
+            .proof
+            .verifier_data
+            .decode::<F, C, D>(&DefaultGateSerializer)?;
+        let proof = self.proof.proof_with_public_inputs.decode::<F, C, D>()?;
+        verify::<F, C, D>(proof, &verifier_data.verifier_only, &verifier_data.common)
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        JsonDocument::new(Self::SCHEMA, self.clone()).to_json()
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        JsonDocument::from_json(json, Self::SCHEMA)
+    }
+}
+
+fn field_arithmetic_vectors(sampler: &mut Sampler) -> Vec<FieldArithmeticVector> {
+    let edge_cases = [
+        F::ZERO,
+        F::ONE,
+        F::TWO,
+        F::NEG_ONE,
+        F::from_canonical_u64(1 << 32),
+        F::from_canonical_u64(u32::MAX as u64),
+        F::from_canonical_u64(F::ORDER - (1 << 32)),
+    ];
+    let mut pairs = Vec::new();
+    for &a in &edge_cases {
+        for &b in &edge_cases {
+            pairs.push((a, b));
+        }
+    }
+    pairs.extend((0..16).map(|_| (sampler.next(), sampler.next())));
+
+    pairs
+        .into_iter()
+        .map(|(a, b)| FieldArithmeticVector {
+            a: field_to_hex(a),
+            b: field_to_hex(b),
+            sum: field_to_hex(a + b),
+            difference: field_to_hex(a - b),
+            product: field_to_hex(a * b),
+            a_inverse: a.try_inverse().map(field_to_hex),
+        })
+        .collect()
+}
+
+fn poseidon_vectors(sampler: &mut Sampler) -> Vec<PoseidonVector> {
+    let mut inputs = vec![
+        [F::ZERO; SPONGE_WIDTH],
+        core::array::from_fn(F::from_canonical_usize),
+        [F::NEG_ONE; SPONGE_WIDTH],
+    ];
+    inputs.extend((0..4).map(|_| core::array::from_fn(|_| sampler.next())));
+
+    inputs
+        .into_iter()
+        .map(|input| PoseidonVector {
+            input: fields_to_hex(&input),
+            output: fields_to_hex(&F::poseidon(input)),
+        })
+        .collect()
+}
+
+fn challenger_vectors(sampler: &mut Sampler) -> Vec<ChallengerVector> {
+    // Cover observations shorter than, equal to and longer than the sponge rate, as well as
+    // squeezing more challenges than fit in one output.
+    [(0, 3), (1, 1), (8, 8), (9, 12), (20, 20)]
+        .into_iter()
+        .map(|(num_observations, num_challenges)| {
+            let observations = (0..num_observations)
+                .map(|_| sampler.next())
+                .collect::<Vec<_>>();
+            let mut challenger = Challenger::<F, <C as GenericConfig<D>>::Hasher>::new();
+            challenger.observe_elements(&observations);
+            ChallengerVector {
+                observations: fields_to_hex(&observations),
+                challenges: fields_to_hex(&challenger.get_n_challenges(num_challenges)),
+            }
+        })
+        .collect()
+}
+
+fn fri_folding_vectors(sampler: &mut Sampler) -> Vec<FriFoldingVector> {
+    let mut vectors = Vec::new();
+    for arity_bits in 1..=4 {
+        let arity = 1 << arity_bits;
+        for x_index_within_coset in [0, arity - 1] {
+            let x = sampler.next();
+            let evals = (0..arity).map(|_| sampler.next_ext()).collect::<Vec<_>>();
+            let beta = sampler.next_ext();
+            let folded =
+                compute_evaluation::<F, D>(x, x_index_within_coset, arity_bits, &evals, beta);
+            vectors.push(FriFoldingVector {
+                arity_bits,
+                x: field_to_hex(x),
+                x_index_within_coset,
+                evals: exts_to_hex::<F, D>(&evals),
+                beta: ext_to_hex::<F, D>(beta),
+                folded: ext_to_hex::<F, D>(folded),
+            });
+        }
+    }
+    vectors
+}
+
+fn proof_vector() -> Result<ProofVector> {
+    let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+    let x = builder.add_virtual_target();
+    let x_squared = builder.square(x);
+    let sum = builder.add(x_squared, x);
+    let one = builder.one();
+    let y = builder.add(sum, one);
+    builder.register_public_input(y);
+    let data = builder.build::<C>();
+
+    let mut pw = PartialWitness::new();
+    pw.set_target(x, F::from_canonical_u64(3));
+    let proof = data.prove(pw)?;
+
+    Ok(ProofVector {
+        verifier_data: JsonVerifierCircuitData::new(&data.verifier_data(), &DefaultGateSerializer)?,
+        proof_with_public_inputs: JsonProofWithPublicInputs::new(&proof),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMMITTED_VECTORS: &str = include_str!("../../tests/vectors/test_vectors.json");
+
+    #[test]
+    fn test_committed_vectors() -> Result<()> {
+        let vectors = TestVectors::from_json(COMMITTED_VECTORS)?;
+        vectors.check()?;
+        assert_eq!(
+            vectors.proof.proof_with_public_inputs.public_inputs,
+            vec![field_to_hex(F::from_canonical_u64(13))]
+        );
+
+        let mut tampered = vectors;
+        tampered.poseidon[0].output[0] = field_to_hex(F::ONE);
+        assert!(tampered.check().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_and_check() -> Result<()> {
+        let vectors = TestVectors::generate()?;
+        TestVectors::from_json(&vectors.to_json()?)?.check()
+    }
+}
@@ -1,7 +1,16 @@
 #[cfg(feature = "timing")]
-use std::time::{Duration, Instant};
+use std::sync::Mutex;
+#[cfg(feature = "timing")]
+use std::time::Duration;
+#[cfg(any(feature = "timing", feature = "metrics"))]
+use std::time::Instant;
 
 use log::{log, Level};
+#[cfg(feature = "timing")]
+use plonky2_maybe_rayon::{current_num_threads, current_thread_index};
+
+#[cfg(feature = "metrics")]
+use crate::util::metrics::{metrics_recorder, PHASE_DURATION_SECONDS};
 
 /// The hierarchy of scopes, and the time consumed by each one. Useful for profiling.
 #[cfg(feature = "timing")]
@@ -16,6 +25,9 @@ pub struct TimingTree {
     exit_time: Option<Instant>,
     /// Any child scopes.
     children: Vec<TimingTree>,
+    /// For scopes merged from parallel tasks, the durations summed over tasks, and the number of
+    /// tasks.
+    merged: Option<(Duration, usize)>,
 }
 
 #[cfg(not(feature = "timing"))]
@@ -44,6 +56,7 @@ impl TimingTree {
             enter_time: Instant::now(),
             exit_time: None,
             children: vec![],
+            merged: None,
         }
     }
 
@@ -96,6 +109,7 @@ impl TimingTree {
             enter_time: Instant::now(),
             exit_time: None,
             children: vec![],
+            merged: None,
         })
     }
 
@@ -122,11 +136,75 @@ impl TimingTree {
 
     #[cfg(feature = "timing")]
     fn duration(&self) -> Duration {
+        if let Some((total, _)) = self.merged {
+            return total;
+        }
         self.exit_time
             .unwrap_or_else(Instant::now)
             .duration_since(self.enter_time)
     }
 
+    /// Adds the trees recorded by the tasks of a parallel loop as children of the deepest open
+    /// scope. The tasks' scopes are combined by name, with their durations summed.
+    #[cfg(feature = "timing")]
+    pub fn merge_parallel(&mut self, timings: ParallelTimings) {
+        assert!(self.is_open());
+
+        if let Some(last_child) = self.children.last_mut() {
+            if last_child.is_open() {
+                last_child.merge_parallel(timings);
+                return;
+            }
+        }
+
+        for tree in timings
+            .per_thread
+            .into_iter()
+            .flat_map(|t| t.into_inner().unwrap())
+        {
+            self.merge_child(&tree);
+        }
+    }
+
+    #[cfg(not(feature = "timing"))]
+    pub fn merge_parallel(&mut self, _timings: ParallelTimings) {}
+
+    /// Merges `tree` into the merged child with the same name, or adds it as a new one.
+    #[cfg(feature = "timing")]
+    fn merge_child(&mut self, tree: &TimingTree) {
+        match self
+            .children
+            .iter_mut()
+            .find(|c| c.merged.is_some() && c.name == tree.name)
+        {
+            Some(child) => child.merge(tree),
+            None => {
+                let now = Instant::now();
+                let mut child = TimingTree {
+                    name: tree.name.clone(),
+                    level: tree.level.max(self.level),
+                    enter_time: now,
+                    exit_time: Some(now),
+                    children: vec![],
+                    merged: Some((Duration::ZERO, 0)),
+                };
+                child.merge(tree);
+                self.children.push(child);
+            }
+        }
+    }
+
+    /// Adds the durations of `tree` and its descendants to this merged scope's.
+    #[cfg(feature = "timing")]
+    fn merge(&mut self, tree: &TimingTree) {
+        let (total, num_tasks) = self.merged.as_mut().expect("Not a merged scope");
+        *total += tree.duration();
+        *num_tasks += tree.merged.map_or(1, |(_, n)| n);
+        for child in &tree.children {
+            self.merge_child(child);
+        }
+    }
+
     /// Filter out children with a low duration.
     #[cfg(feature = "timing")]
     pub fn filter(&self, min_delta: Duration) -> Self {
@@ -135,6 +213,7 @@ impl TimingTree {
             level: self.level,
             enter_time: self.enter_time,
             exit_time: self.exit_time,
+            merged: self.merged,
             children: self
                 .children
                 .iter()
@@ -160,33 +239,203 @@ impl TimingTree {
     #[cfg(feature = "timing")]
     fn print_helper(&self, depth: usize) {
         let prefix = "| ".repeat(depth);
-        log!(
-            self.level,
-            "{}{:.4}s to {}",
-            prefix,
-            self.duration().as_secs_f64(),
-            self.name
-        );
+        match self.merged {
+            Some((total, num_tasks)) => log!(
+                self.level,
+                "{}{:.4}s to {} (summed over {} parallel tasks)",
+                prefix,
+                total.as_secs_f64(),
+                self.name,
+                num_tasks
+            ),
+            None => log!(
+                self.level,
+                "{}{:.4}s to {}",
+                prefix,
+                self.duration().as_secs_f64(),
+                self.name
+            ),
+        }
         for child in &self.children {
             child.print_helper(depth + 1);
         }
     }
 }
 
-/// Creates a named scope; useful for debugging.
+/// Timings recorded inside the tasks of a parallel loop, which a `TimingTree` can't time directly
+/// as its scopes are opened and closed on a single thread. Each task gets a tree of its own, and
+/// each worker thread combines the trees of the tasks it ran, until
+/// [`TimingTree::merge_parallel`] merges the threads' trees into the parent tree.
+#[cfg(feature = "timing")]
+pub struct ParallelTimings {
+    name: String,
+    level: Level,
+    /// One tree per worker thread, plus one for tasks run outside of the thread pool.
+    per_thread: Vec<Mutex<Option<TimingTree>>>,
+}
+
+#[cfg(not(feature = "timing"))]
+pub struct ParallelTimings(Level);
+
+impl ParallelTimings {
+    /// Creates timings whose tasks are all recorded as scopes named `name`.
+    #[cfg(feature = "timing")]
+    pub fn new(name: &str, level: Level) -> Self {
+        Self {
+            name: name.to_string(),
+            level,
+            per_thread: (0..=current_num_threads())
+                .map(|_| Mutex::new(None))
+                .collect(),
+        }
+    }
+
+    #[cfg(not(feature = "timing"))]
+    pub fn new(_name: &str, level: Level) -> Self {
+        Self(level)
+    }
+
+    /// Runs `f` as one task, passing it a tree in which it can open nested scopes, e.g. with
+    /// `timed!`. Tasks should be coarse enough that timing them is cheap in comparison.
+    #[cfg(feature = "timing")]
+    pub fn task<R>(&self, f: impl FnOnce(&mut TimingTree) -> R) -> R {
+        let mut tree = TimingTree::new(&self.name, self.level);
+        let res = f(&mut tree);
+        tree.pop();
+
+        let outside_pool = self.per_thread.len() - 1;
+        let index = current_thread_index().map_or(outside_pool, |i| i.min(outside_pool));
+        let mut thread_tree = self.per_thread[index].lock().unwrap();
+        match thread_tree.as_mut() {
+            Some(thread_tree) => thread_tree.merge(&tree),
+            None => {
+                let mut merged = TimingTree {
+                    merged: Some((Duration::ZERO, 0)),
+                    ..TimingTree::new(&self.name, self.level)
+                };
+                merged.merge(&tree);
+                *thread_tree = Some(merged);
+            }
+        }
+        res
+    }
+
+    #[cfg(not(feature = "timing"))]
+    pub fn task<R>(&self, f: impl FnOnce(&mut TimingTree) -> R) -> R {
+        f(&mut TimingTree::new("", self.0))
+    }
+}
+
+/// A scope of a [`TimingTree`], as seen by telemetry, which lasts until this is dropped. With the
+/// `tracing` feature, it is an entered `tracing` span. With the `metrics` feature, its duration is
+/// reported to the installed metrics recorder. Otherwise, it does nothing.
+pub struct PhaseScope {
+    #[cfg(feature = "tracing")]
+    _span: tracing::span::EnteredSpan,
+    #[cfg(feature = "metrics")]
+    started: Option<(String, Instant)>,
+}
+
+impl PhaseScope {
+    #[cfg_attr(
+        not(any(feature = "tracing", feature = "metrics")),
+        allow(unused_variables)
+    )]
+    pub fn enter(ctx: &str) -> Self {
+        Self {
+            #[cfg(feature = "tracing")]
+            _span: tracing::info_span!("timed", scope = ctx).entered(),
+            #[cfg(feature = "metrics")]
+            started: metrics_recorder().map(|_| (ctx.to_string(), Instant::now())),
+        }
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl Drop for PhaseScope {
+    fn drop(&mut self) {
+        if let (Some(recorder), Some((phase, started))) = (metrics_recorder(), &self.started) {
+            recorder.record_histogram(
+                PHASE_DURATION_SECONDS,
+                &[("phase", phase)],
+                started.elapsed().as_secs_f64(),
+            );
+        }
+    }
+}
+
+/// Creates a named scope; useful for debugging. The scope is also a [`PhaseScope`], so that
+/// services can collect the prover's phases as spans or metrics.
 #[macro_export]
 macro_rules! timed {
     ($timing_tree:expr, $level:expr, $ctx:expr, $exp:expr) => {{
-        $timing_tree.push($ctx, $level);
+        // Evaluate the context once, as it is often a `format!`.
+        let ctx = $ctx;
+        let _scope = $crate::util::timing::PhaseScope::enter(ctx);
+        $timing_tree.push(ctx, $level);
         let res = $exp;
         $timing_tree.pop();
         res
     }};
     // If no context is specified, default to Debug.
     ($timing_tree:expr, $ctx:expr, $exp:expr) => {{
-        $timing_tree.push($ctx, log::Level::Debug);
+        let ctx = $ctx;
+        let _scope = $crate::util::timing::PhaseScope::enter(ctx);
+        $timing_tree.push(ctx, log::Level::Debug);
         let res = $exp;
         $timing_tree.pop();
         res
     }};
 }
+
+#[cfg(all(test, feature = "timing"))]
+mod tests {
+    use plonky2_maybe_rayon::*;
+
+    use super::*;
+    use crate::timed;
+
+    #[test]
+    fn test_merge_parallel() {
+        let mut timing = TimingTree::new("root", Level::Debug);
+        let timings = ParallelTimings::new("task", Level::Debug);
+        (0..16).into_par_iter().for_each(|i| {
+            timings.task(|timing| {
+                timing.push("inner", Level::Debug);
+                timing.pop();
+                if i % 2 == 0 {
+                    timing.push("even", Level::Debug);
+                    timing.pop();
+                }
+            })
+        });
+        timing.merge_parallel(timings);
+        timing.pop();
+
+        assert_eq!(timing.children.len(), 1);
+        let task = &timing.children[0];
+        assert_eq!(task.name, "task");
+        assert_eq!(task.merged.map(|(_, n)| n), Some(16));
+        let children = task
+            .children
+            .iter()
+            .map(|c| (c.name.as_str(), c.merged.map(|(_, n)| n)))
+            .collect::<Vec<_>>();
+        assert_eq!(children, vec![("inner", Some(16)), ("even", Some(8))]);
+    }
+
+    #[test]
+    fn test_timed_evaluates_context_once() {
+        let mut timing = TimingTree::new("root", Level::Debug);
+        let mut evaluations = 0;
+        let mut ctx = || {
+            evaluations += 1;
+            "scope"
+        };
+        assert_eq!(timed!(timing, Level::Info, ctx(), 1), 1);
+        assert_eq!(timed!(timing, ctx(), 2), 2);
+        assert_eq!(evaluations, 2);
+        timing.pop();
+        assert_eq!(timing.children.len(), 2);
+    }
+}
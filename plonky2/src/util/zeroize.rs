@@ -0,0 +1,28 @@
+//! Best-effort erasure of secret witness data, used when the `hardened` feature is enabled.
+//!
+//! Writes go through `write_volatile` followed by a compiler fence, so they can't be optimized
+//! away as dead stores. Copies made elsewhere, e.g. by a `HashMap` resizing its table or by the
+//! prover's polynomial buffers, are not covered.
+
+use alloc::vec::Vec;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{compiler_fence, Ordering};
+
+use crate::field::types::Field;
+
+/// Overwrites every element of `values` with zero.
+pub(crate) fn zeroize_values<'a, F: Field + 'a>(values: impl IntoIterator<Item = &'a mut F>) {
+    for x in values {
+        unsafe { core::ptr::write_volatile(x, F::ZERO) };
+    }
+    compiler_fence(Ordering::SeqCst);
+}
+
+/// Clears `v` and overwrites its entire allocation, including spare capacity, with zero bytes.
+pub(crate) fn zeroize_vec<T: Copy>(v: &mut Vec<T>) {
+    v.clear();
+    for x in v.spare_capacity_mut() {
+        unsafe { core::ptr::write_volatile(x, MaybeUninit::zeroed()) };
+    }
+    compiler_fence(Ordering::SeqCst);
+}
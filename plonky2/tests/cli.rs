@@ -0,0 +1,163 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+use anyhow::Result;
+use plonky2::field::types::Sample;
+use plonky2::iop::witness::{PartialWitness, WitnessWrite};
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::plonk::circuit_data::CircuitConfig;
+use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+use plonky2::util::serialization::{DefaultGateSerializer, DefaultGeneratorSerializer};
+
+const D: usize = 2;
+type C = PoseidonGoldilocksConfig;
+type F = <C as GenericConfig<D>>::F;
+
+/// A fresh directory under the system's temporary directory, removed on drop.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("plonky2-cli-{name}-{}", std::process::id()));
+        fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    fn path(&self, file: &str) -> String {
+        self.0.join(file).to_str().unwrap().to_string()
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn cli(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_plonky2-cli"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // The CLI may exit before reading its input, which then fails with a broken pipe.
+    let _ = child.stdin.take().unwrap().write_all(stdin.as_bytes());
+    child.wait_with_output().unwrap()
+}
+
+fn assert_success(output: &Output) -> String {
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    stdout
+}
+
+/// Checks that the CLI failed with an error message rather than a panic.
+fn assert_failure(output: &Output) {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{stderr}");
+    assert!(!stderr.contains("panicked"), "{stderr}");
+}
+
+/// Writes a circuit proving knowledge of a square root, its verifier data, and a witness for it.
+fn write_files(dir: &TempDir) -> Result<()> {
+    let config = CircuitConfig::standard_recursion_config();
+    let mut builder = CircuitBuilder::<F, D>::new(config);
+    let x = builder.add_virtual_target();
+    let y = builder.square(x);
+    builder.register_public_input(y);
+    let data = builder.build::<C>();
+
+    let generator_serializer = DefaultGeneratorSerializer::<C, D> {
+        _phantom: Default::default(),
+    };
+    let circuit = data
+        .to_bytes(&DefaultGateSerializer, &generator_serializer)
+        .map_err(anyhow::Error::msg)?;
+    fs::write(dir.path("circuit.bin"), circuit)?;
+    let verifier_data = data.verifier_data();
+    fs::write(
+        dir.path("verifier.bin"),
+        verifier_data
+            .to_bytes(&DefaultGateSerializer)
+            .map_err(anyhow::Error::msg)?,
+    )?;
+    fs::write(
+        dir.path("verifier.json"),
+        verifier_data.to_json(&DefaultGateSerializer)?,
+    )?;
+
+    let mut pw = PartialWitness::new();
+    pw.set_target(x, F::rand());
+    fs::write(dir.path("witness.bin"), pw.to_bytes())?;
+    Ok(())
+}
+
+#[test]
+fn test_cli_subcommands() -> Result<()> {
+    let dir = TempDir::new("subcommands");
+    write_files(&dir)?;
+    let circuit = dir.path("circuit.bin");
+    let witness = dir.path("witness.bin");
+
+    for (verifier_data, proof) in [
+        (dir.path("verifier.bin"), dir.path("proof.bin")),
+        (dir.path("verifier.json"), dir.path("proof.json")),
+    ] {
+        let stdout = assert_success(&cli(&["prove", &circuit, &witness, &proof], ""));
+        assert!(stdout.contains("Proof size"));
+        assert!(Path::new(&proof).exists());
+
+        let stdout = assert_success(&cli(&["verify", &verifier_data, &proof], ""));
+        assert!(stdout.contains("Verified"));
+
+        let stdout = assert_success(&cli(&["inspect", &verifier_data], ""));
+        assert!(stdout.contains("Circuit digest"));
+        let stdout = assert_success(&cli(&["inspect", &verifier_data, &proof], ""));
+        assert!(stdout.contains("Public inputs"));
+    }
+
+    let stdout = assert_success(&cli(&["explore", &circuit], "row 0\n"));
+    assert!(stdout.contains("rows"));
+    Ok(())
+}
+
+#[test]
+fn test_cli_malformed_files() -> Result<()> {
+    let dir = TempDir::new("malformed");
+    write_files(&dir)?;
+    let circuit = dir.path("circuit.bin");
+    let witness = dir.path("witness.bin");
+    let verifier_data = dir.path("verifier.bin");
+    let proof = dir.path("proof.bin");
+    assert_success(&cli(&["prove", &circuit, &witness, &proof], ""));
+
+    let garbage = dir.path("garbage.bin");
+    fs::write(&garbage, b"not a plonky2 file")?;
+    let truncated_proof = dir.path("truncated.bin");
+    let proof_bytes = fs::read(&proof)?;
+    fs::write(&truncated_proof, &proof_bytes[..proof_bytes.len() / 2])?;
+    let truncated_witness = dir.path("truncated_witness.bin");
+    let witness_bytes = fs::read(&witness)?;
+    fs::write(
+        &truncated_witness,
+        &witness_bytes[..witness_bytes.len() - 1],
+    )?;
+
+    assert_failure(&cli(&["prove", &garbage, &witness, &proof], ""));
+    assert_failure(&cli(&["prove", &circuit, &truncated_witness, &proof], ""));
+    assert_failure(&cli(&["verify", &garbage, &proof], ""));
+    assert_failure(&cli(&["verify", &verifier_data, &truncated_proof], ""));
+    assert_failure(&cli(&["inspect", &verifier_data, &garbage], ""));
+    assert_failure(&cli(&["explore", &garbage], ""));
+    assert_failure(&cli(&["verify", &dir.path("missing.bin"), &proof], ""));
+    assert_failure(&cli(&["verify"], ""));
+    Ok(())
+}
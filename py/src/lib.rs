@@ -0,0 +1,650 @@
+//! Python bindings for Plonky2 and Starky.
+//!
+//! Everything is specialized to `PoseidonGoldilocksConfig`. Field elements are passed to and from
+//! Python as `int`s, and must be canonical, i.e. less than the Goldilocks order, except for the
+//! coefficients of AIR constraints, which may be negative.
+
+#![allow(incomplete_features)]
+#![feature(generic_const_exprs)]
+
+use plonky2::field::types::{Field, Field64, PrimeField64};
+use plonky2::iop::target::{BoolTarget, Target};
+use plonky2::iop::witness::{PartialWitness, Witness, WitnessWrite};
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::plonk::circuit_data::{CircuitConfig, CircuitData};
+use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+use plonky2::plonk::proof::ProofWithPublicInputs;
+use plonky2::util::timing::TimingTree;
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use starky::config::StarkConfig;
+use starky::polynomial_stark::{Monomial, PolynomialStark, MAX_COLUMNS, MAX_PUBLIC_INPUTS};
+use starky::proof::StarkProofWithPublicInputs;
+use starky::prover::prove;
+use starky::public_cells::PublicCell;
+use starky::verifier::verify_stark_proof;
+
+const D: usize = 2;
+type C = PoseidonGoldilocksConfig;
+type F = <C as GenericConfig<D>>::F;
+
+fn to_field(value: u64) -> PyResult<F> {
+    if value >= F::ORDER {
+        return Err(PyValueError::new_err(format!(
+            "{} is not a canonical field element; expected a value below {}",
+            value,
+            F::ORDER
+        )));
+    }
+    Ok(F::from_canonical_u64(value))
+}
+
+/// Like `to_field`, but maps a negative `value` to `-value`'s negation.
+fn to_field_signed(value: i128) -> PyResult<F> {
+    let magnitude = u64::try_from(value.unsigned_abs())
+        .map_err(|_| PyValueError::new_err(format!("{} is out of range", value)))?;
+    let x = to_field(magnitude)?;
+    Ok(if value < 0 { -x } else { x })
+}
+
+fn to_py_err(e: anyhow::Error) -> PyErr {
+    PyValueError::new_err(format!("{:#}", e))
+}
+
+/// A location in the witness.
+#[pyclass(name = "Target")]
+#[derive(Copy, Clone)]
+pub struct PyTarget(Target);
+
+#[pymethods]
+impl PyTarget {
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.0)
+    }
+
+    fn __eq__(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+#[pyclass(name = "CircuitConfig")]
+#[derive(Clone)]
+pub struct PyCircuitConfig(CircuitConfig);
+
+#[pymethods]
+impl PyCircuitConfig {
+    /// A typical recursion config, without zero-knowledge, targeting ~100 bit security.
+    #[staticmethod]
+    fn standard_recursion_config() -> Self {
+        Self(CircuitConfig::standard_recursion_config())
+    }
+
+    #[staticmethod]
+    fn standard_recursion_zk_config() -> Self {
+        Self(CircuitConfig::standard_recursion_zk_config())
+    }
+
+    #[getter]
+    fn num_wires(&self) -> usize {
+        self.0.num_wires
+    }
+
+    #[getter]
+    fn num_routed_wires(&self) -> usize {
+        self.0.num_routed_wires
+    }
+
+    #[getter]
+    fn security_bits(&self) -> usize {
+        self.0.security_bits
+    }
+
+    #[getter]
+    fn zero_knowledge(&self) -> bool {
+        self.0.zero_knowledge
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.0)
+    }
+}
+
+/// Wraps a `CircuitBuilder`. Building consumes the inner builder, after which any further call
+/// raises a `RuntimeError`.
+#[pyclass(name = "CircuitBuilder", unsendable)]
+pub struct PyCircuitBuilder(Option<CircuitBuilder<F, D>>);
+
+impl PyCircuitBuilder {
+    fn builder(&mut self) -> PyResult<&mut CircuitBuilder<F, D>> {
+        self.0
+            .as_mut()
+            .ok_or_else(|| PyRuntimeError::new_err("This circuit has already been built"))
+    }
+}
+
+#[pymethods]
+impl PyCircuitBuilder {
+    #[new]
+    #[pyo3(signature = (config = None))]
+    fn new(config: Option<PyCircuitConfig>) -> Self {
+        let config = config.map_or_else(CircuitConfig::standard_recursion_config, |c| c.0);
+        Self(Some(CircuitBuilder::new(config)))
+    }
+
+    fn add_virtual_target(&mut self) -> PyResult<PyTarget> {
+        Ok(PyTarget(self.builder()?.add_virtual_target()))
+    }
+
+    fn add_virtual_targets(&mut self, n: usize) -> PyResult<Vec<PyTarget>> {
+        let builder = self.builder()?;
+        Ok(builder
+            .add_virtual_targets(n)
+            .into_iter()
+            .map(PyTarget)
+            .collect())
+    }
+
+    fn add_virtual_public_input(&mut self) -> PyResult<PyTarget> {
+        Ok(PyTarget(self.builder()?.add_virtual_public_input()))
+    }
+
+    fn constant(&mut self, value: u64) -> PyResult<PyTarget> {
+        let c = to_field(value)?;
+        Ok(PyTarget(self.builder()?.constant(c)))
+    }
+
+    fn zero(&mut self) -> PyResult<PyTarget> {
+        Ok(PyTarget(self.builder()?.zero()))
+    }
+
+    fn one(&mut self) -> PyResult<PyTarget> {
+        Ok(PyTarget(self.builder()?.one()))
+    }
+
+    fn add(&mut self, x: PyTarget, y: PyTarget) -> PyResult<PyTarget> {
+        Ok(PyTarget(self.builder()?.add(x.0, y.0)))
+    }
+
+    fn sub(&mut self, x: PyTarget, y: PyTarget) -> PyResult<PyTarget> {
+        Ok(PyTarget(self.builder()?.sub(x.0, y.0)))
+    }
+
+    fn mul(&mut self, x: PyTarget, y: PyTarget) -> PyResult<PyTarget> {
+        Ok(PyTarget(self.builder()?.mul(x.0, y.0)))
+    }
+
+    /// Computes `x * y + z`.
+    fn mul_add(&mut self, x: PyTarget, y: PyTarget, z: PyTarget) -> PyResult<PyTarget> {
+        Ok(PyTarget(self.builder()?.mul_add(x.0, y.0, z.0)))
+    }
+
+    fn neg(&mut self, x: PyTarget) -> PyResult<PyTarget> {
+        Ok(PyTarget(self.builder()?.neg(x.0)))
+    }
+
+    fn square(&mut self, x: PyTarget) -> PyResult<PyTarget> {
+        Ok(PyTarget(self.builder()?.square(x.0)))
+    }
+
+    fn div(&mut self, x: PyTarget, y: PyTarget) -> PyResult<PyTarget> {
+        Ok(PyTarget(self.builder()?.div(x.0, y.0)))
+    }
+
+    fn inverse(&mut self, x: PyTarget) -> PyResult<PyTarget> {
+        Ok(PyTarget(self.builder()?.inverse(x.0)))
+    }
+
+    fn exp_u64(&mut self, base: PyTarget, exponent: u64) -> PyResult<PyTarget> {
+        Ok(PyTarget(self.builder()?.exp_u64(base.0, exponent)))
+    }
+
+    /// Returns a target which is 1 if `x == y` and 0 otherwise.
+    fn is_equal(&mut self, x: PyTarget, y: PyTarget) -> PyResult<PyTarget> {
+        Ok(PyTarget(self.builder()?.is_equal(x.0, y.0).target))
+    }
+
+    fn connect(&mut self, x: PyTarget, y: PyTarget) -> PyResult<()> {
+        self.builder()?.connect(x.0, y.0);
+        Ok(())
+    }
+
+    fn assert_zero(&mut self, x: PyTarget) -> PyResult<()> {
+        self.builder()?.assert_zero(x.0);
+        Ok(())
+    }
+
+    fn assert_one(&mut self, x: PyTarget) -> PyResult<()> {
+        self.builder()?.assert_one(x.0);
+        Ok(())
+    }
+
+    fn assert_bool(&mut self, x: PyTarget) -> PyResult<()> {
+        self.builder()?.assert_bool(BoolTarget::new_unsafe(x.0));
+        Ok(())
+    }
+
+    /// Checks that `x < 2^n_log`.
+    fn range_check(&mut self, x: PyTarget, n_log: usize) -> PyResult<()> {
+        self.builder()?.range_check(x.0, n_log);
+        Ok(())
+    }
+
+    fn register_public_input(&mut self, target: PyTarget) -> PyResult<()> {
+        self.builder()?.register_public_input(target.0);
+        Ok(())
+    }
+
+    fn register_public_inputs(&mut self, targets: Vec<PyTarget>) -> PyResult<()> {
+        let targets = targets.into_iter().map(|t| t.0).collect::<Vec<_>>();
+        self.builder()?.register_public_inputs(&targets);
+        Ok(())
+    }
+
+    fn num_gates(&mut self) -> PyResult<usize> {
+        Ok(self.builder()?.num_gates())
+    }
+
+    fn num_public_inputs(&mut self) -> PyResult<usize> {
+        Ok(self.builder()?.num_public_inputs())
+    }
+
+    /// Builds the circuit, consuming this builder.
+    fn build(&mut self, py: Python<'_>) -> PyResult<PyCircuitData> {
+        let builder = self
+            .0
+            .take()
+            .ok_or_else(|| PyRuntimeError::new_err("This circuit has already been built"))?;
+        Ok(PyCircuitData(py.allow_threads(|| builder.build::<C>())))
+    }
+}
+
+#[pyclass(name = "PartialWitness")]
+#[derive(Clone, Default)]
+pub struct PyPartialWitness(PartialWitness<F>);
+
+#[pymethods]
+impl PyPartialWitness {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn set_target(&mut self, target: PyTarget, value: u64) -> PyResult<()> {
+        let value = to_field(value)?;
+        if let Some(old_value) = self.0.try_get_target(target.0) {
+            if old_value != value {
+                return Err(PyValueError::new_err(format!(
+                    "Target {:?} was set twice with different values: {} != {}",
+                    target.0, old_value, value
+                )));
+            }
+        }
+        self.0.set_target(target.0, value);
+        Ok(())
+    }
+
+    fn set_targets(&mut self, targets: Vec<PyTarget>, values: Vec<u64>) -> PyResult<()> {
+        if targets.len() != values.len() {
+            return Err(PyValueError::new_err(format!(
+                "Got {} targets but {} values",
+                targets.len(),
+                values.len()
+            )));
+        }
+        for (target, value) in targets.into_iter().zip(values) {
+            self.set_target(target, value)?;
+        }
+        Ok(())
+    }
+
+    fn get_target(&self, target: PyTarget) -> Option<u64> {
+        self.0
+            .try_get_target(target.0)
+            .map(|x| x.to_canonical_u64())
+    }
+}
+
+#[pyclass(name = "ProofWithPublicInputs")]
+#[derive(Clone)]
+pub struct PyProof(ProofWithPublicInputs<F, C, D>);
+
+#[pymethods]
+impl PyProof {
+    #[getter]
+    fn public_inputs(&self) -> Vec<u64> {
+        self.0
+            .public_inputs
+            .iter()
+            .map(|x| x.to_canonical_u64())
+            .collect()
+    }
+
+    fn to_bytes<'py>(&self, py: Python<'py>) -> &'py PyBytes {
+        PyBytes::new(py, &self.0.to_bytes())
+    }
+}
+
+#[pyclass(name = "CircuitData")]
+pub struct PyCircuitData(CircuitData<F, C, D>);
+
+#[pymethods]
+impl PyCircuitData {
+    #[getter]
+    fn degree_bits(&self) -> usize {
+        self.0.common.degree_bits()
+    }
+
+    #[getter]
+    fn num_public_inputs(&self) -> usize {
+        self.0.common.num_public_inputs
+    }
+
+    /// Runs witness generation and proves the circuit. The GIL is released while proving.
+    fn prove(&self, py: Python<'_>, witness: &PyPartialWitness) -> PyResult<PyProof> {
+        let inputs = witness.0.clone();
+        py.allow_threads(|| self.0.prove(inputs))
+            .map(PyProof)
+            .map_err(to_py_err)
+    }
+
+    /// Verifies a proof, raising a `ValueError` if it is invalid.
+    fn verify(&self, py: Python<'_>, proof: &PyProof) -> PyResult<()> {
+        let proof = proof.0.clone();
+        py.allow_threads(|| self.0.verify(proof)).map_err(to_py_err)
+    }
+
+    fn proof_from_bytes(&self, bytes: Vec<u8>) -> PyResult<PyProof> {
+        ProofWithPublicInputs::from_bytes(bytes, &self.0.common)
+            .map(PyProof)
+            .map_err(to_py_err)
+    }
+}
+
+#[pyclass(name = "StarkConfig")]
+#[derive(Clone)]
+pub struct PyStarkConfig(StarkConfig);
+
+#[pymethods]
+impl PyStarkConfig {
+    /// A config with a rate of 2, resulting in fast but large proofs, targeting ~100 bit security.
+    #[staticmethod]
+    fn standard_fast_config() -> Self {
+        Self(StarkConfig::standard_fast_config())
+    }
+
+    #[getter]
+    fn security_bits(&self) -> usize {
+        self.0.security_bits
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.0)
+    }
+}
+
+#[pyclass(name = "StarkProof")]
+#[derive(Clone)]
+pub struct PyStarkProof {
+    proof: StarkProofWithPublicInputs<F, C, D>,
+    num_public_inputs: usize,
+}
+
+#[pymethods]
+impl PyStarkProof {
+    /// The public inputs, without the padding of unused public inputs.
+    #[getter]
+    fn public_inputs(&self) -> Vec<u64> {
+        self.proof.public_inputs[..self.num_public_inputs]
+            .iter()
+            .map(|x| x.to_canonical_u64())
+            .collect()
+    }
+}
+
+/// Wraps a `PolynomialStark`, an AIR whose transition constraints are polynomials in the values of
+/// the current and next rows, and whose boundary constraints equate cells of the first or last row
+/// with public inputs.
+///
+/// As a `Stark` fixes its numbers of columns and public inputs at compile time, an AIR has at most
+/// `MAX_COLUMNS` (16) columns and `MAX_PUBLIC_INPUTS` (8) public inputs. The constructor raises a
+/// `ValueError` beyond these.
+#[pyclass(name = "StarkAir")]
+pub struct PyStarkAir {
+    stark: PolynomialStark<F, D>,
+    config: StarkConfig,
+}
+
+impl PyStarkAir {
+    fn wrap_proof(&self, proof: StarkProofWithPublicInputs<F, C, D>) -> PyStarkProof {
+        PyStarkProof {
+            proof,
+            num_public_inputs: self.stark.num_public_inputs(),
+        }
+    }
+}
+
+#[pymethods]
+impl PyStarkAir {
+    #[classattr]
+    const MAX_COLUMNS: usize = MAX_COLUMNS;
+
+    #[classattr]
+    const MAX_PUBLIC_INPUTS: usize = MAX_PUBLIC_INPUTS;
+
+    #[new]
+    #[pyo3(signature = (num_columns, num_public_inputs, config = None))]
+    fn new(
+        num_columns: usize,
+        num_public_inputs: usize,
+        config: Option<PyStarkConfig>,
+    ) -> PyResult<Self> {
+        if !(1..=MAX_COLUMNS).contains(&num_columns) {
+            return Err(PyValueError::new_err(format!(
+                "Expected between 1 and {} columns, got {}",
+                MAX_COLUMNS, num_columns
+            )));
+        }
+        if num_public_inputs > MAX_PUBLIC_INPUTS {
+            return Err(PyValueError::new_err(format!(
+                "Expected at most {} public inputs, got {}",
+                MAX_PUBLIC_INPUTS, num_public_inputs
+            )));
+        }
+        Ok(Self {
+            stark: PolynomialStark::new(num_columns, num_public_inputs).map_err(to_py_err)?,
+            config: config.map_or_else(StarkConfig::standard_fast_config, |c| c.0),
+        })
+    }
+
+    #[getter]
+    fn num_columns(&self) -> usize {
+        self.stark.num_columns()
+    }
+
+    #[getter]
+    fn num_public_inputs(&self) -> usize {
+        self.stark.num_public_inputs()
+    }
+
+    /// Adds the constraint that a sum of terms is zero on every row but the last. Each term is a
+    /// tuple `(coeff, local_columns, next_columns)`, standing for `coeff` times the values of
+    /// `local_columns` in the current row and of `next_columns` in the next one.
+    fn add_transition_constraint(
+        &mut self,
+        terms: Vec<(i128, Vec<usize>, Vec<usize>)>,
+    ) -> PyResult<()> {
+        let terms = terms
+            .into_iter()
+            .map(|(coeff, local_columns, next_columns)| {
+                Ok(Monomial {
+                    coeff: to_field_signed(coeff)?,
+                    local_columns,
+                    next_columns,
+                })
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+        self.stark
+            .add_transition_constraint(terms)
+            .map_err(to_py_err)
+    }
+
+    /// Adds the constraint that `column` in the first row equals the public input at `index`.
+    fn add_first_row_public_input(&mut self, column: usize, index: usize) -> PyResult<()> {
+        self.stark
+            .add_public_cell(PublicCell::first_row(column, index))
+            .map_err(to_py_err)
+    }
+
+    /// Adds the constraint that `column` in the last row equals the public input at `index`.
+    fn add_last_row_public_input(&mut self, column: usize, index: usize) -> PyResult<()> {
+        self.stark
+            .add_public_cell(PublicCell::last_row(column, index))
+            .map_err(to_py_err)
+    }
+
+    /// Proves a trace, given as a list of columns of the same power-of-two length. The GIL is
+    /// released while proving.
+    fn prove(
+        &self,
+        py: Python<'_>,
+        columns: Vec<Vec<u64>>,
+        public_inputs: Vec<u64>,
+    ) -> PyResult<PyStarkProof> {
+        let columns = columns
+            .into_iter()
+            .map(|column| column.into_iter().map(to_field).collect())
+            .collect::<PyResult<Vec<Vec<F>>>>()?;
+        let public_inputs = public_inputs
+            .into_iter()
+            .map(to_field)
+            .collect::<PyResult<Vec<_>>>()?;
+        let trace = self.stark.pad_trace(columns).map_err(to_py_err)?;
+        let public_inputs = self
+            .stark
+            .pad_public_inputs(&public_inputs)
+            .map_err(to_py_err)?;
+        let stark = self.stark.clone();
+        py.allow_threads(|| {
+            prove::<F, C, _, D>(
+                stark,
+                &self.config,
+                trace,
+                public_inputs,
+                &mut TimingTree::default(),
+            )
+        })
+        .map(|proof| self.wrap_proof(proof))
+        .map_err(to_py_err)
+    }
+
+    /// Verifies a proof, raising a `ValueError` if it is invalid.
+    fn verify(&self, py: Python<'_>, proof: &PyStarkProof) -> PyResult<()> {
+        self.stark
+            .check_public_inputs_padding(&proof.proof.public_inputs)
+            .map_err(to_py_err)?;
+        let stark = self.stark.clone();
+        let proof = proof.proof.clone();
+        py.allow_threads(|| verify_stark_proof(stark, proof, &self.config))
+            .map_err(to_py_err)
+    }
+
+    fn proof_to_bytes<'py>(&self, py: Python<'py>, proof: &PyStarkProof) -> &'py PyBytes {
+        PyBytes::new(py, &proof.proof.to_bytes(&self.config))
+    }
+
+    fn proof_from_bytes(&self, bytes: Vec<u8>) -> PyResult<PyStarkProof> {
+        StarkProofWithPublicInputs::from_bytes(&bytes, &self.stark, &self.config)
+            .map(|proof| self.wrap_proof(proof))
+            .map_err(to_py_err)
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "plonky2")]
+fn plonky2_py(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_class::<PyTarget>()?;
+    m.add_class::<PyCircuitConfig>()?;
+    m.add_class::<PyCircuitBuilder>()?;
+    m.add_class::<PyPartialWitness>()?;
+    m.add_class::<PyProof>()?;
+    m.add_class::<PyCircuitData>()?;
+    m.add_class::<PyStarkConfig>()?;
+    m.add_class::<PyStarkProof>()?;
+    m.add_class::<PyStarkAir>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Proves and verifies the Fibonacci sequence through the bindings, as a circuit and as an AIR,
+    /// with a round trip through bytes for each proof.
+    #[test]
+    fn test_bindings_round_trip() -> PyResult<()> {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let num_steps = 31;
+            let (mut a, mut b) = (0u64, 1u64);
+            let mut x0 = vec![a];
+            let mut x1 = vec![b];
+            for _ in 0..num_steps {
+                let sum = F::from_canonical_u64(a) + F::from_canonical_u64(b);
+                (a, b) = (b, sum.to_canonical_u64());
+                x0.push(a);
+                x1.push(b);
+            }
+
+            let mut builder = PyCircuitBuilder::new(None);
+            let first = builder.add_virtual_target()?;
+            let second = builder.add_virtual_target()?;
+            let (mut prev, mut cur) = (first, second);
+            for _ in 0..num_steps {
+                (prev, cur) = (cur, builder.add(prev, cur)?);
+            }
+            builder.register_public_inputs(vec![first, second, cur])?;
+            let data = builder.build(py)?;
+            let mut witness = PyPartialWitness::new();
+            witness.set_targets(vec![first, second], vec![0, 1])?;
+            let proof = data.prove(py, &witness)?;
+            assert_eq!(proof.public_inputs(), vec![0, 1, b]);
+            let proof = data.proof_from_bytes(proof.to_bytes(py).as_bytes().to_vec())?;
+            data.verify(py, &proof)?;
+
+            let mut air = PyStarkAir::new(2, 3, None)?;
+            air.add_transition_constraint(vec![(1, vec![], vec![0]), (-1, vec![1], vec![])])?;
+            air.add_transition_constraint(vec![
+                (1, vec![], vec![1]),
+                (-1, vec![0], vec![]),
+                (-1, vec![1], vec![]),
+            ])?;
+            air.add_first_row_public_input(0, 0)?;
+            air.add_first_row_public_input(1, 1)?;
+            air.add_last_row_public_input(1, 2)?;
+            let proof = air.prove(py, vec![x0.clone(), x1.clone()], vec![0, 1, b])?;
+            assert_eq!(proof.public_inputs(), vec![0, 1, b]);
+            let bytes = air.proof_to_bytes(py, &proof).as_bytes().to_vec();
+            let proof = air.proof_from_bytes(bytes.clone())?;
+            air.verify(py, &proof)?;
+
+            // Wrong public inputs, malformed proofs and out-of-range columns raise errors.
+            assert!(air.prove(py, vec![x0, x1], vec![0, 1, a]).is_err());
+            assert!(air
+                .proof_from_bytes(bytes[..bytes.len() / 2].to_vec())
+                .is_err());
+            assert!(air.add_first_row_public_input(2, 0).is_err());
+
+            // AIRs beyond the column and public input caps are rejected with a `ValueError`.
+            for (num_columns, num_public_inputs) in
+                [(0, 0), (MAX_COLUMNS + 1, 0), (1, MAX_PUBLIC_INPUTS + 1)]
+            {
+                let err = PyStarkAir::new(num_columns, num_public_inputs, None)
+                    .err()
+                    .expect("AIR beyond the caps should be rejected");
+                assert!(err.is_instance_of::<PyValueError>(py));
+            }
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,164 @@
+//! Benchmarks of STARK proving, at trace sizes from 2^12 to 2^22 rows, with throughput reported in
+//! rows per second.
+
+#![allow(incomplete_features)]
+#![feature(generic_const_exprs)]
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use plonky2::field::extension::FieldExtension;
+use plonky2::field::packable::Packable;
+use plonky2::field::packed::PackedField;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::field::types::Field;
+use plonky2::fri::oracle::PolynomialBatch;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+use plonky2::util::timing::TimingTree;
+use starky::config::StarkConfig;
+use starky::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use starky::prover::{compute_quotient_values_range, prove, quotient_domain_size};
+use starky::stark::Stark;
+use starky::util::trace_rows_to_poly_values;
+use starky::vars::{StarkEvaluationTargets, StarkEvaluationVars};
+
+const D: usize = 2;
+type C = PoseidonGoldilocksConfig;
+type F = <C as GenericConfig<D>>::F;
+
+/// Trace sizes, as log2 of the number of rows.
+const SIZES_LOG: [usize; 6] = [12, 14, 16, 18, 20, 22];
+
+/// Computes a Fibonacci sequence with state `[x0, x1]`, with public inputs `[x0, x1]` for the
+/// first row. It isn't generic over the field, as constraints on `Self::COLUMNS` can't be stated
+/// outside of starky.
+#[derive(Copy, Clone)]
+struct FibonacciStark;
+
+impl FibonacciStark {
+    fn generate_trace(&self, num_rows: usize) -> Vec<PolynomialValues<F>> {
+        let rows = (0..num_rows)
+            .scan([F::ZERO, F::ONE], |acc, _| {
+                let row = *acc;
+                *acc = [row[1], row[0] + row[1]];
+                Some(row)
+            })
+            .collect();
+        trace_rows_to_poly_values(rows)
+    }
+}
+
+impl Stark<F, D> for FibonacciStark {
+    const COLUMNS: usize = 2;
+    const PUBLIC_INPUTS: usize = 2;
+
+    fn eval_packed_generic<FE, P, const D2: usize>(
+        &self,
+        vars: StarkEvaluationVars<FE, P, { Self::COLUMNS }, { Self::PUBLIC_INPUTS }>,
+        yield_constr: &mut ConstraintConsumer<P>,
+    ) where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>,
+    {
+        yield_constr.constraint_first_row(vars.local_values[0] - vars.public_inputs[0]);
+        yield_constr.constraint_first_row(vars.local_values[1] - vars.public_inputs[1]);
+        yield_constr.constraint_transition(vars.next_values[0] - vars.local_values[1]);
+        yield_constr.constraint_transition(
+            vars.next_values[1] - vars.local_values[0] - vars.local_values[1],
+        );
+    }
+
+    fn eval_ext_circuit(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: StarkEvaluationTargets<D, { Self::COLUMNS }, { Self::PUBLIC_INPUTS }>,
+        yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+    ) {
+        for i in 0..2 {
+            let constraint = builder.sub_extension(vars.local_values[i], vars.public_inputs[i]);
+            yield_constr.constraint_first_row(builder, constraint);
+        }
+        let constraint = builder.sub_extension(vars.next_values[0], vars.local_values[1]);
+        yield_constr.constraint_transition(builder, constraint);
+        let sum = builder.add_extension(vars.local_values[0], vars.local_values[1]);
+        let constraint = builder.sub_extension(vars.next_values[1], sum);
+        yield_constr.constraint_transition(builder, constraint);
+    }
+
+    fn constraint_degree(&self) -> usize {
+        2
+    }
+}
+
+fn bench_quotient(c: &mut Criterion) {
+    type S = FibonacciStark;
+
+    let mut group = c.benchmark_group("stark-quotient");
+    group.sample_size(10);
+
+    let config = StarkConfig::standard_fast_config();
+    let stark = FibonacciStark;
+    let alphas = vec![F::from_canonical_u64(3), F::from_canonical_u64(5)];
+    for size_log in SIZES_LOG {
+        let size = 1 << size_log;
+        let trace_commitment = PolynomialBatch::<F, C, D>::from_values(
+            stark.generate_trace(size),
+            config.fri_config.rate_bits,
+            false,
+            config.fri_config.cap_height,
+            &mut TimingTree::default(),
+            None,
+        );
+        let domain_size = quotient_domain_size(&stark, size_log);
+
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, _| {
+            b.iter(|| {
+                compute_quotient_values_range::<F, <F as Packable>::Packing, C, S, D>(
+                    &stark,
+                    &trace_commitment,
+                    None,
+                    [F::ZERO, F::ONE],
+                    &alphas,
+                    size_log,
+                    &config,
+                    0..domain_size,
+                )
+            });
+        });
+    }
+}
+
+fn bench_prove(c: &mut Criterion) {
+    type S = FibonacciStark;
+
+    let mut group = c.benchmark_group("stark-prove");
+    group.sample_size(10);
+
+    let config = StarkConfig::standard_fast_config();
+    let stark = FibonacciStark;
+    for size_log in SIZES_LOG {
+        let size = 1 << size_log;
+        let trace = stark.generate_trace(size);
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, _| {
+            b.iter(|| {
+                prove::<F, C, S, D>(
+                    stark,
+                    &config,
+                    trace.clone(),
+                    [F::ZERO, F::ONE],
+                    &mut TimingTree::default(),
+                )
+                .expect("Proving failed")
+            });
+        });
+    }
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    bench_quotient(c);
+    bench_prove(c);
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,204 @@
+//! Type-erased STARK verifiers, so that a service can register verifiers for many STARKs at
+//! runtime and dispatch serialized proofs to them by name.
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use anyhow::{anyhow, ensure, Result};
+use plonky2::field::extension::Extendable;
+use plonky2::hash::hash_types::RichField;
+use plonky2::plonk::config::GenericConfig;
+use serde::{Deserialize, Serialize};
+
+use crate::config::StarkConfig;
+use crate::proof::StarkProofWithPublicInputs;
+use crate::stark::Stark;
+use crate::verifier::verify_stark_proof;
+
+/// Describes the STARK and config behind an [`AnyStarkVerifier`].
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct StarkVerifierMetadata {
+    pub name: String,
+    pub columns: usize,
+    pub public_inputs: usize,
+    pub config: StarkConfig,
+}
+
+/// A verifier for proofs of some STARK, which isn't part of the type. This trait is object safe,
+/// so verifiers of different STARKs can be stored together as `Box<dyn AnyStarkVerifier<..>>`.
+pub trait AnyStarkVerifier<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>:
+    Send + Sync
+{
+    fn metadata(&self) -> &StarkVerifierMetadata;
+
+    /// Deserializes a proof written by `StarkProofWithPublicInputs::to_bytes` and verifies it,
+    /// returning its public inputs.
+    fn verify_bytes(&self, proof_bytes: &[u8]) -> Result<Vec<F>>;
+
+    /// Verifies an already deserialized proof.
+    fn verify(&self, proof_with_pis: StarkProofWithPublicInputs<F, C, D>) -> Result<()>;
+}
+
+/// The [`AnyStarkVerifier`] of a STARK `S` under a fixed config.
+pub struct StarkVerifier<F: RichField + Extendable<D>, S: Stark<F, D>, const D: usize> {
+    stark: S,
+    metadata: StarkVerifierMetadata,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, S: Stark<F, D>, const D: usize> StarkVerifier<F, S, D> {
+    pub fn new(name: &str, stark: S, config: StarkConfig) -> Self {
+        Self {
+            stark,
+            metadata: StarkVerifierMetadata {
+                name: name.into(),
+                columns: S::COLUMNS,
+                public_inputs: S::PUBLIC_INPUTS,
+                config,
+            },
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<F, C, S, const D: usize> AnyStarkVerifier<F, C, D> for StarkVerifier<F, S, D>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    S: Stark<F, D> + Clone + Send,
+    [(); S::COLUMNS]:,
+    [(); S::PUBLIC_INPUTS]:,
+{
+    fn metadata(&self) -> &StarkVerifierMetadata {
+        &self.metadata
+    }
+
+    fn verify_bytes(&self, proof_bytes: &[u8]) -> Result<Vec<F>> {
+        let config = &self.metadata.config;
+        let proof_with_pis =
+            StarkProofWithPublicInputs::<F, C, D>::from_bytes(proof_bytes, &self.stark, config)?;
+        let public_inputs = proof_with_pis.public_inputs.clone();
+        verify_stark_proof(self.stark.clone(), proof_with_pis, config)?;
+        Ok(public_inputs)
+    }
+
+    fn verify(&self, proof_with_pis: StarkProofWithPublicInputs<F, C, D>) -> Result<()> {
+        verify_stark_proof(self.stark.clone(), proof_with_pis, &self.metadata.config)
+    }
+}
+
+/// Verifiers of several STARKs, looked up by the name in their metadata.
+pub struct StarkVerifierRegistry<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+> {
+    verifiers: BTreeMap<String, Box<dyn AnyStarkVerifier<F, C, D>>>,
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    StarkVerifierRegistry<F, C, D>
+{
+    pub fn new() -> Self {
+        Self {
+            verifiers: BTreeMap::new(),
+        }
+    }
+
+    /// Registers a verifier, failing if one with the same name is already registered.
+    pub fn register(&mut self, verifier: Box<dyn AnyStarkVerifier<F, C, D>>) -> Result<()> {
+        let name = verifier.metadata().name.clone();
+        ensure!(
+            !self.verifiers.contains_key(&name),
+            "A STARK verifier named {} is already registered",
+            name
+        );
+        self.verifiers.insert(name, verifier);
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn AnyStarkVerifier<F, C, D>> {
+        self.verifiers.get(name).map(|v| v.as_ref())
+    }
+
+    /// The metadata of all registered verifiers, sorted by name.
+    pub fn metadata(&self) -> Vec<&StarkVerifierMetadata> {
+        self.verifiers.values().map(|v| v.metadata()).collect()
+    }
+
+    /// Verifies a serialized proof with the verifier named `name`, returning its public inputs.
+    pub fn verify_bytes(&self, name: &str, proof_bytes: &[u8]) -> Result<Vec<F>> {
+        self.get(name)
+            .ok_or_else(|| anyhow!("No STARK verifier named {}", name))?
+            .verify_bytes(proof_bytes)
+    }
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> Default
+    for StarkVerifierRegistry<F, C, D>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2::field::types::Field;
+    use plonky2::plonk::config::PoseidonGoldilocksConfig;
+    use plonky2::util::timing::TimingTree;
+
+    use super::*;
+    use crate::fibonacci_stark::FibonacciStark;
+    use crate::prover::prove;
+
+    #[test]
+    fn test_registry() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = FibonacciStark<F, D>;
+
+        let config = StarkConfig::standard_fast_config();
+        let num_rows = 1 << 5;
+        let x = (0..num_rows - 1)
+            .fold((F::ZERO, F::ONE), |x, _| (x.1, x.0 + x.1))
+            .1;
+        let public_inputs = [F::ZERO, F::ONE, x];
+        let stark = S::new(num_rows);
+        let trace = stark.generate_trace(public_inputs[0], public_inputs[1]);
+        let proof = prove::<F, C, S, D>(
+            stark,
+            &config,
+            trace,
+            public_inputs,
+            &mut TimingTree::default(),
+        )?;
+        let bytes = proof.to_bytes(&config);
+
+        let mut registry = StarkVerifierRegistry::<F, C, D>::new();
+        registry.register(Box::new(StarkVerifier::new("fib32", stark, config.clone())))?;
+        registry.register(Box::new(StarkVerifier::new(
+            "fib32_salted",
+            stark,
+            StarkConfig::standard_fast_salted_config(),
+        )))?;
+        assert!(registry
+            .register(Box::new(StarkVerifier::new("fib32", stark, config)))
+            .is_err());
+        assert_eq!(registry.metadata().len(), 2);
+        assert_eq!(
+            registry.get("fib32").unwrap().metadata().columns,
+            S::COLUMNS
+        );
+
+        assert_eq!(registry.verify_bytes("fib32", &bytes)?, public_inputs);
+        // Proofs are only accepted under the config they were generated with.
+        assert!(registry.verify_bytes("fib32_salted", &bytes).is_err());
+        assert!(registry.verify_bytes("unknown", &bytes).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,171 @@
+//! Snapshots of the prover state between phases, so that proving a large trace can be resumed
+//! after an interruption instead of starting over. See [`crate::prover::prove_with_checkpoints`].
+
+use alloc::vec::Vec;
+
+use anyhow::{anyhow, ensure, Result};
+use plonky2::field::extension::Extendable;
+use plonky2::fri::oracle::PolynomialBatch;
+use plonky2::hash::hash_types::RichField;
+use plonky2::plonk::config::GenericConfig;
+use plonky2::util::serialization::{Buffer, IoResult, Read, Remaining, Write};
+
+use crate::config::StarkConfig;
+use crate::stark::{used_columns, Stark};
+
+/// Version of the [`ProverCheckpoint`] byte encoding.
+pub const CHECKPOINT_VERSION: u8 = 1;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ProverPhase {
+    /// The trace and, if any, permutation `Z` polynomials have been committed to.
+    TraceCommitted,
+    /// The quotient polynomials have been committed to as well. Only the openings and the FRI
+    /// proof remain.
+    QuotientCommitted,
+}
+
+/// The commitments computed by the prover so far. Challenges are not stored, as they are
+/// re-derived from the transcript when resuming.
+#[derive(Debug, Eq, PartialEq)]
+pub struct ProverCheckpoint<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+> {
+    pub degree_bits: usize,
+    pub trace_commitment: PolynomialBatch<F, C, D>,
+    pub permutation_zs_commitment: Option<PolynomialBatch<F, C, D>>,
+    pub quotient_commitment: Option<PolynomialBatch<F, C, D>>,
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    ProverCheckpoint<F, C, D>
+{
+    pub fn phase(&self) -> ProverPhase {
+        if self.quotient_commitment.is_some() {
+            ProverPhase::QuotientCommitted
+        } else {
+            ProverPhase::TraceCommitted
+        }
+    }
+
+    /// Checks that this checkpoint could have been produced when proving `stark` with `config`
+    /// over a trace of length `2^degree_bits`.
+    pub fn check_compatible<S: Stark<F, D>>(
+        &self,
+        stark: &S,
+        config: &StarkConfig,
+        degree_bits: usize,
+    ) -> Result<()> {
+        ensure!(
+            self.degree_bits == degree_bits,
+            "Checkpoint is for a trace of 2^{} rows, not 2^{}",
+            self.degree_bits,
+            degree_bits
+        );
+        ensure!(
+            self.trace_commitment.polynomials.len() == used_columns(stark).len(),
+            "Checkpoint does not match the STARK's column usage"
+        );
+        ensure!(
+            self.permutation_zs_commitment.is_some() == stark.uses_auxiliary_polys(),
+            "Checkpoint does not match the STARK's permutation arguments"
+        );
+        let trace_rate_bits = config.trace_rate_bits();
+        let rate_bits = core::iter::once(trace_rate_bits)
+            .chain(
+                self.permutation_zs_commitment
+                    .as_ref()
+                    .map(|_| trace_rate_bits),
+            )
+            .chain(
+                self.quotient_commitment
+                    .as_ref()
+                    .map(|_| config.fri_config.rate_bits),
+            );
+        for (commitment, rate_bits) in self.commitments().zip(rate_bits) {
+            ensure!(
+                commitment.rate_bits == rate_bits
+                    && commitment.leaf_group_bits() == config.leaf_group_bits
+                    && commitment.merkle_tree.cap.height() == config.fri_config.cap_height,
+                "Checkpoint was created with a different FRI configuration"
+            );
+        }
+        Ok(())
+    }
+
+    fn commitments(&self) -> impl Iterator<Item = &PolynomialBatch<F, C, D>> {
+        core::iter::once(&self.trace_commitment)
+            .chain(&self.permutation_zs_commitment)
+            .chain(&self.quotient_commitment)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        self.write(&mut buffer)
+            .expect("Writing to a byte-vector cannot fail.");
+        buffer
+    }
+
+    fn write(&self, buffer: &mut Vec<u8>) -> IoResult<()> {
+        buffer.write_u8(CHECKPOINT_VERSION)?;
+        buffer.write_usize(self.degree_bits)?;
+        buffer.write_polynomial_batch(&self.trace_commitment)?;
+        for commitment in [&self.permutation_zs_commitment, &self.quotient_commitment] {
+            buffer.write_bool(commitment.is_some())?;
+            if let Some(commitment) = commitment {
+                buffer.write_polynomial_batch(commitment)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut buffer = Buffer::new(bytes);
+        let version = buffer.read_u8().map_err(|_| anyhow!("Empty checkpoint"))?;
+        ensure!(
+            version == CHECKPOINT_VERSION,
+            "Unsupported checkpoint version {}",
+            version
+        );
+        let checkpoint = Self::read(&mut buffer).map_err(|_| anyhow!("Invalid checkpoint"))?;
+        ensure!(buffer.is_empty(), "Trailing bytes in checkpoint");
+        Ok(checkpoint)
+    }
+
+    fn read(buffer: &mut Buffer<'_>) -> IoResult<Self> {
+        let degree_bits = buffer.read_usize()?;
+        let trace_commitment = buffer.read_polynomial_batch()?;
+        let mut read_optional = || -> IoResult<Option<PolynomialBatch<F, C, D>>> {
+            if buffer.read_bool()? {
+                buffer.read_polynomial_batch().map(Some)
+            } else {
+                Ok(None)
+            }
+        };
+        let permutation_zs_commitment = read_optional()?;
+        let quotient_commitment = read_optional()?;
+        Ok(Self {
+            degree_bits,
+            trace_commitment,
+            permutation_zs_commitment,
+            quotient_commitment,
+        })
+    }
+
+    /// Writes the checkpoint to `path`. The file is written under a temporary name first and then
+    /// renamed, so an interruption never leaves a truncated checkpoint behind.
+    #[cfg(feature = "std")]
+    pub fn save(&self, path: &std::path::Path) -> Result<()> {
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, self.to_bytes())?;
+        std::fs::rename(tmp, path)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    pub fn load(path: &std::path::Path) -> Result<Self> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+}
@@ -0,0 +1,232 @@
+//! Named trace columns.
+//!
+//! [`column_layout!`](crate::column_layout) declares a `#[repr(C)]` view of a trace row, whose
+//! fields are single columns (`T`), groups of consecutive columns (`[T; N]`) or nested layouts.
+//! It also defines the number of columns, and a map of the layout with each field holding its
+//! column indices. Rows can then be read and written by name, both when generating the trace and
+//! when evaluating constraints:
+//!
+//! ```ignore
+//! column_layout! {
+//!     pub struct FibonacciColumns<T> {
+//!         pub x: [T; 2],
+//!         pub counter: T,
+//!     }
+//!     num_columns = NUM_FIBONACCI_COLUMNS;
+//!     col_map = FIBONACCI_COL_MAP;
+//! }
+//!
+//! let local: &FibonacciColumns<P> = vars.local_values.borrow();
+//! let next: &FibonacciColumns<P> = vars.next_values.borrow();
+//! yield_constr.constraint_transition(next.x[0] - local.x[1]);
+//! ```
+
+#[doc(hidden)]
+pub use alloc::vec::Vec;
+use core::mem::{size_of, transmute_copy, ManuallyDrop};
+use core::ops::Range;
+
+/// Declares a view of a trace row with named columns. See the [module docs](self).
+#[macro_export]
+macro_rules! column_layout {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $name:ident<$t:ident> {
+            $($(#[$field_attr:meta])* $field_vis:vis $field:ident : $field_ty:ty),* $(,)?
+        }
+        num_columns = $num_columns:ident;
+        col_map = $col_map:ident;
+    ) => {
+        $(#[$attr])*
+        #[repr(C)]
+        #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+        $vis struct $name<$t: Copy> {
+            $($(#[$field_attr])* $field_vis $field: $field_ty,)*
+        }
+
+        /// The number of columns in the layout.
+        $vis const $num_columns: usize = ::core::mem::size_of::<$name<u8>>();
+
+        // Every field must consist of columns of type `T` only.
+        const _: () = assert!(
+            ::core::mem::size_of::<$name<u64>>() == $num_columns * ::core::mem::size_of::<u64>()
+        );
+
+        /// The layout with each field holding the indices of its columns.
+        $vis const $col_map: $name<usize> = unsafe {
+            ::core::mem::transmute::<[usize; $num_columns], $name<usize>>(
+                $crate::column_layout::indices_arr::<$num_columns>(),
+            )
+        };
+
+        #[allow(dead_code)]
+        impl $name<usize> {
+            /// The name of each field, with the range of its columns.
+            pub fn fields(
+                &self,
+            ) -> $crate::column_layout::Vec<(&'static str, ::core::ops::Range<usize>)> {
+                $crate::column_layout::Vec::from([$((
+                    stringify!($field),
+                    $crate::column_layout::column_range(unsafe {
+                        $crate::column_layout::field_columns(&self.$field)
+                    }),
+                )),*])
+            }
+
+            /// The name of the field containing `column`, and the column's offset in that field.
+            pub fn column_name(&self, column: usize) -> Option<(&'static str, usize)> {
+                self.fields()
+                    .into_iter()
+                    .find(|(_, range)| range.contains(&column))
+                    .map(|(name, range)| (name, column - range.start))
+            }
+        }
+
+        impl<$t: Copy + Default> Default for $name<$t> {
+            fn default() -> Self {
+                Self::from([$t::default(); $num_columns])
+            }
+        }
+
+        #[allow(dead_code)]
+        impl<$t: Copy> $name<$t> {
+            pub fn as_slice(&self) -> &[$t] {
+                let arr: &[$t; $num_columns] = ::core::borrow::Borrow::borrow(self);
+                arr
+            }
+        }
+
+        impl<$t: Copy> From<[$t; $num_columns]> for $name<$t> {
+            fn from(value: [$t; $num_columns]) -> Self {
+                unsafe { $crate::column_layout::transmute_no_compile_time_size_checks(value) }
+            }
+        }
+
+        impl<$t: Copy> From<$name<$t>> for [$t; $num_columns] {
+            fn from(value: $name<$t>) -> Self {
+                unsafe { $crate::column_layout::transmute_no_compile_time_size_checks(value) }
+            }
+        }
+
+        impl<$t: Copy> ::core::borrow::Borrow<$name<$t>> for [$t; $num_columns] {
+            fn borrow(&self) -> &$name<$t> {
+                unsafe { ::core::mem::transmute(self) }
+            }
+        }
+
+        impl<$t: Copy> ::core::borrow::BorrowMut<$name<$t>> for [$t; $num_columns] {
+            fn borrow_mut(&mut self) -> &mut $name<$t> {
+                unsafe { ::core::mem::transmute(self) }
+            }
+        }
+
+        impl<$t: Copy> ::core::borrow::Borrow<[$t; $num_columns]> for $name<$t> {
+            fn borrow(&self) -> &[$t; $num_columns] {
+                unsafe { ::core::mem::transmute(self) }
+            }
+        }
+
+        impl<$t: Copy> ::core::borrow::BorrowMut<[$t; $num_columns]> for $name<$t> {
+            fn borrow_mut(&mut self) -> &mut [$t; $num_columns] {
+                unsafe { ::core::mem::transmute(self) }
+            }
+        }
+    };
+}
+
+#[doc(hidden)]
+pub const fn indices_arr<const N: usize>() -> [usize; N] {
+    let mut indices_arr = [0; N];
+    let mut i = 0;
+    while i < N {
+        indices_arr[i] = i;
+        i += 1;
+    }
+    indices_arr
+}
+
+/// # Safety
+/// `T` and `U` must have the same size, and the bits of `value` must be a valid `U`.
+#[doc(hidden)]
+pub unsafe fn transmute_no_compile_time_size_checks<T, U>(value: T) -> U {
+    debug_assert_eq!(size_of::<T>(), size_of::<U>());
+    // Need ManuallyDrop so that `value` is not dropped by this function.
+    let value = ManuallyDrop::new(value);
+    // Copy the bit pattern. The original value is no longer safe to use.
+    transmute_copy(&value)
+}
+
+/// The column indices held by a field of a column map.
+///
+/// # Safety
+/// `U` must consist of `usize`s only.
+#[doc(hidden)]
+pub unsafe fn field_columns<U>(field: &U) -> &[usize] {
+    let len = size_of::<U>() / size_of::<usize>();
+    core::slice::from_raw_parts(field as *const U as *const usize, len)
+}
+
+/// The range spanned by a group of consecutive columns, such as `COL_MAP.limbs`.
+pub fn column_range(columns: &[usize]) -> Range<usize> {
+    assert!(!columns.is_empty(), "Empty column group");
+    assert!(
+        columns.windows(2).all(|w| w[1] == w[0] + 1),
+        "Columns are not consecutive"
+    );
+    columns[0]..columns[columns.len() - 1] + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use core::borrow::Borrow;
+
+    use super::*;
+
+    column_layout! {
+        struct InnerColumns<T> {
+            a: T,
+            b: [T; 2],
+        }
+        num_columns = NUM_INNER_COLUMNS;
+        col_map = INNER_COL_MAP;
+    }
+
+    column_layout! {
+        struct TestColumns<T> {
+            x: T,
+            limbs: [T; 3],
+            inner: [InnerColumns<T>; 2],
+        }
+        num_columns = NUM_TEST_COLUMNS;
+        col_map = TEST_COL_MAP;
+    }
+
+    #[test]
+    fn test_column_layout() {
+        assert_eq!(NUM_INNER_COLUMNS, 3);
+        assert_eq!(NUM_TEST_COLUMNS, 10);
+        assert_eq!(TEST_COL_MAP.x, 0);
+        assert_eq!(TEST_COL_MAP.limbs, [1, 2, 3]);
+        assert_eq!(TEST_COL_MAP.inner[1].b, [8, 9]);
+        assert_eq!(INNER_COL_MAP.b, [1, 2]);
+        assert_eq!(column_range(&TEST_COL_MAP.limbs), 1..4);
+        assert_eq!(column_range(TEST_COL_MAP.inner[0].as_slice()), 4..7);
+
+        assert_eq!(
+            TEST_COL_MAP.fields(),
+            vec![("x", 0..1), ("limbs", 1..4), ("inner", 4..10)]
+        );
+        assert_eq!(TEST_COL_MAP.column_name(2), Some(("limbs", 1)));
+        assert_eq!(TEST_COL_MAP.column_name(10), None);
+
+        let mut row = TestColumns::<u64>::default();
+        row.limbs[2] = 5;
+        row.inner[1].a = 7;
+        let values: [u64; NUM_TEST_COLUMNS] = row.into();
+        assert_eq!(values[TEST_COL_MAP.limbs[2]], 5);
+        assert_eq!(values[TEST_COL_MAP.inner[1].a], 7);
+        let view: &TestColumns<u64> = values.borrow();
+        assert_eq!(*view, row);
+    }
+}
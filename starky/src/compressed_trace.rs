@@ -0,0 +1,247 @@
+//! Compressed storage of trace columns which are mostly constant, such as the selectors of a VM
+//! trace. A column is stored as runs of equal values, as indices into a small dictionary of its
+//! distinct values, or as is, whichever is smallest. [`prove_compressed`] decompresses the whole
+//! trace up front and then proves as [`prove`] does, so the prover's peak memory is unchanged.
+//!
+//! This only saves memory while the trace waits to be proven. The trace oracle commits to the LDE
+//! of each column rather than to the column itself, and the LDE of a column that isn't entirely
+//! constant has no repeated structure left, so its Merkle leaves can't be compressed. Doing so
+//! would mean committing to the trace itself, which FRI can't open at out-of-domain points.
+
+use alloc::vec::Vec;
+
+use anyhow::{ensure, Result};
+use plonky2::field::extension::Extendable;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::field::types::Field;
+use plonky2::hash::hash_types::RichField;
+use plonky2::plonk::config::GenericConfig;
+use plonky2::util::timing::TimingTree;
+
+use crate::config::StarkConfig;
+use crate::proof::StarkProofWithPublicInputs;
+use crate::prover::prove;
+use crate::stark::Stark;
+
+/// Dictionaries hold at most this many values, so that indices fit in a byte.
+pub const MAX_DICTIONARY_SIZE: usize = 1 << 8;
+
+/// A trace column, stored in whichever of these encodings is smallest.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CompressedColumn<F: Field> {
+    /// Runs of equal values, as `(value, length)` pairs.
+    RunLength(Vec<(F, usize)>),
+    /// The column's distinct values, and the index of each row's value among them.
+    Dictionary {
+        dictionary: Vec<F>,
+        indices: Vec<u8>,
+    },
+    Raw(Vec<F>),
+}
+
+impl<F: Field> CompressedColumn<F> {
+    pub fn compress(column: &PolynomialValues<F>) -> Self {
+        let values = &column.values;
+        let mut runs: Vec<(F, usize)> = Vec::new();
+        for &value in values {
+            match runs.last_mut() {
+                Some((last, len)) if *last == value => *len += 1,
+                _ => runs.push((value, 1)),
+            }
+        }
+
+        let mut dictionary = Vec::new();
+        let indices = values
+            .iter()
+            .map(|value| {
+                let index = dictionary
+                    .iter()
+                    .position(|v| v == value)
+                    .unwrap_or_else(|| {
+                        dictionary.push(*value);
+                        dictionary.len() - 1
+                    });
+                u8::try_from(index).ok()
+            })
+            .take_while(Option::is_some)
+            .flatten()
+            .collect::<Vec<_>>();
+
+        // Sizes in bytes, counting 8 bytes per field element or run length.
+        let run_length_size = 16 * runs.len();
+        let dictionary_size = (indices.len() == values.len()
+            && dictionary.len() <= MAX_DICTIONARY_SIZE)
+            .then_some(8 * dictionary.len() + indices.len());
+        let raw_size = 8 * values.len();
+        match dictionary_size {
+            Some(size) if size < run_length_size.min(raw_size) => Self::Dictionary {
+                dictionary,
+                indices,
+            },
+            _ if run_length_size < raw_size => Self::RunLength(runs),
+            _ => Self::Raw(values.clone()),
+        }
+    }
+
+    /// The number of rows of the column.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::RunLength(runs) => runs.iter().map(|&(_, len)| len).sum(),
+            Self::Dictionary { indices, .. } => indices.len(),
+            Self::Raw(values) => values.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn decompress(&self) -> PolynomialValues<F> {
+        let values = match self {
+            Self::RunLength(runs) => runs
+                .iter()
+                .flat_map(|&(value, len)| core::iter::repeat(value).take(len))
+                .collect(),
+            Self::Dictionary {
+                dictionary,
+                indices,
+            } => indices.iter().map(|&i| dictionary[i as usize]).collect(),
+            Self::Raw(values) => values.clone(),
+        };
+        PolynomialValues::new(values)
+    }
+}
+
+/// A trace whose columns are each stored compressed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CompressedTrace<F: Field> {
+    pub columns: Vec<CompressedColumn<F>>,
+}
+
+impl<F: Field> CompressedTrace<F> {
+    pub fn compress(trace: &[PolynomialValues<F>]) -> Self {
+        Self {
+            columns: trace.iter().map(CompressedColumn::compress).collect(),
+        }
+    }
+
+    /// Checks that the columns have the same length, which a trace built from separately
+    /// compressed columns might not, and that dictionary indices are in range.
+    pub fn validate(&self) -> Result<()> {
+        let num_rows = self.columns.first().map_or(0, CompressedColumn::len);
+        ensure!(
+            self.columns.iter().all(|c| c.len() == num_rows),
+            "Compressed columns have different lengths"
+        );
+        for column in &self.columns {
+            if let CompressedColumn::Dictionary {
+                dictionary,
+                indices,
+            } = column
+            {
+                ensure!(
+                    indices.iter().all(|&i| (i as usize) < dictionary.len()),
+                    "Dictionary index out of range"
+                );
+            }
+        }
+        Ok(())
+    }
+
+    pub fn decompress(&self) -> Vec<PolynomialValues<F>> {
+        self.columns
+            .iter()
+            .map(CompressedColumn::decompress)
+            .collect()
+    }
+}
+
+/// Like [`prove`], but with a compressed trace. The trace is validated, then decompressed in full
+/// before proving starts.
+pub fn prove_compressed<F, C, S, const D: usize>(
+    stark: S,
+    config: &StarkConfig,
+    trace: &CompressedTrace<F>,
+    public_inputs: [F; S::PUBLIC_INPUTS],
+    timing: &mut TimingTree,
+) -> Result<StarkProofWithPublicInputs<F, C, D>>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    S: Stark<F, D>,
+    [(); S::COLUMNS]:,
+    [(); S::PUBLIC_INPUTS]:,
+{
+    trace.validate()?;
+    prove(stark, config, trace.decompress(), public_inputs, timing)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::field::types::Sample;
+    use plonky2::plonk::config::PoseidonGoldilocksConfig;
+
+    use super::*;
+    use crate::fibonacci_stark::FibonacciStark;
+    use crate::verifier::verify_stark_proof;
+
+    type F = GoldilocksField;
+
+    #[test]
+    fn test_compressed_column_encodings() {
+        let selector = PolynomialValues::new([vec![F::ONE; 100], vec![F::ZERO; 28]].concat());
+        let opcodes =
+            PolynomialValues::new((0..128).map(|i| F::from_canonical_usize(i % 5)).collect());
+        let random = PolynomialValues::new(F::rand_vec(128));
+        let trace = vec![selector, opcodes, random];
+
+        let compressed = CompressedTrace::compress(&trace);
+        assert_eq!(
+            compressed.columns[0],
+            CompressedColumn::RunLength(vec![(F::ONE, 100), (F::ZERO, 28)])
+        );
+        assert!(matches!(
+            compressed.columns[1],
+            CompressedColumn::Dictionary { .. }
+        ));
+        assert!(matches!(compressed.columns[2], CompressedColumn::Raw(_)));
+        assert_eq!(compressed.decompress(), trace);
+
+        let mut truncated = compressed.clone();
+        truncated.columns[0] = CompressedColumn::RunLength(vec![(F::ONE, 127)]);
+        assert!(truncated.validate().is_err());
+
+        let mut out_of_range = compressed;
+        out_of_range.columns[1] = CompressedColumn::Dictionary {
+            dictionary: vec![F::ZERO, F::ONE],
+            indices: [vec![0; 127], vec![2]].concat(),
+        };
+        assert!(out_of_range.validate().is_err());
+    }
+
+    #[test]
+    fn test_prove_compressed() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type S = FibonacciStark<F, D>;
+
+        let config = StarkConfig::standard_fast_config();
+        let num_rows = 1 << 5;
+        let stark = S::new(num_rows);
+        let trace = stark.generate_trace(F::ZERO, F::ONE);
+        let x1 = (0..num_rows - 1)
+            .fold((F::ZERO, F::ONE), |x, _| (x.1, x.0 + x.1))
+            .1;
+        let proof = prove_compressed::<F, C, S, D>(
+            stark,
+            &config,
+            &CompressedTrace::compress(&trace),
+            [F::ZERO, F::ONE, x1],
+            &mut TimingTree::default(),
+        )?;
+        verify_stark_proof(stark, proof, &config)
+    }
+}
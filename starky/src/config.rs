@@ -1,6 +1,15 @@
+use anyhow::{ensure, Result};
+use plonky2::field::extension::Extendable;
+use plonky2::field::types::Field;
 use plonky2::fri::reduction_strategies::FriReductionStrategy;
+use plonky2::fri::security::SecurityAssumption;
 use plonky2::fri::{FriConfig, FriParams};
+use plonky2::hash::hash_types::RichField;
+use serde::{Deserialize, Serialize};
 
+use crate::stark::Stark;
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct StarkConfig {
     pub security_bits: usize,
 
@@ -9,6 +18,33 @@ pub struct StarkConfig {
     pub num_challenges: usize,
 
     pub fri_config: FriConfig,
+
+    /// Whether to salt the leaves of every commitment, so that Merkle proofs don't reveal the
+    /// neighbouring leaves. This costs `SALT_SIZE` extra field elements per leaf, in the prover's
+    /// Merkle trees and in each opened leaf of the proof.
+    ///
+    /// This does not make proofs zero-knowledge. The polynomials aren't blinded, so the openings at
+    /// the out-of-domain point and at the FRI query points reveal information about the trace. For
+    /// those not to leak the witness, the trace itself must be randomized, e.g. with rows of random
+    /// values which the constraints ignore, as plonky2 does for its circuits.
+    #[serde(default, alias = "zero_knowledge")]
+    pub salted_commitments: bool,
+
+    /// How many more rate bits the trace and auxiliary commitments use than
+    /// `fri_config.rate_bits`. Constraints are evaluated on the trace's LDE, which thus needs at
+    /// least `log2(quotient_degree_factor)` rate bits, while the quotient commitment and FRI can
+    /// use a lower rate. This makes the quotient commitment and FRI's commit phase cheaper, but
+    /// needs more query rounds for the same security.
+    #[serde(default)]
+    pub trace_extra_rate_bits: usize,
+
+    /// Each Merkle leaf of the trace, auxiliary and quotient commitments holds the values at
+    /// `2^leaf_group_bits` consecutive points of the LDE, in bit-reversed order. The Merkle trees
+    /// then have `2^leaf_group_bits` times fewer leaves and internal nodes, and each Merkle path is
+    /// `leaf_group_bits` hashes shorter, but each query opens `2^leaf_group_bits` times as many
+    /// values.
+    #[serde(default)]
+    pub leaf_group_bits: usize,
 }
 
 impl StarkConfig {
@@ -24,11 +60,110 @@ impl StarkConfig {
                 proof_of_work_bits: 16,
                 reduction_strategy: FriReductionStrategy::ConstantArityBits(4, 5),
                 num_query_rounds: 84,
+                security_assumption: SecurityAssumption::Conjectured,
+                coset_shift: None,
+            },
+            salted_commitments: false,
+            trace_extra_rate_bits: 0,
+            leaf_group_bits: 0,
+        }
+    }
+
+    /// Like `standard_fast_config`, but with salted commitments. See `salted_commitments`.
+    pub fn standard_fast_salted_config() -> Self {
+        Self {
+            salted_commitments: true,
+            ..Self::standard_fast_config()
+        }
+    }
+
+    /// Like `standard_fast_config`, but targets ~160 bit conjectured security. This needs an
+    /// extension field of more than 160 bits, such as the quintic extension of Goldilocks.
+    pub fn standard_fast_quintic_config() -> Self {
+        Self {
+            security_bits: 160,
+            num_challenges: 4,
+            fri_config: FriConfig {
+                num_query_rounds: 144,
+                ..Self::standard_fast_config().fri_config
             },
+            salted_commitments: false,
+            trace_extra_rate_bits: 0,
+            leaf_group_bits: 0,
         }
     }
 
+    /// A config like `standard_fast_config`, with as few FRI query rounds as reach `security_bits`
+    /// bits of security under `security_assumption`.
+    pub fn for_security(security_bits: usize, security_assumption: SecurityAssumption) -> Self {
+        let standard = Self::standard_fast_config();
+        Self {
+            security_bits,
+            fri_config: FriConfig::for_security(
+                security_bits,
+                security_assumption,
+                standard.fri_config.rate_bits,
+                standard.fri_config.cap_height,
+                standard.fri_config.reduction_strategy,
+            ),
+            ..standard
+        }
+    }
+
+    /// The bits of FRI security achieved when proving traces of `2^degree_bits` rows, over an
+    /// extension field of `F` of degree `D`.
+    pub fn achieved_security_bits<F: RichField + Extendable<D>, const D: usize>(
+        &self,
+        degree_bits: usize,
+    ) -> usize {
+        self.fri_config
+            .security_bits(F::Extension::order().bits() as usize, degree_bits)
+    }
+
+    /// The rate bits of the trace and auxiliary commitments.
+    pub fn trace_rate_bits(&self) -> usize {
+        self.fri_config.rate_bits + self.trace_extra_rate_bits
+    }
+
+    /// The highest constraint degree this config supports, as the trace's LDE must have at least
+    /// `constraint_degree - 1` points per row.
+    pub fn max_constraint_degree(&self) -> usize {
+        (1 << self.trace_rate_bits()) + 1
+    }
+
+    /// Checks that the config is consistent, and that `stark` can be proven with it. The FRI
+    /// parameters are checked again against the trace's length when proving.
+    pub fn validate<F, S, const D: usize>(&self, stark: &S) -> Result<()>
+    where
+        F: RichField + Extendable<D>,
+        S: Stark<F, D>,
+    {
+        self.fri_config.validate()?;
+        ensure!(
+            self.fri_config.coset_shift.is_none(),
+            "STARK provers only support the default coset shift"
+        );
+        ensure!(
+            stark.constraint_degree() <= self.max_constraint_degree(),
+            "The STARK's constraints have degree {}, but the trace's rate supports at most {}; \
+             increase `trace_extra_rate_bits` or `fri_config.rate_bits`",
+            stark.constraint_degree(),
+            self.max_constraint_degree()
+        );
+        // Under proven soundness, FRI's security also decreases with the trace length.
+        let fri_security_bits = self.achieved_security_bits::<F, D>(0);
+        ensure!(
+            fri_security_bits >= self.security_bits,
+            "FRI params fall short of target security: {} bits, targeting {}; add query rounds \
+             or proof-of-work bits",
+            fri_security_bits,
+            self.security_bits
+        );
+        Ok(())
+    }
+
     pub(crate) fn fri_params(&self, degree_bits: usize) -> FriParams {
-        self.fri_config.fri_params(degree_bits, false)
+        self.fri_config
+            .fri_params(degree_bits, self.salted_commitments)
     }
 }
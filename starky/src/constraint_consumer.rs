@@ -9,14 +9,15 @@ use plonky2::iop::ext_target::ExtensionTarget;
 use plonky2::iop::target::Target;
 use plonky2::plonk::circuit_builder::CircuitBuilder;
 
-pub struct ConstraintConsumer<P: PackedField> {
-    /// Random values used to combine multiple constraints into one.
-    alphas: Vec<P::Scalar>,
+pub struct ConstraintConsumer<'a, P: PackedField> {
+    /// Random values used to combine multiple constraints into one. They are borrowed, so that a
+    /// consumer can be created for every batch of points without copying them.
+    alphas: &'a [P::Scalar],
 
-    /// Running sums of constraints that have been emitted so far, scaled by powers of alpha.
-    // TODO(JN): This is pub so it can be used in a test. Once we have an API for accessing this
-    // result, it should be made private.
-    pub constraint_accs: Vec<P>,
+    /// Running sums of constraints that have been emitted so far, scaled by powers of alpha. They
+    /// are written into a buffer provided by the caller, so that evaluating constraints at many
+    /// points doesn't allocate; see `reset`.
+    constraint_accs: &'a mut [P],
 
     /// The evaluation of `X - g^(n-1)`.
     z_last: P,
@@ -30,23 +31,41 @@ pub struct ConstraintConsumer<P: PackedField> {
     lagrange_basis_last: P,
 }
 
-impl<P: PackedField> ConstraintConsumer<P> {
+impl<'a, P: PackedField> ConstraintConsumer<'a, P> {
+    /// Creates a consumer which accumulates constraints into `constraint_accs`, which must have one
+    /// entry per alpha. The accumulators are zeroed first.
     pub fn new(
-        alphas: Vec<P::Scalar>,
+        alphas: &'a [P::Scalar],
+        constraint_accs: &'a mut [P],
         z_last: P,
         lagrange_basis_first: P,
         lagrange_basis_last: P,
     ) -> Self {
+        assert_eq!(
+            constraint_accs.len(),
+            alphas.len(),
+            "Expected one accumulator per alpha"
+        );
+        constraint_accs.fill(P::ZEROS);
         Self {
-            constraint_accs: vec![P::ZEROS; alphas.len()],
             alphas,
+            constraint_accs,
             z_last,
             lagrange_basis_first,
             lagrange_basis_last,
         }
     }
 
-    pub fn accumulators(self) -> Vec<P> {
+    /// Zeroes the accumulators and moves on to the point with the given evaluations, so that one
+    /// consumer can be reused for every point evaluated by a task.
+    pub fn reset(&mut self, z_last: P, lagrange_basis_first: P, lagrange_basis_last: P) {
+        self.constraint_accs.fill(P::ZEROS);
+        self.z_last = z_last;
+        self.lagrange_basis_first = lagrange_basis_first;
+        self.lagrange_basis_last = lagrange_basis_last;
+    }
+
+    pub fn accumulators(&self) -> &[P] {
         self.constraint_accs
     }
 
@@ -57,7 +76,7 @@ impl<P: PackedField> ConstraintConsumer<P> {
 
     /// Add one constraint on all rows.
     pub fn constraint(&mut self, constraint: P) {
-        for (&alpha, acc) in self.alphas.iter().zip(&mut self.constraint_accs) {
+        for (&alpha, acc) in self.alphas.iter().zip(self.constraint_accs.iter_mut()) {
             *acc *= alpha;
             *acc += constraint;
         }
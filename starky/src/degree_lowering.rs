@@ -0,0 +1,514 @@
+//! Lowering the degree of constraints with auxiliary columns.
+//!
+//! Constraints are written as symbolic [`Expr`]s over the current and next rows. Whenever a product
+//! would exceed the maximum degree, [`LoweredConstraints`] moves its higher-degree factor into a
+//! new auxiliary column `aux`, constrained on every row by `aux - factor`, and uses `aux` in its
+//! place. The auxiliary columns follow the original ones, and their values are filled in by
+//! [`LoweredConstraints::generate_aux_columns`].
+//!
+//! A factor may read the next row, in which case its column is defined cyclically: its value on
+//! the last row is computed from the first row, which is what `g * x` refers to there.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::ops::{Add, Mul, Neg, Sub};
+
+use plonky2::field::extension::{Extendable, FieldExtension};
+use plonky2::field::packed::PackedField;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::field::types::Field;
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::ext_target::ExtensionTarget;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+
+use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+
+/// A polynomial expression in the values of the current and next rows.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Expr<F: Field> {
+    Constant(F),
+    /// The value of a column at the current row.
+    Local(usize),
+    /// The value of a column at the next row.
+    Next(usize),
+    Add(Box<Expr<F>>, Box<Expr<F>>),
+    Sub(Box<Expr<F>>, Box<Expr<F>>),
+    Mul(Box<Expr<F>>, Box<Expr<F>>),
+}
+
+impl<F: Field> Expr<F> {
+    pub fn constant(c: F) -> Self {
+        Self::Constant(c)
+    }
+
+    pub fn local(column: usize) -> Self {
+        Self::Local(column)
+    }
+
+    pub fn next(column: usize) -> Self {
+        Self::Next(column)
+    }
+
+    pub fn degree(&self) -> usize {
+        match self {
+            Expr::Constant(_) => 0,
+            Expr::Local(_) | Expr::Next(_) => 1,
+            Expr::Add(a, b) | Expr::Sub(a, b) => a.degree().max(b.degree()),
+            Expr::Mul(a, b) => a.degree() + b.degree(),
+        }
+    }
+
+    /// The largest column index read by this expression, if any.
+    fn max_column(&self) -> Option<usize> {
+        match self {
+            Expr::Constant(_) => None,
+            Expr::Local(c) | Expr::Next(c) => Some(*c),
+            Expr::Add(a, b) | Expr::Sub(a, b) | Expr::Mul(a, b) => {
+                a.max_column().max(b.max_column())
+            }
+        }
+    }
+
+    pub fn eval<FE, P, const D2: usize>(&self, local: &[P], next: &[P]) -> P
+    where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>,
+    {
+        match self {
+            Expr::Constant(c) => P::from(FE::from_basefield(*c)),
+            Expr::Local(c) => local[*c],
+            Expr::Next(c) => next[*c],
+            Expr::Add(a, b) => a.eval(local, next) + b.eval(local, next),
+            Expr::Sub(a, b) => a.eval(local, next) - b.eval(local, next),
+            Expr::Mul(a, b) => a.eval(local, next) * b.eval(local, next),
+        }
+    }
+
+    pub fn eval_circuit<const D: usize>(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        local: &[ExtensionTarget<D>],
+        next: &[ExtensionTarget<D>],
+    ) -> ExtensionTarget<D>
+    where
+        F: RichField + Extendable<D>,
+    {
+        match self {
+            Expr::Constant(c) => builder.constant_extension(F::Extension::from_basefield(*c)),
+            Expr::Local(c) => local[*c],
+            Expr::Next(c) => next[*c],
+            Expr::Add(a, b) => {
+                let a = a.eval_circuit(builder, local, next);
+                let b = b.eval_circuit(builder, local, next);
+                builder.add_extension(a, b)
+            }
+            Expr::Sub(a, b) => {
+                let a = a.eval_circuit(builder, local, next);
+                let b = b.eval_circuit(builder, local, next);
+                builder.sub_extension(a, b)
+            }
+            Expr::Mul(a, b) => {
+                let a = a.eval_circuit(builder, local, next);
+                let b = b.eval_circuit(builder, local, next);
+                builder.mul_extension(a, b)
+            }
+        }
+    }
+}
+
+impl<F: Field> Add for Expr<F> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Expr::Add(Box::new(self), Box::new(rhs))
+    }
+}
+
+impl<F: Field> Sub for Expr<F> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Expr::Sub(Box::new(self), Box::new(rhs))
+    }
+}
+
+impl<F: Field> Mul for Expr<F> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Expr::Mul(Box::new(self), Box::new(rhs))
+    }
+}
+
+impl<F: Field> Neg for Expr<F> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Expr::Constant(F::ZERO) - self
+    }
+}
+
+/// The rows on which a constraint is enforced, as in `ConstraintConsumer`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ConstraintFilter {
+    EveryRow,
+    Transition,
+    FirstRow,
+    LastRow,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SymbolicConstraint<F: Field> {
+    pub filter: ConstraintFilter,
+    pub expr: Expr<F>,
+}
+
+impl<F: Field> SymbolicConstraint<F> {
+    pub fn every_row(expr: Expr<F>) -> Self {
+        Self {
+            filter: ConstraintFilter::EveryRow,
+            expr,
+        }
+    }
+
+    pub fn transition(expr: Expr<F>) -> Self {
+        Self {
+            filter: ConstraintFilter::Transition,
+            expr,
+        }
+    }
+
+    pub fn first_row(expr: Expr<F>) -> Self {
+        Self {
+            filter: ConstraintFilter::FirstRow,
+            expr,
+        }
+    }
+
+    pub fn last_row(expr: Expr<F>) -> Self {
+        Self {
+            filter: ConstraintFilter::LastRow,
+            expr,
+        }
+    }
+}
+
+/// Constraints of degree at most `max_degree`, using auxiliary columns after the original ones.
+#[derive(Clone, Debug)]
+pub struct LoweredConstraints<F: Field> {
+    num_original_columns: usize,
+    max_degree: usize,
+    /// The lowered constraints, followed by the definitions of the auxiliary columns.
+    constraints: Vec<SymbolicConstraint<F>>,
+    /// The expression each auxiliary column is equal to.
+    aux_columns: Vec<Expr<F>>,
+}
+
+impl<F: Field> LoweredConstraints<F> {
+    /// Lowers `constraints`, over a trace of `num_columns` columns, to degree `max_degree`, which
+    /// is typically `StarkConfig::max_constraint_degree`. Identical factors share a column.
+    pub fn new(
+        constraints: Vec<SymbolicConstraint<F>>,
+        num_columns: usize,
+        max_degree: usize,
+    ) -> Self {
+        assert!(max_degree >= 2, "Products can't be lowered below degree 2");
+        assert!(
+            constraints
+                .iter()
+                .all(|c| c.expr.max_column().map_or(true, |m| m < num_columns)),
+            "Constraint reads a column outside the trace"
+        );
+
+        let mut lowered = Self {
+            num_original_columns: num_columns,
+            max_degree,
+            constraints: Vec::with_capacity(constraints.len()),
+            aux_columns: Vec::new(),
+        };
+        for constraint in constraints {
+            let expr = lowered.lower(constraint.expr);
+            lowered.constraints.push(SymbolicConstraint {
+                filter: constraint.filter,
+                expr,
+            });
+        }
+        for (i, definition) in lowered.aux_columns.iter().enumerate() {
+            let aux = Expr::local(num_columns + i);
+            lowered
+                .constraints
+                .push(SymbolicConstraint::every_row(aux - definition.clone()));
+        }
+        lowered
+    }
+
+    /// Rewrites `expr` to have degree at most `max_degree`.
+    fn lower(&mut self, expr: Expr<F>) -> Expr<F> {
+        match expr {
+            Expr::Add(a, b) => self.lower(*a) + self.lower(*b),
+            Expr::Sub(a, b) => self.lower(*a) - self.lower(*b),
+            Expr::Mul(a, b) => {
+                let mut a = self.lower(*a);
+                let mut b = self.lower(*b);
+                while a.degree() + b.degree() > self.max_degree {
+                    if a.degree() >= b.degree() {
+                        a = self.aux_column(a);
+                    } else {
+                        b = self.aux_column(b);
+                    }
+                }
+                a * b
+            }
+            expr => expr,
+        }
+    }
+
+    /// An auxiliary column equal to `definition`, reusing an existing one if possible.
+    fn aux_column(&mut self, definition: Expr<F>) -> Expr<F> {
+        let i = match self.aux_columns.iter().position(|d| *d == definition) {
+            Some(i) => i,
+            None => {
+                self.aux_columns.push(definition);
+                self.aux_columns.len() - 1
+            }
+        };
+        Expr::local(self.num_original_columns + i)
+    }
+
+    /// The number of columns, including the auxiliary ones.
+    pub fn num_columns(&self) -> usize {
+        self.num_original_columns + self.aux_columns.len()
+    }
+
+    pub fn num_aux_columns(&self) -> usize {
+        self.aux_columns.len()
+    }
+
+    /// The lowered constraints, including those defining the auxiliary columns.
+    pub fn constraints(&self) -> &[SymbolicConstraint<F>] {
+        &self.constraints
+    }
+
+    /// The degree of the lowered constraints, to be returned by `Stark::constraint_degree`.
+    pub fn constraint_degree(&self) -> usize {
+        self.constraints
+            .iter()
+            .map(|c| c.expr.degree())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Appends the auxiliary columns to a trace of the original columns.
+    pub fn generate_aux_columns(&self, trace: &mut Vec<PolynomialValues<F>>) {
+        assert_eq!(trace.len(), self.num_original_columns);
+        let n = trace[0].len();
+        let mut rows = (0..n)
+            .map(|r| trace.iter().map(|col| col.values[r]).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        // Definitions only read auxiliary columns at the current row, and only earlier ones, so
+        // the rows can be extended in order.
+        for r in 0..n {
+            for definition in &self.aux_columns {
+                let value = definition.eval(&rows[r], &rows[(r + 1) % n]);
+                rows[r].push(value);
+            }
+        }
+        for i in 0..self.aux_columns.len() {
+            let column = rows.iter().map(|row| row[self.num_original_columns + i]);
+            trace.push(PolynomialValues::new(column.collect()));
+        }
+    }
+
+    pub fn eval_packed_generic<FE, P, const D2: usize>(
+        &self,
+        local: &[P],
+        next: &[P],
+        yield_constr: &mut ConstraintConsumer<P>,
+    ) where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>,
+    {
+        for constraint in &self.constraints {
+            let value = constraint.expr.eval(local, next);
+            match constraint.filter {
+                ConstraintFilter::EveryRow => yield_constr.constraint(value),
+                ConstraintFilter::Transition => yield_constr.constraint_transition(value),
+                ConstraintFilter::FirstRow => yield_constr.constraint_first_row(value),
+                ConstraintFilter::LastRow => yield_constr.constraint_last_row(value),
+            }
+        }
+    }
+
+    pub fn eval_ext_circuit<const D: usize>(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        local: &[ExtensionTarget<D>],
+        next: &[ExtensionTarget<D>],
+        yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+    ) where
+        F: RichField + Extendable<D>,
+    {
+        for constraint in &self.constraints {
+            let value = constraint.expr.eval_circuit(builder, local, next);
+            match constraint.filter {
+                ConstraintFilter::EveryRow => yield_constr.constraint(builder, value),
+                ConstraintFilter::Transition => yield_constr.constraint_transition(builder, value),
+                ConstraintFilter::FirstRow => yield_constr.constraint_first_row(builder, value),
+                ConstraintFilter::LastRow => yield_constr.constraint_last_row(builder, value),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use anyhow::Result;
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::plonk::config::PoseidonGoldilocksConfig;
+    use plonky2::util::timing::TimingTree;
+
+    use super::*;
+    use crate::config::StarkConfig;
+    use crate::prover::prove;
+    use crate::stark::Stark;
+    use crate::stark_testing::{test_stark_circuit_constraints, test_stark_low_degree};
+    use crate::vars::{StarkEvaluationTargets, StarkEvaluationVars};
+    use crate::verifier::verify_stark_proof;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = GoldilocksField;
+
+    const A: usize = 0;
+    const B: usize = 1;
+
+    /// Sums fifth powers: `a' = a + 1` and `b' = b + a^5`, starting from `a = b = 0`.
+    #[derive(Clone)]
+    struct PowerSumStark<F: RichField + Extendable<D>, const D: usize> {
+        num_rows: usize,
+        constraints: LoweredConstraints<F>,
+    }
+
+    impl<F: RichField + Extendable<D>, const D: usize> PowerSumStark<F, D> {
+        fn new(num_rows: usize, config: &StarkConfig) -> Self {
+            let a = || Expr::local(A);
+            let fifth_power = a() * a() * a() * a() * a();
+            let constraints = vec![
+                SymbolicConstraint::first_row(a()),
+                SymbolicConstraint::first_row(Expr::local(B)),
+                SymbolicConstraint::transition(Expr::next(A) - a() - Expr::constant(F::ONE)),
+                SymbolicConstraint::transition(Expr::next(B) - Expr::local(B) - fifth_power),
+            ];
+            let constraints =
+                LoweredConstraints::new(constraints, 2, config.max_constraint_degree());
+            assert_eq!(constraints.num_columns(), Self::COLUMNS);
+            Self {
+                num_rows,
+                constraints,
+            }
+        }
+
+        fn generate_trace(&self) -> Vec<PolynomialValues<F>> {
+            let a = (0..self.num_rows)
+                .map(F::from_canonical_usize)
+                .collect::<Vec<_>>();
+            let mut b = vec![F::ZERO];
+            for r in 0..self.num_rows - 1 {
+                b.push(b[r] + a[r].exp_u64(5));
+            }
+            let mut trace = vec![PolynomialValues::new(a), PolynomialValues::new(b)];
+            self.constraints.generate_aux_columns(&mut trace);
+            trace
+        }
+    }
+
+    impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for PowerSumStark<F, D> {
+        const COLUMNS: usize = 3;
+        const PUBLIC_INPUTS: usize = 0;
+
+        fn eval_packed_generic<FE, P, const D2: usize>(
+            &self,
+            vars: StarkEvaluationVars<FE, P, { Self::COLUMNS }, { Self::PUBLIC_INPUTS }>,
+            yield_constr: &mut ConstraintConsumer<P>,
+        ) where
+            FE: FieldExtension<D2, BaseField = F>,
+            P: PackedField<Scalar = FE>,
+        {
+            self.constraints
+                .eval_packed_generic(vars.local_values, vars.next_values, yield_constr);
+        }
+
+        fn eval_ext_circuit(
+            &self,
+            builder: &mut CircuitBuilder<F, D>,
+            vars: StarkEvaluationTargets<D, { Self::COLUMNS }, { Self::PUBLIC_INPUTS }>,
+            yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+        ) {
+            self.constraints.eval_ext_circuit(
+                builder,
+                vars.local_values,
+                vars.next_values,
+                yield_constr,
+            );
+        }
+
+        fn constraint_degree(&self) -> usize {
+            self.constraints.constraint_degree()
+        }
+    }
+
+    #[test]
+    fn test_lowering() {
+        let x = || Expr::<F>::local(0);
+        let y = || Expr::<F>::next(1);
+        let x4 = x() * x() * x() * x();
+        let constraints = vec![
+            SymbolicConstraint::transition(x4.clone() * y() - x()),
+            SymbolicConstraint::every_row(x4.clone() + x() * y()),
+        ];
+        let lowered = LoweredConstraints::new(constraints, 2, 3);
+        // `x^3` gets a column, shared by both constraints.
+        assert_eq!(lowered.num_aux_columns(), 1);
+        assert_eq!(lowered.constraints().len(), 3);
+        assert_eq!(lowered.constraint_degree(), 3);
+
+        let mut trace = vec![
+            PolynomialValues::new(vec![F::TWO, F::NEG_ONE, F::ONE, F::ZERO]),
+            PolynomialValues::new(vec![F::ONE, F::TWO, F::ZERO, F::ONE]),
+        ];
+        lowered.generate_aux_columns(&mut trace);
+        assert_eq!(trace.len(), 3);
+        assert_eq!(
+            trace[2].values,
+            vec![F::from_canonical_u64(8), F::NEG_ONE, F::ONE, F::ZERO]
+        );
+
+        // Degree 2 needs another column for `x^2`.
+        let lowered = LoweredConstraints::new(vec![SymbolicConstraint::every_row(x4)], 2, 2);
+        assert_eq!(lowered.num_aux_columns(), 2);
+        assert_eq!(lowered.constraint_degree(), 2);
+    }
+
+    #[test]
+    fn test_power_sum_stark() -> Result<()> {
+        // A rate of 1/2 only fits constraints of degree 3, below the 5 needed for the original
+        // transition of `b`.
+        let config = StarkConfig::standard_fast_config();
+        let stark = PowerSumStark::<F, D>::new(1 << 5, &config);
+        assert_eq!(stark.constraint_degree(), 3);
+        let trace = stark.generate_trace();
+        let proof = prove::<F, C, _, D>(
+            stark.clone(),
+            &config,
+            trace,
+            [],
+            &mut TimingTree::default(),
+        )?;
+        verify_stark_proof(stark.clone(), proof, &config)?;
+
+        test_stark_low_degree(stark.clone())?;
+        test_stark_circuit_constraints::<F, C, _, D>(stark)
+    }
+}
@@ -1,26 +1,47 @@
 use alloc::vec;
 use alloc::vec::Vec;
+use core::borrow::Borrow;
 use core::marker::PhantomData;
 
 use plonky2::field::extension::{Extendable, FieldExtension};
 use plonky2::field::packed::PackedField;
 use plonky2::field::polynomial::PolynomialValues;
+use plonky2::field::types::Field;
 use plonky2::hash::hash_types::RichField;
 use plonky2::plonk::circuit_builder::CircuitBuilder;
 
 use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
 use crate::permutation::PermutationPair;
+use crate::public_cells::PublicCell;
 use crate::stark::Stark;
 use crate::util::trace_rows_to_poly_values;
 use crate::vars::{StarkEvaluationTargets, StarkEvaluationVars};
 
+crate::column_layout! {
+    /// The columns of `FibonacciStark`.
+    pub(crate) struct FibonacciColumns<T> {
+        pub(crate) x0: T,
+        pub(crate) x1: T,
+        /// Only used to test the permutation argument.
+        pub(crate) i: T,
+        /// Only used to test the permutation argument.
+        pub(crate) j: T,
+    }
+    num_columns = NUM_FIBONACCI_COLUMNS;
+    col_map = FIBONACCI_COL_MAP;
+}
+
 /// Toy STARK system used for testing.
 /// Computes a Fibonacci sequence with state `[x0, x1, i, j]` using the state transition
 /// `x0' <- x1, x1' <- x0 + x1, i' <- i+1, j' <- j+1`.
 /// Note: The `i, j` columns are only used to test the permutation argument.
 #[derive(Copy, Clone)]
-struct FibonacciStark<F: RichField + Extendable<D>, const D: usize> {
+pub(crate) struct FibonacciStark<F: RichField + Extendable<D>, const D: usize> {
     num_rows: usize,
+    /// Number of rows after the next one at which the trace is additionally opened. If there are
+    /// any, the constraints also check that `x0'' = x0 + x1`. Only used to test
+    /// `Stark::additional_opening_rows`.
+    pub(crate) extra_window_rows: usize,
     _phantom: PhantomData<F>,
 }
 
@@ -33,32 +54,40 @@ impl<F: RichField + Extendable<D>, const D: usize> FibonacciStark<F, D> {
     // `num_rows`-th Fibonacci number.
     const PI_INDEX_RES: usize = 2;
 
-    fn new(num_rows: usize) -> Self {
+    pub(crate) fn new(num_rows: usize) -> Self {
         Self {
             num_rows,
+            extra_window_rows: 0,
             _phantom: PhantomData,
         }
     }
 
     /// Generate the trace using `x0, x1, 0, 1` as initial state values.
-    fn generate_trace(&self, x0: F, x1: F) -> Vec<PolynomialValues<F>> {
+    pub(crate) fn generate_trace(&self, x0: F, x1: F) -> Vec<PolynomialValues<F>> {
+        let first_row = FibonacciColumns {
+            x0,
+            x1,
+            i: F::ZERO,
+            j: F::ONE,
+        };
         let mut trace_rows = (0..self.num_rows)
-            .scan([x0, x1, F::ZERO, F::ONE], |acc, _| {
+            .scan(first_row, |acc, _| {
                 let tmp = *acc;
-                acc[0] = tmp[1];
-                acc[1] = tmp[0] + tmp[1];
-                acc[2] = tmp[2] + F::ONE;
-                acc[3] = tmp[3] + F::ONE;
-                Some(tmp)
+                acc.x0 = tmp.x1;
+                acc.x1 = tmp.x0 + tmp.x1;
+                acc.i = tmp.i + F::ONE;
+                acc.j = tmp.j + F::ONE;
+                Some(<[F; NUM_FIBONACCI_COLUMNS]>::from(tmp))
             })
             .collect::<Vec<_>>();
-        trace_rows[self.num_rows - 1][3] = F::ZERO; // So that column 2 and 3 are permutation of one another.
+        // So that columns `i` and `j` are permutations of one another.
+        trace_rows[self.num_rows - 1][FIBONACCI_COL_MAP.j] = F::ZERO;
         trace_rows_to_poly_values(trace_rows)
     }
 }
 
 impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for FibonacciStark<F, D> {
-    const COLUMNS: usize = 4;
+    const COLUMNS: usize = NUM_FIBONACCI_COLUMNS;
     const PUBLIC_INPUTS: usize = 3;
 
     fn eval_packed_generic<FE, P, const D2: usize>(
@@ -69,20 +98,20 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for FibonacciStar
         FE: FieldExtension<D2, BaseField = F>,
         P: PackedField<Scalar = FE>,
     {
-        // Check public inputs.
-        yield_constr
-            .constraint_first_row(vars.local_values[0] - vars.public_inputs[Self::PI_INDEX_X0]);
-        yield_constr
-            .constraint_first_row(vars.local_values[1] - vars.public_inputs[Self::PI_INDEX_X1]);
-        yield_constr
-            .constraint_last_row(vars.local_values[1] - vars.public_inputs[Self::PI_INDEX_RES]);
-
+        let local: &FibonacciColumns<P> = vars.local_values.borrow();
+        let next: &FibonacciColumns<P> = vars.next_values.borrow();
         // x0' <- x1
-        yield_constr.constraint_transition(vars.next_values[0] - vars.local_values[1]);
+        yield_constr.constraint_transition(next.x0 - local.x1);
         // x1' <- x0 + x1
-        yield_constr.constraint_transition(
-            vars.next_values[1] - vars.local_values[0] - vars.local_values[1],
-        );
+        yield_constr.constraint_transition(next.x1 - local.x0 - local.x1);
+
+        // x0'' <- x0 + x1, except on the second to last row, where `i = num_rows - 2`.
+        if let Some(after_next) = vars.additional_values.first() {
+            let after_next: &FibonacciColumns<P> = after_next.borrow();
+            let not_second_to_last = local.i - FE::from_canonical_usize(self.num_rows - 2);
+            yield_constr
+                .constraint_transition(not_second_to_last * (after_next.x0 - local.x0 - local.x1));
+        }
     }
 
     fn eval_ext_circuit(
@@ -91,33 +120,56 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for FibonacciStar
         vars: StarkEvaluationTargets<D, { Self::COLUMNS }, { Self::PUBLIC_INPUTS }>,
         yield_constr: &mut RecursiveConstraintConsumer<F, D>,
     ) {
-        // Check public inputs.
-        let pis_constraints = [
-            builder.sub_extension(vars.local_values[0], vars.public_inputs[Self::PI_INDEX_X0]),
-            builder.sub_extension(vars.local_values[1], vars.public_inputs[Self::PI_INDEX_X1]),
-            builder.sub_extension(vars.local_values[1], vars.public_inputs[Self::PI_INDEX_RES]),
-        ];
-        yield_constr.constraint_first_row(builder, pis_constraints[0]);
-        yield_constr.constraint_first_row(builder, pis_constraints[1]);
-        yield_constr.constraint_last_row(builder, pis_constraints[2]);
-
+        let local: &FibonacciColumns<_> = vars.local_values.borrow();
+        let next: &FibonacciColumns<_> = vars.next_values.borrow();
         // x0' <- x1
-        let first_col_constraint = builder.sub_extension(vars.next_values[0], vars.local_values[1]);
+        let first_col_constraint = builder.sub_extension(next.x0, local.x1);
         yield_constr.constraint_transition(builder, first_col_constraint);
         // x1' <- x0 + x1
         let second_col_constraint = {
-            let tmp = builder.sub_extension(vars.next_values[1], vars.local_values[0]);
-            builder.sub_extension(tmp, vars.local_values[1])
+            let tmp = builder.sub_extension(next.x1, local.x0);
+            builder.sub_extension(tmp, local.x1)
         };
         yield_constr.constraint_transition(builder, second_col_constraint);
+
+        // x0'' <- x0 + x1, except on the second to last row, where `i = num_rows - 2`.
+        if let Some(after_next) = vars.additional_values.first() {
+            let after_next: &FibonacciColumns<_> = after_next.borrow();
+            let second_to_last =
+                builder.constant_extension(F::Extension::from_canonical_usize(self.num_rows - 2));
+            let not_second_to_last = builder.sub_extension(local.i, second_to_last);
+            let sum = builder.add_extension(local.x0, local.x1);
+            let diff = builder.sub_extension(after_next.x0, sum);
+            let constraint = builder.mul_extension(not_second_to_last, diff);
+            yield_constr.constraint_transition(builder, constraint);
+        }
     }
 
     fn constraint_degree(&self) -> usize {
-        2
+        if self.extra_window_rows > 0 {
+            3
+        } else {
+            2
+        }
+    }
+
+    fn public_cells(&self) -> Vec<PublicCell> {
+        vec![
+            PublicCell::first_row(FIBONACCI_COL_MAP.x0, Self::PI_INDEX_X0),
+            PublicCell::first_row(FIBONACCI_COL_MAP.x1, Self::PI_INDEX_X1),
+            PublicCell::last_row(FIBONACCI_COL_MAP.x1, Self::PI_INDEX_RES),
+        ]
+    }
+
+    fn additional_opening_rows(&self) -> Vec<usize> {
+        (2..2 + self.extra_window_rows).collect()
     }
 
     fn permutation_pairs(&self) -> Vec<PermutationPair> {
-        vec![PermutationPair::singletons(2, 3)]
+        vec![PermutationPair::singletons(
+            FIBONACCI_COL_MAP.i,
+            FIBONACCI_COL_MAP.j,
+        )]
     }
 }
 
@@ -125,25 +177,34 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for FibonacciStar
 mod tests {
     use anyhow::Result;
     use plonky2::field::extension::Extendable;
-    use plonky2::field::types::Field;
+    use plonky2::field::types::{Field, Sample};
+    use plonky2::fri::oracle::{PolynomialBatch, SALT_SIZE};
     use plonky2::hash::hash_types::RichField;
     use plonky2::iop::witness::PartialWitness;
     use plonky2::plonk::circuit_builder::CircuitBuilder;
     use plonky2::plonk::circuit_data::CircuitConfig;
-    use plonky2::plonk::config::{AlgebraicHasher, GenericConfig, PoseidonGoldilocksConfig};
+    use plonky2::plonk::config::{
+        AlgebraicHasher, GenericConfig, PoseidonGoldilocksConfig, PoseidonGoldilocksQuinticConfig,
+    };
     use plonky2::util::timing::TimingTree;
 
+    use crate::checkpoint::{ProverCheckpoint, ProverPhase};
     use crate::config::StarkConfig;
+    use crate::constraint_consumer::ConstraintConsumer;
     use crate::fibonacci_stark::FibonacciStark;
     use crate::proof::StarkProofWithPublicInputs;
-    use crate::prover::prove;
+    use crate::prover::{
+        compute_quotient_polys, compute_quotient_values_range, merge_quotient_chunks, prove,
+        prove_with_checkpoints, quotient_domain_size,
+    };
     use crate::recursive_verifier::{
         add_virtual_stark_proof_with_pis, set_stark_proof_with_pis_target,
         verify_stark_proof_circuit,
     };
     use crate::stark::Stark;
     use crate::stark_testing::{test_stark_circuit_constraints, test_stark_low_degree};
-    use crate::verifier::verify_stark_proof;
+    use crate::vars::StarkEvaluationVars;
+    use crate::verifier::{verify_stark_proof, verify_stark_proofs};
 
     fn fibonacci<F: Field>(n: usize, x0: F, x1: F) -> F {
         (0..n).fold((x0, x1), |x, _| (x.1, x.0 + x.1)).1
@@ -172,6 +233,513 @@ mod tests {
         verify_stark_proof(stark, proof, &config)
     }
 
+    #[test]
+    fn test_fibonacci_stark_batch() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = FibonacciStark<F, D>;
+
+        let config = StarkConfig::standard_fast_config();
+        let num_rows = 1 << 5;
+        let stark = S::new(num_rows);
+        let mut proofs = (0..4)
+            .map(|_| {
+                let (x0, x1) = (F::rand(), F::rand());
+                let public_inputs = [x0, x1, fibonacci(num_rows - 1, x0, x1)];
+                let trace = stark.generate_trace(x0, x1);
+                prove::<F, C, S, D>(
+                    stark,
+                    &config,
+                    trace,
+                    public_inputs,
+                    &mut TimingTree::default(),
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+        verify_stark_proofs(&stark, proofs.clone(), &config)?;
+
+        proofs[1].public_inputs[0] += F::ONE;
+        assert!(verify_stark_proofs(&stark, proofs, &config).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_fibonacci_stark_public_cells() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = FibonacciStark<F, D>;
+
+        let config = StarkConfig::standard_fast_config();
+        let num_rows = 1 << 5;
+        let mut public_inputs = [F::ZERO, F::ONE, fibonacci(num_rows - 1, F::ZERO, F::ONE)];
+        let stark = S::new(num_rows);
+        let trace = stark.generate_trace(public_inputs[0], public_inputs[1]);
+        for cell in stark.public_cells() {
+            assert_eq!(cell.value(&trace), public_inputs[cell.public_input]);
+        }
+
+        // The prover rejects a claimed result which isn't in the last row.
+        public_inputs[S::PI_INDEX_RES] += F::ONE;
+        assert!(prove::<F, C, S, D>(
+            stark,
+            &config,
+            trace,
+            public_inputs,
+            &mut TimingTree::default(),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_config_validation() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = FibonacciStark<F, D>;
+
+        let num_rows = 1 << 5;
+        let stark = S::new(num_rows);
+        StarkConfig::standard_fast_config().validate(&stark)?;
+
+        // The quadratic extension is too small for 160 bits of security.
+        let config = StarkConfig::standard_fast_quintic_config();
+        assert!(config.validate(&stark).is_err());
+        let public_inputs = [F::ZERO, F::ONE, fibonacci(num_rows - 1, F::ZERO, F::ONE)];
+        let trace = stark.generate_trace(public_inputs[0], public_inputs[1]);
+        let result = prove::<F, C, S, D>(
+            stark,
+            &config,
+            trace,
+            public_inputs,
+            &mut TimingTree::default(),
+        );
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_fibonacci_stark_quintic() -> Result<()> {
+        const D: usize = 5;
+        type C = PoseidonGoldilocksQuinticConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = FibonacciStark<F, D>;
+
+        let config = StarkConfig::standard_fast_quintic_config();
+        let num_rows = 1 << 5;
+        let public_inputs = [F::ZERO, F::ONE, fibonacci(num_rows - 1, F::ZERO, F::ONE)];
+        let stark = S::new(num_rows);
+        let trace = stark.generate_trace(public_inputs[0], public_inputs[1]);
+        let proof = prove::<F, C, S, D>(
+            stark,
+            &config,
+            trace,
+            public_inputs,
+            &mut TimingTree::default(),
+        )?;
+
+        verify_stark_proof(stark, proof, &config)
+    }
+
+    #[test]
+    fn test_fibonacci_stark_additional_openings() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = FibonacciStark<F, D>;
+
+        let config = StarkConfig::standard_fast_config();
+        let num_rows = 1 << 5;
+        let public_inputs = [F::ZERO, F::ONE, fibonacci(num_rows - 1, F::ZERO, F::ONE)];
+        let stark = S {
+            extra_window_rows: 2,
+            ..S::new(num_rows)
+        };
+        let trace = stark.generate_trace(public_inputs[0], public_inputs[1]);
+        let proof = prove::<F, C, S, D>(
+            stark,
+            &config,
+            trace,
+            public_inputs,
+            &mut TimingTree::default(),
+        )?;
+        assert_eq!(proof.proof.openings.additional_values.len(), 2);
+        verify_stark_proof(stark, proof.clone(), &config)?;
+
+        let mut bad_proof = proof.clone();
+        bad_proof.proof.openings.additional_values[1][0] += <F as Extendable<D>>::Extension::ONE;
+        assert!(verify_stark_proof(stark, bad_proof, &config).is_err());
+
+        // The constraints read the row after next, whose `x0` must be `x0 + x1`. Rows are
+        // `[x0, x1, i, j]`.
+        let eval = |after_next_x0: u32| {
+            let vars = StarkEvaluationVars {
+                local_values: &[1, 2, 0, 1].map(F::from_canonical_u32),
+                next_values: &[2, 3, 1, 2].map(F::from_canonical_u32),
+                additional_values: &[
+                    [after_next_x0, 5, 2, 3].map(F::from_canonical_u32),
+                    [5, 8, 3, 4].map(F::from_canonical_u32),
+                ],
+                public_inputs: &public_inputs,
+            };
+            let mut accs = [F::ZERO];
+            let mut consumer =
+                ConstraintConsumer::new(&[F::ONE], &mut accs, F::ONE, F::ZERO, F::ZERO);
+            stark.eval_packed_base(vars, &mut consumer);
+            consumer.accumulators()[0]
+        };
+        assert_eq!(eval(3), F::ZERO);
+        assert_ne!(eval(4), F::ZERO);
+
+        test_stark_low_degree(stark)?;
+        test_stark_circuit_constraints::<F, C, S, D>(stark)?;
+        recursive_proof::<F, C, S, C, D>(stark, proof, &config, false)
+    }
+
+    #[test]
+    fn test_fibonacci_stark_resume_from_checkpoint() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = FibonacciStark<F, D>;
+
+        let config = StarkConfig::standard_fast_config();
+        let num_rows = 1 << 5;
+        let public_inputs = [F::ZERO, F::ONE, fibonacci(num_rows - 1, F::ZERO, F::ONE)];
+        let stark = S::new(num_rows);
+        let trace = stark.generate_trace(public_inputs[0], public_inputs[1]);
+
+        let mut checkpoints = Vec::new();
+        let proof = prove_with_checkpoints::<F, C, S, D>(
+            stark,
+            &config,
+            trace.clone(),
+            public_inputs,
+            None,
+            &mut |checkpoint| {
+                checkpoints.push(checkpoint.to_bytes());
+                Ok(())
+            },
+            &mut TimingTree::default(),
+        )?;
+        assert_eq!(checkpoints.len(), 2);
+
+        for (bytes, phase) in checkpoints
+            .iter()
+            .zip([ProverPhase::TraceCommitted, ProverPhase::QuotientCommitted])
+        {
+            let checkpoint = ProverCheckpoint::<F, C, D>::from_bytes(bytes)?;
+            assert_eq!(checkpoint.phase(), phase);
+            let resumed = prove_with_checkpoints::<F, C, S, D>(
+                stark,
+                &config,
+                trace.clone(),
+                public_inputs,
+                Some(checkpoint),
+                &mut |_| Ok(()),
+                &mut TimingTree::default(),
+            )?;
+            assert_eq!(resumed.to_json()?, proof.to_json()?);
+        }
+
+        // A checkpoint for a different trace length is rejected.
+        let longer_stark = S::new(num_rows * 2);
+        let longer_trace = longer_stark.generate_trace(public_inputs[0], public_inputs[1]);
+        let checkpoint = ProverCheckpoint::<F, C, D>::from_bytes(&checkpoints[0])?;
+        assert!(prove_with_checkpoints::<F, C, S, D>(
+            longer_stark,
+            &config,
+            longer_trace,
+            public_inputs,
+            Some(checkpoint),
+            &mut |_| Ok(()),
+            &mut TimingTree::default(),
+        )
+        .is_err());
+
+        verify_stark_proof(stark, proof, &config)
+    }
+
+    #[test]
+    fn test_fibonacci_stark_split_quotient() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = FibonacciStark<F, D>;
+
+        let config = StarkConfig::standard_fast_config();
+        let degree_bits = 5;
+        let num_rows = 1 << degree_bits;
+        let public_inputs = [F::ZERO, F::ONE, fibonacci(num_rows - 1, F::ZERO, F::ONE)];
+        let stark = S::new(num_rows);
+        let trace = stark.generate_trace(public_inputs[0], public_inputs[1]);
+        let trace_commitment = PolynomialBatch::<F, C, D>::from_values(
+            trace,
+            config.fri_config.rate_bits,
+            false,
+            config.fri_config.cap_height,
+            &mut TimingTree::default(),
+            None,
+        );
+        let alphas = F::rand_vec(config.num_challenges);
+        let size = quotient_domain_size(&stark, degree_bits);
+
+        let chunk = |range| {
+            compute_quotient_values_range::<F, F, C, S, D>(
+                &stark,
+                &trace_commitment,
+                None,
+                public_inputs,
+                &alphas,
+                degree_bits,
+                &config,
+                range,
+            )
+        };
+        let whole = merge_quotient_chunks(vec![chunk(0..size)], size, alphas.len())?;
+        // Chunks may be merged in any order.
+        let split = merge_quotient_chunks(
+            vec![chunk(size / 4..size), chunk(0..size / 4)],
+            size,
+            alphas.len(),
+        )?;
+        assert_eq!(whole, split);
+
+        // The prover, which evaluates one coset of the trace domain at a time, agrees.
+        let streamed = compute_quotient_polys::<F, F, C, S, D>(
+            &stark,
+            &trace_commitment,
+            None,
+            public_inputs,
+            alphas.clone(),
+            degree_bits,
+            &config,
+            &mut TimingTree::default(),
+        );
+        assert_eq!(streamed, whole);
+
+        // Incomplete or overlapping chunks are rejected.
+        assert!(merge_quotient_chunks(vec![chunk(0..size / 2)], size, alphas.len()).is_err());
+        assert!(merge_quotient_chunks(
+            vec![chunk(0..size / 2), chunk(size / 4..size)],
+            size,
+            alphas.len()
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_fibonacci_stark_json() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = FibonacciStark<F, D>;
+
+        let config = StarkConfig::standard_fast_config();
+        let config = StarkConfig::from_json(&config.to_json()?)?;
+        let num_rows = 1 << 5;
+        let public_inputs = [F::ZERO, F::ONE, fibonacci(num_rows - 1, F::ZERO, F::ONE)];
+        let stark = S::new(num_rows);
+        let trace = stark.generate_trace(public_inputs[0], public_inputs[1]);
+        let proof = prove::<F, C, S, D>(
+            stark,
+            &config,
+            trace,
+            public_inputs,
+            &mut TimingTree::default(),
+        )?;
+
+        let json = proof.to_json()?;
+        let decoded = StarkProofWithPublicInputs::<F, C, D>::from_json(&json)?;
+        assert_eq!(decoded.to_json()?, json);
+
+        verify_stark_proof(stark, decoded, &config)
+    }
+
+    #[test]
+    fn test_fibonacci_stark_bytes() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = FibonacciStark<F, D>;
+
+        let config = StarkConfig::standard_fast_config();
+        let num_rows = 1 << 5;
+        let public_inputs = [F::ZERO, F::ONE, fibonacci(num_rows - 1, F::ZERO, F::ONE)];
+        let stark = S::new(num_rows);
+        let trace = stark.generate_trace(public_inputs[0], public_inputs[1]);
+        let proof = prove::<F, C, S, D>(
+            stark,
+            &config,
+            trace,
+            public_inputs,
+            &mut TimingTree::default(),
+        )?;
+
+        let bytes = proof.to_bytes(&config);
+        let decode_and_verify = |bytes: &[u8]| -> Result<()> {
+            let proof = StarkProofWithPublicInputs::<F, C, D>::from_bytes(bytes, &stark, &config)?;
+            verify_stark_proof(stark, proof, &config)
+        };
+        let decoded = StarkProofWithPublicInputs::<F, C, D>::from_bytes(&bytes, &stark, &config)?;
+        assert_eq!(decoded.to_bytes(&config), bytes);
+        decode_and_verify(&bytes)?;
+
+        // None of these may panic, and all must be rejected.
+        assert!(decode_and_verify(&bytes[..bytes.len() - 1]).is_err());
+        let mut extended = bytes.clone();
+        extended.push(0);
+        assert!(decode_and_verify(&extended).is_err());
+        let mut non_canonical = bytes.clone();
+        let last = non_canonical.len() - 8;
+        non_canonical[last..].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(decode_and_verify(&non_canonical).is_err());
+        for i in (0..bytes.len()).step_by(bytes.len() / 64) {
+            let mut flipped = bytes.clone();
+            flipped[i] ^= 1;
+            assert!(decode_and_verify(&flipped).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_fibonacci_stark_salted() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = FibonacciStark<F, D>;
+
+        let config = StarkConfig::standard_fast_salted_config();
+        let num_rows = 1 << 5;
+        let public_inputs = [F::ZERO, F::ONE, fibonacci(num_rows - 1, F::ZERO, F::ONE)];
+        let stark = S::new(num_rows);
+        let trace = stark.generate_trace(public_inputs[0], public_inputs[1]);
+        let proof = prove::<F, C, S, D>(
+            stark,
+            &config,
+            trace,
+            public_inputs,
+            &mut TimingTree::default(),
+        )?;
+
+        // Every opened leaf is salted.
+        for round in &proof.proof.opening_proof.query_round_proofs {
+            let (trace_leaf, _) = &round.initial_trees_proof.evals_proofs[0];
+            assert_eq!(trace_leaf.len(), S::COLUMNS + SALT_SIZE);
+        }
+        let bytes = proof.to_bytes(&config);
+        let decoded = StarkProofWithPublicInputs::<F, C, D>::from_bytes(&bytes, &stark, &config)?;
+        assert_eq!(decoded.to_bytes(&config), bytes);
+
+        // The transcript binds whether commitments are salted.
+        let unsalted_config = StarkConfig::standard_fast_config();
+        assert!(verify_stark_proof(stark, proof.clone(), &unsalted_config).is_err());
+        verify_stark_proof(stark, proof.clone(), &config)?;
+
+        recursive_proof::<F, C, S, C, D>(stark, proof, &config, false)
+    }
+
+    #[test]
+    fn test_fibonacci_stark_trace_extra_rate_bits() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = FibonacciStark<F, D>;
+
+        let config = StarkConfig {
+            trace_extra_rate_bits: 2,
+            ..StarkConfig::standard_fast_config()
+        };
+        let num_rows = 1 << 5;
+        let public_inputs = [F::ZERO, F::ONE, fibonacci(num_rows - 1, F::ZERO, F::ONE)];
+        let stark = S::new(num_rows);
+        let trace = stark.generate_trace(public_inputs[0], public_inputs[1]);
+        let proof = prove::<F, C, S, D>(
+            stark,
+            &config,
+            trace,
+            public_inputs,
+            &mut TimingTree::default(),
+        )?;
+
+        // The trace's Merkle paths are longer than the quotient's.
+        let lde_bits = 5 + config.fri_config.rate_bits;
+        let cap_height = config.fri_config.cap_height;
+        for round in &proof.proof.opening_proof.query_round_proofs {
+            let evals_proofs = &round.initial_trees_proof.evals_proofs;
+            assert_eq!(evals_proofs[0].1.len(), lde_bits + 2 - cap_height);
+            let quotient_proof = &evals_proofs.last().unwrap().1;
+            assert_eq!(quotient_proof.len(), lde_bits - cap_height);
+        }
+        assert_eq!(proof.proof.recover_degree_bits(&config), 5);
+        let bytes = proof.to_bytes(&config);
+        let decoded = StarkProofWithPublicInputs::<F, C, D>::from_bytes(&bytes, &stark, &config)?;
+        assert_eq!(decoded.to_bytes(&config), bytes);
+
+        assert!(
+            verify_stark_proof(stark, proof.clone(), &StarkConfig::standard_fast_config()).is_err()
+        );
+        verify_stark_proof(stark, proof.clone(), &config)?;
+
+        recursive_proof::<F, C, S, C, D>(stark, proof, &config, false)
+    }
+
+    #[test]
+    fn test_fibonacci_stark_leaf_groups() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = FibonacciStark<F, D>;
+
+        let num_rows = 1 << 5;
+        let public_inputs = [F::ZERO, F::ONE, fibonacci(num_rows - 1, F::ZERO, F::ONE)];
+        let stark = S::new(num_rows);
+        for trace_extra_rate_bits in [0, 1] {
+            let config = StarkConfig {
+                leaf_group_bits: 2,
+                trace_extra_rate_bits,
+                ..StarkConfig::standard_fast_salted_config()
+            };
+            let trace = stark.generate_trace(public_inputs[0], public_inputs[1]);
+            let proof = prove::<F, C, S, D>(
+                stark,
+                &config,
+                trace,
+                public_inputs,
+                &mut TimingTree::default(),
+            )?;
+
+            // Each opened leaf holds 4 rows, and Merkle paths are 2 hashes shorter.
+            let lde_bits = 5 + config.fri_config.rate_bits;
+            let cap_height = config.fri_config.cap_height;
+            for round in &proof.proof.opening_proof.query_round_proofs {
+                let (trace_evals, trace_proof) = &round.initial_trees_proof.evals_proofs[0];
+                assert_eq!(trace_evals.len(), 4 * (S::COLUMNS + SALT_SIZE));
+                assert_eq!(
+                    trace_proof.len(),
+                    lde_bits + trace_extra_rate_bits - 2 - cap_height
+                );
+            }
+            assert_eq!(proof.proof.recover_degree_bits(&config), 5);
+            let bytes = proof.to_bytes(&config);
+            let decoded =
+                StarkProofWithPublicInputs::<F, C, D>::from_bytes(&bytes, &stark, &config)?;
+            assert_eq!(decoded.to_bytes(&config), bytes);
+
+            let ungrouped_config = StarkConfig {
+                leaf_group_bits: 0,
+                ..config.clone()
+            };
+            assert!(verify_stark_proof(stark, proof.clone(), &ungrouped_config).is_err());
+            verify_stark_proof(stark, proof.clone(), &config)?;
+
+            recursive_proof::<F, C, S, C, D>(stark, proof, &config, false)?;
+        }
+        Ok(())
+    }
+
     #[test]
     fn test_fibonacci_stark_degree() -> Result<()> {
         const D: usize = 2;
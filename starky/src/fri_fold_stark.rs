@@ -0,0 +1,320 @@
+//! An AIR for the arithmetic of the folding chain of a single FRI query. It is not a verifier of
+//! FRI, and is only built with the `experimental` feature.
+//!
+//! Row `i` of the trace holds the `i`-th arity-2 reduction of the query: the evaluations of the
+//! current polynomial `p` at a pair of points `x` and `-x`, the folding challenge `beta`, and the
+//! folded evaluation `p_even(x^2) + beta p_odd(x^2)`, which must reappear in the next row as the
+//! evaluation at `x'` or `-x'`, where `x' = ±x^2`. Values live in the quadratic extension of `F`,
+//! as in FRI over Goldilocks.
+//!
+//! **This AIR is not sound as a check of a FRI query.** Each row's challenge `beta`, the evaluation
+//! at the sibling point and the `negated` choices are unconstrained, and none of them are public
+//! inputs, so a prover can pick them to fold to any final evaluation, and whoever consumes the
+//! proof has no way to check them. Binding them would take the challenges and the Merkle openings
+//! of the sibling evaluations as inputs, through hashing tables and cross-table lookups which this
+//! crate doesn't have.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::marker::PhantomData;
+
+use plonky2::field::extension::{Extendable, FieldExtension};
+use plonky2::field::ops::Square;
+use plonky2::field::packed::PackedField;
+use plonky2::field::polynomial::{PolynomialCoeffs, PolynomialValues};
+use plonky2::field::types::Field;
+use plonky2::hash::hash_types::RichField;
+use plonky2::iop::ext_target::ExtensionTarget;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+
+use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use crate::public_cells::PublicCell;
+use crate::stark::Stark;
+use crate::util::trace_rows_to_poly_values;
+use crate::vars::{StarkEvaluationTargets, StarkEvaluationVars};
+
+crate::column_layout! {
+    /// The columns of `FriFoldStark`.
+    pub struct FriFoldColumns<T> {
+        /// The point whose evaluations, and those at its negation, are folded.
+        pub x: T,
+        /// Whether the previous row's folded evaluation is the one at `-x` rather than `x`.
+        pub negated: T,
+        pub beta: [T; 2],
+        pub eval: [T; 2],
+        pub neg_eval: [T; 2],
+        /// The evaluation of the folded polynomial at `x^2`.
+        pub folded: [T; 2],
+    }
+    num_columns = NUM_FRI_FOLD_COLUMNS;
+    col_map = FRI_FOLD_COL_MAP;
+}
+
+/// Checks the arithmetic of the folding chain of one FRI query, leaving the challenges and sibling
+/// evaluations unconstrained; see the [module docs](self).
+///
+/// The public inputs are the query point, the initial evaluation at it, and the final folded
+/// evaluation. Once the polynomial is folded down to a constant, folding leaves it unchanged, so
+/// the trace can be padded with further reductions to any power-of-two length.
+#[derive(Copy, Clone)]
+pub struct FriFoldStark<F: RichField + Extendable<2>> {
+    num_rows: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<2>> FriFoldStark<F> {
+    const PI_INDEX_X: usize = 0;
+    const PI_INDEX_EVAL: usize = 1;
+    const PI_INDEX_FOLDED: usize = 3;
+
+    pub fn new(num_rows: usize) -> Self {
+        Self {
+            num_rows,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Folds `poly` with `betas`, starting from the query point `x`, and returns the trace along
+    /// with the public inputs. `negated[i]` chooses whether the `i + 1`-th reduction's `x` is the
+    /// square of the previous one or its negation. There must be a challenge and a choice for each
+    /// row, and the first choice must be `false`.
+    pub fn generate_trace(
+        &self,
+        poly: &PolynomialCoeffs<F::Extension>,
+        x: F,
+        betas: &[F::Extension],
+        negated: &[bool],
+    ) -> (Vec<PolynomialValues<F>>, [F; 5]) {
+        assert_eq!(betas.len(), self.num_rows);
+        assert_eq!(negated.len(), self.num_rows);
+        assert!(!negated[0], "The query point can't be negated");
+
+        let mut poly = poly.clone();
+        let mut x = x;
+        let mut rows = Vec::with_capacity(self.num_rows);
+        for (&beta, &negated) in betas.iter().zip(negated) {
+            if negated {
+                x = -x;
+            }
+            let x_ext = F::Extension::from_basefield(x);
+            let eval = poly.eval(x_ext);
+            let neg_eval = poly.eval(-x_ext);
+            poly = fold(&poly, beta);
+            let folded = poly.eval(x_ext.square());
+            rows.push(<[F; NUM_FRI_FOLD_COLUMNS]>::from(FriFoldColumns {
+                x,
+                negated: F::from_bool(negated),
+                beta: beta.to_basefield_array(),
+                eval: eval.to_basefield_array(),
+                neg_eval: neg_eval.to_basefield_array(),
+                folded: folded.to_basefield_array(),
+            }));
+            x = x.square();
+        }
+
+        let first: &FriFoldColumns<F> = rows[0].borrow();
+        let last: &FriFoldColumns<F> = rows[self.num_rows - 1].borrow();
+        let public_inputs = [
+            first.x,
+            first.eval[0],
+            first.eval[1],
+            last.folded[0],
+            last.folded[1],
+        ];
+        (trace_rows_to_poly_values(rows), public_inputs)
+    }
+}
+
+/// The polynomial `p_even + beta p_odd`, where `p(X) = p_even(X^2) + X p_odd(X^2)`.
+fn fold<F: Field>(poly: &PolynomialCoeffs<F>, beta: F) -> PolynomialCoeffs<F> {
+    let coeffs = poly
+        .coeffs
+        .chunks(2)
+        .map(|pair| pair[0] + beta * pair.get(1).copied().unwrap_or(F::ZERO))
+        .collect();
+    PolynomialCoeffs::new(coeffs)
+}
+
+impl<F: RichField + Extendable<2>> Stark<F, 2> for FriFoldStark<F> {
+    const COLUMNS: usize = NUM_FRI_FOLD_COLUMNS;
+    const PUBLIC_INPUTS: usize = 5;
+
+    fn eval_packed_generic<FE, P, const D2: usize>(
+        &self,
+        vars: StarkEvaluationVars<FE, P, { Self::COLUMNS }, { Self::PUBLIC_INPUTS }>,
+        yield_constr: &mut ConstraintConsumer<P>,
+    ) where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>,
+    {
+        let local: &FriFoldColumns<P> = vars.local_values.borrow();
+        let next: &FriFoldColumns<P> = vars.next_values.borrow();
+        let w = FE::from_basefield(<F as Extendable<2>>::W);
+        let two = FE::TWO;
+
+        yield_constr.constraint(local.negated * (local.negated - P::ONES));
+        yield_constr.constraint_first_row(local.negated);
+
+        // 2 x folded = x (eval + neg_eval) + beta (eval - neg_eval)
+        let diff = [
+            local.eval[0] - local.neg_eval[0],
+            local.eval[1] - local.neg_eval[1],
+        ];
+        let beta_diff = [
+            local.beta[0] * diff[0] + local.beta[1] * diff[1] * w,
+            local.beta[0] * diff[1] + local.beta[1] * diff[0],
+        ];
+        for (i, beta_diff) in beta_diff.into_iter().enumerate() {
+            yield_constr.constraint(
+                local.x * local.folded[i] * two
+                    - local.x * (local.eval[i] + local.neg_eval[i])
+                    - beta_diff,
+            );
+        }
+
+        // x' = x^2, or -x^2 if negated'.
+        let x_squared = local.x * local.x;
+        yield_constr.constraint_transition(next.x - x_squared + next.negated * x_squared * two);
+        // The folded evaluation is the next evaluation at x', or at -x' if negated'.
+        for i in 0..2 {
+            let next_eval = next.eval[i] + next.negated * (next.neg_eval[i] - next.eval[i]);
+            yield_constr.constraint_transition(next_eval - local.folded[i]);
+        }
+    }
+
+    fn eval_ext_circuit(
+        &self,
+        builder: &mut CircuitBuilder<F, 2>,
+        vars: StarkEvaluationTargets<2, { Self::COLUMNS }, { Self::PUBLIC_INPUTS }>,
+        yield_constr: &mut RecursiveConstraintConsumer<F, 2>,
+    ) {
+        let local: &FriFoldColumns<ExtensionTarget<2>> = vars.local_values.borrow();
+        let next: &FriFoldColumns<ExtensionTarget<2>> = vars.next_values.borrow();
+        let w = <F as Extendable<2>>::W;
+
+        let negated_bool = builder.mul_sub_extension(local.negated, local.negated, local.negated);
+        yield_constr.constraint(builder, negated_bool);
+        yield_constr.constraint_first_row(builder, local.negated);
+
+        let diff = [
+            builder.sub_extension(local.eval[0], local.neg_eval[0]),
+            builder.sub_extension(local.eval[1], local.neg_eval[1]),
+        ];
+        let beta_diff = {
+            let t0 = builder.mul_extension(local.beta[0], diff[0]);
+            let c0 = builder.arithmetic_extension(w, F::ONE, local.beta[1], diff[1], t0);
+            let t1 = builder.mul_extension(local.beta[0], diff[1]);
+            let c1 = builder.mul_add_extension(local.beta[1], diff[0], t1);
+            [c0, c1]
+        };
+        for (i, beta_diff) in beta_diff.into_iter().enumerate() {
+            let sum = builder.add_extension(local.eval[i], local.neg_eval[i]);
+            let x_sum = builder.mul_extension(local.x, sum);
+            let rhs = builder.add_extension(x_sum, beta_diff);
+            let constraint =
+                builder.arithmetic_extension(F::TWO, F::NEG_ONE, local.x, local.folded[i], rhs);
+            yield_constr.constraint(builder, constraint);
+        }
+
+        let x_squared = builder.square_extension(local.x);
+        let x_constraint = {
+            let t = builder.sub_extension(next.x, x_squared);
+            builder.arithmetic_extension(F::TWO, F::ONE, next.negated, x_squared, t)
+        };
+        yield_constr.constraint_transition(builder, x_constraint);
+        for i in 0..2 {
+            let delta = builder.sub_extension(next.neg_eval[i], next.eval[i]);
+            let next_eval = builder.mul_add_extension(next.negated, delta, next.eval[i]);
+            let constraint = builder.sub_extension(next_eval, local.folded[i]);
+            yield_constr.constraint_transition(builder, constraint);
+        }
+    }
+
+    fn constraint_degree(&self) -> usize {
+        3
+    }
+
+    fn public_cells(&self) -> Vec<PublicCell> {
+        vec![
+            PublicCell::first_row(FRI_FOLD_COL_MAP.x, Self::PI_INDEX_X),
+            PublicCell::first_row(FRI_FOLD_COL_MAP.eval[0], Self::PI_INDEX_EVAL),
+            PublicCell::first_row(FRI_FOLD_COL_MAP.eval[1], Self::PI_INDEX_EVAL + 1),
+            PublicCell::last_row(FRI_FOLD_COL_MAP.folded[0], Self::PI_INDEX_FOLDED),
+            PublicCell::last_row(FRI_FOLD_COL_MAP.folded[1], Self::PI_INDEX_FOLDED + 1),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2::field::types::Sample;
+    use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use plonky2::util::timing::TimingTree;
+
+    use super::*;
+    use crate::config::StarkConfig;
+    use crate::prover::prove;
+    use crate::stark_testing::{test_stark_circuit_constraints, test_stark_low_degree};
+    use crate::verifier::verify_stark_proof;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+    type FE = <F as Extendable<D>>::Extension;
+    type S = FriFoldStark<F>;
+
+    #[test]
+    fn test_fri_fold_stark() -> Result<()> {
+        let config = StarkConfig::standard_fast_config();
+        let num_rows = 1 << 5;
+        let stark = S::new(num_rows);
+
+        // A polynomial of degree below 2^6, which 6 reductions fold down to a constant.
+        let poly = PolynomialCoeffs::new(FE::rand_vec(1 << 6));
+        let betas = FE::rand_vec(num_rows);
+        let negated = (0..num_rows).map(|i| i % 3 == 1).collect::<Vec<_>>();
+        let x = F::primitive_root_of_unity(10).exp_u64(123) * F::coset_shift();
+        let (trace, public_inputs) = stark.generate_trace(&poly, x, &betas, &negated);
+
+        let expected = betas[..6].iter().fold(poly, |p, &beta| fold(&p, beta));
+        assert_eq!(expected.len(), 1);
+        assert_eq!(
+            public_inputs[S::PI_INDEX_FOLDED..],
+            <FE as FieldExtension<D>>::to_basefield_array(&expected.coeffs[0])
+        );
+
+        let proof = prove::<F, C, S, D>(
+            stark,
+            &config,
+            trace.clone(),
+            public_inputs,
+            &mut TimingTree::default(),
+        )?;
+        verify_stark_proof(stark, proof, &config)?;
+
+        // A different final evaluation can't be proven.
+        let mut wrong_public_inputs = public_inputs;
+        wrong_public_inputs[S::PI_INDEX_FOLDED] += F::ONE;
+        assert!(prove::<F, C, S, D>(
+            stark,
+            &config,
+            trace,
+            wrong_public_inputs,
+            &mut TimingTree::default(),
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_fri_fold_stark_degree() -> Result<()> {
+        test_stark_low_degree(S::new(1 << 5))
+    }
+
+    #[test]
+    fn test_fri_fold_stark_circuit() -> Result<()> {
+        test_stark_circuit_constraints::<F, C, S, D>(S::new(1 << 5))
+    }
+}
@@ -1,3 +1,4 @@
+use alloc::vec;
 use alloc::vec::Vec;
 
 use plonky2::field::extension::Extendable;
@@ -9,17 +10,68 @@ use plonky2::hash::merkle_tree::MerkleCap;
 use plonky2::iop::challenger::{Challenger, RecursiveChallenger};
 use plonky2::iop::target::Target;
 use plonky2::plonk::circuit_builder::CircuitBuilder;
-use plonky2::plonk::config::{AlgebraicHasher, GenericConfig};
+use plonky2::plonk::config::{AlgebraicHasher, GenericConfig, Hasher};
 
 use crate::config::StarkConfig;
 use crate::permutation::{
     get_n_permutation_challenge_sets, get_n_permutation_challenge_sets_target,
 };
 use crate::proof::*;
-use crate::stark::Stark;
+use crate::public_cells::BoundaryRow;
+use crate::stark::{opened_next_row_columns, used_columns, Stark};
+
+/// A digest of the STARK's shape and configuration. It is absorbed, after the transcript version,
+/// at the start of the transcript, so that a proof can't be checked against another STARK or
+/// configuration.
+pub(crate) fn domain_separator_digest<F, H, S, const D: usize>(
+    stark: &S,
+    config: &StarkConfig,
+    degree_bits: usize,
+) -> H::Hash
+where
+    F: RichField + Extendable<D>,
+    H: Hasher<F>,
+    S: Stark<F, D>,
+{
+    let mut elements = vec![
+        F::from_canonical_usize(S::COLUMNS),
+        F::from_canonical_usize(S::PUBLIC_INPUTS),
+        F::from_canonical_usize(stark.constraint_degree()),
+        F::from_bool(stark.uses_auxiliary_polys()),
+        F::from_canonical_usize(stark.permutation_batch_size()),
+        F::from_canonical_usize(stark.num_auxiliary_polys(config)),
+        F::from_canonical_usize(config.security_bits),
+        F::from_canonical_usize(config.num_challenges),
+        F::from_canonical_usize(config.trace_extra_rate_bits),
+        F::from_canonical_usize(config.leaf_group_bits),
+    ];
+    // The columns which are committed to, and those which are opened at the next row.
+    for columns in [used_columns(stark), opened_next_row_columns(stark)] {
+        elements.push(F::from_canonical_usize(columns.len()));
+        elements.extend(columns.into_iter().map(F::from_canonical_usize));
+    }
+    let public_cells = stark.public_cells();
+    elements.push(F::from_canonical_usize(public_cells.len()));
+    for cell in public_cells {
+        elements.extend([
+            F::from_bool(cell.row == BoundaryRow::Last),
+            F::from_canonical_usize(cell.column),
+            F::from_canonical_usize(cell.public_input),
+        ]);
+    }
+    let is_padding_column = stark.is_padding_column();
+    elements.extend([
+        F::from_bool(is_padding_column.is_some()),
+        F::from_canonical_usize(is_padding_column.unwrap_or(0)),
+    ]);
+    elements.extend(config.fri_params(degree_bits).to_field_elements::<F>());
+    H::hash_no_pad(&elements)
+}
 
 fn get_challenges<F, C, S, const D: usize>(
     stark: &S,
+    public_inputs: &[F],
+    transcript: StarkTranscript,
     trace_cap: &MerkleCap<F, C::Hasher>,
     permutation_zs_cap: Option<&MerkleCap<F, C::Hasher>>,
     quotient_polys_cap: &MerkleCap<F, C::Hasher>,
@@ -39,6 +91,15 @@ where
 
     let mut challenger = Challenger::<F, C::Hasher>::new();
 
+    if transcript == StarkTranscript::Current {
+        challenger.observe_domain_separator::<C::Hasher>(domain_separator_digest::<
+            F,
+            C::Hasher,
+            S,
+            D,
+        >(stark, config, degree_bits));
+        challenger.observe_elements(public_inputs);
+    }
     challenger.observe_cap(trace_cap);
 
     let permutation_challenge_sets = permutation_zs_cap.map(|permutation_zs_cap| {
@@ -85,17 +146,18 @@ where
         config: &StarkConfig,
         degree_bits: usize,
     ) -> Vec<usize> {
-        self.get_challenges(stark, config, degree_bits)
+        self.get_challenges(stark, config, degree_bits, StarkTranscript::Current)
             .fri_challenges
             .fri_query_indices
     }
 
-    /// Computes all Fiat-Shamir challenges used in the STARK proof.
+    /// Computes all Fiat-Shamir challenges used in the STARK proof, with the given transcript.
     pub(crate) fn get_challenges<S: Stark<F, D>>(
         &self,
         stark: &S,
         config: &StarkConfig,
         degree_bits: usize,
+        transcript: StarkTranscript,
     ) -> StarkProofChallenges<F, D> {
         let StarkProof {
             trace_cap,
@@ -113,6 +175,8 @@ where
 
         get_challenges::<F, C, S, D>(
             stark,
+            &self.public_inputs,
+            transcript,
             trace_cap,
             permutation_zs_cap.as_ref(),
             quotient_polys_cap,
@@ -135,6 +199,7 @@ pub(crate) fn get_challenges_target<
 >(
     builder: &mut CircuitBuilder<F, D>,
     stark: &S,
+    public_inputs: &[Target],
     trace_cap: &MerkleCapTarget,
     permutation_zs_cap: Option<&MerkleCapTarget>,
     quotient_polys_cap: &MerkleCapTarget,
@@ -143,6 +208,7 @@ pub(crate) fn get_challenges_target<
     final_poly: &PolynomialCoeffsExtTarget<D>,
     pow_witness: Target,
     config: &StarkConfig,
+    degree_bits: usize,
 ) -> StarkProofChallengesTarget<D>
 where
     C::Hasher: AlgebraicHasher<F>,
@@ -151,6 +217,11 @@ where
 
     let mut challenger = RecursiveChallenger::<F, C::Hasher, D>::new(builder);
 
+    challenger.observe_domain_separator(
+        builder,
+        domain_separator_digest::<F, C::Hasher, S, D>(stark, config, degree_bits),
+    );
+    challenger.observe_elements(public_inputs);
     challenger.observe_cap(trace_cap);
 
     let permutation_challenge_sets = permutation_zs_cap.map(|permutation_zs_cap| {
@@ -216,6 +287,7 @@ impl<const D: usize> StarkProofWithPublicInputsTarget<D> {
         get_challenges_target::<F, C, S, D>(
             builder,
             stark,
+            &self.public_inputs,
             trace_cap,
             permutation_zs_cap.as_ref(),
             quotient_polys_cap,
@@ -224,6 +296,7 @@ impl<const D: usize> StarkProofWithPublicInputsTarget<D> {
             final_poly,
             *pow_witness,
             config,
+            self.proof.recover_degree_bits(config),
         )
     }
 }
@@ -335,3 +408,91 @@ impl<const D: usize> StarkProofWithPublicInputsTarget<D> {
 //         FriInferredElements(fri_inferred_elements)
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2::field::types::{Field, PrimeField64};
+    use plonky2::iop::generator::generate_partial_witness;
+    use plonky2::iop::witness::{PartialWitness, Witness};
+    use plonky2::plonk::circuit_data::CircuitConfig;
+    use plonky2::plonk::config::PoseidonGoldilocksConfig;
+    use plonky2::util::timing::TimingTree;
+
+    use super::*;
+    use crate::fibonacci_stark::FibonacciStark;
+    use crate::prover::prove;
+    use crate::recursive_verifier::{
+        add_virtual_stark_proof_with_pis, set_stark_proof_with_pis_target,
+    };
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+    type S = FibonacciStark<F, D>;
+
+    #[test]
+    fn test_challenges_bind_public_inputs() -> Result<()> {
+        let config = StarkConfig::standard_fast_config();
+        let num_rows = 1 << 5;
+        let stark = S::new(num_rows);
+        let x1 = (0..num_rows - 1)
+            .fold((F::ZERO, F::ONE), |x, _| (x.1, x.0 + x.1))
+            .1;
+        let proof = prove::<F, C, S, D>(
+            stark,
+            &config,
+            stark.generate_trace(F::ZERO, F::ONE),
+            [F::ZERO, F::ONE, x1],
+            &mut TimingTree::default(),
+        )?;
+        let degree_bits = proof.proof.recover_degree_bits(&config);
+        let challenges = |proof: &StarkProofWithPublicInputs<F, C, D>, transcript| {
+            proof.get_challenges(&stark, &config, degree_bits, transcript)
+        };
+        let current = challenges(&proof, StarkTranscript::Current);
+
+        // Proving is deterministic, so these pin the current transcript, which absorbs the
+        // transcript version, the domain separator and then the public inputs.
+        assert_eq!(
+            current
+                .stark_alphas
+                .iter()
+                .map(|alpha| alpha.to_canonical_u64())
+                .collect::<Vec<_>>(),
+            [16668275527405870629, 6964420069043075850]
+        );
+
+        // Changing a public input changes the challenges, unless with the legacy transcript.
+        let mut other_proof = proof.clone();
+        other_proof.public_inputs[2] += F::ONE;
+        assert_ne!(
+            challenges(&other_proof, StarkTranscript::Current).stark_alphas,
+            current.stark_alphas
+        );
+        assert_eq!(
+            challenges(&other_proof, StarkTranscript::Legacy).stark_alphas,
+            challenges(&proof, StarkTranscript::Legacy).stark_alphas
+        );
+
+        // The recursive challenger derives the same challenges.
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let proof_target =
+            add_virtual_stark_proof_with_pis(&mut builder, stark, &config, degree_bits);
+        let challenges_target =
+            proof_target.get_challenges::<F, C, S>(&mut builder, &stark, &config);
+        let mut pw = PartialWitness::new();
+        set_stark_proof_with_pis_target(&mut pw, &proof_target, &proof);
+        let data = builder.build::<C>();
+        let witness = generate_partial_witness(pw, &data.prover_only, &data.common);
+        assert_eq!(
+            witness.get_targets(&challenges_target.stark_alphas),
+            current.stark_alphas
+        );
+        assert_eq!(
+            witness.get_extension_target(challenges_target.stark_zeta),
+            current.stark_zeta
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,151 @@
+//! JSON encoding of STARK proofs and configs, following the conventions of
+//! `plonky2::util::serialization::json`.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use anyhow::Result;
+use plonky2::field::extension::Extendable;
+use plonky2::hash::hash_types::RichField;
+use plonky2::plonk::config::GenericConfig;
+use plonky2::util::serialization::json::{
+    cap_from_hex, cap_to_hex, exts_from_hex, exts_to_hex, fields_from_hex, fields_to_hex,
+    JsonDocument, JsonFriProof,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::config::StarkConfig;
+use crate::proof::{StarkOpeningSet, StarkProof, StarkProofWithPublicInputs};
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct JsonStarkOpeningSet {
+    pub local_values: Vec<Vec<String>>,
+    pub next_values: Vec<Vec<String>>,
+    #[serde(default)]
+    pub additional_values: Vec<Vec<Vec<String>>>,
+    pub permutation_zs: Option<Vec<Vec<String>>>,
+    pub permutation_zs_next: Option<Vec<Vec<String>>>,
+    pub quotient_polys: Vec<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct JsonStarkProof {
+    pub trace_cap: Vec<String>,
+    pub permutation_zs_cap: Option<Vec<String>>,
+    pub quotient_polys_cap: Vec<String>,
+    pub openings: JsonStarkOpeningSet,
+    pub opening_proof: JsonFriProof,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct JsonStarkProofWithPublicInputs {
+    pub proof: JsonStarkProof,
+    pub public_inputs: Vec<String>,
+}
+
+impl JsonStarkProofWithPublicInputs {
+    pub const SCHEMA: &'static str = "starky/stark_proof_with_public_inputs";
+
+    pub fn new<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+        proof_with_pis: &StarkProofWithPublicInputs<F, C, D>,
+    ) -> Self {
+        let proof = &proof_with_pis.proof;
+        let o = &proof.openings;
+        Self {
+            proof: JsonStarkProof {
+                trace_cap: cap_to_hex(&proof.trace_cap),
+                permutation_zs_cap: proof.permutation_zs_cap.as_ref().map(cap_to_hex),
+                quotient_polys_cap: cap_to_hex(&proof.quotient_polys_cap),
+                openings: JsonStarkOpeningSet {
+                    local_values: exts_to_hex::<F, D>(&o.local_values),
+                    next_values: exts_to_hex::<F, D>(&o.next_values),
+                    additional_values: o
+                        .additional_values
+                        .iter()
+                        .map(|values| exts_to_hex::<F, D>(values))
+                        .collect(),
+                    permutation_zs: o.permutation_zs.as_ref().map(|zs| exts_to_hex::<F, D>(zs)),
+                    permutation_zs_next: o
+                        .permutation_zs_next
+                        .as_ref()
+                        .map(|zs| exts_to_hex::<F, D>(zs)),
+                    quotient_polys: exts_to_hex::<F, D>(&o.quotient_polys),
+                },
+                opening_proof: JsonFriProof::new(&proof.opening_proof),
+            },
+            public_inputs: fields_to_hex(&proof_with_pis.public_inputs),
+        }
+    }
+
+    pub fn decode<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+        &self,
+    ) -> Result<StarkProofWithPublicInputs<F, C, D>> {
+        let proof = &self.proof;
+        let o = &proof.openings;
+        Ok(StarkProofWithPublicInputs {
+            proof: StarkProof {
+                trace_cap: cap_from_hex(&proof.trace_cap)?,
+                permutation_zs_cap: proof
+                    .permutation_zs_cap
+                    .as_ref()
+                    .map(|cap| cap_from_hex(cap))
+                    .transpose()?,
+                quotient_polys_cap: cap_from_hex(&proof.quotient_polys_cap)?,
+                openings: StarkOpeningSet {
+                    local_values: exts_from_hex::<F, D>(&o.local_values)?,
+                    next_values: exts_from_hex::<F, D>(&o.next_values)?,
+                    additional_values: o
+                        .additional_values
+                        .iter()
+                        .map(|values| exts_from_hex::<F, D>(values))
+                        .collect::<Result<_>>()?,
+                    permutation_zs: o
+                        .permutation_zs
+                        .as_ref()
+                        .map(|zs| exts_from_hex::<F, D>(zs))
+                        .transpose()?,
+                    permutation_zs_next: o
+                        .permutation_zs_next
+                        .as_ref()
+                        .map(|zs| exts_from_hex::<F, D>(zs))
+                        .transpose()?,
+                    quotient_polys: exts_from_hex::<F, D>(&o.quotient_polys)?,
+                },
+                opening_proof: proof.opening_proof.decode()?,
+            },
+            public_inputs: fields_from_hex(&self.public_inputs)?,
+        })
+    }
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    StarkProofWithPublicInputs<F, C, D>
+{
+    pub fn to_json(&self) -> Result<String> {
+        JsonDocument::new(
+            JsonStarkProofWithPublicInputs::SCHEMA,
+            JsonStarkProofWithPublicInputs::new(self),
+        )
+        .to_json()
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        JsonDocument::<JsonStarkProofWithPublicInputs>::from_json(
+            json,
+            JsonStarkProofWithPublicInputs::SCHEMA,
+        )?
+        .decode()
+    }
+}
+
+impl StarkConfig {
+    pub const JSON_SCHEMA: &'static str = "starky/stark_config";
+
+    pub fn to_json(&self) -> Result<String> {
+        JsonDocument::new(Self::JSON_SCHEMA, self.clone()).to_json()
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        JsonDocument::from_json(json, Self::JSON_SCHEMA)
+    }
+}
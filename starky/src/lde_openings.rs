@@ -0,0 +1,260 @@
+//! Openings of rows of a proof's committed trace LDE, which anyone holding the trace cap can check
+//! without the rest of the proof, e.g. to bind the trace to data published elsewhere.
+//!
+//! The trace isn't committed to directly, but through its low-degree extension (LDE): the values of
+//! the trace polynomials on a coset of a subgroup `2^trace_rate_bits` times larger than the trace.
+//! Merkle proofs thus open rows of the LDE, each holding the values of the committed columns at a
+//! point of that coset, rather than rows of the trace itself. Since the STARK proof shows that the
+//! committed LDE is close to the evaluations of polynomials of degree below the trace length, this
+//! is the setting of data availability sampling: checking enough random rows against the cap
+//! convinces a party that the whole trace can be recovered from the LDE.
+//!
+//! Openings of trace cells, i.e. of the trace polynomials at the points `g^t` of the trace's
+//! subgroup, aren't supported. The LDE coset doesn't contain these points, so their values would
+//! have to be opened through FRI, with each `g^t` added to the FRI instance of the proof as a
+//! batch of the trace polynomials, and the rows to open made part of the statement checked by
+//! both verifiers. Until then, a row opened here holds evaluations of the trace polynomials, not
+//! values of the trace.
+
+use alloc::vec::Vec;
+
+use anyhow::{ensure, Result};
+use plonky2::field::extension::Extendable;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::fri::oracle::{PolynomialBatch, SALT_SIZE};
+use plonky2::hash::hash_types::RichField;
+use plonky2::hash::merkle_proofs::{verify_merkle_proof_to_cap, MerkleProof};
+use plonky2::hash::merkle_tree::MerkleCap;
+use plonky2::plonk::config::{GenericConfig, Hasher};
+use plonky2::util::timing::TimingTree;
+use plonky2::util::{log2_strict, reverse_bits};
+use serde::{Deserialize, Serialize};
+
+use crate::checkpoint::ProverPhase;
+use crate::config::StarkConfig;
+use crate::proof::{StarkProof, StarkProofWithPublicInputs};
+use crate::prover::prove_with_checkpoints;
+use crate::stark::{used_columns, Stark};
+
+/// What is needed to check openings of rows of a trace LDE: the trace cap, along with the shape of
+/// the LDE and of the Merkle leaves.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct TraceCommitment<F: RichField, H: Hasher<F>> {
+    pub trace_cap: MerkleCap<F, H>,
+    /// The trace has `2^degree_bits` rows, and its LDE `2^(degree_bits + rate_bits)`.
+    pub degree_bits: usize,
+    pub rate_bits: usize,
+    pub leaf_group_bits: usize,
+    /// The committed columns, in the order of their values in each row.
+    pub columns: Vec<usize>,
+    /// Whether each row is followed by `SALT_SIZE` random elements in its leaf.
+    pub salted: bool,
+}
+
+/// A row of a trace LDE, along with a Merkle proof of the leaf containing it.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct LdeRowOpening<F: RichField, H: Hasher<F>> {
+    /// The index of the row in the LDE, whose values are those of the trace polynomials at
+    /// `TraceCommitment::point(index)`.
+    pub index: usize,
+    /// The Merkle leaf, which holds the row along with the others of its group and the salts.
+    pub leaf: Vec<F>,
+    pub proof: MerkleProof<F, H>,
+}
+
+impl<F: RichField, H: Hasher<F>> TraceCommitment<F, H> {
+    /// The trace commitment of `proof`, a proof of `stark` with `config`.
+    pub fn from_proof<C, S, const D: usize>(
+        stark: &S,
+        config: &StarkConfig,
+        proof: &StarkProof<F, C, D>,
+    ) -> Self
+    where
+        F: Extendable<D>,
+        C: GenericConfig<D, F = F, Hasher = H>,
+        S: Stark<F, D>,
+    {
+        Self {
+            trace_cap: proof.trace_cap.clone(),
+            degree_bits: proof.recover_degree_bits(config),
+            rate_bits: config.trace_rate_bits(),
+            leaf_group_bits: config.leaf_group_bits,
+            columns: used_columns(stark),
+            salted: config.salted_commitments,
+        }
+    }
+
+    pub fn lde_bits(&self) -> usize {
+        self.degree_bits + self.rate_bits
+    }
+
+    /// The point at which the row at `index` of the LDE holds the values of the trace polynomials.
+    pub fn point(&self, index: usize) -> F {
+        F::coset_shift() * F::primitive_root_of_unity(self.lde_bits()).exp_u64(index as u64)
+    }
+
+    /// Checks `opening` against the trace cap, and returns the values of the committed columns in
+    /// its row, in the order of `columns`.
+    pub fn verify_opening(&self, opening: &LdeRowOpening<F, H>) -> Result<Vec<F>> {
+        let lde_bits = self.lde_bits();
+        ensure!(
+            opening.index < 1 << lde_bits,
+            "Row {} is outside of an LDE of 2^{} rows",
+            opening.index,
+            lde_bits
+        );
+        let row_len = self.columns.len() + if self.salted { SALT_SIZE } else { 0 };
+        ensure!(
+            opening.leaf.len() == row_len << self.leaf_group_bits,
+            "Wrong leaf length"
+        );
+        let cap_height = log2_strict(self.trace_cap.len());
+        ensure!(
+            opening.proof.len() + self.leaf_group_bits + cap_height == lde_bits,
+            "Wrong Merkle proof length"
+        );
+
+        let position = reverse_bits(opening.index, lde_bits);
+        verify_merkle_proof_to_cap(
+            opening.leaf.clone(),
+            position >> self.leaf_group_bits,
+            &self.trace_cap,
+            &opening.proof,
+        )?;
+        let row_start = (position & ((1 << self.leaf_group_bits) - 1)) * row_len;
+        Ok(opening.leaf[row_start..row_start + self.columns.len()].to_vec())
+    }
+}
+
+/// Opens the rows at `indices` of the LDE committed to in `trace_commitment`.
+pub fn open_lde_rows<F, C, const D: usize>(
+    trace_commitment: &PolynomialBatch<F, C, D>,
+    indices: &[usize],
+) -> Vec<LdeRowOpening<F, C::Hasher>>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+{
+    let lde_bits = trace_commitment.degree_log + trace_commitment.rate_bits;
+    let leaf_group_bits = trace_commitment.leaf_group_bits();
+    indices
+        .iter()
+        .map(|&index| {
+            let leaf_index = reverse_bits(index, lde_bits) >> leaf_group_bits;
+            LdeRowOpening {
+                index,
+                leaf: trace_commitment.merkle_tree.leaves[leaf_index].clone(),
+                proof: trace_commitment.merkle_tree.prove(leaf_index),
+            }
+        })
+        .collect()
+}
+
+/// Like [`prove`](crate::prover::prove), but also opens the rows at `indices` of the trace LDE.
+pub fn prove_with_lde_openings<F, C, S, const D: usize>(
+    stark: S,
+    config: &StarkConfig,
+    trace_poly_values: Vec<PolynomialValues<F>>,
+    public_inputs: [F; S::PUBLIC_INPUTS],
+    indices: &[usize],
+    timing: &mut TimingTree,
+) -> Result<(
+    StarkProofWithPublicInputs<F, C, D>,
+    Vec<LdeRowOpening<F, C::Hasher>>,
+)>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    S: Stark<F, D>,
+    [(); S::COLUMNS]:,
+    [(); S::PUBLIC_INPUTS]:,
+{
+    let mut openings = Vec::new();
+    let proof = prove_with_checkpoints::<F, C, S, D>(
+        stark,
+        config,
+        trace_poly_values,
+        public_inputs,
+        None,
+        &mut |checkpoint| {
+            if checkpoint.phase() == ProverPhase::TraceCommitted {
+                openings = open_lde_rows(&checkpoint.trace_commitment, indices);
+            }
+            Ok(())
+        },
+        timing,
+    )?;
+    Ok((proof, openings))
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2::field::types::Field;
+    use plonky2::plonk::config::PoseidonGoldilocksConfig;
+
+    use super::*;
+    use crate::fibonacci_stark::FibonacciStark;
+    use crate::verifier::verify_stark_proof;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+    type H = <C as GenericConfig<D>>::Hasher;
+    type S = FibonacciStark<F, D>;
+
+    fn test_lde_openings(config: StarkConfig) -> Result<()> {
+        let num_rows = 1 << 5;
+        let stark = S::new(num_rows);
+        let trace = stark.generate_trace(F::ZERO, F::ONE);
+        let x1 = (0..num_rows - 1)
+            .fold((F::ZERO, F::ONE), |x, _| (x.1, x.0 + x.1))
+            .1;
+        let indices = [0, 1, 17, 63];
+        let (proof, openings) = prove_with_lde_openings::<F, C, S, D>(
+            stark,
+            &config,
+            trace.clone(),
+            [F::ZERO, F::ONE, x1],
+            &indices,
+            &mut TimingTree::default(),
+        )?;
+        verify_stark_proof(stark, proof.clone(), &config)?;
+
+        let commitment = TraceCommitment::<F, H>::from_proof(&stark, &config, &proof.proof);
+        let trace_polys = trace.into_iter().map(|v| v.ifft()).collect::<Vec<_>>();
+        for opening in &openings {
+            let values = commitment.verify_opening(opening)?;
+            let x = commitment.point(opening.index);
+            let expected = commitment
+                .columns
+                .iter()
+                .map(|&c| trace_polys[c].eval(x))
+                .collect::<Vec<_>>();
+            assert_eq!(values, expected);
+        }
+
+        // The opening of a row doesn't check out as another row, nor with other values.
+        let mut opening = openings[1].clone();
+        opening.index = 2;
+        assert!(commitment.verify_opening(&opening).is_err());
+        let mut opening = openings[2].clone();
+        opening.leaf[0] += F::ONE;
+        assert!(commitment.verify_opening(&opening).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_lde_openings_standard() -> Result<()> {
+        test_lde_openings(StarkConfig::standard_fast_config())
+    }
+
+    #[test]
+    fn test_lde_openings_salted_and_grouped() -> Result<()> {
+        test_lde_openings(StarkConfig {
+            leaf_group_bits: 1,
+            ..StarkConfig::standard_fast_salted_config()
+        })
+    }
+}
@@ -8,18 +8,36 @@ extern crate alloc;
 
 mod get_challenges;
 
+pub mod any_verifier;
+pub mod checkpoint;
+pub mod column_layout;
+pub mod compressed_trace;
 pub mod config;
 pub mod constraint_consumer;
+pub mod degree_lowering;
+#[cfg(feature = "experimental")]
+pub mod fri_fold_stark;
+pub mod json;
+pub mod lde_openings;
+pub mod lookup;
+pub mod matmul_stark;
+pub mod padding;
 pub mod permutation;
+pub mod polynomial_stark;
 pub mod proof;
 pub mod prover;
+pub mod public_cells;
 pub mod recursive_verifier;
 pub mod stark;
 pub mod stark_testing;
+pub mod trace_snapshot;
 pub mod util;
 pub mod vanishing_poly;
 pub mod vars;
 pub mod verifier;
+pub mod verifier_cost;
 
 #[cfg(test)]
 pub mod fibonacci_stark;
+#[cfg(test)]
+mod malleability_tests;
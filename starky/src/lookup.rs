@@ -0,0 +1,662 @@
+//! Lookup arguments, which check that every value of some columns appears in a table column.
+//!
+//! A STARK chooses a backend for each of its lookups:
+//! - `Lookup::Permuted` is the Halo2-style argument. The trace contains a sorted copy of the
+//!   input column and a matching permutation of the table column, which are checked with two
+//!   permutation arguments.
+//! - `Lookup::LogUp` is the logarithmic derivative argument. For a random `x`, it checks that
+//!   `sum_i sum_c 1 / (x - c_i) = sum_i m_i / (x - t_i)`, where `m_i` is the number of times the
+//!   table value `t_i` is looked up. The trace only needs one frequencies column per table, however
+//!   many columns look into it, so this is cheaper for tables with many lookups.
+
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+use itertools::Itertools;
+use plonky2::field::extension::{Extendable, FieldExtension};
+use plonky2::field::packed::PackedField;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::field::types::PrimeField64;
+use plonky2::hash::hash_types::RichField;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::util::ceil_div_usize;
+
+use crate::config::StarkConfig;
+use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use crate::permutation::{
+    PermutationChallengeSet, PermutationCheckDataTarget, PermutationCheckVars, PermutationPair,
+};
+use crate::stark::Stark;
+use crate::vars::{StarkEvaluationTargets, StarkEvaluationVars};
+
+/// A lookup of some trace columns into a table column, along with the backend used to argue it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Lookup {
+    /// Checks that every value of `input` appears in `table`, using the columns generated by
+    /// `permuted_cols`: `permuted_input` is a sorted copy of `input`, and `permuted_table` a
+    /// permutation of `table` whose rows equal `permuted_input` wherever a new value starts.
+    Permuted {
+        input: usize,
+        table: usize,
+        permuted_input: usize,
+        permuted_table: usize,
+    },
+    /// Checks that every value of each of `columns` appears in `table`, where `frequencies` holds
+    /// how many times each row of `table` is looked up, as generated by `logup_frequencies`.
+    LogUp {
+        columns: Vec<usize>,
+        table: usize,
+        frequencies: usize,
+    },
+}
+
+impl Lookup {
+    /// The permutation pairs checking the permuted columns of a `Permuted` lookup.
+    pub fn permutation_pairs(&self) -> Vec<PermutationPair> {
+        match self {
+            Lookup::Permuted {
+                input,
+                table,
+                permuted_input,
+                permuted_table,
+            } => vec![
+                PermutationPair::singletons(*input, *permuted_input),
+                PermutationPair::singletons(*table, *permuted_table),
+            ],
+            Lookup::LogUp { .. } => vec![],
+        }
+    }
+
+    /// The number of helper polynomials a `LogUp` lookup needs per challenge, when `batch_size`
+    /// inverses are summed in each.
+    pub fn num_helper_polys(&self, batch_size: usize) -> usize {
+        match self {
+            Lookup::Permuted { .. } => 0,
+            // One polynomial per batch of columns, plus the running sum.
+            Lookup::LogUp { columns, .. } => ceil_div_usize(columns.len(), batch_size) + 1,
+        }
+    }
+}
+
+/// The LogUp challenges, one per `config.num_challenges`. These reuse the `beta`s of the first
+/// permutation challenge set, which are only used to combine the columns of permutation pairs.
+fn logup_challenges<T: Copy>(permutation_challenge_sets: &[PermutationChallengeSet<T>]) -> Vec<T> {
+    permutation_challenge_sets[0]
+        .challenges
+        .iter()
+        .map(|challenge| challenge.beta)
+        .collect()
+}
+
+/// Given an input column and a table column, generate the permuted input and permuted table columns
+/// used by `Lookup::Permuted`.
+pub fn permuted_cols<F: PrimeField64>(inputs: &[F], table: &[F]) -> (Vec<F>, Vec<F>) {
+    let n = inputs.len();
+
+    // The permuted inputs do not have to be ordered, but we found that sorting was faster than
+    // hash-based grouping. We also sort the table, as this helps us identify "unused" table
+    // elements efficiently.
+
+    // To compare elements, e.g. for sorting, we first need them in canonical form. It would be
+    // wasteful to canonicalize in each comparison, as a single element may be involved in many
+    // comparisons. So we will canonicalize once upfront, then use `to_noncanonical_u64` when
+    // comparing elements.
+
+    let sorted_inputs = inputs
+        .iter()
+        .map(|x| x.to_canonical())
+        .sorted_unstable_by_key(|x| x.to_noncanonical_u64())
+        .collect_vec();
+    let sorted_table = table
+        .iter()
+        .map(|x| x.to_canonical())
+        .sorted_unstable_by_key(|x| x.to_noncanonical_u64())
+        .collect_vec();
+
+    let mut unused_table_inds = Vec::with_capacity(n);
+    let mut unused_table_vals = Vec::with_capacity(n);
+    let mut permuted_table = vec![F::ZERO; n];
+    let mut i = 0;
+    let mut j = 0;
+    while (j < n) && (i < n) {
+        let input_val = sorted_inputs[i].to_noncanonical_u64();
+        let table_val = sorted_table[j].to_noncanonical_u64();
+        match input_val.cmp(&table_val) {
+            Ordering::Greater => {
+                unused_table_vals.push(sorted_table[j]);
+                j += 1;
+            }
+            Ordering::Less => {
+                if let Some(x) = unused_table_vals.pop() {
+                    permuted_table[i] = x;
+                } else {
+                    unused_table_inds.push(i);
+                }
+                i += 1;
+            }
+            Ordering::Equal => {
+                permuted_table[i] = sorted_table[j];
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+
+    unused_table_vals.extend_from_slice(&sorted_table[j..n]);
+    unused_table_inds.extend(i..n);
+
+    for (ind, val) in unused_table_inds.into_iter().zip_eq(unused_table_vals) {
+        permuted_table[ind] = val;
+    }
+
+    (sorted_inputs, permuted_table)
+}
+
+/// Counts how many times each row of `table` is looked up by `columns`, giving the frequencies
+/// column of a `Lookup::LogUp`. If a value appears in several rows of the table, all its lookups
+/// are counted in the first of them. Panics if some looked up value is missing from the table.
+pub fn logup_frequencies<F: PrimeField64>(columns: &[&[F]], table: &[F]) -> Vec<F> {
+    let mut rows = BTreeMap::new();
+    for (i, x) in table.iter().enumerate().rev() {
+        rows.insert(x.to_canonical_u64(), i);
+    }
+
+    let mut frequencies = vec![0u64; table.len()];
+    for x in columns.iter().flat_map(|col| col.iter()) {
+        let row = rows
+            .get(&x.to_canonical_u64())
+            .expect("Looked up value is missing from the table");
+        frequencies[*row] += 1;
+    }
+    frequencies.into_iter().map(F::from_canonical_u64).collect()
+}
+
+/// Computes the helper polynomials of all of `stark`'s LogUp lookups, for each challenge `x`. For
+/// each batch of looking columns `c_j`, a helper polynomial holds `sum_j 1 / (x - c_j)`. The last
+/// one is the running sum `Z`, with `Z(1) = 0` and
+/// `Z(g w) = Z(w) + sum_h h(w) - m(w) / (x - t(w))`, which wraps around to `0` iff the lookup is
+/// valid.
+pub(crate) fn compute_lookup_helper_polys<F, S, const D: usize>(
+    stark: &S,
+    trace_poly_values: &[PolynomialValues<F>],
+    permutation_challenge_sets: &[PermutationChallengeSet<F>],
+) -> Vec<PolynomialValues<F>>
+where
+    F: RichField + Extendable<D>,
+    S: Stark<F, D>,
+{
+    let degree = trace_poly_values[0].len();
+    let batch_size = stark.lookup_batch_size();
+    let challenges = logup_challenges(permutation_challenge_sets);
+
+    let mut helper_polys = Vec::new();
+    for lookup in stark.lookups() {
+        let (columns, table, frequencies) = match lookup {
+            Lookup::LogUp {
+                columns,
+                table,
+                frequencies,
+            } => (columns, table, frequencies),
+            Lookup::Permuted { .. } => continue,
+        };
+        for &x in &challenges {
+            // Invert `x - c(w)` for all looking columns at once.
+            let denominators = columns
+                .iter()
+                .flat_map(|&c| trace_poly_values[c].values.iter().map(move |&v| x - v))
+                .collect::<Vec<_>>();
+            let inverses = F::batch_multiplicative_inverse(&denominators);
+
+            let mut sum_h = vec![F::ZERO; degree];
+            for batch in inverses.chunks(batch_size * degree) {
+                let h = (0..degree)
+                    .map(|i| batch.iter().skip(i).step_by(degree).copied().sum::<F>())
+                    .collect::<Vec<_>>();
+                for (acc, &v) in sum_h.iter_mut().zip(&h) {
+                    *acc += v;
+                }
+                helper_polys.push(PolynomialValues::new(h));
+            }
+
+            let table_denominators = trace_poly_values[table]
+                .values
+                .iter()
+                .map(|&t| x - t)
+                .collect::<Vec<_>>();
+            let table_inverses = F::batch_multiplicative_inverse(&table_denominators);
+            let mut z = Vec::with_capacity(degree);
+            let mut acc = F::ZERO;
+            for ((&h, &m), &t_inv) in sum_h
+                .iter()
+                .zip(&trace_poly_values[frequencies].values)
+                .zip(&table_inverses)
+            {
+                z.push(acc);
+                acc += h - m * t_inv;
+            }
+            helper_polys.push(PolynomialValues::new(z));
+        }
+    }
+    helper_polys
+}
+
+/// Evaluates the constraints of `stark`'s lookups. The LogUp helper polynomials are those in
+/// `permutation_data` which follow the permutation `Z`s.
+pub(crate) fn eval_lookup_checks<F, FE, P, S, const D: usize, const D2: usize>(
+    stark: &S,
+    config: &StarkConfig,
+    vars: StarkEvaluationVars<FE, P, { S::COLUMNS }, { S::PUBLIC_INPUTS }>,
+    permutation_data: Option<&PermutationCheckVars<F, FE, P, D2>>,
+    consumer: &mut ConstraintConsumer<P>,
+) where
+    F: RichField + Extendable<D>,
+    FE: FieldExtension<D2, BaseField = F>,
+    P: PackedField<Scalar = FE>,
+    S: Stark<F, D>,
+    [(); S::COLUMNS]:,
+    [(); S::PUBLIC_INPUTS]:,
+{
+    let batch_size = stark.lookup_batch_size();
+    let mut helper_index = stark.num_permutation_batches(config);
+    for lookup in stark.lookups() {
+        match lookup {
+            Lookup::Permuted {
+                permuted_input,
+                permuted_table,
+                ..
+            } => {
+                let local_perm_input = vars.local_values[permuted_input];
+                let next_perm_table = vars.next_values[permuted_table];
+                let next_perm_input = vars.next_values[permuted_input];
+
+                // A "vertical" diff between the local and next permuted inputs.
+                let diff_input_prev = next_perm_input - local_perm_input;
+                // A "horizontal" diff between the next permuted input and permuted table value.
+                let diff_input_table = next_perm_input - next_perm_table;
+
+                consumer.constraint(diff_input_prev * diff_input_table);
+
+                // This is actually constraining the first row, since `diff_input_table` is a diff
+                // of the next row's values. In the context of `constraint_last_row`, the next row
+                // is the first row.
+                consumer.constraint_last_row(diff_input_table);
+            }
+            Lookup::LogUp {
+                columns,
+                table,
+                frequencies,
+            } => {
+                let PermutationCheckVars {
+                    local_zs,
+                    next_zs,
+                    permutation_challenge_sets,
+                } = permutation_data.expect("LogUp lookups need helper polynomials");
+                for x in logup_challenges(permutation_challenge_sets) {
+                    let x = FE::from_basefield(x);
+                    let num_helpers = ceil_div_usize(columns.len(), batch_size);
+                    let helpers = &local_zs[helper_index..helper_index + num_helpers];
+
+                    // Check that `h(w) prod_j (x - c_j(w)) = sum_j prod_{k != j} (x - c_k(w))`.
+                    for (&h, batch) in helpers.iter().zip(columns.chunks(batch_size)) {
+                        let denominators = batch
+                            .iter()
+                            .map(|&c| P::from(x) - vars.local_values[c])
+                            .collect::<Vec<_>>();
+                        let numerator = (0..denominators.len())
+                            .map(|j| {
+                                denominators
+                                    .iter()
+                                    .enumerate()
+                                    .filter(|&(k, _)| k != j)
+                                    .map(|(_, &d)| d)
+                                    .product::<P>()
+                            })
+                            .sum::<P>();
+                        consumer
+                            .constraint(h * denominators.into_iter().product::<P>() - numerator);
+                    }
+
+                    // Check that `Z(1) = 0`, and that
+                    // `(Z(g w) - Z(w)) (x - t(w)) = sum_h h(w) (x - t(w)) - m(w)`.
+                    let z = local_zs[helper_index + num_helpers];
+                    let next_z = next_zs[helper_index + num_helpers];
+                    let table_denominator = P::from(x) - vars.local_values[table];
+                    let sum_h = helpers.iter().copied().sum::<P>();
+                    consumer.constraint_first_row(z);
+                    consumer.constraint(
+                        (next_z - z - sum_h) * table_denominator + vars.local_values[frequencies],
+                    );
+
+                    helper_index += num_helpers + 1;
+                }
+            }
+        }
+    }
+}
+
+/// Circuit version of `eval_lookup_checks`.
+pub(crate) fn eval_lookup_checks_circuit<F, S, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    stark: &S,
+    config: &StarkConfig,
+    vars: StarkEvaluationTargets<D, { S::COLUMNS }, { S::PUBLIC_INPUTS }>,
+    permutation_data: Option<&PermutationCheckDataTarget<D>>,
+    consumer: &mut RecursiveConstraintConsumer<F, D>,
+) where
+    F: RichField + Extendable<D>,
+    S: Stark<F, D>,
+    [(); S::COLUMNS]:,
+    [(); S::PUBLIC_INPUTS]:,
+{
+    let batch_size = stark.lookup_batch_size();
+    let mut helper_index = stark.num_permutation_batches(config);
+    for lookup in stark.lookups() {
+        match lookup {
+            Lookup::Permuted {
+                permuted_input,
+                permuted_table,
+                ..
+            } => {
+                let local_perm_input = vars.local_values[permuted_input];
+                let next_perm_table = vars.next_values[permuted_table];
+                let next_perm_input = vars.next_values[permuted_input];
+
+                let diff_input_prev = builder.sub_extension(next_perm_input, local_perm_input);
+                let diff_input_table = builder.sub_extension(next_perm_input, next_perm_table);
+
+                let diff_product = builder.mul_extension(diff_input_prev, diff_input_table);
+                consumer.constraint(builder, diff_product);
+                consumer.constraint_last_row(builder, diff_input_table);
+            }
+            Lookup::LogUp {
+                columns,
+                table,
+                frequencies,
+            } => {
+                let PermutationCheckDataTarget {
+                    local_zs,
+                    next_zs,
+                    permutation_challenge_sets,
+                } = permutation_data.expect("LogUp lookups need helper polynomials");
+                for x in logup_challenges(permutation_challenge_sets) {
+                    let x = builder.convert_to_ext(x);
+                    let num_helpers = ceil_div_usize(columns.len(), batch_size);
+                    let helpers = &local_zs[helper_index..helper_index + num_helpers];
+
+                    for (&h, batch) in helpers.iter().zip(columns.chunks(batch_size)) {
+                        let denominators = batch
+                            .iter()
+                            .map(|&c| builder.sub_extension(x, vars.local_values[c]))
+                            .collect::<Vec<_>>();
+                        let numerator_terms = (0..denominators.len())
+                            .map(|j| {
+                                let others = denominators
+                                    .iter()
+                                    .enumerate()
+                                    .filter(|&(k, _)| k != j)
+                                    .map(|(_, &d)| d)
+                                    .collect::<Vec<_>>();
+                                builder.mul_many_extension(others)
+                            })
+                            .collect::<Vec<_>>();
+                        let numerator = builder.add_many_extension(numerator_terms);
+                        let denominator = builder.mul_many_extension(denominators);
+                        let constraint = builder.mul_sub_extension(h, denominator, numerator);
+                        consumer.constraint(builder, constraint);
+                    }
+
+                    let z = local_zs[helper_index + num_helpers];
+                    let next_z = next_zs[helper_index + num_helpers];
+                    let table_denominator = builder.sub_extension(x, vars.local_values[table]);
+                    let sum_h = builder.add_many_extension(helpers.iter().copied());
+                    consumer.constraint_first_row(builder, z);
+                    let diff = builder.sub_extension(next_z, z);
+                    let diff = builder.sub_extension(diff, sum_h);
+                    let constraint = builder.mul_add_extension(
+                        diff,
+                        table_denominator,
+                        vars.local_values[frequencies],
+                    );
+                    consumer.constraint(builder, constraint);
+
+                    helper_index += num_helpers + 1;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::marker::PhantomData;
+
+    use anyhow::Result;
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::field::types::Field;
+    use plonky2::iop::witness::PartialWitness;
+    use plonky2::plonk::circuit_data::CircuitConfig;
+    use plonky2::plonk::config::PoseidonGoldilocksConfig;
+    use plonky2::util::timing::TimingTree;
+
+    use super::*;
+    use crate::proof::StarkProofWithPublicInputs;
+    use crate::prover::prove;
+    use crate::recursive_verifier::{
+        add_virtual_stark_proof_with_pis, set_stark_proof_with_pis_target,
+        verify_stark_proof_circuit,
+    };
+    use crate::stark_testing::{
+        test_stark_circuit_constraints, test_stark_low_degree, test_stark_next_row_columns,
+    };
+    use crate::verifier::verify_stark_proof;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = GoldilocksField;
+
+    const TABLE: usize = 0;
+    const LOOKING: [usize; 3] = [1, 2, 3];
+    const FREQUENCIES: usize = 4;
+    const PERMUTED_INPUT: usize = 5;
+    const PERMUTED_TABLE: usize = 6;
+
+    /// Range checks three columns against a counter column: all of them with LogUp, and, if
+    /// `permuted` is set, the first one again with a permuted lookup. Otherwise, the permuted
+    /// columns are unused.
+    #[derive(Copy, Clone)]
+    struct RangeCheckStark<F: RichField + Extendable<D>, const D: usize> {
+        num_rows: usize,
+        permuted: bool,
+        _phantom: PhantomData<F>,
+    }
+
+    impl<F: RichField + Extendable<D>, const D: usize> RangeCheckStark<F, D> {
+        fn new(num_rows: usize) -> Self {
+            Self {
+                num_rows,
+                permuted: true,
+                _phantom: PhantomData,
+            }
+        }
+
+        fn generate_trace(&self) -> Vec<PolynomialValues<F>> {
+            let n = self.num_rows;
+            let table = (0..n).map(F::from_canonical_usize).collect::<Vec<_>>();
+            let looking = [(7, 3), (5, 1), (0, 2)].map(|(a, b)| {
+                (0..n)
+                    .map(|i| F::from_canonical_usize((a * i + b) % n))
+                    .collect::<Vec<_>>()
+            });
+            let frequencies = logup_frequencies(&[&looking[0], &looking[1], &looking[2]], &table);
+            let (permuted_input, permuted_table) = permuted_cols(&looking[0], &table);
+
+            let [a, b, c] = looking;
+            [table, a, b, c, frequencies, permuted_input, permuted_table]
+                .into_iter()
+                .map(PolynomialValues::new)
+                .collect()
+        }
+    }
+
+    impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for RangeCheckStark<F, D> {
+        const COLUMNS: usize = 7;
+        const PUBLIC_INPUTS: usize = 0;
+
+        fn eval_packed_generic<FE, P, const D2: usize>(
+            &self,
+            vars: StarkEvaluationVars<FE, P, { Self::COLUMNS }, { Self::PUBLIC_INPUTS }>,
+            yield_constr: &mut ConstraintConsumer<P>,
+        ) where
+            FE: FieldExtension<D2, BaseField = F>,
+            P: PackedField<Scalar = FE>,
+        {
+            let table = vars.local_values[TABLE];
+            yield_constr.constraint_first_row(table);
+            yield_constr.constraint_transition(vars.next_values[TABLE] - table - FE::ONE);
+        }
+
+        fn eval_ext_circuit(
+            &self,
+            builder: &mut CircuitBuilder<F, D>,
+            vars: StarkEvaluationTargets<D, { Self::COLUMNS }, { Self::PUBLIC_INPUTS }>,
+            yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+        ) {
+            let table = vars.local_values[TABLE];
+            yield_constr.constraint_first_row(builder, table);
+            let one = builder.one_extension();
+            let diff = builder.sub_extension(vars.next_values[TABLE], table);
+            let constraint = builder.sub_extension(diff, one);
+            yield_constr.constraint_transition(builder, constraint);
+        }
+
+        fn constraint_degree(&self) -> usize {
+            3
+        }
+
+        fn column_usage(&self) -> Vec<bool> {
+            let mut usage = vec![true; Self::COLUMNS];
+            usage[PERMUTED_INPUT] = self.permuted;
+            usage[PERMUTED_TABLE] = self.permuted;
+            usage
+        }
+
+        fn next_row_columns(&self) -> Vec<usize> {
+            vec![TABLE]
+        }
+
+        fn lookups(&self) -> Vec<Lookup> {
+            let mut lookups = vec![Lookup::LogUp {
+                columns: LOOKING.to_vec(),
+                table: TABLE,
+                frequencies: FREQUENCIES,
+            }];
+            if self.permuted {
+                lookups.push(Lookup::Permuted {
+                    input: LOOKING[0],
+                    table: TABLE,
+                    permuted_input: PERMUTED_INPUT,
+                    permuted_table: PERMUTED_TABLE,
+                });
+            }
+            lookups
+        }
+    }
+
+    type S = RangeCheckStark<F, D>;
+
+    #[test]
+    fn test_lookups() -> Result<()> {
+        let config = StarkConfig::standard_fast_config();
+        let stark = S::new(1 << 5);
+        // Three looking columns in batches of two need two helpers and a running sum.
+        assert_eq!(stark.num_lookup_helper_polys_per_challenge(), 3);
+        assert_eq!(
+            stark.num_auxiliary_polys(&config),
+            stark.num_permutation_batches(&config) + 3 * config.num_challenges
+        );
+
+        let trace = stark.generate_trace();
+        let proof = prove::<F, C, S, D>(stark, &config, trace, [], &mut TimingTree::default())?;
+        // Only the table and the permuted columns are opened at the next row.
+        assert_eq!(proof.proof.openings.next_values.len(), 3);
+        verify_stark_proof(stark, proof.clone(), &config)?;
+        verify_recursively(stark, proof, &config)
+    }
+
+    #[test]
+    fn test_unused_columns() -> Result<()> {
+        let config = StarkConfig::standard_fast_config();
+        let stark = S {
+            permuted: false,
+            ..S::new(1 << 5)
+        };
+        let trace = stark.generate_trace();
+        let proof = prove::<F, C, S, D>(stark, &config, trace, [], &mut TimingTree::default())?;
+        // The permuted columns are neither committed to nor opened.
+        let openings = &proof.proof.openings;
+        assert_eq!(openings.local_values.len(), S::COLUMNS - 2);
+        assert_eq!(openings.next_values.len(), 1);
+        let trace_leaf = &proof.proof.opening_proof.query_round_proofs[0]
+            .initial_trees_proof
+            .evals_proofs[0]
+            .0;
+        assert_eq!(trace_leaf.len(), S::COLUMNS - 2);
+        verify_stark_proof(stark, proof.clone(), &config)?;
+        verify_recursively(stark, proof, &config)
+    }
+
+    fn verify_recursively(
+        stark: S,
+        proof: StarkProofWithPublicInputs<F, C, D>,
+        config: &StarkConfig,
+    ) -> Result<()> {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let mut pw = PartialWitness::new();
+        let degree_bits = proof.proof.recover_degree_bits(config);
+        let pt = add_virtual_stark_proof_with_pis(&mut builder, stark, config, degree_bits);
+        set_stark_proof_with_pis_target(&mut pw, &pt, &proof);
+        verify_stark_proof_circuit::<F, C, S, D>(&mut builder, stark, pt, config);
+        let data = builder.build::<C>();
+        data.verify(data.prove(pw)?)
+    }
+
+    #[test]
+    fn test_lookups_wrong_frequencies() -> Result<()> {
+        let config = StarkConfig::standard_fast_config();
+        let stark = S::new(1 << 5);
+        let mut trace = stark.generate_trace();
+        trace[FREQUENCIES].values[0] += F::ONE;
+        // With degree 3 constraints, the quotient is interpolated from exactly as many points as
+        // its degree bound allows, so the prover can't notice, but the verifier does.
+        let proof = prove::<F, C, S, D>(stark, &config, trace, [], &mut TimingTree::default())?;
+        assert!(verify_stark_proof(stark, proof, &config).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_range_check_stark() -> Result<()> {
+        let stark = S::new(1 << 5);
+        test_stark_low_degree(stark)?;
+        test_stark_next_row_columns(stark)?;
+        test_stark_circuit_constraints::<F, C, S, D>(stark)
+    }
+
+    #[test]
+    fn test_logup_frequencies() {
+        let table = [1, 2, 3, 2].map(F::from_canonical_u64);
+        let a = [2, 2, 3, 1].map(F::from_canonical_u64);
+        let b = [3, 3, 3, 2].map(F::from_canonical_u64);
+        let frequencies = logup_frequencies(&[&a, &b], &table);
+        assert_eq!(frequencies, [1, 3, 4, 0].map(F::from_canonical_u64));
+    }
+
+    #[test]
+    #[should_panic(expected = "missing from the table")]
+    fn test_logup_frequencies_missing_value() {
+        let table = [1, 2].map(F::from_canonical_u64);
+        let a = [3, 1].map(F::from_canonical_u64);
+        logup_frequencies(&[&a], &table);
+    }
+}
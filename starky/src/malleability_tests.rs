@@ -0,0 +1,174 @@
+//! Mutates valid STARK proofs in targeted ways and checks that the verifier rejects each of them,
+//! to catch soundness or transcript-binding regressions.
+
+use anyhow::Result;
+use plonky2::field::extension::Extendable;
+use plonky2::field::types::Field;
+use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+use plonky2::util::timing::TimingTree;
+
+use crate::config::StarkConfig;
+use crate::fibonacci_stark::FibonacciStark;
+use crate::proof::StarkProofWithPublicInputs;
+use crate::prover::prove;
+use crate::verifier::verify_stark_proof;
+
+const D: usize = 2;
+type C = PoseidonGoldilocksConfig;
+type F = <C as GenericConfig<D>>::F;
+type FE = <F as Extendable<D>>::Extension;
+type S = FibonacciStark<F, D>;
+type Mutation = fn(&mut StarkProofWithPublicInputs<F, C, D>);
+
+/// A STARK using permutation arguments and an additional opening, so that every part of the proof
+/// is present. The trace is long enough for FRI to have folding steps.
+fn stark() -> S {
+    let mut stark = S::new(1 << 10);
+    stark.extra_window_rows = 1;
+    stark
+}
+
+fn proof(config: &StarkConfig) -> Result<StarkProofWithPublicInputs<F, C, D>> {
+    let stark = stark();
+    let trace = stark.generate_trace(F::ZERO, F::ONE);
+    let result = trace[1].values[trace[1].len() - 1];
+    prove::<F, C, S, D>(
+        stark,
+        config,
+        trace,
+        [F::ZERO, F::ONE, result],
+        &mut TimingTree::default(),
+    )
+}
+
+/// Applies each mutation to a fresh copy of a valid proof, and checks that the result is
+/// rejected.
+fn check_rejected(mutations: &[(&str, Mutation)]) -> Result<()> {
+    let config = StarkConfig::standard_fast_config();
+    let proof = proof(&config)?;
+    verify_stark_proof(stark(), proof.clone(), &config)?;
+    for (name, mutate) in mutations {
+        let mut mutated = proof.clone();
+        mutate(&mut mutated);
+        assert!(
+            verify_stark_proof(stark(), mutated, &config).is_err(),
+            "Proof with mutation {name} was accepted"
+        );
+    }
+    Ok(())
+}
+
+fn bump(x: &mut FE) {
+    *x += FE::ONE;
+}
+
+#[test]
+fn test_flipped_openings() -> Result<()> {
+    check_rejected(&[
+        ("local_values", |p| {
+            bump(&mut p.proof.openings.local_values[0])
+        }),
+        ("next_values", |p| {
+            bump(&mut p.proof.openings.next_values[0])
+        }),
+        ("additional_values", |p| {
+            bump(&mut p.proof.openings.additional_values[0][0])
+        }),
+        ("permutation_zs", |p| {
+            bump(&mut p.proof.openings.permutation_zs.as_mut().unwrap()[0])
+        }),
+        ("permutation_zs_next", |p| {
+            bump(&mut p.proof.openings.permutation_zs_next.as_mut().unwrap()[0])
+        }),
+        ("quotient_polys", |p| {
+            bump(&mut p.proof.openings.quotient_polys[0])
+        }),
+        ("missing permutation_zs", |p| {
+            p.proof.openings.permutation_zs = None
+        }),
+    ])
+}
+
+#[test]
+fn test_altered_commitments() -> Result<()> {
+    check_rejected(&[
+        ("trace_cap", |p| {
+            p.proof.trace_cap.0[0].elements[0] += F::ONE
+        }),
+        ("permutation_zs_cap", |p| {
+            p.proof.permutation_zs_cap.as_mut().unwrap().0[0].elements[0] += F::ONE
+        }),
+        ("quotient_polys_cap", |p| {
+            p.proof.quotient_polys_cap.0[0].elements[0] += F::ONE
+        }),
+        ("commit_phase_merkle_caps", |p| {
+            p.proof.opening_proof.commit_phase_merkle_caps[0].0[0].elements[0] += F::ONE
+        }),
+    ])
+}
+
+#[test]
+fn test_altered_merkle_paths() -> Result<()> {
+    check_rejected(&[
+        ("truncated initial tree path", |p| {
+            let round = &mut p.proof.opening_proof.query_round_proofs[0];
+            round.initial_trees_proof.evals_proofs[0].1.siblings.pop();
+        }),
+        ("truncated initial tree path in a later round", |p| {
+            let round = &mut p.proof.opening_proof.query_round_proofs[1];
+            round.initial_trees_proof.evals_proofs[0].1.siblings.pop();
+        }),
+        ("truncated query step path", |p| {
+            let round = &mut p.proof.opening_proof.query_round_proofs[0];
+            round.steps[0].merkle_proof.siblings.pop();
+        }),
+        ("altered initial tree leaf", |p| {
+            let round = &mut p.proof.opening_proof.query_round_proofs[0];
+            round.initial_trees_proof.evals_proofs[0].0[0] += F::ONE;
+        }),
+        ("altered query step evaluation", |p| {
+            let round = &mut p.proof.opening_proof.query_round_proofs[0];
+            bump(&mut round.steps[0].evals[0]);
+        }),
+    ])
+}
+
+#[test]
+fn test_swapped_query_responses() -> Result<()> {
+    check_rejected(&[
+        ("swapped query rounds", |p| {
+            p.proof.opening_proof.query_round_proofs.swap(0, 1)
+        }),
+        ("duplicated query round", |p| {
+            let rounds = &mut p.proof.opening_proof.query_round_proofs;
+            rounds[1] = rounds[0].clone();
+        }),
+        ("missing query round", |p| {
+            p.proof.opening_proof.query_round_proofs.pop();
+        }),
+    ])
+}
+
+#[test]
+fn test_altered_fri_tail() -> Result<()> {
+    check_rejected(&[
+        ("final_poly", |p| {
+            bump(&mut p.proof.opening_proof.final_poly.coeffs[0])
+        }),
+        ("pow_witness", |p| {
+            p.proof.opening_proof.pow_witness += F::ONE
+        }),
+    ])
+}
+
+#[test]
+fn test_altered_public_inputs() -> Result<()> {
+    check_rejected(&[
+        ("first public input", |p| p.public_inputs[0] += F::ONE),
+        ("last public input", |p| {
+            *p.public_inputs.last_mut().unwrap() += F::ONE
+        }),
+        ("swapped public inputs", |p| p.public_inputs.swap(0, 1)),
+        ("extra public input", |p| p.public_inputs.push(F::ZERO)),
+    ])
+}
@@ -0,0 +1,506 @@
+//! An AIR for large matrix multiplications, whose inputs and outputs can be wired to a plonky2
+//! circuit.
+//!
+//! A product `C = A B` of an `n x k` matrix by a `k x m` matrix is split into output tiles of
+//! `TILE_SIZE x TILE_SIZE` entries, each proven on its own. The tile of `C` at the rows `I` and
+//! columns `J` is the sum over `t` of the outer products of column `t` of `A[I]` and row `t` of
+//! `B[.., J]`. Row `t` of the trace holds these two vectors, along with accumulation columns
+//! holding the sum of the outer products up to it, so the last row holds the output tile, which is
+//! made of public cells. Rows past `k` are zero, which leaves the sum unchanged, so `k` needn't be
+//! a power of two.
+//!
+//! The inputs span the whole trace, so they can't be public cells. Instead, the first public
+//! inputs are a digest of the input tiles, which the transcript absorbs before any challenge is
+//! drawn, and verifying the tile includes checking that the openings of the input columns at
+//! `zeta` are the evaluations at `zeta` of the interpolants of the input tiles.
+//! [`verify_matmul_stark`] does so natively, and [`verify_matmul_stark_circuit`] within a plonky2
+//! circuit, where both the inputs and the output tile are targets which can be wired to the rest
+//! of the circuit. The binding costs a division per row and a multiplication per input entry,
+//! against `TILE_SIZE^2` multiplications per row to compute the tile with gates.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::array;
+use core::borrow::Borrow;
+use core::marker::PhantomData;
+
+use anyhow::{ensure, Result};
+use plonky2::field::extension::{Extendable, FieldExtension};
+use plonky2::field::packed::PackedField;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::field::types::Field;
+use plonky2::hash::hash_types::{RichField, NUM_HASH_OUT_ELTS};
+use plonky2::iop::ext_target::ExtensionTarget;
+use plonky2::iop::target::Target;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::plonk::config::{AlgebraicHasher, GenericConfig, Hasher};
+
+use crate::config::StarkConfig;
+use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use crate::proof::{StarkProofWithPublicInputs, StarkProofWithPublicInputsTarget, StarkTranscript};
+use crate::public_cells::PublicCell;
+use crate::recursive_verifier::verify_stark_proof_with_challenges_circuit;
+use crate::stark::{used_columns, Stark};
+use crate::util::{scatter_columns, trace_rows_to_poly_values};
+use crate::vars::{StarkEvaluationTargets, StarkEvaluationVars};
+use crate::verifier::verify_stark_proof;
+
+/// The number of rows and columns of an output tile.
+pub const TILE_SIZE: usize = 8;
+
+/// The digest of the input tiles, followed by the output tile in row-major order.
+pub const NUM_MATMUL_PUBLIC_INPUTS: usize = NUM_HASH_OUT_ELTS + TILE_SIZE * TILE_SIZE;
+
+const PI_INDEX_OUTPUT: usize = NUM_HASH_OUT_ELTS;
+
+crate::column_layout! {
+    /// The columns of `MatMulStark`.
+    pub struct MatMulColumns<T> {
+        /// Column `t` of the tile of `A`.
+        pub a: [T; TILE_SIZE],
+        /// Row `t` of the tile of `B`.
+        pub b: [T; TILE_SIZE],
+        /// The sum of the outer products of `a` and `b` in rows up to `t`.
+        pub acc: [[T; TILE_SIZE]; TILE_SIZE],
+    }
+    num_columns = NUM_MATMUL_COLUMNS;
+    col_map = MATMUL_COL_MAP;
+}
+
+/// Proves one output tile of a matrix product, see the [module docs](self).
+#[derive(Copy, Clone)]
+pub struct MatMulStark<F: RichField + Extendable<D>, const D: usize> {
+    num_rows: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> MatMulStark<F, D> {
+    /// A STARK for products whose inner dimension is at most `num_rows`, which must be a power of
+    /// two.
+    pub fn new(num_rows: usize) -> Self {
+        assert!(num_rows.is_power_of_two());
+        Self {
+            num_rows,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Computes the product of the tile `a` of `TILE_SIZE` rows of `A` by the tile `b` of
+    /// `TILE_SIZE` columns of `B`, given by its rows, and returns the trace along with the public
+    /// inputs.
+    pub fn generate_trace<H: AlgebraicHasher<F>>(
+        &self,
+        a: &[Vec<F>],
+        b: &[Vec<F>],
+    ) -> (Vec<PolynomialValues<F>>, [F; NUM_MATMUL_PUBLIC_INPUTS]) {
+        let columns = input_columns(a, b);
+        assert!(b.len() <= self.num_rows, "The inner dimension is too large");
+
+        let mut acc = [[F::ZERO; TILE_SIZE]; TILE_SIZE];
+        let rows = (0..self.num_rows)
+            .map(|t| {
+                let entry = |column: &Vec<F>| column.get(t).copied().unwrap_or(F::ZERO);
+                let a_t: [F; TILE_SIZE] = array::from_fn(|i| entry(&columns[i]));
+                let b_t: [F; TILE_SIZE] = array::from_fn(|j| entry(&columns[TILE_SIZE + j]));
+                for (acc_row, &a_ti) in acc.iter_mut().zip(&a_t) {
+                    for (acc_ij, &b_tj) in acc_row.iter_mut().zip(&b_t) {
+                        *acc_ij += a_ti * b_tj;
+                    }
+                }
+                <[F; NUM_MATMUL_COLUMNS]>::from(MatMulColumns {
+                    a: a_t,
+                    b: b_t,
+                    acc,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let digest = H::hash_no_pad(&columns.concat());
+        let mut public_inputs = [F::ZERO; NUM_MATMUL_PUBLIC_INPUTS];
+        public_inputs[..PI_INDEX_OUTPUT].copy_from_slice(&digest.elements);
+        public_inputs[PI_INDEX_OUTPUT..].copy_from_slice(&acc.concat());
+        (trace_rows_to_poly_values(rows), public_inputs)
+    }
+}
+
+/// The inputs of the output tile at the `row`-th tile of rows and `col`-th tile of columns of the
+/// product of `a` by `b`, padded with `zero` where the tile overhangs the matrices.
+pub fn matmul_tile_inputs<T: Copy>(
+    a: &[Vec<T>],
+    b: &[Vec<T>],
+    row: usize,
+    col: usize,
+    zero: T,
+) -> (Vec<Vec<T>>, Vec<Vec<T>>) {
+    let a_tile = (row * TILE_SIZE..(row + 1) * TILE_SIZE)
+        .map(|i| a.get(i).cloned().unwrap_or_else(|| vec![zero; b.len()]))
+        .collect();
+    let b_tile = b
+        .iter()
+        .map(|b_row| {
+            (col * TILE_SIZE..(col + 1) * TILE_SIZE)
+                .map(|j| b_row.get(j).copied().unwrap_or(zero))
+                .collect()
+        })
+        .collect();
+    (a_tile, b_tile)
+}
+
+/// The values of the input columns of the trace, without padding.
+fn input_columns<T: Copy>(a: &[Vec<T>], b: &[Vec<T>]) -> Vec<Vec<T>> {
+    assert_eq!(a.len(), TILE_SIZE);
+    assert!(a.iter().all(|a_row| a_row.len() == b.len()));
+    assert!(b.iter().all(|b_row| b_row.len() == TILE_SIZE));
+    let b_columns = (0..TILE_SIZE).map(|j| b.iter().map(|b_row| b_row[j]).collect());
+    a.iter().cloned().chain(b_columns).collect()
+}
+
+fn input_column_indices() -> impl Iterator<Item = usize> {
+    MATMUL_COL_MAP.a.into_iter().chain(MATMUL_COL_MAP.b)
+}
+
+fn output_tile<T: Copy>(public_inputs: &[T]) -> [[T; TILE_SIZE]; TILE_SIZE] {
+    array::from_fn(|i| array::from_fn(|j| public_inputs[PI_INDEX_OUTPUT + i * TILE_SIZE + j]))
+}
+
+/// Verifies a proof of the product of the tiles `a` and `b`, and returns the output tile.
+pub fn verify_matmul_stark<F, C, const D: usize>(
+    stark: MatMulStark<F, D>,
+    proof_with_pis: StarkProofWithPublicInputs<F, C, D>,
+    config: &StarkConfig,
+    a: &[Vec<F>],
+    b: &[Vec<F>],
+) -> Result<[[F; TILE_SIZE]; TILE_SIZE]>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    C::Hasher: AlgebraicHasher<F>,
+    [(); MatMulStark::<F, D>::COLUMNS]:,
+    [(); MatMulStark::<F, D>::PUBLIC_INPUTS]:,
+{
+    let columns = input_columns(a, b);
+    ensure!(
+        b.len() <= stark.num_rows,
+        "The inner dimension is too large"
+    );
+    verify_stark_proof(stark, proof_with_pis.clone(), config)?;
+    let digest = C::Hasher::hash_no_pad(&columns.concat());
+    ensure!(
+        proof_with_pis.public_inputs[..PI_INDEX_OUTPUT] == digest.elements,
+        "The proof is for other inputs"
+    );
+
+    let degree_bits = proof_with_pis.proof.recover_degree_bits(config);
+    let zeta = proof_with_pis
+        .get_challenges(&stark, config, degree_bits, StarkTranscript::Current)
+        .stark_zeta;
+    let local_values = scatter_columns(
+        &proof_with_pis.proof.openings.local_values,
+        &used_columns(&stark),
+        NUM_MATMUL_COLUMNS,
+        F::Extension::ZERO,
+    );
+    // L_t(zeta) = g^t (zeta^n - 1) / (n (zeta - g^t)).
+    let z_h_over_n = (zeta.exp_power_of_2(degree_bits) - F::Extension::ONE)
+        / F::Extension::from_canonical_usize(stark.num_rows);
+    let lagrange = F::primitive_root_of_unity(degree_bits)
+        .powers()
+        .take(b.len())
+        .map(|g_t| z_h_over_n.scalar_mul(g_t) / (zeta - F::Extension::from_basefield(g_t)))
+        .collect::<Vec<_>>();
+    for (column, index) in columns.iter().zip(input_column_indices()) {
+        let interpolant = column
+            .iter()
+            .zip(&lagrange)
+            .map(|(&v, &l)| l.scalar_mul(v))
+            .sum::<F::Extension>();
+        ensure!(
+            interpolant == local_values[index],
+            "The trace disagrees with the inputs"
+        );
+    }
+
+    Ok(output_tile(&proof_with_pis.public_inputs))
+}
+
+/// Recursively verifies a proof of the product of the tiles `a` and `b`, and returns the output
+/// tile.
+pub fn verify_matmul_stark_circuit<F, C, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    stark: MatMulStark<F, D>,
+    proof_with_pis: StarkProofWithPublicInputsTarget<D>,
+    inner_config: &StarkConfig,
+    a: &[Vec<Target>],
+    b: &[Vec<Target>],
+) -> [[Target; TILE_SIZE]; TILE_SIZE]
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    C::Hasher: AlgebraicHasher<F>,
+    [(); MatMulStark::<F, D>::COLUMNS]:,
+    [(); MatMulStark::<F, D>::PUBLIC_INPUTS]:,
+{
+    assert_eq!(proof_with_pis.public_inputs.len(), NUM_MATMUL_PUBLIC_INPUTS);
+    let columns = input_columns(a, b);
+    assert!(
+        b.len() <= stark.num_rows,
+        "The inner dimension is too large"
+    );
+    let digest = builder.hash_n_to_hash_no_pad::<C::Hasher>(columns.concat());
+    for (&d, &pi) in digest.elements.iter().zip(&proof_with_pis.public_inputs) {
+        builder.connect(d, pi);
+    }
+
+    let degree_bits = proof_with_pis.proof.recover_degree_bits(inner_config);
+    let challenges = proof_with_pis.get_challenges::<F, C, _>(builder, &stark, inner_config);
+    let zeta = challenges.stark_zeta;
+    let zero = builder.zero_extension();
+    let local_values = scatter_columns(
+        &proof_with_pis.proof.openings.local_values,
+        &used_columns(&stark),
+        NUM_MATMUL_COLUMNS,
+        zero,
+    );
+    let zeta_pow_deg = builder.exp_power_of_2_extension(zeta, degree_bits);
+    let z_h = builder.add_const_extension(zeta_pow_deg, F::NEG_ONE);
+    let n_inv = F::from_canonical_usize(stark.num_rows).inverse();
+    let z_h_over_n = builder.mul_const_extension(n_inv, z_h);
+    let lagrange = F::primitive_root_of_unity(degree_bits)
+        .powers()
+        .take(b.len())
+        .map(|g_t| {
+            let numerator = builder.mul_const_extension(g_t, z_h_over_n);
+            let denominator = builder.add_const_extension(zeta, -g_t);
+            builder.div_extension(numerator, denominator)
+        })
+        .collect::<Vec<_>>();
+    for (column, index) in columns.iter().zip(input_column_indices()) {
+        let interpolant = column.iter().zip(&lagrange).fold(zero, |acc, (&v, &l)| {
+            builder.scalar_mul_add_extension(v, l, acc)
+        });
+        builder.connect_extension(interpolant, local_values[index]);
+    }
+
+    let output = output_tile(&proof_with_pis.public_inputs);
+    verify_stark_proof_with_challenges_circuit::<F, C, _, D>(
+        builder,
+        stark,
+        proof_with_pis,
+        challenges,
+        inner_config,
+        degree_bits,
+    );
+    output
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for MatMulStark<F, D> {
+    const COLUMNS: usize = NUM_MATMUL_COLUMNS;
+    const PUBLIC_INPUTS: usize = NUM_MATMUL_PUBLIC_INPUTS;
+
+    fn eval_packed_generic<FE, P, const D2: usize>(
+        &self,
+        vars: StarkEvaluationVars<FE, P, { Self::COLUMNS }, { Self::PUBLIC_INPUTS }>,
+        yield_constr: &mut ConstraintConsumer<P>,
+    ) where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>,
+    {
+        let local: &MatMulColumns<P> = vars.local_values.borrow();
+        let next: &MatMulColumns<P> = vars.next_values.borrow();
+
+        for i in 0..TILE_SIZE {
+            for j in 0..TILE_SIZE {
+                yield_constr.constraint_first_row(local.acc[i][j] - local.a[i] * local.b[j]);
+                yield_constr.constraint_transition(
+                    next.acc[i][j] - local.acc[i][j] - next.a[i] * next.b[j],
+                );
+            }
+        }
+    }
+
+    fn eval_ext_circuit(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: StarkEvaluationTargets<D, { Self::COLUMNS }, { Self::PUBLIC_INPUTS }>,
+        yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+    ) {
+        let local: &MatMulColumns<ExtensionTarget<D>> = vars.local_values.borrow();
+        let next: &MatMulColumns<ExtensionTarget<D>> = vars.next_values.borrow();
+
+        for i in 0..TILE_SIZE {
+            for j in 0..TILE_SIZE {
+                let first = builder.arithmetic_extension(
+                    F::NEG_ONE,
+                    F::ONE,
+                    local.a[i],
+                    local.b[j],
+                    local.acc[i][j],
+                );
+                yield_constr.constraint_first_row(builder, first);
+                let delta = builder.sub_extension(next.acc[i][j], local.acc[i][j]);
+                let transition =
+                    builder.arithmetic_extension(F::NEG_ONE, F::ONE, next.a[i], next.b[j], delta);
+                yield_constr.constraint_transition(builder, transition);
+            }
+        }
+    }
+
+    fn constraint_degree(&self) -> usize {
+        3
+    }
+
+    fn public_cells(&self) -> Vec<PublicCell> {
+        (0..TILE_SIZE)
+            .flat_map(|i| {
+                (0..TILE_SIZE).map(move |j| {
+                    PublicCell::last_row(
+                        MATMUL_COL_MAP.acc[i][j],
+                        PI_INDEX_OUTPUT + i * TILE_SIZE + j,
+                    )
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2::field::types::Sample;
+    use plonky2::iop::witness::{PartialWitness, WitnessWrite};
+    use plonky2::plonk::circuit_data::CircuitConfig;
+    use plonky2::plonk::config::PoseidonGoldilocksConfig;
+    use plonky2::util::timing::TimingTree;
+
+    use super::*;
+    use crate::prover::prove;
+    use crate::recursive_verifier::{
+        add_virtual_stark_proof_with_pis, set_stark_proof_with_pis_target,
+    };
+    use crate::stark_testing::{test_stark_circuit_constraints, test_stark_low_degree};
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+    type H = <C as GenericConfig<D>>::Hasher;
+    type S = MatMulStark<F, D>;
+
+    fn random_matrix(rows: usize, cols: usize) -> Vec<Vec<F>> {
+        (0..rows).map(|_| F::rand_vec(cols)).collect()
+    }
+
+    fn prove_tile(
+        stark: S,
+        config: &StarkConfig,
+        a: &[Vec<F>],
+        b: &[Vec<F>],
+    ) -> Result<StarkProofWithPublicInputs<F, C, D>> {
+        let (trace, public_inputs) = stark.generate_trace::<H>(a, b);
+        prove::<F, C, S, D>(
+            stark,
+            config,
+            trace,
+            public_inputs,
+            &mut TimingTree::default(),
+        )
+    }
+
+    #[test]
+    fn test_matmul_stark() -> Result<()> {
+        let config = StarkConfig::standard_fast_config();
+        let (n, k, m) = (10, 20, 12);
+        let stark = S::new(32);
+        let a = random_matrix(n, k);
+        let b = random_matrix(k, m);
+
+        for row in 0..2 {
+            for col in 0..2 {
+                let (a_tile, b_tile) = matmul_tile_inputs(&a, &b, row, col, F::ZERO);
+                let proof = prove_tile(stark, &config, &a_tile, &b_tile)?;
+                let output = verify_matmul_stark(stark, proof, &config, &a_tile, &b_tile)?;
+                for (i, output_row) in output.iter().enumerate() {
+                    for (j, &c_ij) in output_row.iter().enumerate() {
+                        let (i, j) = (row * TILE_SIZE + i, col * TILE_SIZE + j);
+                        let expected = if i < n && j < m {
+                            (0..k).map(|t| a[i][t] * b[t][j]).sum()
+                        } else {
+                            F::ZERO
+                        };
+                        assert_eq!(c_ij, expected);
+                    }
+                }
+            }
+        }
+
+        // A proof for other inputs is rejected, even with the digest of the claimed inputs.
+        let (a_tile, b_tile) = matmul_tile_inputs(&a, &b, 0, 0, F::ZERO);
+        let mut other_a_tile = a_tile.clone();
+        other_a_tile[3][5] += F::ONE;
+        let proof = prove_tile(stark, &config, &other_a_tile, &b_tile)?;
+        assert!(verify_matmul_stark(stark, proof, &config, &a_tile, &b_tile).is_err());
+        let (trace, mut public_inputs) = stark.generate_trace::<H>(&other_a_tile, &b_tile);
+        let digest = H::hash_no_pad(&input_columns(&a_tile, &b_tile).concat());
+        public_inputs[..PI_INDEX_OUTPUT].copy_from_slice(&digest.elements);
+        let proof = prove::<F, C, S, D>(
+            stark,
+            &config,
+            trace,
+            public_inputs,
+            &mut TimingTree::default(),
+        )?;
+        assert!(verify_matmul_stark(stark, proof, &config, &a_tile, &b_tile).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_matmul_stark_recursive() -> Result<()> {
+        let inner_config = StarkConfig::standard_fast_config();
+        let k = 20;
+        let stark = S::new(32);
+        let a = random_matrix(TILE_SIZE, k);
+        let b = random_matrix(k, TILE_SIZE);
+        let inner_proof = prove_tile(stark, &inner_config, &a, &b)?;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let a_t = (0..TILE_SIZE)
+            .map(|_| builder.add_virtual_targets(k))
+            .collect::<Vec<_>>();
+        let b_t = (0..k)
+            .map(|_| builder.add_virtual_targets(TILE_SIZE))
+            .collect::<Vec<_>>();
+        let degree_bits = inner_proof.proof.recover_degree_bits(&inner_config);
+        let pt = add_virtual_stark_proof_with_pis(&mut builder, stark, &inner_config, degree_bits);
+        let mut pw = PartialWitness::new();
+        set_stark_proof_with_pis_target(&mut pw, &pt, &inner_proof);
+        let output = verify_matmul_stark_circuit::<F, C, D>(
+            &mut builder,
+            stark,
+            pt,
+            &inner_config,
+            &a_t,
+            &b_t,
+        );
+        builder.register_public_inputs(&output.concat());
+        let data = builder.build::<C>();
+
+        for (targets, values) in a_t.iter().chain(&b_t).zip(a.iter().chain(&b)) {
+            pw.set_target_arr(targets, values);
+        }
+        let proof = data.prove(pw)?;
+        assert_eq!(
+            proof.public_inputs,
+            inner_proof.public_inputs[PI_INDEX_OUTPUT..]
+        );
+        data.verify(proof)
+    }
+
+    #[test]
+    fn test_matmul_stark_degree() -> Result<()> {
+        test_stark_low_degree(S::new(1 << 5))
+    }
+
+    #[test]
+    fn test_matmul_stark_circuit() -> Result<()> {
+        test_stark_circuit_constraints::<F, C, S, D>(S::new(1 << 5))
+    }
+}
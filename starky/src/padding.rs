@@ -0,0 +1,304 @@
+//! Padding traces to a power-of-two length.
+//!
+//! A STARK with a `Stark::padding_policy` can be proven with traces of any non-zero length, which
+//! `prove` pads to the next power of two. If it also has a `Stark::is_padding_column`, `prove`
+//! fills that column with zeros on the original rows and ones on the padding rows, and constraints
+//! are added checking that it is boolean, zero on the first row and never decreases. The STARK's
+//! own constraints can then use it to disable themselves on padding rows.
+//!
+//! Padding rows follow the original last row, so public cells on the last row see the last
+//! padding row.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use anyhow::{anyhow, ensure, Result};
+use plonky2::field::extension::{Extendable, FieldExtension};
+use plonky2::field::packed::PackedField;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::field::types::Field;
+use plonky2::hash::hash_types::RichField;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+
+use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use crate::stark::Stark;
+use crate::vars::{StarkEvaluationTargets, StarkEvaluationVars};
+
+/// The rows appended to a trace to bring its length to a power of two.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PaddingPolicy<F: Field> {
+    /// Copies of the last row.
+    PadLastRow,
+    /// Copies of the given row.
+    PadWithRow(Vec<F>),
+    /// Rows of zeros, for STARKs whose constraints are disabled by zero selectors.
+    PadDisabledSelector,
+}
+
+/// Pads `trace` according to `stark`'s padding policy, and fills in its padding column.
+pub(crate) fn pad_trace<F, S, const D: usize>(
+    stark: &S,
+    mut trace: Vec<PolynomialValues<F>>,
+) -> Result<Vec<PolynomialValues<F>>>
+where
+    F: RichField + Extendable<D>,
+    S: Stark<F, D>,
+{
+    ensure!(trace.len() == S::COLUMNS, "Wrong number of trace columns");
+    // A STARK without columns has no trace length either.
+    let len = trace.first().map_or(0, PolynomialValues::len);
+    ensure!(len > 0, "Empty trace");
+    ensure!(
+        trace.iter().all(|column| column.len() == len),
+        "Trace columns have different lengths"
+    );
+
+    let padded_len = len.next_power_of_two();
+    if padded_len != len {
+        let policy = stark.padding_policy().ok_or_else(|| {
+            anyhow!(
+                "Trace length {} isn't a power of two, and the STARK has no padding policy",
+                len
+            )
+        })?;
+        let row = match policy {
+            PaddingPolicy::PadLastRow => {
+                trace.iter().map(|column| column.values[len - 1]).collect()
+            }
+            PaddingPolicy::PadWithRow(row) => {
+                ensure!(row.len() == S::COLUMNS, "Padding row has the wrong length");
+                row
+            }
+            PaddingPolicy::PadDisabledSelector => vec![F::ZERO; S::COLUMNS],
+        };
+        for (column, value) in trace.iter_mut().zip(row) {
+            column.values.resize(padded_len, value);
+        }
+    }
+
+    if let Some(is_padding) = stark.is_padding_column() {
+        trace[is_padding].values = (0..padded_len).map(|i| F::from_bool(i >= len)).collect();
+    }
+    Ok(trace)
+}
+
+pub(crate) fn eval_padding_column<F, FE, P, S, const D: usize, const D2: usize>(
+    stark: &S,
+    vars: StarkEvaluationVars<FE, P, { S::COLUMNS }, { S::PUBLIC_INPUTS }>,
+    consumer: &mut ConstraintConsumer<P>,
+) where
+    F: RichField + Extendable<D>,
+    FE: FieldExtension<D2, BaseField = F>,
+    P: PackedField<Scalar = FE>,
+    S: Stark<F, D>,
+    [(); S::COLUMNS]:,
+    [(); S::PUBLIC_INPUTS]:,
+{
+    if let Some(column) = stark.is_padding_column() {
+        let local = vars.local_values[column];
+        let next = vars.next_values[column];
+        consumer.constraint(local * (local - FE::ONE));
+        consumer.constraint_first_row(local);
+        // Once a row is padding, so are all the following ones.
+        consumer.constraint_transition(local * (next - FE::ONE));
+    }
+}
+
+pub(crate) fn eval_padding_column_circuit<F, S, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    stark: &S,
+    vars: StarkEvaluationTargets<D, { S::COLUMNS }, { S::PUBLIC_INPUTS }>,
+    consumer: &mut RecursiveConstraintConsumer<F, D>,
+) where
+    F: RichField + Extendable<D>,
+    S: Stark<F, D>,
+    [(); S::COLUMNS]:,
+    [(); S::PUBLIC_INPUTS]:,
+{
+    if let Some(column) = stark.is_padding_column() {
+        let local = vars.local_values[column];
+        let next = vars.next_values[column];
+        let boolean = builder.mul_sub_extension(local, local, local);
+        consumer.constraint(builder, boolean);
+        consumer.constraint_first_row(builder, local);
+        let monotonic = builder.mul_sub_extension(local, next, local);
+        consumer.constraint_transition(builder, monotonic);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::plonk::config::PoseidonGoldilocksConfig;
+    use plonky2::util::timing::TimingTree;
+
+    use super::*;
+    use crate::config::StarkConfig;
+    use crate::prover::prove;
+    use crate::stark_testing::{test_stark_circuit_constraints, test_stark_low_degree};
+    use crate::verifier::verify_stark_proof;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = GoldilocksField;
+
+    const COUNTER: usize = 0;
+    const IS_PADDING: usize = 1;
+
+    /// A counter which increments on every row before the padding.
+    #[derive(Clone)]
+    struct CounterStark<F: RichField + Extendable<D>, const D: usize> {
+        policy: Option<PaddingPolicy<F>>,
+    }
+
+    impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for CounterStark<F, D> {
+        const COLUMNS: usize = 2;
+        const PUBLIC_INPUTS: usize = 0;
+
+        fn eval_packed_generic<FE, P, const D2: usize>(
+            &self,
+            vars: StarkEvaluationVars<FE, P, { Self::COLUMNS }, { Self::PUBLIC_INPUTS }>,
+            yield_constr: &mut ConstraintConsumer<P>,
+        ) where
+            FE: FieldExtension<D2, BaseField = F>,
+            P: PackedField<Scalar = FE>,
+        {
+            let counter = vars.local_values[COUNTER];
+            let increment = vars.next_values[COUNTER] - counter - FE::ONE;
+            yield_constr.constraint_first_row(counter);
+            yield_constr
+                .constraint_transition((vars.next_values[IS_PADDING] - FE::ONE) * increment);
+        }
+
+        fn eval_ext_circuit(
+            &self,
+            builder: &mut CircuitBuilder<F, D>,
+            vars: StarkEvaluationTargets<D, { Self::COLUMNS }, { Self::PUBLIC_INPUTS }>,
+            yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+        ) {
+            let counter = vars.local_values[COUNTER];
+            let one = builder.one_extension();
+            let diff = builder.sub_extension(vars.next_values[COUNTER], counter);
+            let increment = builder.sub_extension(diff, one);
+            let constraint =
+                builder.mul_sub_extension(vars.next_values[IS_PADDING], increment, increment);
+            yield_constr.constraint_first_row(builder, counter);
+            yield_constr.constraint_transition(builder, constraint);
+        }
+
+        fn constraint_degree(&self) -> usize {
+            3
+        }
+
+        fn padding_policy(&self) -> Option<PaddingPolicy<F>> {
+            self.policy.clone()
+        }
+
+        fn is_padding_column(&self) -> Option<usize> {
+            Some(IS_PADDING)
+        }
+    }
+
+    fn counter_trace(len: usize) -> Vec<PolynomialValues<F>> {
+        // Not `PolynomialValues::new`, which expects a power-of-two length.
+        vec![
+            PolynomialValues {
+                values: (0..len).map(F::from_canonical_usize).collect(),
+            },
+            PolynomialValues {
+                values: vec![F::ZERO; len],
+            },
+        ]
+    }
+
+    #[test]
+    fn test_pad_trace() -> Result<()> {
+        let stark = |policy| CounterStark::<F, D> {
+            policy: Some(policy),
+        };
+
+        let padded = pad_trace(&stark(PaddingPolicy::PadLastRow), counter_trace(5))?;
+        assert_eq!(padded[COUNTER].values[5..], [F::from_canonical_usize(4); 3]);
+        assert_eq!(
+            padded[IS_PADDING].values[4..],
+            [F::ZERO, F::ONE, F::ONE, F::ONE]
+        );
+
+        let row = vec![F::TWO, F::ZERO];
+        let padded = pad_trace(&stark(PaddingPolicy::PadWithRow(row)), counter_trace(6))?;
+        assert_eq!(padded[COUNTER].values[6..], [F::TWO; 2]);
+
+        let padded = pad_trace(&stark(PaddingPolicy::PadDisabledSelector), counter_trace(3))?;
+        assert_eq!(padded[COUNTER].values[3], F::ZERO);
+        assert_eq!(
+            padded[IS_PADDING].values,
+            [F::ZERO, F::ZERO, F::ZERO, F::ONE]
+        );
+
+        // Powers of two are left as they are, and are required without a padding policy.
+        let unpadded = CounterStark::<F, D> { policy: None };
+        assert_eq!(pad_trace(&unpadded, counter_trace(8))?[COUNTER].len(), 8);
+        assert!(pad_trace(&unpadded, counter_trace(7)).is_err());
+        Ok(())
+    }
+
+    #[derive(Clone)]
+    struct EmptyStark;
+
+    impl Stark<F, D> for EmptyStark {
+        const COLUMNS: usize = 0;
+        const PUBLIC_INPUTS: usize = 0;
+
+        fn eval_packed_generic<FE, P, const D2: usize>(
+            &self,
+            _vars: StarkEvaluationVars<FE, P, { Self::COLUMNS }, { Self::PUBLIC_INPUTS }>,
+            _yield_constr: &mut ConstraintConsumer<P>,
+        ) where
+            FE: FieldExtension<D2, BaseField = F>,
+            P: PackedField<Scalar = FE>,
+        {
+        }
+
+        fn eval_ext_circuit(
+            &self,
+            _builder: &mut CircuitBuilder<F, D>,
+            _vars: StarkEvaluationTargets<D, { Self::COLUMNS }, { Self::PUBLIC_INPUTS }>,
+            _yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+        ) {
+        }
+
+        fn constraint_degree(&self) -> usize {
+            1
+        }
+    }
+
+    #[test]
+    fn test_pad_trace_without_columns() {
+        assert!(pad_trace(&EmptyStark, vec![]).is_err());
+    }
+
+    #[test]
+    fn test_prove_padded_trace() -> Result<()> {
+        let config = StarkConfig::standard_fast_config();
+        for policy in [
+            PaddingPolicy::PadLastRow,
+            PaddingPolicy::PadDisabledSelector,
+        ] {
+            let stark = CounterStark::<F, D> {
+                policy: Some(policy),
+            };
+            let proof = prove::<F, C, _, D>(
+                stark.clone(),
+                &config,
+                counter_trace(21),
+                [],
+                &mut TimingTree::default(),
+            )?;
+            verify_stark_proof(stark, proof, &config)?;
+        }
+
+        let stark = CounterStark::<F, D> { policy: None };
+        test_stark_low_degree(stark.clone())?;
+        test_stark_circuit_constraints::<F, C, _, D>(stark)
+    }
+}
@@ -15,11 +15,15 @@ use plonky2::iop::ext_target::ExtensionTarget;
 use plonky2::iop::target::Target;
 use plonky2::plonk::circuit_builder::CircuitBuilder;
 use plonky2::plonk::config::{AlgebraicHasher, Hasher};
+use plonky2::util::permutation::{
+    grand_product_constraint, grand_product_constraint_circuit, grand_product_z,
+};
 use plonky2::util::reducing::{ReducingFactor, ReducingFactorTarget};
 use plonky2_maybe_rayon::*;
 
 use crate::config::StarkConfig;
 use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use crate::lookup::Lookup;
 use crate::stark::Stark;
 use crate::vars::{StarkEvaluationTargets, StarkEvaluationVars};
 
@@ -41,6 +45,18 @@ impl PermutationPair {
     }
 }
 
+/// The permutation pairs checked for `stark`: its own `permutation_pairs`, followed by those of its
+/// `Lookup::Permuted` lookups.
+pub(crate) fn all_permutation_pairs<F, S, const D: usize>(stark: &S) -> Vec<PermutationPair>
+where
+    F: RichField + Extendable<D>,
+    S: Stark<F, D> + ?Sized,
+{
+    let mut pairs = stark.permutation_pairs();
+    pairs.extend(stark.lookups().iter().flat_map(Lookup::permutation_pairs));
+    pairs
+}
+
 /// A single instance of a permutation check protocol.
 pub(crate) struct PermutationInstance<'a, T: Copy> {
     pub(crate) pair: &'a PermutationPair,
@@ -49,17 +65,17 @@ pub(crate) struct PermutationInstance<'a, T: Copy> {
 
 /// Randomness for a single instance of a permutation check protocol.
 #[derive(Copy, Clone)]
-pub(crate) struct PermutationChallenge<T: Copy> {
+pub struct PermutationChallenge<T: Copy> {
     /// Randomness used to combine multiple columns into one.
-    pub(crate) beta: T,
+    pub beta: T,
     /// Random offset that's added to the beta-reduced column values.
-    pub(crate) gamma: T,
+    pub gamma: T,
 }
 
 /// Like `PermutationChallenge`, but with `num_challenges` copies to boost soundness.
 #[derive(Clone)]
-pub(crate) struct PermutationChallengeSet<T: Copy> {
-    pub(crate) challenges: Vec<PermutationChallenge<T>>,
+pub struct PermutationChallengeSet<T: Copy> {
+    pub challenges: Vec<PermutationChallenge<T>>,
 }
 
 /// Compute all Z polynomials (for permutation arguments).
@@ -73,7 +89,7 @@ where
     F: RichField + Extendable<D>,
     S: Stark<F, D>,
 {
-    let permutation_pairs = stark.permutation_pairs();
+    let permutation_pairs = all_permutation_pairs(stark);
     let permutation_batches = get_permutation_batches(
         &permutation_pairs,
         permutation_challenge_sets,
@@ -101,19 +117,7 @@ fn compute_permutation_z_poly<F: Field>(
     let numerator = poly_product_elementwise(reduced_lhs_polys.into_iter());
     let denominator = poly_product_elementwise(reduced_rhs_polys.into_iter());
 
-    // Compute the quotients.
-    let denominator_inverses = F::batch_multiplicative_inverse(&denominator.values);
-    let mut quotients = numerator.values;
-    batch_multiply_inplace(&mut quotients, &denominator_inverses);
-
-    // Compute Z, which contains partial products of the quotients.
-    let mut partial_products = Vec::with_capacity(degree);
-    let mut acc = F::ONE;
-    for q in quotients {
-        partial_products.push(acc);
-        acc *= q;
-    }
-    PolynomialValues::new(partial_products)
+    PolynomialValues::new(grand_product_z(&numerator.values, &denominator.values))
 }
 
 /// Computes the reduced polynomial, `\sum beta^i f_i(x) + gamma`, for both the "left" and "right"
@@ -279,12 +283,7 @@ pub(crate) fn eval_permutation_checks<F, FE, P, S, const D: usize, const D2: usi
         permutation_challenge_sets,
     } = permutation_data;
 
-    // Check that Z(1) = 1;
-    for &z in &local_zs {
-        consumer.constraint_first_row(z - FE::ONE);
-    }
-
-    let permutation_pairs = stark.permutation_pairs();
+    let permutation_pairs = all_permutation_pairs(stark);
 
     let permutation_batches = get_permutation_batches(
         &permutation_pairs,
@@ -293,6 +292,11 @@ pub(crate) fn eval_permutation_checks<F, FE, P, S, const D: usize, const D2: usi
         stark.permutation_batch_size(),
     );
 
+    // Check that Z(1) = 1. The permutation Zs are followed by any lookup helper polynomials.
+    for &z in &local_zs[..permutation_batches.len()] {
+        consumer.constraint_first_row(z - FE::ONE);
+    }
+
     // Each zs value corresponds to a permutation batch.
     for (i, instances) in permutation_batches.iter().enumerate() {
         // Z(gx) * down = Z x  * up
@@ -314,9 +318,12 @@ pub(crate) fn eval_permutation_checks<F, FE, P, S, const D: usize, const D2: usi
                 )
             })
             .unzip();
-        let constraint = next_zs[i] * reduced_rhs.into_iter().product::<P>()
-            - local_zs[i] * reduced_lhs.into_iter().product::<P>();
-        consumer.constraint(constraint);
+        consumer.constraint(grand_product_constraint(
+            local_zs[i],
+            next_zs[i],
+            reduced_lhs,
+            reduced_rhs,
+        ));
     }
 }
 
@@ -345,14 +352,7 @@ pub(crate) fn eval_permutation_checks_circuit<F, S, const D: usize>(
         permutation_challenge_sets,
     } = permutation_data;
 
-    let one = builder.one_extension();
-    // Check that Z(1) = 1;
-    for &z in &local_zs {
-        let z_1 = builder.sub_extension(z, one);
-        consumer.constraint_first_row(builder, z_1);
-    }
-
-    let permutation_pairs = stark.permutation_pairs();
+    let permutation_pairs = all_permutation_pairs(stark);
 
     let permutation_batches = get_permutation_batches(
         &permutation_pairs,
@@ -361,6 +361,13 @@ pub(crate) fn eval_permutation_checks_circuit<F, S, const D: usize>(
         stark.permutation_batch_size(),
     );
 
+    let one = builder.one_extension();
+    // Check that Z(1) = 1. The permutation Zs are followed by any lookup helper polynomials.
+    for &z in &local_zs[..permutation_batches.len()] {
+        let z_1 = builder.sub_extension(z, one);
+        consumer.constraint_first_row(builder, z_1);
+    }
+
     // Each zs value corresponds to a permutation batch.
     for (i, instances) in permutation_batches.iter().enumerate() {
         let (reduced_lhs, reduced_rhs): (Vec<ExtensionTarget<D>>, Vec<ExtensionTarget<D>>) =
@@ -386,13 +393,13 @@ pub(crate) fn eval_permutation_checks_circuit<F, S, const D: usize>(
                     )
                 })
                 .unzip();
-        let reduced_lhs_product = builder.mul_many_extension(reduced_lhs);
-        let reduced_rhs_product = builder.mul_many_extension(reduced_rhs);
-        // constraint = next_zs[i] * reduced_rhs_product - local_zs[i] * reduced_lhs_product
-        let constraint = {
-            let tmp = builder.mul_extension(local_zs[i], reduced_lhs_product);
-            builder.mul_sub_extension(next_zs[i], reduced_rhs_product, tmp)
-        };
+        let constraint = grand_product_constraint_circuit(
+            builder,
+            local_zs[i],
+            next_zs[i],
+            reduced_lhs,
+            reduced_rhs,
+        );
         consumer.constraint(builder, constraint)
     }
 }
@@ -0,0 +1,355 @@
+//! An AIR whose constraints are given at runtime rather than in code, for prototyping AIRs, e.g.
+//! from the Python bindings.
+//!
+//! Each transition constraint is a polynomial in the values of the current and next rows, given as
+//! a sum of monomials, and boundary constraints are public cells. As `Stark` fixes its numbers of
+//! columns and public inputs as associated constants, `PolynomialStark` has `MAX_COLUMNS` columns
+//! and `MAX_PUBLIC_INPUTS` public inputs, of which it only uses the first `num_columns` and
+//! `num_public_inputs`. Unused columns aren't committed to, see `Stark::column_usage`, and unused
+//! public inputs must be zero.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use anyhow::{ensure, Result};
+use plonky2::field::extension::{Extendable, FieldExtension};
+use plonky2::field::packed::PackedField;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::field::types::Field;
+use plonky2::hash::hash_types::RichField;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+
+use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use crate::public_cells::PublicCell;
+use crate::stark::Stark;
+use crate::vars::{StarkEvaluationTargets, StarkEvaluationVars};
+
+/// The number of columns of a `PolynomialStark`, which may use fewer.
+pub const MAX_COLUMNS: usize = 16;
+
+/// The number of public inputs of a `PolynomialStark`, which may use fewer.
+pub const MAX_PUBLIC_INPUTS: usize = 8;
+
+/// The term `coeff * local[l_0] * ... * local[l_i] * next[n_0] * ... * next[n_j]` of a constraint,
+/// where the `l`s are `local_columns` and the `n`s `next_columns`. Columns may repeat, for powers.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Monomial<F: Field> {
+    pub coeff: F,
+    pub local_columns: Vec<usize>,
+    pub next_columns: Vec<usize>,
+}
+
+impl<F: Field> Monomial<F> {
+    pub fn degree(&self) -> usize {
+        self.local_columns.len() + self.next_columns.len()
+    }
+}
+
+/// An AIR made of polynomial transition constraints and public cells, see the
+/// [module docs](self).
+#[derive(Clone, Debug)]
+pub struct PolynomialStark<F: RichField + Extendable<D>, const D: usize> {
+    num_columns: usize,
+    num_public_inputs: usize,
+    transition_constraints: Vec<Vec<Monomial<F>>>,
+    public_cells: Vec<PublicCell>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> PolynomialStark<F, D> {
+    /// An AIR without constraints, over `num_columns` columns and `num_public_inputs` public
+    /// inputs.
+    pub fn new(num_columns: usize, num_public_inputs: usize) -> Result<Self> {
+        ensure!(
+            (1..=MAX_COLUMNS).contains(&num_columns),
+            "Expected between 1 and {} columns, got {}",
+            MAX_COLUMNS,
+            num_columns
+        );
+        ensure!(
+            num_public_inputs <= MAX_PUBLIC_INPUTS,
+            "Expected at most {} public inputs, got {}",
+            MAX_PUBLIC_INPUTS,
+            num_public_inputs
+        );
+        Ok(Self {
+            num_columns,
+            num_public_inputs,
+            transition_constraints: vec![],
+            public_cells: vec![],
+        })
+    }
+
+    pub fn num_columns(&self) -> usize {
+        self.num_columns
+    }
+
+    pub fn num_public_inputs(&self) -> usize {
+        self.num_public_inputs
+    }
+
+    /// Adds the constraint that the sum of `terms` is zero on every row but the last.
+    pub fn add_transition_constraint(&mut self, terms: Vec<Monomial<F>>) -> Result<()> {
+        for term in &terms {
+            for &column in term.local_columns.iter().chain(&term.next_columns) {
+                self.check_column(column)?;
+            }
+        }
+        self.transition_constraints.push(terms);
+        Ok(())
+    }
+
+    /// Adds the constraint that a cell of the first or last row equals a public input.
+    pub fn add_public_cell(&mut self, cell: PublicCell) -> Result<()> {
+        self.check_column(cell.column)?;
+        ensure!(
+            cell.public_input < self.num_public_inputs,
+            "Public input {} is out of range; this AIR has {} public inputs",
+            cell.public_input,
+            self.num_public_inputs
+        );
+        self.public_cells.push(cell);
+        Ok(())
+    }
+
+    fn check_column(&self, column: usize) -> Result<()> {
+        ensure!(
+            column < self.num_columns,
+            "Column {} is out of range; this AIR has {} columns",
+            column,
+            self.num_columns
+        );
+        Ok(())
+    }
+
+    /// The trace of `MAX_COLUMNS` columns whose first ones are `columns`, and the others zero.
+    pub fn pad_trace(&self, columns: Vec<Vec<F>>) -> Result<Vec<PolynomialValues<F>>> {
+        ensure!(
+            columns.len() == self.num_columns,
+            "Expected {} columns, got {}",
+            self.num_columns,
+            columns.len()
+        );
+        let num_rows = columns[0].len();
+        ensure!(
+            columns.iter().all(|c| c.len() == num_rows),
+            "The columns have different lengths"
+        );
+        let mut trace = columns
+            .into_iter()
+            .map(PolynomialValues::new)
+            .collect::<Vec<_>>();
+        trace.resize(MAX_COLUMNS, PolynomialValues::zero(num_rows));
+        Ok(trace)
+    }
+
+    /// The `MAX_PUBLIC_INPUTS` public inputs whose first ones are `public_inputs`, and the others
+    /// zero.
+    pub fn pad_public_inputs(&self, public_inputs: &[F]) -> Result<[F; MAX_PUBLIC_INPUTS]> {
+        ensure!(
+            public_inputs.len() == self.num_public_inputs,
+            "Expected {} public inputs, got {}",
+            self.num_public_inputs,
+            public_inputs.len()
+        );
+        let mut padded = [F::ZERO; MAX_PUBLIC_INPUTS];
+        padded[..public_inputs.len()].copy_from_slice(public_inputs);
+        Ok(padded)
+    }
+
+    /// Checks that the public inputs past `num_public_inputs` are zero, as `pad_public_inputs`
+    /// makes them.
+    pub fn check_public_inputs_padding(&self, public_inputs: &[F]) -> Result<()> {
+        ensure!(
+            public_inputs.len() == MAX_PUBLIC_INPUTS
+                && public_inputs[self.num_public_inputs..]
+                    .iter()
+                    .all(|x| x.is_zero()),
+            "Unused public inputs must be zero"
+        );
+        Ok(())
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for PolynomialStark<F, D> {
+    const COLUMNS: usize = MAX_COLUMNS;
+    const PUBLIC_INPUTS: usize = MAX_PUBLIC_INPUTS;
+
+    fn eval_packed_generic<FE, P, const D2: usize>(
+        &self,
+        vars: StarkEvaluationVars<FE, P, { Self::COLUMNS }, { Self::PUBLIC_INPUTS }>,
+        yield_constr: &mut ConstraintConsumer<P>,
+    ) where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>,
+    {
+        for terms in &self.transition_constraints {
+            let constraint = terms
+                .iter()
+                .map(|term| {
+                    let locals = term.local_columns.iter().map(|&c| vars.local_values[c]);
+                    let nexts = term.next_columns.iter().map(|&c| vars.next_values[c]);
+                    locals
+                        .chain(nexts)
+                        .fold(P::from(FE::from_basefield(term.coeff)), |acc, x| acc * x)
+                })
+                .sum::<P>();
+            yield_constr.constraint_transition(constraint);
+        }
+    }
+
+    fn eval_ext_circuit(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: StarkEvaluationTargets<D, { Self::COLUMNS }, { Self::PUBLIC_INPUTS }>,
+        yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+    ) {
+        for terms in &self.transition_constraints {
+            let mut constraint = builder.zero_extension();
+            for term in terms {
+                let mut product =
+                    builder.constant_extension(F::Extension::from_basefield(term.coeff));
+                let locals = term.local_columns.iter().map(|&c| vars.local_values[c]);
+                let nexts = term.next_columns.iter().map(|&c| vars.next_values[c]);
+                for x in locals.chain(nexts) {
+                    product = builder.mul_extension(product, x);
+                }
+                constraint = builder.add_extension(constraint, product);
+            }
+            yield_constr.constraint_transition(builder, constraint);
+        }
+    }
+
+    fn constraint_degree(&self) -> usize {
+        // Transition constraints are multiplied by a selector of degree 1.
+        let max_term_degree = self
+            .transition_constraints
+            .iter()
+            .flatten()
+            .map(Monomial::degree)
+            .max()
+            .unwrap_or(0);
+        (max_term_degree + 1).max(2)
+    }
+
+    fn column_usage(&self) -> Vec<bool> {
+        (0..MAX_COLUMNS).map(|c| c < self.num_columns).collect()
+    }
+
+    fn next_row_columns(&self) -> Vec<usize> {
+        let mut columns = self
+            .transition_constraints
+            .iter()
+            .flatten()
+            .flat_map(|term| term.next_columns.iter().copied())
+            .collect::<Vec<_>>();
+        columns.sort_unstable();
+        columns.dedup();
+        columns
+    }
+
+    fn public_cells(&self) -> Vec<PublicCell> {
+        self.public_cells.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2::field::types::Field;
+    use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use plonky2::util::timing::TimingTree;
+
+    use crate::config::StarkConfig;
+    use crate::polynomial_stark::{Monomial, PolynomialStark};
+    use crate::prover::prove;
+    use crate::public_cells::PublicCell;
+    use crate::stark_testing::{test_stark_circuit_constraints, test_stark_low_degree};
+    use crate::verifier::verify_stark_proof;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+    type S = PolynomialStark<F, D>;
+
+    fn term(coeff: F, local_columns: &[usize], next_columns: &[usize]) -> Monomial<F> {
+        Monomial {
+            coeff,
+            local_columns: local_columns.to_vec(),
+            next_columns: next_columns.to_vec(),
+        }
+    }
+
+    /// The Fibonacci AIR, with `x0' = x1` and `x1' = x0 + x1`.
+    fn fibonacci_stark() -> Result<S> {
+        let mut stark = S::new(2, 3)?;
+        stark.add_transition_constraint(vec![
+            term(F::ONE, &[], &[0]),
+            term(F::NEG_ONE, &[1], &[]),
+        ])?;
+        stark.add_transition_constraint(vec![
+            term(F::ONE, &[], &[1]),
+            term(F::NEG_ONE, &[0], &[]),
+            term(F::NEG_ONE, &[1], &[]),
+        ])?;
+        stark.add_public_cell(PublicCell::first_row(0, 0))?;
+        stark.add_public_cell(PublicCell::first_row(1, 1))?;
+        stark.add_public_cell(PublicCell::last_row(1, 2))?;
+        Ok(stark)
+    }
+
+    #[test]
+    fn test_polynomial_stark_fibonacci() -> Result<()> {
+        let config = StarkConfig::standard_fast_config();
+        let stark = fibonacci_stark()?;
+        let num_rows = 1 << 5;
+        let mut x0 = vec![F::ZERO];
+        let mut x1 = vec![F::ONE];
+        for i in 1..num_rows {
+            x0.push(x1[i - 1]);
+            x1.push(x0[i - 1] + x1[i - 1]);
+        }
+        let public_inputs = [F::ZERO, F::ONE, x1[num_rows - 1]];
+        let wrong_public_inputs = [F::ZERO, F::ONE, x1[num_rows - 2]];
+        let trace = stark.pad_trace(vec![x0, x1])?;
+
+        let proof = prove::<F, C, S, D>(
+            stark.clone(),
+            &config,
+            trace.clone(),
+            stark.pad_public_inputs(&public_inputs)?,
+            &mut TimingTree::default(),
+        )?;
+        stark.check_public_inputs_padding(&proof.public_inputs)?;
+        verify_stark_proof(stark.clone(), proof, &config)?;
+
+        // The prover rejects public inputs which disagree with the trace.
+        assert!(prove::<F, C, S, D>(
+            stark.clone(),
+            &config,
+            trace,
+            stark.pad_public_inputs(&wrong_public_inputs)?,
+            &mut TimingTree::default(),
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_polynomial_stark_degree() -> Result<()> {
+        let stark = fibonacci_stark()?;
+        test_stark_low_degree(stark.clone())?;
+        test_stark_circuit_constraints::<F, C, S, D>(stark)
+    }
+
+    #[test]
+    fn test_polynomial_stark_out_of_range() -> Result<()> {
+        let mut stark = S::new(2, 1)?;
+        assert!(stark
+            .add_transition_constraint(vec![term(F::ONE, &[2], &[])])
+            .is_err());
+        assert!(stark.add_public_cell(PublicCell::first_row(0, 1)).is_err());
+        assert!(S::new(0, 0).is_err());
+        assert!(S::new(2, 9).is_err());
+        Ok(())
+    }
+}
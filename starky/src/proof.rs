@@ -1,6 +1,7 @@
 use alloc::vec;
 use alloc::vec::Vec;
 
+use anyhow::{anyhow, ensure, Result};
 use itertools::Itertools;
 use plonky2::field::extension::{Extendable, FieldExtension};
 use plonky2::fri::oracle::PolynomialBatch;
@@ -15,16 +16,18 @@ use plonky2::hash::merkle_tree::MerkleCap;
 use plonky2::iop::ext_target::ExtensionTarget;
 use plonky2::iop::target::Target;
 use plonky2::plonk::config::GenericConfig;
+use plonky2::util::serialization::{Buffer, IoError, IoResult, Read, Remaining, Write};
 use plonky2_maybe_rayon::*;
 
 use crate::config::StarkConfig;
 use crate::permutation::PermutationChallengeSet;
+use crate::stark::{opened_next_row_columns, used_columns, Stark};
 
 #[derive(Debug, Clone)]
 pub struct StarkProof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> {
     /// Merkle cap of LDEs of trace values.
     pub trace_cap: MerkleCap<F, C::Hasher>,
-    /// Merkle cap of LDEs of permutation Z values.
+    /// Merkle cap of LDEs of permutation Z values, followed by any LogUp helper values.
     pub permutation_zs_cap: Option<MerkleCap<F, C::Hasher>>,
     /// Merkle cap of LDEs of trace values.
     pub quotient_polys_cap: MerkleCap<F, C::Hasher>,
@@ -41,8 +44,10 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> S
             .initial_trees_proof
             .evals_proofs[0]
             .1;
-        let lde_bits = config.fri_config.cap_height + initial_merkle_proof.siblings.len();
-        lde_bits - config.fri_config.rate_bits
+        let lde_bits = config.fri_config.cap_height
+            + initial_merkle_proof.siblings.len()
+            + config.leaf_group_bits;
+        lde_bits - config.trace_rate_bits()
     }
 }
 
@@ -61,11 +66,26 @@ impl<const D: usize> StarkProofTarget<D> {
             .initial_trees_proof
             .evals_proofs[0]
             .1;
-        let lde_bits = config.fri_config.cap_height + initial_merkle_proof.siblings.len();
-        lde_bits - config.fri_config.rate_bits
+        let lde_bits = config.fri_config.cap_height
+            + initial_merkle_proof.siblings.len()
+            + config.leaf_group_bits;
+        lde_bits - config.trace_rate_bits()
     }
 }
 
+/// The Fiat-Shamir transcripts from which STARK proofs' challenges are derived. Proofs have the
+/// same layout under each, so a proof made by an older prover decodes as usual.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum StarkTranscript {
+    /// The transcript of starky 0.1.2, which starts with the trace cap. Its challenges depend on
+    /// neither the STARK, the config nor the public inputs, so verifiers should only accept such
+    /// proofs while provers are being upgraded.
+    Legacy,
+    /// The transcript of `prove`, which starts with `TRANSCRIPT_VERSION`, a digest of the STARK and
+    /// config, and the public inputs.
+    Current,
+}
+
 #[derive(Debug, Clone)]
 pub struct StarkProofWithPublicInputs<
     F: RichField + Extendable<D>,
@@ -77,6 +97,122 @@ pub struct StarkProofWithPublicInputs<
     pub public_inputs: Vec<F>,
 }
 
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    StarkProofWithPublicInputs<F, C, D>
+{
+    /// Serializes the proof. Only the trace's `degree_bits` is written explicitly; every other
+    /// length is determined by it, the STARK and the config.
+    pub fn to_bytes(&self, config: &StarkConfig) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        self.write(&mut buffer, config)
+            .expect("Writing to a byte-vector cannot fail.");
+        buffer
+    }
+
+    fn write(&self, buffer: &mut Vec<u8>, config: &StarkConfig) -> IoResult<()> {
+        let proof = &self.proof;
+        buffer.write_usize(proof.recover_degree_bits(config))?;
+        buffer.write_merkle_cap(&proof.trace_cap)?;
+        if let Some(cap) = &proof.permutation_zs_cap {
+            buffer.write_merkle_cap(cap)?;
+        }
+        buffer.write_merkle_cap(&proof.quotient_polys_cap)?;
+
+        let openings = &proof.openings;
+        buffer.write_field_ext_vec::<F, D>(&openings.local_values)?;
+        buffer.write_field_ext_vec::<F, D>(&openings.next_values)?;
+        for values in &openings.additional_values {
+            buffer.write_field_ext_vec::<F, D>(values)?;
+        }
+        for zs in [&openings.permutation_zs, &openings.permutation_zs_next]
+            .into_iter()
+            .flatten()
+        {
+            buffer.write_field_ext_vec::<F, D>(zs)?;
+        }
+        buffer.write_field_ext_vec::<F, D>(&openings.quotient_polys)?;
+
+        buffer.write_fri_proof::<F, C, D>(&proof.opening_proof)?;
+        buffer.write_field_vec(&self.public_inputs)
+    }
+
+    /// Deserializes a proof of `stark` written by [`Self::to_bytes`]. All lengths are checked
+    /// against those expected for `stark` and `config`, so malformed input gives an error rather
+    /// than a panic, here or in the verifier.
+    pub fn from_bytes<S: Stark<F, D>>(
+        bytes: &[u8],
+        stark: &S,
+        config: &StarkConfig,
+    ) -> Result<Self> {
+        let mut buffer = Buffer::new(bytes);
+        let proof =
+            Self::read(&mut buffer, stark, config).map_err(|_| anyhow!("Invalid STARK proof"))?;
+        ensure!(buffer.is_empty(), "Trailing bytes after STARK proof");
+        Ok(proof)
+    }
+
+    fn read<S: Stark<F, D>>(
+        buffer: &mut Buffer<'_>,
+        stark: &S,
+        config: &StarkConfig,
+    ) -> IoResult<Self> {
+        let degree_bits = buffer.read_usize()?;
+        if degree_bits.saturating_add(config.trace_rate_bits()) > F::TWO_ADICITY {
+            return Err(IoError);
+        }
+        let fri_params = config.fri_params(degree_bits);
+        let cap_height = fri_params.config.cap_height;
+        let uses_auxiliary_polys = stark.uses_auxiliary_polys();
+        let num_zs = stark.num_auxiliary_polys(config);
+        let num_quotient_polys = stark.num_quotient_polys(config);
+
+        let trace_cap = buffer.read_merkle_cap(cap_height)?;
+        let permutation_zs_cap = uses_auxiliary_polys
+            .then(|| buffer.read_merkle_cap(cap_height))
+            .transpose()?;
+        let quotient_polys_cap = buffer.read_merkle_cap(cap_height)?;
+
+        let num_trace_polys = used_columns(stark).len();
+        let local_values = buffer.read_field_ext_vec::<F, D>(num_trace_polys)?;
+        let next_values =
+            buffer.read_field_ext_vec::<F, D>(opened_next_row_columns(stark).len())?;
+        let additional_values = stark
+            .additional_opening_rows()
+            .iter()
+            .map(|_| buffer.read_field_ext_vec::<F, D>(num_trace_polys))
+            .collect::<IoResult<Vec<_>>>()?;
+        let permutation_zs = uses_auxiliary_polys
+            .then(|| buffer.read_field_ext_vec::<F, D>(num_zs))
+            .transpose()?;
+        let permutation_zs_next = uses_auxiliary_polys
+            .then(|| buffer.read_field_ext_vec::<F, D>(num_zs))
+            .transpose()?;
+        let quotient_polys = buffer.read_field_ext_vec::<F, D>(num_quotient_polys)?;
+
+        let opening_proof = buffer
+            .read_fri_proof_for_oracle_infos::<F, C, D>(&stark.fri_oracles(config), &fri_params)?;
+        let public_inputs = buffer.read_field_vec(S::PUBLIC_INPUTS)?;
+
+        Ok(Self {
+            proof: StarkProof {
+                trace_cap,
+                permutation_zs_cap,
+                quotient_polys_cap,
+                openings: StarkOpeningSet {
+                    local_values,
+                    next_values,
+                    additional_values,
+                    permutation_zs,
+                    permutation_zs_next,
+                    quotient_polys,
+                },
+                opening_proof,
+            },
+            public_inputs,
+        })
+    }
+}
+
 pub struct StarkProofWithPublicInputsTarget<const D: usize> {
     pub proof: StarkProofTarget<D>,
     pub public_inputs: Vec<Target>,
@@ -127,17 +263,27 @@ pub(crate) struct StarkProofChallengesTarget<const D: usize> {
 /// Purported values of each polynomial at the challenge point.
 #[derive(Debug, Clone)]
 pub struct StarkOpeningSet<F: RichField + Extendable<D>, const D: usize> {
+    /// Values of the columns given by `Stark::column_usage` at `zeta`.
     pub local_values: Vec<F::Extension>,
+    /// Values of the used columns given by `Stark::next_row_columns` at `g * zeta`.
     pub next_values: Vec<F::Extension>,
+    /// Values of the used columns at `g^k * zeta`, for each row offset `k` given by
+    /// `Stark::additional_opening_rows`.
+    pub additional_values: Vec<Vec<F::Extension>>,
     pub permutation_zs: Option<Vec<F::Extension>>,
     pub permutation_zs_next: Option<Vec<F::Extension>>,
     pub quotient_polys: Vec<F::Extension>,
 }
 
 impl<F: RichField + Extendable<D>, const D: usize> StarkOpeningSet<F, D> {
+    /// Opens the given commitments at `zeta` and `g * zeta`, and the trace commitment additionally
+    /// at `s * zeta` for each `s` in `additional_shifts`. Only the trace polynomials with indices
+    /// in `next_row_polys` are opened at `g * zeta`.
     pub fn new<C: GenericConfig<D, F = F>>(
         zeta: F::Extension,
         g: F,
+        next_row_polys: &[usize],
+        additional_shifts: &[F],
         trace_commitment: &PolynomialBatch<F, C, D>,
         permutation_zs_commitment: Option<&PolynomialBatch<F, C, D>>,
         quotient_commitment: &PolynomialBatch<F, C, D>,
@@ -151,7 +297,18 @@ impl<F: RichField + Extendable<D>, const D: usize> StarkOpeningSet<F, D> {
         let zeta_next = zeta.scalar_mul(g);
         Self {
             local_values: eval_commitment(zeta, trace_commitment),
-            next_values: eval_commitment(zeta_next, trace_commitment),
+            next_values: next_row_polys
+                .par_iter()
+                .map(|&i| {
+                    trace_commitment.polynomials[i]
+                        .to_extension()
+                        .eval(zeta_next)
+                })
+                .collect(),
+            additional_values: additional_shifts
+                .iter()
+                .map(|&shift| eval_commitment(zeta.scalar_mul(shift), trace_commitment))
+                .collect(),
             permutation_zs: permutation_zs_commitment.map(|c| eval_commitment(zeta, c)),
             permutation_zs_next: permutation_zs_commitment.map(|c| eval_commitment(zeta_next, c)),
             quotient_polys: eval_commitment(zeta, quotient_commitment),
@@ -176,15 +333,18 @@ impl<F: RichField + Extendable<D>, const D: usize> StarkOpeningSet<F, D> {
                 .copied()
                 .collect_vec(),
         };
-        FriOpenings {
-            batches: vec![zeta_batch, zeta_next_batch],
-        }
+        let mut batches = vec![zeta_batch, zeta_next_batch];
+        batches.extend(self.additional_values.iter().map(|values| FriOpeningBatch {
+            values: values.clone(),
+        }));
+        FriOpenings { batches }
     }
 }
 
 pub struct StarkOpeningSetTarget<const D: usize> {
     pub local_values: Vec<ExtensionTarget<D>>,
     pub next_values: Vec<ExtensionTarget<D>>,
+    pub additional_values: Vec<Vec<ExtensionTarget<D>>>,
     pub permutation_zs: Option<Vec<ExtensionTarget<D>>>,
     pub permutation_zs_next: Option<Vec<ExtensionTarget<D>>>,
     pub quotient_polys: Vec<ExtensionTarget<D>>,
@@ -209,8 +369,14 @@ impl<const D: usize> StarkOpeningSetTarget<D> {
                 .copied()
                 .collect_vec(),
         };
-        FriOpeningsTarget {
-            batches: vec![zeta_batch, zeta_next_batch],
-        }
+        let mut batches = vec![zeta_batch, zeta_next_batch];
+        batches.extend(
+            self.additional_values
+                .iter()
+                .map(|values| FriOpeningBatchTarget {
+                    values: values.clone(),
+                }),
+        );
+        FriOpeningsTarget { batches }
     }
 }
@@ -1,8 +1,10 @@
 use alloc::vec::Vec;
 use core::iter::once;
+use core::ops::Range;
 
 use anyhow::{ensure, Result};
 use itertools::Itertools;
+use log::Level;
 use plonky2::field::extension::Extendable;
 use plonky2::field::packable::Packable;
 use plonky2::field::packed::PackedField;
@@ -14,18 +16,24 @@ use plonky2::hash::hash_types::RichField;
 use plonky2::iop::challenger::Challenger;
 use plonky2::plonk::config::GenericConfig;
 use plonky2::timed;
-use plonky2::util::timing::TimingTree;
+use plonky2::util::timing::{ParallelTimings, TimingTree};
 use plonky2::util::{log2_ceil, log2_strict, transpose};
 use plonky2_maybe_rayon::*;
 
+use crate::checkpoint::ProverCheckpoint;
 use crate::config::StarkConfig;
 use crate::constraint_consumer::ConstraintConsumer;
+use crate::get_challenges::domain_separator_digest;
+use crate::lookup::{compute_lookup_helper_polys, Lookup};
+use crate::padding::pad_trace;
 use crate::permutation::{
-    compute_permutation_z_polys, get_n_permutation_challenge_sets, PermutationChallengeSet,
-    PermutationCheckVars,
+    all_permutation_pairs, compute_permutation_z_polys, get_n_permutation_challenge_sets,
+    PermutationChallengeSet, PermutationCheckVars,
 };
 use crate::proof::{StarkOpeningSet, StarkProof, StarkProofWithPublicInputs};
-use crate::stark::Stark;
+use crate::public_cells::mismatched_public_cells;
+use crate::stark::{opened_next_row_polys, used_columns, Stark};
+use crate::util::scatter_columns;
 use crate::vanishing_poly::eval_vanishing_poly;
 use crate::vars::StarkEvaluationVars;
 
@@ -43,67 +51,177 @@ where
     [(); S::COLUMNS]:,
     [(); S::PUBLIC_INPUTS]:,
 {
+    prove_with_checkpoints(
+        stark,
+        config,
+        trace_poly_values,
+        public_inputs,
+        None,
+        &mut |_| Ok(()),
+        timing,
+    )
+}
+
+/// Like [`prove`], but calls `on_checkpoint` with a snapshot of the prover state after the trace
+/// (and permutation) commitments, and again after the quotient commitment. Passing one of these
+/// snapshots back as `resume_from` skips the phases it covers. When resuming, `trace_poly_values`
+/// and `public_inputs` must be the same as in the interrupted run; this is not checked.
+pub fn prove_with_checkpoints<F, C, S, const D: usize>(
+    stark: S,
+    config: &StarkConfig,
+    trace_poly_values: Vec<PolynomialValues<F>>,
+    public_inputs: [F; S::PUBLIC_INPUTS],
+    resume_from: Option<ProverCheckpoint<F, C, D>>,
+    on_checkpoint: &mut dyn FnMut(&ProverCheckpoint<F, C, D>) -> Result<()>,
+    timing: &mut TimingTree,
+) -> Result<StarkProofWithPublicInputs<F, C, D>>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    S: Stark<F, D>,
+    [(); S::COLUMNS]:,
+    [(); S::PUBLIC_INPUTS]:,
+{
+    config.validate(&stark)?;
+    let trace_poly_values = pad_trace(&stark, trace_poly_values)?;
     let degree = trace_poly_values[0].len();
     let degree_bits = log2_strict(degree);
+    config.fri_config.validate_for_degree_bits(degree_bits)?;
     let fri_params = config.fri_params(degree_bits);
     let rate_bits = config.fri_config.rate_bits;
     let cap_height = config.fri_config.cap_height;
+
+    let used_columns = used_columns(&stark);
+    #[cfg(feature = "tracing")]
+    let _span = tracing::info_span!(
+        "prove",
+        degree_bits,
+        num_columns = S::COLUMNS,
+        num_used_columns = used_columns.len(),
+        num_public_inputs = S::PUBLIC_INPUTS,
+        num_challenges = config.num_challenges,
+        rate_bits,
+        cap_height,
+        num_query_rounds = config.fri_config.num_query_rounds,
+        proof_of_work_bits = config.fri_config.proof_of_work_bits,
+        salted_commitments = config.salted_commitments,
+        leaf_group_bits = config.leaf_group_bits,
+    )
+    .entered();
+    let referenced_columns = all_permutation_pairs(&stark)
+        .into_iter()
+        .flat_map(|pair| pair.column_pairs)
+        .flat_map(|(lhs, rhs)| [lhs, rhs])
+        .chain(stark.lookups().into_iter().flat_map(|lookup| match lookup {
+            Lookup::Permuted { .. } => vec![],
+            Lookup::LogUp {
+                mut columns,
+                table,
+                frequencies,
+            } => {
+                columns.extend([table, frequencies]);
+                columns
+            }
+        }))
+        .chain(stark.public_cells().into_iter().map(|cell| cell.column))
+        .chain(stark.is_padding_column())
+        .collect::<Vec<_>>();
     assert!(
-        fri_params.total_arities() <= degree_bits + rate_bits - cap_height,
-        "FRI total reduction arity is too large.",
+        referenced_columns
+            .iter()
+            .all(|i| used_columns.binary_search(i).is_ok()),
+        "Permutation pairs, lookups, public cells and the padding column must only use columns in \
+         `Stark::column_usage`"
     );
-
-    let trace_commitment = timed!(
-        timing,
-        "compute trace commitment",
-        PolynomialBatch::<F, C, D>::from_values(
-            // TODO: Cloning this isn't great; consider having `from_values` accept a reference,
-            // or having `compute_permutation_z_polys` read trace values from the `PolynomialBatch`.
-            trace_poly_values.clone(),
-            rate_bits,
-            false,
-            cap_height,
-            timing,
-            None,
-        )
+    let mismatched_cells =
+        mismatched_public_cells(&stark.public_cells(), &trace_poly_values, &public_inputs);
+    ensure!(
+        mismatched_cells.is_empty(),
+        "Public inputs disagree with the trace at {:?}",
+        mismatched_cells
     );
 
-    let trace_cap = trace_commitment.merkle_tree.cap.clone();
-    let mut challenger = Challenger::new();
+    let resumed = resume_from.is_some();
+    let mut checkpoint = match resume_from {
+        Some(checkpoint) => {
+            checkpoint.check_compatible(&stark, config, degree_bits)?;
+            checkpoint
+        }
+        None => ProverCheckpoint {
+            degree_bits,
+            trace_commitment: timed!(
+                timing,
+                "compute trace commitment",
+                PolynomialBatch::<F, C, D>::from_values_grouped(
+                    // TODO: Cloning this isn't great; consider having `from_values` accept a reference,
+                    // or having `compute_permutation_z_polys` read trace values from the `PolynomialBatch`.
+                    used_columns
+                        .iter()
+                        .map(|&i| trace_poly_values[i].clone())
+                        .collect(),
+                    config.trace_rate_bits(),
+                    config.salted_commitments,
+                    cap_height,
+                    config.leaf_group_bits,
+                    timing,
+                    None,
+                )
+            ),
+            permutation_zs_commitment: None,
+            quotient_commitment: None,
+        },
+    };
+
+    let trace_cap = checkpoint.trace_commitment.merkle_tree.cap.clone();
+    let mut challenger = Challenger::<F, C::Hasher>::new();
+    challenger.observe_domain_separator::<C::Hasher>(
+        domain_separator_digest::<F, C::Hasher, S, D>(&stark, config, degree_bits),
+    );
+    challenger.observe_elements(&public_inputs);
     challenger.observe_cap(&trace_cap);
 
-    // Permutation arguments.
-    let permutation_zs_commitment_challenges = stark.uses_permutation_args().then(|| {
-        let permutation_challenge_sets = get_n_permutation_challenge_sets(
+    // Permutation arguments and LogUp lookups. The challenges are derived from the transcript, so
+    // they are recomputed rather than stored when resuming.
+    let permutation_challenge_sets = stark.uses_auxiliary_polys().then(|| {
+        get_n_permutation_challenge_sets(
             &mut challenger,
             config.num_challenges,
             stark.permutation_batch_size(),
-        );
-        let permutation_z_polys = compute_permutation_z_polys::<F, S, D>(
-            &stark,
-            config,
-            &trace_poly_values,
-            &permutation_challenge_sets,
-        );
-
-        let permutation_zs_commitment = timed!(
-            timing,
-            "compute permutation Z commitments",
-            PolynomialBatch::from_values(
-                permutation_z_polys,
-                rate_bits,
-                false,
-                config.fri_config.cap_height,
-                timing,
-                None,
-            )
-        );
-        (permutation_zs_commitment, permutation_challenge_sets)
+        )
     });
-    let permutation_zs_commitment = permutation_zs_commitment_challenges
-        .as_ref()
-        .map(|(comm, _)| comm);
-    let permutation_zs_cap = permutation_zs_commitment
+    if let Some(permutation_challenge_sets) = &permutation_challenge_sets {
+        if checkpoint.permutation_zs_commitment.is_none() {
+            let mut permutation_z_polys = compute_permutation_z_polys::<F, S, D>(
+                &stark,
+                config,
+                &trace_poly_values,
+                permutation_challenge_sets,
+            );
+            permutation_z_polys.extend(compute_lookup_helper_polys::<F, S, D>(
+                &stark,
+                &trace_poly_values,
+                permutation_challenge_sets,
+            ));
+            checkpoint.permutation_zs_commitment = Some(timed!(
+                timing,
+                "compute permutation Z commitments",
+                PolynomialBatch::from_values_grouped(
+                    permutation_z_polys,
+                    config.trace_rate_bits(),
+                    config.salted_commitments,
+                    config.fri_config.cap_height,
+                    config.leaf_group_bits,
+                    timing,
+                    None,
+                )
+            ));
+        }
+    }
+    if !resumed {
+        on_checkpoint(&checkpoint)?;
+    }
+    let permutation_zs_cap = checkpoint
+        .permutation_zs_commitment
         .as_ref()
         .map(|commit| commit.merkle_tree.cap.clone());
     if let Some(cap) = &permutation_zs_cap {
@@ -111,37 +229,58 @@ where
     }
 
     let alphas = challenger.get_n_challenges(config.num_challenges);
-    let quotient_polys = compute_quotient_polys::<F, <F as Packable>::Packing, C, S, D>(
-        &stark,
-        &trace_commitment,
-        &permutation_zs_commitment_challenges,
-        public_inputs,
-        alphas,
-        degree_bits,
-        config,
-    );
-    let all_quotient_chunks = quotient_polys
-        .into_par_iter()
-        .flat_map(|mut quotient_poly| {
-            quotient_poly
-                .trim_to_len(degree * stark.quotient_degree_factor())
-                .expect("Quotient has failed, the vanishing polynomial is not divisible by Z_H");
-            // Split quotient into degree-n chunks.
-            quotient_poly.chunks(degree)
-        })
-        .collect();
-    let quotient_commitment = timed!(
-        timing,
-        "compute quotient commitment",
-        PolynomialBatch::from_coeffs(
-            all_quotient_chunks,
-            rate_bits,
-            false,
-            config.fri_config.cap_height,
+    if checkpoint.quotient_commitment.is_none() {
+        let quotient_polys = timed!(
             timing,
-            None,
-        )
-    );
+            "compute quotient polys",
+            compute_quotient_polys::<F, <F as Packable>::Packing, C, S, D>(
+                &stark,
+                &checkpoint.trace_commitment,
+                checkpoint
+                    .permutation_zs_commitment
+                    .as_ref()
+                    .zip(permutation_challenge_sets.as_deref()),
+                public_inputs,
+                alphas,
+                degree_bits,
+                config,
+                timing,
+            )
+        );
+        let all_quotient_chunks = quotient_polys
+            .into_par_iter()
+            .flat_map(|mut quotient_poly| {
+                quotient_poly
+                    .trim_to_len(degree * stark.quotient_degree_factor())
+                    .expect(
+                        "Quotient has failed, the vanishing polynomial is not divisible by Z_H",
+                    );
+                // Split quotient into degree-n chunks.
+                quotient_poly.chunks(degree)
+            })
+            .collect();
+        checkpoint.quotient_commitment = Some(timed!(
+            timing,
+            "compute quotient commitment",
+            PolynomialBatch::from_coeffs_grouped(
+                all_quotient_chunks,
+                rate_bits,
+                config.salted_commitments,
+                config.fri_config.cap_height,
+                config.leaf_group_bits,
+                timing,
+                None,
+            )
+        ));
+        on_checkpoint(&checkpoint)?;
+    }
+    let ProverCheckpoint {
+        trace_commitment,
+        permutation_zs_commitment,
+        quotient_commitment,
+        ..
+    } = checkpoint;
+    let quotient_commitment = quotient_commitment.expect("Set above");
     let quotient_polys_cap = quotient_commitment.merkle_tree.cap.clone();
     challenger.observe_cap(&quotient_polys_cap);
 
@@ -157,14 +296,16 @@ where
     let openings = StarkOpeningSet::new(
         zeta,
         g,
+        &opened_next_row_polys(&stark),
+        &stark.additional_opening_shifts(g),
         &trace_commitment,
-        permutation_zs_commitment,
+        permutation_zs_commitment.as_ref(),
         &quotient_commitment,
     );
     challenger.observe_openings(&openings.to_fri_openings());
 
     let initial_merkle_trees = once(&trace_commitment)
-        .chain(permutation_zs_commitment)
+        .chain(permutation_zs_commitment.as_ref())
         .chain(once(&quotient_commitment))
         .collect_vec();
 
@@ -187,25 +328,33 @@ where
         opening_proof,
     };
 
-    Ok(StarkProofWithPublicInputs {
+    let proof = StarkProofWithPublicInputs {
         proof,
         public_inputs: public_inputs.to_vec(),
-    })
+    };
+    #[cfg(feature = "metrics")]
+    plonky2::util::metrics::record_proof("starky", || proof.to_bytes(config).len());
+    Ok(proof)
 }
 
 /// Computes the quotient polynomials `(sum alpha^i C_i(x)) / Z_H(x)` for `alpha` in `alphas`,
 /// where the `C_i`s are the Stark constraints.
-fn compute_quotient_polys<'a, F, P, C, S, const D: usize>(
+///
+/// The quotient domain is the union of `2^quotient_degree_bits` cosets of the trace domain. The
+/// constraints are evaluated on one coset at a time, and each coset's values are interpolated
+/// right away, so that only a trace domain's worth of quotient values is held at once.
+pub(crate) fn compute_quotient_polys<'a, F, P, C, S, const D: usize>(
     stark: &S,
     trace_commitment: &'a PolynomialBatch<F, C, D>,
-    permutation_zs_commitment_challenges: &'a Option<(
-        PolynomialBatch<F, C, D>,
-        Vec<PermutationChallengeSet<F>>,
+    permutation_zs_commitment_challenges: Option<(
+        &'a PolynomialBatch<F, C, D>,
+        &'a [PermutationChallengeSet<F>],
     )>,
     public_inputs: [F; S::PUBLIC_INPUTS],
     alphas: Vec<F>,
     degree_bits: usize,
     config: &StarkConfig,
+    timing: &mut TimingTree,
 ) -> Vec<PolynomialCoeffs<F>>
 where
     F: RichField + Extendable<D>,
@@ -216,16 +365,135 @@ where
     [(); S::PUBLIC_INPUTS]:,
 {
     let degree = 1 << degree_bits;
-    let rate_bits = config.fri_config.rate_bits;
+    let quotient_degree_bits = log2_ceil(stark.quotient_degree_factor());
+    let num_cosets = 1 << quotient_degree_bits;
+    let n = F::from_canonical_usize(degree);
+    let subgroup_generator = F::primitive_root_of_unity(degree_bits);
+    let generator = F::primitive_root_of_unity(degree_bits + quotient_degree_bits);
+    // Last element of the subgroup.
+    let last = subgroup_generator.inverse();
+    let z_h_on_coset = ZeroPolyOnCoset::<F>::new(degree_bits, quotient_degree_bits);
+
+    // Write each quotient as `Q(x) = sum_j x^(j n) Q_j(x)` with `deg(Q_j) < n`. On the `k`th
+    // coset `s_k H`, with `s_k = g w^k`, `x^n` is the constant `g^n z^k` where `z = w^n`, so
+    // interpolating there gives `R_k = sum_j (g^n z^k)^j Q_j`. This is a DFT over `k` of the
+    // `g^(j n) Q_j`, which we invert by adding `(g^n z^k)^(-j) R_k / 2^quotient_degree_bits` to
+    // each `Q_j`.
+    let g_pow_n_inv = F::coset_shift().exp_power_of_2(degree_bits).inverse();
+    let z_inv = F::primitive_root_of_unity(quotient_degree_bits).inverse();
+    let num_cosets_inv = F::from_canonical_usize(num_cosets).inverse();
+    let mut quotient_coeffs = vec![vec![F::ZERO; degree * num_cosets]; alphas.len()];
+    let timings = ParallelTimings::new("evaluate constraints", Level::Debug);
+
+    for k in 0..num_cosets {
+        let shift = F::coset_shift() * generator.exp_u64(k as u64);
+        let xs = F::cyclic_subgroup_coset_known_order(subgroup_generator, shift, degree);
+        // `Z_H` is constant on the coset, so the Lagrange polynomials are cheap to evaluate.
+        let z_h = z_h_on_coset.eval(k);
+        let first_denominators = xs.iter().map(|&x| n * (x - F::ONE)).collect::<Vec<_>>();
+        let last_denominators = xs.iter().map(|&x| n * (x - last)).collect::<Vec<_>>();
+        let points = QuotientPoints {
+            start: k,
+            stride: num_cosets,
+            xs,
+            lagrange_first: F::batch_multiplicative_inverse(&first_denominators)
+                .into_iter()
+                .map(|inv| z_h * inv)
+                .collect(),
+            lagrange_last: F::batch_multiplicative_inverse(&last_denominators)
+                .into_iter()
+                .map(|inv| last * z_h * inv)
+                .collect(),
+            z_h_inverses: vec![z_h_on_coset.eval_inverse(k); degree],
+        };
+        let values = eval_quotient_points::<F, P, C, S, D>(
+            stark,
+            trace_commitment,
+            permutation_zs_commitment_challenges,
+            public_inputs,
+            &alphas,
+            degree_bits,
+            config,
+            &points,
+            &timings,
+        );
+
+        let weight_base = g_pow_n_inv * z_inv.exp_u64(k as u64);
+        quotient_coeffs
+            .par_iter_mut()
+            .zip(transpose(&values).into_par_iter())
+            .for_each(|(coeffs, values)| {
+                let remainder = PolynomialValues::new(values).coset_ifft(shift);
+                for (chunk, weight) in coeffs.chunks_exact_mut(degree).zip(weight_base.powers()) {
+                    let weight = weight * num_cosets_inv;
+                    for (c, &r) in chunk.iter_mut().zip(&remainder.coeffs) {
+                        *c += weight * r;
+                    }
+                }
+            });
+    }
+    timing.merge_parallel(timings);
+
+    quotient_coeffs
+        .into_iter()
+        .map(PolynomialCoeffs::new)
+        .collect()
+}
+
+/// Evaluations of the quotient polynomials over a contiguous range of the quotient domain.
+/// Chunks covering the whole domain, possibly computed on different machines, are combined with
+/// [`merge_quotient_chunks`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct QuotientChunk<F: Field> {
+    /// Index of the chunk's first point in the quotient domain.
+    pub start: usize,
+    /// `values[i][j]` is the `j`th quotient polynomial evaluated at point `start + i`.
+    pub values: Vec<Vec<F>>,
+}
+
+/// The size of the coset on which quotient polynomials are evaluated, i.e. the domain which
+/// [`compute_quotient_values_range`] takes a sub-range of.
+pub fn quotient_domain_size<F, S, const D: usize>(stark: &S, degree_bits: usize) -> usize
+where
+    F: RichField + Extendable<D>,
+    S: Stark<F, D>,
+{
+    1 << (degree_bits + log2_ceil(stark.quotient_degree_factor()))
+}
 
+/// Evaluates the quotient polynomials `(sum alpha^i C_i(x)) / Z_H(x)` on the points of the
+/// quotient domain with indices in `range`. Both ends of `range` must be multiples of
+/// `P::WIDTH`.
+#[allow(clippy::too_many_arguments)]
+pub fn compute_quotient_values_range<'a, F, P, C, S, const D: usize>(
+    stark: &S,
+    trace_commitment: &'a PolynomialBatch<F, C, D>,
+    permutation_zs_commitment_challenges: Option<(
+        &'a PolynomialBatch<F, C, D>,
+        &'a [PermutationChallengeSet<F>],
+    )>,
+    public_inputs: [F; S::PUBLIC_INPUTS],
+    alphas: &[F],
+    degree_bits: usize,
+    config: &StarkConfig,
+    range: Range<usize>,
+) -> QuotientChunk<F>
+where
+    F: RichField + Extendable<D>,
+    P: PackedField<Scalar = F>,
+    C: GenericConfig<D, F = F>,
+    S: Stark<F, D>,
+    [(); S::COLUMNS]:,
+    [(); S::PUBLIC_INPUTS]:,
+{
+    let degree = 1 << degree_bits;
     let quotient_degree_bits = log2_ceil(stark.quotient_degree_factor());
+    let size = degree << quotient_degree_bits;
+    assert!(range.end <= size, "Range exceeds the quotient domain");
     assert!(
-        quotient_degree_bits <= rate_bits,
-        "Having constraints of degree higher than the rate is not supported yet."
+        range.start % P::WIDTH == 0 && range.end % P::WIDTH == 0,
+        "Range must be aligned to the packing width"
     );
-    let step = 1 << (rate_bits - quotient_degree_bits);
-    // When opening the `Z`s polys at the "next" point, need to look at the point `next_step` steps away.
-    let next_step = 1 << quotient_degree_bits;
 
     // Evaluation of the first Lagrange polynomial on the LDE domain.
     let lagrange_first = PolynomialValues::selector(degree, 0).lde_onto_coset(quotient_degree_bits);
@@ -235,52 +503,154 @@ where
 
     let z_h_on_coset = ZeroPolyOnCoset::<F>::new(degree_bits, quotient_degree_bits);
 
-    // Retrieve the LDE values at index `i`.
+    let coset = F::cyclic_subgroup_coset_known_order(
+        F::primitive_root_of_unity(degree_bits + quotient_degree_bits),
+        F::coset_shift(),
+        size,
+    );
+
+    let points = QuotientPoints {
+        start: range.start,
+        stride: 1,
+        xs: coset[range.clone()].to_vec(),
+        lagrange_first: lagrange_first.values[range.clone()].to_vec(),
+        lagrange_last: lagrange_last.values[range.clone()].to_vec(),
+        z_h_inverses: range
+            .clone()
+            .map(|i| z_h_on_coset.eval_inverse(i))
+            .collect(),
+    };
+    let values = eval_quotient_points::<F, P, C, S, D>(
+        stark,
+        trace_commitment,
+        permutation_zs_commitment_challenges,
+        public_inputs,
+        alphas,
+        degree_bits,
+        config,
+        &points,
+        &ParallelTimings::new("evaluate constraints", Level::Debug),
+    );
+
+    QuotientChunk {
+        start: range.start,
+        values,
+    }
+}
+
+/// The number of points at which each parallel task evaluates the quotient polynomials. This is a
+/// multiple of any packing width.
+const QUOTIENT_TASK_POINTS: usize = 1 << 8;
+
+/// Points of the quotient domain, with indices `start + i * stride` for `i` in `0..xs.len()`,
+/// along with the values that the quotient's evaluation needs at each of them.
+struct QuotientPoints<F: Field> {
+    start: usize,
+    stride: usize,
+    xs: Vec<F>,
+    lagrange_first: Vec<F>,
+    lagrange_last: Vec<F>,
+    /// The inverses of `Z_H(x)`.
+    z_h_inverses: Vec<F>,
+}
+
+/// Evaluates the quotient polynomials at `points`, whose number must be a multiple of
+/// `P::WIDTH`. `values[i][j]` is the `j`th quotient polynomial evaluated at the `i`th point.
+#[allow(clippy::too_many_arguments)]
+fn eval_quotient_points<'a, F, P, C, S, const D: usize>(
+    stark: &S,
+    trace_commitment: &'a PolynomialBatch<F, C, D>,
+    permutation_zs_commitment_challenges: Option<(
+        &'a PolynomialBatch<F, C, D>,
+        &'a [PermutationChallengeSet<F>],
+    )>,
+    public_inputs: [F; S::PUBLIC_INPUTS],
+    alphas: &[F],
+    degree_bits: usize,
+    config: &StarkConfig,
+    points: &QuotientPoints<F>,
+    timings: &ParallelTimings,
+) -> Vec<Vec<F>>
+where
+    F: RichField + Extendable<D>,
+    P: PackedField<Scalar = F>,
+    C: GenericConfig<D, F = F>,
+    S: Stark<F, D>,
+    [(); S::COLUMNS]:,
+    [(); S::PUBLIC_INPUTS]:,
+{
+    let rate_bits = config.trace_rate_bits();
+
+    let quotient_degree_bits = log2_ceil(stark.quotient_degree_factor());
+    assert!(
+        quotient_degree_bits <= rate_bits,
+        "The trace's rate must be at least the quotient degree; see \
+         `StarkConfig::trace_extra_rate_bits`."
+    );
+    let step = 1 << (rate_bits - quotient_degree_bits);
+    // When opening the `Z`s polys at the "next" point, need to look at the point `next_step` steps away.
+    let next_step = 1 << quotient_degree_bits;
+    let size = 1 << (degree_bits + quotient_degree_bits);
+    let stride = points.stride;
+    let num_points = points.xs.len();
+    assert!(
+        num_points % P::WIDTH == 0,
+        "The number of points must be a multiple of the packing width"
+    );
+
+    // Retrieve the LDE values at indices `i_start + j * stride`. Unused columns aren't committed
+    // to, and are read as zero.
+    let used_columns = used_columns(stark);
     let get_trace_values_packed = |i_start| -> [P; S::COLUMNS] {
-        trace_commitment
-            .get_lde_values_packed(i_start, step)
+        if used_columns.len() == S::COLUMNS {
+            let mut values = [P::ZEROS; S::COLUMNS];
+            trace_commitment.get_lde_values_packed_strided_into(i_start, stride, step, &mut values);
+            return values;
+        }
+        let mut values = vec![P::ZEROS; used_columns.len()];
+        trace_commitment.get_lde_values_packed_strided_into(i_start, stride, step, &mut values);
+        scatter_columns(&values, &used_columns, S::COLUMNS, P::ZEROS)
             .try_into()
             .unwrap()
     };
+    let get_zs_packed = |zs_commitment: &PolynomialBatch<F, C, D>, i_start| -> Vec<P> {
+        let mut zs = vec![P::ZEROS; zs_commitment.polynomials.len()];
+        zs_commitment.get_lde_values_packed_strided_into(i_start, stride, step, &mut zs);
+        zs
+    };
 
     // Last element of the subgroup.
     let last = F::primitive_root_of_unity(degree_bits).inverse();
-    let size = degree << quotient_degree_bits;
-    let coset = F::cyclic_subgroup_coset_known_order(
-        F::primitive_root_of_unity(degree_bits + quotient_degree_bits),
-        F::coset_shift(),
-        size,
-    );
+    let additional_rows = stark.additional_opening_rows();
 
-    // We will step by `P::WIDTH`, and in each iteration, evaluate the quotient polynomial at
-    // a batch of `P::WIDTH` points.
-    let quotient_values = (0..size)
-        .into_par_iter()
-        .step_by(P::WIDTH)
-        .flat_map_iter(|i_start| {
+    // Evaluates the quotient polynomial at a batch of `P::WIDTH` points, appending the values at
+    // each point to `out`.
+    let eval_batch =
+        |consumer: &mut ConstraintConsumer<P>, p_start: usize, out: &mut Vec<Vec<F>>| {
+            let i_start = points.start + p_start * stride;
             let i_next_start = (i_start + next_step) % size;
-            let i_range = i_start..i_start + P::WIDTH;
+            let p_range = p_start..p_start + P::WIDTH;
 
-            let x = *P::from_slice(&coset[i_range.clone()]);
+            let x = *P::from_slice(&points.xs[p_range.clone()]);
             let z_last = x - last;
-            let lagrange_basis_first = *P::from_slice(&lagrange_first.values[i_range.clone()]);
-            let lagrange_basis_last = *P::from_slice(&lagrange_last.values[i_range]);
-
-            let mut consumer = ConstraintConsumer::new(
-                alphas.clone(),
-                z_last,
-                lagrange_basis_first,
-                lagrange_basis_last,
-            );
+            let lagrange_basis_first = *P::from_slice(&points.lagrange_first[p_range.clone()]);
+            let lagrange_basis_last = *P::from_slice(&points.lagrange_last[p_range.clone()]);
+
+            consumer.reset(z_last, lagrange_basis_first, lagrange_basis_last);
+            let additional_values = additional_rows
+                .iter()
+                .map(|&k| get_trace_values_packed((i_start + k * next_step) % size))
+                .collect::<Vec<_>>();
             let vars = StarkEvaluationVars {
                 local_values: &get_trace_values_packed(i_start),
                 next_values: &get_trace_values_packed(i_next_start),
+                additional_values: &additional_values,
                 public_inputs: &public_inputs,
             };
-            let permutation_check_data = permutation_zs_commitment_challenges.as_ref().map(
+            let permutation_check_data = permutation_zs_commitment_challenges.map(
                 |(permutation_zs_commitment, permutation_challenge_sets)| PermutationCheckVars {
-                    local_zs: permutation_zs_commitment.get_lde_values_packed(i_start, step),
-                    next_zs: permutation_zs_commitment.get_lde_values_packed(i_next_start, step),
+                    local_zs: get_zs_packed(permutation_zs_commitment, i_start),
+                    next_zs: get_zs_packed(permutation_zs_commitment, i_next_start),
                     permutation_challenge_sets: permutation_challenge_sets.to_vec(),
                 },
             );
@@ -289,30 +659,73 @@ where
                 config,
                 vars,
                 permutation_check_data,
-                &mut consumer,
+                consumer,
             );
 
-            let mut constraints_evals = consumer.accumulators();
             // We divide the constraints evaluations by `Z_H(x)`.
-            let denominator_inv: P = z_h_on_coset.eval_inverse_packed(i_start);
-
-            for eval in &mut constraints_evals {
-                *eval *= denominator_inv;
+            let denominator_inv = *P::from_slice(&points.z_h_inverses[p_range]);
+            let out_start = out.len();
+            out.extend((0..P::WIDTH).map(|_| Vec::with_capacity(alphas.len())));
+            for &eval in consumer.accumulators() {
+                let quotient = eval * denominator_inv;
+                for (values, &q) in out[out_start..].iter_mut().zip(quotient.as_slice()) {
+                    values.push(q);
+                }
             }
+        };
 
-            let num_challenges = alphas.len();
-
-            (0..P::WIDTH).map(move |i| {
-                (0..num_challenges)
-                    .map(|j| constraints_evals[j].as_slice()[i])
-                    .collect()
+    // Each parallel task evaluates a chunk of points, stepping by `P::WIDTH`, reusing a single
+    // constraint consumer for all of them.
+    (0..num_points)
+        .into_par_iter()
+        .step_by(QUOTIENT_TASK_POINTS)
+        .flat_map_iter(|task_start| {
+            let task_end = (task_start + QUOTIENT_TASK_POINTS).min(num_points);
+            timings.task(|_| {
+                let mut accs = vec![P::ZEROS; alphas.len()];
+                let mut consumer =
+                    ConstraintConsumer::new(alphas, &mut accs, P::ZEROS, P::ZEROS, P::ZEROS);
+                let mut values = Vec::with_capacity(task_end - task_start);
+                for p_start in (task_start..task_end).step_by(P::WIDTH) {
+                    eval_batch(&mut consumer, p_start, &mut values);
+                }
+                values
             })
         })
-        .collect::<Vec<_>>();
+        .collect()
+}
 
-    transpose(&quotient_values)
+/// Combines quotient chunks which together cover the quotient domain of size `domain_size`
+/// exactly once, in any order, into the quotient polynomials in coefficient form.
+pub fn merge_quotient_chunks<F: Field>(
+    mut chunks: Vec<QuotientChunk<F>>,
+    domain_size: usize,
+    num_challenges: usize,
+) -> Result<Vec<PolynomialCoeffs<F>>> {
+    chunks.sort_by_key(|chunk| chunk.start);
+    let mut quotient_values = Vec::with_capacity(domain_size);
+    for chunk in chunks {
+        ensure!(
+            chunk.start == quotient_values.len(),
+            "Quotient chunks must cover the domain without gaps or overlaps"
+        );
+        ensure!(
+            chunk.values.iter().all(|v| v.len() == num_challenges),
+            "Expected {} quotient values per point",
+            num_challenges
+        );
+        quotient_values.extend(chunk.values);
+    }
+    ensure!(
+        quotient_values.len() == domain_size,
+        "Quotient chunks cover {} points, expected {}",
+        quotient_values.len(),
+        domain_size
+    );
+
+    Ok(transpose(&quotient_values)
         .into_par_iter()
         .map(PolynomialValues::new)
         .map(|values| values.coset_ifft(F::coset_shift()))
-        .collect()
+        .collect())
 }
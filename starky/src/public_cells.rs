@@ -0,0 +1,117 @@
+//! Trace cells exposed as public inputs.
+//!
+//! A STARK lists its public cells in `Stark::public_cells`. Each names a column, a boundary row and
+//! a public input, and the vanishing polynomial gets a constraint, filtered by the Lagrange
+//! selector of that row, checking that the cell equals the public input. AIRs thus don't need to
+//! write these boundary constraints themselves.
+
+use alloc::vec::Vec;
+
+use plonky2::field::extension::{Extendable, FieldExtension};
+use plonky2::field::packed::PackedField;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::hash::hash_types::RichField;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+
+use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use crate::stark::Stark;
+use crate::vars::{StarkEvaluationTargets, StarkEvaluationVars};
+
+/// A row at the boundary of the trace.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BoundaryRow {
+    First,
+    Last,
+}
+
+/// A trace cell whose value is the public input at index `public_input`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct PublicCell {
+    pub row: BoundaryRow,
+    pub column: usize,
+    pub public_input: usize,
+}
+
+impl PublicCell {
+    pub fn first_row(column: usize, public_input: usize) -> Self {
+        Self {
+            row: BoundaryRow::First,
+            column,
+            public_input,
+        }
+    }
+
+    pub fn last_row(column: usize, public_input: usize) -> Self {
+        Self {
+            row: BoundaryRow::Last,
+            column,
+            public_input,
+        }
+    }
+
+    /// The value of this cell in a trace given by its columns.
+    pub fn value<F: RichField>(&self, trace_poly_values: &[PolynomialValues<F>]) -> F {
+        let column = &trace_poly_values[self.column].values;
+        match self.row {
+            BoundaryRow::First => column[0],
+            BoundaryRow::Last => column[column.len() - 1],
+        }
+    }
+}
+
+/// The public inputs of `public_cells` which disagree with the trace.
+pub(crate) fn mismatched_public_cells<F: RichField>(
+    public_cells: &[PublicCell],
+    trace_poly_values: &[PolynomialValues<F>],
+    public_inputs: &[F],
+) -> Vec<PublicCell> {
+    public_cells
+        .iter()
+        .filter(|cell| cell.value(trace_poly_values) != public_inputs[cell.public_input])
+        .copied()
+        .collect()
+}
+
+pub(crate) fn eval_public_cells<F, FE, P, S, const D: usize, const D2: usize>(
+    stark: &S,
+    vars: StarkEvaluationVars<FE, P, { S::COLUMNS }, { S::PUBLIC_INPUTS }>,
+    consumer: &mut ConstraintConsumer<P>,
+) where
+    F: RichField + Extendable<D>,
+    FE: FieldExtension<D2, BaseField = F>,
+    P: PackedField<Scalar = FE>,
+    S: Stark<F, D>,
+    [(); S::COLUMNS]:,
+    [(); S::PUBLIC_INPUTS]:,
+{
+    for cell in stark.public_cells() {
+        let diff = vars.local_values[cell.column] - vars.public_inputs[cell.public_input];
+        match cell.row {
+            BoundaryRow::First => consumer.constraint_first_row(diff),
+            BoundaryRow::Last => consumer.constraint_last_row(diff),
+        }
+    }
+}
+
+pub(crate) fn eval_public_cells_circuit<F, S, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    stark: &S,
+    vars: StarkEvaluationTargets<D, { S::COLUMNS }, { S::PUBLIC_INPUTS }>,
+    consumer: &mut RecursiveConstraintConsumer<F, D>,
+) where
+    F: RichField + Extendable<D>,
+    S: Stark<F, D>,
+    [(); S::COLUMNS]:,
+    [(); S::PUBLIC_INPUTS]:,
+{
+    for cell in stark.public_cells() {
+        let diff = builder.sub_extension(
+            vars.local_values[cell.column],
+            vars.public_inputs[cell.public_input],
+        );
+        match cell.row {
+            BoundaryRow::First => consumer.constraint_first_row(builder, diff),
+            BoundaryRow::Last => consumer.constraint_last_row(builder, diff),
+        }
+    }
+}
@@ -11,7 +11,7 @@ use plonky2::iop::ext_target::ExtensionTarget;
 use plonky2::iop::witness::Witness;
 use plonky2::plonk::circuit_builder::CircuitBuilder;
 use plonky2::plonk::config::{AlgebraicHasher, GenericConfig};
-use plonky2::util::reducing::ReducingFactorTarget;
+use plonky2::plonk::plonk_common::check_quotient_openings_circuit;
 use plonky2::with_context;
 
 use crate::config::StarkConfig;
@@ -21,7 +21,8 @@ use crate::proof::{
     StarkOpeningSetTarget, StarkProof, StarkProofChallengesTarget, StarkProofTarget,
     StarkProofWithPublicInputs, StarkProofWithPublicInputsTarget,
 };
-use crate::stark::Stark;
+use crate::stark::{opened_next_row_columns, used_columns, Stark};
+use crate::util::scatter_columns;
 use crate::vanishing_poly::eval_vanishing_poly_circuit;
 use crate::vars::StarkEvaluationTargets;
 
@@ -59,7 +60,7 @@ pub fn verify_stark_proof_circuit<
 }
 
 /// Recursively verifies an inner proof.
-fn verify_stark_proof_with_challenges_circuit<
+pub(crate) fn verify_stark_proof_with_challenges_circuit<
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
     S: Stark<F, D>,
@@ -86,13 +87,33 @@ fn verify_stark_proof_with_challenges_circuit<
     let StarkOpeningSetTarget {
         local_values,
         next_values,
+        additional_values,
         permutation_zs,
         permutation_zs_next,
         quotient_polys,
     } = &proof.openings;
+    // Columns which aren't opened are read as zero.
+    let zero = builder.zero_extension();
+    let used_columns = used_columns(&stark);
+    let local_values = scatter_columns(local_values, &used_columns, S::COLUMNS, zero);
+    let next_values = scatter_columns(
+        next_values,
+        &opened_next_row_columns(&stark),
+        S::COLUMNS,
+        zero,
+    );
+    let additional_values = additional_values
+        .iter()
+        .map(|values| {
+            scatter_columns(values, &used_columns, S::COLUMNS, zero)
+                .try_into()
+                .unwrap()
+        })
+        .collect::<Vec<[_; S::COLUMNS]>>();
     let vars = StarkEvaluationTargets {
-        local_values: &local_values.to_vec().try_into().unwrap(),
-        next_values: &next_values.to_vec().try_into().unwrap(),
+        local_values: &local_values.try_into().unwrap(),
+        next_values: &next_values.try_into().unwrap(),
+        additional_values: &additional_values,
         public_inputs: &public_inputs
             .into_iter()
             .map(|t| builder.convert_to_ext(t))
@@ -118,7 +139,7 @@ fn verify_stark_proof_with_challenges_circuit<
     );
 
     let permutation_data = stark
-        .uses_permutation_args()
+        .uses_auxiliary_polys()
         .then(|| PermutationCheckDataTarget {
             local_zs: permutation_zs.as_ref().unwrap().clone(),
             next_zs: permutation_zs_next.as_ref().unwrap().clone(),
@@ -140,15 +161,13 @@ fn verify_stark_proof_with_challenges_circuit<
     let vanishing_polys_zeta = consumer.accumulators();
 
     // Check each polynomial identity, of the form `vanishing(x) = Z_H(x) quotient(x)`, at zeta.
-    let mut scale = ReducingFactorTarget::new(zeta_pow_deg);
-    for (i, chunk) in quotient_polys
-        .chunks(stark.quotient_degree_factor())
-        .enumerate()
-    {
-        let recombined_quotient = scale.reduce(chunk, builder);
-        let computed_vanishing_poly = builder.mul_extension(z_h_zeta, recombined_quotient);
-        builder.connect_extension(vanishing_polys_zeta[i], computed_vanishing_poly);
-    }
+    check_quotient_openings_circuit(
+        builder,
+        &vanishing_polys_zeta,
+        quotient_polys,
+        stark.quotient_degree_factor(),
+        zeta_pow_deg,
+    );
 
     let merkle_caps = once(proof.trace_cap)
         .chain(proof.permutation_zs_cap)
@@ -217,43 +236,40 @@ pub fn add_virtual_stark_proof<F: RichField + Extendable<D>, S: Stark<F, D>, con
     let fri_params = config.fri_params(degree_bits);
     let cap_height = fri_params.config.cap_height;
 
-    let num_leaves_per_oracle = once(S::COLUMNS)
-        .chain(
-            stark
-                .uses_permutation_args()
-                .then(|| stark.num_permutation_batches(config)),
-        )
-        .chain(once(stark.quotient_degree_factor() * config.num_challenges))
-        .collect_vec();
-
     let permutation_zs_cap = stark
-        .uses_permutation_args()
+        .uses_auxiliary_polys()
         .then(|| builder.add_virtual_cap(cap_height));
 
     StarkProofTarget {
         trace_cap: builder.add_virtual_cap(cap_height),
         permutation_zs_cap,
         quotient_polys_cap: builder.add_virtual_cap(cap_height),
-        openings: add_stark_opening_set_target::<F, S, D>(builder, stark, config),
-        opening_proof: builder.add_virtual_fri_proof(&num_leaves_per_oracle, &fri_params),
+        openings: add_stark_opening_set_target::<F, S, D>(builder, &stark, config),
+        opening_proof: builder
+            .add_virtual_fri_proof_for_oracle_infos(&stark.fri_oracles(config), &fri_params),
     }
 }
 
 fn add_stark_opening_set_target<F: RichField + Extendable<D>, S: Stark<F, D>, const D: usize>(
     builder: &mut CircuitBuilder<F, D>,
-    stark: S,
+    stark: &S,
     config: &StarkConfig,
 ) -> StarkOpeningSetTarget<D> {
     let num_challenges = config.num_challenges;
+    let num_trace_polys = used_columns(stark).len();
+    let num_additional_points = stark.additional_opening_rows().len();
     StarkOpeningSetTarget {
-        local_values: builder.add_virtual_extension_targets(S::COLUMNS),
-        next_values: builder.add_virtual_extension_targets(S::COLUMNS),
+        local_values: builder.add_virtual_extension_targets(num_trace_polys),
+        next_values: builder.add_virtual_extension_targets(opened_next_row_columns(stark).len()),
+        additional_values: (0..num_additional_points)
+            .map(|_| builder.add_virtual_extension_targets(num_trace_polys))
+            .collect(),
         permutation_zs: stark
-            .uses_permutation_args()
-            .then(|| builder.add_virtual_extension_targets(stark.num_permutation_batches(config))),
+            .uses_auxiliary_polys()
+            .then(|| builder.add_virtual_extension_targets(stark.num_auxiliary_polys(config))),
         permutation_zs_next: stark
-            .uses_permutation_args()
-            .then(|| builder.add_virtual_extension_targets(stark.num_permutation_batches(config))),
+            .uses_auxiliary_polys()
+            .then(|| builder.add_virtual_extension_targets(stark.num_auxiliary_polys(config))),
         quotient_polys: builder
             .add_virtual_extension_targets(stark.quotient_degree_factor() * num_challenges),
     }
@@ -312,7 +328,7 @@ pub fn set_stark_proof_target<F, C: GenericConfig<D, F = F>, W, const D: usize>(
 }
 
 /// Utility function to check that all permutation data wrapped in `Option`s are `Some` iff
-/// the Stark uses a permutation argument.
+/// the Stark uses permutation arguments or LogUp lookups.
 fn check_permutation_options<F: RichField + Extendable<D>, S: Stark<F, D>, const D: usize>(
     stark: &S,
     proof_with_pis: &StarkProofWithPublicInputsTarget<D>,
@@ -327,7 +343,7 @@ fn check_permutation_options<F: RichField + Extendable<D>, S: Stark<F, D>, const
     ensure!(
         options_is_some
             .into_iter()
-            .all(|b| b == stark.uses_permutation_args()),
+            .all(|b| b == stark.uses_auxiliary_polys()),
         "Permutation data doesn't match with Stark configuration."
     );
     Ok(())
@@ -14,7 +14,10 @@ use plonky2::util::ceil_div_usize;
 
 use crate::config::StarkConfig;
 use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
-use crate::permutation::PermutationPair;
+use crate::lookup::Lookup;
+use crate::padding::PaddingPolicy;
+use crate::permutation::{all_permutation_pairs, PermutationPair};
+use crate::public_cells::PublicCell;
 use crate::vars::{StarkEvaluationTargets, StarkEvaluationVars};
 
 /// Represents a STARK system.
@@ -84,6 +87,86 @@ pub trait Stark<F: RichField + Extendable<D>, const D: usize>: Sync {
         self.quotient_degree_factor() * config.num_challenges
     }
 
+    /// Which columns this configuration of the STARK uses, e.g. excluding the columns of disabled
+    /// sub-tables. Unused columns are neither committed to nor opened, and the prover and verifier
+    /// both read them as zero, so they must not appear in permutation pairs or lookups. A
+    /// configuration which also lowers the degree of the constraints should lower
+    /// `constraint_degree` accordingly, so that it commits to fewer quotient chunks. All columns
+    /// are used by default.
+    fn column_usage(&self) -> Vec<bool> {
+        vec![true; Self::COLUMNS]
+    }
+
+    /// The columns whose values at the next row are read by the constraints. Only these columns,
+    /// and the permuted columns of any `Lookup::Permuted`, are opened at `g * zeta`, so a STARK
+    /// whose constraints mostly read the current row can save openings. The verifier sets the other
+    /// columns of `next_values` to zero, so the constraints must not read them; see
+    /// `test_stark_next_row_columns`. All columns by default.
+    fn next_row_columns(&self) -> Vec<usize> {
+        (0..Self::COLUMNS).collect()
+    }
+
+    /// Trace cells whose values are public inputs. Each is checked by a constraint on its boundary
+    /// row, so the STARK needn't constrain them itself, and the prover rejects public inputs which
+    /// disagree with the trace. Their columns must be used, as in `column_usage`. Empty by
+    /// default.
+    fn public_cells(&self) -> Vec<PublicCell> {
+        vec![]
+    }
+
+    /// How `prove` pads traces whose length isn't a power of two. Without a policy, the trace
+    /// length must be a power of two. `None` by default.
+    fn padding_policy(&self) -> Option<PaddingPolicy<F>> {
+        None
+    }
+
+    /// A column which `prove` sets to one on padding rows and zero on the others, overwriting the
+    /// given values, and which is constrained accordingly; see the `padding` module. It must be
+    /// used, as in `column_usage`, and is always opened at the next row. `None` by default.
+    fn is_padding_column(&self) -> Option<usize> {
+        None
+    }
+
+    /// Offsets `k > 1` of the rows, besides the local and next ones, which constraints read, so that
+    /// the trace is also opened at `g^k * zeta`, where `g` generates the trace domain. For instance
+    /// an AIR whose constraints span three rows would return `[2]`. Constraints read the values in
+    /// `StarkEvaluationVars::additional_values`, and proofs hold them in
+    /// `StarkOpeningSet::additional_values`, in the same order. Empty by default.
+    fn additional_opening_rows(&self) -> Vec<usize> {
+        vec![]
+    }
+
+    /// The shifts `g^k` of `zeta` at which the trace is opened for `additional_opening_rows`.
+    fn additional_opening_shifts(&self, g: F) -> Vec<F> {
+        self.additional_opening_rows()
+            .into_iter()
+            .map(|k| g.exp_u64(k as u64))
+            .collect()
+    }
+
+    /// The oracles opened by the FRI proof: the trace, the permutation Zs and lookup helpers if
+    /// any, and the quotient chunks.
+    fn fri_oracles(&self, config: &StarkConfig) -> Vec<FriOracleInfo> {
+        let oracle = |num_polys, extra_rate_bits| FriOracleInfo {
+            num_polys,
+            blinding: true,
+            extra_rate_bits,
+            leaf_group_bits: config.leaf_group_bits,
+        };
+        let mut oracles = vec![oracle(
+            used_columns(self).len(),
+            config.trace_extra_rate_bits,
+        )];
+        if self.uses_auxiliary_polys() {
+            oracles.push(oracle(
+                self.num_auxiliary_polys(config),
+                config.trace_extra_rate_bits,
+            ));
+        }
+        oracles.push(oracle(self.num_quotient_polys(config), 0));
+        oracles
+    }
+
     /// Computes the FRI instance used to prove this Stark.
     fn fri_instance(
         &self,
@@ -91,32 +174,24 @@ pub trait Stark<F: RichField + Extendable<D>, const D: usize>: Sync {
         g: F,
         config: &StarkConfig,
     ) -> FriInstanceInfo<F, D> {
-        let mut oracles = vec![];
-
-        let trace_info = FriPolynomialInfo::from_range(oracles.len(), 0..Self::COLUMNS);
-        oracles.push(FriOracleInfo {
-            num_polys: Self::COLUMNS,
-            blinding: false,
-        });
-
-        let permutation_zs_info = if self.uses_permutation_args() {
-            let num_z_polys = self.num_permutation_batches(config);
-            let polys = FriPolynomialInfo::from_range(oracles.len(), 0..num_z_polys);
-            oracles.push(FriOracleInfo {
-                num_polys: num_z_polys,
-                blinding: false,
-            });
-            polys
+        let oracles = self.fri_oracles(config);
+
+        let trace_info = FriPolynomialInfo::from_range(0, 0..oracles[0].num_polys);
+        let next_trace_info = opened_next_row_polys(self)
+            .into_iter()
+            .map(|polynomial_index| FriPolynomialInfo {
+                oracle_index: 0,
+                polynomial_index,
+            })
+            .collect::<Vec<_>>();
+        let permutation_zs_info = if self.uses_auxiliary_polys() {
+            FriPolynomialInfo::from_range(1, 0..oracles[1].num_polys)
         } else {
             vec![]
         };
-
-        let num_quotient_polys = self.quotient_degree_factor() * config.num_challenges;
-        let quotient_info = FriPolynomialInfo::from_range(oracles.len(), 0..num_quotient_polys);
-        oracles.push(FriOracleInfo {
-            num_polys: num_quotient_polys,
-            blinding: false,
-        });
+        let quotient_index = oracles.len() - 1;
+        let quotient_info =
+            FriPolynomialInfo::from_range(quotient_index, 0..oracles[quotient_index].num_polys);
 
         let zeta_batch = FriBatchInfo {
             point: zeta,
@@ -129,9 +204,17 @@ pub trait Stark<F: RichField + Extendable<D>, const D: usize>: Sync {
         };
         let zeta_next_batch = FriBatchInfo {
             point: zeta.scalar_mul(g),
-            polynomials: [trace_info, permutation_zs_info].concat(),
+            polynomials: [next_trace_info, permutation_zs_info].concat(),
         };
-        let batches = vec![zeta_batch, zeta_next_batch];
+        let mut batches = vec![zeta_batch, zeta_next_batch];
+        batches.extend(
+            self.additional_opening_shifts(g)
+                .into_iter()
+                .map(|shift| FriBatchInfo {
+                    point: zeta.scalar_mul(shift),
+                    polynomials: trace_info.clone(),
+                }),
+        );
 
         FriInstanceInfo { oracles, batches }
     }
@@ -144,32 +227,24 @@ pub trait Stark<F: RichField + Extendable<D>, const D: usize>: Sync {
         g: F,
         config: &StarkConfig,
     ) -> FriInstanceInfoTarget<D> {
-        let mut oracles = vec![];
-
-        let trace_info = FriPolynomialInfo::from_range(oracles.len(), 0..Self::COLUMNS);
-        oracles.push(FriOracleInfo {
-            num_polys: Self::COLUMNS,
-            blinding: false,
-        });
-
-        let permutation_zs_info = if self.uses_permutation_args() {
-            let num_z_polys = self.num_permutation_batches(config);
-            let polys = FriPolynomialInfo::from_range(oracles.len(), 0..num_z_polys);
-            oracles.push(FriOracleInfo {
-                num_polys: num_z_polys,
-                blinding: false,
-            });
-            polys
+        let oracles = self.fri_oracles(config);
+
+        let trace_info = FriPolynomialInfo::from_range(0, 0..oracles[0].num_polys);
+        let next_trace_info = opened_next_row_polys(self)
+            .into_iter()
+            .map(|polynomial_index| FriPolynomialInfo {
+                oracle_index: 0,
+                polynomial_index,
+            })
+            .collect::<Vec<_>>();
+        let permutation_zs_info = if self.uses_auxiliary_polys() {
+            FriPolynomialInfo::from_range(1, 0..oracles[1].num_polys)
         } else {
             vec![]
         };
-
-        let num_quotient_polys = self.quotient_degree_factor() * config.num_challenges;
-        let quotient_info = FriPolynomialInfo::from_range(oracles.len(), 0..num_quotient_polys);
-        oracles.push(FriOracleInfo {
-            num_polys: num_quotient_polys,
-            blinding: false,
-        });
+        let quotient_index = oracles.len() - 1;
+        let quotient_info =
+            FriPolynomialInfo::from_range(quotient_index, 0..oracles[quotient_index].num_polys);
 
         let zeta_batch = FriBatchInfoTarget {
             point: zeta,
@@ -183,9 +258,15 @@ pub trait Stark<F: RichField + Extendable<D>, const D: usize>: Sync {
         let zeta_next = builder.mul_const_extension(g, zeta);
         let zeta_next_batch = FriBatchInfoTarget {
             point: zeta_next,
-            polynomials: [trace_info, permutation_zs_info].concat(),
+            polynomials: [next_trace_info, permutation_zs_info].concat(),
         };
-        let batches = vec![zeta_batch, zeta_next_batch];
+        let mut batches = vec![zeta_batch, zeta_next_batch];
+        for shift in self.additional_opening_shifts(g) {
+            batches.push(FriBatchInfoTarget {
+                point: builder.mul_const_extension(shift, zeta),
+                polynomials: trace_info.clone(),
+            });
+        }
 
         FriInstanceInfoTarget { oracles, batches }
     }
@@ -197,7 +278,23 @@ pub trait Stark<F: RichField + Extendable<D>, const D: usize>: Sync {
     }
 
     fn uses_permutation_args(&self) -> bool {
-        !self.permutation_pairs().is_empty()
+        !all_permutation_pairs(self).is_empty()
+    }
+
+    /// Lookups into tables in this STARK's trace, each argued with the backend of its choice.
+    /// Empty by default.
+    fn lookups(&self) -> Vec<Lookup> {
+        vec![]
+    }
+
+    fn uses_lookups(&self) -> bool {
+        !self.lookups().is_empty()
+    }
+
+    /// Whether the STARK commits to permutation `Z`s or LogUp helper polynomials, which share an
+    /// oracle.
+    fn uses_auxiliary_polys(&self) -> bool {
+        self.uses_permutation_args() || self.num_lookup_helper_polys_per_challenge() > 0
     }
 
     /// The number of permutation argument instances that can be combined into a single constraint.
@@ -210,7 +307,7 @@ pub trait Stark<F: RichField + Extendable<D>, const D: usize>: Sync {
     }
 
     fn num_permutation_instances(&self, config: &StarkConfig) -> usize {
-        self.permutation_pairs().len() * config.num_challenges
+        all_permutation_pairs(self).len() * config.num_challenges
     }
 
     fn num_permutation_batches(&self, config: &StarkConfig) -> usize {
@@ -219,4 +316,176 @@ pub trait Stark<F: RichField + Extendable<D>, const D: usize>: Sync {
             self.permutation_batch_size(),
         )
     }
+
+    /// The number of inverses summed in each LogUp helper polynomial.
+    fn lookup_batch_size(&self) -> usize {
+        // Checking a helper polynomial `h` multiplies it by one factor per inverse, so like
+        // `permutation_batch_size`, this should be one less than the constraint degree.
+        self.quotient_degree_factor()
+    }
+
+    fn num_lookup_helper_polys_per_challenge(&self) -> usize {
+        let batch_size = self.lookup_batch_size();
+        self.lookups()
+            .iter()
+            .map(|lookup| lookup.num_helper_polys(batch_size))
+            .sum()
+    }
+
+    /// The number of polynomials in the auxiliary oracle: the permutation `Z`s, followed by the
+    /// LogUp helper polynomials.
+    fn num_auxiliary_polys(&self, config: &StarkConfig) -> usize {
+        self.num_permutation_batches(config)
+            + self.num_lookup_helper_polys_per_challenge() * config.num_challenges
+    }
+}
+
+/// The columns used by `stark`, as given by `Stark::column_usage`, which are committed to in the
+/// trace oracle in this order.
+pub(crate) fn used_columns<F, S, const D: usize>(stark: &S) -> Vec<usize>
+where
+    F: RichField + Extendable<D>,
+    S: Stark<F, D> + ?Sized,
+{
+    let usage = stark.column_usage();
+    assert_eq!(
+        usage.len(),
+        S::COLUMNS,
+        "Column usage must cover every column"
+    );
+    (0..usage.len()).filter(|&i| usage[i]).collect()
+}
+
+/// The used columns opened at `g * zeta`: those given by `Stark::next_row_columns`, along with the
+/// permuted columns of any `Lookup::Permuted` and the padding column, whose constraints span two
+/// rows. Sorted and without duplicates.
+pub(crate) fn opened_next_row_columns<F, S, const D: usize>(stark: &S) -> Vec<usize>
+where
+    F: RichField + Extendable<D>,
+    S: Stark<F, D> + ?Sized,
+{
+    let mut columns = stark.next_row_columns();
+    for lookup in stark.lookups() {
+        if let Lookup::Permuted {
+            permuted_input,
+            permuted_table,
+            ..
+        } = lookup
+        {
+            columns.extend([permuted_input, permuted_table]);
+        }
+    }
+    columns.extend(stark.is_padding_column());
+    assert!(
+        columns.iter().all(|&i| i < S::COLUMNS),
+        "Next row columns, permuted columns and the padding column must be below `Stark::COLUMNS`"
+    );
+    let used_columns = used_columns(stark);
+    columns.retain(|i| used_columns.binary_search(i).is_ok());
+    columns.sort_unstable();
+    columns.dedup();
+    columns
+}
+
+/// The indices in the trace oracle of the columns given by `opened_next_row_columns`.
+pub(crate) fn opened_next_row_polys<F, S, const D: usize>(stark: &S) -> Vec<usize>
+where
+    F: RichField + Extendable<D>,
+    S: Stark<F, D> + ?Sized,
+{
+    let used = used_columns(stark);
+    opened_next_row_columns(stark)
+        .into_iter()
+        .map(|i| used.binary_search(&i).expect("Opened columns are used"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2::field::goldilocks_field::GoldilocksField;
+
+    use super::*;
+
+    const D: usize = 2;
+    type F = GoldilocksField;
+
+    /// A STARK without constraints, to check which columns are opened at the next row.
+    struct ColumnsStark {
+        usage: Vec<bool>,
+        next_row_columns: Vec<usize>,
+        padding_column: Option<usize>,
+    }
+
+    impl Stark<F, D> for ColumnsStark {
+        const COLUMNS: usize = 4;
+        const PUBLIC_INPUTS: usize = 0;
+
+        fn eval_packed_generic<FE, P, const D2: usize>(
+            &self,
+            _vars: StarkEvaluationVars<FE, P, { Self::COLUMNS }, { Self::PUBLIC_INPUTS }>,
+            _yield_constr: &mut ConstraintConsumer<P>,
+        ) where
+            FE: FieldExtension<D2, BaseField = F>,
+            P: PackedField<Scalar = FE>,
+        {
+        }
+
+        fn eval_ext_circuit(
+            &self,
+            _builder: &mut CircuitBuilder<F, D>,
+            _vars: StarkEvaluationTargets<D, { Self::COLUMNS }, { Self::PUBLIC_INPUTS }>,
+            _yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+        ) {
+        }
+
+        fn constraint_degree(&self) -> usize {
+            1
+        }
+
+        fn column_usage(&self) -> Vec<bool> {
+            self.usage.clone()
+        }
+
+        fn next_row_columns(&self) -> Vec<usize> {
+            self.next_row_columns.clone()
+        }
+
+        fn is_padding_column(&self) -> Option<usize> {
+            self.padding_column
+        }
+    }
+
+    #[test]
+    fn test_opened_next_row_columns() {
+        let stark = ColumnsStark {
+            usage: vec![true, true, false, true],
+            next_row_columns: vec![3, 2, 1, 1],
+            padding_column: Some(0),
+        };
+        // Unused columns are skipped, and the rest sorted and deduplicated.
+        assert_eq!(opened_next_row_columns(&stark), [0, 1, 3]);
+        assert_eq!(opened_next_row_polys(&stark), [0, 1, 2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "must be below `Stark::COLUMNS`")]
+    fn test_opened_next_row_columns_out_of_range() {
+        let stark = ColumnsStark {
+            usage: vec![true; 4],
+            next_row_columns: vec![4],
+            padding_column: None,
+        };
+        opened_next_row_columns(&stark);
+    }
+
+    #[test]
+    #[should_panic(expected = "Column usage must cover every column")]
+    fn test_opened_next_row_columns_short_usage() {
+        let stark = ColumnsStark {
+            usage: vec![true; 3],
+            next_row_columns: vec![3],
+            padding_column: None,
+        };
+        opened_next_row_columns(&stark);
+    }
 }
@@ -1,4 +1,3 @@
-use alloc::vec;
 use alloc::vec::Vec;
 
 use anyhow::{ensure, Result};
@@ -13,7 +12,7 @@ use plonky2::plonk::config::GenericConfig;
 use plonky2::util::{log2_ceil, log2_strict, transpose};
 
 use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
-use crate::stark::Stark;
+use crate::stark::{opened_next_row_columns, Stark};
 use crate::vars::{StarkEvaluationTargets, StarkEvaluationVars};
 
 const WITNESS_SIZE: usize = 1 << 5;
@@ -39,20 +38,31 @@ where
     let last = F::primitive_root_of_unity(log2_strict(WITNESS_SIZE)).inverse();
     let subgroup =
         F::cyclic_subgroup_known_order(F::primitive_root_of_unity(log2_strict(size)), size);
-    let alpha = F::rand();
+    let alphas = [F::rand()];
+    let row_values = |i: usize, k: usize| -> [F; S::COLUMNS] {
+        trace_ldes[(i + (k << rate_bits)) % size]
+            .clone()
+            .try_into()
+            .unwrap()
+    };
     let constraint_evals = (0..size)
         .map(|i| {
+            let additional_values = stark
+                .additional_opening_rows()
+                .into_iter()
+                .map(|k| row_values(i, k))
+                .collect::<Vec<_>>();
             let vars = StarkEvaluationVars {
-                local_values: &trace_ldes[i].clone().try_into().unwrap(),
-                next_values: &trace_ldes[(i + (1 << rate_bits)) % size]
-                    .clone()
-                    .try_into()
-                    .unwrap(),
+                local_values: &row_values(i, 0),
+                next_values: &row_values(i, 1),
+                additional_values: &additional_values,
                 public_inputs: &public_inputs,
             };
 
+            let mut accs = [F::ZERO];
             let mut consumer = ConstraintConsumer::<F>::new(
-                vec![alpha],
+                &alphas,
+                &mut accs,
                 subgroup[i] - last,
                 lagrange_first.values[i],
                 lagrange_last.values[i],
@@ -91,21 +101,30 @@ where
     [(); S::PUBLIC_INPUTS]:,
 {
     // Compute native constraint evaluation on random values.
+    let additional_values = stark
+        .additional_opening_rows()
+        .iter()
+        .map(|_| F::Extension::rand_array::<{ S::COLUMNS }>())
+        .collect::<Vec<_>>();
     let vars = StarkEvaluationVars {
         local_values: &F::Extension::rand_array::<{ S::COLUMNS }>(),
         next_values: &F::Extension::rand_array::<{ S::COLUMNS }>(),
+        additional_values: &additional_values,
         public_inputs: &F::Extension::rand_array::<{ S::PUBLIC_INPUTS }>(),
     };
     let alphas = F::rand_vec(1);
     let z_last = F::Extension::rand();
     let lagrange_first = F::Extension::rand();
     let lagrange_last = F::Extension::rand();
+    let alphas_ext = alphas
+        .iter()
+        .copied()
+        .map(F::Extension::from_basefield)
+        .collect::<Vec<_>>();
+    let mut accs = [F::Extension::ZERO];
     let mut consumer = ConstraintConsumer::<F::Extension>::new(
-        alphas
-            .iter()
-            .copied()
-            .map(F::Extension::from_basefield)
-            .collect(),
+        &alphas_ext,
+        &mut accs,
         z_last,
         lagrange_first,
         lagrange_last,
@@ -122,6 +141,14 @@ where
     pw.set_extension_targets(&locals_t, vars.local_values);
     let nexts_t = builder.add_virtual_extension_targets(S::COLUMNS);
     pw.set_extension_targets(&nexts_t, vars.next_values);
+    let additional_t = additional_values
+        .iter()
+        .map(|values| {
+            let values_t = builder.add_virtual_extension_targets(S::COLUMNS);
+            pw.set_extension_targets(&values_t, values);
+            values_t.try_into().unwrap()
+        })
+        .collect::<Vec<[_; S::COLUMNS]>>();
     let pis_t = builder.add_virtual_extension_targets(S::PUBLIC_INPUTS);
     pw.set_extension_targets(&pis_t, vars.public_inputs);
     let alphas_t = builder.add_virtual_targets(1);
@@ -136,6 +163,7 @@ where
     let vars = StarkEvaluationTargets::<D, { S::COLUMNS }, { S::PUBLIC_INPUTS }> {
         local_values: &locals_t.try_into().unwrap(),
         next_values: &nexts_t.try_into().unwrap(),
+        additional_values: &additional_t,
         public_inputs: &pis_t.try_into().unwrap(),
     };
     let mut consumer = RecursiveConstraintConsumer::<F, D>::new(
@@ -155,6 +183,58 @@ where
     data.verify(proof)
 }
 
+/// Tests that the constraints imposed by the given STARK don't read any values at the next row
+/// besides those of the columns opened at `g * zeta`, as given by `Stark::next_row_columns`.
+pub fn test_stark_next_row_columns<F: RichField + Extendable<D>, S: Stark<F, D>, const D: usize>(
+    stark: S,
+) -> Result<()>
+where
+    [(); S::COLUMNS]:,
+    [(); S::PUBLIC_INPUTS]:,
+{
+    let local_values = F::Extension::rand_array::<{ S::COLUMNS }>();
+    let next_values = F::Extension::rand_array::<{ S::COLUMNS }>();
+    let additional_values = stark
+        .additional_opening_rows()
+        .iter()
+        .map(|_| F::Extension::rand_array::<{ S::COLUMNS }>())
+        .collect::<Vec<_>>();
+    let public_inputs = F::Extension::rand_array::<{ S::PUBLIC_INPUTS }>();
+    let mut opened_next_values = [F::Extension::ZERO; S::COLUMNS];
+    for i in opened_next_row_columns(&stark) {
+        opened_next_values[i] = next_values[i];
+    }
+
+    let alphas = [F::Extension::from_basefield(F::rand())];
+    let z_last = F::Extension::rand();
+    let lagrange_first = F::Extension::rand();
+    let lagrange_last = F::Extension::rand();
+    let eval = |next_values: &[F::Extension; S::COLUMNS]| {
+        let vars = StarkEvaluationVars {
+            local_values: &local_values,
+            next_values,
+            additional_values: &additional_values,
+            public_inputs: &public_inputs,
+        };
+        let mut accs = [F::Extension::ZERO];
+        let mut consumer = ConstraintConsumer::<F::Extension>::new(
+            &alphas,
+            &mut accs,
+            z_last,
+            lagrange_first,
+            lagrange_last,
+        );
+        stark.eval_ext(vars, &mut consumer);
+        consumer.accumulators()[0]
+    };
+
+    ensure!(
+        eval(&next_values) == eval(&opened_next_values),
+        "Constraints read next row values of columns missing from `next_row_columns`"
+    );
+    Ok(())
+}
+
 fn random_low_degree_matrix<F: Field>(num_polys: usize, rate_bits: usize) -> Vec<Vec<F>> {
     let polys = (0..num_polys)
         .map(|_| random_low_degree_values(rate_bits))
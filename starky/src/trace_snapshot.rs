@@ -0,0 +1,219 @@
+//! Snapshots of generated traces, to check that a refactor of an AIR or of its trace generation
+//! leaves the trace unchanged. A trace is serialized with [`trace_to_bytes`], stored alongside the
+//! tests, and compared against freshly generated traces with [`assert_traces_eq`], which reports
+//! mismatches by column name.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+use anyhow::{anyhow, ensure, Result};
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::hash::hash_types::RichField;
+use plonky2::util::serialization::{Buffer, IoResult, Read, Remaining, Write};
+
+/// Version of the trace snapshot byte encoding.
+pub const TRACE_SNAPSHOT_VERSION: u8 = 1;
+
+pub fn trace_to_bytes<F: RichField>(trace: &[PolynomialValues<F>]) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    write_trace(&mut buffer, trace).expect("Writing to a byte-vector cannot fail.");
+    buffer
+}
+
+fn write_trace<F: RichField>(buffer: &mut Vec<u8>, trace: &[PolynomialValues<F>]) -> IoResult<()> {
+    buffer.write_u8(TRACE_SNAPSHOT_VERSION)?;
+    buffer.write_usize(trace.len())?;
+    buffer.write_usize(trace.first().map_or(0, |column| column.len()))?;
+    for column in trace {
+        buffer.write_field_vec(&column.values)?;
+    }
+    Ok(())
+}
+
+pub fn trace_from_bytes<F: RichField>(bytes: &[u8]) -> Result<Vec<PolynomialValues<F>>> {
+    let mut buffer = Buffer::new(bytes);
+    let version = buffer
+        .read_u8()
+        .map_err(|_| anyhow!("Empty trace snapshot"))?;
+    ensure!(
+        version == TRACE_SNAPSHOT_VERSION,
+        "Unsupported trace snapshot version {}",
+        version
+    );
+    let trace = read_trace(&mut buffer).map_err(|_| anyhow!("Invalid trace snapshot"))?;
+    ensure!(buffer.is_empty(), "Trailing bytes in trace snapshot");
+    Ok(trace)
+}
+
+fn read_trace<F: RichField>(buffer: &mut Buffer<'_>) -> IoResult<Vec<PolynomialValues<F>>> {
+    let num_columns = buffer.read_usize()?;
+    let num_rows = buffer.read_usize()?;
+    (0..num_columns)
+        .map(|_| buffer.read_field_vec(num_rows).map(PolynomialValues::new))
+        .collect()
+}
+
+#[cfg(feature = "std")]
+pub fn save_trace<F: RichField>(
+    trace: &[PolynomialValues<F>],
+    path: &std::path::Path,
+) -> Result<()> {
+    std::fs::write(path, trace_to_bytes(trace))?;
+    Ok(())
+}
+
+#[cfg(feature = "std")]
+pub fn load_trace<F: RichField>(path: &std::path::Path) -> Result<Vec<PolynomialValues<F>>> {
+    trace_from_bytes(&std::fs::read(path)?)
+}
+
+/// The differences between the expected and actual values of a single column.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ColumnDiff<F: RichField> {
+    pub column: usize,
+    /// The column's name, if it belongs to a named field.
+    pub name: Option<String>,
+    /// The rows at which the values differ, in increasing order.
+    pub rows: Vec<usize>,
+    /// The expected and actual values at the first differing row.
+    pub first_expected: F,
+    pub first_actual: F,
+}
+
+impl<F: RichField> fmt::Display for ColumnDiff<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "column {} ({})", self.column, name)?,
+            None => write!(f, "column {}", self.column)?,
+        }
+        write!(
+            f,
+            ": {} rows differ, first at row {}: expected {}, got {}",
+            self.rows.len(),
+            self.rows[0],
+            self.first_expected,
+            self.first_actual
+        )
+    }
+}
+
+/// Compares two traces column by column, returning the columns whose values differ. Columns are
+/// named after `fields`, e.g. the output of the `fields` method of a
+/// [`column_layout!`](crate::column_layout) column map; columns outside of `fields` are unnamed.
+/// Fails if the traces don't have the same shape.
+pub fn diff_traces<F: RichField>(
+    expected: &[PolynomialValues<F>],
+    actual: &[PolynomialValues<F>],
+    fields: &[(&'static str, Range<usize>)],
+) -> Result<Vec<ColumnDiff<F>>> {
+    ensure!(
+        expected.len() == actual.len(),
+        "Expected {} columns, got {}",
+        expected.len(),
+        actual.len()
+    );
+    let mut diffs = Vec::new();
+    for (column, (expected, actual)) in expected.iter().zip(actual).enumerate() {
+        ensure!(
+            expected.len() == actual.len(),
+            "Expected {} rows in column {}, got {}",
+            expected.len(),
+            column,
+            actual.len()
+        );
+        let rows = (0..expected.len())
+            .filter(|&row| expected.values[row] != actual.values[row])
+            .collect::<Vec<_>>();
+        if let Some(&first) = rows.first() {
+            diffs.push(ColumnDiff {
+                column,
+                name: column_name(fields, column),
+                rows,
+                first_expected: expected.values[first],
+                first_actual: actual.values[first],
+            });
+        }
+    }
+    Ok(diffs)
+}
+
+fn column_name(fields: &[(&'static str, Range<usize>)], column: usize) -> Option<String> {
+    fields
+        .iter()
+        .find(|(_, range)| range.contains(&column))
+        .map(|(name, range)| {
+            if range.len() == 1 {
+                String::from(*name)
+            } else {
+                format!("{}[{}]", name, column - range.start)
+            }
+        })
+}
+
+/// Panics with a per-column report if the two traces differ.
+pub fn assert_traces_eq<F: RichField>(
+    expected: &[PolynomialValues<F>],
+    actual: &[PolynomialValues<F>],
+    fields: &[(&'static str, Range<usize>)],
+) {
+    let diffs = diff_traces(expected, actual, fields).unwrap_or_else(|e| panic!("{}", e));
+    if !diffs.is_empty() {
+        let report = diffs
+            .iter()
+            .map(|diff| format!("  {}", diff))
+            .collect::<Vec<_>>()
+            .join("\n");
+        panic!("Traces differ in {} columns:\n{}", diffs.len(), report);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::field::types::Field;
+
+    use super::*;
+    use crate::fibonacci_stark::{FibonacciStark, FIBONACCI_COL_MAP};
+
+    type F = GoldilocksField;
+
+    #[test]
+    fn test_trace_snapshot() -> Result<()> {
+        let stark = FibonacciStark::<F, 2>::new(1 << 4);
+        let trace = stark.generate_trace(F::ZERO, F::ONE);
+        let fields = FIBONACCI_COL_MAP.fields();
+
+        let snapshot = trace_to_bytes(&trace);
+        let restored = trace_from_bytes::<F>(&snapshot)?;
+        assert_traces_eq(&trace, &restored, &fields);
+        assert!(trace_from_bytes::<F>(&snapshot[..snapshot.len() - 1]).is_err());
+
+        let mut modified = trace.clone();
+        modified[FIBONACCI_COL_MAP.x1].values[3] += F::ONE;
+        modified[FIBONACCI_COL_MAP.x1].values[5] += F::ONE;
+        let diffs = diff_traces(&trace, &modified, &fields)?;
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].column, FIBONACCI_COL_MAP.x1);
+        assert_eq!(diffs[0].name.as_deref(), Some("x1"));
+        assert_eq!(diffs[0].rows, vec![3, 5]);
+        assert_eq!(diffs[0].first_actual, diffs[0].first_expected + F::ONE);
+
+        assert!(diff_traces(&trace, &modified[1..], &fields).is_err());
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic(expected = "column 2 (i): 1 rows differ, first at row 0")]
+    fn test_assert_traces_eq() {
+        let stark = FibonacciStark::<F, 2>::new(1 << 4);
+        let trace = stark.generate_trace(F::ZERO, F::ONE);
+        let mut modified = trace.clone();
+        modified[FIBONACCI_COL_MAP.i].values[0] = F::TWO;
+        assert_traces_eq(&trace, &modified, &FIBONACCI_COL_MAP.fields());
+    }
+}
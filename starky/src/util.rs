@@ -1,6 +1,6 @@
+use alloc::vec;
 use alloc::vec::Vec;
 
-use itertools::Itertools;
 use plonky2::field::polynomial::PolynomialValues;
 use plonky2::field::types::Field;
 use plonky2::util::transpose;
@@ -9,10 +9,26 @@ use plonky2::util::transpose;
 pub fn trace_rows_to_poly_values<F: Field, const COLUMNS: usize>(
     trace_rows: Vec<[F; COLUMNS]>,
 ) -> Vec<PolynomialValues<F>> {
-    let trace_row_vecs = trace_rows.into_iter().map(|row| row.to_vec()).collect_vec();
-    let trace_col_vecs: Vec<Vec<F>> = transpose(&trace_row_vecs);
+    let trace_col_vecs: Vec<Vec<F>> = transpose(&trace_rows);
     trace_col_vecs
         .into_iter()
         .map(|column| PolynomialValues::new(column))
         .collect()
 }
+
+/// Spreads the values of `columns`, given in the same order, over a row of `num_columns` values,
+/// with the other columns set to `zero`. This expands committed or opened values, which may only
+/// cover some columns, into a full row.
+pub(crate) fn scatter_columns<T: Copy>(
+    values: &[T],
+    columns: &[usize],
+    num_columns: usize,
+    zero: T,
+) -> Vec<T> {
+    debug_assert_eq!(values.len(), columns.len());
+    let mut row = vec![zero; num_columns];
+    for (&i, &v) in columns.iter().zip(values) {
+        row[i] = v;
+    }
+    row
+}
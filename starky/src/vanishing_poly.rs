@@ -5,10 +5,13 @@ use plonky2::plonk::circuit_builder::CircuitBuilder;
 
 use crate::config::StarkConfig;
 use crate::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use crate::lookup::{eval_lookup_checks, eval_lookup_checks_circuit};
+use crate::padding::{eval_padding_column, eval_padding_column_circuit};
 use crate::permutation::{
     eval_permutation_checks, eval_permutation_checks_circuit, PermutationCheckDataTarget,
     PermutationCheckVars,
 };
+use crate::public_cells::{eval_public_cells, eval_public_cells_circuit};
 use crate::stark::Stark;
 use crate::vars::{StarkEvaluationTargets, StarkEvaluationVars};
 
@@ -27,6 +30,15 @@ pub(crate) fn eval_vanishing_poly<F, FE, P, S, const D: usize, const D2: usize>(
     [(); S::PUBLIC_INPUTS]:,
 {
     stark.eval_packed_generic(vars, consumer);
+    eval_public_cells::<F, FE, P, S, D, D2>(stark, vars, consumer);
+    eval_padding_column::<F, FE, P, S, D, D2>(stark, vars, consumer);
+    eval_lookup_checks::<F, FE, P, S, D, D2>(
+        stark,
+        config,
+        vars,
+        permutation_data.as_ref(),
+        consumer,
+    );
     if let Some(permutation_data) = permutation_data {
         eval_permutation_checks::<F, FE, P, S, D, D2>(
             stark,
@@ -52,6 +64,16 @@ pub(crate) fn eval_vanishing_poly_circuit<F, S, const D: usize>(
     [(); S::PUBLIC_INPUTS]:,
 {
     stark.eval_ext_circuit(builder, vars, consumer);
+    eval_public_cells_circuit::<F, S, D>(builder, stark, vars, consumer);
+    eval_padding_column_circuit::<F, S, D>(builder, stark, vars, consumer);
+    eval_lookup_checks_circuit::<F, S, D>(
+        builder,
+        stark,
+        config,
+        vars,
+        permutation_data.as_ref(),
+        consumer,
+    );
     if let Some(permutation_data) = permutation_data {
         eval_permutation_checks_circuit::<F, S, D>(
             builder,
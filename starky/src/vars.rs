@@ -10,6 +10,8 @@ where
 {
     pub local_values: &'a [P; COLUMNS],
     pub next_values: &'a [P; COLUMNS],
+    /// The values at the rows of `Stark::additional_opening_rows`, in the same order.
+    pub additional_values: &'a [[P; COLUMNS]],
     pub public_inputs: &'a [P::Scalar; PUBLIC_INPUTS],
 }
 
@@ -22,5 +24,7 @@ pub struct StarkEvaluationTargets<
 > {
     pub local_values: &'a [ExtensionTarget<D>; COLUMNS],
     pub next_values: &'a [ExtensionTarget<D>; COLUMNS],
+    /// The values at the rows of `Stark::additional_opening_rows`, in the same order.
+    pub additional_values: &'a [[ExtensionTarget<D>; COLUMNS]],
     pub public_inputs: &'a [ExtensionTarget<D>; PUBLIC_INPUTS],
 }
@@ -1,3 +1,4 @@
+use alloc::vec;
 use alloc::vec::Vec;
 use core::iter::once;
 
@@ -8,13 +9,17 @@ use plonky2::field::types::Field;
 use plonky2::fri::verifier::verify_fri_proof;
 use plonky2::hash::hash_types::RichField;
 use plonky2::plonk::config::GenericConfig;
-use plonky2::plonk::plonk_common::reduce_with_powers;
+use plonky2::plonk::plonk_common::check_quotient_openings;
+use plonky2_maybe_rayon::*;
 
 use crate::config::StarkConfig;
 use crate::constraint_consumer::ConstraintConsumer;
 use crate::permutation::PermutationCheckVars;
-use crate::proof::{StarkOpeningSet, StarkProof, StarkProofChallenges, StarkProofWithPublicInputs};
-use crate::stark::Stark;
+use crate::proof::{
+    StarkOpeningSet, StarkProof, StarkProofChallenges, StarkProofWithPublicInputs, StarkTranscript,
+};
+use crate::stark::{opened_next_row_columns, used_columns, Stark};
+use crate::util::scatter_columns;
 use crate::vanishing_poly::eval_vanishing_poly;
 use crate::vars::StarkEvaluationVars;
 
@@ -32,12 +37,56 @@ where
     [(); S::COLUMNS]:,
     [(); S::PUBLIC_INPUTS]:,
 {
-    ensure!(proof_with_pis.public_inputs.len() == S::PUBLIC_INPUTS);
-    let degree_bits = proof_with_pis.proof.recover_degree_bits(config);
-    let challenges = proof_with_pis.get_challenges(&stark, config, degree_bits);
+    verify_stark_proof_with_transcript(stark, proof_with_pis, config, StarkTranscript::Current)
+}
+
+/// Verifies a proof whose challenges were derived with `transcript`, such as one made by a prover
+/// of starky 0.1.2 with `StarkTranscript::Legacy`. The STARK must evaluate its constraints in the
+/// same order as when the proof was made.
+pub fn verify_stark_proof_with_transcript<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    S: Stark<F, D>,
+    const D: usize,
+>(
+    stark: S,
+    proof_with_pis: StarkProofWithPublicInputs<F, C, D>,
+    config: &StarkConfig,
+    transcript: StarkTranscript,
+) -> Result<()>
+where
+    [(); S::COLUMNS]:,
+    [(); S::PUBLIC_INPUTS]:,
+{
+    let degree_bits = checked_degree_bits(&proof_with_pis.proof, config)?;
+    // Validate the shape before deriving challenges, so that malformed proofs are rejected
+    // without panicking.
+    validate_proof_shape(&stark, &proof_with_pis, degree_bits, config)?;
+    let challenges = proof_with_pis.get_challenges(&stark, config, degree_bits, transcript);
     verify_stark_proof_with_challenges(stark, proof_with_pis, challenges, degree_bits, config)
 }
 
+/// Verifies several proofs of `stark`, in parallel when the `parallel` feature is enabled. Fails if
+/// any of them is invalid.
+pub fn verify_stark_proofs<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    S: Stark<F, D> + Clone + Sync,
+    const D: usize,
+>(
+    stark: &S,
+    proofs_with_pis: Vec<StarkProofWithPublicInputs<F, C, D>>,
+    config: &StarkConfig,
+) -> Result<()>
+where
+    [(); S::COLUMNS]:,
+    [(); S::PUBLIC_INPUTS]:,
+{
+    proofs_with_pis
+        .into_par_iter()
+        .try_for_each(|proof_with_pis| verify_stark_proof(stark.clone(), proof_with_pis, config))
+}
+
 pub(crate) fn verify_stark_proof_with_challenges<
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
@@ -54,7 +103,6 @@ where
     [(); S::COLUMNS]:,
     [(); S::PUBLIC_INPUTS]:,
 {
-    validate_proof_shape(&stark, &proof_with_pis, config)?;
     check_permutation_options(&stark, &proof_with_pis, &challenges)?;
     let StarkProofWithPublicInputs {
         proof,
@@ -63,13 +111,33 @@ where
     let StarkOpeningSet {
         local_values,
         next_values,
+        additional_values,
         permutation_zs,
         permutation_zs_next,
         quotient_polys,
     } = &proof.openings;
+    // Columns which aren't opened are read as zero.
+    let zero = F::Extension::ZERO;
+    let used_columns = used_columns(&stark);
+    let local_values = scatter_columns(local_values, &used_columns, S::COLUMNS, zero);
+    let next_values = scatter_columns(
+        next_values,
+        &opened_next_row_columns(&stark),
+        S::COLUMNS,
+        zero,
+    );
+    let additional_values = additional_values
+        .iter()
+        .map(|values| {
+            scatter_columns(values, &used_columns, S::COLUMNS, zero)
+                .try_into()
+                .unwrap()
+        })
+        .collect::<Vec<[_; S::COLUMNS]>>();
     let vars = StarkEvaluationVars {
-        local_values: &local_values.to_vec().try_into().unwrap(),
-        next_values: &next_values.to_vec().try_into().unwrap(),
+        local_values: &local_values.try_into().unwrap(),
+        next_values: &next_values.try_into().unwrap(),
+        additional_values: &additional_values,
         public_inputs: &public_inputs
             .into_iter()
             .map(F::Extension::from_basefield)
@@ -81,17 +149,15 @@ where
     let (l_0, l_last) = eval_l_0_and_l_last(degree_bits, challenges.stark_zeta);
     let last = F::primitive_root_of_unity(degree_bits).inverse();
     let z_last = challenges.stark_zeta - last.into();
-    let mut consumer = ConstraintConsumer::<F::Extension>::new(
-        challenges
-            .stark_alphas
-            .iter()
-            .map(|&alpha| F::Extension::from_basefield(alpha))
-            .collect::<Vec<_>>(),
-        z_last,
-        l_0,
-        l_last,
-    );
-    let permutation_data = stark.uses_permutation_args().then(|| PermutationCheckVars {
+    let alphas = challenges
+        .stark_alphas
+        .iter()
+        .map(|&alpha| F::Extension::from_basefield(alpha))
+        .collect::<Vec<_>>();
+    let mut accs = vec![F::Extension::ZERO; alphas.len()];
+    let mut consumer =
+        ConstraintConsumer::<F::Extension>::new(&alphas, &mut accs, z_last, l_0, l_last);
+    let permutation_data = stark.uses_auxiliary_polys().then(|| PermutationCheckVars {
         local_zs: permutation_zs.as_ref().unwrap().clone(),
         next_zs: permutation_zs_next.as_ref().unwrap().clone(),
         permutation_challenge_sets: challenges.permutation_challenge_sets.unwrap(),
@@ -107,21 +173,12 @@ where
 
     // Check each polynomial identity, of the form `vanishing(x) = Z_H(x) quotient(x)`, at zeta.
     let zeta_pow_deg = challenges.stark_zeta.exp_power_of_2(degree_bits);
-    let z_h_zeta = zeta_pow_deg - F::Extension::ONE;
-    // `quotient_polys_zeta` holds `num_challenges * quotient_degree_factor` evaluations.
-    // Each chunk of `quotient_degree_factor` holds the evaluations of `t_0(zeta),...,t_{quotient_degree_factor-1}(zeta)`
-    // where the "real" quotient polynomial is `t(X) = t_0(X) + t_1(X)*X^n + t_2(X)*X^{2n} + ...`.
-    // So to reconstruct `t(zeta)` we can compute `reduce_with_powers(chunk, zeta^n)` for each
-    // `quotient_degree_factor`-sized chunk of the original evaluations.
-    for (i, chunk) in quotient_polys
-        .chunks(stark.quotient_degree_factor())
-        .enumerate()
-    {
-        ensure!(
-            vanishing_polys_zeta[i] == z_h_zeta * reduce_with_powers(chunk, zeta_pow_deg),
-            "Mismatch between evaluation and opening of quotient polynomial"
-        );
-    }
+    check_quotient_openings(
+        vanishing_polys_zeta,
+        quotient_polys,
+        stark.quotient_degree_factor(),
+        zeta_pow_deg,
+    )?;
 
     let merkle_caps = once(proof.trace_cap)
         .chain(proof.permutation_zs_cap)
@@ -147,6 +204,7 @@ where
 fn validate_proof_shape<F, C, S, const D: usize>(
     stark: &S,
     proof_with_pis: &StarkProofWithPublicInputs<F, C, D>,
+    degree_bits: usize,
     config: &StarkConfig,
 ) -> anyhow::Result<()>
 where
@@ -159,7 +217,6 @@ where
         proof,
         public_inputs,
     } = proof_with_pis;
-    let degree_bits = proof.recover_degree_bits(config);
 
     let StarkProof {
         trace_cap,
@@ -174,6 +231,7 @@ where
     let StarkOpeningSet {
         local_values,
         next_values,
+        additional_values,
         permutation_zs,
         permutation_zs_next,
         quotient_polys,
@@ -183,16 +241,19 @@ where
 
     let fri_params = config.fri_params(degree_bits);
     let cap_height = fri_params.config.cap_height;
-    let num_zs = stark.num_permutation_batches(config);
+    let num_zs = stark.num_auxiliary_polys(config);
 
     ensure!(trace_cap.height() == cap_height);
     ensure!(quotient_polys_cap.height() == cap_height);
 
-    ensure!(local_values.len() == S::COLUMNS);
-    ensure!(next_values.len() == S::COLUMNS);
+    let num_trace_polys = used_columns(stark).len();
+    ensure!(local_values.len() == num_trace_polys);
+    ensure!(next_values.len() == opened_next_row_columns(stark).len());
+    ensure!(additional_values.len() == stark.additional_opening_rows().len());
+    ensure!(additional_values.iter().all(|v| v.len() == num_trace_polys));
     ensure!(quotient_polys.len() == stark.num_quotient_polys(config));
 
-    if stark.uses_permutation_args() {
+    if stark.uses_auxiliary_polys() {
         let permutation_zs_cap = permutation_zs_cap
             .as_ref()
             .ok_or_else(|| anyhow!("Missing Zs cap"))?;
@@ -215,6 +276,32 @@ where
     Ok(())
 }
 
+/// Like [`StarkProof::recover_degree_bits`], but fails instead of panicking on malformed proofs.
+fn checked_degree_bits<F, C, const D: usize>(
+    proof: &StarkProof<F, C, D>,
+    config: &StarkConfig,
+) -> Result<usize>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+{
+    let (_, initial_merkle_proof) = proof
+        .opening_proof
+        .query_round_proofs
+        .first()
+        .and_then(|round| round.initial_trees_proof.evals_proofs.first())
+        .ok_or_else(|| anyhow!("Missing FRI query rounds"))?;
+    // The first oracle is the trace, committed with `trace_rate_bits` and `leaf_group_bits`.
+    let lde_bits =
+        config.fri_config.cap_height + initial_merkle_proof.siblings.len() + config.leaf_group_bits;
+    let rate_bits = config.trace_rate_bits();
+    ensure!(
+        (rate_bits..=F::TWO_ADICITY).contains(&lde_bits),
+        "Invalid Merkle proof length"
+    );
+    Ok(lde_bits - rate_bits)
+}
+
 /// Evaluate the Lagrange polynomials `L_0` and `L_(n-1)` at a point `x`.
 /// `L_0(x) = (x^n - 1)/(n * (x - 1))`
 /// `L_(n-1)(x) = (x^n - 1)/(n * (g * x - 1))`, with `g` the first element of the subgroup.
@@ -228,7 +315,7 @@ fn eval_l_0_and_l_last<F: Field>(log_n: usize, x: F) -> (F, F) {
 }
 
 /// Utility function to check that all permutation data wrapped in `Option`s are `Some` iff
-/// the Stark uses a permutation argument.
+/// the Stark uses permutation arguments or LogUp lookups.
 fn check_permutation_options<
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
@@ -248,7 +335,7 @@ fn check_permutation_options<
     ensure!(
         options_is_some
             .into_iter()
-            .all(|b| b == stark.uses_permutation_args()),
+            .all(|b| b == stark.uses_auxiliary_polys()),
         "Permutation data doesn't match with Stark configuration."
     );
     Ok(())
@@ -257,10 +344,137 @@ fn check_permutation_options<
 #[cfg(test)]
 mod tests {
     use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::field::packed::PackedField;
     use plonky2::field::polynomial::PolynomialValues;
     use plonky2::field::types::Sample;
+    use plonky2::plonk::circuit_builder::CircuitBuilder;
+    use plonky2::plonk::config::PoseidonGoldilocksConfig;
+    use plonky2::util::timing::TimingTree;
+
+    use super::*;
+    use crate::constraint_consumer::RecursiveConstraintConsumer;
+    use crate::permutation::PermutationPair;
+    use crate::prover::prove;
+    use crate::util::trace_rows_to_poly_values;
+    use crate::vars::StarkEvaluationTargets;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = GoldilocksField;
 
-    use crate::verifier::eval_l_0_and_l_last;
+    /// A proof of `LegacyFibonacciStark` with 32 rows, made by starky 0.1.2 with
+    /// `StarkConfig::standard_fast_config()`. That release had no proof serializer, so it was
+    /// written in the layout of `StarkProofWithPublicInputs::to_bytes` with plonky2's `Write`.
+    const LEGACY_PROOF: &[u8] = include_bytes!("../tests/fixtures/legacy_fibonacci_proof.bin");
+
+    /// The `FibonacciStark` of starky 0.1.2. It checks its public inputs before its transitions,
+    /// whereas the current `FibonacciStark` checks them as public cells, after its own constraints,
+    /// which changes how the constraints are combined.
+    #[derive(Copy, Clone)]
+    struct LegacyFibonacciStark {
+        num_rows: usize,
+    }
+
+    impl LegacyFibonacciStark {
+        fn generate_trace(&self, x0: F, x1: F) -> Vec<PolynomialValues<F>> {
+            let mut trace_rows = (0..self.num_rows)
+                .scan([x0, x1, F::ZERO, F::ONE], |acc, _| {
+                    let tmp = *acc;
+                    acc[0] = tmp[1];
+                    acc[1] = tmp[0] + tmp[1];
+                    acc[2] = tmp[2] + F::ONE;
+                    acc[3] = tmp[3] + F::ONE;
+                    Some(tmp)
+                })
+                .collect::<Vec<_>>();
+            trace_rows[self.num_rows - 1][3] = F::ZERO;
+            trace_rows_to_poly_values(trace_rows)
+        }
+    }
+
+    impl Stark<F, D> for LegacyFibonacciStark {
+        const COLUMNS: usize = 4;
+        const PUBLIC_INPUTS: usize = 3;
+
+        fn eval_packed_generic<FE, P, const D2: usize>(
+            &self,
+            vars: StarkEvaluationVars<FE, P, { Self::COLUMNS }, { Self::PUBLIC_INPUTS }>,
+            yield_constr: &mut ConstraintConsumer<P>,
+        ) where
+            FE: FieldExtension<D2, BaseField = F>,
+            P: PackedField<Scalar = FE>,
+        {
+            yield_constr.constraint_first_row(vars.local_values[0] - vars.public_inputs[0]);
+            yield_constr.constraint_first_row(vars.local_values[1] - vars.public_inputs[1]);
+            yield_constr.constraint_last_row(vars.local_values[1] - vars.public_inputs[2]);
+            yield_constr.constraint_transition(vars.next_values[0] - vars.local_values[1]);
+            yield_constr.constraint_transition(
+                vars.next_values[1] - vars.local_values[0] - vars.local_values[1],
+            );
+        }
+
+        fn eval_ext_circuit(
+            &self,
+            builder: &mut CircuitBuilder<F, D>,
+            vars: StarkEvaluationTargets<D, { Self::COLUMNS }, { Self::PUBLIC_INPUTS }>,
+            yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+        ) {
+            let x0 = builder.sub_extension(vars.local_values[0], vars.public_inputs[0]);
+            yield_constr.constraint_first_row(builder, x0);
+            let x1 = builder.sub_extension(vars.local_values[1], vars.public_inputs[1]);
+            yield_constr.constraint_first_row(builder, x1);
+            let res = builder.sub_extension(vars.local_values[1], vars.public_inputs[2]);
+            yield_constr.constraint_last_row(builder, res);
+            let first_col = builder.sub_extension(vars.next_values[0], vars.local_values[1]);
+            yield_constr.constraint_transition(builder, first_col);
+            let second_col = {
+                let tmp = builder.sub_extension(vars.next_values[1], vars.local_values[0]);
+                builder.sub_extension(tmp, vars.local_values[1])
+            };
+            yield_constr.constraint_transition(builder, second_col);
+        }
+
+        fn constraint_degree(&self) -> usize {
+            2
+        }
+
+        fn permutation_pairs(&self) -> Vec<PermutationPair> {
+            vec![PermutationPair::singletons(2, 3)]
+        }
+    }
+
+    #[test]
+    fn test_verify_legacy_proof() -> Result<()> {
+        let config = StarkConfig::standard_fast_config();
+        let stark = LegacyFibonacciStark { num_rows: 1 << 5 };
+        let legacy_proof =
+            StarkProofWithPublicInputs::<F, C, D>::from_bytes(LEGACY_PROOF, &stark, &config)?;
+        let x1 = (0..stark.num_rows - 1)
+            .fold((F::ZERO, F::ONE), |x, _| (x.1, x.0 + x.1))
+            .1;
+        assert_eq!(legacy_proof.public_inputs, [F::ZERO, F::ONE, x1]);
+
+        let verify = |proof: &StarkProofWithPublicInputs<F, C, D>, transcript| {
+            verify_stark_proof_with_transcript(stark, proof.clone(), &config, transcript)
+        };
+        verify(&legacy_proof, StarkTranscript::Legacy)?;
+        assert!(verify(&legacy_proof, StarkTranscript::Current).is_err());
+        // The legacy transcript doesn't absorb the public inputs, but the constraints check them.
+        let mut wrong_public_inputs = legacy_proof;
+        wrong_public_inputs.public_inputs[2] += F::ONE;
+        assert!(verify(&wrong_public_inputs, StarkTranscript::Legacy).is_err());
+
+        let proof = prove::<F, C, _, D>(
+            stark,
+            &config,
+            stark.generate_trace(F::ZERO, F::ONE),
+            [F::ZERO, F::ONE, x1],
+            &mut TimingTree::default(),
+        )?;
+        verify(&proof, StarkTranscript::Current)?;
+        assert!(verify(&proof, StarkTranscript::Legacy).is_err());
+        Ok(())
+    }
 
     #[test]
     fn test_eval_l_0_and_l_last() {
@@ -0,0 +1,211 @@
+//! Estimates the cost of verifying STARK proofs, recursively or on chain, to help choose a
+//! `StarkConfig`.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use plonky2::field::extension::Extendable;
+use plonky2::field::types::Field;
+use plonky2::fri::onchain_cost::OnChainVerifierCost;
+use plonky2::hash::hash_types::RichField;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::plonk::circuit_data::CircuitConfig;
+use plonky2::plonk::config::{AlgebraicHasher, GenericConfig};
+
+use crate::config::StarkConfig;
+use crate::recursive_verifier::{add_virtual_stark_proof_with_pis, verify_stark_proof_circuit};
+use crate::stark::Stark;
+
+/// The instances of one type of gate in a recursive verifier.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GateCost {
+    /// The gate's ID, as given by `Gate::id`.
+    pub gate: String,
+    pub instances: usize,
+    /// The number of wires each instance uses, out of the circuit's `num_wires`.
+    pub wires_per_instance: usize,
+    pub degree: usize,
+}
+
+impl GateCost {
+    pub fn wires_used(&self) -> usize {
+        self.instances * self.wires_per_instance
+    }
+}
+
+/// The size of a circuit verifying one STARK proof.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RecursiveVerifierCost {
+    /// The number of rows used by the verifier, before padding.
+    pub num_gates: usize,
+    /// The log2 of the number of rows of the built circuit, which also contains the gates
+    /// `CircuitBuilder::build` adds for public inputs and constants.
+    pub degree_bits: usize,
+    /// The number of wires per row.
+    pub num_wires: usize,
+    /// Per-gate-type costs, sorted by gate ID.
+    pub gates: Vec<GateCost>,
+}
+
+impl RecursiveVerifierCost {
+    /// The highest degree of any gate, which bounds the circuit's quotient degree.
+    pub fn max_degree(&self) -> usize {
+        self.gates.iter().map(|g| g.degree).max().unwrap_or(0)
+    }
+
+    /// The fraction of the verifier's wires, over its unpadded rows, which are used by some gate.
+    pub fn wire_utilization(&self) -> f64 {
+        let used = self.gates.iter().map(GateCost::wires_used).sum::<usize>();
+        used as f64 / (self.num_gates * self.num_wires) as f64
+    }
+}
+
+/// Builds the circuit verifying proofs of `stark` with `inner_config` over traces of
+/// `2^degree_bits` rows, and reports its size. The circuit uses `circuit_config`, and `C` for both
+/// the inner proof and the circuit itself.
+pub fn recursive_verifier_cost<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    S: Stark<F, D> + Copy,
+    const D: usize,
+>(
+    stark: S,
+    inner_config: &StarkConfig,
+    degree_bits: usize,
+    circuit_config: CircuitConfig,
+) -> RecursiveVerifierCost
+where
+    C::Hasher: AlgebraicHasher<F>,
+    [(); S::COLUMNS]:,
+    [(); S::PUBLIC_INPUTS]:,
+{
+    let num_wires = circuit_config.num_wires;
+    let mut builder = CircuitBuilder::<F, D>::new(circuit_config);
+    let proof_with_pis =
+        add_virtual_stark_proof_with_pis(&mut builder, stark, inner_config, degree_bits);
+    verify_stark_proof_circuit::<F, C, S, D>(&mut builder, stark, proof_with_pis, inner_config);
+
+    let num_gates = builder.num_gates();
+    let gates = builder
+        .gate_counts()
+        .into_iter()
+        .map(|(gate, instances)| GateCost {
+            gate: gate.0.id(),
+            instances,
+            wires_per_instance: gate.0.num_wires(),
+            degree: gate.0.degree(),
+        })
+        .collect();
+    let data = builder.build::<C>();
+
+    RecursiveVerifierCost {
+        num_gates,
+        degree_bits: data.common.degree_bits(),
+        num_wires,
+        gates,
+    }
+}
+
+/// Estimates the cost of verifying a proof of `stark` with `config` over a trace of
+/// `2^degree_bits` rows on chain, with the proof serialized by
+/// `StarkProofWithPublicInputs::to_bytes`.
+pub fn onchain_verifier_cost<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    S: Stark<F, D>,
+    const D: usize,
+>(
+    stark: &S,
+    config: &StarkConfig,
+    degree_bits: usize,
+) -> OnChainVerifierCost {
+    let oracles = stark.fri_oracles(config);
+    let g = F::primitive_root_of_unity(degree_bits);
+    let num_openings = stark
+        .fri_instance(F::Extension::ZERO, g, config)
+        .batches
+        .iter()
+        .map(|batch| batch.polynomials.len())
+        .sum();
+    // The proof starts with the trace's `degree_bits`, written as a single element.
+    config
+        .fri_params(degree_bits)
+        .onchain_verifier_cost::<F, C::Hasher>(
+            &oracles,
+            oracles.len(),
+            num_openings,
+            1 + S::PUBLIC_INPUTS,
+            D,
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2::plonk::config::PoseidonGoldilocksConfig;
+    use plonky2::util::timing::TimingTree;
+
+    use super::*;
+    use crate::fibonacci_stark::FibonacciStark;
+    use crate::prover::prove;
+
+    #[test]
+    fn test_fibonacci_verifier_cost() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = FibonacciStark<F, D>;
+
+        let config = StarkConfig::standard_fast_config();
+        let circuit_config = CircuitConfig::standard_recursion_config();
+        let stark = S::new(1 << 5);
+        let small =
+            recursive_verifier_cost::<F, C, S, D>(stark, &config, 5, circuit_config.clone());
+        let large = recursive_verifier_cost::<F, C, S, D>(stark, &config, 10, circuit_config);
+
+        assert_eq!(
+            small.num_gates,
+            small.gates.iter().map(|g| g.instances).sum::<usize>()
+        );
+        assert!(small
+            .gates
+            .iter()
+            .any(|g| g.gate.starts_with("PoseidonGate")));
+        assert!(small.num_gates <= 1 << small.degree_bits);
+        assert!(small.max_degree() <= 8);
+        assert!(small.wire_utilization() > 0.0 && small.wire_utilization() <= 1.0);
+        // Longer Merkle paths and more FRI rounds make verification more expensive.
+        assert!(large.num_gates > small.num_gates);
+    }
+
+    #[test]
+    fn test_fibonacci_onchain_verifier_cost() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = FibonacciStark<F, D>;
+
+        let config = StarkConfig::standard_fast_config();
+        let num_rows = 1 << 5;
+        let last = (0..num_rows - 1)
+            .fold((F::ZERO, F::ONE), |x, _| (x.1, x.0 + x.1))
+            .1;
+        let public_inputs = [F::ZERO, F::ONE, last];
+        let stark = S::new(num_rows);
+        let trace = stark.generate_trace(public_inputs[0], public_inputs[1]);
+        let proof = prove::<F, C, S, D>(
+            stark,
+            &config,
+            trace,
+            public_inputs,
+            &mut TimingTree::default(),
+        )?;
+
+        let cost = onchain_verifier_cost::<F, C, S, D>(&stark, &config, 5);
+        assert_eq!(cost.proof_bytes, proof.to_bytes(&config).len());
+        // Longer Merkle paths cost more hashes.
+        let larger = onchain_verifier_cost::<F, C, S, D>(&stark, &config, 10);
+        assert!(larger.path_hashes > cost.path_hashes);
+        Ok(())
+    }
+}